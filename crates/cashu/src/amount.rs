@@ -26,6 +26,9 @@ pub enum Error {
     /// Cannot convert units
     #[error("Cannot convert units")]
     CannotConvertUnits,
+    /// Custom units have no defined conversion to other units
+    #[error("Cannot convert between {0} and {1}: custom units have no conversion rate")]
+    CannotConvertCustomUnit(CurrencyUnit, CurrencyUnit),
     /// Cannot perform operation on amounts with different units
     #[error("Unit mismatch: cannot operate on {0} and {1}")]
     UnitMismatch(CurrencyUnit, CurrencyUnit),
@@ -80,6 +83,24 @@ impl FeeAndAmounts {
     pub fn amounts(&self) -> &[u64] {
         &self.amounts
     }
+
+    /// Input fee of spending `count` proofs of the keyset, rounded up as the mint does
+    pub fn fee_for_count(&self, count: usize) -> Result<Amount, Error> {
+        let fee_ppk = self
+            .fee
+            .checked_mul(count as u64)
+            .ok_or(Error::AmountOverflow)?;
+
+        Ok(Amount::from(fee_ppk.div_ceil(1000)))
+    }
+
+    /// Drop the amounts above `max_amount`, the largest denomination the mint signs
+    pub fn with_max_amount(mut self, max_amount: Option<Amount>) -> Self {
+        if let Some(max_amount) = max_amount {
+            self.amounts.retain(|amount| *amount <= max_amount.value);
+        }
+        self
+    }
 }
 
 /// Fees and Amounts for each Keyset
@@ -193,15 +214,16 @@ impl Amount<()> {
     }
 
     /// Split into parts that are powers of two
+    ///
+    /// The largest available amount is repeated as often as needed, so amounts above the
+    /// largest denomination of a keyset are split completely.
     pub fn split(&self, fee_and_amounts: &FeeAndAmounts) -> Vec<Self> {
         fee_and_amounts
             .amounts
             .iter()
             .rev()
             .fold((Vec::new(), self.value), |(mut acc, total), &amount| {
-                if total >= amount {
-                    acc.push(Self::from(amount));
-                }
+                acc.extend((0..total / amount).map(|_| Self::from(amount)));
                 (acc, total % amount)
             })
             .0
@@ -216,7 +238,7 @@ impl Amount<()> {
         let mut parts = match target {
             SplitTarget::None => self.split(fee_and_amounts),
             SplitTarget::Value(amount) => {
-                if self.le(amount) {
+                if self.le(amount) || *amount == Amount::ZERO {
                     return Ok(self.split(fee_and_amounts));
                 }
 
@@ -247,6 +269,15 @@ impl Amount<()> {
                 parts
             }
             SplitTarget::Values(values) => {
+                if let Some(value) = values
+                    .iter()
+                    .find(|value| !fee_and_amounts.amounts().contains(&value.value))
+                {
+                    return Err(Error::InvalidAmount(format!(
+                        "{value} is not a denomination of the keyset"
+                    )));
+                }
+
                 let values_total: Amount = Amount::try_sum(values.clone().into_iter())?;
 
                 match self.cmp(&values_total) {
@@ -273,29 +304,45 @@ impl Amount<()> {
     }
 
     /// Splits amount into powers of two while accounting for the swap fee
+    ///
+    /// Returns the split of the smallest gross amount that still nets this amount once the
+    /// input fee of spending all its parts is paid, so a receiver swapping the parts keeps
+    /// exactly this amount. Adding the fee can change the number of parts and with it the fee,
+    /// so gross amounts are tried in turn. When no gross nets exactly this amount, because
+    /// a carry drops parts and the fee with them, the receiver nets slightly more.
     pub fn split_with_fee(&self, fee_and_amounts: &FeeAndAmounts) -> Result<Vec<Self>, Error> {
-        let without_fee_amounts = self.split(fee_and_amounts);
-        let total_fee_ppk = fee_and_amounts
-            .fee
-            .checked_mul(without_fee_amounts.len() as u64)
-            .ok_or(Error::AmountOverflow)?;
-        let fee = Amount::from(total_fee_ppk.div_ceil(1000));
-        let new_amount = self.checked_add(fee).ok_or(Error::AmountOverflow)?;
+        if fee_and_amounts.fee == 0 || *self == Amount::ZERO {
+            return Ok(self.split(fee_and_amounts));
+        }
+
+        // With every part costing as much as the largest denomination is worth, adding parts
+        // never nets more
+        let max_denomination = fee_and_amounts.amounts.iter().max().copied().unwrap_or(0);
+        if fee_and_amounts.fee >= max_denomination.saturating_mul(1000) {
+            return Err(Error::InvalidAmount(format!(
+                "Fee of {} ppk per proof exceeds the largest denomination {}",
+                fee_and_amounts.fee, max_denomination
+            )));
+        }
 
-        let split = new_amount.split(fee_and_amounts);
-        let split_fee_ppk = (split.len() as u64)
-            .checked_mul(fee_and_amounts.fee)
+        // Whoever redeems the parts pays the fee of at least one proof
+        let mut gross = self
+            .checked_add(fee_and_amounts.fee_for_count(1)?)
             .ok_or(Error::AmountOverflow)?;
-        let split_fee = Amount::from(split_fee_ppk.div_ceil(1000));
 
-        if let Some(net_amount) = new_amount.checked_sub(split_fee) {
-            if net_amount >= *self {
+        loop {
+            let split = gross.split(fee_and_amounts);
+            let split_total = Amount::try_sum(split.iter().copied())?;
+            let fee = fee_and_amounts.fee_for_count(split.len())?;
+
+            if split_total.checked_sub(fee).is_some_and(|net| net >= *self) {
                 return Ok(split);
             }
+
+            gross = gross
+                .checked_add(Amount::ONE)
+                .ok_or(Error::AmountOverflow)?;
         }
-        self.checked_add(Amount::ONE)
-            .ok_or(Error::AmountOverflow)?
-            .split_with_fee(fee_and_amounts)
     }
 
     /// Checked addition for Amount. Returns None if overflow occurs.
@@ -523,6 +570,12 @@ impl Amount<CurrencyUnit> {
                 .checked_mul(MSAT_IN_SAT)
                 .ok_or(Error::AmountOverflow)?,
             (CurrencyUnit::Msat, CurrencyUnit::Sat) => self.value / MSAT_IN_SAT,
+            (CurrencyUnit::Custom(_), _) | (_, CurrencyUnit::Custom(_)) => {
+                return Err(Error::CannotConvertCustomUnit(
+                    self.unit.clone(),
+                    target_unit.clone(),
+                ))
+            }
             _ => return Err(Error::CannotConvertUnits),
         };
 
@@ -665,7 +718,10 @@ pub enum SplitTarget {
     None,
     /// Target amount for wallet to have most proofs that add up to value
     Value(Amount),
-    /// Specific amounts to split into **MUST** equal amount being split
+    /// Specific amounts to split into, each a denomination of the keyset
+    ///
+    /// Values must not add up to more than the amount being split, any
+    /// remainder is split into the fewest proofs.
     Values(Vec<Amount>),
 }
 
@@ -701,6 +757,27 @@ mod tests {
         assert_eq!(Amount::from(255).split(&fee_and_amounts), amounts);
     }
 
+    #[test]
+    fn test_split_amount_above_max_amount() {
+        let fee_and_amounts = FeeAndAmounts::from((0, (0..32).map(|x| 2u64.pow(x)).collect()))
+            .with_max_amount(Some(Amount::from(64)));
+
+        assert_eq!(fee_and_amounts.amounts().last(), Some(&64));
+
+        // The natural split of 200 is 128 + 64 + 8
+        let amounts: Vec<Amount> = [64, 64, 64, 8].iter().map(|a| Amount::from(*a)).collect();
+        assert_eq!(Amount::from(200).split(&fee_and_amounts), amounts);
+
+        let amounts: Vec<Amount> = [32, 4].iter().map(|a| Amount::from(*a)).collect();
+        assert_eq!(Amount::from(36).split(&fee_and_amounts), amounts);
+
+        let split = Amount::from(300)
+            .split_targeted(&SplitTarget::Value(Amount::from(128)), &fee_and_amounts)
+            .unwrap();
+        assert!(split.iter().all(|amount| *amount <= Amount::from(64)));
+        assert_eq!(Amount::try_sum(split).unwrap(), Amount::from(300));
+    }
+
     #[test]
     fn test_split_target_amount() {
         let fee_and_amounts = (0, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
@@ -769,9 +846,8 @@ mod tests {
         let fee_and_amounts = (1000, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
 
         let split = amount.split_with_fee(&fee_and_amounts).unwrap();
-        // With fee_ppk=1000 (100%), amount 3 requires proofs totaling at least 5
-        // to cover both the amount (3) and fees (~2 for 2 proofs)
-        assert_eq!(split, vec![Amount::from(4), Amount::from(1)]);
+        // With fee_ppk=1000 (100%), a single proof of 4 pays its fee of 1 and nets 3
+        assert_eq!(split, vec![Amount::from(4)]);
     }
 
     #[test]
@@ -905,6 +981,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_fee_for_count() {
+        let fee_and_amounts: FeeAndAmounts = (400, vec![1, 2, 4]).into();
+
+        assert_eq!(fee_and_amounts.fee_for_count(0).unwrap(), Amount::ZERO);
+        assert_eq!(fee_and_amounts.fee_for_count(1).unwrap(), Amount::from(1));
+        assert_eq!(fee_and_amounts.fee_for_count(3).unwrap(), Amount::from(2));
+        assert_eq!(fee_and_amounts.fee_for_count(5).unwrap(), Amount::from(2));
+
+        let fee_and_amounts: FeeAndAmounts = (u64::MAX, vec![1]).into();
+        assert!(fee_and_amounts.fee_for_count(2).is_err());
+    }
+
+    #[test]
+    fn test_split_with_fee_converges_when_fee_changes_proof_count() {
+        let fee_and_amounts: FeeAndAmounts =
+            (400, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
+
+        // 7 splits into three proofs costing 2, but 7 + 2 = 9 splits into two proofs costing
+        // 1; a single proof of 8 costs 1 and nets exactly 7
+        let split = Amount::from(7).split_with_fee(&fee_and_amounts).unwrap();
+        assert_eq!(split, vec![Amount::from(8)]);
+    }
+
+    #[test]
+    fn test_split_with_fee_nets_amount_with_smallest_gross() {
+        let net = |split: &[Amount], fee_and_amounts: &FeeAndAmounts| {
+            let total = Amount::try_sum(split.iter().copied()).unwrap();
+            let fee = fee_and_amounts.fee_for_count(split.len()).unwrap();
+            total.checked_sub(fee)
+        };
+
+        for fee_ppk in [1, 10, 100, 250, 400, 999, 1000, 2500] {
+            let fee_and_amounts: FeeAndAmounts =
+                (fee_ppk, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
+
+            for amount in (1..=1100).chain([4095, 4096, 65_535, 100_000, 1_000_000]) {
+                let amount = Amount::from(amount);
+                let split = amount.split_with_fee(&fee_and_amounts).unwrap();
+                let gross = Amount::try_sum(split.iter().copied()).unwrap();
+                let net_amount = net(&split, &fee_and_amounts).unwrap();
+
+                assert!(
+                    net_amount >= amount,
+                    "{amount} with fee_ppk {fee_ppk} nets only {net_amount}"
+                );
+
+                // Fees below one unit for every proof a split can have never change the fee
+                if fee_ppk * 64 <= 1000 {
+                    assert_eq!(net_amount, amount, "{amount} with fee_ppk {fee_ppk}");
+                }
+
+                // No smaller gross nets the amount, so the receiver never nets more than
+                // the denominations force
+                let smaller_covers = (u64::from(amount)..u64::from(gross)).any(|smaller| {
+                    let split = Amount::from(smaller).split(&fee_and_amounts);
+                    net(&split, &fee_and_amounts).is_some_and(|net| net >= amount)
+                });
+                assert!(
+                    !smaller_covers,
+                    "{amount} with fee_ppk {fee_ppk} has a gross below {gross}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_split_with_fee_rejects_fee_above_denominations() {
+        let fee_and_amounts: FeeAndAmounts = (4000, vec![1, 2, 4]).into();
+
+        assert!(Amount::from(3).split_with_fee(&fee_and_amounts).is_err());
+    }
+
     #[test]
     fn test_split_values() {
         let fee_and_amounts = (0, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
@@ -937,6 +1086,35 @@ mod tests {
         assert!(values.is_err())
     }
 
+    #[test]
+    fn test_split_values_rejects_unknown_denomination() {
+        let fee_and_amounts = (0, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
+        let amount = Amount::from(10);
+
+        let split_target = SplitTarget::Values(vec![Amount::from(3), Amount::from(1)]);
+        assert!(matches!(
+            amount.split_targeted(&split_target, &fee_and_amounts),
+            Err(Error::InvalidAmount(_))
+        ));
+
+        let split_target = SplitTarget::Values(vec![Amount::ZERO]);
+        assert!(matches!(
+            amount.split_targeted(&split_target, &fee_and_amounts),
+            Err(Error::InvalidAmount(_))
+        ));
+    }
+
+    #[test]
+    fn test_split_value_zero_falls_back_to_default() {
+        let fee_and_amounts = (0, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into();
+        let amount = Amount::from(10);
+
+        let split = amount
+            .split_targeted(&SplitTarget::Value(Amount::ZERO), &fee_and_amounts)
+            .unwrap();
+        assert_eq!(split, amount.split(&fee_and_amounts));
+    }
+
     #[test]
     #[should_panic]
     fn test_amount_addition() {
@@ -1728,6 +1906,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_typed_amount_convert_custom_unit() {
+        let gold = CurrencyUnit::Custom("gold".to_string());
+
+        // Same custom unit is a no-op
+        let amount = Amount::new(100, gold.clone());
+        assert_eq!(amount.convert_to(&gold).unwrap().value(), 100);
+
+        // Custom to bitcoin units is rejected
+        match amount.convert_to(&CurrencyUnit::Sat).unwrap_err() {
+            Error::CannotConvertCustomUnit(from, to) => {
+                assert_eq!(from, gold);
+                assert_eq!(to, CurrencyUnit::Sat);
+            }
+            _ => panic!("Expected CannotConvertCustomUnit error"),
+        }
+
+        // Bitcoin units to custom is rejected
+        let msat = Amount::new(1000, CurrencyUnit::Msat);
+        assert!(matches!(
+            msat.convert_to(&gold),
+            Err(Error::CannotConvertCustomUnit(_, _))
+        ));
+    }
+
     #[test]
     fn test_typed_amount_add_overflow() {
         let a = Amount::new(u64::MAX, CurrencyUnit::Sat);
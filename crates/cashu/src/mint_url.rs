@@ -49,37 +49,23 @@ impl<'de> Deserialize<'de> for MintUrl {
 }
 
 impl MintUrl {
+    /// Canonical form of a mint url
+    ///
+    /// Surrounding whitespace, trailing slashes, the query and the fragment are removed, the
+    /// scheme and host are lowercased and the default port of the scheme is dropped, so
+    /// `HTTPS://Mint.com:443/Bitcoin/` and `https://mint.com/Bitcoin` are the same mint. The case
+    /// of the path is kept.
     fn format_url(url: &str) -> Result<String, Error> {
+        let url = url.trim();
         ensure_cdk!(!url.is_empty(), Error::InvalidUrl);
 
-        let url = url.trim_end_matches('/');
-        // https://URL.com/path/TO/resource -> https://url.com/path/TO/resource
-        let protocol = url
-            .split("://")
-            .nth(0)
-            .ok_or(Error::InvalidUrl)?
-            .to_lowercase();
-        let host = url
-            .split("://")
-            .nth(1)
-            .ok_or(Error::InvalidUrl)?
-            .split('/')
-            .nth(0)
-            .ok_or(Error::InvalidUrl)?
-            .to_lowercase();
-        let path = url
-            .split("://")
-            .nth(1)
-            .ok_or(Error::InvalidUrl)?
-            .split('/')
-            .skip(1)
-            .collect::<Vec<&str>>()
-            .join("/");
-        let mut formatted_url = format!("{protocol}://{host}");
-        if !path.is_empty() {
-            formatted_url.push_str(&format!("/{path}"));
-        }
-        Ok(formatted_url)
+        // Parsing lowercases the scheme and host and drops the default port
+        let mut url = Url::parse(url)?;
+        ensure_cdk!(url.has_host(), Error::InvalidUrl);
+        url.set_query(None);
+        url.set_fragment(None);
+
+        Ok(url.as_str().trim_end_matches('/').to_string())
     }
 
     /// Join onto url
@@ -165,6 +151,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_default_port_removed() {
+        let formatted_url = "https://url-to-check.com/path";
+
+        let url = MintUrl::from_str("https://url-to-check.com:443/path").unwrap();
+        assert_eq!(formatted_url, url.to_string());
+
+        let url = MintUrl::from_str("http://url-to-check.com:80").unwrap();
+        assert_eq!("http://url-to-check.com", url.to_string());
+
+        // Only the default port of the scheme is dropped
+        let url = MintUrl::from_str("http://url-to-check.com:443").unwrap();
+        assert_eq!("http://url-to-check.com:443", url.to_string());
+        let url = MintUrl::from_str("https://url-to-check.com:3338").unwrap();
+        assert_eq!("https://url-to-check.com:3338", url.to_string());
+    }
+
+    #[test]
+    fn test_whitespace_query_and_fragment_removed() {
+        let formatted_url = "https://url-to-check.com/path";
+
+        let url = MintUrl::from_str("  HTTPS://URL-to-check.com/path/ \n").unwrap();
+        assert_eq!(formatted_url, url.to_string());
+
+        let url = MintUrl::from_str("https://url-to-check.com/path/?lang=en#info").unwrap();
+        assert_eq!(formatted_url, url.to_string());
+
+        assert_eq!(
+            MintUrl::from_str("https://url-to-check.com/path?").unwrap(),
+            MintUrl::from_str(formatted_url).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_invalid_urls() {
+        for url in [
+            "",
+            "   ",
+            "url-to-check.com",
+            "localhost:3338",
+            "mailto:mint@example.com",
+        ] {
+            assert_eq!(MintUrl::from_str(url), Err(Error::InvalidUrl), "{url}");
+        }
+    }
+
     #[test]
     fn test_join_paths() {
         let url_no_path = "http://url-to-check.com";
@@ -39,7 +39,7 @@ pub use auth::{
 };
 pub use nut00::{
     BlindSignature, BlindedMessage, CurrencyUnit, PaymentMethod, Proof, Proofs, ProofsMethods,
-    Token, TokenV3, TokenV4, Witness,
+    Token, TokenMemo, TokenV3, TokenV4, Witness,
 };
 #[cfg(feature = "wallet")]
 pub use nut00::{PreMint, PreMintSecrets};
@@ -58,9 +58,12 @@ pub use nut05::{
     MeltMethodSettings, MeltQuoteCustomRequest, MeltQuoteCustomResponse, MeltRequest,
     QuoteState as MeltQuoteState, Settings as NUT05Settings,
 };
-pub use nut06::{ContactInfo, MintInfo, MintVersion, Nuts};
+pub use nut06::{
+    ContactInfo, MeltChangePolicy, MintInfo, MintVersion, Nuts, PreviousPubkey, RequestLimits,
+    UnitMetadata,
+};
 pub use nut07::{CheckStateRequest, CheckStateResponse, ProofState, State};
-pub use nut09::{RestoreRequest, RestoreResponse};
+pub use nut09::{RestoreRequest, RestoreResponse, MAX_RESTORE_PAGE_SIZE};
 pub use nut10::{Kind, Secret as Nut10Secret, SecretData, SpendingConditionVerification};
 pub use nut11::{Conditions, P2PKWitness, SigFlag, SpendingConditions};
 pub use nut12::{BlindSignatureDleq, ProofDleq};
@@ -71,9 +74,13 @@ pub use nut18::{
     Nut10SecretRequest, PaymentRequest, PaymentRequestBuilder, PaymentRequestPayload, Transport,
     TransportBuilder, TransportType,
 };
+pub use nut20::{
+    MintQuoteStatus, MintQuotesPubkey, MintQuotesRequest, MintQuotesResponse,
+    MAX_MINT_QUOTES_PUBKEYS,
+};
 pub use nut23::{
-    MeltOptions, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, QuoteState as MintQuoteState,
+    MeltOptions, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltQuoteFees,
+    MintQuoteBolt11Request, MintQuoteBolt11Response, QuoteState as MintQuoteState,
 };
 pub use nut25::{MeltQuoteBolt12Request, MintQuoteBolt12Request, MintQuoteBolt12Response};
 #[cfg(all(feature = "wallet", feature = "nostr"))]
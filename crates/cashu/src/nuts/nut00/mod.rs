@@ -35,7 +35,7 @@ use crate::secret::Secret;
 use crate::Amount;
 
 pub mod token;
-pub use token::{Token, TokenV3, TokenV4};
+pub use token::{Token, TokenMemo, TokenV3, TokenV4};
 
 /// List of [Proof]
 pub type Proofs = Vec<Proof>;
@@ -582,9 +582,23 @@ impl CurrencyUnit {
             Self::Usd => Some(2),
             Self::Eur => Some(3),
             Self::Auth => Some(4),
-            _ => None,
+            Self::Custom(name) => Some(Self::custom_derivation_index(name)),
         }
     }
+
+    /// Deterministic derivation index for a custom unit
+    ///
+    /// Derived from the hash of the lowercased unit name and kept clear of the
+    /// indexes reserved for the built-in units.
+    fn custom_derivation_index(name: &str) -> u32 {
+        use bitcoin::hashes::sha256;
+
+        let hash = <sha256::Hash as bitcoin::hashes::Hash>::hash(name.to_lowercase().as_bytes());
+        let bytes = bitcoin::hashes::Hash::as_byte_array(&hash);
+        let index = u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) & 0x7fff_ffff;
+
+        index.max(5)
+    }
 }
 
 impl FromStr for CurrencyUnit {
@@ -918,9 +932,29 @@ impl PreMintSecrets {
         })
     }
 
+    /// Number of blank outputs needed for the NUT-08 change of `fee_reserve`
+    pub fn blank_count(fee_reserve: Amount) -> u32 {
+        ((u64::from(fee_reserve) as f64).log2().ceil() as u32).max(1)
+    }
+
+    /// Number of blank outputs needed for the NUT-08 change of `fee_reserve` from a mint that
+    /// signs no amount above `max_amount`
+    ///
+    /// Change above `max_amount` is returned as several outputs of `max_amount`, the rest as
+    /// powers of two below it.
+    pub fn blank_count_with_max_amount(fee_reserve: Amount, max_amount: Option<Amount>) -> u32 {
+        match max_amount {
+            Some(max_amount) if max_amount > Amount::ZERO && fee_reserve > max_amount => {
+                let repeated = u64::from(fee_reserve) / u64::from(max_amount);
+                (repeated as u32).saturating_add(Self::blank_count(max_amount))
+            }
+            _ => Self::blank_count(fee_reserve),
+        }
+    }
+
     /// Blank Outputs used for NUT-08 change
     pub fn blank(keyset_id: Id, fee_reserve: Amount) -> Result<Self, Error> {
-        let count = ((u64::from(fee_reserve) as f64).log2().ceil() as u64).max(1);
+        let count = Self::blank_count(fee_reserve);
 
         let mut output = Vec::with_capacity(count as usize);
 
@@ -1096,12 +1130,33 @@ mod tests {
         )
         .unwrap();
         assert_eq!(b.len(), 10);
+        assert_eq!(PreMintSecrets::blank_count(Amount::from(1000)), 10);
 
         let b = PreMintSecrets::blank(Id::from_str("009a1f293253e41e").unwrap(), Amount::from(1))
             .unwrap();
         assert_eq!(b.len(), 1);
     }
 
+    #[test]
+    fn test_blank_count_with_max_amount() {
+        let max_amount = Some(Amount::from(64));
+
+        assert_eq!(
+            PreMintSecrets::blank_count_with_max_amount(Amount::from(1000), None),
+            10
+        );
+        assert_eq!(
+            PreMintSecrets::blank_count_with_max_amount(Amount::from(50), max_amount),
+            PreMintSecrets::blank_count(Amount::from(50))
+        );
+
+        // 1000 = 15 * 64 + 40, the 40 needs at most 6 outputs below 64
+        assert_eq!(
+            PreMintSecrets::blank_count_with_max_amount(Amount::from(1000), max_amount),
+            21
+        );
+    }
+
     #[test]
     fn custom_unit_ser_der() {
         let unit = CurrencyUnit::Custom(String::from("test"));
@@ -1110,6 +1165,24 @@ mod tests {
         assert_eq!(unit, deserialized)
     }
 
+    #[test]
+    #[cfg(feature = "mint")]
+    fn custom_unit_derivation_index() {
+        let gold = CurrencyUnit::Custom(String::from("gold"));
+        let index = gold.derivation_index().unwrap();
+
+        assert!(index > 4);
+        assert!(index < 0x8000_0000);
+        assert_eq!(
+            CurrencyUnit::Custom(String::from("GOLD")).derivation_index(),
+            Some(index)
+        );
+        assert_ne!(
+            CurrencyUnit::Custom(String::from("silver")).derivation_index(),
+            Some(index)
+        );
+    }
+
     #[test]
     fn test_payment_method_parsing() {
         // Test known methods (case insensitive)
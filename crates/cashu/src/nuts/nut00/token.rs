@@ -91,6 +91,19 @@ impl Token {
         }
     }
 
+    /// [`Token`] memo parsed as a [`TokenMemo`], if it is one
+    pub fn structured_memo(&self) -> Option<TokenMemo> {
+        self.memo().as_deref().and_then(TokenMemo::parse)
+    }
+
+    /// Text of the [`Token`] memo, without the mint snapshot of a [`TokenMemo`]
+    pub fn memo_text(&self) -> Option<String> {
+        match self.structured_memo() {
+            Some(memo) => memo.text,
+            None => self.memo().clone(),
+        }
+    }
+
     /// Unit
     pub fn unit(&self) -> Option<CurrencyUnit> {
         match self {
@@ -616,6 +629,42 @@ impl TokenV4Token {
     }
 }
 
+/// Structured token memo
+///
+/// Carries a snapshot of the mint's name and icon next to the memo text, so the receiver sees
+/// where the token is from before contacting the mint. Encoded as a JSON object in the memo of
+/// the token, wallets without support show the JSON as the memo.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenMemo {
+    /// Memo text
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    /// Name of the mint when the token was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint_name: Option<String>,
+    /// Icon url of the mint when the token was created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mint_icon_url: Option<String>,
+}
+
+impl TokenMemo {
+    /// Parse a token memo, `None` if it is a plain text memo
+    pub fn parse(memo: &str) -> Option<Self> {
+        serde_json::from_str::<Self>(memo)
+            .ok()
+            .filter(|memo| memo != &Self::default())
+    }
+}
+
+impl fmt::Display for TokenMemo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use serde::ser::Error;
+        let json = serde_json::to_string(self).map_err(|e| fmt::Error::custom(e.to_string()))?;
+        write!(f, "{json}")
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use std::str::FromStr;
@@ -628,9 +677,14 @@ mod tests {
     use crate::dhke::hash_to_curve;
     use crate::mint_url::MintUrl;
     use crate::nuts::nut11::{Conditions, SigFlag, SpendingConditions};
+    use crate::nuts::{ProofDleq, SecretKey};
     use crate::secret::Secret;
     use crate::util::hex;
 
+    /// CBOR bytes the DLEQ proof adds to a proof of a V4 token, three 32 byte keys and their
+    /// field names
+    const DLEQ_SIZE: usize = 108;
+
     #[test]
     fn test_token_padding() {
         let token_str_with_padding = "cashuAeyJ0b2tlbiI6W3sibWludCI6Imh0dHBzOi8vODMzMy5zcGFjZTozMzM4IiwicHJvb2ZzIjpbeyJhbW91bnQiOjIsImlkIjoiMDA5YTFmMjkzMjUzZTQxZSIsInNlY3JldCI6IjQwNzkxNWJjMjEyYmU2MWE3N2UzZTZkMmFlYjRjNzI3OTgwYmRhNTFjZDA2YTZhZmMyOWUyODYxNzY4YTc4MzciLCJDIjoiMDJiYzkwOTc5OTdkODFhZmIyY2M3MzQ2YjVlNDM0NWE5MzQ2YmQyYTUwNmViNzk1ODU5OGE3MmYwY2Y4NTE2M2VhIn0seyJhbW91bnQiOjgsImlkIjoiMDA5YTFmMjkzMjUzZTQxZSIsInNlY3JldCI6ImZlMTUxMDkzMTRlNjFkNzc1NmIwZjhlZTBmMjNhNjI0YWNhYTNmNGUwNDJmNjE0MzNjNzI4YzcwNTdiOTMxYmUiLCJDIjoiMDI5ZThlNTA1MGI4OTBhN2Q2YzA5NjhkYjE2YmMxZDVkNWZhMDQwZWExZGUyODRmNmVjNjlkNjEyOTlmNjcxMDU5In1dfV0sInVuaXQiOiJzYXQiLCJtZW1vIjoiVGhhbmsgeW91IHZlcnkgbXVjaC4ifQ==";
@@ -835,6 +889,7 @@ mod tests {
                     active: true,
                     input_fee_ppk: 0,
                     final_expiry: None,
+                    max_amount: None,
                 }
             })
             .collect();
@@ -883,6 +938,7 @@ mod tests {
                     active: true,
                     input_fee_ppk: 0,
                     final_expiry: None,
+                    max_amount: None,
                 }
             })
             .collect();
@@ -1066,4 +1122,107 @@ mod tests {
         // token_secrets length equals number of proofs even if conditions identical
         assert_eq!(token.token_secrets().len(), 2);
     }
+
+    fn proofs_with_dleq(count: u64, with_dleq: bool) -> Proofs {
+        let keyset_id = Id::from_str("009a1f293253e41e").unwrap();
+        (0..count)
+            .map(|i| Proof {
+                amount: Amount::from(1 << i),
+                keyset_id,
+                secret: Secret::generate(),
+                c: SecretKey::generate().public_key(),
+                witness: None,
+                dleq: with_dleq.then(|| {
+                    ProofDleq::new(
+                        SecretKey::generate(),
+                        SecretKey::generate(),
+                        SecretKey::generate(),
+                    )
+                }),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_token_v4_dleq_size() {
+        let mint_url = MintUrl::from_str("https://example.com").unwrap();
+        let size = |proofs: Proofs| {
+            Token::new(mint_url.clone(), proofs, None, CurrencyUnit::Sat)
+                .to_raw_bytes()
+                .unwrap()
+                .len()
+        };
+
+        let dleq_size = size(proofs_with_dleq(1, true)) - size(proofs_with_dleq(1, false));
+        assert_eq!(dleq_size, DLEQ_SIZE);
+
+        // Every proof grows by the same fixed size
+        for count in [2, 8] {
+            let without_dleq = size(proofs_with_dleq(count, false));
+            let with_dleq = size(proofs_with_dleq(count, true));
+            assert_eq!(with_dleq - without_dleq, count as usize * DLEQ_SIZE);
+        }
+
+        // And the DLEQ proofs survive encoding
+        let token = Token::new(
+            mint_url.clone(),
+            proofs_with_dleq(4, true),
+            None,
+            CurrencyUnit::Sat,
+        );
+        let decoded = Token::from_str(&token.to_string()).unwrap();
+        let keysets = vec![KeySetInfo {
+            id: Id::from_str("009a1f293253e41e").unwrap(),
+            unit: CurrencyUnit::Sat,
+            active: true,
+            input_fee_ppk: 0,
+            final_expiry: None,
+            max_amount: None,
+        }];
+        assert!(decoded
+            .proofs(&keysets)
+            .unwrap()
+            .iter()
+            .all(|proof| proof.dleq.is_some()));
+    }
+
+    #[test]
+    fn test_token_memo() {
+        let mint_url = MintUrl::from_str("https://example.com").unwrap();
+        let memo = TokenMemo {
+            text: Some("Thanks for lunch".to_string()),
+            mint_name: Some("Example mint".to_string()),
+            mint_icon_url: Some("https://example.com/icon.png".to_string()),
+        };
+
+        let token = Token::new(
+            mint_url.clone(),
+            proofs_with_dleq(1, false),
+            Some(memo.to_string()),
+            CurrencyUnit::Sat,
+        );
+        let decoded = Token::from_str(&token.to_string()).unwrap();
+        assert_eq!(decoded.structured_memo(), Some(memo.clone()));
+        assert_eq!(decoded.memo_text(), Some("Thanks for lunch".to_string()));
+
+        // The mint snapshot costs its JSON, next to the plain text memo
+        let plain = Token::new(
+            mint_url,
+            proofs_with_dleq(1, false),
+            Some("Thanks for lunch".to_string()),
+            CurrencyUnit::Sat,
+        );
+        assert!(plain.structured_memo().is_none());
+        assert_eq!(plain.memo_text(), Some("Thanks for lunch".to_string()));
+        // plus a byte for the CBOR length of the longer string
+        assert_eq!(
+            token.to_raw_bytes().unwrap().len() - plain.to_raw_bytes().unwrap().len(),
+            memo.to_string().len() - "Thanks for lunch".len() + 1
+        );
+
+        // Plain text memos that happen to be JSON are left alone
+        assert_eq!(TokenMemo::parse("{}"), None);
+        assert_eq!(TokenMemo::parse(r#"{"text":"hi","amount":1}"#), None);
+        assert_eq!(TokenMemo::parse("\"hi\""), None);
+    }
 }
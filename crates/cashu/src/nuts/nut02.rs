@@ -535,6 +535,11 @@ pub struct KeySetInfo {
     /// Expiry of the keyset
     #[serde(skip_serializing_if = "Option::is_none")]
     pub final_expiry: Option<u64>,
+    /// Largest amount the mint signs with this keyset
+    ///
+    /// Outputs above it are rejected, even if the keyset has a key for the amount.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Amount>,
 }
 
 /// List of [KeySetInfo]
@@ -220,11 +220,19 @@ impl Serialize for MeltMethodSettings {
         }
 
         let mut amountless_in_top_level = false;
-        if let Some(MeltMethodOptions::Bolt11 { amountless }) = &self.options {
-            if *amountless {
+        let mut options_field = None;
+        match &self.options {
+            Some(MeltMethodOptions::Bolt11 { amountless }) => {
+                if *amountless {
+                    num_fields += 1;
+                    amountless_in_top_level = true;
+                }
+            }
+            Some(options @ MeltMethodOptions::Keysend { .. }) => {
                 num_fields += 1;
-                amountless_in_top_level = true;
+                options_field = Some(options);
             }
+            None => {}
         }
 
         let mut state = serializer.serialize_struct("MeltMethodSettings", num_fields)?;
@@ -245,6 +253,11 @@ impl Serialize for MeltMethodSettings {
             state.serialize_field("amountless", &true)?;
         }
 
+        // Options other than bolt11 have no top level fields
+        if let Some(options) = options_field {
+            state.serialize_field("options", options)?;
+        }
+
         state.end()
     }
 }
@@ -267,6 +280,7 @@ impl<'de> Visitor<'de> for MeltMethodSettingsVisitor {
         let mut min_amount: Option<Amount> = None;
         let mut max_amount: Option<Amount> = None;
         let mut amountless: Option<bool> = None;
+        let mut other_options: Option<MeltMethodOptions> = None;
 
         while let Some(key) = map.next_key::<String>()? {
             match key.as_str() {
@@ -305,14 +319,19 @@ impl<'de> Visitor<'de> for MeltMethodSettingsVisitor {
                     // field which we will handle specially
                     let options: Option<MeltMethodOptions> = map.next_value()?;
 
-                    if let Some(MeltMethodOptions::Bolt11 {
-                        amountless: amountless_from_options,
-                    }) = options
-                    {
-                        // If we already found a top-level amountless, use that instead
-                        if amountless.is_none() {
-                            amountless = Some(amountless_from_options);
+                    match options {
+                        Some(MeltMethodOptions::Bolt11 {
+                            amountless: amountless_from_options,
+                        }) => {
+                            // If we already found a top-level amountless, use that instead
+                            if amountless.is_none() {
+                                amountless = Some(amountless_from_options);
+                            }
+                        }
+                        Some(options @ MeltMethodOptions::Keysend { .. }) => {
+                            other_options = Some(options);
                         }
+                        None => {}
                     }
                 }
                 _ => {
@@ -330,7 +349,7 @@ impl<'de> Visitor<'de> for MeltMethodSettingsVisitor {
         {
             amountless.map(|amountless| MeltMethodOptions::Bolt11 { amountless })
         } else {
-            None
+            other_options
         };
 
         Ok(MeltMethodSettings {
@@ -362,6 +381,11 @@ pub enum MeltMethodOptions {
         /// Mint supports paying bolt11 amountless
         amountless: bool,
     },
+    /// Keysend Options
+    Keysend {
+        /// Mint estimates the fee reserve by probing a route to the destination
+        fee_probe: bool,
+    },
 }
 
 impl Settings {
@@ -605,4 +629,23 @@ mod tests {
             _ => panic!("Expected Bolt11 options with amountless = true"),
         }
     }
+
+    #[test]
+    fn test_keysend_options_round_trip() {
+        let settings = MeltMethodSettings {
+            method: PaymentMethod::from("keysend"),
+            unit: CurrencyUnit::Sat,
+            min_amount: Some(Amount::from(1)),
+            max_amount: Some(Amount::from(10000)),
+            options: Some(MeltMethodOptions::Keysend { fee_probe: true }),
+        };
+
+        let serialized = to_string(&settings).unwrap();
+        let parsed: serde_json::Value = from_str(&serialized).unwrap();
+        assert_eq!(parsed["options"], json!({ "fee_probe": true }));
+        assert!(parsed.get("amountless").is_none());
+
+        let deserialized: MeltMethodSettings = from_str(&serialized).unwrap();
+        assert_eq!(deserialized, settings);
+    }
 }
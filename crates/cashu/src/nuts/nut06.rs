@@ -14,7 +14,7 @@ use super::nut19::CachedEndpoint;
 use super::{nut04, nut05, nut15, nut19, MppMethodSettings};
 #[cfg(feature = "auth")]
 use super::{AuthRequired, BlindAuthSettings, ClearAuthSettings, ProtectedEndpoint};
-use crate::CurrencyUnit;
+use crate::{Amount, CurrencyUnit};
 
 /// Mint Version
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
@@ -76,6 +76,9 @@ pub struct MintInfo {
     /// hex pubkey of the mint
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pubkey: Option<PublicKey>,
+    /// Pubkeys the mint rotated away from, still valid for announcements until they expire
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub previous_pubkeys: Vec<PreviousPubkey>,
     /// implementation name and the version running
     #[serde(skip_serializing_if = "Option::is_none")]
     pub version: Option<MintVersion>,
@@ -186,6 +189,17 @@ impl MintInfo {
         }
     }
 
+    /// Set mint urls
+    pub fn urls<S>(self, urls: Vec<S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            urls: Some(urls.into_iter().map(Into::into).collect()),
+            ..self
+        }
+    }
+
     /// Set motd
     pub fn motd<S>(self, motd: S) -> Self
     where
@@ -219,6 +233,16 @@ impl MintInfo {
         }
     }
 
+    /// Pubkeys an announcement of the mint may be signed with at `now`
+    pub fn announcement_pubkeys(&self, now: u64) -> impl Iterator<Item = &PublicKey> {
+        self.pubkey.iter().chain(
+            self.previous_pubkeys
+                .iter()
+                .filter(move |previous| previous.expires_at > now)
+                .map(|previous| &previous.pubkey),
+        )
+    }
+
     /// Get protected endpoints
     #[cfg(feature = "auth")]
     pub fn protected_endpoints(&self) -> HashMap<ProtectedEndpoint, AuthRequired> {
@@ -337,6 +361,18 @@ pub struct Nuts {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[cfg(feature = "auth")]
     pub nut22: Option<BlindAuthSettings>,
+    /// Maximum number of inputs, outputs and checked Ys accepted in a single request
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_limits: Option<RequestLimits>,
+    /// Limits on the change signed for melts
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub melt_change: Option<MeltChangePolicy>,
+    /// Display metadata for custom currency units
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub units: Vec<UnitMetadata>,
 }
 
 impl Nuts {
@@ -422,6 +458,7 @@ impl Nuts {
         Self {
             nut15: nut15::Settings {
                 methods: mpp_settings,
+                disabled: false,
             },
             ..self
         }
@@ -430,7 +467,10 @@ impl Nuts {
     /// Nut17 settings
     pub fn nut17(self, supported: Vec<SupportedMethods>) -> Self {
         Self {
-            nut17: super::nut17::SupportedSettings { supported },
+            nut17: super::nut17::SupportedSettings {
+                supported,
+                ..self.nut17
+            },
             ..self
         }
     }
@@ -454,6 +494,27 @@ impl Nuts {
         }
     }
 
+    /// Request limits
+    pub fn request_limits(self, request_limits: RequestLimits) -> Self {
+        Self {
+            request_limits: Some(request_limits),
+            ..self
+        }
+    }
+
+    /// Melt change policy
+    pub fn melt_change(self, melt_change: MeltChangePolicy) -> Self {
+        Self {
+            melt_change: Some(melt_change),
+            ..self
+        }
+    }
+
+    /// Custom unit metadata
+    pub fn units(self, units: Vec<UnitMetadata>) -> Self {
+        Self { units, ..self }
+    }
+
     /// Units where minting is supported
     pub fn supported_mint_units(&self) -> Vec<&CurrencyUnit> {
         self.nut04
@@ -485,6 +546,88 @@ pub struct SupportedSettings {
     pub supported: bool,
 }
 
+/// Display metadata for a currency unit
+///
+/// Advertised for custom units so wallets know how to present amounts that
+/// have no conversion to bitcoin units.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct UnitMetadata {
+    /// Unit the metadata applies to
+    pub unit: CurrencyUnit,
+    /// Symbol used when displaying amounts
+    pub symbol: String,
+    /// Number of decimal places of the smallest amount
+    pub decimals: u8,
+    /// Description of the unit
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+/// Default maximum number of inputs in a single request
+pub const DEFAULT_MAX_INPUTS_PER_REQUEST: usize = 1000;
+
+/// Default maximum number of outputs in a single request
+pub const DEFAULT_MAX_OUTPUTS_PER_REQUEST: usize = 1000;
+
+/// Default maximum number of Ys in a single NUT-07 check state request
+pub const DEFAULT_MAX_CHECK_STATE_YS: usize = 1000;
+
+fn default_max_check_state_ys() -> usize {
+    DEFAULT_MAX_CHECK_STATE_YS
+}
+
+/// Request size limits
+///
+/// Wallets should split swaps, melts, mints and state checks that exceed these
+/// limits into several requests.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct RequestLimits {
+    /// Maximum number of inputs (proofs) per request
+    pub max_inputs: usize,
+    /// Maximum number of outputs (blinded messages) per request
+    pub max_outputs: usize,
+    /// Maximum number of Ys per check state request
+    #[serde(default = "default_max_check_state_ys")]
+    pub max_check_state_ys: usize,
+}
+
+impl Default for RequestLimits {
+    fn default() -> Self {
+        Self {
+            max_inputs: DEFAULT_MAX_INPUTS_PER_REQUEST,
+            max_outputs: DEFAULT_MAX_OUTPUTS_PER_REQUEST,
+            max_check_state_ys: DEFAULT_MAX_CHECK_STATE_YS,
+        }
+    }
+}
+
+/// Limits on the change signed for melts
+///
+/// Change owed beyond these limits is kept by the mint, so wallets can warn users
+/// before melting with inputs that would leave such a remainder.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct MeltChangePolicy {
+    /// Maximum number of change outputs signed, the largest denominations are kept
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_outputs: Option<usize>,
+    /// Smallest denomination signed as change
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub min_denomination: Option<Amount>,
+}
+
+impl MeltChangePolicy {
+    /// Whether the policy signs all the change owed
+    pub fn is_unrestricted(&self) -> bool {
+        self.max_outputs.is_none() && self.min_denomination.is_none()
+    }
+}
+
 /// Contact Info
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
@@ -502,6 +645,16 @@ impl ContactInfo {
     }
 }
 
+/// Pubkey the mint rotated away from
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct PreviousPubkey {
+    /// hex pubkey
+    pub pubkey: PublicKey,
+    /// Unix timestamp until which announcements signed by this pubkey are accepted
+    pub expires_at: u64,
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -682,6 +835,41 @@ mod tests {
         assert_eq!(info, mint_info);
     }
 
+    #[test]
+    fn test_mint_info_urls() {
+        let mint_info_str = r#"{
+  "name": "Bob's Cashu mint",
+  "nuts": {},
+  "urls": ["https://mint.example.com", "http://mintxyz.onion"],
+  "tos_url": "https://mint.example.com/tos"
+}"#;
+        let mint_info: MintInfo = serde_json::from_str(mint_info_str).unwrap();
+        assert_eq!(
+            mint_info.urls,
+            Some(vec![
+                "https://mint.example.com".to_string(),
+                "http://mintxyz.onion".to_string()
+            ])
+        );
+        assert_eq!(
+            mint_info.tos_url.as_deref(),
+            Some("https://mint.example.com/tos")
+        );
+
+        // Older mints do not advertise them
+        let mint_info: MintInfo =
+            serde_json::from_str(r#"{"name": "Bob's Cashu mint", "nuts": {}}"#).unwrap();
+        assert_eq!(mint_info.urls, None);
+        assert_eq!(mint_info.tos_url, None);
+
+        let mint_info = MintInfo::new()
+            .urls(vec!["https://mint.example.com"])
+            .tos_url("https://mint.example.com/tos");
+        let json = serde_json::to_string(&mint_info).unwrap();
+        assert!(json.contains(r#""urls":["https://mint.example.com"]"#));
+        assert!(json.contains(r#""tos_url":"https://mint.example.com/tos""#));
+    }
+
     #[test]
     fn test_nut15_not_serialized_when_empty() {
         // Test with default (empty) NUT15
@@ -700,10 +888,10 @@ mod tests {
         // Test with non-empty NUT15
         let mint_info_with_nut15 = MintInfo {
             name: Some("Test Mint".to_string()),
-            nuts: Nuts::default().nut15(vec![MppMethodSettings {
-                method: crate::PaymentMethod::Known(KnownMethod::Bolt11),
-                unit: crate::CurrencyUnit::Sat,
-            }]),
+            nuts: Nuts::default().nut15(vec![MppMethodSettings::new(
+                crate::PaymentMethod::Known(KnownMethod::Bolt11),
+                crate::CurrencyUnit::Sat,
+            )]),
             ..Default::default()
         };
 
@@ -715,4 +903,66 @@ mod tests {
         assert!(parsed["nuts"]["15"]["methods"].is_array());
         assert_eq!(parsed["nuts"]["15"]["methods"].as_array().unwrap().len(), 1);
     }
+
+    #[test]
+    fn test_melt_change_policy_extension() {
+        let json = serde_json::to_value(MintInfo::default()).unwrap();
+        assert!(json["nuts"]["melt_change"].is_null());
+
+        let mint_info = MintInfo {
+            nuts: Nuts::default().melt_change(MeltChangePolicy {
+                max_outputs: Some(4),
+                min_denomination: Some(Amount::from(8)),
+            }),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&mint_info).unwrap();
+        assert_eq!(json["nuts"]["melt_change"]["max_outputs"], 4);
+        assert_eq!(json["nuts"]["melt_change"]["min_denomination"], 8);
+
+        let parsed: MintInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, mint_info);
+    }
+
+    #[test]
+    fn test_request_limits_check_state_default() {
+        // Mints advertising limits before the check state limit get the default
+        let json = r#"{"nuts":{"request_limits":{"max_inputs":100,"max_outputs":200}}}"#;
+        let mint_info: MintInfo = serde_json::from_str(json).unwrap();
+
+        assert_eq!(
+            mint_info.nuts.request_limits,
+            Some(RequestLimits {
+                max_inputs: 100,
+                max_outputs: 200,
+                max_check_state_ys: DEFAULT_MAX_CHECK_STATE_YS,
+            })
+        );
+    }
+
+    #[test]
+    fn test_unit_metadata_extension() {
+        // Not serialized when no custom units are configured
+        let json = serde_json::to_value(MintInfo::default()).unwrap();
+        assert!(json["nuts"]["units"].is_null());
+
+        let mint_info = MintInfo {
+            nuts: Nuts::default().units(vec![UnitMetadata {
+                unit: crate::CurrencyUnit::Custom("gold".to_string()),
+                symbol: "Au".to_string(),
+                decimals: 3,
+                description: Some("Grams of gold".to_string()),
+            }]),
+            ..Default::default()
+        };
+
+        let json = serde_json::to_value(&mint_info).unwrap();
+        assert_eq!(json["nuts"]["units"][0]["unit"], "gold");
+        assert_eq!(json["nuts"]["units"][0]["symbol"], "Au");
+        assert_eq!(json["nuts"]["units"][0]["decimals"], 3);
+
+        let parsed: MintInfo = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, mint_info);
+    }
 }
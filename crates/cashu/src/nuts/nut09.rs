@@ -6,12 +6,47 @@ use serde::{Deserialize, Serialize};
 
 use super::nut00::{BlindSignature, BlindedMessage};
 
+/// Maximum number of outputs a mint processes in one page of a paginated restore
+pub const MAX_RESTORE_PAGE_SIZE: u64 = 1_000;
+
 /// Restore Request [NUT-09]
+///
+/// Setting `limit` opts in to a paginated response: the mint processes at most `limit` outputs
+/// and, if outputs are left, returns the offset to continue from. Without it the whole request
+/// is answered at once.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
 pub struct RestoreRequest {
     /// Outputs
     pub outputs: Vec<BlindedMessage>,
+    /// Position of the first output in the client's full list of outputs
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Maximum number of outputs to process
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+}
+
+impl RestoreRequest {
+    /// Create new [`RestoreRequest`] answered at once
+    pub fn new(outputs: Vec<BlindedMessage>) -> Self {
+        Self {
+            outputs,
+            offset: None,
+            limit: None,
+        }
+    }
+
+    /// Create new [`RestoreRequest`] for a page of at most `limit` outputs
+    ///
+    /// `outputs` are the outputs from position `offset` of the full list.
+    pub fn paginated(outputs: Vec<BlindedMessage>, offset: u64, limit: u64) -> Self {
+        Self {
+            outputs,
+            offset: Some(offset),
+            limit: Some(limit),
+        }
+    }
 }
 
 /// Restore Response [NUT-09]
@@ -25,6 +60,17 @@ pub struct RestoreResponse {
     /// Promises
     // Temp compatibility with cashu-ts
     pub promises: Option<Vec<BlindSignature>>,
+    /// Offset of the page, echoed from a paginated request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub offset: Option<u64>,
+    /// Page size the mint applied to a paginated request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// Position of the first output left unprocessed, if any
+    ///
+    /// The client continues by sending its outputs from this position.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub next_offset: Option<u64>,
 }
 
 mod test {
@@ -38,4 +84,37 @@ mod test {
 
         println!("{:?}", res);
     }
+
+    #[test]
+    fn restore_pagination_fields() {
+        use super::*;
+
+        // Requests without pagination keep the original shape
+        let request = RestoreRequest::new(vec![]);
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"outputs":[]}"#
+        );
+
+        let request = RestoreRequest::paginated(vec![], 1000, 500);
+        let json = serde_json::to_string(&request).unwrap();
+        assert_eq!(json, r#"{"outputs":[],"offset":1000,"limit":500}"#);
+        assert_eq!(
+            serde_json::from_str::<RestoreRequest>(&json).unwrap(),
+            request
+        );
+
+        // Responses of mints without pagination have no page fields
+        let res: RestoreResponse =
+            serde_json::from_str(r#"{"outputs":[],"signatures":[]}"#).unwrap();
+        assert_eq!(res.next_offset, None);
+
+        let res: RestoreResponse = serde_json::from_str(
+            r#"{"outputs":[],"signatures":[],"offset":0,"limit":500,"next_offset":500}"#,
+        )
+        .unwrap();
+        assert_eq!(res.offset, Some(0));
+        assert_eq!(res.limit, Some(500));
+        assert_eq!(res.next_offset, Some(500));
+    }
 }
@@ -541,20 +541,15 @@ pub trait SpendingConditionVerification {
 
         // Try primary path first (data + pubkeys)
         // Per NUT-11: "Locktime Multisig conditions continue to apply"
-        {
-            let primary_valid = extract_signatures_from_witness(first_witness)
-                .ok()
-                .and_then(|sigs| {
-                    super::nut11::valid_signatures(
-                        msg_to_sign.as_bytes(),
-                        &requirements.pubkeys,
-                        &sigs,
-                    )
-                    .ok()
-                })
-                .is_some_and(|count| count >= requirements.required_sigs);
-
-            if primary_valid {
+        // Duplicate signatures are rejected outright rather than failing the path
+        if let Ok(sigs) = extract_signatures_from_witness(first_witness) {
+            let valid_sig_count = super::nut11::valid_signatures(
+                msg_to_sign.as_bytes(),
+                &requirements.pubkeys,
+                &sigs,
+            )?;
+
+            if valid_sig_count >= requirements.required_sigs {
                 return Ok(());
             }
         }
@@ -71,6 +71,9 @@ pub enum Error {
     /// Duplicate signature from same pubkey
     #[error("Duplicate signature from the same pubkey detected")]
     DuplicateSignature,
+    /// Signature is not from any key of the spending conditions
+    #[error("Signature is not from a key of the spending conditions")]
+    UnknownSigner,
     /// Preimage not supported in P2PK
     #[error("P2PK does not support preimage requirements")]
     PreimageNotSupportedInP2PK,
@@ -118,24 +121,24 @@ impl P2PKWitness {
 
 impl Proof {
     /// Sign [Proof]
+    ///
+    /// Signing again with a key that already signed the proof does nothing, as the mint rejects
+    /// more than one signature per key.
     pub fn sign_p2pk(&mut self, secret_key: SecretKey) -> Result<(), Error> {
         let msg: Vec<u8> = self.secret.to_bytes();
-        let signature: Signature = secret_key.sign(&msg)?;
-
-        let signatures = vec![signature.to_string()];
+        add_signature(&mut self.witness, &msg, &secret_key)
+    }
 
-        match self.witness.as_mut() {
-            Some(witness) => {
-                witness.add_signatures(signatures);
-            }
-            None => {
-                let mut p2pk_witness = Witness::P2PKWitness(P2PKWitness::default());
-                p2pk_witness.add_signatures(signatures);
-                self.witness = Some(p2pk_witness);
-            }
-        };
+    /// Add the signatures of `other`, a copy of this proof signed by co-signers
+    ///
+    /// Collects the signatures of a multisig proof that each signer signed separately. Signatures
+    /// from keys that already signed this proof are skipped.
+    pub fn combine_p2pk_signatures(&mut self, other: &Proof) -> Result<(), Error> {
+        ensure_cdk!(self.secret == other.secret, Error::IncorrectSecretKind);
 
-        Ok(())
+        let msg: Vec<u8> = self.secret.to_bytes();
+        let signers = condition_keys(&self.secret)?;
+        combine_signatures(&mut self.witness, other.witness.as_ref(), &msg, &signers)
     }
 
     /// Verify P2PK signature on [Proof]
@@ -178,46 +181,32 @@ impl Proof {
 
         let msg: &[u8] = self.secret.as_bytes();
 
+        let signatures = witness_signatures.as_ref().and_then(|sigs| {
+            sigs.iter()
+                .map(|s| Signature::from_str(s))
+                .collect::<Result<Vec<_>, _>>()
+                .ok()
+        });
+
         // Try primary path first (data + pubkeys)
         // Per NUT-11: "Locktime Multisig conditions continue to apply"
-        {
-            let primary_valid = witness_signatures
-                .as_ref()
-                .and_then(|sigs| {
-                    sigs.iter()
-                        .map(|s| Signature::from_str(s))
-                        .collect::<Result<Vec<_>, _>>()
-                        .ok()
-                })
-                .and_then(|sigs| valid_signatures(msg, &requirements.pubkeys, &sigs).ok())
-                .is_some_and(|count| count >= requirements.required_sigs);
-
-            if primary_valid {
+        // Duplicate signatures are rejected outright rather than failing the path
+        if let Some(sigs) = &signatures {
+            if valid_signatures(msg, &requirements.pubkeys, sigs)? >= requirements.required_sigs {
                 return Ok(());
             }
         }
 
         // Primary path failed or no signatures - try refund path if available
-        {
-            if let Some(refund_path) = &requirements.refund_path {
-                // Anyone can spend (locktime passed, no refund keys)
-                if refund_path.required_sigs == 0 {
-                    return Ok(());
-                }
+        if let Some(refund_path) = &requirements.refund_path {
+            // Anyone can spend (locktime passed, no refund keys)
+            if refund_path.required_sigs == 0 {
+                return Ok(());
+            }
 
-                // Need signatures for refund path
-                let refund_valid = witness_signatures
-                    .as_ref()
-                    .and_then(|sigs| {
-                        sigs.iter()
-                            .map(|s| Signature::from_str(s))
-                            .collect::<Result<Vec<_>, _>>()
-                            .ok()
-                    })
-                    .and_then(|sigs| valid_signatures(msg, &refund_path.pubkeys, &sigs).ok())
-                    .is_some_and(|count| count >= refund_path.required_sigs);
-
-                if refund_valid {
+            // Need signatures for refund path
+            if let Some(sigs) = &signatures {
+                if valid_signatures(msg, &refund_path.pubkeys, sigs)? >= refund_path.required_sigs {
                     return Ok(());
                 }
             }
@@ -233,15 +222,27 @@ impl Proof {
 }
 
 /// Returns count of valid signatures (each public key is only counted once)
-/// Returns error if the same pubkey has multiple valid signatures
+///
+/// A key listed more than once, e.g. in the secret data and in the `pubkeys` tag, is checked
+/// once. Returns error if the same pubkey has multiple valid signatures or a signature is
+/// repeated.
 pub fn valid_signatures(
     msg: &[u8],
     pubkeys: &[PublicKey],
     signatures: &[Signature],
 ) -> Result<u64, Error> {
+    let unique_signatures: HashSet<&Signature> = signatures.iter().collect();
+    if unique_signatures.len() != signatures.len() {
+        return Err(Error::DuplicateSignature);
+    }
+
+    let mut checked_keys = HashSet::new();
     let mut verified_pubkeys = HashSet::new();
 
-    for pubkey in pubkeys {
+    for pubkey in pubkeys
+        .iter()
+        .filter(|pubkey| checked_keys.insert(pubkey.x_only_public_key()))
+    {
         for signature in signatures {
             if pubkey.verify(msg, signature).is_ok() {
                 // If the pubkey is already verified, return a duplicate signature error
@@ -255,6 +256,83 @@ pub fn valid_signatures(
     Ok(verified_pubkeys.len() as u64)
 }
 
+/// Keys that can sign for the spending conditions of `secret`, refund keys included
+fn condition_keys(secret: &Secret) -> Result<Vec<PublicKey>, Error> {
+    let conditions = SpendingConditions::try_from(secret)?;
+    let mut keys = conditions.pubkeys().unwrap_or_default();
+    keys.extend(conditions.refund_keys().unwrap_or_default());
+    Ok(keys)
+}
+
+/// Whether `witness` holds a signature of `msg` by `pubkey`
+fn signed_by(witness: Option<&Witness>, msg: &[u8], pubkey: &PublicKey) -> bool {
+    witness
+        .and_then(Witness::signatures)
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|sig| Signature::from_str(sig).ok())
+        .any(|sig| pubkey.verify(msg, &sig).is_ok())
+}
+
+/// Sign `msg` into `witness`, unless the key already signed it
+fn add_signature(
+    witness: &mut Option<Witness>,
+    msg: &[u8],
+    secret_key: &SecretKey,
+) -> Result<(), Error> {
+    if signed_by(witness.as_ref(), msg, &secret_key.public_key()) {
+        return Ok(());
+    }
+
+    let signatures = vec![secret_key.sign(msg)?.to_string()];
+
+    match witness.as_mut() {
+        Some(witness) => {
+            witness.add_signatures(signatures);
+        }
+        None => {
+            let mut p2pk_witness = Witness::P2PKWitness(P2PKWitness::default());
+            p2pk_witness.add_signatures(signatures);
+            *witness = Some(p2pk_witness);
+        }
+    };
+
+    Ok(())
+}
+
+/// Add the signatures of `msg` in `other` to `witness`, one per signer
+///
+/// Every signature must be from one of `signers`; signers that already signed are skipped.
+fn combine_signatures(
+    witness: &mut Option<Witness>,
+    other: Option<&Witness>,
+    msg: &[u8],
+    signers: &[PublicKey],
+) -> Result<(), Error> {
+    for sig in other.and_then(Witness::signatures).unwrap_or_default() {
+        let signature = Signature::from_str(&sig).map_err(|_| Error::InvalidSignature)?;
+        let signer = signers
+            .iter()
+            .find(|signer| signer.verify(msg, &signature).is_ok())
+            .ok_or(Error::UnknownSigner)?;
+
+        if signed_by(witness.as_ref(), msg, signer) {
+            continue;
+        }
+
+        match witness.as_mut() {
+            Some(witness) => witness.add_signatures(vec![sig]),
+            None => {
+                *witness = Some(Witness::P2PKWitness(P2PKWitness {
+                    signatures: vec![sig],
+                }))
+            }
+        }
+    }
+
+    Ok(())
+}
+
 impl BlindedMessage {
     /// Sign [BlindedMessage]
     pub fn sign_p2pk(&mut self, secret_key: SecretKey) -> Result<(), Error> {
@@ -915,10 +993,11 @@ impl From<Tag> for Vec<String> {
 
 impl SwapRequest {
     /// Sign swap request with SIG_ALL
+    ///
+    /// Signing again with a key that already signed the request does nothing.
     pub fn sign_sig_all(&mut self, secret_key: SecretKey) -> Result<(), Error> {
         // Get message to sign
         let msg = self.sig_all_msg_to_sign();
-        let signature = secret_key.sign(msg.as_bytes())?;
 
         // Add signature to first input witness
         let first_input = self
@@ -926,18 +1005,30 @@ impl SwapRequest {
             .first_mut()
             .ok_or(Error::IncorrectSecretKind)?;
 
-        match first_input.witness.as_mut() {
-            Some(witness) => {
-                witness.add_signatures(vec![signature.to_string()]);
-            }
-            None => {
-                let mut p2pk_witness = Witness::P2PKWitness(P2PKWitness::default());
-                p2pk_witness.add_signatures(vec![signature.to_string()]);
-                first_input.witness = Some(p2pk_witness);
-            }
-        };
+        add_signature(&mut first_input.witness, msg.as_bytes(), &secret_key)
+    }
 
-        Ok(())
+    /// Add the SIG_ALL signatures of `other`, a copy of this request signed by co-signers
+    pub fn combine_sig_all_signatures(&mut self, other: &SwapRequest) -> Result<(), Error> {
+        let msg = self.sig_all_msg_to_sign();
+        ensure_cdk!(
+            msg == other.sig_all_msg_to_sign(),
+            Error::SpendConditionsNotMet
+        );
+
+        let first_input = self
+            .inputs_mut()
+            .first_mut()
+            .ok_or(Error::IncorrectSecretKind)?;
+        let signers = condition_keys(&first_input.secret)?;
+        let other_witness = other.inputs().first().and_then(|p| p.witness.as_ref());
+
+        combine_signatures(
+            &mut first_input.witness,
+            other_witness,
+            msg.as_bytes(),
+            &signers,
+        )
     }
 }
 
@@ -946,10 +1037,11 @@ where
     Q: std::fmt::Display + Serialize + DeserializeOwned,
 {
     /// Sign melt request with SIG_ALL
+    ///
+    /// Signing again with a key that already signed the request does nothing.
     pub fn sign_sig_all(&mut self, secret_key: SecretKey) -> Result<(), Error> {
         // Get message to sign
         let msg = self.sig_all_msg_to_sign();
-        let signature = secret_key.sign(msg.as_bytes())?;
 
         // Add signature to first input witness
         let first_input = self
@@ -957,18 +1049,7 @@ where
             .first_mut()
             .ok_or(Error::SpendConditionsNotMet)?;
 
-        match first_input.witness.as_mut() {
-            Some(witness) => {
-                witness.add_signatures(vec![signature.to_string()]);
-            }
-            None => {
-                let mut p2pk_witness = Witness::P2PKWitness(P2PKWitness::default());
-                p2pk_witness.add_signatures(vec![signature.to_string()]);
-                first_input.witness = Some(p2pk_witness);
-            }
-        };
-
-        Ok(())
+        add_signature(&mut first_input.witness, msg.as_bytes(), &secret_key)
     }
 }
 
@@ -2328,4 +2409,224 @@ mod tests {
             "Both signatures should verify"
         );
     }
+
+    /// Keys of the multisig test vectors: the data key and two more `pubkeys`
+    fn multisig_keys() -> [SecretKey; 3] {
+        [
+            "99590802251e78ee1051648439eedb003dc539093a48a44e7b8f2642c909ea37",
+            "0000000000000000000000000000000000000000000000000000000000000001",
+            "7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f7f",
+        ]
+        .map(|key| SecretKey::from_str(key).unwrap())
+    }
+
+    /// 2-of-3 multisig proof locked to the data key and the two other keys
+    fn two_of_three_proof(keys: &[SecretKey; 3], sig_flag: SigFlag) -> Proof {
+        let conditions = Conditions {
+            num_sigs: Some(2),
+            sig_flag,
+            pubkeys: Some(vec![keys[1].public_key(), keys[2].public_key()]),
+            ..Default::default()
+        };
+        let secret = create_test_secret(keys[0].public_key(), conditions);
+        create_test_proof(secret, keys[0].public_key(), "009a1f293253e41e")
+    }
+
+    #[test]
+    fn test_multisig_two_of_three() {
+        let keys = multisig_keys();
+
+        for (first, second) in [(0, 1), (0, 2), (1, 2)] {
+            let mut proof = two_of_three_proof(&keys, SigFlag::SigInputs);
+            proof.sign_p2pk(keys[first].clone()).unwrap();
+            assert!(matches!(
+                proof.verify_p2pk(),
+                Err(Error::SpendConditionsNotMet)
+            ));
+
+            proof.sign_p2pk(keys[second].clone()).unwrap();
+            assert!(proof.verify_p2pk().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_multisig_wrong_key() {
+        let keys = multisig_keys();
+        let outsider = SecretKey::generate();
+
+        let mut proof = two_of_three_proof(&keys, SigFlag::SigInputs);
+        proof.sign_p2pk(keys[0].clone()).unwrap();
+        proof.sign_p2pk(outsider).unwrap();
+
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+    }
+
+    #[test]
+    fn test_multisig_duplicate_signatures() {
+        let keys = multisig_keys();
+        let mut proof = two_of_three_proof(&keys, SigFlag::SigInputs);
+        let msg = proof.secret.to_bytes();
+
+        // Signing twice with the same key adds a single signature
+        proof.sign_p2pk(keys[0].clone()).unwrap();
+        proof.sign_p2pk(keys[0].clone()).unwrap();
+        assert_eq!(
+            proof.witness.as_ref().unwrap().signatures().unwrap().len(),
+            1
+        );
+
+        // Two distinct signatures by one key do not count twice and are rejected
+        let first = keys[0].sign(&msg).unwrap().to_string();
+        let second = keys[0].sign(&msg).unwrap().to_string();
+        assert_ne!(first, second);
+        proof.witness = Some(Witness::P2PKWitness(P2PKWitness {
+            signatures: vec![first.clone(), second],
+        }));
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::DuplicateSignature)
+        ));
+
+        // So is a repeated signature
+        proof.witness = Some(Witness::P2PKWitness(P2PKWitness {
+            signatures: vec![first.clone(), first],
+        }));
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::DuplicateSignature)
+        ));
+    }
+
+    #[test]
+    fn test_multisig_data_key_listed_in_pubkeys() {
+        let keys = multisig_keys();
+        let conditions = Conditions {
+            num_sigs: Some(2),
+            pubkeys: Some(vec![keys[0].public_key(), keys[1].public_key()]),
+            ..Default::default()
+        };
+        let secret = create_test_secret(keys[0].public_key(), conditions);
+        let mut proof = create_test_proof(secret, keys[0].public_key(), "009a1f293253e41e");
+
+        // The data key is counted once even though it is listed twice
+        proof.sign_p2pk(keys[0].clone()).unwrap();
+        assert!(matches!(
+            proof.verify_p2pk(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+
+        proof.sign_p2pk(keys[1].clone()).unwrap();
+        assert!(proof.verify_p2pk().is_ok());
+    }
+
+    #[test]
+    fn test_multisig_signature_reuse_across_proofs() {
+        let keys = multisig_keys();
+        let mut signed = two_of_three_proof(&keys, SigFlag::SigInputs);
+        signed.sign_p2pk(keys[0].clone()).unwrap();
+        signed.sign_p2pk(keys[1].clone()).unwrap();
+        assert!(signed.verify_p2pk().is_ok());
+
+        // Same conditions, different nonce: the signatures do not carry over
+        let mut other = two_of_three_proof(&keys, SigFlag::SigInputs);
+        other.witness = signed.witness.clone();
+        assert!(matches!(
+            other.verify_p2pk(),
+            Err(Error::SpendConditionsNotMet)
+        ));
+    }
+
+    #[test]
+    fn test_multisig_refund_keys_after_locktime() {
+        let keys = multisig_keys();
+        let refund = SecretKey::generate();
+
+        for (locktime, refund_valid) in [(unix_time() + 3600, false), (21, true)] {
+            let conditions = Conditions {
+                locktime: Some(locktime),
+                num_sigs: Some(2),
+                pubkeys: Some(vec![keys[1].public_key(), keys[2].public_key()]),
+                refund_keys: Some(vec![refund.public_key()]),
+                ..Default::default()
+            };
+            let secret = create_test_secret(keys[0].public_key(), conditions);
+            let mut proof = create_test_proof(secret, keys[0].public_key(), "009a1f293253e41e");
+
+            proof.sign_p2pk(refund.clone()).unwrap();
+            assert_eq!(proof.verify_p2pk().is_ok(), refund_valid);
+
+            // The multisig path stays available after the locktime
+            proof.witness = None;
+            proof.sign_p2pk(keys[1].clone()).unwrap();
+            proof.sign_p2pk(keys[2].clone()).unwrap();
+            assert!(proof.verify_p2pk().is_ok());
+        }
+    }
+
+    #[test]
+    fn test_combine_p2pk_signatures() {
+        let keys = multisig_keys();
+        let proof = two_of_three_proof(&keys, SigFlag::SigInputs);
+
+        // Each co-signer signs their own copy
+        let mut alice = proof.clone();
+        alice.sign_p2pk(keys[0].clone()).unwrap();
+        let mut carol = proof.clone();
+        carol.sign_p2pk(keys[2].clone()).unwrap();
+
+        alice.combine_p2pk_signatures(&carol).unwrap();
+        // Combining again, or with a copy by a key that already signed, adds nothing
+        alice.combine_p2pk_signatures(&carol).unwrap();
+        let mut alice_again = proof.clone();
+        alice_again.sign_p2pk(keys[0].clone()).unwrap();
+        alice.combine_p2pk_signatures(&alice_again).unwrap();
+
+        assert_eq!(
+            alice.witness.as_ref().unwrap().signatures().unwrap().len(),
+            2
+        );
+        assert!(alice.verify_p2pk().is_ok());
+
+        let mut outsider = proof.clone();
+        outsider.sign_p2pk(SecretKey::generate()).unwrap();
+        assert!(matches!(
+            alice.combine_p2pk_signatures(&outsider),
+            Err(Error::UnknownSigner)
+        ));
+
+        let other_proof = two_of_three_proof(&keys, SigFlag::SigInputs);
+        assert!(alice.combine_p2pk_signatures(&other_proof).is_err());
+    }
+
+    #[test]
+    fn test_sig_all_swap_combine_signatures() {
+        let keys = multisig_keys();
+        let proof = two_of_three_proof(&keys, SigFlag::SigAll);
+        let mut second_input = proof.clone();
+        second_input.amount = Amount::from(2);
+        let swap = SwapRequest::new(
+            vec![proof, second_input],
+            vec![create_test_blinded_msg(keys[0].public_key())],
+        );
+
+        let mut bob = swap.clone();
+        bob.sign_sig_all(keys[1].clone()).unwrap();
+        let mut carol = swap.clone();
+        carol.sign_sig_all(keys[2].clone()).unwrap();
+        assert!(bob.verify_spending_conditions().is_err());
+
+        bob.combine_sig_all_signatures(&carol).unwrap();
+        assert!(bob.verify_spending_conditions().is_ok());
+
+        // Signatures of a request with other outputs cannot be combined
+        let mut other = SwapRequest::new(
+            swap.inputs().clone(),
+            vec![create_test_blinded_msg(keys[1].public_key())],
+        );
+        other.sign_sig_all(keys[0].clone()).unwrap();
+        assert!(bob.combine_sig_all_signatures(&other).is_err());
+    }
 }
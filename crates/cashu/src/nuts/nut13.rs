@@ -161,11 +161,23 @@ impl PreMintSecrets {
         counter: u32,
         seed: &[u8; 64],
         amount: Amount,
+    ) -> Result<Self, Error> {
+        Self::from_seed_blank_with_max_amount(keyset_id, counter, seed, amount, None)
+    }
+
+    /// New [`PreMintSecrets`] from seed with a zero amount used for change from a mint that
+    /// signs no amount above `max_amount`
+    pub fn from_seed_blank_with_max_amount(
+        keyset_id: Id,
+        counter: u32,
+        seed: &[u8; 64],
+        amount: Amount,
+        max_amount: Option<Amount>,
     ) -> Result<Self, Error> {
         if amount <= Amount::ZERO {
             return Ok(PreMintSecrets::new(keyset_id));
         }
-        let count = ((u64::from(amount) as f64).log2().ceil() as u64).max(1);
+        let count = Self::blank_count_with_max_amount(amount, max_amount);
         let mut pre_mint_secrets = PreMintSecrets::new(keyset_id);
 
         let mut counter = counter;
@@ -24,6 +24,30 @@ pub struct MppMethodSettings {
     pub method: PaymentMethod,
     /// Currency Unit e.g. sat
     pub unit: CurrencyUnit,
+    /// Min amount of a single part
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_amount: Option<Amount>,
+    /// Max amount of a single part
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_amount: Option<Amount>,
+}
+
+impl MppMethodSettings {
+    /// Create new [`MppMethodSettings`] without part limits
+    pub fn new(method: PaymentMethod, unit: CurrencyUnit) -> Self {
+        Self {
+            method,
+            unit,
+            min_amount: None,
+            max_amount: None,
+        }
+    }
+
+    /// Check if a part of `amount` is within the min and max amount
+    pub fn is_within_limits(&self, amount: Amount) -> bool {
+        self.min_amount.is_none_or(|min| amount >= min)
+            && self.max_amount.is_none_or(|max| amount <= max)
+    }
 }
 
 /// Mpp Settings
@@ -32,6 +56,9 @@ pub struct MppMethodSettings {
 pub struct Settings {
     /// Method settings
     pub methods: Vec<MppMethodSettings>,
+    /// Multi-part payments disabled
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
 }
 
 impl Settings {
@@ -39,6 +66,35 @@ impl Settings {
     pub fn is_empty(&self) -> bool {
         self.methods.is_empty()
     }
+
+    /// Get [`MppMethodSettings`] for unit method pair
+    ///
+    /// Returns `None` when multi-part payments are disabled.
+    pub fn get_settings(
+        &self,
+        unit: &CurrencyUnit,
+        method: &PaymentMethod,
+    ) -> Option<&MppMethodSettings> {
+        if self.disabled {
+            return None;
+        }
+
+        self.methods
+            .iter()
+            .find(|settings| settings.method.eq(method) && settings.unit.eq(unit))
+    }
+
+    /// Remove [`MppMethodSettings`] for unit method pair
+    pub fn remove_settings(
+        &mut self,
+        unit: &CurrencyUnit,
+        method: &PaymentMethod,
+    ) -> Option<MppMethodSettings> {
+        self.methods
+            .iter()
+            .position(|settings| settings.method.eq(method) && settings.unit.eq(unit))
+            .map(|index| self.methods.remove(index))
+    }
 }
 
 // Custom deserialization to handle both array and object formats
@@ -51,13 +107,20 @@ impl<'de> Deserialize<'de> for Settings {
         #[serde(untagged)]
         enum SettingsFormat {
             Array(Vec<MppMethodSettings>),
-            Object { methods: Vec<MppMethodSettings> },
+            Object {
+                methods: Vec<MppMethodSettings>,
+                #[serde(default)]
+                disabled: bool,
+            },
         }
 
         let format = SettingsFormat::deserialize(deserializer)?;
         match format {
-            SettingsFormat::Array(methods) => Ok(Settings { methods }),
-            SettingsFormat::Object { methods } => Ok(Settings { methods }),
+            SettingsFormat::Array(methods) => Ok(Settings {
+                methods,
+                disabled: false,
+            }),
+            SettingsFormat::Object { methods, disabled } => Ok(Settings { methods, disabled }),
         }
     }
 }
@@ -94,10 +157,11 @@ mod tests {
     #[test]
     fn test_nut15_settings_serialization() {
         let settings = Settings {
-            methods: vec![MppMethodSettings {
-                method: PaymentMethod::Known(KnownMethod::Bolt11),
-                unit: CurrencyUnit::Sat,
-            }],
+            methods: vec![MppMethodSettings::new(
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                CurrencyUnit::Sat,
+            )],
+            disabled: false,
         };
 
         let json = serde_json::to_string(&settings).unwrap();
@@ -106,15 +170,34 @@ mod tests {
 
     #[test]
     fn test_nut15_settings_empty() {
-        let settings = Settings { methods: vec![] };
+        let settings = Settings::default();
         assert!(settings.is_empty());
 
         let settings_with_data = Settings {
-            methods: vec![MppMethodSettings {
-                method: PaymentMethod::Known(KnownMethod::Bolt11),
-                unit: CurrencyUnit::Sat,
-            }],
+            methods: vec![MppMethodSettings::new(
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                CurrencyUnit::Sat,
+            )],
+            disabled: false,
         };
         assert!(!settings_with_data.is_empty());
     }
+
+    #[test]
+    fn test_nut15_part_limits() {
+        let json = r#"{"methods":[{"method":"bolt11","unit":"sat","min_amount":100,"max_amount":10000}],"disabled":true}"#;
+        let settings: Settings = serde_json::from_str(json).unwrap();
+        assert!(settings.disabled);
+        assert_eq!(serde_json::to_string(&settings).unwrap(), json);
+
+        // Disabled settings advertise no unit method pair
+        let bolt11 = PaymentMethod::Known(KnownMethod::Bolt11);
+        assert!(settings.get_settings(&CurrencyUnit::Sat, &bolt11).is_none());
+
+        let method_settings = &settings.methods[0];
+        assert!(!method_settings.is_within_limits(Amount::from(99)));
+        assert!(method_settings.is_within_limits(Amount::from(100)));
+        assert!(method_settings.is_within_limits(Amount::from(10_000)));
+        assert!(!method_settings.is_within_limits(Amount::from(10_001)));
+    }
 }
@@ -2,12 +2,16 @@
 use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
 
+use std::collections::BTreeMap;
+
 use super::PublicKey;
 use crate::nut00::KnownMethod;
 use crate::nuts::{
-    CurrencyUnit, MeltQuoteBolt11Response, MintQuoteBolt11Response, PaymentMethod, ProofState,
+    CurrencyUnit, Id, MeltQuoteBolt11Response, MintInfo, MintQuoteBolt11Response, PaymentMethod,
+    ProofState,
 };
 use crate::quote_id::QuoteIdError;
+use crate::Amount;
 use crate::MintQuoteBolt12Response;
 
 pub mod ws;
@@ -25,12 +29,21 @@ pub struct Params<I> {
     pub id: I,
 }
 
+/// Default maximum number of filters accepted in a single subscription
+pub const DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION: usize = 1000;
+
 /// Check state Settings
 #[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
 pub struct SupportedSettings {
     /// Supported methods
     pub supported: Vec<SupportedMethods>,
+    /// Maximum number of filters accepted in a single subscription
+    ///
+    /// Filters beyond the limit are listed as rejected in the subscription response, wallets
+    /// should subscribe to them with further subscriptions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_filters: Option<usize>,
 }
 
 /// Supported WS Methods
@@ -60,7 +73,9 @@ impl SupportedMethods {
         let commands = vec![
             WsCommand::Bolt11MintQuote,
             WsCommand::Bolt11MeltQuote,
+            WsCommand::Bolt11MeltProgress,
             WsCommand::ProofState,
+            WsCommand::MintInfo,
         ];
 
         Self {
@@ -76,6 +91,7 @@ impl SupportedMethods {
             WsCommand::Bolt12MintQuote,
             WsCommand::Bolt12MeltQuote,
             WsCommand::ProofState,
+            WsCommand::MintInfo,
         ];
 
         Self {
@@ -92,6 +108,7 @@ impl SupportedMethods {
             WsCommand::Custom(format!("{}_mint_quote", method_name)),
             WsCommand::Custom(format!("{}_melt_quote", method_name)),
             WsCommand::ProofState,
+            WsCommand::MintInfo,
         ];
 
         Self {
@@ -122,12 +139,16 @@ pub enum WsCommand {
     Bolt11MintQuote,
     /// Command to request a Lightning payment for melting tokens
     Bolt11MeltQuote,
+    /// Progress of Bolt11 melts while their payment is made
+    Bolt11MeltProgress,
     /// Websocket support for Bolt12 Mint Quote
     Bolt12MintQuote,
     /// Websocket support for Bolt12 Melt Quote
     Bolt12MeltQuote,
     /// Command to check the state of a proof
     ProofState,
+    /// Changes of the mint info and the active keysets
+    MintInfo,
     /// Custom payment method command
     Custom(String),
 }
@@ -140,9 +161,11 @@ impl Serialize for WsCommand {
         let s = match self {
             WsCommand::Bolt11MintQuote => "bolt11_mint_quote",
             WsCommand::Bolt11MeltQuote => "bolt11_melt_quote",
+            WsCommand::Bolt11MeltProgress => "bolt11_melt_progress",
             WsCommand::Bolt12MintQuote => "bolt12_mint_quote",
             WsCommand::Bolt12MeltQuote => "bolt12_melt_quote",
             WsCommand::ProofState => "proof_state",
+            WsCommand::MintInfo => "mint_info",
             WsCommand::Custom(custom) => custom.as_str(),
         };
         serializer.serialize_str(s)
@@ -158,9 +181,11 @@ impl<'de> Deserialize<'de> for WsCommand {
         Ok(match s.as_str() {
             "bolt11_mint_quote" => WsCommand::Bolt11MintQuote,
             "bolt11_melt_quote" => WsCommand::Bolt11MeltQuote,
+            "bolt11_melt_progress" => WsCommand::Bolt11MeltProgress,
             "bolt12_mint_quote" => WsCommand::Bolt12MintQuote,
             "bolt12_melt_quote" => WsCommand::Bolt12MeltQuote,
             "proof_state" => WsCommand::ProofState,
+            "mint_info" => WsCommand::MintInfo,
             custom => WsCommand::Custom(custom.to_string()),
         })
     }
@@ -191,6 +216,162 @@ where
     MintQuoteBolt11Response(MintQuoteBolt11Response<T>),
     /// Mint Quote Bolt12 Response
     MintQuoteBolt12Response(MintQuoteBolt12Response<T>),
+    /// Melt settled internally against a mint quote of the same mint
+    InternalSettlement(InternalSettlementNotification<T>),
+    /// Progress of a melt while its payment is made
+    MeltProgress(MeltProgressNotification<T>),
+    /// Mint info or active keysets changed
+    MintInfo(MintInfoNotification),
+}
+
+/// Notification that a melt quote was settled against a mint quote of the
+/// same mint, without a lightning payment
+///
+/// The mint quote is not included: anyone knowing the melt quote id can
+/// subscribe, and the id of a mint quote not locked to a pubkey is enough to
+/// mint its ecash.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound = "T: Serialize + DeserializeOwned")]
+pub struct InternalSettlementNotification<T> {
+    /// Melt quote that was settled
+    pub melt_quote: T,
+    /// Amount settled
+    pub amount: Amount,
+    /// Unit of the amount
+    pub unit: CurrencyUnit,
+    /// Unix timestamp of the settlement
+    pub time: u64,
+}
+
+impl<T> InternalSettlementNotification<T>
+where
+    T: ToString,
+{
+    /// Convert the quote ids to `String`
+    pub fn to_string_id(&self) -> InternalSettlementNotification<String> {
+        InternalSettlementNotification {
+            melt_quote: self.melt_quote.to_string(),
+            amount: self.amount,
+            unit: self.unit.clone(),
+            time: self.time,
+        }
+    }
+}
+
+impl<T> From<InternalSettlementNotification<T>> for NotificationPayload<T>
+where
+    T: Clone,
+{
+    fn from(settlement: InternalSettlementNotification<T>) -> NotificationPayload<T> {
+        NotificationPayload::InternalSettlement(settlement)
+    }
+}
+
+/// Progress of a melt before it reaches its final state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MeltProgress {
+    /// The inputs were accepted and the payment started
+    Pending,
+    /// The payment is still in flight, sent periodically until it settles
+    Attempting,
+}
+
+/// Notification of the progress of a melt quote while its payment is made
+///
+/// Published to the opt-in `bolt11_melt_progress` subscriptions of the melt quote, between
+/// the `PENDING` and the final state of the quote. Subscriptions to the quote itself only
+/// receive the quote.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(bound = "T: Serialize + DeserializeOwned")]
+pub struct MeltProgressNotification<T> {
+    /// Melt quote being paid
+    pub quote: T,
+    /// Progress of the payment
+    pub progress: MeltProgress,
+    /// Milliseconds since the payment started
+    pub elapsed_ms: u64,
+    /// Unix timestamp of the event
+    pub time: u64,
+}
+
+impl<T> MeltProgressNotification<T>
+where
+    T: ToString,
+{
+    /// Convert the quote id to `String`
+    pub fn to_string_id(&self) -> MeltProgressNotification<String> {
+        MeltProgressNotification {
+            quote: self.quote.to_string(),
+            progress: self.progress,
+            elapsed_ms: self.elapsed_ms,
+            time: self.time,
+        }
+    }
+}
+
+impl<T> From<MeltProgressNotification<T>> for NotificationPayload<T>
+where
+    T: Clone,
+{
+    fn from(progress: MeltProgressNotification<T>) -> NotificationPayload<T> {
+        NotificationPayload::MeltProgress(progress)
+    }
+}
+
+/// Notification that the mint info or the active keysets of the mint changed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MintInfoNotification {
+    /// New value of each top level field of the mint info that changed, `null` if removed.
+    /// Empty if only the keysets changed
+    pub changed: BTreeMap<String, serde_json::Value>,
+    /// Active keysets after the change
+    pub active_keysets: Vec<Id>,
+    /// Unix timestamp of the change
+    pub time: u64,
+}
+
+impl MintInfoNotification {
+    /// Notification of the fields that differ between `old` and `new`
+    pub fn new(
+        old: &MintInfo,
+        new: &MintInfo,
+        active_keysets: Vec<Id>,
+        time: u64,
+    ) -> Result<Self, serde_json::Error> {
+        let old = match serde_json::to_value(old)? {
+            serde_json::Value::Object(fields) => fields,
+            _ => serde_json::Map::new(),
+        };
+        let new = match serde_json::to_value(new)? {
+            serde_json::Value::Object(fields) => fields,
+            _ => serde_json::Map::new(),
+        };
+
+        let mut changed: BTreeMap<String, serde_json::Value> = new
+            .iter()
+            .filter(|(field, value)| old.get(*field) != Some(*value))
+            .map(|(field, value)| (field.clone(), value.clone()))
+            .collect();
+        for field in old.keys().filter(|field| !new.contains_key(*field)) {
+            changed.insert(field.clone(), serde_json::Value::Null);
+        }
+
+        Ok(Self {
+            changed,
+            active_keysets,
+            time,
+        })
+    }
+}
+
+impl<T> From<MintInfoNotification> for NotificationPayload<T>
+where
+    T: Clone,
+{
+    fn from(notification: MintInfoNotification) -> NotificationPayload<T> {
+        NotificationPayload::MintInfo(notification)
+    }
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Deserialize, Hash, Serialize)]
@@ -210,6 +391,12 @@ where
     MintQuoteBolt12(T),
     /// MintQuote id is an QuoteId
     MeltQuoteBolt12(T),
+    /// Internal settlement id is the melt QuoteId
+    InternalSettlement(T),
+    /// Melt progress id is the melt QuoteId
+    MeltProgress(T),
+    /// Mint info changes have no id, there is a single topic
+    MintInfo,
 }
 
 /// Kind
@@ -224,6 +411,12 @@ pub enum Kind {
     ProofState,
     /// Bolt 12 Mint Quote
     Bolt12MintQuote,
+    /// Melt settled internally, filtered by melt quote id
+    InternalSettlement,
+    /// Progress of Bolt 11 melts while they are paid, filtered by melt quote id
+    Bolt11MeltProgress,
+    /// Mint info and active keyset changes, filters are ignored
+    MintInfo,
 }
 
 impl<I> AsRef<I> for Params<I> {
@@ -243,3 +436,97 @@ pub enum Error {
     /// PublicKey Error
     PublicKey(#[from] crate::nuts::nut01::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    #[test]
+    fn mint_info_notification_lists_changed_fields() {
+        let old = MintInfo::new().name("mint").motd("hello");
+        let new = MintInfo::new().name("mint").description("a mint");
+        let keyset = Id::from_str("009a1f293253e41e").unwrap();
+
+        let notification = MintInfoNotification::new(&old, &new, vec![keyset], 1_000).unwrap();
+
+        assert_eq!(
+            notification.changed.into_iter().collect::<Vec<_>>(),
+            vec![
+                ("description".to_string(), "a mint".into()),
+                ("motd".to_string(), serde_json::Value::Null),
+            ]
+        );
+        assert_eq!(notification.active_keysets, vec![keyset]);
+    }
+
+    #[test]
+    fn mint_info_payload_round_trip() {
+        let notification = MintInfoNotification::new(
+            &MintInfo::new(),
+            &MintInfo::new().motd("hello"),
+            vec![Id::from_str("009a1f293253e41e").unwrap()],
+            1_000,
+        )
+        .unwrap();
+        let payload: NotificationPayload<String> = notification.into();
+
+        let json = serde_json::to_string(&payload).unwrap();
+        let parsed: NotificationPayload<String> = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(parsed, payload);
+        assert_eq!(
+            serde_json::from_str::<Kind>("\"mint_info\"").unwrap(),
+            Kind::MintInfo
+        );
+    }
+
+    #[test]
+    fn melt_progress_payload_round_trip() {
+        let payload: NotificationPayload<String> = MeltProgressNotification {
+            quote: "quote".to_string(),
+            progress: MeltProgress::Attempting,
+            elapsed_ms: 5_000,
+            time: 1_000,
+        }
+        .into();
+
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({
+                "quote": "quote",
+                "progress": "attempting",
+                "elapsed_ms": 5_000,
+                "time": 1_000
+            })
+        );
+
+        let parsed: NotificationPayload<String> = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, payload);
+        assert_eq!(
+            serde_json::from_str::<Kind>("\"bolt11_melt_progress\"").unwrap(),
+            Kind::Bolt11MeltProgress
+        );
+    }
+
+    #[test]
+    fn subscribe_response_lists_rejected_filters() {
+        let accepted = ws::WsSubscribeResponse {
+            status: "OK".to_string(),
+            sub_id: "sub".to_string(),
+            rejected: vec![],
+        };
+        assert_eq!(
+            serde_json::to_value(&accepted).unwrap(),
+            serde_json::json!({"status": "OK", "subId": "sub"})
+        );
+
+        let partial: ws::WsSubscribeResponse<String> = serde_json::from_value(
+            serde_json::json!({"status": "OK", "subId": "sub", "rejected": ["invalid"]}),
+        )
+        .unwrap();
+        assert_eq!(partial.rejected, vec!["invalid".to_string()]);
+    }
+}
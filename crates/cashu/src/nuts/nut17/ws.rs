@@ -17,6 +17,12 @@ pub struct WsSubscribeResponse<I> {
     /// Subscription ID
     #[serde(rename = "subId")]
     pub sub_id: I,
+    /// Filters of the request the subscription does not include
+    ///
+    /// The subscription is partially accepted when a filter is invalid or the request has more
+    /// filters than the mint accepts in a single subscription.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub rejected: Vec<String>,
 }
 
 /// The response to an unsubscription request
@@ -3,9 +3,22 @@
 use std::str::FromStr;
 
 use bitcoin::secp256k1::schnorr::Signature;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use super::{MintRequest, PublicKey, SecretKey};
+use super::nut23::QuoteState;
+use super::{CurrencyUnit, MintRequest, PaymentMethod, PublicKey, SecretKey};
+use crate::mint_url::MintUrl;
+#[cfg(feature = "mint")]
+use crate::quote_id::QuoteId;
+use crate::Amount;
+
+/// Domain separator of the message signed to list the quotes of a pubkey
+const MINT_QUOTES_DOMAIN: &[u8] = b"cashu_mint_quotes";
+
+/// Maximum number of pubkeys a mint lists the quotes of in one [`MintQuotesRequest`]
+pub const MAX_MINT_QUOTES_PUBKEYS: usize = 100;
 
 /// Nut19 Error
 #[derive(Debug, Error)]
@@ -16,6 +29,15 @@ pub enum Error {
     /// Quote signature invalid signature
     #[error("Quote signature invalid signature")]
     InvalidSignature,
+    /// Request timestamp too far from the time of the mint
+    #[error("Request timestamp outside of the accepted window")]
+    StaleTimestamp,
+    /// Request signed for another mint
+    #[error("Request is for another mint")]
+    MintUrlMismatch,
+    /// Request lists more pubkeys than the mint accepts
+    #[error("Request has more than `{0}` pubkeys")]
+    TooManyPubkeys(usize),
     /// Nut01 error
     #[error(transparent)]
     NUT01(#[from] crate::nuts::nut01::Error),
@@ -71,6 +93,152 @@ where
     }
 }
 
+/// Request for the mint quotes locked to some pubkeys
+///
+/// Each pubkey signs the request, so only the owner of the quotes can list them. The signed
+/// message binds the mint url and the timestamp, so a request can't be replayed to another mint
+/// or for long.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct MintQuotesRequest {
+    /// Url of the mint the request is for
+    #[cfg_attr(feature = "swagger", schema(value_type = String))]
+    pub mint_url: MintUrl,
+    /// Unix time of the request, bounds how long a signature can be replayed
+    pub timestamp: u64,
+    /// Pubkeys the quotes are locked to, with their signatures on the request
+    pub pubkeys: Vec<MintQuotesPubkey>,
+}
+
+/// Pubkey of a [`MintQuotesRequest`] with its signature on the request
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct MintQuotesPubkey {
+    /// Pubkey the quotes are locked to
+    pub pubkey: PublicKey,
+    /// Signature by the pubkey on the request
+    #[cfg_attr(feature = "swagger", schema(max_length = 1_000))]
+    pub signature: String,
+}
+
+impl MintQuotesRequest {
+    /// Create a [`MintQuotesRequest`] to `mint_url` signed by each of `secret_keys`
+    pub fn new(
+        secret_keys: &[SecretKey],
+        mint_url: MintUrl,
+        timestamp: u64,
+    ) -> Result<Self, Error> {
+        let pubkeys = secret_keys
+            .iter()
+            .map(|secret_key| {
+                let pubkey = secret_key.public_key();
+                let signature = secret_key.sign(&Self::msg(&mint_url, &pubkey, timestamp))?;
+
+                Ok(MintQuotesPubkey {
+                    pubkey,
+                    signature: signature.to_string(),
+                })
+            })
+            .collect::<Result<_, Error>>()?;
+
+        Ok(Self {
+            mint_url,
+            timestamp,
+            pubkeys,
+        })
+    }
+
+    fn msg(mint_url: &MintUrl, pubkey: &PublicKey, timestamp: u64) -> Vec<u8> {
+        let mut msg = MINT_QUOTES_DOMAIN.to_vec();
+        msg.extend_from_slice(mint_url.to_string().as_bytes());
+        msg.extend_from_slice(pubkey.to_hex().as_bytes());
+        msg.extend_from_slice(timestamp.to_string().as_bytes());
+        msg
+    }
+
+    /// Constructs the message to be signed by `pubkey`
+    ///
+    /// Format: `"cashu_mint_quotes" || mint_url || pubkey || timestamp`, with the pubkey as hex
+    /// and the timestamp as decimal, encoded as UTF-8 bytes
+    pub fn msg_to_sign(&self, pubkey: &PublicKey) -> Vec<u8> {
+        Self::msg(&self.mint_url, pubkey, self.timestamp)
+    }
+
+    /// Verify the signature of every pubkey on [`MintQuotesRequest`]
+    pub fn verify_signatures(&self) -> Result<(), Error> {
+        for MintQuotesPubkey { pubkey, signature } in &self.pubkeys {
+            let signature = Signature::from_str(signature).map_err(|_| Error::InvalidSignature)?;
+
+            pubkey.verify(&self.msg_to_sign(pubkey), &signature)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// State of a mint quote locked to a pubkey
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuoteStatus<Q> {
+    /// Quote id
+    pub quote: Q,
+    /// Pubkey the quote is locked to
+    pub pubkey: PublicKey,
+    /// Payment method of the quote
+    pub method: PaymentMethod,
+    /// Payment request to fulfil
+    pub request: String,
+    /// Unit of the quote
+    pub unit: CurrencyUnit,
+    /// Amount of the quote, if fixed
+    pub amount: Option<Amount>,
+    /// Quote state
+    pub state: QuoteState,
+    /// Amount paid to the quote
+    pub amount_paid: Amount,
+    /// Amount issued for the quote
+    pub amount_issued: Amount,
+    /// Unix timestamp until the quote is valid
+    pub expiry: u64,
+}
+
+/// Mint quotes locked to the pubkeys of a [`MintQuotesRequest`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+#[serde(bound = "Q: Serialize + DeserializeOwned")]
+pub struct MintQuotesResponse<Q> {
+    /// Quotes of the pubkeys
+    pub quotes: Vec<MintQuoteStatus<Q>>,
+}
+
+#[cfg(feature = "mint")]
+impl From<MintQuoteStatus<QuoteId>> for MintQuoteStatus<String> {
+    fn from(value: MintQuoteStatus<QuoteId>) -> Self {
+        Self {
+            quote: value.quote.to_string(),
+            pubkey: value.pubkey,
+            method: value.method,
+            request: value.request,
+            unit: value.unit,
+            amount: value.amount,
+            state: value.state,
+            amount_paid: value.amount_paid,
+            amount_issued: value.amount_issued,
+            expiry: value.expiry,
+        }
+    }
+}
+
+#[cfg(feature = "mint")]
+impl From<MintQuotesResponse<QuoteId>> for MintQuotesResponse<String> {
+    fn from(value: MintQuotesResponse<QuoteId>) -> Self {
+        Self {
+            quotes: value.quotes.into_iter().map(Into::into).collect(),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
 
@@ -149,4 +317,44 @@ mod tests {
         // Signature is on a different quote id verification should fail
         assert!(request.verify_signature(pubkey).is_err());
     }
+
+    #[test]
+    fn test_mint_quotes_request_signature() {
+        let secret =
+            SecretKey::from_hex("50d7fd7aa2b2fe4607f41f4ce6f8794fc184dd47b8cdfbe4b3d1249aa02d35aa")
+                .unwrap();
+        let other = SecretKey::generate();
+        let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+
+        let request = MintQuotesRequest::new(
+            &[secret.clone(), other.clone()],
+            mint_url.clone(),
+            1_700_000_000,
+        )
+        .unwrap();
+        assert_eq!(request.pubkeys.len(), 2);
+        assert_eq!(request.pubkeys[0].pubkey, secret.public_key());
+        assert!(request.verify_signatures().is_ok());
+
+        let mut msg = b"cashu_mint_quotes".to_vec();
+        msg.extend_from_slice(b"https://mint.example.com");
+        msg.extend_from_slice(secret.public_key().to_hex().as_bytes());
+        msg.extend_from_slice(b"1700000000");
+        assert_eq!(request.msg_to_sign(&secret.public_key()), msg);
+
+        // The signatures cover the timestamp
+        let mut replayed = request.clone();
+        replayed.timestamp += 1;
+        assert!(replayed.verify_signatures().is_err());
+
+        // and the mint url
+        let mut replayed = request.clone();
+        replayed.mint_url = MintUrl::from_str("https://other.example.com").unwrap();
+        assert!(replayed.verify_signatures().is_err());
+
+        // and the pubkey
+        let mut forged = request.clone();
+        forged.pubkeys[1].pubkey = SecretKey::generate().public_key();
+        assert!(forged.verify_signatures().is_err());
+    }
 }
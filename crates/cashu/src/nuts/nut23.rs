@@ -102,6 +102,12 @@ pub struct MintQuoteBolt11Response<Q> {
     /// NUT-19 Pubkey
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pubkey: Option<PublicKey>,
+    /// Amount received for the request so far, which may differ from the quoted amount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_paid: Option<Amount>,
+    /// The request was only partially paid, the quote stays unpaid until the rest is received
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub partial: bool,
 }
 impl<Q: ToString> MintQuoteBolt11Response<Q> {
     /// Convert the MintQuote with a quote type Q to a String
@@ -114,6 +120,8 @@ impl<Q: ToString> MintQuoteBolt11Response<Q> {
             pubkey: self.pubkey,
             amount: self.amount,
             unit: self.unit.clone(),
+            amount_paid: self.amount_paid,
+            partial: self.partial,
         }
     }
 }
@@ -129,6 +137,8 @@ impl From<MintQuoteBolt11Response<QuoteId>> for MintQuoteBolt11Response<String>
             pubkey: value.pubkey,
             amount: value.amount,
             unit: value.unit.clone(),
+            amount_paid: value.amount_paid,
+            partial: value.partial,
         }
     }
 }
@@ -234,6 +244,23 @@ impl MeltQuoteBolt11Request {
     }
 }
 
+/// Fee breakdown of a melt quote
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq, Serialize, Deserialize)]
+#[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
+pub struct MeltQuoteFees {
+    /// Fee reserved for the lightning payment
+    pub ln_fee_reserve: Amount,
+    /// Input fee in parts per thousand charged per proof of the active keyset
+    pub input_fee_ppk: u64,
+    /// Lightning fee actually paid, only set once the melt has completed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ln_fee_paid: Option<Amount>,
+    /// Melt was settled against a mint quote of the same mint, so no
+    /// lightning payment was made and no lightning fee was charged
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub internal: bool,
+}
+
 /// Melt quote response [NUT-05]
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
@@ -263,6 +290,14 @@ pub struct MeltQuoteBolt11Response<Q> {
     // REVIEW: This is now required in the spec, we should remove the option once all mints update
     #[serde(skip_serializing_if = "Option::is_none")]
     pub unit: Option<CurrencyUnit>,
+    /// Fee breakdown
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fees: Option<MeltQuoteFees>,
+    /// Amount spent by the melt, the quote amount and the lightning fee paid
+    ///
+    /// Only set once the quote is paid.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub amount_spent: Option<Amount>,
 }
 
 impl<Q: ToString> MeltQuoteBolt11Response<Q> {
@@ -279,6 +314,8 @@ impl<Q: ToString> MeltQuoteBolt11Response<Q> {
             change: self.change,
             request: self.request,
             unit: self.unit,
+            fees: self.fees,
+            amount_spent: self.amount_spent,
         }
     }
 }
@@ -296,6 +333,8 @@ impl From<MeltQuoteBolt11Response<QuoteId>> for MeltQuoteBolt11Response<String>
             change: value.change,
             request: value.request,
             unit: value.unit,
+            fees: value.fees,
+            amount_spent: value.amount_spent,
         }
     }
 }
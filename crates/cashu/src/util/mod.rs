@@ -22,6 +22,14 @@ pub fn unix_time() -> u64 {
         .as_secs()
 }
 
+/// Check that `url` is an absolute http or https URL
+pub fn validate_http_url(url: &str) -> Result<(), String> {
+    match url::Url::parse(url) {
+        Ok(parsed) if matches!(parsed.scheme(), "http" | "https") && parsed.has_host() => Ok(()),
+        _ => Err(format!("Invalid mint info URL, expected http(s): {url}")),
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 /// Error type for serialization
 pub enum CborError {
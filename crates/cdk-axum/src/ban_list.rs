@@ -0,0 +1,160 @@
+//! Rejection of clients banned for replaying spent proofs
+//!
+//! Mint, melt and swap requests of a client banned by the mint are answered with
+//! `429 Too Many Requests` before they reach the handler, so their proofs and signatures are not
+//! verified. The double spends of the other requests are reported to the mint, which decides when
+//! to ban a client (see [`cdk::mint::BanPolicy`]).
+//!
+//! Clients are identified by their [`ClientIp`]; requests without one, and all requests while the
+//! mint has no ban policy, are passed on unchanged.
+
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::extract::{Request, State};
+use axum::http::{header, Method, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use cdk::error::ErrorCode;
+use cdk::mint::Mint;
+use cdk::nuts::{BlindedMessage, Proof, PublicKey};
+use serde::Deserialize;
+
+use crate::client_ip::ClientIp;
+
+/// State of [`ban_list_middleware`]
+#[derive(Clone)]
+pub(crate) struct BanListState {
+    pub(crate) mint: Arc<Mint>,
+    /// Maximum request body size in bytes
+    pub(crate) max_body_size: usize,
+}
+
+/// Inputs and outputs of a mint, melt or swap request
+#[derive(Deserialize)]
+struct SpendingRequest {
+    #[serde(default)]
+    inputs: Vec<Proof>,
+    #[serde(default)]
+    outputs: Vec<BlindedMessage>,
+}
+
+impl SpendingRequest {
+    /// Ys of the inputs, or the blinded secrets of the outputs of a request without inputs
+    fn keys(&self) -> Vec<PublicKey> {
+        if self.inputs.is_empty() {
+            self.outputs
+                .iter()
+                .map(|output| output.blinded_secret)
+                .collect()
+        } else {
+            self.inputs
+                .iter()
+                .filter_map(|proof| proof.y().ok())
+                .collect()
+        }
+    }
+}
+
+/// Whether `path` is a mint, melt or swap request
+fn is_spending_request(path: &str) -> bool {
+    let path = path.strip_prefix("/v1").unwrap_or(path);
+
+    match path.split('/').collect::<Vec<_>>().as_slice() {
+        ["", "swap"] => true,
+        ["", "mint", "quotes"] => false,
+        ["", "mint" | "melt", method] => *method != "quote",
+        _ => false,
+    }
+}
+
+/// Reject requests of banned clients and report double spends to the mint
+pub(crate) async fn ban_list_middleware(
+    State(state): State<BanListState>,
+    req: Request,
+    next: Next,
+) -> Response {
+    if req.method() != Method::POST
+        || !is_spending_request(req.uri().path())
+        || !state.mint.ban_list_enabled()
+    {
+        return next.run(req).await;
+    }
+
+    let Some(ClientIp(client)) = req.extensions().get::<ClientIp>().copied() else {
+        return next.run(req).await;
+    };
+
+    if let Some(expires_in) = state.mint.client_ban(&client) {
+        tracing::debug!("Rejecting request of banned client {}", client);
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, expires_in.as_secs().max(1).to_string())],
+            "Too many double spends",
+        )
+            .into_response();
+    }
+
+    // The body is kept to tell retries of the same request apart from new double spends by the
+    // proofs and outputs it lists
+    let (parts, body) = req.into_parts();
+    let Ok(body) = to_bytes(body, state.max_body_size).await else {
+        return StatusCode::PAYLOAD_TOO_LARGE.into_response();
+    };
+
+    let response = next
+        .run(Request::from_parts(parts, Body::from(body.clone())))
+        .await;
+
+    if let Some(code) = response.extensions().get::<ErrorCode>() {
+        if let Ok(request) = serde_json::from_slice::<SpendingRequest>(&body) {
+            state
+                .mint
+                .record_client_rejection(client, *code, &request.keys());
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_keys() {
+        let output = r#"{"amount":1,"id":"009a1f293253e41e","B_":"02c020067db727d586bc3183aecf97fcb800c3f4cc4759f69c626c9db5d8f5b5d4"}"#;
+        let input = r#"{"amount":1,"id":"009a1f293253e41e","secret":"407915bc212be61a77e3e6d2aeb4c727980bda51cd06a6afc29e2861768a7837","C":"02bc9097997d81afb2cc7346b5e4345a9346bd2a506eb7958598a72f0cf85163ea"}"#;
+
+        // A mint request is identified by its outputs
+        let request: SpendingRequest =
+            serde_json::from_str(&format!(r#"{{"quote":"q","outputs":[{output}]}}"#)).unwrap();
+        assert_eq!(
+            request.keys(),
+            vec![PublicKey::from_hex(
+                "02c020067db727d586bc3183aecf97fcb800c3f4cc4759f69c626c9db5d8f5b5d4"
+            )
+            .unwrap()]
+        );
+
+        // A swap or melt request by its inputs, whatever outputs it asks for
+        let request: SpendingRequest =
+            serde_json::from_str(&format!(r#"{{"inputs":[{input}],"outputs":[{output}]}}"#))
+                .unwrap();
+        let proof: Proof = serde_json::from_str(input).unwrap();
+        assert_eq!(request.keys(), vec![proof.y().unwrap()]);
+    }
+
+    #[test]
+    fn spending_requests() {
+        assert!(is_spending_request("/v1/swap"));
+        assert!(is_spending_request("/v1/mint/bolt11"));
+        assert!(is_spending_request("/v1/melt/bolt12"));
+
+        assert!(!is_spending_request("/v1/mint/quote/bolt11"));
+        assert!(!is_spending_request("/v1/mint/quotes"));
+        assert!(!is_spending_request("/v1/melt/quote/bolt11/1234"));
+        assert!(!is_spending_request("/v1/checkstate"));
+        assert!(!is_spending_request("/v1/info"));
+    }
+}
@@ -1,5 +1,6 @@
 use std::time::Duration;
 
+use cdk::mint::{ResponseCacheScope, ResponseCacheStats};
 use moka::future::Cache;
 
 use crate::cache::{HttpCacheKey, HttpCacheStorage, DEFAULT_TTI_SECS, DEFAULT_TTL_SECS};
@@ -21,6 +22,7 @@ impl Default for InMemoryHttpCache {
                 .max_capacity(10_000)
                 .time_to_live(Duration::from_secs(DEFAULT_TTL_SECS))
                 .time_to_idle(Duration::from_secs(DEFAULT_TTI_SECS))
+                .support_invalidation_closures()
                 .build(),
         )
     }
@@ -33,6 +35,7 @@ impl HttpCacheStorage for InMemoryHttpCache {
             .max_capacity(10_000)
             .time_to_live(cache_ttl)
             .time_to_idle(cache_tti)
+            .support_invalidation_closures()
             .build();
     }
 
@@ -43,4 +46,43 @@ impl HttpCacheStorage for InMemoryHttpCache {
     async fn set(&self, key: HttpCacheKey, value: Vec<u8>) {
         self.0.insert(key, value).await;
     }
+
+    async fn set_if_absent(&self, key: HttpCacheKey, value: Vec<u8>) -> Option<bool> {
+        Some(self.0.entry(key).or_insert(value).await.is_fresh())
+    }
+
+    async fn remove(&self, key: &HttpCacheKey) {
+        self.0.invalidate(key).await;
+    }
+
+    async fn clear(&self, scope: &ResponseCacheScope) {
+        if *scope == ResponseCacheScope::All {
+            self.0.invalidate_all();
+            return;
+        }
+
+        let scope = scope.clone();
+        if let Err(err) = self
+            .0
+            .invalidate_entries_if(move |key, _| key.is_in_scope(&scope))
+        {
+            tracing::error!("Failed to clear cache: {:?}", err);
+        }
+    }
+
+    async fn stats(&self) -> ResponseCacheStats {
+        self.0.run_pending_tasks().await;
+
+        let size_bytes = self
+            .0
+            .iter()
+            .map(|(key, value)| (key.endpoint().len() + key.len() + value.len()) as u64)
+            .sum();
+
+        ResponseCacheStats {
+            entries: Some(self.0.entry_count()),
+            size_bytes: Some(size_bytes),
+            ..Default::default()
+        }
+    }
 }
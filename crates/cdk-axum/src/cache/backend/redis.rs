@@ -1,16 +1,22 @@
 use std::time::Duration;
 
+use cdk::mint::{ResponseCacheScope, ResponseCacheStats};
 use redis::AsyncCommands;
 use serde::{Deserialize, Serialize};
 
 use crate::cache::{HttpCacheKey, HttpCacheStorage};
 
+/// Prefix of the cache keys unless another one is set
+pub const DEFAULT_KEY_PREFIX: &str = "cdk-mint:";
+
 /// Redis cache storage for the HTTP cache.
 ///
-/// This cache storage backend uses Redis to store the cache.
+/// This cache storage backend uses Redis to store the cache. The keys are namespaced under a
+/// prefix, [`DEFAULT_KEY_PREFIX`] by default, so clearing and counting the cache leaves the other
+/// keys of a shared Redis alone.
 pub struct HttpCacheRedis {
     cache_ttl: Duration,
-    prefix: Option<Vec<u8>>,
+    prefix: Vec<u8>,
     client: redis::Client,
 }
 
@@ -26,7 +32,7 @@ impl std::fmt::Debug for HttpCacheRedis {
 /// Configuration for the Redis cache storage.
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Config {
-    /// Commong key prefix
+    /// Common key prefix, [`DEFAULT_KEY_PREFIX`] if unset
     pub key_prefix: Option<String>,
 
     /// Connection string to the Redis server.
@@ -38,7 +44,7 @@ impl HttpCacheRedis {
     pub fn new(client: redis::Client) -> Self {
         Self {
             client,
-            prefix: None,
+            prefix: DEFAULT_KEY_PREFIX.as_bytes().to_vec(),
             cache_ttl: Duration::from_secs(60),
         }
     }
@@ -47,10 +53,67 @@ impl HttpCacheRedis {
     ///
     /// This is useful to have all the HTTP cache keys under a common prefix,
     /// some sort of namespace, to make management of the database easier.
+    /// Without a prefix the cache can only be cleared by endpoint, as clearing
+    /// it all or by request would match keys of other applications.
     pub fn set_prefix(mut self, prefix: Vec<u8>) -> Self {
-        self.prefix = Some(prefix);
+        self.prefix = prefix;
         self
     }
+
+    /// Database key of a cache key, `<prefix><endpoint>:<hash>`
+    fn db_key(&self, key: &HttpCacheKey) -> Vec<u8> {
+        let mut db_key = self.prefix.clone();
+        db_key.extend(format!("{}:{}", key.endpoint(), key).as_bytes());
+        db_key
+    }
+
+    /// Pattern matching the database keys in `scope`
+    ///
+    /// `None` if the pattern would start with a wildcard, matching keys outside of the cache.
+    fn scope_pattern(&self, scope: &ResponseCacheScope) -> Option<Vec<u8>> {
+        let suffix = match scope {
+            ResponseCacheScope::All => "*".to_owned(),
+            ResponseCacheScope::Endpoint(endpoint) => format!("{endpoint}:*"),
+            ResponseCacheScope::Request(hash) => format!("*:{}", hash.to_lowercase()),
+        };
+        if self.prefix.is_empty() && suffix.starts_with('*') {
+            return None;
+        }
+
+        let mut pattern = self.prefix.clone();
+        pattern.extend(suffix.as_bytes());
+        Some(pattern)
+    }
+
+    /// Database keys in `scope`
+    async fn scan(
+        &self,
+        conn: &mut redis::aio::MultiplexedConnection,
+        scope: &ResponseCacheScope,
+    ) -> Option<Vec<Vec<u8>>> {
+        let Some(pattern) = self.scope_pattern(scope) else {
+            tracing::warn!(
+                "Refusing to scan redis for {:?} without a key prefix",
+                scope
+            );
+            return None;
+        };
+
+        let mut iter = conn
+            .scan_match::<_, Vec<u8>>(pattern)
+            .await
+            .map_err(|err| {
+                tracing::error!("Failed to scan redis keys: {:?}", err);
+                err
+            })
+            .ok()?;
+
+        let mut keys = Vec::new();
+        while let Some(key) = iter.next_item().await {
+            keys.push(key);
+        }
+        Some(keys)
+    }
 }
 
 #[async_trait::async_trait]
@@ -70,10 +133,7 @@ impl HttpCacheStorage for HttpCacheRedis {
             })
             .ok()?;
 
-        let mut db_key = self.prefix.clone().unwrap_or_default();
-        db_key.extend(&**key);
-
-        conn.get(db_key)
+        conn.get(self.db_key(key))
             .await
             .map_err(|err| {
                 tracing::error!("Failed to get value from redis: {:?}", err);
@@ -83,8 +143,7 @@ impl HttpCacheStorage for HttpCacheRedis {
     }
 
     async fn set(&self, key: HttpCacheKey, value: Vec<u8>) {
-        let mut db_key = self.prefix.clone().unwrap_or_default();
-        db_key.extend(&*key);
+        let db_key = self.db_key(&key);
 
         let mut conn = match self.client.get_multiplexed_tokio_connection().await {
             Ok(conn) => conn,
@@ -102,4 +161,130 @@ impl HttpCacheStorage for HttpCacheRedis {
                 err
             });
     }
+
+    async fn set_if_absent(&self, key: HttpCacheKey, value: Vec<u8>) -> Option<bool> {
+        let db_key = self.db_key(&key);
+
+        let mut conn = match self.client.get_multiplexed_tokio_connection().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                tracing::error!("Failed to get redis connection: {:?}", err);
+                return None;
+            }
+        };
+
+        // `SET NX` replies nil when the key is already set
+        redis::cmd("SET")
+            .arg(db_key)
+            .arg(value)
+            .arg("NX")
+            .arg("EX")
+            .arg(self.cache_ttl.as_secs())
+            .query_async::<Option<String>>(&mut conn)
+            .await
+            .map_err(|err| {
+                tracing::error!("Failed to set value in redis: {:?}", err);
+                err
+            })
+            .ok()
+            .map(|reply| reply.is_some())
+    }
+
+    async fn remove(&self, key: &HttpCacheKey) {
+        let mut conn = match self.client.get_multiplexed_tokio_connection().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                tracing::error!("Failed to get redis connection: {:?}", err);
+                return;
+            }
+        };
+
+        let _: Result<(), _> = conn.del(self.db_key(key)).await.map_err(|err| {
+            tracing::error!("Failed to remove value from redis: {:?}", err);
+            err
+        });
+    }
+
+    async fn clear(&self, scope: &ResponseCacheScope) {
+        let mut conn = match self.client.get_multiplexed_tokio_connection().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                tracing::error!("Failed to get redis connection: {:?}", err);
+                return;
+            }
+        };
+
+        let Some(keys) = self.scan(&mut conn, scope).await else {
+            return;
+        };
+
+        if keys.is_empty() {
+            return;
+        }
+
+        let _: Result<(), _> = conn.del(keys).await.map_err(|err| {
+            tracing::error!("Failed to clear values in redis: {:?}", err);
+            err
+        });
+    }
+
+    async fn stats(&self) -> ResponseCacheStats {
+        let entries = match self.client.get_multiplexed_tokio_connection().await {
+            Ok(mut conn) => self
+                .scan(&mut conn, &ResponseCacheScope::All)
+                .await
+                .map(|keys| keys.len() as u64),
+            Err(err) => {
+                tracing::error!("Failed to get redis connection: {:?}", err);
+                None
+            }
+        };
+
+        ResponseCacheStats {
+            entries,
+            ..Default::default()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cache() -> HttpCacheRedis {
+        HttpCacheRedis::new(redis::Client::open("redis://localhost").unwrap())
+    }
+
+    #[test]
+    fn scopes_are_namespaced_by_default() {
+        let cache = cache();
+
+        assert_eq!(
+            cache.scope_pattern(&ResponseCacheScope::All),
+            Some(b"cdk-mint:*".to_vec())
+        );
+        assert_eq!(
+            cache.scope_pattern(&ResponseCacheScope::Endpoint("/v1/swap".to_owned())),
+            Some(b"cdk-mint:/v1/swap:*".to_vec())
+        );
+        assert_eq!(
+            cache.scope_pattern(&ResponseCacheScope::Request("AB".to_owned())),
+            Some(b"cdk-mint:*:ab".to_vec())
+        );
+    }
+
+    #[test]
+    fn unprefixed_cache_is_only_cleared_by_endpoint() {
+        let cache = cache().set_prefix(Vec::new());
+
+        assert_eq!(cache.scope_pattern(&ResponseCacheScope::All), None);
+        assert_eq!(
+            cache.scope_pattern(&ResponseCacheScope::Request("ab".to_owned())),
+            None
+        );
+        assert_eq!(
+            cache.scope_pattern(&ResponseCacheScope::Endpoint("/v1/swap".to_owned())),
+            Some(b"/v1/swap:*".to_vec())
+        );
+    }
 }
@@ -8,12 +8,17 @@
 //!
 //! This mod also provides common backend implementations as well, such as In
 //! Memory (default) and Redis.
+use std::fmt;
 use std::ops::Deref;
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::Arc;
 use std::time::Duration;
 
+use cdk::mint::{QuoteId, ResponseCache, ResponseCacheScope, ResponseCacheStats};
+use cdk::nuts::PublicKey;
+use cdk::util::hex;
 use serde::de::DeserializeOwned;
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 
 mod backend;
@@ -33,9 +38,39 @@ pub trait HttpCacheStorage {
 
     /// Set a value in the cache.
     async fn set(&self, key: HttpCacheKey, value: Vec<u8>);
+
+    /// Set a value in the cache unless the key is already set, returns whether it was set.
+    ///
+    /// `None` if the backend could not tell, e.g. because it is unreachable. The default
+    /// implementation is not atomic, backends shared between instances should override it.
+    async fn set_if_absent(&self, key: HttpCacheKey, value: Vec<u8>) -> Option<bool> {
+        if self.get(&key).await.is_some() {
+            return Some(false);
+        }
+        self.set(key, value).await;
+        Some(true)
+    }
+
+    /// Remove a value from the cache.
+    async fn remove(&self, key: &HttpCacheKey) {
+        self.clear(&ResponseCacheScope::Request(key.to_string()))
+            .await;
+    }
+
+    /// Remove the values in `scope` from the cache.
+    async fn clear(&self, scope: &ResponseCacheScope);
+
+    /// Number and size of the cached values, as far as the backend can tell.
+    ///
+    /// Hits and misses are counted by [`HttpCache`].
+    async fn stats(&self) -> ResponseCacheStats;
 }
 
 /// Http cache with a pluggable storage backend.
+///
+/// Clones share the storage and the statistics, so a clone can be handed to the mint to manage
+/// the cache used by the router.
+#[derive(Clone)]
 pub struct HttpCache {
     /// Time to live for the cache.
     pub ttl: Duration,
@@ -43,6 +78,10 @@ pub struct HttpCache {
     pub tti: Duration,
     /// Storage backend for the cache.
     storage: Arc<Box<dyn HttpCacheStorage + Send + Sync>>,
+    /// Lookups answered from the cache.
+    hits: Arc<AtomicU64>,
+    /// Lookups not found in the cache.
+    misses: Arc<AtomicU64>,
 }
 
 impl std::fmt::Debug for HttpCache {
@@ -71,22 +110,88 @@ impl Default for HttpCache {
 const MAX_PAYLOAD_SIZE: usize = 10 * 1024 * 1024;
 
 /// Default TTL for the cache.
-const DEFAULT_TTL_SECS: u64 = 60;
+pub const DEFAULT_TTL_SECS: u64 = 60;
 
 /// Default TTI for the cache.
-const DEFAULT_TTI_SECS: u64 = 60;
+pub const DEFAULT_TTI_SECS: u64 = 60;
 
 /// Http cache key.
 ///
-/// This type ensures no `Vec<u8>` is used as a key, which is error-prone.
+/// This type ensures no `Vec<u8>` is used as a key, which is error-prone. The key is the hash of
+/// the request, scoped to the endpoint it was sent to.
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
-pub struct HttpCacheKey([u8; 32]);
+pub struct HttpCacheKey {
+    endpoint: String,
+    hash: [u8; 32],
+}
+
+impl HttpCacheKey {
+    /// Endpoint the request was sent to.
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Whether the key is in `scope`.
+    pub fn is_in_scope(&self, scope: &ResponseCacheScope) -> bool {
+        match scope {
+            ResponseCacheScope::All => true,
+            ResponseCacheScope::Endpoint(endpoint) => &self.endpoint == endpoint,
+            ResponseCacheScope::Request(hash) => self.to_string().eq_ignore_ascii_case(hash),
+        }
+    }
+}
+
+/// Outcome of claiming an idempotency key with [`HttpCache::claim`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IdempotencyClaim<V> {
+    /// No request used the key, the caller executes the request
+    Claimed,
+    /// A request with the key completed with this response
+    Replay(V),
+    /// A request with the key is still executing
+    InFlight,
+    /// The cache backend failed, whether the key was used is unknown
+    Unavailable,
+}
+
+/// What an idempotency key is scoped to, besides the endpoint
+///
+/// The same key sent by two wallets, or by one wallet for two operations, must not replay the
+/// response of the other request.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum IdempotencyScope<'a> {
+    /// Requests on a quote, i.e. mint and melt
+    Quote(&'a QuoteId),
+    /// Requests spending proofs, by their sorted Ys, i.e. swap
+    Inputs(Vec<PublicKey>),
+}
+
+impl IdempotencyScope<'_> {
+    /// Scope of a request spending the proofs with `ys`
+    pub fn inputs(mut ys: Vec<PublicKey>) -> Self {
+        ys.sort();
+        Self::Inputs(ys)
+    }
+}
+
+/// Value stored under an idempotency key
+#[derive(Serialize, Deserialize)]
+enum IdempotentEntry<V> {
+    InFlight,
+    Done(V),
+}
 
 impl Deref for HttpCacheKey {
     type Target = [u8; 32];
 
     fn deref(&self) -> &Self::Target {
-        &self.0
+        &self.hash
+    }
+}
+
+impl fmt::Display for HttpCacheKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", hex::encode(self.hash))
     }
 }
 
@@ -102,13 +207,10 @@ impl From<config::Config> for HttpCache {
             config::Backend::Redis(redis_config) => {
                 let client = redis::Client::open(redis_config.connection_string)
                     .expect("Failed to create Redis client");
-                let storage = HttpCacheRedis::new(client).set_prefix(
-                    redis_config
-                        .key_prefix
-                        .unwrap_or_default()
-                        .as_bytes()
-                        .to_vec(),
-                );
+                let mut storage = HttpCacheRedis::new(client);
+                if let Some(key_prefix) = redis_config.key_prefix {
+                    storage = storage.set_prefix(key_prefix.into_bytes());
+                }
                 Self::new(
                     Duration::from_secs(config.ttl.unwrap_or(DEFAULT_TTL_SECS)),
                     Duration::from_secs(config.tti.unwrap_or(DEFAULT_TTI_SECS)),
@@ -133,10 +235,12 @@ impl HttpCache {
             ttl,
             tti,
             storage: Arc::new(storage),
+            hits: Arc::new(AtomicU64::new(0)),
+            misses: Arc::new(AtomicU64::new(0)),
         }
     }
 
-    /// Calculate a cache key from a serializable value.
+    /// Calculate a cache key for a request to `endpoint` from a serializable value.
     ///
     /// Usually the input is the request body or query parameters.
     ///
@@ -148,7 +252,7 @@ impl HttpCache {
     /// double hash to have a predictable key size, although it may open the
     /// window for CPU attacks with large payloads, but it is a trade-off.
     /// Perhaps upper layer have a protection against large payloads.
-    pub fn calculate_key<K>(&self, key: &K) -> Option<HttpCacheKey>
+    pub fn calculate_key<K>(&self, endpoint: &str, key: &K) -> Option<HttpCacheKey>
     where
         K: Serialize,
     {
@@ -167,7 +271,71 @@ impl HttpCache {
 
         let first_hash = Sha256::digest(json_value);
         let second_hash = Sha256::digest(first_hash);
-        Some(HttpCacheKey(second_hash.into()))
+        Some(HttpCacheKey {
+            endpoint: endpoint.to_owned(),
+            hash: second_hash.into(),
+        })
+    }
+
+    /// Calculate the cache key of the response to a request to `endpoint` with an idempotency
+    /// key, within `scope`.
+    ///
+    /// Unlike [`HttpCache::calculate_key`] the payload is not part of the key, so retries
+    /// with the same idempotency key share it whatever their outputs.
+    pub fn calculate_idempotency_key(
+        &self,
+        endpoint: &str,
+        idempotency_key: &str,
+        scope: &IdempotencyScope<'_>,
+    ) -> Option<HttpCacheKey> {
+        self.calculate_key(endpoint, &("idempotency-key", idempotency_key, scope))
+    }
+
+    /// Claim an idempotency key for a request.
+    ///
+    /// The key is marked in flight until the response is stored with [`HttpCache::complete`]
+    /// or the claim is given up with [`HttpCache::release`]. The mark expires with the cache
+    /// TTL, like the responses.
+    pub async fn claim<V>(self: &Arc<Self>, key: &HttpCacheKey) -> IdempotencyClaim<V>
+    where
+        V: DeserializeOwned,
+    {
+        let in_flight = serde_json::to_vec(&IdempotentEntry::<()>::InFlight).unwrap_or_default();
+        match self.storage.set_if_absent(key.clone(), in_flight).await {
+            Some(true) => {
+                self.misses.fetch_add(1, Ordering::Relaxed);
+                return IdempotencyClaim::Claimed;
+            }
+            Some(false) => {}
+            None => return IdempotencyClaim::Unavailable,
+        }
+
+        // The mark may also have expired since, the retry can be sent again
+        let entry = self
+            .storage
+            .get(key)
+            .await
+            .and_then(|value| serde_json::from_slice::<IdempotentEntry<V>>(&value).ok());
+        match entry {
+            Some(IdempotentEntry::Done(response)) => {
+                self.hits.fetch_add(1, Ordering::Relaxed);
+                IdempotencyClaim::Replay(response)
+            }
+            _ => IdempotencyClaim::InFlight,
+        }
+    }
+
+    /// Store the response to a request under the idempotency key it claimed.
+    pub async fn complete<V>(self: &Arc<Self>, key: HttpCacheKey, response: &V)
+    where
+        V: Serialize,
+    {
+        self.set(key, &IdempotentEntry::Done(response)).await;
+    }
+
+    /// Give up the claim of an idempotency key, e.g. because the request failed.
+    pub async fn release(self: &Arc<Self>, key: &HttpCacheKey) {
+        self.storage.remove(key).await;
     }
 
     /// Get a value from the cache.
@@ -175,7 +343,14 @@ impl HttpCache {
     where
         V: DeserializeOwned,
     {
-        self.storage.get(key).await.and_then(|value| {
+        let value = self.storage.get(key).await;
+        let counter = match value {
+            Some(_) => &self.hits,
+            None => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+
+        value.and_then(|value| {
             serde_json::from_slice(&value)
                 .map_err(|e| {
                     tracing::warn!("Failed to deserialize value: {:?}", e);
@@ -191,7 +366,112 @@ impl HttpCache {
             tracing::warn!("Failed to serialize value: {:?}", e);
             e
         }) {
+            tracing::debug!("Caching response for {} under {}", key.endpoint(), key);
             self.storage.set(key, bytes).await;
         }
     }
 }
+
+#[async_trait::async_trait]
+impl ResponseCache for HttpCache {
+    async fn stats(&self) -> ResponseCacheStats {
+        ResponseCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            ..self.storage.stats().await
+        }
+    }
+
+    async fn clear(&self, scope: &ResponseCacheScope) {
+        self.storage.clear(scope).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cdk::nuts::{SwapRequest, SwapResponse};
+
+    use super::*;
+
+    fn swap_request(n: u64) -> SwapRequest {
+        serde_json::from_value(serde_json::json!({
+            "inputs": [],
+            "outputs": [{
+                "amount": n,
+                "id": "009a1f293253e41e",
+                "B_": "02634a2c2b34bec9e8a4aba4361f6bf202d7fa2365379b0840afe249a7a9d71239"
+            }]
+        }))
+        .unwrap()
+    }
+
+    async fn cache_swap(cache: &Arc<HttpCache>, n: u64) -> HttpCacheKey {
+        let key = cache.calculate_key("/v1/swap", &swap_request(n)).unwrap();
+        cache
+            .set(key.clone(), &SwapResponse { signatures: vec![] })
+            .await;
+        key
+    }
+
+    #[tokio::test]
+    async fn targeted_clear_removes_cached_swap() {
+        let cache = Arc::new(HttpCache::default());
+        let cleared = cache_swap(&cache, 1).await;
+        let kept = cache_swap(&cache, 2).await;
+
+        cache
+            .clear(&ResponseCacheScope::Request(cleared.to_string()))
+            .await;
+
+        assert!(cache.get::<SwapResponse>(&cleared).await.is_none());
+        assert!(cache.get::<SwapResponse>(&kept).await.is_some());
+    }
+
+    #[tokio::test]
+    async fn endpoint_clear_keeps_other_endpoints() {
+        let cache = Arc::new(HttpCache::default());
+        let swap = cache_swap(&cache, 1).await;
+        let mint = cache.calculate_key("/v1/mint/bolt11", &1).unwrap();
+        cache
+            .set(mint.clone(), &SwapResponse { signatures: vec![] })
+            .await;
+
+        // Same request to another endpoint is cached separately
+        assert_ne!(
+            swap,
+            cache
+                .calculate_key("/v1/melt/bolt11", &swap_request(1))
+                .unwrap()
+        );
+
+        cache
+            .clear(&ResponseCacheScope::Endpoint("/v1/swap".to_string()))
+            .await;
+
+        assert!(cache.get::<SwapResponse>(&swap).await.is_none());
+        assert!(cache.get::<SwapResponse>(&mint).await.is_some());
+
+        cache.clear(&ResponseCacheScope::All).await;
+        assert!(cache.get::<SwapResponse>(&mint).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn stats_count_entries_and_hits() {
+        let cache = Arc::new(HttpCache::default());
+        let handle = HttpCache::clone(&cache);
+        let key = cache_swap(&cache, 1).await;
+
+        assert!(cache.get::<SwapResponse>(&key).await.is_some());
+        assert!(cache
+            .get::<SwapResponse>(&cache.calculate_key("/v1/swap", &swap_request(2)).unwrap())
+            .await
+            .is_none());
+
+        // Clones share storage and counters
+        let stats = ResponseCache::stats(&handle).await;
+        assert_eq!(stats.entries, Some(1));
+        assert!(stats.size_bytes.unwrap_or_default() > 0);
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+        assert_eq!(stats.hit_rate(), Some(0.5));
+    }
+}
@@ -0,0 +1,343 @@
+//! Client IP resolution behind trusted reverse proxies
+//!
+//! The address of the connection is only the client when the mint is reached directly. When the
+//! peer is one of the configured trusted proxies, the client is taken from the `Forwarded` or
+//! `X-Forwarded-For` header: hops are read from the right, skipping trusted proxies, and the first
+//! untrusted hop is the client. Everything left of it was written by the client and is ignored.
+//! Headers from untrusted peers are ignored entirely.
+//!
+//! The resolved address is stored as a [`ClientIp`] request extension, which is the key to use
+//! for rate limiting, and recorded on the tracing span of the request.
+
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::extract::{ConnectInfo, Request, State};
+use axum::http::HeaderMap;
+use axum::middleware::Next;
+use axum::response::Response;
+use ipnet::IpNet;
+use tracing::Instrument;
+
+const FORWARDED: &str = "forwarded";
+const X_FORWARDED_FOR: &str = "x-forwarded-for";
+
+/// IP address of the client that sent a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientIp(pub IpAddr);
+
+/// Reverse proxies whose forwarding headers are trusted
+#[derive(Debug, Clone, Default)]
+pub struct TrustedProxies {
+    networks: Arc<Vec<IpNet>>,
+}
+
+impl TrustedProxies {
+    /// Parse a list of addresses (`10.0.0.1`) or networks (`10.0.0.0/8`)
+    pub fn new<S>(proxies: &[S]) -> Result<Self, String>
+    where
+        S: AsRef<str>,
+    {
+        let networks = proxies
+            .iter()
+            .map(|proxy| {
+                let proxy = proxy.as_ref().trim();
+                IpNet::from_str(proxy)
+                    .or_else(|_| IpAddr::from_str(proxy).map(IpNet::from))
+                    .map_err(|_| format!("Invalid trusted proxy: {proxy}"))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            networks: Arc::new(networks),
+        })
+    }
+
+    /// Whether `ip` is a trusted proxy
+    pub fn is_trusted(&self, ip: &IpAddr) -> bool {
+        let ip = ip.to_canonical();
+        self.networks.iter().any(|network| network.contains(&ip))
+    }
+
+    /// Resolve the client address of a request received from `peer`
+    pub fn client_ip(&self, peer: IpAddr, headers: &HeaderMap) -> IpAddr {
+        if !self.is_trusted(&peer) {
+            return peer;
+        }
+
+        let Some(hops) = forwarded_hops(headers) else {
+            return peer;
+        };
+
+        let mut client = peer;
+        for hop in hops.iter().rev() {
+            // A hop that is not an address can't be attributed, keep the last known one
+            let Some(ip) = hop else {
+                return client;
+            };
+            client = *ip;
+            if !self.is_trusted(ip) {
+                break;
+            }
+        }
+
+        client
+    }
+}
+
+/// Hops listed by the `Forwarded` header, or else by `X-Forwarded-For`, from client to proxy
+///
+/// Hops that are not IP addresses (`unknown`, obfuscated identifiers) are `None`.
+fn forwarded_hops(headers: &HeaderMap) -> Option<Vec<Option<IpAddr>>> {
+    let forwarded = header_values(headers, FORWARDED);
+    if !forwarded.is_empty() {
+        return Some(
+            forwarded
+                .iter()
+                .flat_map(|value| value.split(','))
+                .map(|element| {
+                    element.split(';').find_map(|pair| {
+                        let (key, value) = pair.split_once('=')?;
+                        key.trim()
+                            .eq_ignore_ascii_case("for")
+                            .then(|| parse_node(value))
+                    })?
+                })
+                .collect(),
+        );
+    }
+
+    let forwarded_for = header_values(headers, X_FORWARDED_FOR);
+    if !forwarded_for.is_empty() {
+        return Some(
+            forwarded_for
+                .iter()
+                .flat_map(|value| value.split(','))
+                .map(parse_node)
+                .collect(),
+        );
+    }
+
+    None
+}
+
+fn header_values<'a>(headers: &'a HeaderMap, name: &str) -> Vec<&'a str> {
+    headers
+        .get_all(name)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .collect()
+}
+
+/// Parse a node as `1.2.3.4`, `1.2.3.4:80`, `2001:db8::1` or `"[2001:db8::1]:80"`
+fn parse_node(node: &str) -> Option<IpAddr> {
+    let node = node.trim().trim_matches('"');
+
+    if let Some(rest) = node.strip_prefix('[') {
+        let (ip, _) = rest.split_once(']')?;
+        return IpAddr::from_str(ip).ok().map(|ip| ip.to_canonical());
+    }
+
+    IpAddr::from_str(node)
+        .or_else(|_| SocketAddr::from_str(node).map(|addr| addr.ip()))
+        .ok()
+        .map(|ip| ip.to_canonical())
+}
+
+/// Attach the [`ClientIp`] to the request and record it on the request span
+///
+/// Requires the server to provide [`ConnectInfo<SocketAddr>`]; without it the request is passed
+/// on unchanged.
+pub async fn client_ip_middleware(
+    State(proxies): State<TrustedProxies>,
+    mut req: Request,
+    next: Next,
+) -> Response {
+    let Some(peer) = req
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip().to_canonical())
+    else {
+        return next.run(req).await;
+    };
+
+    let client_ip = proxies.client_ip(peer, req.headers());
+    req.extensions_mut().insert(ClientIp(client_ip));
+
+    let span = tracing::info_span!("request", client_ip = %client_ip);
+    next.run(req).instrument(span).await
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::http::HeaderValue;
+
+    use super::*;
+
+    fn ip(ip: &str) -> IpAddr {
+        IpAddr::from_str(ip).unwrap()
+    }
+
+    fn header_map(entries: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in entries {
+            headers.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    fn proxies() -> TrustedProxies {
+        TrustedProxies::new(&["127.0.0.1", "10.0.0.0/8", "fd00::/8"]).unwrap()
+    }
+
+    #[test]
+    fn parses_nodes() {
+        assert_eq!(parse_node(" 203.0.113.7 "), Some(ip("203.0.113.7")));
+        assert_eq!(parse_node("203.0.113.7:4711"), Some(ip("203.0.113.7")));
+        assert_eq!(parse_node("2001:db8::17"), Some(ip("2001:db8::17")));
+        assert_eq!(
+            parse_node("\"[2001:db8::17]:4711\""),
+            Some(ip("2001:db8::17"))
+        );
+        assert_eq!(parse_node("[2001:db8::17]"), Some(ip("2001:db8::17")));
+        assert_eq!(parse_node("::ffff:203.0.113.7"), Some(ip("203.0.113.7")));
+        assert_eq!(parse_node("unknown"), None);
+        assert_eq!(parse_node("_hidden"), None);
+    }
+
+    #[test]
+    fn rejects_invalid_proxy() {
+        assert!(TrustedProxies::new(&["10.0.0.0/33"]).is_err());
+        assert!(TrustedProxies::new(&["proxy.local"]).is_err());
+    }
+
+    #[test]
+    fn untrusted_peer_headers_are_ignored() {
+        let headers = header_map(&[
+            (X_FORWARDED_FOR, "198.51.100.1"),
+            (FORWARDED, "for=198.51.100.2"),
+        ]);
+        assert_eq!(
+            proxies().client_ip(ip("203.0.113.7"), &headers),
+            ip("203.0.113.7")
+        );
+        assert_eq!(
+            TrustedProxies::default().client_ip(ip("127.0.0.1"), &headers),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn trusted_peer_without_headers() {
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &HeaderMap::new()),
+            ip("127.0.0.1")
+        );
+    }
+
+    #[test]
+    fn x_forwarded_for_rightmost_untrusted_hop() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "203.0.113.7, 10.1.2.3")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+
+        // Split over several headers
+        let headers = header_map(&[
+            (X_FORWARDED_FOR, "198.51.100.9"),
+            (X_FORWARDED_FOR, "203.0.113.7, 10.1.2.3"),
+        ]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn spoofed_hops_are_ignored() {
+        // The client prepends addresses, the proxy appends the real one
+        let headers = header_map(&[(X_FORWARDED_FOR, "10.9.9.9, 198.51.100.1, 203.0.113.7")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+
+        // Claiming to be a trusted proxy does not hide the real address
+        let headers = header_map(&[(X_FORWARDED_FOR, "127.0.0.1, 203.0.113.7")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+
+        // Garbage left of the real address is never reached
+        let headers = header_map(&[(X_FORWARDED_FOR, "not-an-ip, 203.0.113.7")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn all_hops_trusted() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "10.0.0.2, 10.0.0.3")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn unknown_hop_keeps_last_known_address() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "unknown, 10.0.0.2")]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("10.0.0.2")
+        );
+    }
+
+    #[test]
+    fn forwarded_header() {
+        let headers = header_map(&[(
+            FORWARDED,
+            "for=198.51.100.1;proto=https, For=\"[2001:db8:cafe::17]:4711\";by=10.0.0.1, for=10.0.0.2",
+        )]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("2001:db8:cafe::17")
+        );
+
+        // Forwarded takes precedence over X-Forwarded-For
+        let headers = header_map(&[
+            (FORWARDED, "for=203.0.113.7"),
+            (X_FORWARDED_FOR, "198.51.100.1"),
+        ]);
+        assert_eq!(
+            proxies().client_ip(ip("127.0.0.1"), &headers),
+            ip("203.0.113.7")
+        );
+    }
+
+    #[test]
+    fn ipv6_proxies_and_peers() {
+        let headers = header_map(&[(X_FORWARDED_FOR, "2001:db8::1, fd00::2")]);
+        assert_eq!(
+            proxies().client_ip(ip("fd00::1"), &headers),
+            ip("2001:db8::1")
+        );
+
+        // An IPv4 proxy connecting over a dual stack socket is still trusted
+        let headers = header_map(&[(X_FORWARDED_FOR, "203.0.113.7")]);
+        assert_eq!(
+            proxies().client_ip(ip("::ffff:127.0.0.1").to_canonical(), &headers),
+            ip("203.0.113.7")
+        );
+
+        // Untrusted IPv6 peer
+        assert_eq!(
+            proxies().client_ip(ip("2001:db8::99"), &headers),
+            ip("2001:db8::99")
+        );
+    }
+}
@@ -11,6 +11,8 @@ use axum::extract::{FromRequestParts, Json, Path, State};
 use axum::http::request::Parts;
 use axum::http::StatusCode;
 use axum::response::{IntoResponse, Response};
+#[cfg(feature = "swagger")]
+use cdk::error::ErrorResponse;
 use cdk::mint::QuoteId;
 #[cfg(feature = "auth")]
 use cdk::nuts::nut21::{Method, ProtectedEndpoint, RoutePath};
@@ -25,6 +27,7 @@ use tracing::instrument;
 
 #[cfg(feature = "auth")]
 use crate::auth::AuthHeader;
+use crate::idempotency::{run_once, IdempotencyKey};
 use crate::router_handlers::into_response;
 use crate::MintState;
 
@@ -70,6 +73,26 @@ where
         })
     }
 }
+
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/mint/quote/{method}",
+    params(("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`")),
+    request_body(content = MintQuoteBolt11Request, description = "Quote request, shown for bolt11", content_type = "application/json", example = json!({"amount": 10, "unit": "sat"})),
+    responses(
+        (status = 200, description = "Successful response, shown for bolt11", body = MintQuoteBolt11Response<String>, content_type = "application/json", example = json!({
+            "quote": "9d745270-1405-46de-b5c5-e2762b4f5e00",
+            "request": "lnbc100n1pj4apw9pp5cfgcfy9k79u5ztvvp3yc2dwl9xrc0jz2jwmy7jsgk55ke7ps8yxqdqqcqzzsxqyz5vqsp5nt5txmqfxfs6ejmfhqe5nvqcgjg3tz4k8f5q5rrldjhyq3d3ltxq9qyyssqjg85cdvu8yanhujhx2ssvwsvrpyhk0z6yqq9wspf3z3ve6rk2qh0sycn7gplzpr3u3j3ahq0cnmc7wfqzqvmrjxmxzrq5g7q0fjzqpd8qhrl",
+            "amount": 10,
+            "unit": "sat",
+            "state": "UNPAID",
+            "expiry": 1701704757,
+            "pubkey": null
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Generic handler for custom payment method mint quotes
 ///
 /// This handler works for ANY custom payment method (e.g., paypal, venmo, cashapp, bolt11, bolt12).
@@ -157,6 +180,24 @@ pub async fn post_mint_custom_quote(
     }
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/mint/quote/{method}/{quote_id}",
+    params(("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`"), ("quote_id" = String, Path, description = "The quote ID")),
+    responses(
+        (status = 200, description = "Successful response, shown for bolt11", body = MintQuoteBolt11Response<String>, content_type = "application/json", example = json!({
+            "quote": "9d745270-1405-46de-b5c5-e2762b4f5e00",
+            "request": "lnbc100n1pj4apw9pp5cfgcfy9k79u5ztvvp3yc2dwl9xrc0jz2jwmy7jsgk55ke7ps8yxqdqqcqzzsxqyz5vqsp5nt5txmqfxfs6ejmfhqe5nvqcgjg3tz4k8f5q5rrldjhyq3d3ltxq9qyyssqjg85cdvu8yanhujhx2ssvwsvrpyhk0z6yqq9wspf3z3ve6rk2qh0sycn7gplzpr3u3j3ahq0cnmc7wfqzqvmrjxmxzrq5g7q0fjzqpd8qhrl",
+            "amount": 10,
+            "unit": "sat",
+            "state": "UNPAID",
+            "expiry": 1701704757,
+            "pubkey": null
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Get custom payment method mint quote status
 #[instrument(skip_all, fields(method = ?method, quote_id = ?quote_id))]
 pub async fn get_check_mint_custom_quote(
@@ -211,6 +252,21 @@ pub async fn get_check_mint_custom_quote(
     }
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/mint/{method}",
+    params(
+        ("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the response of the first request with this key, whatever the payload")
+    ),
+    request_body(content = MintRequest<String>, description = "Mint params", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successful response", body = MintResponse, content_type = "application/json"),
+        (status = 409, description = "A request with the same `Idempotency-Key` is in progress"),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Mint tokens with custom payment method
 #[instrument(skip_all, fields(method = ?method, quote_id = ?payload.quote))]
 pub async fn post_mint_custom(
@@ -242,6 +298,27 @@ pub async fn post_mint_custom(
     Ok(Json(res))
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/melt/quote/{method}",
+    params(("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`")),
+    request_body(content = MeltQuoteBolt11Request, description = "Quote request, shown for bolt11", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successful response", body = MeltQuoteBolt11Response<String>, content_type = "application/json", example = json!({
+            "quote": "4f3d9b9e-0c13-4b5e-9f0c-0a1c9d1f3b22",
+            "amount": 10,
+            "fee_reserve": 2,
+            "state": "UNPAID",
+            "expiry": 1701704757,
+            "payment_preimage": null,
+            "change": null,
+            "request": "lnbc100n1pj4apw9pp5cfgcfy9k79u5ztvvp3yc2dwl9xrc0jz2jwmy7jsgk55ke7ps8yxqdqqcqzzsxqyz5vqsp5nt5txmqfxfs6ejmfhqe5nvqcgjg3tz4k8f5q5rrldjhyq3d3ltxq9qyyssqjg85cdvu8yanhujhx2ssvwsvrpyhk0z6yqq9wspf3z3ve6rk2qh0sycn7gplzpr3u3j3ahq0cnmc7wfqzqvmrjxmxzrq5g7q0fjzqpd8qhrl",
+            "unit": "sat"
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Request a melt quote for custom payment method
 #[instrument(skip_all, fields(method = ?method))]
 pub async fn post_melt_custom_quote(
@@ -307,6 +384,26 @@ pub async fn post_melt_custom_quote(
     Ok(Json(response))
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/melt/quote/{method}/{quote_id}",
+    params(("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`"), ("quote_id" = String, Path, description = "The quote ID")),
+    responses(
+        (status = 200, description = "Successful response", body = MeltQuoteBolt11Response<String>, content_type = "application/json", example = json!({
+            "quote": "4f3d9b9e-0c13-4b5e-9f0c-0a1c9d1f3b22",
+            "amount": 10,
+            "fee_reserve": 2,
+            "state": "UNPAID",
+            "expiry": 1701704757,
+            "payment_preimage": null,
+            "change": null,
+            "request": "lnbc100n1pj4apw9pp5cfgcfy9k79u5ztvvp3yc2dwl9xrc0jz2jwmy7jsgk55ke7ps8yxqdqqcqzzsxqyz5vqsp5nt5txmqfxfs6ejmfhqe5nvqcgjg3tz4k8f5q5rrldjhyq3d3ltxq9qyyssqjg85cdvu8yanhujhx2ssvwsvrpyhk0z6yqq9wspf3z3ve6rk2qh0sycn7gplzpr3u3j3ahq0cnmc7wfqzqvmrjxmxzrq5g7q0fjzqpd8qhrl",
+            "unit": "sat"
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Get custom payment method melt quote status
 #[instrument(skip_all, fields(method = ?method, quote_id = ?quote_id))]
 pub async fn get_check_melt_custom_quote(
@@ -337,6 +434,22 @@ pub async fn get_check_melt_custom_quote(
     Ok(Json(quote))
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/melt/{method}",
+    params(
+        ("method" = String, Path, description = "Payment method, e.g. `bolt11` or `bolt12`"),
+        ("Prefer" = Option<String>, Header, description = "`respond-async` returns before the payment completes"),
+        ("Idempotency-Key" = Option<String>, Header, description = "Replays the response of the first request with this key, whatever the payload")
+    ),
+    request_body(content = cdk::nuts::MeltRequest<String>, description = "Melt params", content_type = "application/json"),
+    responses(
+        (status = 200, description = "Successful response", body = MeltQuoteBolt11Response<String>, content_type = "application/json"),
+        (status = 409, description = "A request with the same `Idempotency-Key` is in progress"),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20001, "detail": "Quote not paid"}))
+    )
+))]
 /// Melt tokens with custom payment method
 #[instrument(skip_all, fields(method = ?method))]
 pub async fn post_melt_custom(
@@ -377,10 +490,11 @@ pub async fn post_melt_custom(
 // CACHED HANDLERS FOR NUT-19 SUPPORT
 // ============================================================================
 
-/// Cached version of post_mint_custom for NUT-19 caching support
+/// Cached version of post_mint_custom for NUT-19 and idempotency key support
 #[instrument(skip_all, fields(method = ?method, quote_id = ?payload.quote))]
 pub async fn cache_post_mint_custom(
     #[cfg(feature = "auth")] auth: AuthHeader,
+    idempotency_key: IdempotencyKey,
     state: State<MintState>,
     method: Path<String>,
     payload: Json<MintRequest<QuoteId>>,
@@ -390,7 +504,30 @@ pub async fn cache_post_mint_custom(
     let State(mint_state) = state.clone();
     let json_extracted_payload = payload.deref();
 
-    let cache_key = match mint_state.cache.calculate_key(json_extracted_payload) {
+    let endpoint = format!("/v1/mint/{}", method.as_str());
+    if let Some(key) =
+        idempotency_key.cache_key(&mint_state.cache, &endpoint, json_extracted_payload)
+    {
+        #[cfg(feature = "auth")]
+        return run_once(
+            &mint_state.cache,
+            key,
+            post_mint_custom(auth, state, method, payload),
+        )
+        .await;
+        #[cfg(not(feature = "auth"))]
+        return run_once(
+            &mint_state.cache,
+            key,
+            post_mint_custom(state, method, payload),
+        )
+        .await;
+    }
+
+    let cache_key = match mint_state
+        .cache
+        .calculate_key(&endpoint, json_extracted_payload)
+    {
         Some(key) => key,
         None => {
             // Could not calculate key, just return the handler result
@@ -525,10 +662,11 @@ mod tests {
     }
 }
 
-/// Cached version of post_melt_custom for NUT-19 caching support
+/// Cached version of post_melt_custom for NUT-19 and idempotency key support
 #[instrument(skip_all, fields(method = ?method))]
 pub async fn cache_post_melt_custom(
     #[cfg(feature = "auth")] auth: AuthHeader,
+    idempotency_key: IdempotencyKey,
     prefer: PreferHeader,
     state: State<MintState>,
     method: Path<String>,
@@ -539,7 +677,30 @@ pub async fn cache_post_melt_custom(
     let State(mint_state) = state.clone();
     let json_extracted_payload = payload.deref();
 
-    let cache_key = match mint_state.cache.calculate_key(json_extracted_payload) {
+    let endpoint = format!("/v1/melt/{}", method.as_str());
+    if let Some(key) =
+        idempotency_key.cache_key(&mint_state.cache, &endpoint, json_extracted_payload)
+    {
+        #[cfg(feature = "auth")]
+        return run_once(
+            &mint_state.cache,
+            key,
+            post_melt_custom(auth, prefer, state, method, payload),
+        )
+        .await;
+        #[cfg(not(feature = "auth"))]
+        return run_once(
+            &mint_state.cache,
+            key,
+            post_melt_custom(prefer, state, method, payload),
+        )
+        .await;
+    }
+
+    let cache_key = match mint_state
+        .cache
+        .calculate_key(&endpoint, json_extracted_payload)
+    {
         Some(key) => key,
         None => {
             // Could not calculate key, just return the handler result
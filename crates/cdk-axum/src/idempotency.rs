@@ -0,0 +1,440 @@
+//! Idempotency keys
+//!
+//! A wallet can send an `Idempotency-Key` header with a swap, mint or melt. The first request with
+//! a key is executed and its response stored in the HTTP cache under the key, scoped to the
+//! endpoint and to the quote for mint and melt, or to the Ys of the inputs for swap. Retries with
+//! the same key get the stored response within the cache TTL whatever their outputs, and a retry
+//! sent while the first request is still executing is rejected with `409 Conflict`. If the cache
+//! backend fails the request is rejected with `503 Service Unavailable`, as it can't be told
+//! whether it already executed.
+//!
+//! The NUT-19 cache keys responses by the hash of the payload instead, so a wallet that rebuilt a
+//! request, e.g. with new blinded messages, would have it executed again. Failed requests are not
+//! stored, they can be retried with the same key.
+
+use std::future::Future;
+use std::sync::Arc;
+
+use axum::extract::{FromRequestParts, Json};
+use axum::http::request::Parts;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use cdk::mint::QuoteId;
+use cdk::nuts::nut00::ProofsMethods;
+use cdk::nuts::{MeltRequest, MintRequest, SwapRequest};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::cache::{HttpCache, HttpCacheKey, IdempotencyClaim, IdempotencyScope};
+
+/// Request header holding the idempotency key
+pub const IDEMPOTENCY_KEY_HEADER: &str = "Idempotency-Key";
+
+/// Longest idempotency key accepted
+const MAX_IDEMPOTENCY_KEY_LEN: usize = 255;
+
+/// Header extractor for the optional `Idempotency-Key` header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IdempotencyKey(pub Option<String>);
+
+impl<S> FromRequestParts<S> for IdempotencyKey
+where
+    S: Send + Sync,
+{
+    type Rejection = (StatusCode, String);
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let Some(value) = parts.headers.get(IDEMPOTENCY_KEY_HEADER) else {
+            return Ok(IdempotencyKey(None));
+        };
+
+        match value.to_str() {
+            Ok(key) if !key.is_empty() && key.len() <= MAX_IDEMPOTENCY_KEY_LEN => {
+                Ok(IdempotencyKey(Some(key.to_owned())))
+            }
+            _ => Err((
+                StatusCode::BAD_REQUEST,
+                format!("Invalid {IDEMPOTENCY_KEY_HEADER} header value"),
+            )),
+        }
+    }
+}
+
+impl IdempotencyKey {
+    /// Cache key of the response to `request` sent to `endpoint`, if the request has an
+    /// idempotency key
+    pub fn cache_key<R>(
+        &self,
+        cache: &HttpCache,
+        endpoint: &str,
+        request: &R,
+    ) -> Option<HttpCacheKey>
+    where
+        R: IdempotentRequest,
+    {
+        let idempotency_key = self.0.as_deref()?;
+        cache.calculate_idempotency_key(endpoint, idempotency_key, &request.idempotency_scope()?)
+    }
+}
+
+/// Request that can be sent with an idempotency key
+pub trait IdempotentRequest {
+    /// What the idempotency key of the request is scoped to, `None` if it can't be told
+    fn idempotency_scope(&self) -> Option<IdempotencyScope<'_>>;
+}
+
+impl IdempotentRequest for SwapRequest {
+    fn idempotency_scope(&self) -> Option<IdempotencyScope<'_>> {
+        Some(IdempotencyScope::inputs(self.inputs().ys().ok()?))
+    }
+}
+
+impl IdempotentRequest for MintRequest<QuoteId> {
+    fn idempotency_scope(&self) -> Option<IdempotencyScope<'_>> {
+        Some(IdempotencyScope::Quote(&self.quote))
+    }
+}
+
+impl IdempotentRequest for MeltRequest<QuoteId> {
+    fn idempotency_scope(&self) -> Option<IdempotencyScope<'_>> {
+        Some(IdempotencyScope::Quote(self.quote()))
+    }
+}
+
+/// Execute `handler` once for the idempotency `key`
+///
+/// Returns the stored response if a request with the key completed, `409 Conflict` if one is
+/// still executing and `503 Service Unavailable` if the cache can't tell.
+pub(crate) async fn run_once<V, F>(
+    cache: &Arc<HttpCache>,
+    key: HttpCacheKey,
+    handler: F,
+) -> Result<Json<V>, Response>
+where
+    V: Serialize + DeserializeOwned,
+    F: Future<Output = Result<Json<V>, Response>>,
+{
+    match cache.claim::<V>(&key).await {
+        IdempotencyClaim::Claimed => {}
+        IdempotencyClaim::Replay(response) => return Ok(Json(response)),
+        IdempotencyClaim::InFlight => {
+            return Err((
+                StatusCode::CONFLICT,
+                format!("A request with the same {IDEMPOTENCY_KEY_HEADER} is in progress"),
+            )
+                .into_response())
+        }
+        IdempotencyClaim::Unavailable => {
+            return Err((
+                StatusCode::SERVICE_UNAVAILABLE,
+                format!("Requests with an {IDEMPOTENCY_KEY_HEADER} are unavailable"),
+            )
+                .into_response())
+        }
+    }
+
+    let guard = ClaimGuard {
+        cache,
+        key: &key,
+        armed: true,
+    };
+    let result = handler.await;
+    guard.disarm();
+
+    match &result {
+        Ok(response) => cache.complete(key, &response.0).await,
+        Err(_) => cache.release(&key).await,
+    }
+
+    result
+}
+
+/// Releases a claimed idempotency key if the request is dropped before it completes, e.g. because
+/// the client disconnected
+struct ClaimGuard<'a> {
+    cache: &'a Arc<HttpCache>,
+    key: &'a HttpCacheKey,
+    armed: bool,
+}
+
+impl ClaimGuard<'_> {
+    /// Keep the claim, the request completed and settles the key itself
+    fn disarm(mut self) {
+        self.armed = false;
+    }
+}
+
+impl Drop for ClaimGuard<'_> {
+    fn drop(&mut self) {
+        if !self.armed {
+            return;
+        }
+
+        if let Ok(runtime) = tokio::runtime::Handle::try_current() {
+            let cache = Arc::clone(self.cache);
+            let key = self.key.clone();
+            runtime.spawn(async move { cache.release(&key).await });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use axum::http::{HeaderValue, Request};
+    use cdk::mint::{ResponseCacheScope, ResponseCacheStats};
+    use cdk::nuts::SwapResponse;
+    use tokio::sync::oneshot;
+
+    use super::*;
+    use crate::cache::HttpCacheStorage;
+
+    const ENDPOINT: &str = "/v1/swap";
+
+    async fn idempotency_key(
+        header: Option<&[u8]>,
+    ) -> Result<IdempotencyKey, (StatusCode, String)> {
+        let mut req = Request::builder().method("POST").uri(ENDPOINT);
+        if let Some(header) = header {
+            req = req.header(
+                IDEMPOTENCY_KEY_HEADER,
+                HeaderValue::from_bytes(header).unwrap(),
+            );
+        }
+        let (mut parts, _) = req.body(()).unwrap().into_parts();
+
+        IdempotencyKey::from_request_parts(&mut parts, &()).await
+    }
+
+    fn swap_request(secret: &str) -> SwapRequest {
+        serde_json::from_value(serde_json::json!({
+            "inputs": [{
+                "amount": 1,
+                "id": "009a1f293253e41e",
+                "secret": secret,
+                "C": "02bc9097997d81afb2cc7346b5e4345a9346bd2a506eb7958598a72f0cf85163ea"
+            }],
+            "outputs": []
+        }))
+        .unwrap()
+    }
+
+    /// Cache storage whose backend is unreachable
+    struct UnreachableStorage;
+
+    #[async_trait::async_trait]
+    impl HttpCacheStorage for UnreachableStorage {
+        fn set_expiration_times(&mut self, _cache_ttl: Duration, _cache_tti: Duration) {}
+
+        async fn get(&self, _key: &HttpCacheKey) -> Option<Vec<u8>> {
+            None
+        }
+
+        async fn set(&self, _key: HttpCacheKey, _value: Vec<u8>) {}
+
+        async fn set_if_absent(&self, _key: HttpCacheKey, _value: Vec<u8>) -> Option<bool> {
+            None
+        }
+
+        async fn clear(&self, _scope: &ResponseCacheScope) {}
+
+        async fn stats(&self) -> ResponseCacheStats {
+            ResponseCacheStats::default()
+        }
+    }
+
+    fn ok(signatures: usize) -> Result<Json<SwapResponse>, Response> {
+        let signature = serde_json::json!({
+            "amount": 1,
+            "id": "009a1f293253e41e",
+            "C_": "02634a2c2b34bec9e8a4aba4361f6bf202d7fa2365379b0840afe249a7a9d71239"
+        });
+        Ok(Json(
+            serde_json::from_value(serde_json::json!({
+                "signatures": vec![signature; signatures]
+            }))
+            .unwrap(),
+        ))
+    }
+
+    #[tokio::test]
+    async fn header_is_optional_and_validated() {
+        assert_eq!(idempotency_key(None).await.unwrap(), IdempotencyKey(None));
+        assert_eq!(
+            idempotency_key(Some(b"retry-1")).await.unwrap(),
+            IdempotencyKey(Some("retry-1".to_owned()))
+        );
+
+        for invalid in [
+            &b""[..],
+            &[0xFF, 0xFE],
+            &[b'a'; MAX_IDEMPOTENCY_KEY_LEN + 1],
+        ] {
+            let (status, _) = idempotency_key(Some(invalid)).await.unwrap_err();
+            assert_eq!(status, StatusCode::BAD_REQUEST);
+        }
+    }
+
+    #[tokio::test]
+    async fn same_key_replays_the_first_response_whatever_the_payload() {
+        let cache = Arc::new(HttpCache::default());
+        let key = IdempotencyKey(Some("retry-1".to_owned()));
+        let cache_key = || key.cache_key(&cache, ENDPOINT, &swap_request("a")).unwrap();
+
+        let first = run_once(&cache, cache_key(), async { ok(1) })
+            .await
+            .unwrap();
+        // A retry with another payload would have got another response
+        let retry = run_once(&cache, cache_key(), async { ok(2) })
+            .await
+            .unwrap();
+        assert_eq!(retry.0, first.0);
+
+        // Another key, or the same key spending other proofs, is executed
+        let other = IdempotencyKey(Some("retry-2".to_owned()))
+            .cache_key(&cache, ENDPOINT, &swap_request("a"))
+            .unwrap();
+        assert_eq!(
+            run_once(&cache, other, async { ok(2) })
+                .await
+                .unwrap()
+                .0
+                .signatures
+                .len(),
+            2
+        );
+        let scoped = key.cache_key(&cache, ENDPOINT, &swap_request("b")).unwrap();
+        assert_eq!(
+            run_once(&cache, scoped, async { ok(3) })
+                .await
+                .unwrap()
+                .0
+                .signatures
+                .len(),
+            3
+        );
+    }
+
+    #[tokio::test]
+    async fn mint_and_melt_keys_are_scoped_to_the_quote() {
+        let cache = HttpCache::default();
+        let key = IdempotencyKey(Some("retry-1".to_owned()));
+        let quote = QuoteId::new_uuid();
+
+        let mint: MintRequest<QuoteId> = serde_json::from_value(serde_json::json!({
+            "quote": quote.to_string(),
+            "outputs": []
+        }))
+        .unwrap();
+        let other_mint: MintRequest<QuoteId> = serde_json::from_value(serde_json::json!({
+            "quote": QuoteId::new_uuid().to_string(),
+            "outputs": []
+        }))
+        .unwrap();
+
+        assert_eq!(
+            mint.idempotency_scope(),
+            Some(IdempotencyScope::Quote(&quote))
+        );
+        assert_ne!(
+            key.cache_key(&cache, "/v1/mint/bolt11", &mint),
+            key.cache_key(&cache, "/v1/mint/bolt11", &other_mint)
+        );
+    }
+
+    #[tokio::test]
+    async fn unreachable_cache_rejects_the_request() {
+        let cache = Arc::new(HttpCache::new(
+            Duration::from_secs(60),
+            Duration::from_secs(60),
+            Some(Box::new(UnreachableStorage)),
+        ));
+        let key = IdempotencyKey(Some("retry-1".to_owned()))
+            .cache_key(&cache, ENDPOINT, &swap_request("a"))
+            .unwrap();
+
+        let executed = std::sync::atomic::AtomicBool::new(false);
+        let rejected = run_once(&cache, key, async {
+            executed.store(true, std::sync::atomic::Ordering::Relaxed);
+            ok(1)
+        })
+        .await
+        .unwrap_err();
+
+        assert_eq!(rejected.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(!executed.load(std::sync::atomic::Ordering::Relaxed));
+    }
+
+    #[tokio::test]
+    async fn failed_request_can_be_retried_with_the_same_key() {
+        let cache = Arc::new(HttpCache::default());
+        let key = IdempotencyKey(Some("retry-1".to_owned()))
+            .cache_key(&cache, ENDPOINT, &swap_request("a"))
+            .unwrap();
+
+        let failed = run_once(&cache, key.clone(), async {
+            Err::<Json<SwapResponse>, _>(StatusCode::BAD_REQUEST.into_response())
+        })
+        .await;
+        assert!(failed.is_err());
+
+        let retry = run_once(&cache, key, async { ok(1) }).await.unwrap();
+        assert_eq!(retry.0.signatures.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn concurrent_request_with_the_same_key_conflicts() {
+        let cache = Arc::new(HttpCache::default());
+        let key = IdempotencyKey(Some("retry-1".to_owned()))
+            .cache_key(&cache, ENDPOINT, &swap_request("a"))
+            .unwrap();
+
+        let (started_tx, started_rx) = oneshot::channel();
+        let (finish_tx, finish_rx) = oneshot::channel::<()>();
+        let first = tokio::spawn({
+            let cache = Arc::clone(&cache);
+            let key = key.clone();
+            async move {
+                run_once(&cache, key, async move {
+                    started_tx.send(()).unwrap();
+                    finish_rx.await.unwrap();
+                    ok(1)
+                })
+                .await
+            }
+        });
+        started_rx.await.unwrap();
+
+        let conflict = run_once(&cache, key.clone(), async { ok(2) })
+            .await
+            .unwrap_err();
+        assert_eq!(conflict.status(), StatusCode::CONFLICT);
+
+        finish_tx.send(()).unwrap();
+        let first = first.await.unwrap().unwrap();
+        let retry = run_once(&cache, key, async { ok(2) }).await.unwrap();
+        assert_eq!(retry.0, first.0);
+    }
+
+    #[tokio::test]
+    async fn dropped_request_releases_its_key() {
+        let cache = Arc::new(HttpCache::default());
+        let key = IdempotencyKey(Some("retry-1".to_owned()))
+            .cache_key(&cache, ENDPOINT, &swap_request("a"))
+            .unwrap();
+
+        let dropped = run_once(
+            &cache,
+            key.clone(),
+            std::future::pending::<Result<Json<SwapResponse>, Response>>(),
+        );
+        assert!(tokio::time::timeout(Duration::from_millis(10), dropped)
+            .await
+            .is_err());
+        tokio::time::sleep(Duration::from_millis(10)).await;
+
+        let retry = run_once(&cache, key, async { ok(1) }).await.unwrap();
+        assert_eq!(retry.0.signatures.len(), 1);
+    }
+}
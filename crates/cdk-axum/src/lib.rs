@@ -7,7 +7,8 @@ use std::sync::Arc;
 use anyhow::Result;
 #[cfg(feature = "auth")]
 use auth::create_auth_router;
-use axum::middleware::from_fn;
+use axum::extract::DefaultBodyLimit;
+use axum::middleware::{from_fn, from_fn_with_state};
 use axum::response::Response;
 use axum::routing::{get, post};
 use axum::Router;
@@ -19,9 +20,15 @@ mod metrics;
 
 #[cfg(feature = "auth")]
 mod auth;
+mod ban_list;
 pub mod cache;
+pub mod client_ip;
 mod custom_handlers;
 mod custom_router;
+pub mod idempotency;
+pub mod limits;
+pub mod rate_limit;
+pub mod request_id;
 mod router_handlers;
 mod ws;
 
@@ -44,6 +51,9 @@ mod swagger_imports {
     pub use cdk::nuts::nut12::{BlindSignatureDleq, ProofDleq};
     pub use cdk::nuts::nut14::HTLCWitness;
     pub use cdk::nuts::nut15::{Mpp, MppMethodSettings};
+    pub use cdk::nuts::nut20::{
+        MintQuoteStatus, MintQuotesPubkey, MintQuotesRequest, MintQuotesResponse,
+    };
     pub use cdk::nuts::nut23::{
         MeltQuoteBolt11Request, MeltQuoteBolt11Response, MintQuoteBolt11Request,
         MintQuoteBolt11Response,
@@ -86,7 +96,15 @@ macro_rules! define_api_doc {
                 get_mint_info,
                 post_swap,
                 post_check,
-                post_restore
+                post_restore,
+                post_mint_quotes,
+                ws_handler,
+                custom_handlers::post_mint_custom_quote,
+                custom_handlers::get_check_mint_custom_quote,
+                custom_handlers::post_mint_custom,
+                custom_handlers::post_melt_custom_quote,
+                custom_handlers::get_check_melt_custom_quote,
+                custom_handlers::post_melt_custom
                 $(,$($path,)*)?
                 $(,$($auth_path,)*)?
             )
@@ -128,6 +146,10 @@ define_api_doc! {
         MintQuoteBolt11Request,
         MintQuoteBolt11Response<String>,
         MintQuoteState,
+        MintQuoteStatus<String>,
+        MintQuotesPubkey,
+        MintQuotesRequest,
+        MintQuotesResponse<String>,
         MintMethodSettings,
         MintVersion,
         Mpp,
@@ -184,6 +206,10 @@ define_api_doc! {
         MintQuoteBolt11Request,
         MintQuoteBolt11Response<String>,
         MintQuoteState,
+        MintQuoteStatus<String>,
+        MintQuotesPubkey,
+        MintQuotesRequest,
+        MintQuotesResponse<String>,
         MintMethodSettings,
         MintVersion,
         Mpp,
@@ -228,9 +254,9 @@ async fn cors_middleware(
     next: axum::middleware::Next,
 ) -> Response {
     #[cfg(feature = "auth")]
-    let allowed_headers = "Content-Type, Clear-auth, Blind-auth";
+    let allowed_headers = "Content-Type, Idempotency-Key, Clear-auth, Blind-auth";
     #[cfg(not(feature = "auth"))]
-    let allowed_headers = "Content-Type";
+    let allowed_headers = "Content-Type, Idempotency-Key";
 
     // Handle preflight requests
     if req.method() == axum::http::Method::OPTIONS {
@@ -278,21 +304,61 @@ pub async fn create_mint_router_with_custom_cache(
     mint: Arc<Mint>,
     cache: HttpCache,
     custom_methods: Vec<String>,
+) -> Result<Router> {
+    create_mint_router_with_limits(mint, cache, custom_methods, Default::default()).await
+}
+
+/// Create mint [`Router`] with a custom backend for cache and custom request limits
+///
+/// See [`limits`] for how the body size limit and the timeouts are applied. Mint, melt and swap
+/// requests of clients banned by the mint are rejected with `429 Too Many Requests`; this needs
+/// the [`client_ip::ClientIp`] of the requests, see [`client_ip::client_ip_middleware`]. The
+/// same address limits the mint quote lookups, state checks and restores of a client, see
+/// [`rate_limit`].
+pub async fn create_mint_router_with_limits(
+    mint: Arc<Mint>,
+    cache: HttpCache,
+    custom_methods: Vec<String>,
+    limits: limits::Config,
 ) -> Result<Router> {
     let state = MintState {
         mint,
         cache: Arc::new(cache),
     };
 
+    let (check_state, restore) = match limits.read_heavy_requests_per_minute() {
+        Some(max_requests) => {
+            let read_heavy_limit = from_fn_with_state(
+                rate_limit::RateLimiter::new(max_requests, rate_limit::READ_HEAVY_WINDOW),
+                rate_limit::rate_limit_middleware,
+            );
+            (
+                post(post_check).route_layer(read_heavy_limit.clone()),
+                post(post_restore).route_layer(read_heavy_limit),
+            )
+        }
+        None => (post(post_check), post(post_restore)),
+    };
+
     let v1_router = Router::new()
         .route("/keys", get(get_keys))
         .route("/keysets", get(get_keysets))
         .route("/keys/{keyset_id}", get(get_keyset_pubkeys))
         .route("/swap", post(cache_post_swap))
         .route("/ws", get(ws_handler))
-        .route("/checkstate", post(post_check))
+        .route("/checkstate", check_state)
         .route("/info", get(get_mint_info))
-        .route("/restore", post(post_restore));
+        .route("/restore", restore)
+        .route(
+            "/mint/quotes",
+            post(post_mint_quotes).route_layer(from_fn_with_state(
+                rate_limit::RateLimiter::new(
+                    rate_limit::MINT_QUOTES_MAX_REQUESTS,
+                    rate_limit::MINT_QUOTES_WINDOW,
+                ),
+                rate_limit::rate_limit_middleware,
+            )),
+        );
 
     let mint_router = Router::new().nest("/v1", v1_router);
 
@@ -327,8 +393,70 @@ pub async fn create_mint_router_with_custom_cache(
         metrics::global_metrics_middleware,
     ));
     let mint_router = mint_router
+        .layer(from_fn_with_state(limits, limits::timeout_middleware))
+        .layer(DefaultBodyLimit::max(limits.max_body_size()))
+        .layer(from_fn_with_state(
+            ban_list::BanListState {
+                mint: Arc::clone(&state.mint),
+                max_body_size: limits.max_body_size(),
+            },
+            ban_list::ban_list_middleware,
+        ))
         .layer(from_fn(cors_middleware))
+        .layer(from_fn(request_id::request_id_middleware))
         .with_state(state);
 
     Ok(mint_router)
 }
+
+#[cfg(all(test, feature = "swagger"))]
+mod tests {
+    use utoipa::OpenApi;
+
+    use super::ApiDoc;
+
+    /// Every v1 route must be described, so clients can be generated from the document
+    #[test]
+    fn openapi_document_covers_all_routes() {
+        let document: serde_json::Value =
+            serde_json::from_str(&ApiDoc::openapi().to_json().unwrap()).unwrap();
+        let paths = document["paths"].as_object().unwrap();
+
+        let routes = [
+            ("/v1/keys", "get"),
+            ("/v1/keys/{keyset_id}", "get"),
+            ("/v1/keysets", "get"),
+            ("/v1/info", "get"),
+            ("/v1/swap", "post"),
+            ("/v1/checkstate", "post"),
+            ("/v1/restore", "post"),
+            ("/v1/mint/quotes", "post"),
+            ("/v1/ws", "get"),
+            ("/v1/mint/quote/{method}", "post"),
+            ("/v1/mint/quote/{method}/{quote_id}", "get"),
+            ("/v1/mint/{method}", "post"),
+            ("/v1/melt/quote/{method}", "post"),
+            ("/v1/melt/quote/{method}/{quote_id}", "get"),
+            ("/v1/melt/{method}", "post"),
+        ];
+
+        for (path, method) in routes {
+            let operation = &paths
+                .get(path)
+                .unwrap_or_else(|| panic!("{path} missing from the OpenAPI document"))[method];
+            assert!(operation.is_object(), "{method} {path} is not documented");
+
+            // Errors are NUT-00 error responses
+            if path != "/v1/info" {
+                assert_eq!(
+                    operation["responses"]["400"]["content"]["application/json"]["schema"]["$ref"],
+                    "#/components/schemas/ErrorResponse",
+                    "{method} {path} has no error response"
+                );
+            }
+        }
+
+        let error_code = &document["components"]["schemas"]["ErrorResponse"]["properties"]["code"];
+        assert_eq!(error_code["type"], "integer");
+    }
+}
@@ -0,0 +1,273 @@
+//! Request size limits and timeouts
+//!
+//! The body limit is enforced by the extractors while they read the body, so it applies to the
+//! decompressed body when a decompression layer wraps the router. Oversized requests are answered
+//! with `413 Payload Too Large`.
+//!
+//! Requests that take longer than their timeout are answered with `504 Gateway Timeout`. The
+//! handler keeps running in the background, so an operation such as a melt is never cancelled
+//! halfway; the wallet can check the quote state afterwards.
+
+use std::time::Duration;
+
+use axum::extract::{Request, State};
+use axum::http::StatusCode;
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+use serde::{Deserialize, Serialize};
+use tracing::Instrument;
+
+use crate::rate_limit::READ_HEAVY_MAX_REQUESTS;
+
+pub const ENV_CDK_MINTD_MAX_BODY_SIZE: &str = "CDK_MINTD_MAX_BODY_SIZE";
+pub const ENV_CDK_MINTD_REQUEST_TIMEOUT: &str = "CDK_MINTD_REQUEST_TIMEOUT";
+pub const ENV_CDK_MINTD_MELT_REQUEST_TIMEOUT: &str = "CDK_MINTD_MELT_REQUEST_TIMEOUT";
+pub const ENV_CDK_MINTD_INFO_REQUEST_TIMEOUT: &str = "CDK_MINTD_INFO_REQUEST_TIMEOUT";
+pub const ENV_CDK_MINTD_READ_HEAVY_REQUESTS_PER_MINUTE: &str =
+    "CDK_MINTD_READ_HEAVY_REQUESTS_PER_MINUTE";
+
+/// Default maximum request body size in bytes, after decompression
+pub const DEFAULT_MAX_BODY_SIZE: usize = 2 * 1024 * 1024;
+/// Default request timeout in seconds
+pub const DEFAULT_REQUEST_TIMEOUT: u64 = 60;
+/// Default timeout in seconds for melt requests, which wait for the payment
+pub const DEFAULT_MELT_REQUEST_TIMEOUT: u64 = 300;
+/// Default timeout in seconds for the mint info and keys requests
+pub const DEFAULT_INFO_REQUEST_TIMEOUT: u64 = 10;
+
+/// Request limits configuration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct Config {
+    /// Maximum request body size in bytes, after decompression.
+    pub max_body_size: Option<usize>,
+
+    /// Timeout in seconds for requests without a specific timeout.
+    pub timeout: Option<u64>,
+
+    /// Timeout in seconds for melt requests.
+    pub melt_timeout: Option<u64>,
+
+    /// Timeout in seconds for the info, keys and keysets requests.
+    pub info_timeout: Option<u64>,
+
+    /// State checks and restores allowed per client and minute on each endpoint, 0 disables
+    /// the limit.
+    pub read_heavy_requests_per_minute: Option<usize>,
+}
+
+impl Config {
+    /// Config from env
+    pub fn from_env(mut self) -> Self {
+        use std::env;
+
+        if let Ok(size_str) = env::var(ENV_CDK_MINTD_MAX_BODY_SIZE) {
+            if let Ok(size) = size_str.parse() {
+                self.max_body_size = Some(size);
+            }
+        }
+
+        if let Ok(timeout_str) = env::var(ENV_CDK_MINTD_REQUEST_TIMEOUT) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.timeout = Some(timeout);
+            }
+        }
+
+        if let Ok(timeout_str) = env::var(ENV_CDK_MINTD_MELT_REQUEST_TIMEOUT) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.melt_timeout = Some(timeout);
+            }
+        }
+
+        if let Ok(timeout_str) = env::var(ENV_CDK_MINTD_INFO_REQUEST_TIMEOUT) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.info_timeout = Some(timeout);
+            }
+        }
+
+        if let Ok(max_str) = env::var(ENV_CDK_MINTD_READ_HEAVY_REQUESTS_PER_MINUTE) {
+            if let Ok(max) = max_str.parse() {
+                self.read_heavy_requests_per_minute = Some(max);
+            }
+        }
+
+        self
+    }
+
+    /// Maximum request body size in bytes
+    pub fn max_body_size(&self) -> usize {
+        self.max_body_size.unwrap_or(DEFAULT_MAX_BODY_SIZE)
+    }
+
+    /// State checks and restores allowed per client and minute, `None` when the limit is off
+    pub fn read_heavy_requests_per_minute(&self) -> Option<usize> {
+        match self
+            .read_heavy_requests_per_minute
+            .unwrap_or(READ_HEAVY_MAX_REQUESTS)
+        {
+            0 => None,
+            max => Some(max),
+        }
+    }
+
+    /// Timeout for a request to `path`
+    pub fn timeout_for(&self, path: &str) -> Duration {
+        let path = path.strip_prefix("/v1").unwrap_or(path);
+
+        let seconds = if path.starts_with("/melt/") {
+            self.melt_timeout.unwrap_or(DEFAULT_MELT_REQUEST_TIMEOUT)
+        } else if path == "/info" || path == "/keysets" || path.starts_with("/keys") {
+            self.info_timeout.unwrap_or(DEFAULT_INFO_REQUEST_TIMEOUT)
+        } else {
+            self.timeout.unwrap_or(DEFAULT_REQUEST_TIMEOUT)
+        };
+
+        Duration::from_secs(seconds)
+    }
+}
+
+/// Answers with `504 Gateway Timeout` once the request takes longer than its timeout
+pub(crate) async fn timeout_middleware(
+    State(config): State<Config>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let timeout = config.timeout_for(req.uri().path());
+    let path = req.uri().path().to_owned();
+
+    // Spawned so a timeout does not cancel the handler in the middle of an operation. The
+    // handler stays in the request span so its events keep the request id.
+    let handler = tokio::spawn(next.run(req).in_current_span());
+
+    match tokio::time::timeout(timeout, handler).await {
+        Ok(Ok(response)) => response,
+        Ok(Err(err)) => {
+            tracing::error!("Handler for {} failed: {}", path, err);
+            StatusCode::INTERNAL_SERVER_ERROR.into_response()
+        }
+        Err(_) => {
+            tracing::warn!("Request to {} timed out after {:?}", path, timeout);
+            (StatusCode::GATEWAY_TIMEOUT, "Request timed out").into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use axum::body::Body;
+    use axum::extract::DefaultBodyLimit;
+    use axum::http::header::CONTENT_ENCODING;
+    use axum::middleware::from_fn_with_state;
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use tower::ServiceExt;
+    use tower_http::decompression::RequestDecompressionLayer;
+
+    use super::*;
+
+    fn router(config: Config) -> Router {
+        Router::new()
+            .route(
+                "/v1/swap",
+                post(|Json(body): Json<serde_json::Value>| async move { Json(body) }),
+            )
+            .route(
+                "/v1/info",
+                get(|| async {
+                    tokio::time::sleep(Duration::from_secs(2)).await;
+                    "info"
+                }),
+            )
+            .layer(from_fn_with_state(config, timeout_middleware))
+            .layer(DefaultBodyLimit::max(config.max_body_size()))
+            .layer(RequestDecompressionLayer::new())
+    }
+
+    fn gzip_json_request(padding: usize) -> Request {
+        let body = serde_json::json!({ "padding": "a".repeat(padding) }).to_string();
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+        encoder.write_all(body.as_bytes()).unwrap();
+
+        Request::post("/v1/swap")
+            .header("content-type", "application/json")
+            .header(CONTENT_ENCODING, "gzip")
+            .body(Body::from(encoder.finish().unwrap()))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn compressed_body_over_limit_is_rejected() {
+        let config = Config {
+            max_body_size: Some(1024),
+            ..Default::default()
+        };
+
+        let response = router(config)
+            .oneshot(gzip_json_request(100))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Compresses to far less than the limit, but is checked once decompressed
+        let request = gzip_json_request(64 * 1024);
+        let response = router(config).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[tokio::test]
+    async fn slow_request_times_out() {
+        let config = Config {
+            info_timeout: Some(1),
+            ..Default::default()
+        };
+
+        let request = Request::get("/v1/info").body(Body::empty()).unwrap();
+        let response = router(config).oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::GATEWAY_TIMEOUT);
+    }
+
+    #[test]
+    fn read_heavy_limit_defaults_and_turns_off() {
+        assert_eq!(
+            Config::default().read_heavy_requests_per_minute(),
+            Some(READ_HEAVY_MAX_REQUESTS)
+        );
+
+        let config = Config {
+            read_heavy_requests_per_minute: Some(600),
+            ..Default::default()
+        };
+        assert_eq!(config.read_heavy_requests_per_minute(), Some(600));
+
+        let config = Config {
+            read_heavy_requests_per_minute: Some(0),
+            ..Default::default()
+        };
+        assert_eq!(config.read_heavy_requests_per_minute(), None);
+    }
+
+    #[test]
+    fn route_timeouts() {
+        let config = Config {
+            timeout: Some(30),
+            ..Default::default()
+        };
+
+        assert_eq!(config.timeout_for("/v1/swap"), Duration::from_secs(30));
+        assert_eq!(
+            config.timeout_for("/v1/melt/bolt11"),
+            Duration::from_secs(DEFAULT_MELT_REQUEST_TIMEOUT)
+        );
+        assert_eq!(
+            config.timeout_for("/v1/keys/009a1f293253e41e"),
+            Duration::from_secs(DEFAULT_INFO_REQUEST_TIMEOUT)
+        );
+        assert_eq!(
+            config.timeout_for("/v1/melt/quote/bolt11"),
+            Duration::from_secs(DEFAULT_MELT_REQUEST_TIMEOUT)
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Rate limits of the mint quote lookup and the read-heavy endpoints
+//!
+//! Listing the mint quotes of a pubkey needs a signature by that pubkey, so the lookup does not
+//! reveal the quotes of others. The limit keeps clients from using it to probe the mint at
+//! scale: a client may send [`MINT_QUOTES_MAX_REQUESTS`] lookups per [`MINT_QUOTES_WINDOW`],
+//! further lookups are answered with `429 Too Many Requests`.
+//!
+//! State checks and restores read up to a request limit of proofs or outputs from the database
+//! without changing anything, so a client may send [`READ_HEAVY_MAX_REQUESTS`] of each per
+//! [`READ_HEAVY_WINDOW`] unless the operator configures another limit or turns it off. Wallets
+//! restoring from a seed send many restores in a row and should wait out the `Retry-After`.
+//!
+//! Clients are identified by their [`ClientIp`]; requests without one are passed on unchanged.
+//! Requests are counted per client and endpoint, so a limiter shared by several endpoints
+//! limits each of them separately.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use axum::extract::{Request, State};
+use axum::http::{header, StatusCode};
+use axum::middleware::Next;
+use axum::response::{IntoResponse, Response};
+
+use crate::client_ip::ClientIp;
+
+/// Mint quote lookups allowed per client in [`MINT_QUOTES_WINDOW`]
+pub const MINT_QUOTES_MAX_REQUESTS: usize = 10;
+/// Window of the mint quote lookup limit
+pub const MINT_QUOTES_WINDOW: Duration = Duration::from_secs(60);
+/// Default requests to each read-heavy endpoint allowed per client in [`READ_HEAVY_WINDOW`]
+pub const READ_HEAVY_MAX_REQUESTS: usize = 60;
+/// Window of the read-heavy endpoint limit
+pub const READ_HEAVY_WINDOW: Duration = Duration::from_secs(60);
+
+/// Client and endpoint the requests are counted for
+type RequestKey = (IpAddr, String);
+
+/// Sliding window limit of the requests of each client
+#[derive(Debug, Clone)]
+pub(crate) struct RateLimiter {
+    max_requests: usize,
+    window: Duration,
+    requests: Arc<Mutex<HashMap<RequestKey, VecDeque<Instant>>>>,
+}
+
+impl RateLimiter {
+    pub(crate) fn new(max_requests: usize, window: Duration) -> Self {
+        Self {
+            max_requests,
+            window,
+            requests: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Record a request of `client` to `endpoint` at `now`
+    ///
+    /// Returns the time until the client may send again when it is over the limit, the
+    /// refused request is not recorded.
+    fn check(&self, client: IpAddr, endpoint: &str, now: Instant) -> Result<(), Duration> {
+        // The request times stay consistent even if a holder panicked
+        let mut requests = self
+            .requests
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        // Forget the requests out of the window, and the clients left without any
+        requests.retain(|_, times| {
+            while times
+                .front()
+                .is_some_and(|time| now.saturating_duration_since(*time) >= self.window)
+            {
+                times.pop_front();
+            }
+            !times.is_empty()
+        });
+
+        let times = requests.entry((client, endpoint.to_string())).or_default();
+        if times.len() >= self.max_requests {
+            let oldest = times.front().copied().unwrap_or(now);
+            return Err(self.window - now.saturating_duration_since(oldest));
+        }
+
+        times.push_back(now);
+        Ok(())
+    }
+}
+
+/// Reject the requests of clients over the limit of `limiter`
+pub(crate) async fn rate_limit_middleware(
+    State(limiter): State<RateLimiter>,
+    req: Request,
+    next: Next,
+) -> Response {
+    let Some(ClientIp(client)) = req.extensions().get::<ClientIp>().copied() else {
+        return next.run(req).await;
+    };
+
+    if let Err(retry_after) = limiter.check(client, req.uri().path(), Instant::now()) {
+        tracing::debug!("Rate limiting client {} on {}", client, req.uri().path());
+        return (
+            StatusCode::TOO_MANY_REQUESTS,
+            [(
+                header::RETRY_AFTER,
+                retry_after.as_secs().max(1).to_string(),
+            )],
+            "Too many requests",
+        )
+            .into_response();
+    }
+
+    next.run(req).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+
+    use super::*;
+
+    #[test]
+    fn requests_over_the_limit_are_refused_until_the_window_passes() {
+        let limiter = RateLimiter::new(2, Duration::from_secs(60));
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let other = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2));
+        let start = Instant::now();
+
+        assert!(limiter.check(client, "/v1/mint/quotes", start).is_ok());
+        assert!(limiter
+            .check(client, "/v1/mint/quotes", start + Duration::from_secs(10))
+            .is_ok());
+        assert_eq!(
+            limiter.check(client, "/v1/mint/quotes", start + Duration::from_secs(20)),
+            Err(Duration::from_secs(40))
+        );
+
+        // Other clients have their own limit
+        assert!(limiter
+            .check(other, "/v1/mint/quotes", start + Duration::from_secs(20))
+            .is_ok());
+
+        // The first request leaves the window
+        assert!(limiter
+            .check(client, "/v1/mint/quotes", start + Duration::from_secs(60))
+            .is_ok());
+        assert!(limiter
+            .check(client, "/v1/mint/quotes", start + Duration::from_secs(61))
+            .is_err());
+    }
+
+    #[test]
+    fn idle_clients_are_forgotten() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let start = Instant::now();
+
+        limiter
+            .check(
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1)),
+                "/v1/checkstate",
+                start,
+            )
+            .unwrap();
+        limiter
+            .check(
+                IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)),
+                "/v1/checkstate",
+                start + Duration::from_secs(90),
+            )
+            .unwrap();
+
+        assert_eq!(limiter.requests.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn endpoints_are_limited_separately() {
+        let limiter = RateLimiter::new(1, Duration::from_secs(60));
+        let client = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+        let start = Instant::now();
+
+        assert!(limiter.check(client, "/v1/checkstate", start).is_ok());
+        assert!(limiter.check(client, "/v1/checkstate", start).is_err());
+
+        // The same client still has its limit on the other endpoints of the limiter
+        assert!(limiter.check(client, "/v1/restore", start).is_ok());
+        assert!(limiter.check(client, "/v1/restore", start).is_err());
+    }
+}
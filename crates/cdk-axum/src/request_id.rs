@@ -0,0 +1,75 @@
+//! Per-request correlation ids
+//!
+//! Every request gets a fresh id, stored as a [`RequestId`] request extension, recorded as the
+//! `request_id` field of the tracing span of the request and returned in the `X-Request-Id`
+//! response header. All events logged while handling the request, including those of the mint,
+//! carry the id, so an operator can find the log lines of a request a wallet reports.
+
+use axum::extract::Request;
+use axum::http::HeaderValue;
+use axum::middleware::Next;
+use axum::response::Response;
+use tracing::Instrument;
+use uuid::Uuid;
+
+/// Response header holding the id of the request
+pub const X_REQUEST_ID: &str = "x-request-id";
+
+/// Correlation id of a request
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct RequestId(pub Uuid);
+
+/// Attach a new [`RequestId`] to the request, record it on the request span and return it in the
+/// `X-Request-Id` header
+pub async fn request_id_middleware(mut req: Request, next: Next) -> Response {
+    let request_id = Uuid::new_v4();
+    req.extensions_mut().insert(RequestId(request_id));
+
+    let span = tracing::info_span!("request", request_id = %request_id);
+    let mut response = next.run(req).instrument(span).await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id.to_string()) {
+        response.headers_mut().insert(X_REQUEST_ID, value);
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use axum::body::Body;
+    use axum::extract::Extension;
+    use axum::middleware::from_fn;
+    use axum::routing::get;
+    use axum::Router;
+    use tower::ServiceExt;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn request_id_is_returned() {
+        let router = Router::new()
+            .route(
+                "/v1/info",
+                get(|Extension(RequestId(id)): Extension<RequestId>| async move { id.to_string() }),
+            )
+            .layer(from_fn(request_id_middleware));
+
+        let request = Request::get("/v1/info").body(Body::empty()).unwrap();
+        let response = router.clone().oneshot(request).await.unwrap();
+
+        let header = response.headers()[X_REQUEST_ID]
+            .to_str()
+            .unwrap()
+            .to_owned();
+        let body = axum::body::to_bytes(response.into_body(), 1024)
+            .await
+            .unwrap();
+        assert_eq!(header, String::from_utf8(body.to_vec()).unwrap());
+
+        // Each request gets its own id
+        let request = Request::get("/v1/info").body(Body::empty()).unwrap();
+        let response = router.oneshot(request).await.unwrap();
+        assert_ne!(response.headers()[X_REQUEST_ID].to_str().unwrap(), header);
+    }
+}
@@ -1,14 +1,16 @@
 use anyhow::Result;
 use axum::extract::ws::WebSocketUpgrade;
 use axum::extract::{Json, Path, State};
-use axum::http::StatusCode;
+use axum::http::{header, StatusCode};
 use axum::response::{IntoResponse, Response};
 use cdk::error::ErrorResponse;
+use cdk::mint::QuoteId;
 #[cfg(feature = "auth")]
 use cdk::nuts::nut21::{Method, ProtectedEndpoint, RoutePath};
 use cdk::nuts::{
     CheckStateRequest, CheckStateResponse, Id, KeysResponse, KeysetResponse, MintInfo,
-    RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
+    MintQuotesRequest, MintQuotesResponse, RestoreRequest, RestoreResponse, SwapRequest,
+    SwapResponse,
 };
 use cdk::util::unix_time;
 use paste::paste;
@@ -16,25 +18,35 @@ use tracing::instrument;
 
 #[cfg(feature = "auth")]
 use crate::auth::AuthHeader;
+use crate::idempotency::{run_once, IdempotencyKey};
 use crate::ws::main_websocket;
 use crate::MintState;
 
 /// Macro to add cache to endpoint
 #[macro_export]
 macro_rules! post_cache_wrapper {
-    ($handler:ident, $request_type:ty, $response_type:ty) => {
+    ($handler:ident, $endpoint:expr, $request_type:ty, $response_type:ty) => {
         paste! {
             /// Cache wrapper function for $handler:
             /// Wrap $handler into a function that caches responses using the request as key
             pub async fn [<cache_ $handler>](
                 #[cfg(feature = "auth")] auth: AuthHeader,
+                idempotency_key: IdempotencyKey,
                 state: State<MintState>,
                 payload: Json<$request_type>
             ) -> Result<Json<$response_type>, Response> {
                 use std::ops::Deref;
-                let json_extracted_payload = payload.deref();
+
                 let State(mint_state) = state.clone();
-                let cache_key = match mint_state.cache.calculate_key(&json_extracted_payload) {
+                if let Some(key) = idempotency_key.cache_key(&mint_state.cache, $endpoint, payload.deref()) {
+                    #[cfg(feature = "auth")]
+                    return run_once(&mint_state.cache, key, $handler(auth, state, payload)).await;
+                    #[cfg(not(feature = "auth"))]
+                    return run_once(&mint_state.cache, key, $handler(state, payload)).await;
+                }
+
+                let json_extracted_payload = payload.deref();
+                let cache_key = match mint_state.cache.calculate_key($endpoint, &json_extracted_payload) {
                     Some(key) => key,
                     None => {
                         // Could not calculate key, just return the handler result
@@ -61,21 +73,29 @@ macro_rules! post_cache_wrapper {
 /// Macro to add cache to endpoint with prefer header support (for async operations)
 #[macro_export]
 macro_rules! post_cache_wrapper_with_prefer {
-    ($handler:ident, $request_type:ty, $response_type:ty) => {
+    ($handler:ident, $endpoint:expr, $request_type:ty, $response_type:ty) => {
         paste! {
             /// Cache wrapper function for $handler with PreferHeader support:
             /// Wrap $handler into a function that caches responses using the request as key
             pub async fn [<cache_ $handler>](
                 #[cfg(feature = "auth")] auth: AuthHeader,
+                idempotency_key: IdempotencyKey,
                 prefer: PreferHeader,
                 state: State<MintState>,
                 payload: Json<$request_type>
             ) -> Result<Json<$response_type>, Response> {
                 use std::ops::Deref;
 
-                let json_extracted_payload = payload.deref();
                 let State(mint_state) = state.clone();
-                let cache_key = match mint_state.cache.calculate_key(&json_extracted_payload) {
+                if let Some(key) = idempotency_key.cache_key(&mint_state.cache, $endpoint, payload.deref()) {
+                    #[cfg(feature = "auth")]
+                    return run_once(&mint_state.cache, key, $handler(auth, prefer, state, payload)).await;
+                    #[cfg(not(feature = "auth"))]
+                    return run_once(&mint_state.cache, key, $handler(prefer, state, payload)).await;
+                }
+
+                let json_extracted_payload = payload.deref();
+                let cache_key = match mint_state.cache.calculate_key($endpoint, &json_extracted_payload) {
                     Some(key) => key,
                     None => {
                         // Could not calculate key, just return the handler result
@@ -99,14 +119,15 @@ macro_rules! post_cache_wrapper_with_prefer {
     };
 }
 
-post_cache_wrapper!(post_swap, SwapRequest, SwapResponse);
+post_cache_wrapper!(post_swap, "/v1/swap", SwapRequest, SwapResponse);
 
 #[cfg_attr(feature = "swagger", utoipa::path(
     get,
     context_path = "/v1",
     path = "/keys",
     responses(
-        (status = 200, description = "Successful response", body = KeysResponse, content_type = "application/json")
+        (status = 200, description = "Successful response", body = KeysResponse, content_type = "application/json"),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Get the public keys of the newest mint keyset
@@ -128,7 +149,7 @@ pub(crate) async fn get_keys(
     ),
     responses(
         (status = 200, description = "Successful response", body = KeysResponse, content_type = "application/json"),
-        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Get the public keys of a specific keyset
@@ -153,7 +174,7 @@ pub(crate) async fn get_keyset_pubkeys(
     path = "/keysets",
     responses(
         (status = 200, description = "Successful response", body = KeysetResponse, content_type = "application/json"),
-        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Get all active keyset IDs of the mint
@@ -167,6 +188,16 @@ pub(crate) async fn get_keysets(
 }
 
 #[instrument(skip_all)]
+#[cfg_attr(feature = "swagger", utoipa::path(
+    get,
+    context_path = "/v1",
+    path = "/ws",
+    responses(
+        (status = 101, description = "Switching to a NUT-17 websocket, subscriptions are made with JSON-RPC `subscribe` and `unsubscribe` requests"),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
+    )
+))]
+/// Subscribe to state updates of quotes and proofs (NUT-17)
 pub(crate) async fn ws_handler(
     #[cfg(feature = "auth")] auth: AuthHeader,
     State(state): State<MintState>,
@@ -191,10 +222,18 @@ pub(crate) async fn ws_handler(
     post,
     context_path = "/v1",
     path = "/checkstate",
-    request_body(content = CheckStateRequest, description = "State params", content_type = "application/json"),
+    request_body(content = CheckStateRequest, description = "State params", content_type = "application/json", example = json!({
+        "Ys": ["02599b9ea0a1ad4143706c2a5a4a568ce442dd4313e1cf1f7f0b58a317c1a355ee"]
+    })),
     responses(
-        (status = 200, description = "Successful response", body = CheckStateResponse, content_type = "application/json"),
-        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+        (status = 200, description = "Successful response", body = CheckStateResponse, content_type = "application/json", example = json!({
+            "states": [{
+                "Y": "02599b9ea0a1ad4143706c2a5a4a568ce442dd4313e1cf1f7f0b58a317c1a355ee",
+                "state": "SPENT",
+                "witness": null
+            }]
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Check whether a proof is spent already or is pending in a transaction
@@ -257,10 +296,12 @@ pub(crate) async fn get_mint_info(
     post,
     context_path = "/v1",
     path = "/swap",
+    params(("Idempotency-Key" = Option<String>, Header, description = "Replays the response of the first request with this key, whatever the payload")),
     request_body(content = SwapRequest, description = "Swap params", content_type = "application/json"),
     responses(
         (status = 200, description = "Successful response", body = SwapResponse, content_type = "application/json"),
-        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+        (status = 409, description = "A request with the same `Idempotency-Key` is in progress"),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Swap inputs for outputs of the same value
@@ -302,10 +343,27 @@ pub(crate) async fn post_swap(
     post,
     context_path = "/v1",
     path = "/restore",
-    request_body(content = RestoreRequest, description = "Restore params", content_type = "application/json"),
+    request_body(content = RestoreRequest, description = "Restore params", content_type = "application/json", example = json!({
+        "outputs": [{
+            "amount": 2,
+            "id": "009a1f293253e41e",
+            "B_": "02634a2c2b34bec9e8a4aba4361f6bf202d7fa2365379b0840afe249a7a9d71239"
+        }]
+    })),
     responses(
-        (status = 200, description = "Successful response", body = RestoreResponse, content_type = "application/json"),
-        (status = 500, description = "Server error", body = ErrorResponse, content_type = "application/json")
+        (status = 200, description = "Successful response", body = RestoreResponse, content_type = "application/json", example = json!({
+            "outputs": [{
+                "amount": 2,
+                "id": "009a1f293253e41e",
+                "B_": "02634a2c2b34bec9e8a4aba4361f6bf202d7fa2365379b0840afe249a7a9d71239"
+            }],
+            "signatures": [{
+                "amount": 2,
+                "id": "009a1f293253e41e",
+                "C_": "0271bf0d702dbad86cbe0af3ab2bfba70a0338f22728e412d88a830ed0580b9de4"
+            }]
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 11001, "detail": "Token already spent"}))
     )
 ))]
 /// Restores blind signature for a set of outputs.
@@ -335,12 +393,258 @@ pub(crate) async fn post_restore(
     Ok(Json(restore_response))
 }
 
+#[cfg_attr(feature = "swagger", utoipa::path(
+    post,
+    context_path = "/v1",
+    path = "/mint/quotes",
+    request_body(content = MintQuotesRequest, description = "Pubkeys of the quotes, each signing the request", content_type = "application/json", example = json!({
+        "mint_url": "https://mint.example.com",
+        "timestamp": 1701704757,
+        "pubkeys": [{
+            "pubkey": "03d56ce4e446a85bbdaa547b4ec2b073d40ff802831352b8272b7dd7a4de5a7cac",
+            "signature": "d4b386f21f7aa7172f0994ee6e4dd966539484247ea71c99b81b8e09b1bb2acbc0026a43c221fd773471dc30d6a32b04692e6837ddaccf0830a63128308e4ee0"
+        }]
+    })),
+    responses(
+        (status = 200, description = "Successful response", body = MintQuotesResponse<String>, content_type = "application/json", example = json!({
+            "quotes": [{
+                "quote": "9d745270-1405-46de-b5c5-e2762b4f5e00",
+                "pubkey": "03d56ce4e446a85bbdaa547b4ec2b073d40ff802831352b8272b7dd7a4de5a7cac",
+                "method": "bolt11",
+                "request": "lnbc100n1pj4apw9pp5cfgcfy9k79u5ztvvp3yc2dwl9xrc0jz2jwmy7jsgk55ke7ps8yxqdqqcqzzsxqyz5vqsp5nt5txmqfxfs6ejmfhqe5nvqcgjg3tz4k8f5q5rrldjhyq3d3ltxq9qyyssqjg85cdvu8yanhujhx2ssvwsvrpyhk0z6yqq9wspf3z3ve6rk2qh0sycn7gplzpr3u3j3ahq0cnmc7wfqzqvmrjxmxzrq5g7q0fjzqpd8qhrl",
+                "unit": "sat",
+                "amount": 10,
+                "state": "PAID",
+                "amount_paid": 10,
+                "amount_issued": 0,
+                "expiry": 1701704757
+            }]
+        })),
+        (status = 400, description = "Cashu error with a NUT-00 error code", body = ErrorResponse, content_type = "application/json", example = json!({"code": 20008, "detail": "Quote signature invalid signature"})),
+        (status = 429, description = "Too many lookups from the client")
+    )
+))]
+/// Lists the mint quotes locked to some pubkeys, for wallets recovering their quotes
+///
+/// The request is authenticated by the signatures of the pubkeys, quotes without a pubkey are
+/// never listed.
+#[instrument(skip_all, fields(pubkeys = payload.pubkeys.len()))]
+pub(crate) async fn post_mint_quotes(
+    State(state): State<MintState>,
+    Json(payload): Json<MintQuotesRequest>,
+) -> Result<Json<MintQuotesResponse<QuoteId>>, Response> {
+    let quotes = state
+        .mint
+        .mint_quotes_by_pubkeys(&payload)
+        .await
+        .map_err(into_response)?;
+
+    Ok(Json(quotes))
+}
+
 #[instrument(skip_all)]
 pub(crate) fn into_response<T>(error: T) -> Response
 where
-    T: Into<ErrorResponse>,
+    T: Into<cdk::Error>,
 {
+    let error: cdk::Error = error.into();
+
+    // An overloaded payment backend is not an error of the request, the wallet should retry
+    if let cdk::Error::PaymentBackendBusy(retry_after) = error {
+        let err_response: ErrorResponse = error.into();
+        return (
+            StatusCode::SERVICE_UNAVAILABLE,
+            [(header::RETRY_AFTER, retry_after.to_string())],
+            Json(err_response),
+        )
+            .into_response();
+    }
+
     let err_response: ErrorResponse = error.into();
+    let code = err_response.code;
     // Per NUT-00 spec: "In case of an error, mints respond with the HTTP status code 400"
-    (StatusCode::BAD_REQUEST, Json(err_response)).into_response()
+    let mut response = (StatusCode::BAD_REQUEST, Json(err_response)).into_response();
+    // Read by the ban list to count double spends
+    response.extensions_mut().insert(code);
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use cdk::Error;
+
+    use super::*;
+
+    async fn response_parts(error: Error) -> (StatusCode, serde_json::Value) {
+        let response = into_response(error);
+        let status = response.status();
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX)
+            .await
+            .unwrap();
+
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    /// Every error with a NUT-00 code must reach the wallet with that code
+    #[tokio::test]
+    async fn test_error_code_table() {
+        let table: Vec<(Error, u16)> = vec![
+            // 10xxx
+            (Error::DHKE(cdk::dhke::Error::TokenNotVerified), 10001),
+            (Error::CouldNotVerifyDleq, 10001),
+            // 11xxx
+            (Error::TokenAlreadySpent, 11001),
+            (Error::TokenPending, 11002),
+            (Error::BlindedMessageAlreadySigned, 11003),
+            (Error::TransactionUnbalanced(10, 9, 0), 11005),
+            (Error::InsufficientFunds, 11005),
+            (Error::SplitValuesGreater, 11005),
+            (Error::AmountOverflow, 11005),
+            (Error::OverIssue, 11005),
+            (
+                Error::AmountOutofLimitRange(1.into(), 10.into(), 11.into()),
+                11006,
+            ),
+            (Error::DuplicateInputs, 11007),
+            (
+                Error::NUT00(cdk::nuts::nut00::Error::DuplicateProofs),
+                11007,
+            ),
+            (Error::DuplicateOutputs, 11008),
+            (Error::MultipleUnits, 11009),
+            (Error::UnitMismatch, 11010),
+            (Error::AmountLessNotAllowed, 11011),
+            (Error::InvoiceAmountUndefined, 11011),
+            (
+                Error::AmountlessInvoiceNotSupported(
+                    cdk::nuts::CurrencyUnit::Sat,
+                    cdk::nuts::PaymentMethod::Known(cdk::nuts::nut00::KnownMethod::Bolt11),
+                ),
+                11011,
+            ),
+            (Error::IncorrectQuoteAmount, 11012),
+            (Error::UnsupportedUnit, 11013),
+            (
+                Error::NUT05(cdk::nuts::nut05::Error::UnsupportedUnit),
+                11013,
+            ),
+            (
+                Error::Payment(cdk::cdk_payment::Error::UnsupportedUnit),
+                11013,
+            ),
+            (Error::MaxInputsExceeded(2, 1), 11014),
+            (Error::MaxOutputsExceeded(2, 1), 11015),
+            // 12xxx
+            (Error::UnknownKeySet, 12001),
+            (
+                Error::UnknownKeyset(vec![cdk::nuts::Id::from_bytes(&[0; 8]).unwrap()]),
+                12001,
+            ),
+            (Error::AmountKey, 12001),
+            (Error::InactiveKeyset, 12002),
+            (Error::NoActiveKeyset, 12002),
+            // 20xxx
+            (Error::UnpaidQuote, 20001),
+            (Error::IssuedQuote, 20002),
+            (Error::MintingDisabled, 20003),
+            (Error::MeltingDisabled, 20003),
+            (Error::PaymentFailed, 20004),
+            (Error::PendingQuote, 20005),
+            (Error::PaymentPending, 20005),
+            (
+                Error::Payment(cdk::cdk_payment::Error::InvoicePaymentPending),
+                20005,
+            ),
+            (Error::RequestAlreadyPaid, 20006),
+            (Error::PaidQuote, 20006),
+            (Error::DuplicatePaymentId, 20006),
+            (Error::Database(cdk::cdk_database::Error::Duplicate), 20006),
+            (
+                Error::Payment(cdk::cdk_payment::Error::InvoiceAlreadyPaid),
+                20006,
+            ),
+            (Error::ExpiredQuote(1, 2), 20007),
+            (Error::SignatureMissingOrInvalid, 20008),
+            (Error::SigAllUsedInMelt, 20008),
+            (Error::DuplicateSignatureError, 20008),
+            (
+                Error::NUT11(cdk::nuts::nut11::Error::SignaturesNotProvided),
+                20008,
+            ),
+            (Error::NUT14(cdk::nuts::nut14::Error::Preimage), 20008),
+            (Error::P2PKConditionsNotMet("locktime".to_string()), 20008),
+            (Error::PreimageNotProvided, 20008),
+            (Error::PubkeyRequired, 20009),
+            // 3xxxx
+            (Error::ClearAuthRequired, 30001),
+            (Error::ClearAuthFailed, 30002),
+            (Error::BlindAuthRequired, 31001),
+            (Error::BlindAuthFailed, 31002),
+            (Error::InsufficientBlindAuthTokens, 31003),
+            // No spec code
+            (Error::UnknownQuote, 50000),
+            (Error::Internal, 50000),
+            (
+                Error::Database(cdk::cdk_database::Error::Internal("db".to_string())),
+                50000,
+            ),
+        ];
+
+        for (error, code) in table {
+            let description = format!("{error:?}");
+            let detail = error.to_string();
+            let (status, body) = response_parts(error).await;
+
+            assert_eq!(status, StatusCode::BAD_REQUEST, "{description}");
+            assert_eq!(body["code"], code, "{description}");
+            assert!(body["detail"].is_string(), "{description}");
+            // Errors without a custom detail report their display string
+            if !matches!(code, 11005 | 20006 | 20008) {
+                assert_eq!(body["detail"], detail, "{description}");
+            }
+        }
+    }
+
+    /// Detail strings that wallets may already match on must not change
+    #[tokio::test]
+    async fn test_error_details_backwards_compatible() {
+        let (_, body) = response_parts(Error::RequestAlreadyPaid).await;
+        assert_eq!(body["detail"], "Invoice already paid.");
+
+        // The expected outputs are appended, the detail up to them is unchanged
+        let (_, body) = response_parts(Error::TransactionUnbalanced(10, 9, 0)).await;
+        assert_eq!(
+            body["detail"],
+            "Inputs: 10, Outputs: 9, expected_fee: 0. Transaction inputs should equal outputs less fee, expected outputs: 10"
+        );
+
+        let (_, body) = response_parts(Error::Database(cdk::cdk_database::Error::Duplicate)).await;
+        assert_eq!(body["detail"], "Invoice already paid or pending");
+
+        let (_, body) =
+            response_parts(Error::NUT11(cdk::nuts::nut11::Error::SignaturesNotProvided)).await;
+        assert_eq!(
+            body["detail"],
+            format!(
+                "{}. P2PK signatures are required but not provided",
+                cdk::nuts::nut11::Error::SignaturesNotProvided
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_busy_payment_backend_is_unavailable() {
+        let response = into_response(Error::PaymentBackendBusy(30));
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "30");
+    }
+
+    #[tokio::test]
+    async fn test_error_code_is_attached_to_response() {
+        let response = into_response(Error::TokenAlreadySpent);
+        assert_eq!(
+            response.extensions().get::<cdk::error::ErrorCode>(),
+            Some(&cdk::error::ErrorCode::TokenAlreadySpent)
+        );
+    }
 }
@@ -1,12 +1,15 @@
-use cdk::subscription::Params;
+use cdk::subscription::{limit_filters, Params};
 use cdk::ws::{WsResponseResult, WsSubscribeResponse};
 
 use super::{WsContext, WsError};
 
 /// The `handle` method is called when a client sends a subscription request
+///
+/// Filters that are invalid or beyond the mint's limit per subscription are left out of the
+/// subscription and listed as rejected in the response, a request with no filter left is refused.
 pub(crate) async fn handle(
     context: &mut WsContext,
-    params: Params,
+    mut params: Params,
 ) -> Result<WsResponseResult, WsError> {
     let sub_id = params.id.clone();
     if context.subscriptions.contains_key(&sub_id) {
@@ -15,6 +18,11 @@ pub(crate) async fn handle(
         return Err(WsError::InvalidParams);
     }
 
+    let rejected = limit_filters(&mut params, context.state.mint.max_subscription_filters());
+    if params.filters.is_empty() && !rejected.is_empty() {
+        return Err(WsError::InvalidParams);
+    }
+
     let mut subscription = context
         .state
         .mint
@@ -35,6 +43,7 @@ pub(crate) async fn handle(
     Ok(WsSubscribeResponse {
         status: "OK".to_string(),
         sub_id,
+        rejected,
     }
     .into())
 }
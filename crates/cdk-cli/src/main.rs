@@ -58,6 +58,10 @@ struct Cli {
     /// Currency unit to use for the wallet
     #[arg(short, long, default_value = "sat")]
     unit: String,
+    /// Reclaim proofs left pending by an interrupted send or melt before running the command.
+    /// Also releases the proofs reserved for sends prepared but not confirmed yet
+    #[arg(long)]
+    check_pending_on_startup: bool,
     /// NpubCash API URL
     #[cfg(feature = "npubcash")]
     #[arg(long, default_value = "https://npubx.cash")]
@@ -245,6 +249,16 @@ async fn main() -> Result<()> {
         }
     };
 
+    if let Err(err) = multi_mint_wallet.recover_interrupted_operations().await {
+        tracing::warn!("Could not recover interrupted operations: {}", err);
+    }
+
+    if args.check_pending_on_startup {
+        if let Err(err) = multi_mint_wallet.check_all_pending_proofs().await {
+            tracing::warn!("Could not check pending proofs: {}", err);
+        }
+    }
+
     match &args.command {
         Commands::DecodeToken(sub_command_args) => {
             sub_commands::decode_token::decode_token(sub_command_args)
@@ -24,9 +24,9 @@ pub async fn check_pending(multi_mint_wallet: &MultiMintWallet) -> Result<()> {
         );
 
         // Try to reclaim any proofs that are no longer pending
-        match wallet.reclaim_unspent(pending_proofs).await {
-            Ok(()) => println!("Successfully reclaimed pending proofs"),
-            Err(e) => println!("Error reclaimed pending proofs: {e}"),
+        match wallet.check_all_pending_proofs().await {
+            Ok(amount) => println!("Reclaimed {} {}", amount, wallet.unit),
+            Err(e) => println!("Error reclaiming pending proofs: {e}"),
         }
     }
     Ok(())
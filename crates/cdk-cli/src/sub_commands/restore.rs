@@ -1,12 +1,18 @@
 use anyhow::Result;
 use cdk::mint_url::MintUrl;
-use cdk::wallet::MultiMintWallet;
+use cdk::wallet::{MultiMintWallet, SeedRestoreOptions};
 use clap::Args;
 
 #[derive(Args)]
 pub struct RestoreSubCommand {
     /// Mint Url
     mint_url: MintUrl,
+    /// Number of counters checked with the mint per request
+    #[arg(long, default_value_t = 100)]
+    batch_size: u32,
+    /// Number of consecutive empty batches before a keyset is considered restored
+    #[arg(long, default_value_t = 3)]
+    gap_limit: u32,
 }
 
 pub async fn restore(
@@ -27,7 +33,23 @@ pub async fn restore(
         }
     };
 
-    let amount = wallet.restore().await?;
+    let options = SeedRestoreOptions {
+        batch_size: sub_command_args.batch_size,
+        gap_limit: sub_command_args.gap_limit,
+    };
+
+    let amount = wallet
+        .restore_with_options(options, |progress| {
+            println!(
+                "Keyset {} ({}/{}): checked up to counter {}, restored {}",
+                progress.keyset_id,
+                progress.keyset_index + 1,
+                progress.keyset_count,
+                progress.counter,
+                progress.restored
+            );
+        })
+        .await?;
 
     println!("Restored {amount}");
 
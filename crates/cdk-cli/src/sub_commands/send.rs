@@ -4,7 +4,7 @@ use anyhow::{anyhow, Result};
 use cdk::mint_url::MintUrl;
 use cdk::nuts::{Conditions, PublicKey, SpendingConditions};
 use cdk::wallet::types::SendKind;
-use cdk::wallet::{MultiMintWallet, SendMemo, SendOptions};
+use cdk::wallet::{MultiMintWallet, PreparedSend, SendMemo, SendOptions};
 use cdk::Amount;
 use clap::Args;
 
@@ -42,6 +42,12 @@ pub struct SendSubCommand {
     /// Include fee to redeem in token
     #[arg(short, long)]
     include_fee: bool,
+    /// Include the DLEQ proofs in the token, so the receiver can verify it offline
+    #[arg(long)]
+    include_dleq: bool,
+    /// Include the name and icon of the mint in the token memo
+    #[arg(long)]
+    mint_info_memo: bool,
     /// Amount willing to overpay to avoid a swap
     #[arg(short, long)]
     tolerance: Option<u64>,
@@ -255,12 +261,16 @@ pub async fn send(
     };
 
     let send_options = SendOptions {
-        memo: sub_command_args.memo.clone().map(|memo| SendMemo {
-            memo,
-            include_memo: true,
+        memo: (sub_command_args.memo.is_some() || sub_command_args.mint_info_memo).then(|| {
+            SendMemo {
+                memo: sub_command_args.memo.clone().unwrap_or_default(),
+                include_memo: sub_command_args.memo.is_some(),
+                include_mint_info: sub_command_args.mint_info_memo,
+            }
         }),
         send_kind,
         include_fee: sub_command_args.include_fee,
+        include_dleq: sub_command_args.include_dleq,
         conditions,
         ..Default::default()
     };
@@ -288,6 +298,10 @@ pub async fn send(
             .prepare_send(specific_mint, token_amount, multi_mint_options)
             .await?;
 
+        if send_options.include_fee {
+            print_gross_amount(&prepared);
+        }
+
         let memo = send_options.memo.clone();
         prepared.confirm(memo).await?
     } else {
@@ -311,6 +325,10 @@ pub async fn send(
             .prepare_send(best_mint, token_amount, multi_mint_options)
             .await?;
 
+        if send_options.include_fee {
+            print_gross_amount(&prepared);
+        }
+
         let memo = send_options.memo.clone();
         prepared.confirm(memo).await?
     };
@@ -328,3 +346,13 @@ pub async fn send(
 
     Ok(())
 }
+
+/// Show what the token holds when it includes the receiver's fee
+fn print_gross_amount(prepared: &PreparedSend) {
+    println!(
+        "Token value: {} ({} plus {} for the receiver's fee)",
+        prepared.gross_amount(),
+        prepared.amount(),
+        prepared.send_fee()
+    );
+}
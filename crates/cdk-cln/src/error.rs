@@ -17,6 +17,12 @@ pub enum Error {
     /// Invalid payment hash
     #[error("Invalid hash")]
     InvalidHash,
+    /// Invalid node pubkey
+    #[error("Invalid pubkey")]
+    InvalidPubkey,
+    /// No route to the destination
+    #[error("No route to destination")]
+    NoRoute,
     /// Cln Error
     #[error(transparent)]
     Cln(#[from] cln_rpc::Error),
@@ -35,6 +41,9 @@ pub enum Error {
     /// Database Error
     #[error("Database error: {0}")]
     Database(String),
+    /// Keysend payment has no label
+    #[error("Keysend payment has no label")]
+    KeysendLabelMissing,
 }
 
 impl From<Error> for cdk_common::payment::Error {
@@ -18,21 +18,21 @@ use cdk_common::database::DynKVStore;
 use cdk_common::nuts::{CurrencyUnit, MeltOptions, MeltQuoteState};
 use cdk_common::payment::{
     self, Bolt11IncomingPaymentOptions, Bolt12IncomingPaymentOptions,
-    CreateIncomingPaymentResponse, Event, IncomingPaymentOptions, MakePaymentResponse, MintPayment,
-    OutgoingPaymentOptions, PaymentIdentifier, PaymentQuoteResponse, SettingsResponse,
-    WaitPaymentResponse,
+    CreateIncomingPaymentResponse, CustomOutgoingPaymentOptions, Event, IncomingPaymentOptions,
+    KeysendSettings, MakePaymentResponse, MintPayment, OutgoingPaymentOptions, PaymentIdentifier,
+    PaymentQuoteResponse, SettingsResponse, WaitPaymentResponse, KEYSEND_METHOD,
 };
 use cdk_common::util::{hex, unix_time};
 use cdk_common::Bolt11Invoice;
 use cln_rpc::model::requests::{
-    DecodeRequest, FetchinvoiceRequest, InvoiceRequest, ListinvoicesRequest, ListpaysRequest,
-    OfferRequest, PayRequest, WaitanyinvoiceRequest,
+    DecodeRequest, FetchinvoiceRequest, GetrouteRequest, InvoiceRequest, KeysendRequest,
+    ListinvoicesRequest, ListpaysRequest, OfferRequest, PayRequest, WaitanyinvoiceRequest,
 };
 use cln_rpc::model::responses::{
-    DecodeResponse, ListinvoicesInvoices, ListinvoicesInvoicesStatus, ListpaysPaysStatus,
-    PayStatus, WaitanyinvoiceResponse, WaitanyinvoiceStatus,
+    DecodeResponse, KeysendStatus, ListinvoicesInvoices, ListinvoicesInvoicesStatus, ListpaysPays,
+    ListpaysPaysStatus, PayStatus, WaitanyinvoiceResponse, WaitanyinvoiceStatus,
 };
-use cln_rpc::primitives::{Amount as CLN_Amount, AmountOrAny, Sha256};
+use cln_rpc::primitives::{Amount as CLN_Amount, AmountOrAny, PublicKey, Sha256};
 use cln_rpc::ClnRpc;
 use error::Error;
 use futures::{Stream, StreamExt};
@@ -55,6 +55,7 @@ pub struct Cln {
     wait_invoice_cancel_token: CancellationToken,
     wait_invoice_is_active: Arc<AtomicBool>,
     kv_store: DynKVStore,
+    payment_timeout: Option<Duration>,
 }
 
 impl std::fmt::Debug for Cln {
@@ -79,14 +80,27 @@ impl Cln {
             wait_invoice_cancel_token: CancellationToken::new(),
             wait_invoice_is_active: Arc::new(AtomicBool::new(false)),
             kv_store,
+            payment_timeout: None,
         })
     }
+
+    /// Set the maximum time to spend on an outgoing payment
+    ///
+    /// Passed to CLN as `retry_for` and used by the mint to stop waiting.
+    pub fn with_payment_timeout(mut self, payment_timeout: Duration) -> Self {
+        self.payment_timeout = Some(payment_timeout);
+        self
+    }
 }
 
 #[async_trait]
 impl MintPayment for Cln {
     type Err = payment::Error;
 
+    fn payment_timeout(&self) -> Option<Duration> {
+        self.payment_timeout
+    }
+
     async fn get_settings(&self) -> Result<SettingsResponse, Self::Err> {
         use std::collections::HashMap;
         Ok(SettingsResponse {
@@ -97,7 +111,10 @@ impl MintPayment for Cln {
                 invoice_description: true,
             }),
             bolt12: Some(payment::Bolt12Settings { amountless: true }),
-            custom: HashMap::new(),
+            custom: HashMap::from([(
+                KEYSEND_METHOD.to_string(),
+                serde_json::to_string(&KeysendSettings { fee_probe: true })?,
+            )]),
         })
     }
 
@@ -312,8 +329,31 @@ impl MintPayment for Cln {
         options: OutgoingPaymentOptions,
     ) -> Result<PaymentQuoteResponse, Self::Err> {
         match options {
-            cdk_common::payment::OutgoingPaymentOptions::Custom(_) => {
-                Err(cdk_common::payment::Error::UnsupportedPaymentOption)
+            OutgoingPaymentOptions::Custom(custom_options) => {
+                let (destination, amount) = custom_options.keysend()?;
+                let amount = Amount::new(amount.into(), unit.clone());
+                let amount_msat = amount.convert_to(&CurrencyUnit::Msat)?;
+
+                // Probe for a route to estimate the routing fee
+                let routing_fee_msat = self
+                    .keysend_routing_fee(&destination.serialize(), amount_msat.value())
+                    .await?;
+                let routing_fee =
+                    Amount::new(routing_fee_msat, CurrencyUnit::Msat).convert_to(unit)?;
+
+                let absolute_fee_reserve: u64 = self.fee_reserve.min_fee_reserve.into();
+                let fee = max(routing_fee.value(), absolute_fee_reserve);
+
+                // The payment hash of a keysend is only known once it is sent, the payment is
+                // labelled so it can be looked up before that
+                let label = format!("cdk-keysend-{}", Uuid::new_v4());
+
+                Ok(PaymentQuoteResponse {
+                    request_lookup_id: Some(PaymentIdentifier::Label(label)),
+                    amount,
+                    fee: Amount::new(fee, unit.clone()),
+                    state: MeltQuoteState::Unpaid,
+                })
             }
             OutgoingPaymentOptions::Bolt11(bolt11_options) => {
                 // If we have specific amount options, use those
@@ -325,7 +365,7 @@ impl MintPayment for Cln {
                             if let Some(invoice_amount) =
                                 bolt11_options.bolt11.amount_milli_satoshis()
                             {
-                                if !invoice_amount == u64::from(amount_msat) {
+                                if invoice_amount != u64::from(amount_msat) {
                                     return Err(payment::Error::AmountMismatch);
                                 }
                             }
@@ -404,6 +444,10 @@ impl MintPayment for Cln {
         let mut partial_amount: Option<u64> = None;
         let mut amount_msat: Option<u64> = None;
 
+        if let OutgoingPaymentOptions::Custom(custom_options) = &options {
+            return self.pay_keysend(unit, custom_options).await;
+        }
+
         let mut cln_client = self.cln_client().await?;
 
         let invoice = match &options {
@@ -417,12 +461,21 @@ impl MintPayment for Cln {
                     match melt_options {
                         MeltOptions::Mpp { mpp } => partial_amount = Some(mpp.amount.into()),
                         MeltOptions::Amountless { amountless } => {
-                            amount_msat = Some(amountless.amount_msat.into());
+                            // CLN only accepts an explicit amount for zero-amount invoices
+                            if bolt11_options.bolt11.amount_milli_satoshis().is_none() {
+                                amount_msat = Some(amountless.amount_msat.into());
+                            }
                         }
                     }
                 }
 
-                max_fee_msat = bolt11_options.max_fee_amount.map(|a| a.into());
+                max_fee_msat = bolt11_options
+                    .max_fee_amount
+                    .map(|fee| {
+                        Amount::new(fee.into(), unit.clone()).convert_to(&CurrencyUnit::Msat)
+                    })
+                    .transpose()?
+                    .map(|fee| fee.value());
 
                 bolt11_options.bolt11.to_string()
             }
@@ -477,7 +530,13 @@ impl MintPayment for Cln {
 
                 self.check_outgoing_unpaided(&payment_identifier).await?;
 
-                max_fee_msat = bolt12_options.max_fee_amount.map(|a| a.into());
+                max_fee_msat = bolt12_options
+                    .max_fee_amount
+                    .map(|fee| {
+                        Amount::new(fee.into(), unit.clone()).convert_to(&CurrencyUnit::Msat)
+                    })
+                    .transpose()?
+                    .map(|fee| fee.value());
 
                 cln_response.invoice
             }
@@ -496,7 +555,9 @@ impl MintPayment for Cln {
                 label: None,
                 riskfactor: None,
                 maxfeepercent: None,
-                retry_for: None,
+                retry_for: self
+                    .payment_timeout
+                    .map(|timeout| u16::try_from(timeout.as_secs()).unwrap_or(u16::MAX)),
                 maxdelay: None,
                 exemptfee: None,
                 localinvreqid: None,
@@ -527,15 +588,33 @@ impl MintPayment for Cln {
                     }
                 };
 
+                // The completed payment in listpays holds the amount actually sent over all
+                // parts and attempts, the pay response is only used if it cannot be listed
+                let amount_sent_msat = match status {
+                    MeltQuoteState::Paid => {
+                        match self.list_pay(pay_response.payment_hash.as_ref()).await {
+                            Ok(Some(pay)) => pay.amount_sent_msat.map(|amount| amount.msat()),
+                            Ok(None) => None,
+                            Err(err) => {
+                                tracing::warn!(
+                                    "Could not list payment {}: {}",
+                                    pay_response.payment_hash,
+                                    err
+                                );
+                                None
+                            }
+                        }
+                    }
+                    _ => None,
+                }
+                .unwrap_or(pay_response.amount_sent_msat.msat());
+
                 MakePaymentResponse {
                     payment_lookup_id: payment_identifier,
                     payment_proof: Some(hex::encode(pay_response.payment_preimage.to_vec())),
                     status,
-                    total_spent: Amount::new(
-                        pay_response.amount_sent_msat.msat(),
-                        CurrencyUnit::Msat,
-                    )
-                    .convert_to(unit)?,
+                    total_spent: Amount::new(amount_sent_msat, CurrencyUnit::Msat)
+                        .convert_to(unit)?,
                 }
             }
             Err(err) => {
@@ -717,17 +796,16 @@ impl MintPayment for Cln {
             .invoices
             .iter()
             .filter(|p| p.status == ListinvoicesInvoicesStatus::PAID)
-            .filter(|p| p.amount_msat.is_some()) // Filter out invoices without an amount
-            .map(|p| WaitPaymentResponse {
-                payment_identifier: payment_identifier.clone(),
-                payment_amount: Amount::new(
-                    p.amount_msat
-                        // Safe to expect since we filtered for Some
-                        .expect("We have filter out those without amounts")
-                        .msat(),
-                    CurrencyUnit::Msat,
-                ),
-                payment_id: p.payment_hash.to_string(),
+            .filter_map(|p| {
+                // The amount received may differ from the invoice amount when the payer
+                // overpaid, the invoice amount is only a fallback
+                let amount_msat = p.amount_received_msat.or(p.amount_msat)?;
+
+                Some(WaitPaymentResponse {
+                    payment_identifier: payment_identifier.clone(),
+                    payment_amount: Amount::new(amount_msat.msat(), CurrencyUnit::Msat),
+                    payment_id: p.payment_hash.to_string(),
+                })
             })
             .collect())
     }
@@ -737,30 +815,17 @@ impl MintPayment for Cln {
         &self,
         payment_identifier: &PaymentIdentifier,
     ) -> Result<MakePaymentResponse, Self::Err> {
-        let mut cln_client = self.cln_client().await?;
-
-        let payment_hash = match payment_identifier {
-            PaymentIdentifier::PaymentHash(hash) => hash,
-            PaymentIdentifier::Bolt12PaymentHash(hash) => hash,
+        let pay = match payment_identifier {
+            PaymentIdentifier::PaymentHash(hash) => self.list_pay(hash).await?,
+            PaymentIdentifier::Bolt12PaymentHash(hash) => self.list_pay(hash).await?,
+            PaymentIdentifier::Label(label) => self.list_pay_by_label(label).await?,
             _ => {
                 tracing::error!("Unsupported identifier to check outgoing payment for cln.");
                 return Err(payment::Error::UnknownPaymentState);
             }
         };
 
-        let listpays_response = cln_client
-            .call_typed(&ListpaysRequest {
-                payment_hash: Some(*Sha256::from_bytes_ref(payment_hash)),
-                bolt11: None,
-                status: None,
-                start: None,
-                index: None,
-                limit: None,
-            })
-            .await
-            .map_err(Error::from)?;
-
-        match listpays_response.pays.first() {
+        match pay {
             Some(pays_response) => {
                 let status = cln_pays_status_to_mint_state(pays_response.status);
 
@@ -790,6 +855,53 @@ impl Cln {
         Ok(cln_rpc::ClnRpc::new(&self.rpc_socket).await?)
     }
 
+    /// Payment of `payment_hash` as listed by CLN
+    ///
+    /// A payment hash is listed once per payment attempt, the completed attempt is returned if
+    /// there is one, then a pending one.
+    async fn list_pay(&self, payment_hash: &[u8; 32]) -> Result<Option<ListpaysPays>, Error> {
+        let mut cln_client = self.cln_client().await?;
+
+        let listpays_response = cln_client
+            .call_typed(&ListpaysRequest {
+                payment_hash: Some(*Sha256::from_bytes_ref(payment_hash)),
+                bolt11: None,
+                status: None,
+                start: None,
+                index: None,
+                limit: None,
+            })
+            .await?;
+
+        Ok(select_pay(listpays_response.pays))
+    }
+
+    /// Payment labelled `label` as listed by CLN
+    ///
+    /// Used for keysend payments, whose payment hash is not known before they are sent.
+    async fn list_pay_by_label(&self, label: &str) -> Result<Option<ListpaysPays>, Error> {
+        let mut cln_client = self.cln_client().await?;
+
+        let listpays_response = cln_client
+            .call_typed(&ListpaysRequest {
+                payment_hash: None,
+                bolt11: None,
+                status: None,
+                start: None,
+                index: None,
+                limit: None,
+            })
+            .await?;
+
+        Ok(select_pay(
+            listpays_response
+                .pays
+                .into_iter()
+                .filter(|pay| pay.label.as_deref() == Some(label))
+                .collect(),
+        ))
+    }
+
     /// Get last pay index for cln
     async fn get_last_pay_index(&self) -> Result<Option<u64>, Error> {
         // First try to read from KV store
@@ -847,6 +959,101 @@ impl Cln {
             })
     }
 
+    /// Routing fee in msat of the cheapest route to the destination
+    #[instrument(skip(self))]
+    async fn keysend_routing_fee(
+        &self,
+        destination: &[u8],
+        amount_msat: u64,
+    ) -> Result<u64, Error> {
+        let mut cln_client = self.cln_client().await?;
+
+        let getroute_response = cln_client
+            .call_typed(&GetrouteRequest {
+                id: PublicKey::from_slice(destination).map_err(|_| Error::InvalidPubkey)?,
+                amount_msat: CLN_Amount::from_msat(amount_msat),
+                riskfactor: 10,
+                cltv: None,
+                fromid: None,
+                fuzzpercent: None,
+                maxhops: None,
+                exclude: None,
+            })
+            .await
+            .map_err(|err| {
+                tracing::warn!("Could not find route for keysend: {}", err);
+                Error::NoRoute
+            })?;
+
+        // The first hop carries the amount plus the fees of every later hop
+        let first_hop = getroute_response.route.first().ok_or(Error::NoRoute)?;
+
+        Ok(first_hop.amount_msat.msat().saturating_sub(amount_msat))
+    }
+
+    /// Pay a keysend payment
+    ///
+    /// The payment hash is only known once the payment is sent, so it is returned as the
+    /// lookup id.
+    #[instrument(skip_all)]
+    async fn pay_keysend(
+        &self,
+        unit: &CurrencyUnit,
+        options: &CustomOutgoingPaymentOptions,
+    ) -> Result<MakePaymentResponse, payment::Error> {
+        let (destination, amount) = options.keysend()?;
+        let Some(PaymentIdentifier::Label(label)) = options.keysend_request_lookup_id() else {
+            tracing::error!("Keysend payment has no label to pay under");
+            return Err(Error::KeysendLabelMissing.into());
+        };
+        let payment_identifier = PaymentIdentifier::Label(label.clone());
+
+        self.check_outgoing_unpaided(&payment_identifier).await?;
+
+        let amount_msat =
+            Amount::new(amount.into(), unit.clone()).convert_to(&CurrencyUnit::Msat)?;
+        let max_fee_msat = options
+            .max_fee_amount
+            .map(|fee| Amount::new(fee.into(), unit.clone()).convert_to(&CurrencyUnit::Msat))
+            .transpose()?;
+
+        let mut cln_client = self.cln_client().await?;
+
+        let cln_response = cln_client
+            .call_typed(&KeysendRequest {
+                destination: PublicKey::from_slice(&destination.serialize())
+                    .map_err(|_| Error::InvalidPubkey)?,
+                amount_msat: CLN_Amount::from_msat(amount_msat.value()),
+                maxfee: max_fee_msat.map(|fee| CLN_Amount::from_msat(fee.value())),
+                retry_for: self
+                    .payment_timeout
+                    .map(|timeout| u32::try_from(timeout.as_secs()).unwrap_or(u32::MAX)),
+                exemptfee: None,
+                extratlvs: None,
+                label: Some(label),
+                maxdelay: None,
+                maxfeepercent: None,
+                routehints: None,
+            })
+            .await
+            .map_err(|err| {
+                tracing::error!("Could not pay keysend: {}", err);
+                Error::ClnRpc(err)
+            })?;
+
+        let status = match cln_response.status {
+            KeysendStatus::COMPLETE => MeltQuoteState::Paid,
+        };
+
+        Ok(MakePaymentResponse {
+            payment_lookup_id: payment_identifier,
+            payment_proof: Some(hex::encode(cln_response.payment_preimage.to_vec())),
+            status,
+            total_spent: Amount::new(cln_response.amount_sent_msat.msat(), CurrencyUnit::Msat)
+                .convert_to(unit)?,
+        })
+    }
+
     /// Checks that outgoing payment is not already paid
     #[instrument(skip(self))]
     async fn check_outgoing_unpaided(
@@ -869,6 +1076,15 @@ impl Cln {
     }
 }
 
+/// Attempt that decides the outcome of a payment: the completed one, else a pending one
+fn select_pay(pays: Vec<ListpaysPays>) -> Option<ListpaysPays> {
+    pays.into_iter().max_by_key(|pay| match pay.status {
+        ListpaysPaysStatus::COMPLETE => 2,
+        ListpaysPaysStatus::PENDING => 1,
+        ListpaysPaysStatus::FAILED => 0,
+    })
+}
+
 fn cln_pays_status_to_mint_state(status: ListpaysPaysStatus) -> MeltQuoteState {
     match status {
         ListpaysPaysStatus::PENDING => MeltQuoteState::Pending,
@@ -934,3 +1150,36 @@ async fn fetch_invoice_by_payment_hash(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pay(status: &str, amount_sent_msat: u64) -> ListpaysPays {
+        serde_json::from_value(serde_json::json!({
+            "status": status,
+            "created_at": 1_700_000_000,
+            "payment_hash": "00".repeat(32),
+            "amount_msat": 10_000_000,
+            "amount_sent_msat": amount_sent_msat,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_select_pay_prefers_completed_attempt() {
+        let selected = select_pay(vec![
+            pay("failed", 10_002_000),
+            pay("complete", 10_001_010),
+            pay("failed", 10_003_000),
+        ])
+        .unwrap();
+        assert_eq!(selected.status, ListpaysPaysStatus::COMPLETE);
+        assert_eq!(selected.amount_sent_msat.unwrap().msat(), 10_001_010);
+
+        let selected = select_pay(vec![pay("failed", 0), pay("pending", 10_001_000)]).unwrap();
+        assert_eq!(selected.status, ListpaysPaysStatus::PENDING);
+
+        assert!(select_pay(Vec::new()).is_none());
+    }
+}
@@ -6,8 +6,8 @@ use crate::error::Error;
 use crate::mint_url::MintUrl;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
-    CurrencyUnit, MeltQuoteState, PaymentMethod, Proof, Proofs, PublicKey, SpendingConditions,
-    State,
+    CurrencyUnit, MeltQuoteFees, MeltQuoteState, PaymentMethod, Proof, Proofs, PublicKey,
+    SpendingConditions, State,
 };
 use crate::Amount;
 
@@ -24,6 +24,15 @@ pub struct Melted {
     pub amount: Amount,
     /// Fee paid
     pub fee_paid: Amount,
+    /// Fee breakdown reported by the mint
+    #[serde(default)]
+    pub fees: Option<MeltQuoteFees>,
+    /// Preimage hashes to the payment hash of the paid invoice
+    #[serde(default)]
+    pub preimage_verified: bool,
+    /// Amount spent by the melt as reported by the mint, the quote amount and the lightning fee
+    #[serde(default)]
+    pub amount_spent: Option<Amount>,
 }
 
 impl Melted {
@@ -62,6 +71,9 @@ impl Melted {
             change: change_proofs,
             amount: quote_amount,
             fee_paid,
+            fees: None,
+            preimage_verified: false,
+            amount_spent: None,
         })
     }
 
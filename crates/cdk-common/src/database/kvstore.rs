@@ -101,6 +101,36 @@ pub trait KVStoreTransaction<Error>: DbTransactionFinalizer<Err = Error> {
         primary_namespace: &str,
         secondary_namespace: &str,
     ) -> Result<Vec<String>, Error>;
+
+    /// Write value to key-value store only if the stored value is `expected`
+    ///
+    /// `expected` set to `None` means the key must not exist yet. Returns `false`, without
+    /// writing, if the stored value is not the expected one. Unlike [`Self::kv_write`], which is
+    /// last-write-wins, this is meant for cursors updated by concurrent writers.
+    ///
+    /// The default implementation reads and then writes in the transaction, backends should
+    /// override it if their transactions do not isolate that read.
+    async fn kv_compare_and_swap(
+        &mut self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+    ) -> Result<bool, Error> {
+        let current = self
+            .kv_read(primary_namespace, secondary_namespace, key)
+            .await?;
+
+        if current.as_deref() != expected {
+            return Ok(false);
+        }
+
+        self.kv_write(primary_namespace, secondary_namespace, key, value)
+            .await?;
+
+        Ok(true)
+    }
 }
 
 /// Key-Value Store Database trait
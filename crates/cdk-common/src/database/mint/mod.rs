@@ -12,8 +12,8 @@ use crate::mint::{
     self, MeltQuote, MintKeySetInfo, MintQuote as MintMintQuote, Operation, ProofsWithState,
 };
 use crate::nuts::{
-    BlindSignature, BlindedMessage, CurrencyUnit, Id, MeltQuoteState, Proof, Proofs, PublicKey,
-    State,
+    BlindSignature, BlindedMessage, CurrencyUnit, Id, Keys, MeltQuoteState, Proof, Proofs,
+    PublicKey, State, Witness,
 };
 use crate::payment::PaymentIdentifier;
 
@@ -63,6 +63,18 @@ pub trait KeysDatabaseTransaction<'a, Error>: DbTransactionFinalizer<Err = Error
 
     /// Add [`MintKeySetInfo`]
     async fn add_keyset_info(&mut self, keyset: MintKeySetInfo) -> Result<(), Error>;
+
+    /// Reserve the next derivation path index for a unit
+    ///
+    /// The reservation is persisted with the transaction and never moves backwards, so an index is
+    /// handed out at most once even if keysets are later removed or edited. The first index of a
+    /// unit is `0`.
+    async fn reserve_derivation_path_index(&mut self, unit: &CurrencyUnit) -> Result<u32, Error>;
+
+    /// Add the public [`Keys`] of a keyset
+    ///
+    /// Keys already stored for the keyset are kept, the public keys of a keyset never change.
+    async fn add_keyset_keys(&mut self, id: &Id, keys: &Keys) -> Result<(), Error>;
 }
 
 /// Mint Keys Database trait
@@ -87,6 +99,9 @@ pub trait KeysDatabase {
 
     /// Get [`MintKeySetInfo`]s
     async fn get_keyset_infos(&self) -> Result<Vec<MintKeySetInfo>, Self::Err>;
+
+    /// Get the public [`Keys`] stored for a keyset
+    async fn get_keyset_keys(&self, id: &Id) -> Result<Option<Keys>, Self::Err>;
 }
 
 /// Mint Quote Database writer trait
@@ -230,6 +245,15 @@ pub trait QuotesTransaction {
         new_request_lookup_id: &PaymentIdentifier,
     ) -> Result<(), Self::Err>;
 
+    /// Records the amount spent by a paid melt quote, the quote amount and the fee paid.
+    ///
+    /// Requires an [`Acquired`] melt quote to ensure the row is locked before modification.
+    async fn update_melt_quote_amount_spent(
+        &mut self,
+        quote: &mut Acquired<mint::MeltQuote>,
+        amount_spent: Amount<CurrencyUnit>,
+    ) -> Result<(), Self::Err>;
+
     /// Update [`mint::MeltQuote`] state.
     ///
     /// Requires an [`Acquired`] melt quote to ensure the row is locked before modification.
@@ -252,6 +276,12 @@ pub trait QuotesTransaction {
         &mut self,
         request_lookup_id: &PaymentIdentifier,
     ) -> Result<Option<Acquired<MintMintQuote>>, Self::Err>;
+
+    /// Record a melt settled internally against a mint quote
+    async fn add_internal_settlement(
+        &mut self,
+        settlement: &mint::InternalSettlement,
+    ) -> Result<(), Self::Err>;
 }
 
 /// Mint Quote Database trait
@@ -275,6 +305,11 @@ pub trait QuotesDatabase {
     ) -> Result<Option<MintMintQuote>, Self::Err>;
     /// Get Mint Quotes
     async fn get_mint_quotes(&self) -> Result<Vec<MintMintQuote>, Self::Err>;
+    /// Get the [`MintMintQuote`]s locked to `pubkey`
+    async fn get_mint_quotes_by_pubkey(
+        &self,
+        pubkey: &PublicKey,
+    ) -> Result<Vec<MintMintQuote>, Self::Err>;
     /// Get [`mint::MeltQuote`]
     async fn get_melt_quote(
         &self,
@@ -282,6 +317,18 @@ pub trait QuotesDatabase {
     ) -> Result<Option<mint::MeltQuote>, Self::Err>;
     /// Get all [`mint::MeltQuote`]s
     async fn get_melt_quotes(&self) -> Result<Vec<mint::MeltQuote>, Self::Err>;
+    /// Get all [`mint::MeltQuote`]s for a payment request
+    async fn get_melt_quotes_by_request(
+        &self,
+        request: &mint::MeltPaymentRequest,
+    ) -> Result<Vec<mint::MeltQuote>, Self::Err>;
+    /// Get the [`mint::InternalSettlement`] of a melt quote
+    async fn get_internal_settlement(
+        &self,
+        melt_quote_id: &QuoteId,
+    ) -> Result<Option<mint::InternalSettlement>, Self::Err>;
+    /// Get all [`mint::InternalSettlement`]s
+    async fn get_internal_settlements(&self) -> Result<Vec<mint::InternalSettlement>, Self::Err>;
 }
 
 /// Mint Proof Transaction trait
@@ -352,6 +399,13 @@ pub trait ProofsDatabase {
     ) -> Result<Vec<PublicKey>, Self::Err>;
     /// Get [`Proofs`] state
     async fn get_proofs_states(&self, ys: &[PublicKey]) -> Result<Vec<Option<State>>, Self::Err>;
+    /// Get [`Proofs`] state and witness in a single lookup
+    ///
+    /// Returns `None` for the ys the mint has never seen, in the order of `ys`.
+    async fn get_proofs_states_with_witness(
+        &self,
+        ys: &[PublicKey],
+    ) -> Result<Vec<Option<(State, Option<Witness>)>>, Self::Err>;
 
     /// Get [`Proofs`] by state
     async fn get_proofs_by_keyset_id(
@@ -388,6 +442,21 @@ pub trait SignaturesTransaction {
         &mut self,
         blinded_messages: &[PublicKey],
     ) -> Result<Vec<Option<BlindSignature>>, Self::Err>;
+
+    /// Get the signed outputs of a quote, grouped by the operation that issued them
+    ///
+    /// Each output is returned as its blinded message and [`BlindSignature`]. Outputs without an
+    /// operation id, signed before operations were tracked, are not returned.
+    async fn get_blind_signatures_for_quote_by_operation(
+        &mut self,
+        quote_id: &QuoteId,
+    ) -> Result<HashMap<uuid::Uuid, Vec<(PublicKey, BlindSignature)>>, Self::Err>;
+
+    /// Raise the total amount issued by a keyset to at least `amount`
+    ///
+    /// Repairs a counter that fell behind the stored signatures; it is never lowered.
+    async fn raise_total_issued(&mut self, keyset_id: &Id, amount: Amount)
+        -> Result<(), Self::Err>;
 }
 
 #[async_trait]
@@ -0,0 +1,112 @@
+//! Completed operations database tests
+
+use std::collections::HashMap;
+
+use cashu::nut00::KnownMethod;
+use cashu::{Amount, PaymentMethod};
+
+use crate::database::mint::{Database, Error};
+use crate::mint::{Operation, OperationKind};
+
+/// Test adding and retrieving completed operations of every kind
+pub async fn add_and_get_completed_operations<DB>(db: DB)
+where
+    DB: Database<Error>,
+{
+    let swap = Operation::new_swap(Amount::from(98), Amount::from(100), Amount::from(2));
+    let mint = Operation::new_mint(
+        Amount::from(1000),
+        PaymentMethod::Known(KnownMethod::Bolt11),
+    );
+    let mut melt = Operation::new_melt(
+        Amount::from(512),
+        Amount::from(1),
+        PaymentMethod::Known(KnownMethod::Bolt11),
+    );
+    melt.set_change_burnt(Amount::from(7));
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for operation in [&swap, &mint, &melt] {
+        tx.add_completed_operation(operation, &HashMap::new())
+            .await
+            .unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let retrieved = db
+        .get_completed_operation(swap.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(retrieved.id(), swap.id());
+    assert_eq!(retrieved.kind(), OperationKind::Swap);
+    assert_eq!(retrieved.total_issued(), Amount::from(98));
+    assert_eq!(retrieved.total_redeemed(), Amount::from(100));
+    assert_eq!(retrieved.fee_collected(), Amount::from(2));
+    assert!(retrieved.completed_at().is_some());
+
+    let retrieved = db
+        .get_completed_operation(mint.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(retrieved.kind(), OperationKind::Mint);
+    assert_eq!(retrieved.total_issued(), Amount::from(1000));
+    assert_eq!(retrieved.payment_method(), mint.payment_method());
+
+    let retrieved = db
+        .get_completed_operation(melt.id())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(retrieved.kind(), OperationKind::Melt);
+    assert_eq!(retrieved.total_redeemed(), Amount::from(512));
+    assert_eq!(retrieved.fee_collected(), Amount::from(1));
+    assert_eq!(retrieved.change_burnt(), Some(Amount::from(7)));
+
+    let melts = db
+        .get_completed_operations_by_kind(OperationKind::Melt)
+        .await
+        .unwrap();
+    assert!(melts.iter().any(|operation| operation.id() == melt.id()));
+    assert!(melts
+        .iter()
+        .all(|operation| operation.kind() == OperationKind::Melt));
+
+    let all = db.get_completed_operations().await.unwrap();
+    for operation in [&swap, &mint, &melt] {
+        assert!(all.iter().any(|stored| stored.id() == operation.id()));
+    }
+}
+
+/// Test retrieving a completed operation that doesn't exist
+pub async fn get_nonexistent_completed_operation<DB>(db: DB)
+where
+    DB: Database<Error>,
+{
+    let retrieved = db
+        .get_completed_operation(&uuid::Uuid::new_v4())
+        .await
+        .unwrap();
+    assert!(retrieved.is_none());
+}
+
+/// Test completed operation transaction rollback
+pub async fn completed_operation_rollback<DB>(db: DB)
+where
+    DB: Database<Error>,
+{
+    let swap = Operation::new_swap(Amount::from(10), Amount::from(10), Amount::ZERO);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_completed_operation(&swap, &HashMap::new())
+        .await
+        .unwrap();
+    tx.rollback().await.unwrap();
+
+    assert!(db
+        .get_completed_operation(swap.id())
+        .await
+        .unwrap()
+        .is_none());
+}
@@ -3,7 +3,7 @@
 use std::str::FromStr;
 
 use bitcoin::bip32::DerivationPath;
-use cashu::{CurrencyUnit, Id};
+use cashu::{Amount, CurrencyUnit, Id, Keys, SecretKey};
 
 use crate::database::mint::{Database, Error, KeysDatabase};
 use crate::mint::MintKeySetInfo;
@@ -284,3 +284,104 @@ where
     let active_id = db.get_active_keyset_id(&CurrencyUnit::Sat).await.unwrap();
     assert!(active_id.is_none());
 }
+
+/// Test reserving derivation path indexes
+pub async fn reserve_derivation_path_index<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    // The first index of a unit is 0
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    let index = tx
+        .reserve_derivation_path_index(&CurrencyUnit::Sat)
+        .await
+        .unwrap();
+    assert_eq!(index, 0);
+    tx.commit().await.unwrap();
+
+    // A rolled back reservation is not kept
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    let index = tx
+        .reserve_derivation_path_index(&CurrencyUnit::Sat)
+        .await
+        .unwrap();
+    assert_eq!(index, 1);
+    tx.rollback().await.unwrap();
+
+    // Stored keysets above the counter are never reused
+    let keyset_info = MintKeySetInfo {
+        id: Id::from_str("00916bbf7ef91a36").unwrap(),
+        unit: CurrencyUnit::Sat,
+        active: true,
+        valid_from: 0,
+        final_expiry: None,
+        derivation_path: DerivationPath::from_str("m/0'/0'/5'").unwrap(),
+        derivation_path_index: Some(5),
+        input_fee_ppk: 0,
+        amounts: standard_keyset_amounts(32),
+    };
+
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    tx.add_keyset_info(keyset_info).await.unwrap();
+    let index = tx
+        .reserve_derivation_path_index(&CurrencyUnit::Sat)
+        .await
+        .unwrap();
+    assert_eq!(index, 6);
+    tx.commit().await.unwrap();
+
+    // Counters are kept per unit
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    let index = tx
+        .reserve_derivation_path_index(&CurrencyUnit::Usd)
+        .await
+        .unwrap();
+    assert_eq!(index, 0);
+    let index = tx
+        .reserve_derivation_path_index(&CurrencyUnit::Sat)
+        .await
+        .unwrap();
+    assert_eq!(index, 7);
+    tx.commit().await.unwrap();
+}
+
+/// Test storing the public keys of a keyset
+pub async fn add_and_get_keyset_keys<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let keyset_id = Id::from_str("00916bbf7ef91a36").unwrap();
+    let keys = Keys::new(
+        (0..4)
+            .map(|n| {
+                (
+                    Amount::from(2u64.pow(n)),
+                    SecretKey::generate().public_key(),
+                )
+            })
+            .collect(),
+    );
+
+    assert!(db.get_keyset_keys(&keyset_id).await.unwrap().is_none());
+
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    tx.add_keyset_keys(&keyset_id, &keys).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(
+        db.get_keyset_keys(&keyset_id).await.unwrap(),
+        Some(keys.clone())
+    );
+
+    // The stored keys of a keyset are never replaced
+    let other_keys = Keys::new(
+        [(Amount::from(1), SecretKey::generate().public_key())]
+            .into_iter()
+            .collect(),
+    );
+    let mut tx = KeysDatabase::begin_transaction(&db).await.unwrap();
+    tx.add_keyset_keys(&keyset_id, &other_keys).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(db.get_keyset_keys(&keyset_id).await.unwrap(), Some(keys));
+}
@@ -645,8 +645,9 @@ where
             ),
         )
         .await;
-    // Expect a database error due to unique violation
-    assert!(result.is_err()); // Specific error might be DB-specific, e.g., SqliteError or PostgresError
+    // Expect the unique violation to surface as a duplicate, which the mint maps to
+    // BlindedMessageAlreadySigned
+    assert!(matches!(result, Err(Error::Duplicate)));
     tx.rollback().await.unwrap();
 }
 
@@ -821,7 +822,17 @@ where
     assert_eq!(old_state, MeltQuoteState::Pending);
     assert_eq!(quote.state, MeltQuoteState::Paid);
     // The payment proof is stored in the melt quote (verification depends on implementation)
+    tx.update_melt_quote_amount_spent(&mut quote, Amount::new(104, cashu::CurrencyUnit::Sat))
+        .await
+        .unwrap();
     tx.commit().await.unwrap();
+
+    // The amount spent is kept with the paid quote
+    let quote = db.get_melt_quote(&melt_quote.id).await.unwrap().unwrap();
+    assert_eq!(
+        quote.amount_spent,
+        Some(Amount::new(104, cashu::CurrencyUnit::Sat))
+    );
 }
 
 /// Test updating melt quote request lookup id
@@ -861,6 +872,152 @@ where
     assert_eq!(retrieved.request_lookup_id, Some(new_lookup_id));
 }
 
+/// Test that a melt of a mint's own invoice is still matched to the mint quote after the
+/// melt quote's request lookup id changes
+pub async fn internal_melt_lookup_after_request_lookup_id_change<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    use crate::database::mint::test::unique_string;
+
+    let bolt11 = "lnbc330n1p5d85skpp5344v3ktclujsjl3h09wgsfm7zytumr7h7zhrl857f5w8nv0a52zqdqqcqzzsxqyz5vqrzjqvueefmrckfdwyyu39m0lf24sqzcr9vcrmxrvgfn6empxz7phrjxvrttncqq0lcqqyqqqqlgqqqqqqgq2qsp5j3rrg8kvpemqxtf86j8tjm90wq77c7ende4e5qmrerq4xsg02vhq9qxpqysgqjltywgyk6uc5qcgwh8xnzmawl2tjlhz8d28tgp3yx8xwtz76x0jqkfh6mmq70hervjxs0keun7ur0spldgll29l0dnz3md50d65sfqqqwrwpsu";
+
+    let mint_quote = MintQuote::new(
+        None,
+        bolt11.to_owned(),
+        cashu::CurrencyUnit::Sat,
+        None,
+        0,
+        PaymentIdentifier::CustomId(unique_string()),
+        None,
+        Amount::new(0, cashu::CurrencyUnit::Sat),
+        Amount::new(0, cashu::CurrencyUnit::Sat),
+        cashu::PaymentMethod::Known(KnownMethod::Bolt11),
+        0,
+        vec![],
+        vec![],
+        None,
+    );
+
+    let old_lookup_id = PaymentIdentifier::CustomId(unique_string());
+    let melt_quote = MeltQuote::new(
+        MeltPaymentRequest::Bolt11 {
+            bolt11: bolt11.parse().unwrap(),
+        },
+        cashu::CurrencyUnit::Sat,
+        Amount::new(33, cashu::CurrencyUnit::Sat),
+        Amount::new(0, cashu::CurrencyUnit::Sat),
+        0,
+        Some(old_lookup_id.clone()),
+        None,
+        cashu::PaymentMethod::Known(KnownMethod::Bolt11),
+    );
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_mint_quote(mint_quote.clone()).await.unwrap();
+    tx.add_melt_quote(melt_quote.clone()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let new_lookup_id = PaymentIdentifier::CustomId(unique_string());
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    let mut quote = tx.get_melt_quote(&melt_quote.id).await.unwrap().unwrap();
+    tx.update_melt_quote_request_lookup_id(&mut quote, &new_lookup_id)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    // The melt quote is only found under its new lookup id
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert!(tx
+        .get_melt_quotes_by_request_lookup_id(&old_lookup_id)
+        .await
+        .unwrap()
+        .is_empty());
+    let by_lookup_id = tx
+        .get_melt_quotes_by_request_lookup_id(&new_lookup_id)
+        .await
+        .unwrap();
+    assert_eq!(by_lookup_id.len(), 1);
+    assert_eq!(by_lookup_id[0].id, melt_quote.id);
+
+    // Internal settlement detection matches on the request, not the lookup id
+    let internal = tx
+        .get_mint_quote_by_request(&by_lookup_id[0].request.to_string())
+        .await
+        .unwrap()
+        .expect("mint quote for the melt request");
+    assert_eq!(internal.id, mint_quote.id);
+    tx.commit().await.unwrap();
+
+    // The mint quote's own lookup id is untouched
+    let retrieved = db
+        .get_mint_quote_by_request_lookup_id(&mint_quote.request_lookup_id)
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(retrieved.id, mint_quote.id);
+}
+
+/// Test looking up the melt quotes of a payment request
+pub async fn get_melt_quotes_by_request<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    use crate::database::mint::test::unique_string;
+
+    let bolt11: MeltPaymentRequest = MeltPaymentRequest::Bolt11 {
+        bolt11: "lnbc330n1p5d85skpp5344v3ktclujsjl3h09wgsfm7zytumr7h7zhrl857f5w8nv0a52zqdqqcqzzsxqyz5vqrzjqvueefmrckfdwyyu39m0lf24sqzcr9vcrmxrvgfn6empxz7phrjxvrttncqq0lcqqyqqqqlgqqqqqqgq2qsp5j3rrg8kvpemqxtf86j8tjm90wq77c7ende4e5qmrerq4xsg02vhq9qxpqysgqjltywgyk6uc5qcgwh8xnzmawl2tjlhz8d28tgp3yx8xwtz76x0jqkfh6mmq70hervjxs0keun7ur0spldgll29l0dnz3md50d65sfqqqwrwpsu".parse().unwrap(),
+    };
+
+    let new_quote = |request: MeltPaymentRequest| {
+        MeltQuote::new(
+            request,
+            cashu::CurrencyUnit::Sat,
+            Amount::new(33, cashu::CurrencyUnit::Sat),
+            Amount::new(0, cashu::CurrencyUnit::Sat),
+            0,
+            Some(PaymentIdentifier::CustomId(unique_string())),
+            None,
+            cashu::PaymentMethod::Known(KnownMethod::Bolt11),
+        )
+    };
+
+    assert!(db
+        .get_melt_quotes_by_request(&bolt11)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let first = new_quote(bolt11.clone());
+    let second = new_quote(bolt11.clone());
+    let other = new_quote(MeltPaymentRequest::Custom {
+        method: "custom".to_string(),
+        request: unique_string(),
+    });
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_melt_quote(first.clone()).await.unwrap();
+    tx.add_melt_quote(second.clone()).await.unwrap();
+    tx.add_melt_quote(other.clone()).await.unwrap();
+    tx.commit().await.unwrap();
+
+    let mut ids: Vec<_> = db
+        .get_melt_quotes_by_request(&bolt11)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|quote| quote.id)
+        .collect();
+    ids.sort();
+    let mut expected = vec![first.id, second.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    let by_other = db.get_melt_quotes_by_request(&other.request).await.unwrap();
+    assert_eq!(by_other.len(), 1);
+    assert_eq!(by_other[0].id, other.id);
+}
+
 /// Test getting all mint quotes
 pub async fn get_all_mint_quotes<DB>(db: DB)
 where
@@ -915,6 +1072,61 @@ where
     assert!(all_quotes.iter().any(|q| q.id == quote2.id));
 }
 
+/// Test getting the mint quotes locked to a pubkey
+pub async fn get_mint_quotes_by_pubkey<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let pubkey = SecretKey::generate().public_key();
+    let quote = |pubkey| {
+        MintQuote::new(
+            None,
+            unique_string(),
+            cashu::CurrencyUnit::Sat,
+            None,
+            0,
+            PaymentIdentifier::CustomId(unique_string()),
+            pubkey,
+            Amount::new(0, cashu::CurrencyUnit::Sat),
+            Amount::new(0, cashu::CurrencyUnit::Sat),
+            cashu::PaymentMethod::Known(KnownMethod::Bolt11),
+            0,
+            vec![],
+            vec![],
+            None,
+        )
+    };
+    let locked1 = quote(Some(pubkey));
+    let locked2 = quote(Some(pubkey));
+    let other = quote(Some(SecretKey::generate().public_key()));
+    let unlocked = quote(None);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    for quote in [&locked1, &locked2, &other, &unlocked] {
+        tx.add_mint_quote(quote.clone()).await.unwrap();
+    }
+    tx.commit().await.unwrap();
+
+    let mut ids: Vec<_> = db
+        .get_mint_quotes_by_pubkey(&pubkey)
+        .await
+        .unwrap()
+        .into_iter()
+        .map(|quote| quote.id)
+        .collect();
+    ids.sort();
+    let mut expected = vec![locked1.id, locked2.id];
+    expected.sort();
+    assert_eq!(ids, expected);
+
+    let unused = SecretKey::generate().public_key();
+    assert!(db
+        .get_mint_quotes_by_pubkey(&unused)
+        .await
+        .unwrap()
+        .is_empty());
+}
+
 /// Test getting all melt quotes
 pub async fn get_all_melt_quotes<DB>(db: DB)
 where
@@ -1608,3 +1820,43 @@ where
     let retrieved = db.get_mint_quote(&mint_quote.id).await.unwrap().unwrap();
     assert_eq!(retrieved.amount_paid().value(), 100);
 }
+
+/// Record an internal settlement and read it back
+pub async fn add_and_get_internal_settlement<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    use crate::mint::InternalSettlement;
+
+    let settlement = InternalSettlement::new(
+        QuoteId::new_uuid(),
+        QuoteId::new_uuid(),
+        Amount::new(21, CurrencyUnit::Sat),
+    );
+
+    assert!(db
+        .get_internal_settlement(&settlement.melt_quote_id)
+        .await
+        .unwrap()
+        .is_none());
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_internal_settlement(&settlement).await.unwrap();
+    tx.commit().await.unwrap();
+
+    assert_eq!(
+        db.get_internal_settlement(&settlement.melt_quote_id)
+            .await
+            .unwrap(),
+        Some(settlement.clone())
+    );
+    assert_eq!(
+        db.get_internal_settlements().await.unwrap(),
+        vec![settlement.clone()]
+    );
+
+    // A melt quote can only be settled internally once
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    assert!(tx.add_internal_settlement(&settlement).await.is_err());
+    tx.rollback().await.unwrap();
+}
@@ -15,12 +15,14 @@ use super::*;
 use crate::database::KVStoreDatabase;
 use crate::mint::MintKeySetInfo;
 
+mod completed_operations;
 mod keys;
 mod mint;
 mod proofs;
 mod saga;
 mod signatures;
 
+pub use self::completed_operations::*;
 pub use self::keys::*;
 pub use self::mint::*;
 pub use self::proofs::*;
@@ -142,6 +144,58 @@ where
     }
 }
 
+/// Test KV store compare-and-swap, including concurrent writers expecting the same value
+pub async fn kvstore_compare_and_swap<DB>(db: DB)
+where
+    DB: Database<crate::database::Error> + KVStoreDatabase<Err = crate::database::Error>,
+{
+    let swap = |expected: Option<&'static [u8]>, value: &'static [u8]| {
+        let db = &db;
+        async move {
+            let mut tx = Database::begin_transaction(db).await?;
+            let swapped = tx
+                .kv_compare_and_swap("test_namespace", "cursors", "cursor", expected, value)
+                .await?;
+            if swapped {
+                tx.commit().await?;
+            } else {
+                tx.rollback().await?;
+            }
+            Ok::<_, crate::database::Error>(swapped)
+        }
+    };
+
+    // Only one of the writers creating the key wins
+    let (first, second) = tokio::join!(swap(None, b"1"), swap(None, b"2"));
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert!(first != second, "Exactly one writer should create the key");
+    let winner: &[u8] = if first { b"1" } else { b"2" };
+    assert_eq!(
+        db.kv_read("test_namespace", "cursors", "cursor")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(winner)
+    );
+
+    // A stale expected value doesn't overwrite
+    assert!(!swap(Some(b"0"), b"3").await.unwrap());
+    assert!(!swap(None, b"3").await.unwrap());
+
+    // Only one of the writers moving from the same value wins
+    let (first, second) = tokio::join!(swap(Some(winner), b"3"), swap(Some(winner), b"4"));
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert!(first != second, "Exactly one writer should move the cursor");
+    let winner: &[u8] = if first { b"3" } else { b"4" };
+    assert_eq!(
+        db.kv_read("test_namespace", "cursors", "cursor")
+            .await
+            .unwrap()
+            .as_deref(),
+        Some(winner)
+    );
+}
+
 static COUNTER: AtomicU64 = AtomicU64::new(0);
 
 /// Returns a unique, random-looking Base62 string (no external crates).
@@ -190,6 +244,7 @@ macro_rules! mint_db_test {
             add_and_find_proofs,
             add_duplicate_proofs,
             kvstore_functionality,
+            kvstore_compare_and_swap,
             add_mint_quote,
             add_mint_quote_only_once,
             register_payments,
@@ -209,15 +264,21 @@ macro_rules! mint_db_test {
             add_melt_quote_only_once,
             update_melt_quote_state_transition,
             update_melt_quote_request_lookup_id,
+            internal_melt_lookup_after_request_lookup_id_change,
+            get_melt_quotes_by_request,
             get_all_mint_quotes,
+            get_mint_quotes_by_pubkey,
             get_all_melt_quotes,
+            add_and_get_internal_settlement,
             get_mint_quote_by_request,
             get_mint_quote_by_request_lookup_id,
             delete_blinded_messages,
             add_and_get_blind_signatures,
             get_blind_signatures_for_keyset,
             get_blind_signatures_for_quote,
+            get_blind_signatures_for_quote_by_operation,
             get_total_issued,
+            raise_total_issued,
             get_nonexistent_blind_signatures,
             add_duplicate_blind_signatures,
             add_and_get_keyset_info,
@@ -228,7 +289,10 @@ macro_rules! mint_db_test {
             update_active_keyset,
             get_nonexistent_keyset_info,
             get_active_keyset_when_none_set,
+            reserve_derivation_path_index,
+            add_and_get_keyset_keys,
             get_proofs_states,
+            get_proofs_states_with_witness,
             get_nonexistent_proof_states,
             get_proofs_by_nonexistent_ys,
             proof_transaction_isolation,
@@ -258,6 +322,11 @@ macro_rules! mint_db_test {
             get_proofs_with_inconsistent_states_fails,
             get_proofs_fails_when_some_not_found,
             update_proofs_state_updates_proofs_with_state,
+            add_and_get_completed_operations,
+            get_nonexistent_completed_operation,
+            completed_operation_rollback,
+            add_spent_proofs_again_fails,
+            add_overlapping_proofs_concurrently,
         );
     };
     ($make_db_fn:ident, $($name:ident),+ $(,)?) => {
@@ -569,6 +569,67 @@ where
     // It's OK if state is None for some implementations
 }
 
+/// Test getting states and witnesses in a single lookup
+pub async fn get_proofs_states_with_witness<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    use cashu::{P2PKWitness, State, Witness};
+
+    let keyset_id = setup_keyset(&db).await;
+    let witness = Witness::P2PKWitness(P2PKWitness {
+        signatures: vec!["signature".to_string()],
+    });
+
+    let proofs = vec![
+        Proof {
+            amount: Amount::from(100),
+            keyset_id,
+            secret: Secret::generate(),
+            c: SecretKey::generate().public_key(),
+            witness: Some(witness.clone()),
+            dleq: None,
+        },
+        Proof {
+            amount: Amount::from(200),
+            keyset_id,
+            secret: Secret::generate(),
+            c: SecretKey::generate().public_key(),
+            witness: None,
+            dleq: None,
+        },
+    ];
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_proofs(
+        proofs.clone(),
+        None,
+        &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    let unknown = SecretKey::generate().public_key();
+    let ys = vec![proofs[1].y().unwrap(), unknown, proofs[0].y().unwrap()];
+
+    let states = db.get_proofs_states_with_witness(&ys).await.unwrap();
+    assert_eq!(
+        states,
+        vec![
+            Some((State::Unspent, None)),
+            None,
+            Some((State::Unspent, Some(witness))),
+        ]
+    );
+
+    assert!(db
+        .get_proofs_states_with_witness(&[])
+        .await
+        .unwrap()
+        .is_empty());
+}
+
 /// Test getting states for non-existent proofs
 pub async fn get_nonexistent_proof_states<DB>(db: DB)
 where
@@ -1011,3 +1072,116 @@ where
 
     tx.rollback().await.unwrap();
 }
+
+/// Test re-adding spent proofs fails with AttemptUpdateSpentProof instead of Duplicate
+pub async fn add_spent_proofs_again_fails<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    use cashu::State;
+
+    let keyset_id = setup_keyset(&db).await;
+
+    let proofs = vec![Proof {
+        amount: Amount::from(100),
+        keyset_id,
+        secret: Secret::generate(),
+        c: SecretKey::generate().public_key(),
+        witness: None,
+        dleq: None,
+    }];
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    let mut records = tx
+        .add_proofs(
+            proofs.clone(),
+            None,
+            &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+        )
+        .await
+        .unwrap();
+    tx.update_proofs_state(&mut records, State::Spent)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    let result = tx
+        .add_proofs(
+            proofs,
+            None,
+            &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+        )
+        .await;
+
+    assert!(
+        matches!(result, Err(Error::AttemptUpdateSpentProof)),
+        "Re-adding a spent proof should fail with AttemptUpdateSpentProof"
+    );
+
+    tx.rollback().await.unwrap();
+}
+
+/// Test two transactions adding overlapping proofs at the same time: only one of them may succeed
+pub async fn add_overlapping_proofs_concurrently<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error>,
+{
+    let keyset_id = setup_keyset(&db).await;
+
+    let new_proof = || Proof {
+        amount: Amount::from(100),
+        keyset_id,
+        secret: Secret::generate(),
+        c: SecretKey::generate().public_key(),
+        witness: None,
+        dleq: None,
+    };
+
+    let shared = new_proof();
+    let first = vec![shared.clone(), new_proof()];
+    let second = vec![shared.clone(), new_proof()];
+
+    let add = |proofs: Vec<Proof>| {
+        let db = &db;
+        async move {
+            let mut tx = Database::begin_transaction(db).await?;
+            match tx
+                .add_proofs(
+                    proofs,
+                    None,
+                    &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+                )
+                .await
+            {
+                Ok(_) => tx.commit().await,
+                Err(err) => {
+                    tx.rollback().await?;
+                    Err(err)
+                }
+            }
+        }
+    };
+
+    let (first_result, second_result) = tokio::join!(add(first.clone()), add(second.clone()));
+
+    assert!(
+        first_result.is_ok() != second_result.is_ok(),
+        "Exactly one transaction should add the shared proof: {first_result:?} {second_result:?}"
+    );
+
+    let (added, rejected) = if first_result.is_ok() {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    let added_ys: Vec<_> = added.iter().map(|p| p.y().unwrap()).collect();
+    let states = db.get_proofs_states(&added_ys).await.unwrap();
+    assert!(states.iter().all(|state| state.is_some()));
+
+    // Nothing of the rejected transaction is stored
+    let rejected_y = rejected[1].y().unwrap();
+    let states = db.get_proofs_states(&[rejected_y]).await.unwrap();
+    assert_eq!(states[0], None);
+}
@@ -168,6 +168,101 @@ where
     assert_eq!(sigs2[0].amount, sig2.amount);
 }
 
+/// Test getting the signed outputs of a quote grouped by operation
+pub async fn get_blind_signatures_for_quote_by_operation<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error> + MintSignaturesDatabase<Err = Error>,
+{
+    use cashu::nut00::KnownMethod;
+    use cashu::{BlindedMessage, PaymentMethod};
+
+    use crate::mint::Operation;
+
+    let keyset_id = Id::from_str("001711afb1de20cb").unwrap();
+    let quote_id = QuoteId::new_uuid();
+
+    let new_output = |amount: u64| {
+        (
+            BlindedMessage::new(
+                Amount::from(amount),
+                keyset_id,
+                SecretKey::generate().public_key(),
+            ),
+            BlindSignature {
+                amount: Amount::from(amount),
+                keyset_id,
+                c: SecretKey::generate().public_key(),
+                dleq: None,
+            },
+        )
+    };
+
+    // Two issuances for the same quote, plus an output that is reserved but not signed yet
+    let first = vec![new_output(1), new_output(2)];
+    let second = vec![new_output(4)];
+    let (pending, _) = new_output(8);
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    let mut operations = vec![];
+    for outputs in [&first, &second] {
+        let operation =
+            Operation::new_mint(Amount::ZERO, PaymentMethod::Known(KnownMethod::Bolt11));
+        let (messages, signatures): (Vec<_>, Vec<_>) = outputs.iter().cloned().unzip();
+        tx.add_blinded_messages(Some(&quote_id), &messages, &operation)
+            .await
+            .unwrap();
+        tx.add_blind_signatures(
+            &messages
+                .iter()
+                .map(|message| message.blinded_secret)
+                .collect::<Vec<_>>(),
+            &signatures,
+            Some(quote_id.clone()),
+        )
+        .await
+        .unwrap();
+        operations.push(*operation.id());
+    }
+    tx.add_blinded_messages(
+        Some(&quote_id),
+        &[pending],
+        &Operation::new_mint(Amount::ZERO, PaymentMethod::Known(KnownMethod::Bolt11)),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    let issued = tx
+        .get_blind_signatures_for_quote_by_operation(&quote_id)
+        .await
+        .unwrap();
+    assert_eq!(issued.len(), 2);
+
+    for (operation_id, outputs) in operations.iter().zip([&first, &second]) {
+        let mut stored = issued.get(operation_id).cloned().unwrap();
+        stored.sort_by_key(|(_, signature)| signature.amount);
+        let expected = outputs
+            .iter()
+            .map(|(message, signature)| (message.blinded_secret, signature.c))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            stored
+                .iter()
+                .map(|(message, signature)| (*message, signature.c))
+                .collect::<Vec<_>>(),
+            expected
+        );
+    }
+
+    assert!(tx
+        .get_blind_signatures_for_quote_by_operation(&QuoteId::new_uuid())
+        .await
+        .unwrap()
+        .is_empty());
+    tx.rollback().await.unwrap();
+}
+
 /// Test getting total issued by keyset
 pub async fn get_total_issued<DB>(db: DB)
 where
@@ -222,6 +317,58 @@ where
     assert!(total >= Amount::from(600));
 }
 
+/// Test raising the total issued counter of a keyset
+pub async fn raise_total_issued<DB>(db: DB)
+where
+    DB: Database<Error> + KeysDatabase<Err = Error> + MintSignaturesDatabase<Err = Error>,
+{
+    let keyset_id = Id::from_str("001711afb1de20cb").unwrap();
+
+    let sig = BlindSignature {
+        amount: Amount::from(100u64),
+        keyset_id,
+        c: SecretKey::generate().public_key(),
+        dleq: None,
+    };
+
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.add_blind_signatures(&[SecretKey::generate().public_key()], &[sig], None)
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    // Raising the counter sets it to the given amount
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.raise_total_issued(&keyset_id, Amount::from(500u64))
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let totals = db.get_total_issued().await.unwrap();
+    assert_eq!(totals.get(&keyset_id), Some(&Amount::from(500u64)));
+
+    // The counter is never lowered
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.raise_total_issued(&keyset_id, Amount::from(200u64))
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let totals = db.get_total_issued().await.unwrap();
+    assert_eq!(totals.get(&keyset_id), Some(&Amount::from(500u64)));
+
+    // A keyset without a counter gets one
+    let other_keyset_id = Id::from_str("00916bbf7ef91a36").unwrap();
+    let mut tx = Database::begin_transaction(&db).await.unwrap();
+    tx.raise_total_issued(&other_keyset_id, Amount::from(64u64))
+        .await
+        .unwrap();
+    tx.commit().await.unwrap();
+
+    let totals = db.get_total_issued().await.unwrap();
+    assert_eq!(totals.get(&other_keyset_id), Some(&Amount::from(64u64)));
+}
+
 /// Test retrieving non-existent blind signatures
 pub async fn get_nonexistent_blind_signatures<DB>(db: DB)
 where
@@ -282,6 +282,14 @@ pub enum Error {
     #[error("Missing placeholder value {0}")]
     MissingPlaceholder(String),
 
+    /// Database migrated by a newer version
+    #[error("Database schema version {0} is newer than the supported version {1}; it was written by a newer version and can't be downgraded")]
+    NewerSchemaVersion(u32, u32),
+
+    /// Inconsistent data found after migrating
+    #[error("Database integrity check failed: {0}")]
+    IntegrityCheck(String),
+
     /// Unknown quote ttl
     #[error("Unknown quote ttl")]
     UnknownQuoteTTL,
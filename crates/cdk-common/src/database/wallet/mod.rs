@@ -106,6 +106,16 @@ where
     /// Add transaction to storage (without transaction)
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), Err>;
 
+    /// Update the proofs in storage and record the [`Transaction`] that caused
+    /// the update in a single atomic write, so transaction history cannot
+    /// disagree with the balance
+    async fn update_proofs_with_transaction(
+        &self,
+        added: Vec<ProofInfo>,
+        removed_ys: Vec<PublicKey>,
+        transaction: Transaction,
+    ) -> Result<(), Err>;
+
     /// Update mint url (without transaction)
     async fn update_mint_url(
         &self,
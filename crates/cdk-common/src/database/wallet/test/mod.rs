@@ -18,7 +18,7 @@ use super::*;
 use crate::common::ProofInfo;
 use crate::mint_url::MintUrl;
 use crate::nuts::{Id, KeySetInfo, Keys, MintInfo, Proof, State};
-use crate::wallet::{MeltQuote, MintQuote, Transaction, TransactionDirection};
+use crate::wallet::{MeltQuote, MintQuote, Transaction, TransactionDirection, TransactionKind};
 
 static COUNTER: AtomicU64 = AtomicU64::new(0);
 
@@ -81,6 +81,7 @@ fn test_keyset_info(keyset_id: Id, _mint_url: &MintUrl) -> KeySetInfo {
         active: true,
         input_fee_ppk: 0,
         final_expiry: None,
+        max_amount: None,
     }
 }
 
@@ -128,6 +129,7 @@ fn test_melt_quote() -> MeltQuote {
         expiry: 9999999999,
         payment_preimage: None,
         payment_method: cashu::PaymentMethod::Known(KnownMethod::Bolt11),
+        fees: None,
     }
 }
 
@@ -148,6 +150,9 @@ fn test_transaction(mint_url: MintUrl, direction: TransactionDirection) -> Trans
         payment_request: None,
         payment_proof: None,
         payment_method: None,
+        kind: Some(TransactionKind::Receive),
+        preimage_verified: false,
+        amount_spent: None,
     }
 }
 
@@ -215,14 +220,63 @@ where
 {
     let old_url = test_mint_url();
     let new_url = test_mint_url_2();
+    let keyset_id = test_keyset_id();
 
     // Add mint with old URL
     db.add_mint(old_url.clone(), None).await.unwrap();
+    db.add_mint_keysets(old_url.clone(), vec![test_keyset_info(keyset_id, &old_url)])
+        .await
+        .unwrap();
+    let proof_info = test_proof_info(keyset_id, 100, old_url.clone());
+    db.update_proofs(vec![proof_info.clone()], vec![])
+        .await
+        .unwrap();
+    let quote = test_mint_quote(old_url.clone());
+    db.add_mint_quote(quote.clone()).await.unwrap();
+    let transaction = test_transaction(old_url.clone(), TransactionDirection::Incoming);
+    db.add_transaction(transaction.clone()).await.unwrap();
 
     // Update URL
     db.update_mint_url(old_url.clone(), new_url.clone())
         .await
         .unwrap();
+
+    let mints = db.get_mints().await.unwrap();
+    assert!(mints.contains_key(&new_url));
+    assert!(!mints.contains_key(&old_url));
+
+    let keysets = db.get_mint_keysets(new_url.clone()).await.unwrap().unwrap();
+    assert_eq!(keysets[0].id, keyset_id);
+    assert!(db
+        .get_mint_keysets(old_url.clone())
+        .await
+        .unwrap()
+        .is_none());
+
+    let proofs = db
+        .get_proofs(Some(new_url.clone()), None, None, None)
+        .await
+        .unwrap();
+    assert_eq!(proofs.len(), 1);
+    assert_eq!(proofs[0].y, proof_info.y);
+    assert!(db
+        .get_proofs(Some(old_url.clone()), None, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    let quote = db.get_mint_quote(&quote.id).await.unwrap().unwrap();
+    assert_eq!(quote.mint_url, new_url);
+
+    let transaction = db.get_transaction(transaction.id()).await.unwrap().unwrap();
+    assert_eq!(transaction.mint_url, new_url);
+
+    // Updating to the same URL keeps everything
+    db.update_mint_url(new_url.clone(), new_url.clone())
+        .await
+        .unwrap();
+    assert!(db.get_mints().await.unwrap().contains_key(&new_url));
+    assert!(db.get_mint_keysets(new_url).await.unwrap().is_some());
 }
 
 // =============================================================================
@@ -236,7 +290,8 @@ where
 {
     let mint_url = test_mint_url();
     let keyset_id = test_keyset_id();
-    let keyset_info = test_keyset_info(keyset_id, &mint_url);
+    let mut keyset_info = test_keyset_info(keyset_id, &mint_url);
+    keyset_info.max_amount = Some(Amount::from(65536));
 
     // Add mint first
     db.add_mint(mint_url.clone(), None).await.unwrap();
@@ -247,7 +302,9 @@ where
     // Get keyset by ID
     let retrieved = db.get_keyset_by_id(&keyset_id).await.unwrap();
     assert!(retrieved.is_some());
-    assert_eq!(retrieved.unwrap().id, keyset_id);
+    let retrieved = retrieved.unwrap();
+    assert_eq!(retrieved.id, keyset_id);
+    assert_eq!(retrieved.max_amount, Some(Amount::from(65536)));
 
     // Get keysets for mint
     let keysets = db.get_mint_keysets(mint_url).await.unwrap();
@@ -440,6 +497,32 @@ where
     assert!(!quotes.is_empty());
 }
 
+/// Test that the melt quote fee breakdown is persisted
+pub async fn add_and_get_melt_quote_with_fees<DB>(db: DB)
+where
+    DB: Database<crate::database::Error>,
+{
+    let mut quote = test_melt_quote();
+    quote.fees = Some(cashu::MeltQuoteFees {
+        ln_fee_reserve: Amount::from(10),
+        input_fee_ppk: 100,
+        ln_fee_paid: Some(Amount::from(4)),
+        internal: false,
+    });
+
+    db.add_melt_quote(quote.clone()).await.unwrap();
+
+    let retrieved = db.get_melt_quote(&quote.id).await.unwrap().unwrap();
+    assert_eq!(retrieved.fees, quote.fees);
+
+    // Clearing the breakdown is persisted as well
+    quote.fees = None;
+    db.add_melt_quote(quote.clone()).await.unwrap();
+
+    let retrieved = db.get_melt_quote(&quote.id).await.unwrap().unwrap();
+    assert_eq!(retrieved.fees, None);
+}
+
 /// Test getting melt quote in transaction
 pub async fn get_melt_quote_in_transaction<DB>(db: DB)
 where
@@ -749,7 +832,9 @@ where
     DB: Database<crate::database::Error>,
 {
     let mint_url = test_mint_url();
-    let transaction = test_transaction(mint_url.clone(), TransactionDirection::Incoming);
+    let mut transaction = test_transaction(mint_url.clone(), TransactionDirection::Incoming);
+    transaction.preimage_verified = true;
+    transaction.amount_spent = Some(Amount::from(1_005));
     let tx_id = transaction.id();
 
     // Add transaction
@@ -758,7 +843,10 @@ where
     // Get transaction
     let retrieved = db.get_transaction(tx_id).await.unwrap();
     assert!(retrieved.is_some());
-    assert_eq!(retrieved.unwrap().id(), tx_id);
+    let retrieved = retrieved.unwrap();
+    assert_eq!(retrieved.id(), tx_id);
+    assert!(retrieved.preimage_verified);
+    assert_eq!(retrieved.amount_spent, Some(Amount::from(1_005)));
 }
 
 /// Test listing transactions
@@ -792,6 +880,44 @@ where
     assert_eq!(outgoing.len(), 1);
 }
 
+/// Test updating proofs and recording a transaction together
+pub async fn update_proofs_with_transaction<DB>(db: DB)
+where
+    DB: Database<crate::database::Error>,
+{
+    let mint_url = test_mint_url();
+    let keyset_id = test_keyset_id();
+    let proof_info_1 = test_proof_info(keyset_id, 100, mint_url.clone());
+    let proof_info_2 = test_proof_info(keyset_id, 64, mint_url.clone());
+
+    db.update_proofs(vec![proof_info_1.clone()], vec![])
+        .await
+        .unwrap();
+
+    let mut transaction = test_transaction(mint_url.clone(), TransactionDirection::Outgoing);
+    transaction.ys = vec![proof_info_1.y];
+    transaction.kind = Some(TransactionKind::Swap);
+    let tx_id = transaction.id();
+
+    db.update_proofs_with_transaction(
+        vec![proof_info_2.clone()],
+        vec![proof_info_1.y],
+        transaction,
+    )
+    .await
+    .unwrap();
+
+    // Verify proofs
+    let proofs = db.get_proofs(None, None, None, None).await.unwrap();
+    assert_eq!(proofs.len(), 1);
+    assert_eq!(proofs[0].y, proof_info_2.y);
+
+    // Verify transaction, including its kind
+    let retrieved = db.get_transaction(tx_id).await.unwrap().unwrap();
+    assert_eq!(retrieved.kind, Some(TransactionKind::Swap));
+    assert_eq!(retrieved.direction, TransactionDirection::Outgoing);
+}
+
 /// Test filtering transactions by mint
 pub async fn filter_transactions_by_mint<DB>(db: DB)
 where
@@ -1039,6 +1165,7 @@ macro_rules! wallet_db_test {
             get_mint_quote_in_transaction,
             remove_mint_quote,
             add_and_get_melt_quote,
+            add_and_get_melt_quote_with_fees,
             get_melt_quote_in_transaction,
             remove_melt_quote,
             add_and_get_proofs,
@@ -1053,6 +1180,7 @@ macro_rules! wallet_db_test {
             keyset_counter_isolation,
             add_and_get_transaction,
             list_transactions,
+            update_proofs_with_transaction,
             filter_transactions_by_mint,
             remove_transaction,
             kvstore_write_and_read,
@@ -23,6 +23,18 @@ pub enum Error {
     /// Keyset is not known
     #[error("Keyset id not known: `{0}`")]
     KeysetUnknown(Id),
+    /// Active keyset was not derived from the configured seed
+    #[error("Active keyset `{0}` was not derived from the configured seed; refusing to start with a different seed")]
+    SeedKeysetMismatch(Id),
+    /// Active keyset was derived under another account than the configured one
+    #[error("Active keyset `{0}` was derived under account {1}, not the configured account {2}; the derivation account of an existing mint can't be changed")]
+    DerivationAccountMismatch(Id, u32, u32),
+    /// Payment backend does not work in the unit it is registered for
+    #[error("Payment backend for unit `{0}` and method `{1}` failed its self-check: {2}")]
+    PaymentBackendSelfCheck(CurrencyUnit, PaymentMethod, String),
+    /// Mint builder was misused
+    #[error(transparent)]
+    MintBuilder(#[from] MintBuilderError),
     /// Unsupported unit
     #[error("Unit unsupported")]
     UnsupportedUnit,
@@ -124,6 +136,9 @@ pub enum Error {
     /// Operation timeout
     #[error("Operation timeout")]
     Timeout,
+    /// Too many payment operations running against the payment backend
+    #[error("Payment backend is busy, retry in {0} seconds")]
+    PaymentBackendBusy(u64),
 
     /// BIP353 address resolution error
     #[error("Failed to resolve BIP353 address: {0}")]
@@ -160,6 +175,9 @@ pub enum Error {
     /// Amount is outside of allowed range
     #[error("Amount must be between `{0}` and `{1}` is `{2}`")]
     AmountOutofLimitRange(Amount, Amount, Amount),
+    /// Output amount is above the largest amount the mint signs
+    #[error("Output amount `{0}` is above the max amount `{1}`")]
+    OutputAmountNotAllowed(Amount, Amount),
     /// Quote is not paiud
     #[error("Quote not paid")]
     UnpaidQuote,
@@ -181,6 +199,9 @@ pub enum Error {
     /// Unknown Keyset
     #[error("Unknown Keyset")]
     UnknownKeySet,
+    /// Inputs reference keysets the mint never issued, e.g. proofs of another mint
+    #[error("Inputs reference unknown keysets: {}", .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(", "))]
+    UnknownKeyset(Vec<Id>),
     /// BlindedMessage is already signed
     #[error("Blinded Message is already signed")]
     BlindedMessageAlreadySigned,
@@ -196,12 +217,21 @@ pub enum Error {
     /// Duplicate output
     #[error("Duplicate outputs")]
     DuplicateOutputs,
+    /// Too many inputs in a single request
+    #[error("Too many inputs: `{0}`, maximum allowed: `{1}`")]
+    MaxInputsExceeded(usize, usize),
+    /// Too many outputs in a single request
+    #[error("Too many outputs: `{0}`, maximum allowed: `{1}`")]
+    MaxOutputsExceeded(usize, usize),
     /// Multiple units provided
     #[error("Cannot have multiple units")]
     MultipleUnits,
     /// Unit mismatch
     #[error("Input unit must match output")]
     UnitMismatch,
+    /// Melt change outputs reference more than one keyset
+    #[error("Change outputs must all use the same keyset")]
+    MultipleChangeKeysets,
     /// Sig all cannot be used in melt
     #[error("Sig all cannot be used in melt")]
     SigAllUsedInMelt,
@@ -257,6 +287,12 @@ pub enum Error {
     /// Token does not match wallet mint
     #[error("Token does not match wallet mint")]
     IncorrectMint,
+    /// New url of a mint does not serve the keysets known for its current url
+    #[error("Mint at {mint_url} does not serve the known keysets of the wallet mint")]
+    MintUrlKeysetMismatch {
+        /// New URL of the mint
+        mint_url: String,
+    },
     /// Receive can only be used with tokens from single mint
     #[error("Multiple mint tokens not supported by receive. Please deconstruct the token and use receive with_proof")]
     MultiMintTokenNotSupported,
@@ -280,7 +316,7 @@ pub enum Error {
         mint_url: String,
     },
     /// Transfer between mints timed out
-    #[error("Transfer timeout: failed to transfer {amount} from {source_mint} to {target_mint}")]
+    #[error("Transfer timeout: failed to transfer {amount} from {source_mint} to {target_mint}, resume with mint quote {mint_quote_id}")]
     TransferTimeout {
         /// Source mint URL
         source_mint: String,
@@ -288,6 +324,24 @@ pub enum Error {
         target_mint: String,
         /// Amount that failed to transfer
         amount: Amount,
+        /// Mint quote at the target mint that can be used to resume the transfer
+        mint_quote_id: String,
+    },
+    /// Transfer melt was paid but minting at the target mint failed
+    #[error("Transfer paid but minting at {target_mint} failed: {reason}, resume with mint quote {mint_quote_id}")]
+    TransferMintFailed {
+        /// Target mint URL
+        target_mint: String,
+        /// Mint quote at the target mint that can be used to resume the transfer
+        mint_quote_id: String,
+        /// Error returned by the mint operation
+        reason: String,
+    },
+    /// Transfer source and target are the same mint
+    #[error("Cannot transfer to the same mint: {mint_url}")]
+    SameMintTransfer {
+        /// Mint URL
+        mint_url: String,
     },
     /// Insufficient Funds
     #[error("Insufficient funds")]
@@ -298,6 +352,12 @@ pub enum Error {
     /// No active keyset
     #[error("No active keyset")]
     NoActiveKeyset,
+    /// Signatory can't derive announcement keys
+    #[error("Signatory does not support announcement keys")]
+    AnnouncementKeyUnsupported,
+    /// Announcement is not signed by a pubkey of the mint
+    #[error("Invalid announcement signature")]
+    InvalidAnnouncementSignature,
     /// Incorrect quote amount
     #[error("Incorrect quote amount")]
     IncorrectQuoteAmount,
@@ -307,6 +367,15 @@ pub enum Error {
     /// Invalid transaction direction
     #[error("Invalid transaction direction")]
     InvalidTransactionDirection,
+    /// Invalid transaction kind
+    #[error("Invalid transaction kind")]
+    InvalidTransactionKind,
+    /// Wallet backup is malformed or of an unsupported version
+    #[error("Invalid wallet backup: {0}")]
+    InvalidBackup(String),
+    /// Wallet backup could not be decrypted, wrong passphrase or corrupted data
+    #[error("Could not decrypt wallet backup")]
+    BackupDecryption,
     /// Invalid transaction id
     #[error("Invalid transaction id")]
     InvalidTransactionId,
@@ -354,6 +423,9 @@ pub enum Error {
     /// Http transport error
     #[error("Http transport error {0:?}: {1}")]
     HttpError(Option<u16>, String),
+    /// The mint rate limited the request, with the seconds to wait from `Retry-After`
+    #[error("Rate limited by the mint, retry after {0:?} seconds")]
+    RateLimited(Option<u64>),
     /// Parse invoice error
     #[cfg(feature = "mint")]
     #[error(transparent)]
@@ -435,6 +507,30 @@ pub enum Error {
     Payment(#[from] crate::payment::Error),
 }
 
+/// Misuse of the mint builder
+///
+/// Returned instead of building a mint that could not serve what it advertises.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum MintBuilderError {
+    /// Neither a payment backend nor a custom unit was added, so the mint has no unit
+    #[error("No payment backend nor custom unit was added")]
+    NoPaymentBackend,
+    /// A payment backend was already added for the unit and method
+    #[error("A payment backend was already added for unit `{0}` and method `{1}`")]
+    DuplicatePaymentBackend(CurrencyUnit, PaymentMethod),
+    /// The mint info advertises a unit and method no payment backend was added for
+    #[error(
+        "Unit `{0}` and method `{1}` are advertised but no payment backend was added for them"
+    )]
+    NoBackendForMethod(CurrencyUnit, PaymentMethod),
+    /// The seed to derive the keysets from is empty
+    #[error("The seed is empty")]
+    EmptySeed,
+    /// The payment concurrency limit allows no payment operation at all
+    #[error("The payment concurrency limit must allow at least one payment")]
+    ZeroPaymentConcurrency,
+}
+
 /// CDK Error Response
 ///
 /// See NUT definition in [00](https://github.com/cashubtc/nuts/blob/main/00.md)
@@ -442,9 +538,11 @@ pub enum Error {
 #[cfg_attr(feature = "swagger", derive(utoipa::ToSchema))]
 pub struct ErrorResponse {
     /// Error Code
+    #[cfg_attr(feature = "swagger", schema(value_type = u16, example = 11001))]
     pub code: ErrorCode,
     /// Human readable description
     #[serde(default)]
+    #[cfg_attr(feature = "swagger", schema(example = "Token already spent"))]
     pub detail: String,
 }
 
@@ -479,264 +577,245 @@ impl ErrorResponse {
     }
 }
 
-/// Maps NUT11 errors to appropriate error codes
-/// All NUT11 errors are witness/signature related, so they map to WitnessMissingOrInvalid (20008)
-fn map_nut11_error(_nut11_error: &crate::nuts::nut11::Error) -> ErrorCode {
-    // All NUT11 errors relate to P2PK/witness validation, which maps to 20008
-    ErrorCode::WitnessMissingOrInvalid
-}
+impl Error {
+    /// Output total the mint expected for an unbalanced transaction
+    ///
+    /// `None` for other errors and for unbalanced transactions that don't report their amounts.
+    pub fn expected_output_amount(&self) -> Option<Amount> {
+        match self {
+            Self::TransactionUnbalanced(inputs_total, _, fee_expected) if *inputs_total > 0 => {
+                inputs_total.checked_sub(*fee_expected).map(Amount::from)
+            }
+            _ => None,
+        }
+    }
 
-impl From<Error> for ErrorResponse {
-    fn from(err: Error) -> ErrorResponse {
-        match err {
-            Error::TokenAlreadySpent => ErrorResponse {
-                code: ErrorCode::TokenAlreadySpent,
-                detail: err.to_string(),
-            },
-            Error::UnsupportedUnit => ErrorResponse {
-                code: ErrorCode::UnsupportedUnit,
-                detail: err.to_string(),
-            },
-            Error::PaymentFailed => ErrorResponse {
-                code: ErrorCode::LightningError,
-                detail: err.to_string(),
-            },
-            Error::RequestAlreadyPaid => ErrorResponse {
-                code: ErrorCode::InvoiceAlreadyPaid,
-                detail: "Invoice already paid.".to_string(),
-            },
-            Error::TransactionUnbalanced(inputs_total, outputs_total, fee_expected) => {
-                ErrorResponse {
-                    code: ErrorCode::TransactionUnbalanced,
-                    detail: format!(
-                        "Inputs: {inputs_total}, Outputs: {outputs_total}, expected_fee: {fee_expected}. Transaction inputs should equal outputs less fee"
-                    ),
-                }
+    /// NUT-00 error code reported to clients for this error
+    ///
+    /// The match is deliberately exhaustive, so every new variant has to be assigned a code
+    /// instead of silently falling back to an unknown error.
+    pub fn error_code(&self) -> ErrorCode {
+        match self {
+            // 10xxx - Proof/Token verification errors
+            Self::DHKE(crate::dhke::Error::TokenNotVerified) | Self::CouldNotVerifyDleq => {
+                ErrorCode::TokenNotVerified
             }
-            Error::MintingDisabled => ErrorResponse {
-                code: ErrorCode::MintingDisabled,
-                detail: err.to_string(),
-            },
-            Error::BlindedMessageAlreadySigned => ErrorResponse {
-                code: ErrorCode::BlindedMessageAlreadySigned,
-                detail: err.to_string(),
-            },
-            Error::InsufficientFunds => ErrorResponse {
-                code: ErrorCode::TransactionUnbalanced,
-                detail: err.to_string(),
-            },
-            Error::AmountOutofLimitRange(_min, _max, _amount) => ErrorResponse {
-                code: ErrorCode::AmountOutofLimitRange,
-                detail: err.to_string(),
-            },
-            Error::ExpiredQuote(_, _) => ErrorResponse {
-                code: ErrorCode::QuoteExpired,
-                detail: err.to_string(),
-            },
-            Error::PendingQuote => ErrorResponse {
-                code: ErrorCode::QuotePending,
-                detail: err.to_string(),
-            },
-            Error::TokenPending => ErrorResponse {
-                code: ErrorCode::TokenPending,
-                detail: err.to_string(),
-            },
-            Error::ClearAuthRequired => ErrorResponse {
-                code: ErrorCode::ClearAuthRequired,
-                detail: Error::ClearAuthRequired.to_string(),
-            },
-            Error::ClearAuthFailed => ErrorResponse {
-                code: ErrorCode::ClearAuthFailed,
-                detail: Error::ClearAuthFailed.to_string(),
-            },
-            Error::BlindAuthRequired => ErrorResponse {
-                code: ErrorCode::BlindAuthRequired,
-                detail: Error::BlindAuthRequired.to_string(),
-            },
-            Error::BlindAuthFailed => ErrorResponse {
-                code: ErrorCode::BlindAuthFailed,
-                detail: Error::BlindAuthFailed.to_string(),
-            },
-            Error::NUT20(err) => ErrorResponse {
-                code: ErrorCode::WitnessMissingOrInvalid,
-                detail: err.to_string(),
-            },
-            Error::DuplicateInputs => ErrorResponse {
-                code: ErrorCode::DuplicateInputs,
-                detail: err.to_string(),
-            },
-            Error::DuplicateOutputs => ErrorResponse {
-                code: ErrorCode::DuplicateOutputs,
-                detail: err.to_string(),
-            },
-            Error::MultipleUnits => ErrorResponse {
-                code: ErrorCode::MultipleUnits,
-                detail: err.to_string(),
-            },
-            Error::UnitMismatch => ErrorResponse {
-                code: ErrorCode::UnitMismatch,
-                detail: err.to_string(),
-            },
-            Error::UnpaidQuote => ErrorResponse {
-                code: ErrorCode::QuoteNotPaid,
-                detail: Error::UnpaidQuote.to_string(),
-            },
-            Error::NUT11(err) => {
-                let code = map_nut11_error(&err);
-                let extra = if matches!(err, crate::nuts::nut11::Error::SignaturesNotProvided) {
-                    Some("P2PK signatures are required but not provided".to_string())
-                } else {
-                    None
-                };
-                ErrorResponse {
-                    code,
-                    detail: match extra {
-                        Some(extra) => format!("{err}. {extra}"),
-                        None => err.to_string(),
-                    },
-                }
-            },
-            Error::DuplicateSignatureError => ErrorResponse {
-                code: ErrorCode::WitnessMissingOrInvalid,
-                detail: err.to_string(),
-            },
-            Error::IssuedQuote => ErrorResponse {
-                code: ErrorCode::TokensAlreadyIssued,
-                detail: err.to_string(),
-            },
-            Error::UnknownKeySet => ErrorResponse {
-                code: ErrorCode::KeysetNotFound,
-                detail: err.to_string(),
-            },
-            Error::InactiveKeyset => ErrorResponse {
-                code: ErrorCode::KeysetInactive,
-                detail: err.to_string(),
-            },
-            Error::AmountLessNotAllowed => ErrorResponse {
-                code: ErrorCode::AmountlessInvoiceNotSupported,
-                detail: err.to_string(),
-            },
-            Error::IncorrectQuoteAmount => ErrorResponse {
-                code: ErrorCode::IncorrectQuoteAmount,
-                detail: err.to_string(),
-            },
-            Error::PubkeyRequired => ErrorResponse {
-                code: ErrorCode::PubkeyRequired,
-                detail: err.to_string(),
-            },
-            Error::PaidQuote => ErrorResponse {
-                code: ErrorCode::InvoiceAlreadyPaid,
-                detail: err.to_string(),
-            },
-            Error::DuplicatePaymentId => ErrorResponse {
-                code: ErrorCode::InvoiceAlreadyPaid,
-                detail: err.to_string(),
-            },
-            // Database duplicate error indicates another quote with same invoice is already pending/paid
-            Error::Database(crate::database::Error::Duplicate) => ErrorResponse {
-                code: ErrorCode::InvoiceAlreadyPaid,
-                detail: "Invoice already paid or pending".to_string(),
-            },
 
-            // DHKE errors - TokenNotVerified for actual verification failures
-            Error::DHKE(crate::dhke::Error::TokenNotVerified) => ErrorResponse {
-                code: ErrorCode::TokenNotVerified,
-                detail: err.to_string(),
-            },
-            Error::DHKE(_) => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
+            // 11xxx - Input/Output errors
+            Self::TokenAlreadySpent => ErrorCode::TokenAlreadySpent,
+            Self::TokenPending => ErrorCode::TokenPending,
+            Self::BlindedMessageAlreadySigned => ErrorCode::BlindedMessageAlreadySigned,
+            Self::TransactionUnbalanced(..)
+            | Self::InsufficientFunds
+            | Self::SplitValuesGreater
+            | Self::AmountOverflow
+            | Self::OverIssue => ErrorCode::TransactionUnbalanced,
+            Self::AmountOutofLimitRange(..) | Self::OutputAmountNotAllowed(..) => {
+                ErrorCode::AmountOutofLimitRange
+            }
+            Self::DuplicateInputs | Self::NUT00(crate::nuts::nut00::Error::DuplicateProofs) => {
+                ErrorCode::DuplicateInputs
+            }
+            Self::DuplicateOutputs => ErrorCode::DuplicateOutputs,
+            Self::MultipleUnits => ErrorCode::MultipleUnits,
+            Self::UnitMismatch => ErrorCode::UnitMismatch,
+            Self::AmountLessNotAllowed
+            | Self::AmountlessInvoiceNotSupported(..)
+            | Self::InvoiceAmountUndefined => ErrorCode::AmountlessInvoiceNotSupported,
+            Self::IncorrectQuoteAmount => ErrorCode::IncorrectQuoteAmount,
+            Self::UnsupportedUnit
+            | Self::NUT00(crate::nuts::nut00::Error::UnsupportedUnit)
+            | Self::NUT05(crate::nuts::nut05::Error::UnsupportedUnit) => ErrorCode::UnsupportedUnit,
+            #[cfg(feature = "mint")]
+            Self::Payment(crate::payment::Error::UnsupportedUnit) => ErrorCode::UnsupportedUnit,
+            Self::MaxInputsExceeded(..) => ErrorCode::MaxInputsExceeded,
+            Self::MaxOutputsExceeded(..) => ErrorCode::MaxOutputsExceeded,
 
-            // Verification errors
-            Error::CouldNotVerifyDleq => ErrorResponse {
-                code: ErrorCode::TokenNotVerified,
-                detail: err.to_string(),
-            },
-            Error::SignatureMissingOrInvalid => ErrorResponse {
-                code: ErrorCode::WitnessMissingOrInvalid,
-                detail: err.to_string(),
-            },
-            Error::SigAllUsedInMelt => ErrorResponse {
-                code: ErrorCode::WitnessMissingOrInvalid,
-                detail: err.to_string(),
-            },
+            // 12xxx - Keyset errors
+            Self::UnknownKeySet
+            | Self::UnknownKeyset(_)
+            | Self::AmountKey
+            | Self::KeysetUnknown(_) => ErrorCode::KeysetNotFound,
+            Self::InactiveKeyset | Self::NoActiveKeyset => ErrorCode::KeysetInactive,
 
-            // Keyset/key errors
-            Error::AmountKey => ErrorResponse {
-                code: ErrorCode::KeysetNotFound,
-                detail: err.to_string(),
-            },
-            Error::KeysetUnknown(_) => ErrorResponse {
-                code: ErrorCode::KeysetNotFound,
-                detail: err.to_string(),
-            },
-            Error::NoActiveKeyset => ErrorResponse {
-                code: ErrorCode::KeysetInactive,
-                detail: err.to_string(),
-            },
+            // 20xxx - Quote/Payment errors
+            Self::UnpaidQuote => ErrorCode::QuoteNotPaid,
+            Self::IssuedQuote => ErrorCode::TokensAlreadyIssued,
+            Self::MintingDisabled | Self::MeltingDisabled => ErrorCode::MintingDisabled,
+            Self::PaymentFailed => ErrorCode::LightningError,
+            Self::PendingQuote | Self::PaymentPending => ErrorCode::QuotePending,
+            #[cfg(feature = "mint")]
+            Self::Payment(crate::payment::Error::InvoicePaymentPending) => ErrorCode::QuotePending,
+            // A database duplicate means another quote with the same invoice is pending or paid
+            Self::RequestAlreadyPaid
+            | Self::PaidQuote
+            | Self::DuplicatePaymentId
+            | Self::Database(crate::database::Error::Duplicate) => ErrorCode::InvoiceAlreadyPaid,
+            #[cfg(feature = "mint")]
+            Self::Payment(crate::payment::Error::InvoiceAlreadyPaid) => {
+                ErrorCode::InvoiceAlreadyPaid
+            }
+            Self::ExpiredQuote(..) => ErrorCode::QuoteExpired,
+            // All spending condition and signature failures are witness errors
+            Self::NUT11(_)
+            | Self::NUT14(_)
+            | Self::NUT20(_)
+            | Self::SignatureMissingOrInvalid
+            | Self::SigAllUsedInMelt
+            | Self::DuplicateSignatureError
+            | Self::P2PKConditionsNotMet(_)
+            | Self::LocktimeNotProvided
+            | Self::InvalidSpendConditions(_)
+            | Self::PreimageNotProvided => ErrorCode::WitnessMissingOrInvalid,
+            Self::PubkeyRequired => ErrorCode::PubkeyRequired,
 
-            // Quote/payment errors
-            Error::UnknownQuote => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
-            Error::MeltingDisabled => ErrorResponse {
-                code: ErrorCode::MintingDisabled,
-                detail: err.to_string(),
-            },
-            Error::PaymentPending => ErrorResponse {
-                code: ErrorCode::QuotePending,
-                detail: err.to_string(),
-            },
-            Error::UnknownPaymentState => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
+            // 30xxx - Clear auth errors
+            Self::ClearAuthRequired => ErrorCode::ClearAuthRequired,
+            Self::ClearAuthFailed => ErrorCode::ClearAuthFailed,
+            #[cfg(feature = "auth")]
+            Self::NUT21(_) => ErrorCode::ClearAuthFailed,
 
-            // Transaction/amount errors
-            Error::SplitValuesGreater => ErrorResponse {
-                code: ErrorCode::TransactionUnbalanced,
-                detail: err.to_string(),
-            },
-            Error::AmountOverflow => ErrorResponse {
-                code: ErrorCode::TransactionUnbalanced,
-                detail: err.to_string(),
-            },
-            Error::OverIssue => ErrorResponse {
-                code: ErrorCode::TransactionUnbalanced,
-                detail: err.to_string(),
-            },
+            // 31xxx - Blind auth errors
+            Self::BlindAuthRequired => ErrorCode::BlindAuthRequired,
+            Self::BlindAuthFailed => ErrorCode::BlindAuthFailed,
+            #[cfg(feature = "auth")]
+            Self::NUT22(_) => ErrorCode::BlindAuthFailed,
+            Self::InsufficientBlindAuthTokens => ErrorCode::BatMintMaxExceeded,
 
-            // Invoice parsing errors - no spec code for invalid format
-            Error::InvalidPaymentRequest => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
-            Error::InvoiceAmountUndefined => ErrorResponse {
-                code: ErrorCode::AmountlessInvoiceNotSupported,
-                detail: err.to_string(),
-            },
+            // No spec code: internal, backend and wallet side errors
+            Self::DHKE(_)
+            | Self::NUT00(_)
+            | Self::NUT05(_)
+            | Self::Database(_)
+            | Self::UnknownQuote
+            | Self::UnknownPaymentState
+            | Self::InvalidPaymentRequest
+            | Self::InternalMultiPartMeltQuote
+            | Self::MppUnitMethodNotSupported(..)
+            | Self::AuthSettingsUndefined
+            | Self::MintTimeExceedsTolerance
+            | Self::AuthLocalstoreUndefined
+            | Self::CatNotSet
+            | Self::CouldNotGetMintInfo
+            | Self::InvalidPaymentMethod
+            | Self::AmountUndefined
+            | Self::UnsupportedPaymentMethod
+            | Self::Bolt12parse
+            | Self::InvalidInvoice
+            | Self::Bip353Parse(_)
+            | Self::Timeout
+            | Self::PaymentBackendBusy(_)
+            | Self::MultipleChangeKeysets
+            | Self::Bip353Resolve(_)
+            | Self::Bip353NoLightningOffer
+            | Self::LightningAddressParse(_)
+            | Self::LightningAddressRequest(_)
+            | Self::SendError(_)
+            | Self::RecvError(_)
+            | Self::Internal
+            | Self::SeedKeysetMismatch(_)
+            | Self::DerivationAccountMismatch(..)
+            | Self::PaymentBackendSelfCheck(..)
+            | Self::MintBuilder(_)
+            | Self::AnnouncementKeyUnsupported
+            | Self::InvalidAnnouncementSignature
+            | Self::OidcNotSet
+            | Self::IncorrectWallet(_)
+            | Self::MaxFeeExceeded
+            | Self::UrlPathSegments
+            | Self::UnknownErrorResponse(_)
+            | Self::DleqProofNotProvided
+            | Self::IncorrectMint
+            | Self::MintUrlKeysetMismatch { .. }
+            | Self::MultiMintTokenNotSupported
+            | Self::MultiMintCurrencyUnitMismatch { .. }
+            | Self::UnknownMint { .. }
+            | Self::TransferTimeout { .. }
+            | Self::TransferMintFailed { .. }
+            | Self::SameMintTransfer { .. }
+            | Self::UnexpectedProofState
+            | Self::InvoiceDescriptionUnsupported
+            | Self::InvalidTransactionDirection
+            | Self::InvalidTransactionKind
+            | Self::InvalidBackup(_)
+            | Self::BackupDecryption
+            | Self::InvalidTransactionId
+            | Self::TransactionNotFound
+            | Self::KVStoreInvalidKey(_)
+            | Self::InvalidMintResponse(_)
+            | Self::SubscriptionError(_)
+            | Self::Custom(_)
+            | Self::Invoice(_)
+            | Self::Bip32(_)
+            | Self::ParseInt(_)
+            | Self::UrlParseError(_)
+            | Self::Utf8ParseError(_)
+            | Self::SerdeJsonError(_)
+            | Self::Base64Error(_)
+            | Self::HexError(_)
+            | Self::HttpError(..)
+            | Self::RateLimited(_)
+            | Self::CashuUrl(_)
+            | Self::Secret(_)
+            | Self::AmountError(_)
+            | Self::NUT01(_)
+            | Self::NUT02(_)
+            | Self::NUT03(_)
+            | Self::NUT04(_)
+            | Self::NUT12(_)
+            | Self::NUT18(_)
+            | Self::NUT23(_)
+            | Self::TryFromSliceError(_) => ErrorCode::Unknown(50000),
+            #[cfg(feature = "wallet")]
+            Self::UnknownWallet(_) | Self::NUT13(_) => ErrorCode::Unknown(50000),
+            #[cfg(feature = "mint")]
+            Self::Uuid(_) | Self::QuoteId(_) | Self::Payment(_) => ErrorCode::Unknown(50000),
+        }
+    }
+}
 
-            // Internal/system errors - use Unknown(99999)
-            Error::Internal => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
-            Error::Database(_) => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
+impl From<Error> for ErrorResponse {
+    fn from(err: Error) -> ErrorResponse {
+        let code = err.error_code();
 
-            // Fallback for any remaining errors - use Unknown(99999) instead of TokenNotVerified
-            _ => ErrorResponse {
-                code: ErrorCode::Unknown(50000),
-                detail: err.to_string(),
-            },
-        }
+        // Details that predate the generic `Display` based ones are kept for compatibility
+        let detail = match &err {
+            Error::RequestAlreadyPaid => "Invoice already paid.".to_string(),
+            Error::TransactionUnbalanced(inputs_total, outputs_total, fee_expected) => {
+                let detail = format!(
+                    "Inputs: {inputs_total}, Outputs: {outputs_total}, expected_fee: {fee_expected}. Transaction inputs should equal outputs less fee"
+                );
+                match err.expected_output_amount() {
+                    Some(expected) => format!("{detail}, expected outputs: {expected}"),
+                    None => detail,
+                }
+            }
+            Error::NUT11(crate::nuts::nut11::Error::SignaturesNotProvided) => {
+                format!("{err}. P2PK signatures are required but not provided")
+            }
+            Error::Database(crate::database::Error::Duplicate) => {
+                "Invoice already paid or pending".to_string()
+            }
+            _ => err.to_string(),
+        };
+
+        ErrorResponse { code, detail }
     }
 }
 
+/// Amounts of the detail of a [`Error::TransactionUnbalanced`] response
+fn parse_transaction_unbalanced(detail: &str) -> Option<(u64, u64, u64)> {
+    let (inputs_total, rest) = detail.strip_prefix("Inputs: ")?.split_once(", Outputs: ")?;
+    let (outputs_total, rest) = rest.split_once(", expected_fee: ")?;
+    let fee_expected = rest.split(|c: char| !c.is_ascii_digit()).next()?;
+
+    Some((
+        inputs_total.parse().ok()?,
+        outputs_total.parse().ok()?,
+        fee_expected.parse().ok()?,
+    ))
+}
+
 #[cfg(feature = "mint")]
 impl From<crate::database::Error> for Error {
     fn from(db_error: crate::database::Error) -> Self {
@@ -769,12 +848,18 @@ impl From<ErrorResponse> for Error {
             ErrorCode::TokenPending => Self::TokenPending,
             ErrorCode::BlindedMessageAlreadySigned => Self::BlindedMessageAlreadySigned,
             ErrorCode::OutputsPending => Self::TokenPending, // Map to closest equivalent
-            ErrorCode::TransactionUnbalanced => Self::TransactionUnbalanced(0, 0, 0),
+            ErrorCode::TransactionUnbalanced => {
+                let (inputs_total, outputs_total, fee_expected) =
+                    parse_transaction_unbalanced(&err.detail).unwrap_or_default();
+                Self::TransactionUnbalanced(inputs_total, outputs_total, fee_expected)
+            }
             ErrorCode::AmountOutofLimitRange => {
                 Self::AmountOutofLimitRange(Amount::default(), Amount::default(), Amount::default())
             }
             ErrorCode::DuplicateInputs => Self::DuplicateInputs,
             ErrorCode::DuplicateOutputs => Self::DuplicateOutputs,
+            ErrorCode::MaxInputsExceeded => Self::MaxInputsExceeded(0, 0),
+            ErrorCode::MaxOutputsExceeded => Self::MaxOutputsExceeded(0, 0),
             ErrorCode::MultipleUnits => Self::MultipleUnits,
             ErrorCode::UnitMismatch => Self::UnitMismatch,
             ErrorCode::AmountlessInvoiceNotSupported => Self::AmountLessNotAllowed,
@@ -841,6 +926,10 @@ pub enum ErrorCode {
     IncorrectQuoteAmount,
     /// Unit in request is not supported (11013)
     UnsupportedUnit,
+    /// Too many inputs in request (11014)
+    MaxInputsExceeded,
+    /// Too many outputs in request (11015)
+    MaxOutputsExceeded,
 
     // 12xxx - Keyset errors
     /// Keyset is not known (12001)
@@ -908,6 +997,8 @@ impl ErrorCode {
             11011 => Self::AmountlessInvoiceNotSupported,
             11012 => Self::IncorrectQuoteAmount,
             11013 => Self::UnsupportedUnit,
+            11014 => Self::MaxInputsExceeded,
+            11015 => Self::MaxOutputsExceeded,
             // 12xxx - Keyset errors
             12001 => Self::KeysetNotFound,
             12002 => Self::KeysetInactive,
@@ -952,6 +1043,8 @@ impl ErrorCode {
             Self::AmountlessInvoiceNotSupported => 11011,
             Self::IncorrectQuoteAmount => 11012,
             Self::UnsupportedUnit => 11013,
+            Self::MaxInputsExceeded => 11014,
+            Self::MaxOutputsExceeded => 11015,
             // 12xxx - Keyset errors
             Self::KeysetNotFound => 12001,
             Self::KeysetInactive => 12002,
@@ -1003,3 +1096,36 @@ impl fmt::Display for ErrorCode {
         write!(f, "{}", self.to_code())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn transaction_unbalanced_keeps_amounts_over_the_wire() {
+        let response = ErrorResponse::from(Error::TransactionUnbalanced(2047, 2046, 2));
+        assert_eq!(response.code, ErrorCode::TransactionUnbalanced);
+        assert!(response.detail.ends_with("expected outputs: 2045"));
+
+        let err = Error::from(response);
+        assert!(matches!(err, Error::TransactionUnbalanced(2047, 2046, 2)));
+        assert_eq!(err.expected_output_amount(), Some(Amount::from(2045)));
+    }
+
+    #[test]
+    fn transaction_unbalanced_without_amounts() {
+        let err = Error::from(ErrorResponse::new(
+            ErrorCode::TransactionUnbalanced,
+            "unbalanced".to_string(),
+        ));
+        assert!(matches!(err, Error::TransactionUnbalanced(0, 0, 0)));
+        assert_eq!(err.expected_output_amount(), None);
+
+        // Detail of mints that predate the expected outputs
+        let err = Error::from(ErrorResponse::new(
+            ErrorCode::TransactionUnbalanced,
+            "Inputs: 10, Outputs: 10, expected_fee: 1. Transaction inputs should equal outputs less fee".to_string(),
+        ));
+        assert_eq!(err.expected_output_amount(), Some(Amount::from(9)));
+    }
+}
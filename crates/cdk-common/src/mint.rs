@@ -5,11 +5,12 @@ use std::ops::Deref;
 use std::str::FromStr;
 
 use bitcoin::bip32::DerivationPath;
+use cashu::nut17::InternalSettlementNotification;
 use cashu::quote_id::QuoteId;
 use cashu::util::unix_time;
 use cashu::{
     Bolt11Invoice, MeltOptions, MeltQuoteBolt11Response, MintQuoteBolt11Response,
-    MintQuoteBolt12Response, PaymentMethod, Proofs, State,
+    MintQuoteBolt12Response, MintQuoteStatus, PaymentMethod, Proofs, State,
 };
 use lightning::offers::offer::Offer;
 use serde::{Deserialize, Serialize};
@@ -286,6 +287,8 @@ pub struct Operation {
     payment_amount: Option<Amount>,
     /// Payment fee (only for melt operations)
     payment_fee: Option<Amount>,
+    /// Change owed but not signed (only for melt operations)
+    change_burnt: Option<Amount>,
     /// Payment method (only for mint/melt operations)
     payment_method: Option<PaymentMethod>,
 }
@@ -310,6 +313,7 @@ impl Operation {
             complete_at,
             payment_amount: None,
             payment_fee: None,
+            change_burnt: None,
             payment_method,
         }
     }
@@ -325,6 +329,7 @@ impl Operation {
             complete_at: None,
             payment_amount: None,
             payment_fee: None,
+            change_burnt: None,
             payment_method: Some(payment_method),
         }
     }
@@ -345,6 +350,7 @@ impl Operation {
             complete_at: None,
             payment_amount: None,
             payment_fee: None,
+            change_burnt: None,
             payment_method: Some(payment_method),
         }
     }
@@ -360,6 +366,7 @@ impl Operation {
             complete_at: None,
             payment_amount: None,
             payment_fee: None,
+            change_burnt: None,
             payment_method: None,
         }
     }
@@ -415,6 +422,19 @@ impl Operation {
         self.payment_fee = Some(payment_fee);
     }
 
+    /// Change owed but not signed (only for melt operations)
+    ///
+    /// Change is burnt when the wallet provides too few outputs or when the melt change
+    /// policy of the mint limits the change signed.
+    pub fn change_burnt(&self) -> Option<Amount> {
+        self.change_burnt
+    }
+
+    /// Set the change owed but not signed for melt operations
+    pub fn set_change_burnt(&mut self, change_burnt: Amount) {
+        self.change_burnt = Some(change_burnt);
+    }
+
     /// Payment method (only for mint/melt operations)
     pub fn payment_method(&self) -> Option<PaymentMethod> {
         self.payment_method.clone()
@@ -591,6 +611,30 @@ impl MintQuote {
         self.amount_issued.clone()
     }
 
+    /// Whether less than the quoted amount has been received for a bolt11 quote
+    ///
+    /// A bolt11 quote can only be minted in full, so it stays unpaid until the rest of the
+    /// payment is received.
+    pub fn is_partially_paid(&self) -> bool {
+        self.payment_method.is_bolt11()
+            && self.amount.as_ref().is_some_and(|amount| {
+                self.amount_paid.value() > 0 && self.amount_paid.value() < amount.value()
+            })
+    }
+
+    /// Amount received above the quoted amount of a bolt11 quote
+    ///
+    /// Only the quoted amount is issued, the excess is kept by the mint.
+    pub fn amount_overpaid(&self) -> Amount<CurrencyUnit> {
+        match &self.amount {
+            Some(amount) if self.payment_method.is_bolt11() => self
+                .amount_paid
+                .checked_sub(amount)
+                .unwrap_or_else(|_| Amount::new(0, self.unit.clone())),
+            _ => Amount::new(0, self.unit.clone()),
+        }
+    }
+
     /// Get state of mint quote
     #[instrument(skip(self))]
     pub fn state(&self) -> MintQuoteState {
@@ -687,6 +731,17 @@ impl MintQuote {
             return MintQuoteState::Unpaid;
         }
 
+        if self.payment_method.is_bolt11() {
+            if let Some(amount) = &self.amount {
+                if self.amount_issued.value() >= amount.value() {
+                    return MintQuoteState::Issued;
+                }
+                if self.amount_paid.value() < amount.value() {
+                    return MintQuoteState::Unpaid;
+                }
+            }
+        }
+
         match self.amount_paid.value().cmp(&self.amount_issued.value()) {
             std::cmp::Ordering::Less => {
                 tracing::error!("We should not have issued more then has been paid");
@@ -736,6 +791,46 @@ impl Issuance {
     }
 }
 
+/// Melt settled against a mint quote of the same mint, without a lightning payment
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+pub struct InternalSettlement {
+    /// Melt quote that was settled
+    pub melt_quote_id: QuoteId,
+    /// Mint quote that was paid by the melt
+    pub mint_quote_id: QuoteId,
+    /// Amount settled
+    pub amount: Amount<CurrencyUnit>,
+    /// Unix time of the settlement
+    pub created_time: u64,
+}
+
+impl InternalSettlement {
+    /// Create new [`InternalSettlement`] timestamped now
+    pub fn new(
+        melt_quote_id: QuoteId,
+        mint_quote_id: QuoteId,
+        amount: Amount<CurrencyUnit>,
+    ) -> Self {
+        Self {
+            melt_quote_id,
+            mint_quote_id,
+            amount,
+            created_time: unix_time(),
+        }
+    }
+}
+
+impl From<InternalSettlement> for InternalSettlementNotification<QuoteId> {
+    fn from(settlement: InternalSettlement) -> Self {
+        InternalSettlementNotification {
+            melt_quote: settlement.melt_quote_id,
+            unit: settlement.amount.unit().clone(),
+            amount: settlement.amount.into(),
+            time: settlement.created_time,
+        }
+    }
+}
+
 /// Melt Quote Info
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
 pub struct MeltQuote {
@@ -765,6 +860,8 @@ pub struct MeltQuote {
     pub created_time: u64,
     /// Unix time quote was paid
     pub paid_time: Option<u64>,
+    /// Amount spent by the paid quote, the quote amount and the fee paid
+    pub amount_spent: Option<Amount<CurrencyUnit>>,
     /// Payment method
     pub payment_method: PaymentMethod,
 }
@@ -797,6 +894,7 @@ impl MeltQuote {
             options,
             created_time: unix_time(),
             paid_time: None,
+            amount_spent: None,
             payment_method,
         }
     }
@@ -837,6 +935,7 @@ impl MeltQuote {
         options: Option<MeltOptions>,
         created_time: u64,
         paid_time: Option<u64>,
+        amount_spent: Option<u64>,
         payment_method: PaymentMethod,
     ) -> Self {
         Self {
@@ -844,7 +943,8 @@ impl MeltQuote {
             unit: unit.clone(),
             request,
             amount: Amount::new(amount, unit.clone()),
-            fee_reserve: Amount::new(fee_reserve, unit),
+            fee_reserve: Amount::new(fee_reserve, unit.clone()),
+            amount_spent: amount_spent.map(|amount| Amount::new(amount, unit)),
             state,
             expiry,
             payment_preimage,
@@ -895,12 +995,17 @@ impl From<MintKeySetInfo> for KeySetInfo {
             active: keyset_info.active,
             input_fee_ppk: keyset_info.input_fee_ppk,
             final_expiry: keyset_info.final_expiry,
+            max_amount: None,
         }
     }
 }
 
 impl From<MintQuote> for MintQuoteBolt11Response<QuoteId> {
     fn from(mint_quote: crate::mint::MintQuote) -> MintQuoteBolt11Response<QuoteId> {
+        let partial = mint_quote.is_partially_paid();
+        let amount_paid = (mint_quote.amount_paid.value() > 0)
+            .then(|| Amount::from(mint_quote.amount_paid.value()));
+
         MintQuoteBolt11Response {
             quote: mint_quote.id.clone(),
             state: mint_quote.state(),
@@ -909,6 +1014,8 @@ impl From<MintQuote> for MintQuoteBolt11Response<QuoteId> {
             pubkey: mint_quote.pubkey,
             amount: mint_quote.amount.map(Into::into),
             unit: Some(mint_quote.unit.clone()),
+            amount_paid,
+            partial,
         }
     }
 }
@@ -921,6 +1028,25 @@ impl From<MintQuote> for MintQuoteBolt11Response<String> {
     }
 }
 
+impl TryFrom<MintQuote> for MintQuoteStatus<QuoteId> {
+    type Error = crate::Error;
+
+    fn try_from(mint_quote: MintQuote) -> Result<Self, Self::Error> {
+        Ok(MintQuoteStatus {
+            quote: mint_quote.id.clone(),
+            pubkey: mint_quote.pubkey.ok_or(crate::Error::PubkeyRequired)?,
+            method: mint_quote.payment_method.clone(),
+            state: mint_quote.state(),
+            amount_paid: Amount::from(mint_quote.amount_paid.value()),
+            amount_issued: Amount::from(mint_quote.amount_issued.value()),
+            request: mint_quote.request,
+            unit: mint_quote.unit,
+            amount: mint_quote.amount.map(Into::into),
+            expiry: mint_quote.expiry,
+        })
+    }
+}
+
 impl TryFrom<crate::mint::MintQuote> for MintQuoteBolt12Response<QuoteId> {
     type Error = crate::Error;
 
@@ -987,6 +1113,8 @@ impl From<&MeltQuote> for MeltQuoteBolt11Response<QuoteId> {
             fee_reserve: melt_quote.fee_reserve().clone().into(),
             request: None,
             unit: Some(melt_quote.unit.clone()),
+            fees: None,
+            amount_spent: melt_quote.amount_spent.clone().map(Into::into),
         }
     }
 }
@@ -1003,6 +1131,8 @@ impl From<MeltQuote> for MeltQuoteBolt11Response<QuoteId> {
             change: None,
             request: Some(melt_quote.request.to_string()),
             unit: Some(melt_quote.unit.clone()),
+            fees: None,
+            amount_spent: melt_quote.amount_spent.map(Into::into),
         }
     }
 }
@@ -2,8 +2,10 @@
 
 use std::convert::Infallible;
 use std::pin::Pin;
+use std::str::FromStr;
 
 use async_trait::async_trait;
+use bitcoin::secp256k1::PublicKey;
 use cashu::util::hex;
 use cashu::{Bolt11Invoice, MeltOptions};
 #[cfg(feature = "prometheus")]
@@ -263,6 +265,41 @@ pub struct CustomOutgoingPaymentOptions {
     pub extra_json: Option<String>,
 }
 
+impl CustomOutgoingPaymentOptions {
+    /// Destination and amount of a [`KEYSEND_METHOD`] payment
+    pub fn keysend(&self) -> Result<(PublicKey, Amount), Error> {
+        if self.method != KEYSEND_METHOD {
+            return Err(Error::UnsupportedPaymentOption);
+        }
+
+        let destination = PublicKey::from_str(&self.request)
+            .map_err(|err| Error::Custom(format!("Invalid keysend destination: {err}")))?;
+
+        let extra: KeysendExtra = serde_json::from_str(
+            self.extra_json
+                .as_deref()
+                .ok_or_else(|| Error::Custom("Keysend amount is required".to_string()))?,
+        )?;
+
+        if extra.amount == Amount::ZERO {
+            return Err(Error::Custom("Keysend amount is required".to_string()));
+        }
+
+        Ok((destination, extra.amount))
+    }
+
+    /// Lookup id the backend created for a [`KEYSEND_METHOD`] quote, if it created one
+    ///
+    /// Set by the mint from the quote when paying, so the backend sends the payment it can later
+    /// look up by this id.
+    pub fn keysend_request_lookup_id(&self) -> Option<PaymentIdentifier> {
+        self.extra_json
+            .as_deref()
+            .and_then(|extra| serde_json::from_str::<KeysendExtra>(extra).ok())
+            .and_then(|extra| extra.request_lookup_id)
+    }
+}
+
 /// Options for creating an outgoing payment
 #[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum OutgoingPaymentOptions {
@@ -304,16 +341,29 @@ impl TryFrom<crate::mint::MeltQuote> for OutgoingPaymentOptions {
                     },
                 )))
             }
-            MeltPaymentRequest::Custom { method, request } => Ok(OutgoingPaymentOptions::Custom(
-                Box::new(CustomOutgoingPaymentOptions {
-                    method: method.to_string(),
-                    request: request.to_string(),
-                    max_fee_amount: Some(melt_quote.fee_reserve().into()),
-                    timeout_secs: None,
-                    melt_options: melt_quote.options,
-                    extra_json: None,
-                }),
-            )),
+            MeltPaymentRequest::Custom { method, request } => {
+                // A keysend request has no amount, pass the quoted one back to the backend along
+                // with the lookup id it created for the quote
+                let extra_json = if method == KEYSEND_METHOD {
+                    Some(serde_json::to_string(&KeysendExtra {
+                        amount: melt_quote.amount().into(),
+                        request_lookup_id: melt_quote.request_lookup_id.clone(),
+                    })?)
+                } else {
+                    None
+                };
+
+                Ok(OutgoingPaymentOptions::Custom(Box::new(
+                    CustomOutgoingPaymentOptions {
+                        method: method.to_string(),
+                        request: request.to_string(),
+                        max_fee_amount: Some(melt_quote.fee_reserve().into()),
+                        timeout_secs: None,
+                        melt_options: melt_quote.options,
+                        extra_json,
+                    },
+                )))
+            }
         }
     }
 }
@@ -341,6 +391,14 @@ pub trait MintPayment {
     /// Base Settings
     async fn get_settings(&self) -> Result<SettingsResponse, Self::Err>;
 
+    /// Maximum time the mint waits for an outgoing payment to settle
+    ///
+    /// When exceeded the melt is left pending and resolved by a later
+    /// payment status check. `None` waits for the backend to return.
+    fn payment_timeout(&self) -> Option<std::time::Duration> {
+        None
+    }
+
     /// Create a new invoice
     async fn create_incoming_payment_request(
         &self,
@@ -348,6 +406,16 @@ pub trait MintPayment {
         options: IncomingPaymentOptions,
     ) -> Result<CreateIncomingPaymentResponse, Self::Err>;
 
+    /// Cancel an incoming payment request that will not be paid
+    ///
+    /// Backends that can't cancel a request let it expire.
+    async fn cancel_incoming_payment_request(
+        &self,
+        _request_lookup_id: &PaymentIdentifier,
+    ) -> Result<(), Self::Err> {
+        Ok(())
+    }
+
     /// Get payment quote
     /// Used to get fee and amount required for a payment request
     async fn get_payment_quote(
@@ -502,6 +570,31 @@ pub struct Bolt12Settings {
     pub amountless: bool,
 }
 
+/// Custom payment method name for keysend (spontaneous) payments
+///
+/// The melt quote request is the hex encoded pubkey of the destination node,
+/// the amount to send is given as the `amount` extra field in the quote unit.
+pub const KEYSEND_METHOD: &str = "keysend";
+
+/// Keysend settings
+///
+/// Serialized as the settings of the [`KEYSEND_METHOD`] custom method
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub struct KeysendSettings {
+    /// Fee reserve is estimated by probing a route to the destination
+    pub fee_probe: bool,
+}
+
+/// Extra fields of a keysend payment
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeysendExtra {
+    /// Amount to send in the unit of the quote
+    pub amount: Amount,
+    /// Lookup id the backend created for the quote, set by the mint when paying
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_lookup_id: Option<PaymentIdentifier>,
+}
+
 /// Payment processor settings response
 /// Mirrors the proto SettingsResponse structure
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
@@ -570,6 +663,10 @@ where
 {
     type Err = T::Err;
 
+    fn payment_timeout(&self) -> Option<std::time::Duration> {
+        self.inner.payment_timeout()
+    }
+
     async fn get_settings(&self) -> Result<SettingsResponse, Self::Err> {
         let start = std::time::Instant::now();
         METRICS.inc_in_flight_requests("get_settings");
@@ -607,6 +704,15 @@ where
         result
     }
 
+    async fn cancel_incoming_payment_request(
+        &self,
+        request_lookup_id: &PaymentIdentifier,
+    ) -> Result<(), Self::Err> {
+        self.inner
+            .cancel_incoming_payment_request(request_lookup_id)
+            .await
+    }
+
     async fn get_payment_quote(
         &self,
         unit: &CurrencyUnit,
@@ -104,6 +104,8 @@ mod test {
     pub enum SubscriptionReq {
         Foo(u64),
         Bar(u64),
+        Foos(Vec<u64>),
+        Invalid,
     }
 
     impl SubscriptionRequest for SubscriptionReq {
@@ -112,10 +114,12 @@ mod test {
         type SubscriptionId = String;
 
         fn try_get_topics(&self) -> Result<Vec<Self::Topic>, Error> {
-            Ok(vec![match self {
-                SubscriptionReq::Bar(n) => IndexTest::Bar(*n),
-                SubscriptionReq::Foo(n) => IndexTest::Foo(*n),
-            }])
+            Ok(match self {
+                SubscriptionReq::Bar(n) => vec![IndexTest::Bar(*n)],
+                SubscriptionReq::Foo(n) => vec![IndexTest::Foo(*n)],
+                SubscriptionReq::Foos(n) => n.iter().copied().map(IndexTest::Foo).collect(),
+                SubscriptionReq::Invalid => return Err(Error::ParsingError("invalid".to_owned())),
+            })
         }
 
         fn subscription_name(&self) -> Arc<Self::SubscriptionId> {
@@ -177,4 +181,42 @@ mod test {
         let mut y = pubsub.subscribe(SubscriptionReq::Bar(2)).unwrap();
         assert_eq!(y.recv().await.map(|x| x.foo), Some(1));
     }
+
+    #[tokio::test]
+    async fn failed_subscription_is_not_counted() {
+        let pubsub = Pubsub::new(CustomPubSub::new_instance(()));
+
+        assert!(pubsub.subscribe(SubscriptionReq::Invalid).is_err());
+        assert_eq!(pubsub.active_subscribers(), 0);
+    }
+
+    #[tokio::test]
+    async fn publish_visits_only_the_event_topics_with_10k_subscribed_topics() {
+        let pubsub = Pubsub::new(CustomPubSub::new_instance(()));
+        let mut watcher = pubsub.subscribe(SubscriptionReq::Foo(0)).unwrap();
+
+        let mut batch = pubsub
+            .subscribe(SubscriptionReq::Foos((1..=10_000).collect()))
+            .unwrap();
+        let singles = (10_001..=20_000)
+            .map(|n| pubsub.subscribe(SubscriptionReq::Foo(n)).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(pubsub.active_subscribers(), 10_002);
+
+        // Only the subscriber of `Foo(0)` and the next entry of the index, which ends the
+        // range, are visited. `Bar(1)` has no subscriber and sorts after every `Foo`.
+        assert_eq!(
+            pubsub.publish_counting_visits(Message { foo: 0, bar: 1 }),
+            2
+        );
+        assert_eq!(watcher.recv().await.map(|x| x.bar), Some(1));
+        assert!(batch.try_recv().is_none());
+
+        pubsub.publish_now(Message { foo: 5_000, bar: 0 }).unwrap();
+        assert_eq!(batch.recv().await.map(|x| x.foo), Some(5_000));
+
+        drop(singles);
+        drop(batch);
+        assert_eq!(pubsub.active_subscribers(), 1);
+    }
 }
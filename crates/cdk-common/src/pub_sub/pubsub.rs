@@ -64,13 +64,22 @@ where
     /// Publish an event to all listenrs
     #[inline(always)]
     fn publish_internal(event: S::Event, listeners_index: &TopicTree<S>) -> Result<(), Error> {
+        Self::deliver(&event, listeners_index);
+        Ok(())
+    }
+
+    /// Send the event to the subscribers of its topics, returning the number of index entries
+    /// visited to find them
+    fn deliver(event: &S::Event, listeners_index: &TopicTree<S>) -> usize {
         let index_storage = listeners_index.read();
 
+        let mut visited = 0;
         let mut sent = HashSet::new();
         for topic in event.get_topics() {
             for ((subscription_index, unique_id), sender) in
                 index_storage.range((topic.clone(), 0)..)
             {
+                visited += 1;
                 if subscription_index.cmp(&topic) != Ordering::Equal {
                     break;
                 }
@@ -82,7 +91,13 @@ where
             }
         }
 
-        Ok(())
+        visited
+    }
+
+    /// Publish an event right away, returning the number of index entries visited
+    #[cfg(test)]
+    pub(super) fn publish_counting_visits(&self, event: S::Event) -> usize {
+        Self::deliver(&event, &self.listeners_topics)
     }
 
     /// Broadcast an event to all listeners
@@ -127,6 +142,7 @@ where
         >,
     {
         let subscription_name = request.subscription_name();
+        let subscribed_to = request.try_get_topics()?;
         let sender = Subscriber::new(subscription_name.clone(), sender);
         let mut index_storage = self.listeners_topics.write();
         let subscription_internal_id = self
@@ -136,8 +152,6 @@ where
         self.active_subscribers
             .fetch_add(1, std::sync::atomic::Ordering::Relaxed);
 
-        let subscribed_to = request.try_get_topics()?;
-
         for index in subscribed_to.iter() {
             index_storage.insert((index.clone(), subscription_internal_id), sender.clone());
         }
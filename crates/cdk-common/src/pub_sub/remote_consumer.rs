@@ -476,12 +476,12 @@ mod tests {
     use std::sync::atomic::{AtomicUsize, Ordering};
     use std::sync::Arc;
 
-    use tokio::sync::{mpsc, Mutex};
+    use tokio::sync::{mpsc, Mutex, Notify};
     use tokio::time::{timeout, Duration};
 
     use super::{
         InternalRelay, RemoteActiveConsumer, StreamCtrl, SubscribeMessage, Transport,
-        INTERNAL_POLL_SIZE,
+        INTERNAL_POLL_SIZE, STREAM_CONNECTION_BACKOFF,
     };
     use crate::pub_sub::remote_consumer::Consumer;
     use crate::pub_sub::test::{CustomPubSub, IndexTest, Message};
@@ -527,6 +527,8 @@ mod tests {
         support_long: bool,
         support_poll: bool,
         rx: Mutex<mpsc::Receiver<Message>>,
+        // Notified to simulate the remote end dropping the long connection.
+        disconnect: Arc<Notify>,
     }
 
     impl TestTransport {
@@ -548,10 +550,15 @@ mod tests {
                 observe_ctrl_tx,
                 support_long,
                 support_poll,
+                disconnect: Arc::new(Notify::new()),
             };
 
             (t, events_tx, observe_ctrl_rx)
         }
+
+        fn disconnect_handle(&self) -> Arc<Notify> {
+            self.disconnect.clone()
+        }
     }
 
     #[async_trait::async_trait]
@@ -593,6 +600,9 @@ mod tests {
                     Some(msg) = rx.recv() => {
                         reply_to.send(msg);
                     }
+                    _ = self.disconnect.notified() => {
+                        return Err(Error::InternalStr("connection dropped".to_owned()));
+                    }
                 }
             }
 
@@ -799,6 +809,38 @@ mod tests {
         let _ = expect_ctrl(&mut ctrl_rx, 2000, |m| matches!(m, StreamCtrl::Stop)).await;
     }
 
+    #[tokio::test]
+    async fn stream_reconnects_and_resubscribes_after_drop() {
+        let (transport, events_tx, mut ctrl_rx) = TestTransport::new(true, true);
+        let disconnect = transport.disconnect_handle();
+        let consumer = Consumer::new(transport, false, ());
+
+        let mut sub = consumer
+            .subscribe(SubscriptionReq::Foo("t".to_owned(), 3))
+            .expect("subscribe ok");
+        let _ = expect_ctrl(
+            &mut ctrl_rx,
+            1000,
+            |m| matches!(m, StreamCtrl::Subscribe((_, idx)) if *idx == IndexTest::Foo(3)),
+        )
+        .await;
+
+        // Drop the connection, the consumer must reconnect and subscribe to the topic again
+        disconnect.notify_one();
+        let _ = expect_ctrl(
+            &mut ctrl_rx,
+            STREAM_CONNECTION_BACKOFF.as_millis() as u64 + 3000,
+            |m| matches!(m, StreamCtrl::Subscribe((_, idx)) if *idx == IndexTest::Foo(3)),
+        )
+        .await;
+
+        events_tx.send(Message { foo: 3, bar: 8 }).await.unwrap();
+        let got = recv_next::<TestTransport>(&mut sub, 1000)
+            .await
+            .expect("event after reconnect");
+        assert_eq!(got, Message { foo: 3, bar: 8 });
+    }
+
     #[tokio::test]
     async fn falls_back_to_poll_when_stream_not_supported() {
         // stream NOT supported, poll supported
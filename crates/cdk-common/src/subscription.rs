@@ -1,4 +1,5 @@
 //! Subscription types and traits
+use std::collections::HashSet;
 use std::ops::Deref;
 use std::str::FromStr;
 use std::sync::Arc;
@@ -25,27 +26,71 @@ impl SubscriptionRequest for Params {
     }
 
     fn try_get_topics(&self) -> Result<Vec<Self::Topic>, Error> {
+        if self.kind == Kind::MintInfo {
+            return Ok(vec![NotificationId::MintInfo]);
+        }
+
         self.filters
             .iter()
-            .map(|filter| match self.kind {
-                Kind::Bolt11MeltQuote => QuoteId::from_str(filter)
-                    .map(NotificationId::MeltQuoteBolt11)
-                    .map_err(|_| Error::ParsingError(filter.to_owned())),
-                Kind::Bolt11MintQuote => QuoteId::from_str(filter)
-                    .map(NotificationId::MintQuoteBolt11)
-                    .map_err(|_| Error::ParsingError(filter.to_owned())),
-                Kind::ProofState => PublicKey::from_str(filter)
-                    .map(NotificationId::ProofState)
-                    .map_err(|_| Error::ParsingError(filter.to_owned())),
-
-                Kind::Bolt12MintQuote => QuoteId::from_str(filter)
-                    .map(NotificationId::MintQuoteBolt12)
-                    .map_err(|_| Error::ParsingError(filter.to_owned())),
-            })
+            .map(|filter| parse_filter(self.kind, filter))
             .collect::<Result<Vec<_>, _>>()
     }
 }
 
+/// Parses a filter of a mint subscription of `kind`
+fn parse_filter(kind: Kind, filter: &str) -> Result<NotificationId<QuoteId>, Error> {
+    match kind {
+        Kind::Bolt11MeltQuote => QuoteId::from_str(filter)
+            .map(NotificationId::MeltQuoteBolt11)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+        Kind::Bolt11MintQuote => QuoteId::from_str(filter)
+            .map(NotificationId::MintQuoteBolt11)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+        Kind::ProofState => PublicKey::from_str(filter)
+            .map(NotificationId::ProofState)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+
+        Kind::Bolt12MintQuote => QuoteId::from_str(filter)
+            .map(NotificationId::MintQuoteBolt12)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+        Kind::InternalSettlement => QuoteId::from_str(filter)
+            .map(NotificationId::InternalSettlement)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+        Kind::Bolt11MeltProgress => QuoteId::from_str(filter)
+            .map(NotificationId::MeltProgress)
+            .map_err(|_| Error::ParsingError(filter.to_owned())),
+        Kind::MintInfo => Ok(NotificationId::MintInfo),
+    }
+}
+
+/// Keeps the filters of `params` the mint can subscribe to and returns the rejected ones
+///
+/// Invalid filters and the filters beyond the first `max_filters` valid ones are rejected,
+/// duplicated filters are only kept once.
+pub fn limit_filters(params: &mut Params, max_filters: usize) -> Vec<String> {
+    if params.kind == Kind::MintInfo {
+        return vec![];
+    }
+
+    let kind = params.kind;
+    let mut seen = HashSet::new();
+    let mut rejected = vec![];
+
+    params.filters.retain(|filter| {
+        if seen.contains(filter) {
+            return false;
+        }
+        if parse_filter(kind, filter).is_err() || seen.len() >= max_filters {
+            rejected.push(filter.to_owned());
+            return false;
+        }
+        seen.insert(filter.to_owned());
+        true
+    });
+
+    rejected
+}
+
 /// Subscriptions parameters for the wallet
 ///
 /// This is because the Wallet can subscribe to non CDK quotes, where IDs are not constraint to
@@ -62,6 +107,10 @@ impl SubscriptionRequest for WalletParams {
     }
 
     fn try_get_topics(&self) -> Result<Vec<Self::Topic>, Error> {
+        if self.kind == Kind::MintInfo {
+            return Ok(vec![NotificationId::MintInfo]);
+        }
+
         self.filters
             .iter()
             .map(|filter| {
@@ -73,6 +122,11 @@ impl SubscriptionRequest for WalletParams {
                         .map_err(|_| Error::ParsingError(filter.to_owned()))?,
 
                     Kind::Bolt12MintQuote => NotificationId::MintQuoteBolt12(filter.to_owned()),
+                    Kind::InternalSettlement => {
+                        NotificationId::InternalSettlement(filter.to_owned())
+                    }
+                    Kind::Bolt11MeltProgress => NotificationId::MeltProgress(filter.to_owned()),
+                    Kind::MintInfo => NotificationId::MintInfo,
                 })
             })
             .collect::<Result<Vec<_>, _>>()
@@ -113,3 +167,53 @@ impl Deref for SubId {
         &self.0
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cashu::SecretKey;
+
+    use super::*;
+
+    fn proof_state_params(filters: Vec<String>) -> Params {
+        Params {
+            kind: Kind::ProofState,
+            filters,
+            id: Arc::new("sub".into()),
+        }
+    }
+
+    #[test]
+    fn limit_filters_rejects_invalid_and_overflowing_filters() {
+        let ys: Vec<String> = (0..4)
+            .map(|_| SecretKey::generate().public_key().to_hex())
+            .collect();
+        let mut params = proof_state_params(vec![
+            ys[0].clone(),
+            "not a y".to_owned(),
+            ys[1].clone(),
+            ys[0].clone(),
+            ys[2].clone(),
+            ys[3].clone(),
+        ]);
+
+        let rejected = limit_filters(&mut params, 2);
+
+        assert_eq!(params.filters, vec![ys[0].clone(), ys[1].clone()]);
+        assert_eq!(
+            rejected,
+            vec!["not a y".to_owned(), ys[2].clone(), ys[3].clone()]
+        );
+        assert_eq!(params.try_get_topics().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn limit_filters_keeps_requests_within_the_limit() {
+        let ys: Vec<String> = (0..3)
+            .map(|_| SecretKey::generate().public_key().to_hex())
+            .collect();
+        let mut params = proof_state_params(ys.clone());
+
+        assert!(limit_filters(&mut params, 3).is_empty());
+        assert_eq!(params.filters, ys);
+    }
+}
@@ -10,7 +10,7 @@ use cashu::{nut00, PaymentMethod, Proofs, PublicKey};
 use serde::{Deserialize, Serialize};
 
 use crate::mint_url::MintUrl;
-use crate::nuts::{CurrencyUnit, MeltQuoteState, MintQuoteState, SecretKey};
+use crate::nuts::{CurrencyUnit, MeltQuoteFees, MeltQuoteState, MintQuoteState, SecretKey};
 use crate::{Amount, Error};
 
 /// Wallet Key
@@ -85,6 +85,9 @@ pub struct MeltQuote {
     pub payment_preimage: Option<String>,
     /// Payment method
     pub payment_method: PaymentMethod,
+    /// Fee breakdown reported by the mint
+    #[serde(default)]
+    pub fees: Option<MeltQuoteFees>,
 }
 
 impl MintQuote {
@@ -179,6 +182,22 @@ impl SendKind {
     }
 }
 
+/// Proof selection strategy
+///
+/// Decides which of the wallet's proofs are used as inputs when sending or melting.
+#[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum SelectionStrategy {
+    /// Spend the smallest proofs first, consolidating small denominations
+    SmallestFirst,
+    /// Spend the largest proofs first, using as few proofs as possible
+    LargestFirst,
+    #[default]
+    /// Prefer proofs that exactly match the optimal split of the amount
+    ExactMatchPreferred,
+    /// Prefer the proofs that result in the lowest input fee
+    MinimizeFee,
+}
+
 /// Wallet Transaction
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Transaction {
@@ -209,6 +228,21 @@ pub struct Transaction {
     /// Payment method (e.g., Bolt11, Bolt12) for mint/melt transactions
     #[serde(default)]
     pub payment_method: Option<PaymentMethod>,
+    /// Operation that produced the transaction
+    ///
+    /// `None` for entries recorded before the kind was stored.
+    #[serde(default)]
+    pub kind: Option<TransactionKind>,
+    /// Payment proof was checked against the payment hash of the request
+    ///
+    /// Only set for melts of bolt11 invoices whose preimage matched.
+    #[serde(default)]
+    pub preimage_verified: bool,
+    /// Amount spent by a melt as reported by the mint, the quote amount and the lightning fee
+    ///
+    /// `None` for other operations and for mints that do not report it.
+    #[serde(default)]
+    pub amount_spent: Option<Amount>,
 }
 
 impl Transaction {
@@ -241,6 +275,29 @@ impl Transaction {
         }
         true
     }
+
+    /// Check if transaction matches a [`TransactionFilter`]
+    pub fn matches_filter(&self, filter: &TransactionFilter) -> bool {
+        if !self.matches_conditions(&filter.mint_url, &filter.direction, &filter.unit) {
+            return false;
+        }
+        if let Some(kind) = filter.kind {
+            if self.kind != Some(kind) {
+                return false;
+            }
+        }
+        if let Some(since) = filter.since {
+            if self.timestamp < since {
+                return false;
+            }
+        }
+        if let Some(until) = filter.until {
+            if self.timestamp > until {
+                return false;
+            }
+        }
+        true
+    }
 }
 
 impl PartialOrd for Transaction {
@@ -288,6 +345,137 @@ impl FromStr for TransactionDirection {
     }
 }
 
+/// Transaction Kind
+///
+/// The wallet operation that produced a [`Transaction`].
+#[derive(Debug, Copy, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TransactionKind {
+    /// Proofs issued for a paid mint quote
+    Mint,
+    /// Proofs spent to pay a melt quote
+    Melt,
+    /// Proofs sent as a token
+    Send,
+    /// Token received into the wallet
+    Receive,
+    /// Proofs swapped for new proofs at the same mint
+    ///
+    /// Recorded as outgoing with the swapped amount; only the fee leaves the
+    /// wallet.
+    Swap,
+}
+
+impl std::fmt::Display for TransactionKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TransactionKind::Mint => write!(f, "Mint"),
+            TransactionKind::Melt => write!(f, "Melt"),
+            TransactionKind::Send => write!(f, "Send"),
+            TransactionKind::Receive => write!(f, "Receive"),
+            TransactionKind::Swap => write!(f, "Swap"),
+        }
+    }
+}
+
+impl FromStr for TransactionKind {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "Mint" => Ok(Self::Mint),
+            "Melt" => Ok(Self::Melt),
+            "Send" => Ok(Self::Send),
+            "Receive" => Ok(Self::Receive),
+            "Swap" => Ok(Self::Swap),
+            _ => Err(Error::InvalidTransactionKind),
+        }
+    }
+}
+
+/// Transaction Filter
+///
+/// Every set field must match for a transaction to be included.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionFilter {
+    /// Only transactions with this mint
+    pub mint_url: Option<MintUrl>,
+    /// Only transactions in this direction
+    pub direction: Option<TransactionDirection>,
+    /// Only transactions in this unit
+    pub unit: Option<CurrencyUnit>,
+    /// Only transactions of this kind
+    pub kind: Option<TransactionKind>,
+    /// Only transactions at or after this unix timestamp
+    pub since: Option<u64>,
+    /// Only transactions at or before this unix timestamp
+    pub until: Option<u64>,
+}
+
+impl TransactionFilter {
+    /// Create a filter that matches every transaction
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Only include transactions in `direction`
+    pub fn with_direction(mut self, direction: TransactionDirection) -> Self {
+        self.direction = Some(direction);
+        self
+    }
+
+    /// Only include transactions of `kind`
+    pub fn with_kind(mut self, kind: TransactionKind) -> Self {
+        self.kind = Some(kind);
+        self
+    }
+
+    /// Only include transactions at or after `since`
+    pub fn with_since(mut self, since: u64) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    /// Only include transactions at or before `until`
+    pub fn with_until(mut self, until: u64) -> Self {
+        self.until = Some(until);
+        self
+    }
+}
+
+impl From<Option<TransactionDirection>> for TransactionFilter {
+    fn from(direction: Option<TransactionDirection>) -> Self {
+        Self {
+            direction,
+            ..Default::default()
+        }
+    }
+}
+
+/// Pagination for transaction history
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Pagination {
+    /// Number of transactions to skip
+    pub offset: usize,
+    /// Maximum number of transactions to return, `None` for no limit
+    pub limit: Option<usize>,
+}
+
+impl Pagination {
+    /// Create new [`Pagination`]
+    pub fn new(offset: usize, limit: Option<usize>) -> Self {
+        Self { offset, limit }
+    }
+
+    /// Apply pagination to an ordered list of items
+    pub fn apply<T>(&self, items: Vec<T>) -> Vec<T> {
+        let items = items.into_iter().skip(self.offset);
+        match self.limit {
+            Some(limit) => items.take(limit).collect(),
+            None => items.collect(),
+        }
+    }
+}
+
 /// Transaction ID
 #[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -398,4 +586,76 @@ mod tests {
         let res = TransactionId::from_hex(hex_str);
         assert!(matches!(res, Err(Error::InvalidTransactionId)));
     }
+
+    fn test_transaction(timestamp: u64, kind: TransactionKind) -> Transaction {
+        Transaction {
+            mint_url: MintUrl::from_str("https://mint.example.com").unwrap(),
+            direction: TransactionDirection::Incoming,
+            amount: Amount::from(10),
+            fee: Amount::ZERO,
+            unit: CurrencyUnit::Sat,
+            ys: vec![SecretKey::generate().public_key()],
+            timestamp,
+            memo: None,
+            metadata: HashMap::new(),
+            quote_id: None,
+            payment_request: None,
+            payment_proof: None,
+            payment_method: None,
+            kind: Some(kind),
+            preimage_verified: false,
+            amount_spent: None,
+        }
+    }
+
+    #[test]
+    fn test_transaction_matches_filter() {
+        let transaction = test_transaction(100, TransactionKind::Receive);
+
+        assert!(transaction.matches_filter(&TransactionFilter::new()));
+        assert!(transaction.matches_filter(
+            &TransactionFilter::new()
+                .with_kind(TransactionKind::Receive)
+                .with_since(100)
+                .with_until(100)
+        ));
+        assert!(
+            !transaction.matches_filter(&TransactionFilter::new().with_kind(TransactionKind::Mint))
+        );
+        assert!(!transaction.matches_filter(&TransactionFilter::new().with_since(101)));
+        assert!(!transaction.matches_filter(&TransactionFilter::new().with_until(99)));
+        assert!(!transaction.matches_filter(
+            &TransactionFilter::new().with_direction(TransactionDirection::Outgoing)
+        ));
+    }
+
+    #[test]
+    fn test_transaction_kind_round_trip() {
+        for kind in [
+            TransactionKind::Mint,
+            TransactionKind::Melt,
+            TransactionKind::Send,
+            TransactionKind::Receive,
+            TransactionKind::Swap,
+        ] {
+            assert_eq!(TransactionKind::from_str(&kind.to_string()).unwrap(), kind);
+        }
+        assert!(matches!(
+            TransactionKind::from_str("Refund"),
+            Err(Error::InvalidTransactionKind)
+        ));
+    }
+
+    #[test]
+    fn test_pagination_apply() {
+        let items: Vec<u32> = (0..10).collect();
+
+        assert_eq!(Pagination::default().apply(items.clone()), items);
+        assert_eq!(
+            Pagination::new(2, Some(3)).apply(items.clone()),
+            vec![2, 3, 4]
+        );
+        assert_eq!(Pagination::new(8, Some(5)).apply(items.clone()), vec![8, 9]);
+        assert!(Pagination::new(20, None).apply(items).is_empty());
+    }
 }
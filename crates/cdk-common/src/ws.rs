@@ -65,6 +65,15 @@ pub fn notification_uuid_to_notification_string(
             NotificationPayload::MintQuoteBolt12Response(quote) => {
                 NotificationPayload::MintQuoteBolt12Response(quote.to_string_id())
             }
+            NotificationPayload::InternalSettlement(settlement) => {
+                NotificationPayload::InternalSettlement(settlement.to_string_id())
+            }
+            NotificationPayload::MeltProgress(progress) => {
+                NotificationPayload::MeltProgress(progress.to_string_id())
+            }
+            NotificationPayload::MintInfo(notification) => {
+                NotificationPayload::MintInfo(notification)
+            }
         },
     }
 }
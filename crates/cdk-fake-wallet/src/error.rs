@@ -1,5 +1,6 @@
 //! Fake Wallet Error
 
+use cdk_common::CurrencyUnit;
 use thiserror::Error;
 
 /// Fake Wallet Error
@@ -14,6 +15,12 @@ pub enum Error {
     /// Unknown invoice
     #[error("No channel receiver")]
     NoReceiver,
+    /// Custom unit has no configured exchange rate
+    #[error("No exchange rate configured for custom unit {0}")]
+    NoCustomUnitRate(CurrencyUnit),
+    /// Amount conversion error
+    #[error(transparent)]
+    Amount(#[from] cdk_common::amount::Error),
 }
 
 impl From<Error> for cdk_common::payment::Error {
@@ -67,15 +67,26 @@ struct MempoolPricesResponse {
 #[derive(Debug, Clone)]
 struct ExchangeRateCache {
     rates: Arc<Mutex<Option<(MempoolPricesResponse, Instant)>>>,
+    /// Msat value of one unit for explicitly configured custom units
+    custom_rates: HashMap<CurrencyUnit, u64>,
 }
 
 impl ExchangeRateCache {
     fn new() -> Self {
         Self {
             rates: Arc::new(Mutex::new(None)),
+            custom_rates: HashMap::new(),
         }
     }
 
+    /// Msat value of one unit of a custom currency
+    fn custom_msat_rate(&self, currency: &CurrencyUnit) -> Result<u64, Error> {
+        self.custom_rates
+            .get(currency)
+            .copied()
+            .ok_or_else(|| Error::NoCustomUnitRate(currency.clone()))
+    }
+
     /// Get current BTC rate for the specified currency with caching and fallback
     async fn get_btc_rate(&self, currency: &CurrencyUnit) -> Result<f64, Error> {
         // Return cached rate if still valid
@@ -188,6 +199,22 @@ async fn convert_currency_amount(
             )) // to cents
         }
 
+        // Custom units only convert at an explicitly configured rate
+        (Custom(_), Sat | Msat) => {
+            let amount_msat = amount
+                .checked_mul(rate_cache.custom_msat_rate(from_unit)?)
+                .ok_or(Error::UnknownInvoiceAmount)?;
+            Ok(Amount::new(amount_msat, Msat).convert_to(target_unit)?)
+        }
+        (Sat | Msat, Custom(_)) => {
+            let amount_msat = Amount::new(amount, from_unit.clone()).convert_to(&Msat)?;
+            let rate = rate_cache.custom_msat_rate(target_unit)?;
+            Ok(Amount::new(
+                amount_msat.value().div_ceil(rate),
+                target_unit.clone(),
+            ))
+        }
+
         _ => Err(Error::UnknownInvoiceAmount), // Unsupported conversion
     }
 }
@@ -339,10 +366,13 @@ pub struct FakeWallet {
     payment_states: Arc<Mutex<HashMap<String, PaymentStateEntry>>>,
     failed_payment_check: Arc<Mutex<HashSet<String>>>,
     payment_delay: u64,
+    payment_timeout: Option<Duration>,
     wait_invoice_cancel_token: CancellationToken,
     wait_invoice_is_active: Arc<AtomicBool>,
     incoming_payments: Arc<RwLock<HashMap<PaymentIdentifier, Vec<WaitPaymentResponse>>>>,
+    cancelled_incoming_payments: Arc<RwLock<HashSet<PaymentIdentifier>>>,
     unit: CurrencyUnit,
+    invoice_unit: Option<CurrencyUnit>,
     secondary_repayment_queue: SecondaryRepaymentQueue,
     exchange_rate_cache: ExchangeRateCache,
 }
@@ -388,14 +418,42 @@ impl FakeWallet {
             payment_states: Arc::new(Mutex::new(payment_states)),
             failed_payment_check: Arc::new(Mutex::new(fail_payment_check)),
             payment_delay,
+            payment_timeout: None,
             wait_invoice_cancel_token: CancellationToken::new(),
             wait_invoice_is_active: Arc::new(AtomicBool::new(false)),
             incoming_payments,
+            cancelled_incoming_payments: Arc::new(RwLock::new(HashSet::new())),
             unit,
+            invoice_unit: None,
             secondary_repayment_queue,
             exchange_rate_cache: ExchangeRateCache::new(),
         }
     }
+
+    /// Set the maximum time the mint waits for an outgoing payment
+    pub fn with_payment_timeout(mut self, payment_timeout: Duration) -> Self {
+        self.payment_timeout = Some(payment_timeout);
+        self
+    }
+
+    /// Value one unit of a custom currency at `msat_per_unit`
+    ///
+    /// Custom units have no conversion to bitcoin units, so the fake wallet
+    /// rejects them unless a rate is configured here.
+    pub fn with_custom_unit_rate(mut self, unit: CurrencyUnit, msat_per_unit: u64) -> Self {
+        self.exchange_rate_cache
+            .custom_rates
+            .insert(unit, msat_per_unit.max(1));
+        self
+    }
+
+    /// Read the amounts of BOLT11 invoices in `unit`, whatever unit the mint asks for
+    ///
+    /// Simulates a backend configured with the wrong unit, e.g. msat while the keysets are sat.
+    pub fn with_invoice_unit(mut self, unit: CurrencyUnit) -> Self {
+        self.invoice_unit = Some(unit);
+        self
+    }
 }
 
 /// Struct for signaling what methods should respond via invoice description
@@ -409,6 +467,9 @@ pub struct FakeInvoiceDescription {
     pub pay_err: bool,
     /// Should check failure
     pub check_err: bool,
+    /// Milliseconds to wait before the payment settles
+    #[serde(default)]
+    pub pay_delay_ms: u64,
 }
 
 impl Default for FakeInvoiceDescription {
@@ -418,10 +479,18 @@ impl Default for FakeInvoiceDescription {
             check_payment_state: MeltQuoteState::Paid,
             pay_err: false,
             check_err: false,
+            pay_delay_ms: 0,
         }
     }
 }
 
+/// Struct for signaling how an incoming invoice is paid via its description
+#[derive(Debug, Clone, Hash, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FakeIncomingPaymentDescription {
+    /// Amount received for the invoice, in the unit of the request, instead of its amount
+    pub amount_received: Amount,
+}
+
 #[async_trait]
 impl MintPayment for FakeWallet {
     type Err = payment::Error;
@@ -432,7 +501,7 @@ impl MintPayment for FakeWallet {
             unit: self.unit.to_string(),
             bolt11: Some(payment::Bolt11Settings {
                 mpp: true,
-                amountless: false,
+                amountless: true,
                 invoice_description: true,
             }),
             bolt12: Some(payment::Bolt12Settings { amountless: false }),
@@ -545,6 +614,10 @@ impl MintPayment for FakeWallet {
         })
     }
 
+    fn payment_timeout(&self) -> Option<Duration> {
+        self.payment_timeout
+    }
+
     #[instrument(skip_all)]
     async fn make_payment(
         &self,
@@ -561,7 +634,6 @@ impl MintPayment for FakeWallet {
                 let status: Option<FakeInvoiceDescription> =
                     serde_json::from_str(&description).ok();
 
-                let mut payment_states = self.payment_states.lock().await;
                 let payment_status = status
                     .clone()
                     .map(|s| s.pay_invoice_state)
@@ -587,6 +659,17 @@ impl MintPayment for FakeWallet {
                     Amount::new(0, CurrencyUnit::Msat)
                 };
 
+                let pay_delay_ms = status.as_ref().map_or(0, |s| s.pay_delay_ms);
+                if pay_delay_ms > 0 {
+                    // Report the payment as in flight until the delay has elapsed
+                    self.payment_states.lock().await.insert(
+                        payment_hash.clone(),
+                        (MeltQuoteState::Pending, Amount::new(0, CurrencyUnit::Msat)),
+                    );
+                    time::sleep(Duration::from_millis(pay_delay_ms)).await;
+                }
+
+                let mut payment_states = self.payment_states.lock().await;
                 payment_states.insert(payment_hash.clone(), (checkout_going_status, amount_spent));
 
                 if let Some(description) = status {
@@ -656,7 +739,7 @@ impl MintPayment for FakeWallet {
         unit: &CurrencyUnit,
         options: IncomingPaymentOptions,
     ) -> Result<CreateIncomingPaymentResponse, Self::Err> {
-        let (payment_hash, request, amount, expiry) = match options {
+        let (payment_hash, request, amount, amount_received, expiry) = match options {
             IncomingPaymentOptions::Bolt12(bolt12_options) => {
                 let description = bolt12_options.description.unwrap_or_default();
                 let amount = bolt12_options.amount;
@@ -688,6 +771,7 @@ impl MintPayment for FakeWallet {
                     PaymentIdentifier::OfferId(offer.id().to_string()),
                     offer.to_string(),
                     amount.unwrap_or(Amount::ZERO),
+                    None,
                     expiry,
                 )
             }
@@ -698,7 +782,7 @@ impl MintPayment for FakeWallet {
 
                 let amount_msat = convert_currency_amount(
                     u64::from(amount),
-                    unit,
+                    self.invoice_unit.as_ref().unwrap_or(unit),
                     &CurrencyUnit::Msat,
                     &self.exchange_rate_cache,
                 )
@@ -707,10 +791,16 @@ impl MintPayment for FakeWallet {
                 let invoice = create_fake_invoice(amount_msat.value(), description.clone());
                 let payment_hash = invoice.payment_hash();
 
+                let amount_received =
+                    serde_json::from_str::<FakeIncomingPaymentDescription>(&description)
+                        .ok()
+                        .map(|description| description.amount_received);
+
                 (
                     PaymentIdentifier::PaymentHash(*payment_hash.as_ref()),
                     invoice.to_string(),
                     amount,
+                    amount_received,
                     expiry,
                 )
             }
@@ -725,8 +815,12 @@ impl MintPayment for FakeWallet {
         let duration = time::Duration::from_secs(self.payment_delay);
         let payment_hash_clone = payment_hash.clone();
         let incoming_payment = self.incoming_payments.clone();
+        let cancelled_incoming_payments = self.cancelled_incoming_payments.clone();
 
-        let final_amount = if amount == Amount::ZERO {
+        let final_amount = if let Some(amount_received) = amount_received {
+            // Simulate a payer that overpaid or only partially paid the invoice
+            Amount::new(u64::from(amount_received), unit.clone())
+        } else if amount == Amount::ZERO {
             // For any-amount invoices, generate a random amount for the initial payment
             use bitcoin::secp256k1::rand::rngs::OsRng;
             use bitcoin::secp256k1::rand::Rng;
@@ -743,6 +837,14 @@ impl MintPayment for FakeWallet {
             // Wait for the random delay to elapse
             time::sleep(duration).await;
 
+            if cancelled_incoming_payments
+                .read()
+                .await
+                .contains(&payment_hash_clone)
+            {
+                return;
+            }
+
             let response = WaitPaymentResponse {
                 payment_identifier: payment_hash_clone.clone(),
                 payment_amount: final_amount,
@@ -780,6 +882,18 @@ impl MintPayment for FakeWallet {
         })
     }
 
+    #[instrument(skip_all)]
+    async fn cancel_incoming_payment_request(
+        &self,
+        request_lookup_id: &PaymentIdentifier,
+    ) -> Result<(), Self::Err> {
+        self.cancelled_incoming_payments
+            .write()
+            .await
+            .insert(request_lookup_id.clone());
+        Ok(())
+    }
+
     #[instrument(skip_all)]
     async fn check_incoming_payment_status(
         &self,
@@ -828,6 +942,20 @@ impl MintPayment for FakeWallet {
 /// Panics if the hardcoded secret key or payment hash bytes are invalid.
 #[instrument]
 pub fn create_fake_invoice(amount_msat: u64, description: String) -> Bolt11Invoice {
+    build_fake_invoice(Some(amount_msat), description)
+}
+
+/// Create fake zero-amount invoice
+///
+/// # Panics
+///
+/// Panics if the hardcoded secret key or payment hash bytes are invalid.
+#[instrument]
+pub fn create_fake_amountless_invoice(description: String) -> Bolt11Invoice {
+    build_fake_invoice(None, description)
+}
+
+fn build_fake_invoice(amount_msat: Option<u64>, description: String) -> Bolt11Invoice {
     let private_key = SecretKey::from_slice(
         &[
             0xe1, 0x26, 0xf6, 0x8f, 0x7e, 0xaf, 0xcc, 0x8b, 0x74, 0xf5, 0x4d, 0x26, 0x9f, 0xe2,
@@ -846,11 +974,17 @@ pub fn create_fake_invoice(amount_msat: u64, description: String) -> Bolt11Invoi
     let payment_hash = sha256::Hash::from_slice(&random_bytes).expect("Valid 32-byte hash input");
     let payment_secret = PaymentSecret([42u8; 32]);
 
-    InvoiceBuilder::new(Currency::Bitcoin)
+    let builder = InvoiceBuilder::new(Currency::Bitcoin)
         .description(description)
         .payment_hash(payment_hash)
-        .payment_secret(payment_secret)
-        .amount_milli_satoshis(amount_msat)
+        .payment_secret(payment_secret);
+
+    let builder = match amount_msat {
+        Some(amount_msat) => builder.amount_milli_satoshis(amount_msat),
+        None => builder,
+    };
+
+    builder
         .current_timestamp()
         .min_final_cltv_expiry_delta(144)
         .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &private_key))
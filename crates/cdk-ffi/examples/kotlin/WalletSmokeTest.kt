@@ -0,0 +1,104 @@
+// Smoke test of the Kotlin bindings against a mint with the fake wallet backend
+//
+// See ../README.md to run it.
+
+import java.io.File
+import kotlinx.coroutines.CompletableDeferred
+import kotlinx.coroutines.runBlocking
+import kotlinx.coroutines.withTimeout
+import org.cashudevkit.*
+
+class PaidObserver : QuoteObserver {
+    val paid = CompletableDeferred<String>()
+
+    override fun onMintQuoteUpdate(quote: MintQuoteBolt11Response) {
+        if (quote.state == QuoteState.PAID || quote.state == QuoteState.ISSUED) {
+            paid.complete(quote.quote)
+        }
+    }
+
+    override fun onMeltQuoteUpdate(quote: MeltQuoteBolt11Response) {}
+
+    override fun onMeltProgress(quoteId: String, progress: MeltProgress, elapsedMs: ULong) {
+        println("melt $quoteId: $progress after $elapsedMs ms")
+    }
+}
+
+fun createWallet(mintUrl: String, name: String): Wallet {
+    val storage = File.createTempFile("cdk-ffi-$name", ".sqlite")
+    return Wallet.newWithStoragePath(
+        mintUrl,
+        CurrencyUnit.Sat,
+        generateMnemonic(),
+        storage.absolutePath,
+        WalletConfig(targetProofCount = null),
+    )
+}
+
+fun sendOptions() = SendOptions(
+    memo = null,
+    conditions = null,
+    amountSplitTarget = SplitTarget.None,
+    sendKind = SendKind.OnlineExact,
+    includeFee = false,
+    maxProofs = null,
+    metadata = emptyMap(),
+    selectionStrategy = SelectionStrategy.SMALLEST_FIRST,
+    includeDleq = false,
+)
+
+fun receiveOptions() = ReceiveOptions(
+    amountSplitTarget = SplitTarget.None,
+    p2pkSigningKeys = emptyList(),
+    preimages = emptyList(),
+    metadata = emptyMap(),
+    allOrNothing = false,
+)
+
+fun main() = runBlocking {
+    val mintUrl = System.getenv("MINT_URL") ?: "http://127.0.0.1:8085"
+    val alice = createWallet(mintUrl, "alice")
+    val bob = createWallet(mintUrl, "bob")
+
+    // Mint, waiting for the quote to be paid through the observer
+    val quote = alice.mintQuote(Amount(100UL), null)
+    val observer = PaidObserver()
+    val watch = alice.watchQuotes(SubscriptionKind.BOLT11_MINT_QUOTE, listOf(quote.id), observer)
+    withTimeout(30_000) { observer.paid.await() }
+    watch.cancel()
+    alice.mint(quote.id, SplitTarget.None, null)
+    check(alice.totalBalance().value == 100UL)
+
+    // Send to bob, through the serialized token
+    val token = alice.prepareSend(Amount(40UL), sendOptions()).confirm("smoke test")
+    val parsed = Token.decode(token.encode())
+    check(parsed.value().value == 40UL)
+    check(bob.receive(parsed, receiveOptions()).value == 40UL)
+
+    // Melt from bob
+    val meltQuote = bob.meltQuote(System.getenv("BOLT11_INVOICE") ?: fakeInvoice(bob), null)
+    val melted = bob.melt(meltQuote.id)
+    check(melted.state == QuoteState.PAID)
+
+    check(alice.listTransactions(null).size == 2)
+    check(bob.listTransactions(null).size == 2)
+
+    // Errors are typed exceptions
+    try {
+        Token.decode("cashuBinvalid")
+        error("an invalid token was parsed")
+    } catch (e: FfiException.Internal) {
+        println("invalid token rejected: ${e.errorMessage}")
+    }
+    try {
+        alice.melt("unknown-quote")
+        error("an unknown quote was melted")
+    } catch (e: FfiException.Cdk) {
+        println("unknown quote rejected with code ${e.code}")
+    }
+
+    println("Kotlin smoke test passed")
+}
+
+/** Invoice of a fresh mint quote, paid by the fake wallet backend when melted */
+suspend fun fakeInvoice(wallet: Wallet): String = wallet.mintQuote(Amount(10UL), null).request
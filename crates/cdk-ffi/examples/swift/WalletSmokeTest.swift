@@ -0,0 +1,109 @@
+// Smoke test of the Swift bindings against a mint with the fake wallet backend
+//
+// See ../README.md to run it.
+
+import Foundation
+
+final class PaidObserver: QuoteObserver, @unchecked Sendable {
+    private var continuation: CheckedContinuation<String, Never>?
+
+    func waitPaid() async -> String {
+        await withCheckedContinuation { continuation = $0 }
+    }
+
+    func onMintQuoteUpdate(quote: MintQuoteBolt11Response) {
+        if quote.state == .paid || quote.state == .issued {
+            continuation?.resume(returning: quote.quote)
+            continuation = nil
+        }
+    }
+
+    func onMeltQuoteUpdate(quote: MeltQuoteBolt11Response) {}
+
+    func onMeltProgress(quoteId: String, progress: MeltProgress, elapsedMs: UInt64) {
+        print("melt \(quoteId): \(progress) after \(elapsedMs) ms")
+    }
+}
+
+func createWallet(mintUrl: String, name: String) throws -> Wallet {
+    let storage = FileManager.default.temporaryDirectory
+        .appendingPathComponent("cdk-ffi-\(name)-\(UUID().uuidString).sqlite")
+    return try Wallet.newWithStoragePath(
+        mintUrl: mintUrl,
+        unit: .sat,
+        mnemonic: try generateMnemonic(),
+        storagePath: storage.path,
+        config: WalletConfig(targetProofCount: nil)
+    )
+}
+
+let sendOptions = SendOptions(
+    memo: nil,
+    conditions: nil,
+    amountSplitTarget: .none,
+    sendKind: .onlineExact,
+    includeFee: false,
+    maxProofs: nil,
+    metadata: [:],
+    selectionStrategy: .smallestFirst,
+    includeDleq: false
+)
+
+let receiveOptions = ReceiveOptions(
+    amountSplitTarget: .none,
+    p2pkSigningKeys: [],
+    preimages: [],
+    metadata: [:],
+    allOrNothing: false
+)
+
+@main
+struct WalletSmokeTest {
+    static func main() async throws {
+        let mintUrl = ProcessInfo.processInfo.environment["MINT_URL"] ?? "http://127.0.0.1:8085"
+        let alice = try createWallet(mintUrl: mintUrl, name: "alice")
+        let bob = try createWallet(mintUrl: mintUrl, name: "bob")
+
+        // Mint, waiting for the quote to be paid through the observer
+        let quote = try await alice.mintQuote(amount: Amount(value: 100), description: nil)
+        let observer = PaidObserver()
+        let watch = try await alice.watchQuotes(
+            kind: .bolt11MintQuote, quoteIds: [quote.id], observer: observer)
+        _ = await observer.waitPaid()
+        watch.cancel()
+        _ = try await alice.mint(quoteId: quote.id, amountSplitTarget: .none, spendingConditions: nil)
+        precondition(try await alice.totalBalance().value == 100)
+
+        // Send to bob, through the serialized token
+        let token = try await alice.prepareSend(amount: Amount(value: 40), options: sendOptions)
+            .confirm(memo: "smoke test")
+        let parsed = try Token.decode(encodedToken: token.encode())
+        precondition(try parsed.value().value == 40)
+        precondition(try await bob.receive(token: parsed, options: receiveOptions).value == 40)
+
+        // Melt from bob, the invoice of a fresh mint quote is paid by the fake wallet backend
+        let invoice = try await bob.mintQuote(amount: Amount(value: 10), description: nil).request
+        let meltQuote = try await bob.meltQuote(request: invoice, options: nil)
+        let melted = try await bob.melt(quoteId: meltQuote.id)
+        precondition(melted.state == .paid)
+
+        precondition(try await alice.listTransactions(direction: nil).count == 2)
+        precondition(try await bob.listTransactions(direction: nil).count == 2)
+
+        // Errors are typed
+        do {
+            _ = try Token.decode(encodedToken: "cashuBinvalid")
+            fatalError("an invalid token was parsed")
+        } catch FfiError.Internal(let message) {
+            print("invalid token rejected: \(message)")
+        }
+        do {
+            _ = try await alice.melt(quoteId: "unknown-quote")
+            fatalError("an unknown quote was melted")
+        } catch FfiError.Cdk(let code, _) {
+            print("unknown quote rejected with code \(code)")
+        }
+
+        print("Swift smoke test passed")
+    }
+}
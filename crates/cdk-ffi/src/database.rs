@@ -603,6 +603,18 @@ impl CdkWalletDatabase<cdk::cdk_database::Error> for WalletDatabaseBridge {
             .map_err(|e| cdk::cdk_database::Error::Database(e.to_string().into()))
     }
 
+    async fn update_proofs_with_transaction(
+        &self,
+        added: Vec<cdk::types::ProofInfo>,
+        removed_ys: Vec<cdk::nuts::PublicKey>,
+        transaction: cdk::wallet::types::Transaction,
+    ) -> Result<(), cdk::cdk_database::Error> {
+        // The foreign database interface has no transactional write, so the
+        // proofs are updated first and the transaction recorded afterwards
+        self.update_proofs(added, removed_ys).await?;
+        self.add_transaction(transaction).await
+    }
+
     async fn update_mint_url(
         &self,
         old_mint_url: cdk::mint_url::MintUrl,
@@ -28,6 +28,7 @@ uniffi::setup_scaffolding!();
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::token::Token;
 
     #[test]
     fn test_amount_conversion() {
@@ -85,6 +86,7 @@ mod tests {
         let memo = SendMemo {
             memo: memo_text.clone(),
             include_memo: true,
+            include_mint_info: false,
         };
 
         assert_eq!(memo.memo, memo_text);
@@ -165,6 +167,7 @@ mod tests {
         let memo = SendMemo {
             memo: "Test memo".to_string(),
             include_memo: true,
+            include_mint_info: false,
         };
 
         let mut metadata = HashMap::new();
@@ -188,6 +191,8 @@ mod tests {
             include_fee: true,
             max_proofs: Some(10),
             metadata,
+            selection_strategy: SelectionStrategy::MinimizeFee,
+            include_dleq: true,
         };
 
         assert!(options.memo.is_some());
@@ -220,6 +225,8 @@ mod tests {
             p2pk_signing_keys: vec![secret_key],
             preimages: vec!["preimage1".to_string(), "preimage2".to_string()],
             metadata,
+            all_or_nothing: true,
+            defer_if_unreachable: false,
         };
 
         assert!(matches!(
@@ -293,4 +300,103 @@ mod tests {
         let invalid_result = mnemonic_to_entropy("invalid mnemonic".to_string());
         assert!(invalid_result.is_err());
     }
+
+    #[test]
+    fn test_wallet_object_surface() {
+        let storage_path = std::env::temp_dir()
+            .join(format!("cdk-ffi-{}.sqlite", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .into_owned();
+        let config = WalletConfig {
+            target_proof_count: None,
+        };
+
+        let wallet = Wallet::new_with_storage_path(
+            "https://mint.example.com".to_string(),
+            CurrencyUnit::Sat,
+            generate_mnemonic().unwrap(),
+            storage_path.clone(),
+            config.clone(),
+        )
+        .unwrap();
+        assert_eq!(wallet.unit(), CurrencyUnit::Sat);
+        assert_eq!(wallet.mint_url().url, "https://mint.example.com");
+
+        let runtime = tokio::runtime::Runtime::new().unwrap();
+        runtime.block_on(async {
+            assert!(wallet.total_balance().await.unwrap().is_zero());
+            assert!(wallet.list_transactions(None).await.unwrap().is_empty());
+        });
+
+        // Errors are typed for the foreign code
+        let invalid_mnemonic = Wallet::new_with_storage_path(
+            "https://mint.example.com".to_string(),
+            CurrencyUnit::Sat,
+            "invalid mnemonic".to_string(),
+            storage_path.clone(),
+            config,
+        );
+        assert!(matches!(invalid_mnemonic, Err(FfiError::Internal { .. })));
+        assert!(Token::decode("cashuBinvalid".to_string()).is_err());
+
+        let _ = std::fs::remove_file(storage_path);
+    }
+
+    #[test]
+    fn test_token_parse_and_serialize() {
+        let token = Token::decode("cashuBpGF0gaJhaUgArSaMTR9YJmFwgaNhYQFhc3hAOWE2ZGJiODQ3YmQyMzJiYTc2ZGIwZGYxOTcyMTZiMjlkM2I4Y2MxNDU1M2NkMjc4MjdmYzFjYzk0MmZlZGI0ZWFjWCEDhhhUP_trhpXfStS6vN6So0qWvc2X3O4NfM-Y1HISZ5JhZGlUaGFuayB5b3VhbXVodHRwOi8vbG9jYWxob3N0OjMzMzhhdWNzYXQ=".to_string()).unwrap();
+        assert_eq!(token.value().unwrap().value, 1);
+        assert_eq!(token.unit(), Some(CurrencyUnit::Sat));
+
+        let decoded = Token::decode(token.encode()).unwrap();
+        assert_eq!(decoded.value().unwrap().value, 1);
+        assert_eq!(decoded.mint_url().unwrap(), token.mint_url().unwrap());
+        assert_eq!(decoded.memo(), token.memo());
+    }
+
+    #[test]
+    fn test_quote_observer_callbacks() {
+        #[derive(Default)]
+        struct RecordingObserver(std::sync::Mutex<Vec<String>>);
+
+        impl QuoteObserver for RecordingObserver {
+            fn on_mint_quote_update(&self, quote: MintQuoteBolt11Response) {
+                self.0.lock().unwrap().push(format!("mint {}", quote.quote));
+            }
+
+            fn on_melt_quote_update(&self, quote: MeltQuoteBolt11Response) {
+                self.0.lock().unwrap().push(format!("melt {}", quote.quote));
+            }
+
+            fn on_melt_progress(&self, quote_id: String, progress: MeltProgress, elapsed_ms: u64) {
+                self.0
+                    .lock()
+                    .unwrap()
+                    .push(format!("progress {quote_id} {progress:?} {elapsed_ms}"));
+            }
+        }
+
+        let observer = RecordingObserver::default();
+        notify_observer(
+            &observer,
+            NotificationPayload::MeltProgress {
+                quote: "quote-1".to_string(),
+                progress: MeltProgress::Attempting,
+                elapsed_ms: 5000,
+                time: 0,
+            },
+        );
+        // Notifications of other kinds are not for the observer
+        notify_observer(
+            &observer,
+            NotificationPayload::ProofState {
+                proof_states: vec![],
+            },
+        );
+
+        assert_eq!(
+            *observer.0.lock().unwrap(),
+            vec!["progress quote-1 Attempting 5000".to_string()]
+        );
+    }
 }
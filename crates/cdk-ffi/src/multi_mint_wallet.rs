@@ -570,6 +570,20 @@ impl MultiMintWallet {
         Ok(result.into())
     }
 
+    /// Resume a transfer whose melt was paid but whose tokens were not yet minted
+    pub async fn resume_transfer(
+        &self,
+        target_mint: MintUrl,
+        mint_quote_id: String,
+    ) -> Result<Amount, FfiError> {
+        let target_cdk: cdk::mint_url::MintUrl = target_mint.try_into()?;
+        let amount = self
+            .inner
+            .resume_transfer(&target_cdk, &mint_quote_id)
+            .await?;
+        Ok(amount.into())
+    }
+
     /// Swap proofs with automatic wallet selection
     pub async fn swap(
         &self,
@@ -60,6 +60,11 @@ impl Token {
         self.inner.memo().clone()
     }
 
+    /// Get the memo text, without the mint name and icon of a structured memo
+    pub fn memo_text(&self) -> Option<String> {
+        self.inner.memo_text()
+    }
+
     /// Get the currency unit
     pub fn unit(&self) -> Option<CurrencyUnit> {
         self.inner.unit().map(Into::into)
@@ -36,6 +36,7 @@ impl From<KeySetInfo> for cdk::nuts::KeySetInfo {
             unit: keyset.unit.into(),
             active: keyset.active,
             final_expiry: None,
+            max_amount: None,
             input_fee_ppk: keyset.input_fee_ppk,
         }
     }
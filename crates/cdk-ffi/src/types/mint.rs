@@ -115,6 +115,24 @@ impl From<ContactInfo> for cdk::nuts::ContactInfo {
     }
 }
 
+/// FFI-compatible PreviousPubkey
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct PreviousPubkey {
+    /// hex pubkey
+    pub pubkey: String,
+    /// Unix timestamp until which announcements signed by this pubkey are accepted
+    pub expires_at: u64,
+}
+
+impl From<cdk::nuts::PreviousPubkey> for PreviousPubkey {
+    fn from(previous: cdk::nuts::PreviousPubkey) -> Self {
+        Self {
+            pubkey: previous.pubkey.to_string(),
+            expires_at: previous.expires_at,
+        }
+    }
+}
+
 impl ContactInfo {
     /// Convert ContactInfo to JSON string
     pub fn to_json(&self) -> Result<String, FfiError> {
@@ -577,6 +595,9 @@ impl TryFrom<Nuts> for cdk::nuts::Nuts {
             },
             nut21: n.nut21.map(|s| s.try_into()).transpose()?,
             nut22: n.nut22.map(|s| s.try_into()).transpose()?,
+            request_limits: None,
+            melt_change: None,
+            units: Vec::new(),
         })
     }
 }
@@ -607,6 +628,9 @@ pub struct MintInfo {
     pub name: Option<String>,
     /// hex pubkey of the mint
     pub pubkey: Option<String>,
+    /// Pubkeys the mint rotated away from
+    #[serde(default)]
+    pub previous_pubkeys: Vec<PreviousPubkey>,
     /// implementation name and the version running
     pub version: Option<MintVersion>,
     /// short description of the mint
@@ -634,6 +658,7 @@ impl From<cdk::nuts::MintInfo> for MintInfo {
         Self {
             name: info.name,
             pubkey: info.pubkey.map(|p| p.to_string()),
+            previous_pubkeys: info.previous_pubkeys.into_iter().map(Into::into).collect(),
             version: info.version.map(Into::into),
             description: info.description,
             description_long: info.description_long,
@@ -657,6 +682,16 @@ impl From<MintInfo> for cdk::nuts::MintInfo {
         Self {
             name: info.name,
             pubkey: info.pubkey.and_then(|p| p.parse().ok()),
+            previous_pubkeys: info
+                .previous_pubkeys
+                .into_iter()
+                .filter_map(|previous| {
+                    Some(cdk::nuts::PreviousPubkey {
+                        pubkey: previous.pubkey.parse().ok()?,
+                        expires_at: previous.expires_at,
+                    })
+                })
+                .collect(),
             version: info.version.map(Into::into),
             description: info.description,
             description_long: info.description_long,
@@ -749,6 +784,9 @@ mod tests {
                     ),
                 )],
             }),
+            request_limits: None,
+            melt_change: None,
+            units: Vec::new(),
         }
     }
 
@@ -888,6 +926,9 @@ mod tests {
             nut20: cdk::nuts::nut06::SupportedSettings { supported: false },
             nut21: None,
             nut22: None,
+            request_limits: None,
+            melt_change: None,
+            units: Vec::new(),
         };
 
         let ffi_nuts: Nuts = cdk_nuts.into();
@@ -128,6 +128,10 @@ pub struct MintQuoteBolt11Response {
     pub unit: Option<CurrencyUnit>,
     /// Pubkey (optional)
     pub pubkey: Option<String>,
+    /// Amount received so far (optional)
+    pub amount_paid: Option<Amount>,
+    /// Whether the request was only partially paid
+    pub partial: bool,
 }
 
 impl From<cdk::nuts::MintQuoteBolt11Response<String>> for MintQuoteBolt11Response {
@@ -140,6 +144,8 @@ impl From<cdk::nuts::MintQuoteBolt11Response<String>> for MintQuoteBolt11Respons
             amount: response.amount.map(Into::into),
             unit: response.unit.map(Into::into),
             pubkey: response.pubkey.map(|p| p.to_string()),
+            amount_paid: response.amount_paid.map(Into::into),
+            partial: response.partial,
         }
     }
 }
@@ -192,6 +198,41 @@ impl From<cdk::nuts::MintQuoteCustomResponse<String>> for MintQuoteCustomRespons
     }
 }
 
+/// FFI-compatible MeltQuoteFees
+#[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
+pub struct MeltQuoteFees {
+    /// Fee reserved for the lightning payment
+    pub ln_fee_reserve: Amount,
+    /// Input fee in parts per thousand per proof
+    pub input_fee_ppk: u64,
+    /// Lightning fee actually paid (optional)
+    pub ln_fee_paid: Option<Amount>,
+    /// Melt was settled internally by the mint, without a lightning payment
+    pub internal: bool,
+}
+
+impl From<cdk::nuts::MeltQuoteFees> for MeltQuoteFees {
+    fn from(fees: cdk::nuts::MeltQuoteFees) -> Self {
+        Self {
+            ln_fee_reserve: fees.ln_fee_reserve.into(),
+            input_fee_ppk: fees.input_fee_ppk,
+            ln_fee_paid: fees.ln_fee_paid.map(Into::into),
+            internal: fees.internal,
+        }
+    }
+}
+
+impl From<MeltQuoteFees> for cdk::nuts::MeltQuoteFees {
+    fn from(fees: MeltQuoteFees) -> Self {
+        Self {
+            ln_fee_reserve: fees.ln_fee_reserve.into(),
+            input_fee_ppk: fees.input_fee_ppk,
+            ln_fee_paid: fees.ln_fee_paid.map(Into::into),
+            internal: fees.internal,
+        }
+    }
+}
+
 /// FFI-compatible MeltQuoteBolt11Response
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct MeltQuoteBolt11Response {
@@ -211,6 +252,10 @@ pub struct MeltQuoteBolt11Response {
     pub request: Option<String>,
     /// Unit (optional)
     pub unit: Option<CurrencyUnit>,
+    /// Fee breakdown (optional)
+    pub fees: Option<MeltQuoteFees>,
+    /// Amount spent, the quote amount and the lightning fee (optional)
+    pub amount_spent: Option<Amount>,
 }
 
 impl From<cdk::nuts::MeltQuoteBolt11Response<String>> for MeltQuoteBolt11Response {
@@ -224,6 +269,8 @@ impl From<cdk::nuts::MeltQuoteBolt11Response<String>> for MeltQuoteBolt11Respons
             payment_preimage: response.payment_preimage,
             request: response.request,
             unit: response.unit.map(Into::into),
+            fees: response.fees.map(Into::into),
+            amount_spent: response.amount_spent.map(Into::into),
         }
     }
 }
@@ -333,6 +380,9 @@ pub struct MeltQuote {
     pub payment_preimage: Option<String>,
     /// Payment method
     pub payment_method: PaymentMethod,
+    /// Fee breakdown (optional)
+    #[serde(default)]
+    pub fees: Option<MeltQuoteFees>,
 }
 
 impl From<cdk::wallet::MeltQuote> for MeltQuote {
@@ -347,6 +397,7 @@ impl From<cdk::wallet::MeltQuote> for MeltQuote {
             expiry: quote.expiry,
             payment_preimage: quote.payment_preimage.clone(),
             payment_method: quote.payment_method.into(),
+            fees: quote.fees.map(Into::into),
         }
     }
 }
@@ -365,6 +416,7 @@ impl TryFrom<MeltQuote> for cdk::wallet::MeltQuote {
             expiry: quote.expiry,
             payment_preimage: quote.payment_preimage,
             payment_method: quote.payment_method.into(),
+            fees: quote.fees.map(Into::into),
         })
     }
 }
@@ -4,6 +4,7 @@ use std::sync::Arc;
 use cdk::event::MintEvent;
 use serde::{Deserialize, Serialize};
 
+use super::amount::{Amount, CurrencyUnit};
 use super::proof::ProofStateUpdate;
 use super::quote::{MeltQuoteBolt11Response, MintQuoteBolt11Response};
 use crate::error::FfiError;
@@ -19,6 +20,12 @@ pub enum SubscriptionKind {
     Bolt12MintQuote,
     /// Proof State
     ProofState,
+    /// Internal settlement of a melt quote
+    InternalSettlement,
+    /// Progress of the payment of a Bolt 11 melt quote
+    Bolt11MeltProgress,
+    /// Mint info and active keyset changes
+    MintInfo,
 }
 
 impl From<SubscriptionKind> for cdk::nuts::nut17::Kind {
@@ -28,6 +35,9 @@ impl From<SubscriptionKind> for cdk::nuts::nut17::Kind {
             SubscriptionKind::Bolt11MintQuote => cdk::nuts::nut17::Kind::Bolt11MintQuote,
             SubscriptionKind::Bolt12MintQuote => cdk::nuts::nut17::Kind::Bolt12MintQuote,
             SubscriptionKind::ProofState => cdk::nuts::nut17::Kind::ProofState,
+            SubscriptionKind::InternalSettlement => cdk::nuts::nut17::Kind::InternalSettlement,
+            SubscriptionKind::Bolt11MeltProgress => cdk::nuts::nut17::Kind::Bolt11MeltProgress,
+            SubscriptionKind::MintInfo => cdk::nuts::nut17::Kind::MintInfo,
         }
     }
 }
@@ -39,6 +49,9 @@ impl From<cdk::nuts::nut17::Kind> for SubscriptionKind {
             cdk::nuts::nut17::Kind::Bolt11MintQuote => SubscriptionKind::Bolt11MintQuote,
             cdk::nuts::nut17::Kind::Bolt12MintQuote => SubscriptionKind::Bolt12MintQuote,
             cdk::nuts::nut17::Kind::ProofState => SubscriptionKind::ProofState,
+            cdk::nuts::nut17::Kind::InternalSettlement => SubscriptionKind::InternalSettlement,
+            cdk::nuts::nut17::Kind::Bolt11MeltProgress => SubscriptionKind::Bolt11MeltProgress,
+            cdk::nuts::nut17::Kind::MintInfo => SubscriptionKind::MintInfo,
         }
     }
 }
@@ -131,6 +144,24 @@ impl ActiveSubscription {
     }
 }
 
+/// FFI-compatible progress of a melt before it reaches its final state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, uniffi::Enum)]
+pub enum MeltProgress {
+    /// The inputs were accepted and the payment started
+    Pending,
+    /// The payment is still in flight
+    Attempting,
+}
+
+impl From<cdk::nuts::nut17::MeltProgress> for MeltProgress {
+    fn from(progress: cdk::nuts::nut17::MeltProgress) -> Self {
+        match progress {
+            cdk::nuts::nut17::MeltProgress::Pending => MeltProgress::Pending,
+            cdk::nuts::nut17::MeltProgress::Attempting => MeltProgress::Attempting,
+        }
+    }
+}
+
 /// FFI-compatible NotificationPayload
 #[derive(Debug, Clone, uniffi::Enum)]
 pub enum NotificationPayload {
@@ -140,6 +171,29 @@ pub enum NotificationPayload {
     MintQuoteUpdate { quote: MintQuoteBolt11Response },
     /// Melt quote update
     MeltQuoteUpdate { quote: MeltQuoteBolt11Response },
+    /// Melt quote settled internally against a mint quote
+    InternalSettlement {
+        melt_quote: String,
+        amount: Amount,
+        unit: CurrencyUnit,
+        time: u64,
+    },
+    /// Progress of the payment of a melt quote
+    MeltProgress {
+        quote: String,
+        progress: MeltProgress,
+        /// Milliseconds since the payment started
+        elapsed_ms: u64,
+        time: u64,
+    },
+    /// Mint info or active keysets changed
+    MintInfoUpdate {
+        /// Top level fields of the mint info that changed
+        changed_fields: Vec<String>,
+        /// Active keyset ids after the change
+        active_keysets: Vec<String>,
+        time: u64,
+    },
 }
 
 impl From<MintEvent<String>> for NotificationPayload {
@@ -158,6 +212,33 @@ impl From<MintEvent<String>> for NotificationPayload {
                     quote: quote_resp.into(),
                 }
             }
+            cdk::nuts::NotificationPayload::InternalSettlement(settlement) => {
+                NotificationPayload::InternalSettlement {
+                    melt_quote: settlement.melt_quote,
+                    amount: settlement.amount.into(),
+                    unit: settlement.unit.into(),
+                    time: settlement.time,
+                }
+            }
+            cdk::nuts::NotificationPayload::MeltProgress(progress) => {
+                NotificationPayload::MeltProgress {
+                    quote: progress.quote,
+                    progress: progress.progress.into(),
+                    elapsed_ms: progress.elapsed_ms,
+                    time: progress.time,
+                }
+            }
+            cdk::nuts::NotificationPayload::MintInfo(notification) => {
+                NotificationPayload::MintInfoUpdate {
+                    changed_fields: notification.changed.into_keys().collect(),
+                    active_keysets: notification
+                        .active_keysets
+                        .iter()
+                        .map(ToString::to_string)
+                        .collect(),
+                    time: notification.time,
+                }
+            }
             _ => {
                 // For now, handle other notification types as empty ProofState
                 NotificationPayload::ProofState {
@@ -167,3 +248,67 @@ impl From<MintEvent<String>> for NotificationPayload {
         }
     }
 }
+
+/// Observer of quote updates, implemented by the foreign code
+///
+/// Passed to [`crate::Wallet::watch_quotes`], it is called from a background task for every
+/// update of the watched quotes.
+#[uniffi::export(with_foreign)]
+pub trait QuoteObserver: Send + Sync {
+    /// A watched mint quote changed
+    fn on_mint_quote_update(&self, quote: MintQuoteBolt11Response);
+
+    /// A watched melt quote changed
+    fn on_melt_quote_update(&self, quote: MeltQuoteBolt11Response);
+
+    /// The payment of a watched melt quote is still in flight
+    fn on_melt_progress(&self, quote_id: String, progress: MeltProgress, elapsed_ms: u64);
+}
+
+/// Forward a notification to the callback of `observer` it is for
+pub(crate) fn notify_observer(observer: &dyn QuoteObserver, payload: NotificationPayload) {
+    match payload {
+        NotificationPayload::MintQuoteUpdate { quote } => observer.on_mint_quote_update(quote),
+        NotificationPayload::MeltQuoteUpdate { quote } => observer.on_melt_quote_update(quote),
+        NotificationPayload::MeltProgress {
+            quote,
+            progress,
+            elapsed_ms,
+            ..
+        } => observer.on_melt_progress(quote, progress, elapsed_ms),
+        _ => {}
+    }
+}
+
+/// Quotes watched by a [`QuoteObserver`]
+///
+/// The observer is called until [`QuoteWatch::cancel`] is called or the watch is dropped.
+#[derive(uniffi::Object)]
+pub struct QuoteWatch {
+    task: tokio::task::JoinHandle<()>,
+}
+
+impl QuoteWatch {
+    pub(crate) fn new(task: tokio::task::JoinHandle<()>) -> Self {
+        Self { task }
+    }
+}
+
+#[uniffi::export]
+impl QuoteWatch {
+    /// Stop calling the observer
+    pub fn cancel(&self) {
+        self.task.abort();
+    }
+
+    /// Whether the observer is no longer called
+    pub fn is_cancelled(&self) -> bool {
+        self.task.is_finished()
+    }
+}
+
+impl Drop for QuoteWatch {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
@@ -42,6 +42,15 @@ pub struct Transaction {
     pub payment_proof: Option<String>,
     /// Payment method (e.g., Bolt11, Bolt12) for mint/melt transactions
     pub payment_method: Option<PaymentMethod>,
+    /// Operation that produced the transaction
+    #[serde(default)]
+    pub kind: Option<TransactionKind>,
+    /// Payment proof was checked against the payment hash of the request
+    #[serde(default)]
+    pub preimage_verified: bool,
+    /// Amount spent by a melt as reported by the mint
+    #[serde(default)]
+    pub amount_spent: Option<Amount>,
 }
 
 impl From<cdk::wallet::types::Transaction> for Transaction {
@@ -61,6 +70,9 @@ impl From<cdk::wallet::types::Transaction> for Transaction {
             payment_request: tx.payment_request,
             payment_proof: tx.payment_proof,
             payment_method: tx.payment_method.map(Into::into),
+            kind: tx.kind.map(Into::into),
+            preimage_verified: tx.preimage_verified,
+            amount_spent: tx.amount_spent.map(Into::into),
         }
     }
 }
@@ -88,6 +100,9 @@ impl TryFrom<Transaction> for cdk::wallet::types::Transaction {
             payment_request: tx.payment_request,
             payment_proof: tx.payment_proof,
             payment_method: tx.payment_method.map(Into::into),
+            kind: tx.kind.map(Into::into),
+            preimage_verified: tx.preimage_verified,
+            amount_spent: tx.amount_spent.map(Into::into),
         })
     }
 }
@@ -153,6 +168,45 @@ impl From<TransactionDirection> for cdk::wallet::types::TransactionDirection {
     }
 }
 
+/// FFI-compatible TransactionKind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, uniffi::Enum)]
+pub enum TransactionKind {
+    /// Proofs issued for a paid mint quote
+    Mint,
+    /// Proofs spent to pay a melt quote
+    Melt,
+    /// Proofs sent as a token
+    Send,
+    /// Token received into the wallet
+    Receive,
+    /// Proofs swapped for new proofs at the same mint
+    Swap,
+}
+
+impl From<cdk::wallet::types::TransactionKind> for TransactionKind {
+    fn from(kind: cdk::wallet::types::TransactionKind) -> Self {
+        match kind {
+            cdk::wallet::types::TransactionKind::Mint => TransactionKind::Mint,
+            cdk::wallet::types::TransactionKind::Melt => TransactionKind::Melt,
+            cdk::wallet::types::TransactionKind::Send => TransactionKind::Send,
+            cdk::wallet::types::TransactionKind::Receive => TransactionKind::Receive,
+            cdk::wallet::types::TransactionKind::Swap => TransactionKind::Swap,
+        }
+    }
+}
+
+impl From<TransactionKind> for cdk::wallet::types::TransactionKind {
+    fn from(kind: TransactionKind) -> Self {
+        match kind {
+            TransactionKind::Mint => cdk::wallet::types::TransactionKind::Mint,
+            TransactionKind::Melt => cdk::wallet::types::TransactionKind::Melt,
+            TransactionKind::Send => cdk::wallet::types::TransactionKind::Send,
+            TransactionKind::Receive => cdk::wallet::types::TransactionKind::Receive,
+            TransactionKind::Swap => cdk::wallet::types::TransactionKind::Swap,
+        }
+    }
+}
+
 /// FFI-compatible TransactionId
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 #[serde(transparent)]
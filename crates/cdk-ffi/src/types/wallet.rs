@@ -17,6 +17,9 @@ pub struct SendMemo {
     pub memo: String,
     /// Include memo in token
     pub include_memo: bool,
+    /// Include the name and icon of the mint in the token memo
+    #[serde(default)]
+    pub include_mint_info: bool,
 }
 
 impl From<SendMemo> for cdk::wallet::SendMemo {
@@ -24,6 +27,7 @@ impl From<SendMemo> for cdk::wallet::SendMemo {
         cdk::wallet::SendMemo {
             memo: memo.memo,
             include_memo: memo.include_memo,
+            include_mint_info: memo.include_mint_info,
         }
     }
 }
@@ -33,6 +37,7 @@ impl From<cdk::wallet::SendMemo> for SendMemo {
         Self {
             memo: memo.memo,
             include_memo: memo.include_memo,
+            include_mint_info: memo.include_mint_info,
         }
     }
 }
@@ -99,6 +104,46 @@ impl From<cdk::wallet::SendKind> for SendKind {
     }
 }
 
+/// FFI-compatible SelectionStrategy
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, uniffi::Enum)]
+pub enum SelectionStrategy {
+    /// Spend the smallest proofs first, consolidating small denominations
+    SmallestFirst,
+    /// Spend the largest proofs first, using as few proofs as possible
+    LargestFirst,
+    /// Prefer proofs that exactly match the optimal split of the amount
+    #[default]
+    ExactMatchPreferred,
+    /// Prefer the proofs that result in the lowest input fee
+    MinimizeFee,
+}
+
+impl From<SelectionStrategy> for cdk::wallet::SelectionStrategy {
+    fn from(strategy: SelectionStrategy) -> Self {
+        match strategy {
+            SelectionStrategy::SmallestFirst => cdk::wallet::SelectionStrategy::SmallestFirst,
+            SelectionStrategy::LargestFirst => cdk::wallet::SelectionStrategy::LargestFirst,
+            SelectionStrategy::ExactMatchPreferred => {
+                cdk::wallet::SelectionStrategy::ExactMatchPreferred
+            }
+            SelectionStrategy::MinimizeFee => cdk::wallet::SelectionStrategy::MinimizeFee,
+        }
+    }
+}
+
+impl From<cdk::wallet::SelectionStrategy> for SelectionStrategy {
+    fn from(strategy: cdk::wallet::SelectionStrategy) -> Self {
+        match strategy {
+            cdk::wallet::SelectionStrategy::SmallestFirst => SelectionStrategy::SmallestFirst,
+            cdk::wallet::SelectionStrategy::LargestFirst => SelectionStrategy::LargestFirst,
+            cdk::wallet::SelectionStrategy::ExactMatchPreferred => {
+                SelectionStrategy::ExactMatchPreferred
+            }
+            cdk::wallet::SelectionStrategy::MinimizeFee => SelectionStrategy::MinimizeFee,
+        }
+    }
+}
+
 /// FFI-compatible Send options
 #[derive(Debug, Clone, Serialize, Deserialize, uniffi::Record)]
 pub struct SendOptions {
@@ -116,6 +161,12 @@ pub struct SendOptions {
     pub max_proofs: Option<u32>,
     /// Metadata
     pub metadata: HashMap<String, String>,
+    /// Strategy used to select the proofs to send
+    #[serde(default)]
+    pub selection_strategy: SelectionStrategy,
+    /// Include the DLEQ proof of every proof in the token
+    #[serde(default)]
+    pub include_dleq: bool,
 }
 
 impl Default for SendOptions {
@@ -128,6 +179,8 @@ impl Default for SendOptions {
             include_fee: false,
             max_proofs: None,
             metadata: HashMap::new(),
+            selection_strategy: SelectionStrategy::default(),
+            include_dleq: false,
         }
     }
 }
@@ -142,6 +195,8 @@ impl From<SendOptions> for cdk::wallet::SendOptions {
             include_fee: opts.include_fee,
             max_proofs: opts.max_proofs.map(|p| p as usize),
             metadata: opts.metadata,
+            selection_strategy: opts.selection_strategy.into(),
+            include_dleq: opts.include_dleq,
         }
     }
 }
@@ -156,6 +211,8 @@ impl From<cdk::wallet::SendOptions> for SendOptions {
             include_fee: opts.include_fee,
             max_proofs: opts.max_proofs.map(|p| p as u32),
             metadata: opts.metadata,
+            selection_strategy: opts.selection_strategy.into(),
+            include_dleq: opts.include_dleq,
         }
     }
 }
@@ -243,6 +300,12 @@ pub struct ReceiveOptions {
     pub preimages: Vec<String>,
     /// Metadata
     pub metadata: HashMap<String, String>,
+    /// Fail if any proof is already spent or pending instead of claiming the others
+    #[serde(default)]
+    pub all_or_nothing: bool,
+    /// Store the token to claim it later if the mint can't be reached, instead of failing
+    #[serde(default)]
+    pub defer_if_unreachable: bool,
 }
 
 impl Default for ReceiveOptions {
@@ -252,6 +315,8 @@ impl Default for ReceiveOptions {
             p2pk_signing_keys: Vec::new(),
             preimages: Vec::new(),
             metadata: HashMap::new(),
+            all_or_nothing: false,
+            defer_if_unreachable: false,
         }
     }
 }
@@ -263,6 +328,8 @@ impl From<ReceiveOptions> for cdk::wallet::ReceiveOptions {
             p2pk_signing_keys: opts.p2pk_signing_keys.into_iter().map(Into::into).collect(),
             preimages: opts.preimages,
             metadata: opts.metadata,
+            all_or_nothing: opts.all_or_nothing,
+            defer_if_unreachable: opts.defer_if_unreachable,
         }
     }
 }
@@ -274,6 +341,8 @@ impl From<cdk::wallet::ReceiveOptions> for ReceiveOptions {
             p2pk_signing_keys: opts.p2pk_signing_keys.into_iter().map(Into::into).collect(),
             preimages: opts.preimages,
             metadata: opts.metadata,
+            all_or_nothing: opts.all_or_nothing,
+            defer_if_unreachable: opts.defer_if_unreachable,
         }
     }
 }
@@ -342,6 +411,20 @@ impl PreparedSend {
         self.proofs.clone()
     }
 
+    /// Get the value of the token: the amount plus the fee the receiver pays to redeem it
+    /// when fees are included
+    pub fn gross_amount(&self) -> Amount {
+        if let Ok(guard) = self.inner.lock() {
+            if let Some(ref inner) = *guard {
+                inner.gross_amount().into()
+            } else {
+                self.amount
+            }
+        } else {
+            self.amount
+        }
+    }
+
     /// Get the total fee for this send operation
     pub fn fee(&self) -> Amount {
         if let Ok(guard) = self.inner.lock() {
@@ -408,6 +491,9 @@ pub struct Melted {
     pub change: Option<Proofs>,
     pub amount: Amount,
     pub fee_paid: Amount,
+    pub fees: Option<super::quote::MeltQuoteFees>,
+    pub preimage_verified: bool,
+    pub amount_spent: Option<Amount>,
 }
 
 // MeltQuoteState is just an alias for nut05::QuoteState, so we don't need a separate implementation
@@ -422,6 +508,9 @@ impl From<cdk::types::Melted> for Melted {
                 .map(|proofs| proofs.into_iter().map(|p| p.into()).collect()),
             amount: melted.amount.into(),
             fee_paid: melted.fee_paid.into(),
+            fees: melted.fees.map(Into::into),
+            preimage_verified: melted.preimage_verified,
+            amount_spent: melted.amount_spent.map(Into::into),
         }
     }
 }
@@ -59,6 +59,19 @@ impl Wallet {
         })
     }
 
+    /// Create a new Wallet from mnemonic, stored in a SQLite database at `storage_path`
+    #[uniffi::constructor]
+    pub fn new_with_storage_path(
+        mint_url: String,
+        unit: CurrencyUnit,
+        mnemonic: String,
+        storage_path: String,
+        config: WalletConfig,
+    ) -> Result<Self, FfiError> {
+        let db = crate::sqlite::WalletSqliteDatabase::new(storage_path)?;
+        Self::new(mint_url, unit, mnemonic, db, config)
+    }
+
     /// Get the mint URL
     pub fn mint_url(&self) -> MintUrl {
         self.inner.mint_url.clone().into()
@@ -143,6 +156,12 @@ impl Wallet {
         Ok(amount.into())
     }
 
+    /// Recover the mint quotes of the wallet missing from its storage
+    pub async fn recover_mint_quotes(&self) -> Result<Vec<MintQuote>, FfiError> {
+        let quotes = self.inner.recover_mint_quotes().await?;
+        Ok(quotes.into_iter().map(Into::into).collect())
+    }
+
     /// Verify token DLEQ proofs
     pub async fn verify_token_dleq(&self, token: std::sync::Arc<Token>) -> Result<(), FfiError> {
         let cdk_token = token.inner.clone();
@@ -449,8 +468,12 @@ impl Wallet {
         &self,
         direction: Option<TransactionDirection>,
     ) -> Result<Vec<Transaction>, FfiError> {
-        let cdk_direction = direction.map(Into::into);
-        let transactions = self.inner.list_transactions(cdk_direction).await?;
+        let cdk_direction: Option<cdk::wallet::types::TransactionDirection> =
+            direction.map(Into::into);
+        let transactions = self
+            .inner
+            .list_transactions(cdk_direction.into(), Default::default())
+            .await?;
         Ok(transactions.into_iter().map(Into::into).collect())
     }
 
@@ -497,6 +520,41 @@ impl Wallet {
         )))
     }
 
+    /// Call `observer` on every update of the given quotes
+    ///
+    /// `kind` is one of the quote subscription kinds, e.g. [`SubscriptionKind::Bolt11MeltQuote`]
+    /// for melt quotes, or [`SubscriptionKind::Bolt11MeltProgress`] for the progress of their
+    /// payment.
+    pub async fn watch_quotes(
+        &self,
+        kind: SubscriptionKind,
+        quote_ids: Vec<String>,
+        observer: Arc<dyn QuoteObserver>,
+    ) -> Result<Arc<QuoteWatch>, FfiError> {
+        if matches!(
+            kind,
+            SubscriptionKind::ProofState | SubscriptionKind::MintInfo
+        ) {
+            return Err(FfiError::internal(format!("{kind:?} is not a quote kind")));
+        }
+
+        let params: cdk::nuts::nut17::Params<Arc<String>> = SubscribeParams {
+            kind,
+            filters: quote_ids,
+            id: None,
+        }
+        .into();
+        let mut subscription = self.inner.subscribe(params).await?;
+
+        let task = tokio::spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                notify_observer(observer.as_ref(), event.into());
+            }
+        });
+
+        Ok(Arc::new(QuoteWatch::new(task)))
+    }
+
     /// Refresh keysets from the mint
     pub async fn refresh_keysets(&self) -> Result<Vec<KeySetInfo>, FfiError> {
         let keysets = self.inner.refresh_keysets().await?;
@@ -79,7 +79,9 @@ async fn start_cln_mint(
         rpc_path: cln_rpc_path,
         bolt12: false,
         fee_percent: 0.0,
-        reserve_fee_min: 0.into(),
+        // Leave room for the routing fee of payments forwarded by another node
+        reserve_fee_min: 10.into(),
+        limits: Default::default(),
     };
 
     // Create settings struct for CLN mint using shared function
@@ -151,6 +153,7 @@ async fn start_lnd_mint(
         macaroon_file: lnd_macaroon_file,
         fee_percent: 0.0,
         reserve_fee_min: 0.into(),
+        limits: Default::default(),
     };
 
     // Create settings struct for LND mint using shared function
@@ -222,6 +225,7 @@ async fn start_ldk_mint(
         rgs_url: None,
         webserver_host: Some("127.0.0.1".to_string()),
         webserver_port: Some(port + 1), // Use next port for web interface
+        limits: Default::default(),
     };
 
     // Create settings struct for LDK mint using a new shared function
@@ -287,6 +291,8 @@ fn create_ldk_settings(
             max_mint: 500_000.into(),
             min_melt: 1.into(),
             max_melt: 500_000.into(),
+            max_concurrent_payments: None,
+            payment_queue_timeout_secs: None,
         },
         cln: None,
         lnbits: None,
@@ -2,12 +2,14 @@ use std::collections::{HashMap, HashSet};
 use std::fmt::{Debug, Formatter};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
 use std::{env, fs};
 
 use anyhow::{anyhow, bail, Result};
 use async_trait::async_trait;
 use bip39::Mnemonic;
+use bitcoin::hashes::Hash;
 use cashu::nut00::KnownMethod;
 use cashu::quote_id::QuoteId;
 use cashu::{
@@ -21,8 +23,8 @@ use cdk::nuts::nut00::ProofsMethods;
 use cdk::nuts::{
     CheckStateRequest, CheckStateResponse, CurrencyUnit, Id, KeySet, KeysetResponse,
     MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintRequest, MintResponse, PaymentMethod, RestoreRequest,
-    RestoreResponse, SwapRequest, SwapResponse,
+    MintQuoteBolt11Response, MintQuotesRequest, MintQuotesResponse, MintRequest, MintResponse,
+    PaymentMethod, RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
 };
 use cdk::types::{FeeReserve, QuoteTTL};
 use cdk::util::unix_time;
@@ -33,9 +35,16 @@ use tokio::sync::RwLock;
 use tracing_subscriber::EnvFilter;
 use uuid::Uuid;
 
+/// Metadata of the LNURL-pay endpoint served by [`DirectMintConnection`]
+pub const MOCK_LNURL_METADATA: &str = r#"[["text/plain","Pay to the test node"]]"#;
+
 pub struct DirectMintConnection {
     pub mint: Mint,
     auth_wallet: Arc<RwLock<Option<AuthWallet>>>,
+    drop_responses: Arc<AtomicBool>,
+    offline: Arc<AtomicBool>,
+    lnurl_offline: Arc<AtomicBool>,
+    reject_mints: Arc<AtomicBool>,
 }
 
 impl DirectMintConnection {
@@ -43,7 +52,57 @@ impl DirectMintConnection {
         Self {
             mint,
             auth_wallet: Arc::new(RwLock::new(None)),
+            drop_responses: Arc::new(AtomicBool::new(false)),
+            offline: Arc::new(AtomicBool::new(false)),
+            lnurl_offline: Arc::new(AtomicBool::new(false)),
+            reject_mints: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Let the mint process mint, swap and melt requests but fail them as if the connection
+    /// was lost before the response arrived
+    pub fn drop_responses(&self, drop: bool) {
+        self.drop_responses.store(drop, Ordering::SeqCst);
+    }
+
+    /// Fail the requests for mint info, keysets, proof states and swaps as if the mint could not
+    /// be reached
+    pub fn set_offline(&self, offline: bool) {
+        self.offline.store(offline, Ordering::SeqCst);
+    }
+
+    /// Fail the requests to the mocked LNURL-pay endpoint as if it could not be reached
+    pub fn set_lnurl_offline(&self, offline: bool) {
+        self.lnurl_offline.store(offline, Ordering::SeqCst);
+    }
+
+    /// Fail mint requests before the mint processes them
+    pub fn reject_mints(&self, reject: bool) {
+        self.reject_mints.store(reject, Ordering::SeqCst);
+    }
+
+    fn lnurl_reachable(&self) -> Result<(), Error> {
+        if self.lnurl_offline.load(Ordering::SeqCst) {
+            return Err(Error::HttpError(
+                None,
+                "LNURL endpoint unreachable".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    fn reachable(&self) -> Result<(), Error> {
+        if self.offline.load(Ordering::SeqCst) {
+            return Err(Error::HttpError(None, "Mint unreachable".to_string()));
         }
+        Ok(())
+    }
+
+    fn respond<T>(&self, response: Result<T, Error>) -> Result<T, Error> {
+        if self.drop_responses.load(Ordering::SeqCst) {
+            return Err(Error::HttpError(None, "Connection lost".to_string()));
+        }
+        response
     }
 }
 
@@ -63,29 +122,73 @@ impl MintConnector for DirectMintConnection {
         panic!("Not implemented");
     }
 
+    /// Any Lightning address resolves to a pay request of the fake node
     async fn fetch_lnurl_pay_request(
         &self,
         _url: &str,
     ) -> Result<cdk::wallet::LnurlPayResponse, Error> {
-        unimplemented!("Lightning address not supported in DirectMintConnection")
+        self.lnurl_reachable()?;
+        Ok(cdk::wallet::LnurlPayResponse {
+            callback: "https://example.com/lnurlp/callback".to_string(),
+            min_sendable: 1_000,
+            max_sendable: 1_000_000_000,
+            metadata: MOCK_LNURL_METADATA.to_string(),
+            tag: Some("payRequest".to_string()),
+            reason: None,
+            comment_allowed: None,
+        })
     }
 
+    /// Invoices committing to [`MOCK_LNURL_METADATA`], paid by the fake wallet of the mint
     async fn fetch_lnurl_invoice(
         &self,
-        _url: &str,
+        url: &str,
     ) -> Result<cdk::wallet::LnurlPayInvoiceResponse, Error> {
-        unimplemented!("Lightning address not supported in DirectMintConnection")
+        self.lnurl_reachable()?;
+        let amount_msat: u64 = url
+            .split(['?', '&'])
+            .find_map(|param| param.strip_prefix("amount="))
+            .and_then(|amount| amount.parse().ok())
+            .ok_or_else(|| Error::Custom(format!("No amount in LNURL callback {url}")))?;
+
+        let node_key =
+            bitcoin::secp256k1::SecretKey::from_slice(&[0x42; 32]).expect("Valid secret key");
+        let invoice = lightning_invoice::InvoiceBuilder::new(lightning_invoice::Currency::Bitcoin)
+            .description_hash(bitcoin::hashes::sha256::Hash::hash(
+                MOCK_LNURL_METADATA.as_bytes(),
+            ))
+            .payment_hash(bitcoin::hashes::sha256::Hash::hash(
+                Uuid::new_v4().as_bytes(),
+            ))
+            .payment_secret(lightning_invoice::PaymentSecret([42u8; 32]))
+            .amount_milli_satoshis(amount_msat)
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(144)
+            .build_signed(|hash| {
+                bitcoin::secp256k1::Secp256k1::new().sign_ecdsa_recoverable(hash, &node_key)
+            })
+            .map_err(|err| Error::Custom(err.to_string()))?;
+
+        Ok(cdk::wallet::LnurlPayInvoiceResponse {
+            pr: Some(invoice.to_string()),
+            success_action: None,
+            routes: None,
+            reason: None,
+        })
     }
 
     async fn get_mint_keys(&self) -> Result<Vec<KeySet>, Error> {
+        self.reachable()?;
         Ok(self.mint.pubkeys().keysets)
     }
 
     async fn get_mint_keyset(&self, keyset_id: Id) -> Result<KeySet, Error> {
+        self.reachable()?;
         self.mint.keyset(&keyset_id).ok_or(Error::UnknownKeySet)
     }
 
     async fn get_mint_keysets(&self) -> Result<KeysetResponse, Error> {
+        self.reachable()?;
         Ok(self.mint.keysets())
     }
 
@@ -110,8 +213,11 @@ impl MintConnector for DirectMintConnection {
     }
 
     async fn post_mint(&self, request: MintRequest<String>) -> Result<MintResponse, Error> {
+        if self.reject_mints.load(Ordering::SeqCst) {
+            return Err(Error::HttpError(None, "Mint request rejected".to_string()));
+        }
         let request_id: MintRequest<QuoteId> = request.try_into().unwrap();
-        self.mint.process_mint_request(request_id).await
+        self.respond(self.mint.process_mint_request(request_id).await)
     }
 
     async fn post_melt_quote(
@@ -139,14 +245,16 @@ impl MintConnector for DirectMintConnection {
         request: MeltRequest<String>,
     ) -> Result<MeltQuoteBolt11Response<String>, Error> {
         let request_uuid = request.try_into().unwrap();
-        self.mint.melt(&request_uuid).await.map(Into::into)
+        self.respond(self.mint.melt(&request_uuid).await.map(Into::into))
     }
 
     async fn post_swap(&self, swap_request: SwapRequest) -> Result<SwapResponse, Error> {
-        self.mint.process_swap_request(swap_request).await
+        self.reachable()?;
+        self.respond(self.mint.process_swap_request(swap_request).await)
     }
 
     async fn get_mint_info(&self) -> Result<MintInfo, Error> {
+        self.reachable()?;
         Ok(self.mint.mint_info().await?.clone().time(unix_time()))
     }
 
@@ -154,6 +262,7 @@ impl MintConnector for DirectMintConnection {
         &self,
         request: CheckStateRequest,
     ) -> Result<CheckStateResponse, Error> {
+        self.reachable()?;
         self.mint.check_state(&request).await
     }
 
@@ -161,6 +270,16 @@ impl MintConnector for DirectMintConnection {
         self.mint.restore(request).await
     }
 
+    async fn post_mint_quotes(
+        &self,
+        request: MintQuotesRequest,
+    ) -> Result<MintQuotesResponse<String>, Error> {
+        self.mint
+            .mint_quotes_by_pubkeys(&request)
+            .await
+            .map(Into::into)
+    }
+
     /// Get the auth wallet for the client
     async fn get_auth_wallet(&self) -> Option<AuthWallet> {
         self.auth_wallet.read().await.clone()
@@ -325,6 +444,12 @@ pub async fn create_and_start_test_mint() -> Result<Mint> {
 }
 
 pub async fn create_test_wallet_for_mint(mint: Mint) -> Result<Wallet> {
+    let seed = Mnemonic::generate(12)?.to_seed_normalized("");
+    create_test_wallet_for_mint_with_seed(mint, seed).await
+}
+
+/// Creates a wallet with empty storage for the given seed
+pub async fn create_test_wallet_for_mint_with_seed(mint: Mint, seed: [u8; 64]) -> Result<Wallet> {
     let connector = DirectMintConnection::new(mint.clone());
 
     let mint_info = mint.mint_info().await?;
@@ -335,7 +460,6 @@ pub async fn create_test_wallet_for_mint(mint: Mint) -> Result<Wallet> {
         .first()
         .ok_or(anyhow!("Test mint has empty URLs list"))?;
 
-    let seed = Mnemonic::generate(12)?.to_seed_normalized("");
     let unit = CurrencyUnit::Sat;
 
     // Read environment variable to determine database type
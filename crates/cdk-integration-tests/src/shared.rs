@@ -223,6 +223,8 @@ pub fn create_fake_wallet_settings(
             max_mint: DEFAULT_MAX_MINT.into(),
             min_melt: DEFAULT_MIN_MELT.into(),
             max_melt: DEFAULT_MAX_MELT.into(),
+            max_concurrent_payments: None,
+            payment_queue_timeout_secs: None,
         },
         cln: None,
         lnbits: None,
@@ -276,6 +278,8 @@ pub fn create_cln_settings(
             max_mint: DEFAULT_MAX_MINT.into(),
             min_melt: DEFAULT_MIN_MELT.into(),
             max_melt: DEFAULT_MAX_MELT.into(),
+            max_concurrent_payments: None,
+            payment_queue_timeout_secs: None,
         },
         cln: Some(cln_config),
         lnbits: None,
@@ -324,6 +328,8 @@ pub fn create_lnd_settings(
             max_mint: DEFAULT_MAX_MINT.into(),
             min_melt: DEFAULT_MIN_MELT.into(),
             max_melt: DEFAULT_MAX_MELT.into(),
+            max_concurrent_payments: None,
+            payment_queue_timeout_secs: None,
         },
         cln: None,
         lnbits: None,
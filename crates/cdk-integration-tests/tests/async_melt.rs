@@ -54,6 +54,7 @@ async fn test_async_melt_returns_pending() {
         check_payment_state: MeltQuoteState::Paid,
         pay_err: false,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(
@@ -117,6 +118,7 @@ async fn test_sync_melt_completes_fully() {
         check_payment_state: MeltQuoteState::Paid,
         pay_err: false,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(
@@ -255,7 +255,7 @@ async fn test_check_without_auth() {
 async fn test_restore_without_auth() {
     let client = HttpClient::new(MintUrl::from_str(MINT_URL).expect("Valid mint url"), None);
 
-    let request = RestoreRequest { outputs: vec![] };
+    let request = RestoreRequest::new(vec![]);
 
     let restore_res = client.post_restore(request).await;
 
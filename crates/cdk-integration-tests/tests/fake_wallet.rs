@@ -22,13 +22,15 @@ use cashu::Amount;
 use cdk::amount::SplitTarget;
 use cdk::nuts::nut00::ProofsMethods;
 use cdk::nuts::{
-    CurrencyUnit, MeltQuoteState, MeltRequest, MintRequest, PreMintSecrets, Proofs, SecretKey,
-    State, SwapRequest,
+    CurrencyUnit, MeltOptions, MeltQuoteState, MeltRequest, MintRequest, PreMintSecrets, Proofs,
+    SecretKey, State, SwapRequest,
 };
-use cdk::wallet::types::TransactionDirection;
+use cdk::wallet::types::{Pagination, TransactionDirection, TransactionFilter};
 use cdk::wallet::{HttpClient, MintConnector, Wallet};
 use cdk::StreamExt;
-use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
+use cdk_fake_wallet::{
+    create_fake_amountless_invoice, create_fake_invoice, FakeInvoiceDescription,
+};
 use cdk_sqlite::wallet::memory;
 
 const MINT_URL: &str = "http://127.0.0.1:8086";
@@ -60,6 +62,7 @@ async fn test_fake_tokens_pending() {
         check_payment_state: MeltQuoteState::Pending,
         pay_err: false,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -107,6 +110,7 @@ async fn test_fake_melt_payment_fail() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -122,6 +126,7 @@ async fn test_fake_melt_payment_fail() {
         check_payment_state: MeltQuoteState::Failed,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -164,6 +169,7 @@ async fn test_fake_melt_payment_fail_and_check() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: true,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -210,6 +216,7 @@ async fn test_fake_melt_payment_return_fail_status() {
         check_payment_state: MeltQuoteState::Failed,
         pay_err: false,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -235,6 +242,7 @@ async fn test_fake_melt_payment_return_fail_status() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: false,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -283,6 +291,7 @@ async fn test_fake_melt_payment_error_unknown() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -298,6 +307,7 @@ async fn test_fake_melt_payment_error_unknown() {
         check_payment_state: MeltQuoteState::Unknown,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -346,6 +356,7 @@ async fn test_fake_melt_payment_err_paid() {
         check_payment_state: MeltQuoteState::Paid,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(7000, serde_json::to_string(&fake_description).unwrap());
@@ -395,7 +406,10 @@ async fn test_fake_melt_change_in_quote() {
         .expect("no error");
 
     let transaction = wallet
-        .list_transactions(Some(TransactionDirection::Incoming))
+        .list_transactions(
+            TransactionFilter::new().with_direction(TransactionDirection::Incoming),
+            Pagination::default(),
+        )
         .await
         .unwrap()
         .pop()
@@ -447,6 +461,52 @@ async fn test_fake_melt_change_in_quote() {
     assert_eq!(melt_change, check);
 }
 
+/// Tests melting a zero-amount invoice with the amountless option
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_fake_melt_amountless_invoice() {
+    let wallet = Wallet::new(
+        MINT_URL,
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("Failed to create new wallet");
+
+    let mint_quote = wallet.mint_quote(100.into(), None).await.unwrap();
+
+    let mut proof_streams = wallet.proof_stream(mint_quote.clone(), SplitTarget::default(), None);
+
+    let _proofs = proof_streams
+        .next()
+        .await
+        .expect("payment")
+        .expect("no error");
+
+    let fake_description = FakeInvoiceDescription::default();
+
+    let invoice = create_fake_amountless_invoice(serde_json::to_string(&fake_description).unwrap());
+
+    // A zero-amount invoice cannot be quoted without an amount
+    let result = wallet.melt_quote(invoice.to_string(), None).await;
+    assert!(result.is_err());
+
+    let melt_quote = wallet
+        .melt_quote(
+            invoice.to_string(),
+            Some(MeltOptions::new_amountless(9_000)),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(melt_quote.amount, Amount::from(9));
+
+    let melted = wallet.melt(&melt_quote.id).await.unwrap();
+
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.amount, Amount::from(9));
+}
+
 /// Tests minting tokens with a valid witness signature
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_fake_mint_with_witness() {
@@ -1432,6 +1492,7 @@ async fn test_wallet_proof_recovery_after_failed_melt() {
         check_payment_state: MeltQuoteState::Unpaid,
         pay_err: true,
         check_err: false,
+        pay_delay_ms: 0,
     };
 
     let invoice = create_fake_invoice(1000, serde_json::to_string(&fake_description).unwrap());
@@ -1728,7 +1789,10 @@ async fn test_melt_proofs_external() {
 
     // Verify a transaction was recorded
     let transactions = wallet_melter
-        .list_transactions(Some(TransactionDirection::Outgoing))
+        .list_transactions(
+            TransactionFilter::new().with_direction(TransactionDirection::Outgoing),
+            Pagination::default(),
+        )
         .await
         .unwrap();
     assert_eq!(transactions.len(), 1);
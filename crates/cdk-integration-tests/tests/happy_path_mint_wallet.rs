@@ -22,7 +22,8 @@ use cashu::{MeltRequest, PreMintSecrets};
 use cdk::amount::{Amount, SplitTarget};
 use cdk::mint_url::MintUrl;
 use cdk::nuts::nut00::ProofsMethods;
-use cdk::nuts::{CurrencyUnit, MeltQuoteState, NotificationPayload, State};
+use cdk::nuts::{CurrencyUnit, MeltQuoteState, MintQuoteState, NotificationPayload, State};
+use cdk::wallet::types::{Pagination, TransactionFilter};
 use cdk::wallet::{HttpClient, MintConnector, MultiMintWallet, Wallet};
 use cdk_integration_tests::{create_invoice_for_env, get_mint_url_from_env, pay_if_regtest};
 use cdk_sqlite::wallet::memory;
@@ -191,7 +192,10 @@ async fn test_happy_mint_melt_round_trip() {
     assert!(melt_response.preimage.is_some());
     assert_eq!(melt_response.state, MeltQuoteState::Paid);
 
-    let txs = wallet.list_transactions(None).await.unwrap();
+    let txs = wallet
+        .list_transactions(TransactionFilter::new(), Pagination::default())
+        .await
+        .unwrap();
     let tx = txs
         .into_iter()
         .find(|tx| tx.quote_id == Some(melt.id.clone()))
@@ -269,6 +273,57 @@ async fn test_happy_mint() {
     assert!(mint_amount == 100.into());
 }
 
+/// Tests waiting for a mint quote payment through the mint's websocket endpoint
+///
+/// This test verifies that:
+/// 1. `wait_for_mint_quote_paid` resolves once the quote is paid
+/// 2. The stored quote is updated to the paid state
+/// 3. The quote can then be minted without further waiting
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_wait_for_mint_quote_paid() {
+    let wallet = Wallet::new(
+        &get_mint_url_from_env(),
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("failed to create new wallet");
+
+    let mint_info = wallet.fetch_mint_info().await.unwrap().unwrap();
+    assert!(
+        !mint_info.nuts.nut17.supported.is_empty(),
+        "mint should advertise websocket subscriptions"
+    );
+
+    let mint_quote = wallet.mint_quote(100.into(), None).await.unwrap();
+
+    let invoice = Bolt11Invoice::from_str(&mint_quote.request).unwrap();
+    pay_if_regtest(&get_test_temp_dir(), &invoice)
+        .await
+        .unwrap();
+
+    let paid_quote = wallet
+        .wait_for_mint_quote_paid(&mint_quote.id, Duration::from_secs(60))
+        .await
+        .expect("quote paid");
+    assert_eq!(paid_quote.state, MintQuoteState::Paid);
+
+    let stored_quote = wallet
+        .localstore
+        .get_mint_quote(&mint_quote.id)
+        .await
+        .unwrap()
+        .expect("quote stored");
+    assert_eq!(stored_quote.state, MintQuoteState::Paid);
+
+    let proofs = wallet
+        .mint(&mint_quote.id, SplitTarget::default(), None)
+        .await
+        .unwrap();
+    assert_eq!(proofs.total_amount().unwrap(), 100.into());
+}
+
 /// Tests wallet restoration and proof state verification
 ///
 /// This test verifies the wallet restoration process:
@@ -20,16 +20,23 @@ use cashu::amount::SplitTarget;
 use cashu::dhke::construct_proofs;
 use cashu::mint_url::MintUrl;
 use cashu::{
-    CurrencyUnit, Id, MeltRequest, NotificationPayload, PreMintSecrets, ProofState, SecretKey,
-    SpendingConditions, State, SwapRequest,
+    CurrencyUnit, Id, MeltQuoteState, MeltRequest, MintQuoteState, NotificationPayload, PreMintSecrets, ProofState,
+    SecretKey, SpendingConditions, State, SwapRequest,
 };
 use cdk::mint::Mint;
 use cdk::nuts::nut00::ProofsMethods;
 use cdk::subscription::Params;
-use cdk::wallet::types::{TransactionDirection, TransactionId};
-use cdk::wallet::{ReceiveOptions, SendMemo, SendOptions};
+use cdk::wallet::multi_mint_wallet::{TransferMode, WalletConfig};
+use cdk::wallet::types::{
+    Pagination, TransactionDirection, TransactionFilter, TransactionId, TransactionKind,
+};
+use cdk::wallet::{
+    AutoMeltOutcome, AutoMeltTarget, KeysetMigrationOptions, MultiMintWallet, OfflineCheck,
+    OfflineDleqStatus, OfflineKeysetStatus, ReceiveOptions, SeedRestoreOptions, SendMemo,
+    SendOptions, Wallet, WalletBuilder, AUTO_MELT_METADATA_KEY,
+};
 use cdk::Amount;
-use cdk_fake_wallet::create_fake_invoice;
+use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
 use cdk_integration_tests::init_pure_tests::*;
 use tokio::time::sleep;
 
@@ -197,7 +204,7 @@ async fn test_mint_nut06() {
     );
 
     let transaction = wallet_alice
-        .list_transactions(None)
+        .list_transactions(TransactionFilter::new(), Pagination::default())
         .await
         .expect("Failed to list transactions")
         .pop()
@@ -842,6 +849,288 @@ async fn test_mint_change_with_fee_melt() {
         "Should have redeemed 100 sats from the melt"
     );
 }
+/// Tests that the wallet pays the input fee advertised with the melt quote:
+/// 1. The mint's active keyset charges 100 ppk per input
+/// 2. The melt quote advertises the input fee ppk
+/// 3. Melting proofs that cover only the amount and fee reserve fails in the wallet
+/// 4. Melting with selected proofs succeeds on the first attempt
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_melt_with_advertised_input_fee() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            100,
+        )
+        .await
+        .unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        100,
+        Some(SplitTarget::Value(Amount::ONE)),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let fake_invoice = create_fake_invoice(10_000, "".to_string());
+    let melt_quote = wallet_alice
+        .melt_quote(fake_invoice.to_string(), None)
+        .await
+        .unwrap();
+    assert_eq!(melt_quote.fees.as_ref().unwrap().input_fee_ppk, 100);
+
+    // Proofs covering the amount and fee reserve but not their input fee
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    let uncovered: cdk::nuts::Proofs = proofs
+        .into_iter()
+        .take(u64::from(melt_quote.amount + melt_quote.fee_reserve) as usize)
+        .collect();
+    match wallet_alice.melt_proofs(&melt_quote.id, uncovered).await {
+        Err(cdk::Error::InsufficientFunds) => (),
+        res => panic!("Expected insufficient funds, got {:?}", res),
+    }
+
+    let melted = wallet_alice.melt(&melt_quote.id).await.unwrap();
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.amount, Amount::from(10));
+
+    let balance = wallet_alice.total_balance().await.unwrap();
+    assert!(balance < Amount::from(90));
+    assert!(balance > Amount::ZERO);
+}
+
+/// Tests that a send including the fee lets the receiver net the amount:
+/// 1. The mint's active keyset charges each fee ppk in turn
+/// 2. The prepared send reports the gross amount, the amount plus the send fee
+/// 3. The token holds exactly the gross amount
+/// 4. The receiver claims exactly the amount after paying the swap fee, or slightly more
+///    when no smaller gross splits into proofs netting the amount
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_send_include_fee_receiver_nets_amount() {
+    setup_tracing();
+
+    // (fee ppk, amount, most the receiver claims)
+    let cases = [
+        (100, 1, 1),
+        (100, 100, 100),
+        (100, 333, 333),
+        (400, 7, 7),
+        (400, 100, 100),
+        // 11 splits into three proofs netting 9, 12 into two netting 11
+        (400, 10, 11),
+        (1000, 10, 10),
+        (1000, 64, 64),
+        (1000, 333, 333),
+    ];
+
+    for (fee_ppk, amount, max_claimed) in cases {
+        let mint_bob = create_and_start_test_mint()
+            .await
+            .expect("Failed to create test mint");
+
+        mint_bob
+            .rotate_keyset(
+                CurrencyUnit::Sat,
+                cdk_integration_tests::standard_keyset_amounts(32),
+                fee_ppk,
+            )
+            .await
+            .unwrap();
+
+        let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+            .await
+            .expect("Failed to create test wallet");
+        let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+            .await
+            .expect("Failed to create test wallet");
+
+        fund_wallet(wallet_alice.clone(), 1000, None)
+            .await
+            .expect("Failed to fund wallet");
+
+        let amount = Amount::from(amount);
+        let prepared = wallet_alice
+            .prepare_send(
+                amount,
+                SendOptions {
+                    include_fee: true,
+                    ..Default::default()
+                },
+            )
+            .await
+            .expect("Failed to prepare send");
+        let gross_amount = prepared.gross_amount();
+        assert_eq!(gross_amount, amount + prepared.send_fee());
+
+        let token = prepared.confirm(None).await.expect("Failed to send");
+        assert_eq!(token.value().unwrap(), gross_amount);
+
+        let claimed = wallet_carol
+            .receive(&token.to_string(), ReceiveOptions::default())
+            .await
+            .expect("Failed to receive");
+        // Stored proofs netting exactly the amount are sent as they are
+        assert!(
+            claimed >= amount && claimed <= Amount::from(max_claimed),
+            "receiver of {amount} at {fee_ppk} ppk claimed {claimed}"
+        );
+    }
+}
+
+/// Tests consolidating small proofs on a keyset with input fees:
+/// 1. The mint's active keyset charges 100 ppk per input
+/// 2. Alice is funded with 100 proofs of 1 sat
+/// 3. Consolidation swaps them for the value less the 10 sat input fee
+/// 4. A second consolidation is skipped, it would not reduce the number of proofs
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_consolidate_with_input_fee() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            100,
+        )
+        .await
+        .unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        100,
+        Some(SplitTarget::Value(Amount::ONE)),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let result = wallet_alice.consolidate().await.unwrap();
+    assert_eq!(result.proofs_consolidated, 100);
+    assert_eq!(result.fee_paid, Amount::from(10));
+    assert_eq!(result.amount_received, Amount::from(90));
+    // 90 = 64 + 16 + 8 + 2
+    assert_eq!(result.proofs_received, 4);
+
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    assert_eq!(proofs.len(), 4);
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(90)
+    );
+
+    let result = wallet_alice.consolidate().await.unwrap();
+    assert_eq!(result.proofs_consolidated, 0);
+    assert_eq!(result.fee_paid, Amount::ZERO);
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(90)
+    );
+}
+
+/// Tests that consolidation leaves proofs whose input fee would consume their value:
+/// 1. The mint's active keyset charges 1000 ppk per input
+/// 2. Alice is funded with 3 proofs of 1 sat
+/// 3. Consolidation swaps nothing and the balance is unchanged
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_consolidate_skips_batches_consumed_by_fees() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            1000,
+        )
+        .await
+        .unwrap();
+
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(
+        wallet_alice.clone(),
+        3,
+        Some(SplitTarget::Value(Amount::ONE)),
+    )
+    .await
+    .expect("Failed to fund wallet");
+
+    let result = wallet_alice.consolidate().await.unwrap();
+    assert_eq!(result.proofs_consolidated, 0);
+    assert_eq!(result.amount_received, Amount::ZERO);
+    assert_eq!(wallet_alice.get_unspent_proofs().await.unwrap().len(), 3);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::from(3));
+}
+
+/// Tests that the multi-mint wallet consolidates through the fee aware wallet consolidation:
+/// 1. The mint's active keyset charges 100 ppk per input
+/// 2. The multi-mint wallet is funded with 100 proofs of 1 sat
+/// 3. Consolidation reports the value received after the 10 sat input fee
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_multi_mint_consolidate_with_input_fee() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            100,
+        )
+        .await
+        .unwrap();
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let mint_url = MintUrl::from_str("https://mint-bob.example.com").unwrap();
+    multi_mint_wallet
+        .add_mint_with_config(
+            mint_url.clone(),
+            WalletConfig::new()
+                .with_mint_connector(Arc::new(DirectMintConnection::new(mint_bob.clone()))),
+        )
+        .await
+        .expect("Failed to add mint");
+
+    let wallet = multi_mint_wallet.get_wallet(&mint_url).await.unwrap();
+    fund_wallet(wallet.clone(), 100, Some(SplitTarget::Value(Amount::ONE)))
+        .await
+        .expect("Failed to fund wallet");
+
+    let consolidated = multi_mint_wallet.consolidate().await.unwrap();
+    assert_eq!(consolidated, Amount::from(90));
+    assert_eq!(wallet.get_unspent_proofs().await.unwrap().len(), 4);
+    assert_eq!(wallet.total_balance().await.unwrap(), Amount::from(90));
+}
+
 /// Tests concurrent double-spending attempts by trying to use the same proofs
 /// in 3 swap transactions simultaneously using tokio tasks
 #[tokio::test(flavor = "multi_thread", worker_threads = 3)]
@@ -1046,9 +1335,2202 @@ async fn test_concurrent_double_spend_melt() {
     }
 }
 
+/// Tests reclaiming proofs left pending by an interrupted melt:
+/// 1. Alice gets funded with 100 sats
+/// 2. A melt whose payment stays pending at the mint keeps its proofs pending
+/// 3. Proofs marked pending by a melt that never reached the mint are reclaimed
+#[tokio::test]
+async fn test_check_all_pending_proofs_after_failed_melt() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    // Payment stays pending at the mint, so the inputs must not be reclaimed
+    let fake_description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Pending,
+        check_payment_state: MeltQuoteState::Pending,
+        pay_err: false,
+        check_err: false,
+        pay_delay_ms: 0,
+    };
+    let invoice = create_fake_invoice(10_000, serde_json::to_string(&fake_description).unwrap());
+    let melt_quote = wallet_alice
+        .melt_quote(invoice.to_string(), None)
+        .await
+        .expect("Failed to create melt quote");
+    assert!(wallet_alice.melt(&melt_quote.id).await.is_err());
+
+    let pending_at_mint = wallet_alice.get_pending_proofs().await.unwrap();
+    assert!(!pending_at_mint.is_empty());
+
+    let reclaimed = wallet_alice
+        .check_all_pending_proofs()
+        .await
+        .expect("Failed to check pending proofs");
+    assert_eq!(reclaimed, Amount::ZERO);
+    assert_eq!(
+        wallet_alice.get_pending_proofs().await.unwrap().len(),
+        pending_at_mint.len()
+    );
+
+    // The app is killed after marking the inputs of a melt as pending, before calling the mint
+    let balance = wallet_alice.total_balance().await.unwrap();
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    wallet_alice
+        .localstore
+        .update_proofs_state(proofs.ys().unwrap(), State::Pending)
+        .await
+        .unwrap();
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::ZERO);
+
+    let reclaimed = wallet_alice
+        .check_all_pending_proofs()
+        .await
+        .expect("Failed to check pending proofs");
+    assert_eq!(reclaimed, balance);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), balance);
+    assert_eq!(
+        wallet_alice.get_pending_proofs().await.unwrap().len(),
+        pending_at_mint.len()
+    );
+}
+
+/// Tests restoring a wallet from its seed (NUT-13):
+/// 1. Alice gets funded from a keyset that is then rotated out and from the new keyset
+/// 2. Alice sends part of her balance to Carol
+/// 3. A wallet with Alice's seed and empty storage restores Alice's balance from both keysets
+/// 4. The restored wallet can mint again without reusing counters
+#[tokio::test]
+async fn test_restore_from_seed() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let wallet_alice = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            0,
+        )
+        .await
+        .unwrap();
+    wallet_alice.refresh_keysets().await.unwrap();
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let token = wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .unwrap()
+        .confirm(None)
+        .await
+        .unwrap();
+    wallet_carol
+        .receive(&token.to_string(), ReceiveOptions::default())
+        .await
+        .unwrap();
+
+    let balance = wallet_alice.total_balance().await.unwrap();
+    assert_eq!(balance, Amount::from(154));
+
+    let wallet_restored = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    assert_eq!(wallet_restored.total_balance().await.unwrap(), Amount::ZERO);
+
+    let mut progress = Vec::new();
+    let restored = wallet_restored
+        .restore_with_options(SeedRestoreOptions::default(), |p| progress.push(p))
+        .await
+        .unwrap();
+
+    assert_eq!(restored, balance);
+    assert_eq!(wallet_restored.total_balance().await.unwrap(), balance);
+
+    // Both the inactive and the active keyset were restored
+    let keysets: HashSet<Id> = progress.iter().map(|p| p.keyset_id).collect();
+    assert_eq!(keysets.len(), 2);
+    assert!(progress.iter().all(|p| p.keyset_count == 2));
+    assert_eq!(progress.last().map(|p| p.restored), Some(balance));
+
+    // New outputs must not collide with the restored ones
+    fund_wallet(wallet_restored.clone(), 10, None)
+        .await
+        .expect("Failed to fund restored wallet");
+    assert_eq!(
+        wallet_restored.total_balance().await.unwrap(),
+        balance + Amount::from(10)
+    );
+}
+
+/// Tests that restoring lists the keysets of the mint instead of the cached ones:
+/// 1. A wallet caches the keysets of the mint, then the mint rotates its keyset
+/// 2. The inactive keyset is still served by id and listed as inactive, but not with the active keys
+/// 3. Restoring the wallet recovers the ecash of both keysets
+#[tokio::test]
+async fn test_restore_discovers_rotated_keysets() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let wallet_alice = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_restored = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    wallet_restored.refresh_keysets().await.unwrap();
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let old_keyset_id = get_keyset_id(&mint_bob).await;
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            0,
+        )
+        .await
+        .unwrap();
+    wallet_alice.refresh_keysets().await.unwrap();
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let old_keys = mint_bob.keyset_pubkeys(&old_keyset_id).unwrap();
+    assert_eq!(old_keys.keysets[0].active, Some(false));
+    assert!(old_keys.keysets[0].verify_id().is_ok());
+    assert!(mint_bob
+        .keysets()
+        .keysets
+        .iter()
+        .any(|keyset| keyset.id == old_keyset_id && !keyset.active));
+    assert!(mint_bob
+        .pubkeys()
+        .keysets
+        .iter()
+        .all(|keyset| keyset.id != old_keyset_id));
+
+    let restored = wallet_restored.restore().await.unwrap();
+    assert_eq!(restored, Amount::from(164));
+}
+
 async fn get_keyset_id(mint: &Mint) -> Id {
     let keys = mint.pubkeys().keysets.first().unwrap().clone();
     keys.verify_id()
         .expect("Keyset ID generation is successful");
     keys.id
 }
+
+/// Tests that a P2PK locked token can be redeemed by the receiver before the locktime:
+/// 1. Alice sends 40 sats locked to Carol's key with Alice as the refund key
+/// 2. Carol cannot receive the token without signing
+/// 3. Carol receives the token by signing with her key
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2pk_send_redeemed_by_receiver() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let alice_key = SecretKey::generate();
+    let carol_key = SecretKey::generate();
+
+    let send_options = SendOptions::default()
+        .with_p2pk_lock(
+            vec![carol_key.public_key()],
+            None,
+            Some(cdk::util::unix_time() + 3600),
+            Some(vec![alice_key.public_key()]),
+        )
+        .expect("Valid p2pk conditions");
+
+    let prepared_send = wallet_alice
+        .prepare_send(40.into(), send_options)
+        .await
+        .expect("Failed to prepare send");
+    let token = prepared_send.confirm(None).await.expect("Failed to send");
+
+    assert!(
+        wallet_carol
+            .receive(&token.to_string(), ReceiveOptions::default())
+            .await
+            .is_err(),
+        "Receiving a locked token without a signature should fail"
+    );
+
+    let received = wallet_carol
+        .receive(
+            &token.to_string(),
+            ReceiveOptions {
+                p2pk_signing_keys: vec![carol_key],
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Carol should redeem the token");
+
+    assert_eq!(received, Amount::from(40));
+    assert_eq!(
+        wallet_carol.total_balance().await.unwrap(),
+        Amount::from(40)
+    );
+}
+
+/// Tests that the sender can reclaim a P2PK locked token through the refund path:
+/// 1. Alice sends 40 sats locked to Carol's key with a short locktime
+/// 2. Alice cannot reclaim the token before the locktime
+/// 3. After the locktime Alice reclaims the token with her refund key
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_p2pk_send_reclaimed_after_locktime() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let alice_key = SecretKey::generate();
+    let carol_key = SecretKey::generate();
+
+    let send_options = SendOptions::default()
+        .with_p2pk_lock(
+            vec![carol_key.public_key()],
+            None,
+            Some(cdk::util::unix_time() + 2),
+            Some(vec![alice_key.public_key()]),
+        )
+        .expect("Valid p2pk conditions");
+
+    let prepared_send = wallet_alice
+        .prepare_send(40.into(), send_options)
+        .await
+        .expect("Failed to prepare send");
+    let token = prepared_send.confirm(None).await.expect("Failed to send");
+
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(60)
+    );
+
+    let reclaim_options = ReceiveOptions {
+        p2pk_signing_keys: vec![alice_key],
+        ..Default::default()
+    };
+
+    assert!(
+        wallet_alice
+            .receive(&token.to_string(), reclaim_options.clone())
+            .await
+            .is_err(),
+        "Refund path should not be available before the locktime"
+    );
+
+    sleep(Duration::from_secs(3)).await;
+
+    let reclaimed = wallet_alice
+        .receive(&token.to_string(), reclaim_options)
+        .await
+        .expect("Alice should reclaim the token after the locktime");
+
+    assert_eq!(reclaimed, Amount::from(40));
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+}
+
+/// Tests paying a single invoice split across two mints with MPP:
+/// 1. Fund a multi-mint wallet with 64 sats at each of two mints
+/// 2. Pay a 100 sat invoice, which neither mint can cover alone
+/// 3. Verify both mints paid a part and the balances reflect the amounts and fees
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_multi_mint_auto_mpp_melt() {
+    setup_tracing();
+    let mint_one = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_two = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let mint_one_url = MintUrl::from_str("https://mint-one.example.com").unwrap();
+    let mint_two_url = MintUrl::from_str("https://mint-two.example.com").unwrap();
+
+    for (mint_url, mint) in [(&mint_one_url, &mint_one), (&mint_two_url, &mint_two)] {
+        multi_mint_wallet
+            .add_mint_with_config(
+                mint_url.clone(),
+                WalletConfig::new()
+                    .with_mint_connector(Arc::new(DirectMintConnection::new(mint.clone()))),
+            )
+            .await
+            .expect("Failed to add mint");
+
+        let wallet = multi_mint_wallet.get_wallet(mint_url).await.unwrap();
+        fund_wallet(wallet, 64, None)
+            .await
+            .expect("Failed to fund wallet");
+    }
+
+    let invoice = create_fake_invoice(100_000, "".to_string());
+
+    let melted = multi_mint_wallet
+        .melt_with_auto_mpp(&invoice.to_string(), None)
+        .await
+        .expect("MPP melt should succeed");
+
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.amount, Amount::from(100));
+    assert_eq!(melted.parts.len(), 2);
+
+    let part_mints: HashSet<_> = melted.parts.iter().map(|(url, _)| url.clone()).collect();
+    assert!(part_mints.contains(&mint_one_url) && part_mints.contains(&mint_two_url));
+
+    let parts_fee = melted
+        .parts
+        .iter()
+        .fold(Amount::ZERO, |total, (_, part)| total + part.fee_paid);
+    assert_eq!(melted.fee_paid, parts_fee);
+
+    assert_eq!(
+        multi_mint_wallet.total_balance().await.unwrap(),
+        Amount::from(128) - melted.amount - melted.fee_paid
+    );
+}
+
+/// Tests resuming a transfer whose melt was paid but whose mint at the target failed:
+/// 1. Fund a multi-mint wallet with 64 sats at a source mint
+/// 2. Transfer 20 sats to a target mint that rejects the mint request
+/// 3. Resume the transfer from the mint quote carried by the error
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_multi_mint_resume_transfer_after_mint_failure() {
+    setup_tracing();
+    let mint_source = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_target = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let source_url = MintUrl::from_str("https://mint-source.example.com").unwrap();
+    let target_url = MintUrl::from_str("https://mint-target.example.com").unwrap();
+    let target_connector = Arc::new(DirectMintConnection::new(mint_target.clone()));
+
+    multi_mint_wallet
+        .add_mint_with_config(
+            source_url.clone(),
+            WalletConfig::new()
+                .with_mint_connector(Arc::new(DirectMintConnection::new(mint_source.clone()))),
+        )
+        .await
+        .expect("Failed to add mint");
+    multi_mint_wallet
+        .add_mint_with_config(
+            target_url.clone(),
+            WalletConfig::new().with_mint_connector(target_connector.clone()),
+        )
+        .await
+        .expect("Failed to add mint");
+
+    let source_wallet = multi_mint_wallet.get_wallet(&source_url).await.unwrap();
+    fund_wallet(source_wallet, 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    target_connector.reject_mints(true);
+    let err = multi_mint_wallet
+        .transfer(
+            &source_url,
+            &target_url,
+            TransferMode::ExactReceive(Amount::from(20)),
+        )
+        .await
+        .expect_err("Minting at the target should fail");
+    let cdk::Error::TransferMintFailed {
+        target_mint,
+        mint_quote_id,
+        ..
+    } = err
+    else {
+        panic!("Expected TransferMintFailed, got {err:?}");
+    };
+    assert_eq!(target_mint, target_url.to_string());
+
+    let target_wallet = multi_mint_wallet.get_wallet(&target_url).await.unwrap();
+    assert_eq!(target_wallet.total_balance().await.unwrap(), Amount::ZERO);
+
+    target_connector.reject_mints(false);
+    let target_mint_url = MintUrl::from_str(&target_mint).unwrap();
+    let minted = multi_mint_wallet
+        .resume_transfer(&target_mint_url, &mint_quote_id)
+        .await
+        .expect("Failed to resume transfer");
+
+    assert_eq!(minted, Amount::from(20));
+    assert_eq!(
+        target_wallet.total_balance().await.unwrap(),
+        Amount::from(20)
+    );
+}
+
+/// Tests that a failed MPP melt only reclaims the inputs of its parts:
+/// 1. Fund a multi-mint wallet with 64 sats at each of two mints
+/// 2. Prepare a send at the first mint, reserving its proofs
+/// 3. Pay a 100 sat invoice whose payment fails at both mints
+/// 4. Verify the melt inputs are spendable again and the prepared send keeps its proofs
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_multi_mint_auto_mpp_melt_failure_keeps_prepared_send() {
+    setup_tracing();
+    let mint_one = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_two = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let mint_one_url = MintUrl::from_str("https://mint-one.example.com").unwrap();
+    let mint_two_url = MintUrl::from_str("https://mint-two.example.com").unwrap();
+
+    for (mint_url, mint) in [(&mint_one_url, &mint_one), (&mint_two_url, &mint_two)] {
+        multi_mint_wallet
+            .add_mint_with_config(
+                mint_url.clone(),
+                WalletConfig::new()
+                    .with_mint_connector(Arc::new(DirectMintConnection::new(mint.clone()))),
+            )
+            .await
+            .expect("Failed to add mint");
+
+        let wallet = multi_mint_wallet.get_wallet(mint_url).await.unwrap();
+        fund_wallet(wallet, 64, None)
+            .await
+            .expect("Failed to fund wallet");
+    }
+
+    let wallet_one = multi_mint_wallet.get_wallet(&mint_one_url).await.unwrap();
+    let prepared_send = wallet_one
+        .prepare_send(Amount::from(8), SendOptions::default())
+        .await
+        .expect("Failed to prepare send");
+    let reserved_ys: HashSet<_> = wallet_one
+        .get_reserved_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert!(!reserved_ys.is_empty());
+    let balance = multi_mint_wallet.total_balance().await.unwrap();
+
+    let fake_description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Failed,
+        check_payment_state: MeltQuoteState::Failed,
+        pay_err: true,
+        check_err: false,
+        pay_delay_ms: 0,
+    };
+    let invoice = create_fake_invoice(100_000, serde_json::to_string(&fake_description).unwrap());
+
+    assert!(multi_mint_wallet
+        .melt_with_auto_mpp(&invoice.to_string(), None)
+        .await
+        .is_err());
+
+    assert_eq!(multi_mint_wallet.total_balance().await.unwrap(), balance);
+    let still_reserved: HashSet<_> = wallet_one
+        .get_reserved_proofs()
+        .await
+        .unwrap()
+        .ys()
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert_eq!(still_reserved, reserved_ys);
+
+    let token = prepared_send
+        .confirm(None)
+        .await
+        .expect("Prepared send should still be valid");
+    assert_eq!(token.value().unwrap(), Amount::from(8));
+}
+
+/// Tests that MPP splitting keeps each part within the mint's advertised part limits:
+/// 1. Fund a multi-mint wallet with 70 sats at a mint limiting parts to 40 sats and 64 at another
+/// 2. Pay a 100 sat invoice
+/// 3. Verify the limited mint paid a 40 sat part and the other mint the remaining 60 sats
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_multi_mint_auto_mpp_melt_part_limits() {
+    setup_tracing();
+    let mint_one = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_two = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    let mut mint_info = mint_one.mint_info().await.unwrap();
+    for settings in mint_info.nuts.nut15.methods.iter_mut() {
+        settings.max_amount = Some(Amount::from(40));
+    }
+    mint_one.set_mint_info(mint_info).await.unwrap();
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let mint_one_url = MintUrl::from_str("https://mint-one.example.com").unwrap();
+    let mint_two_url = MintUrl::from_str("https://mint-two.example.com").unwrap();
+
+    for (mint_url, mint, amount) in [
+        (&mint_one_url, &mint_one, 70),
+        (&mint_two_url, &mint_two, 64),
+    ] {
+        multi_mint_wallet
+            .add_mint_with_config(
+                mint_url.clone(),
+                WalletConfig::new()
+                    .with_mint_connector(Arc::new(DirectMintConnection::new(mint.clone()))),
+            )
+            .await
+            .expect("Failed to add mint");
+
+        let wallet = multi_mint_wallet.get_wallet(mint_url).await.unwrap();
+        fund_wallet(wallet, amount, None)
+            .await
+            .expect("Failed to fund wallet");
+    }
+
+    let invoice = create_fake_invoice(100_000, "".to_string());
+
+    let melted = multi_mint_wallet
+        .melt_with_auto_mpp(&invoice.to_string(), None)
+        .await
+        .expect("MPP melt should succeed");
+
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.amount, Amount::from(100));
+
+    let part_amounts: HashMap<_, _> = melted
+        .parts
+        .iter()
+        .map(|(url, part)| (url.clone(), part.amount))
+        .collect();
+    assert_eq!(part_amounts.get(&mint_one_url), Some(&Amount::from(40)));
+    assert_eq!(part_amounts.get(&mint_two_url), Some(&Amount::from(60)));
+}
+
+/// Tests paying a NUT-18 payment request over the HTTP POST transport:
+/// 1. Carol serves a payment request inbox over HTTP and creates a request for 21 sats
+/// 2. Alice pays the encoded request from her multi-mint wallet
+/// 3. Carol's inbox receives the proofs and her balance reflects the payment
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_payment_request_http_transport() {
+    use axum::extract::State as AxumState;
+    use axum::http::StatusCode;
+    use axum::routing::post;
+    use axum::{Json, Router};
+    use cdk::nuts::{PaymentRequestPayload, Transport, TransportType};
+    use cdk::wallet::PaymentRequestInbox;
+
+    async fn pay(
+        AxumState(inbox): AxumState<PaymentRequestInbox>,
+        Json(payload): Json<PaymentRequestPayload>,
+    ) -> StatusCode {
+        match inbox.accept(payload).await {
+            Ok(_) => StatusCode::OK,
+            Err(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_url = MintUrl::from_str("https://aaa").unwrap();
+
+    let mut multi_mint_wallets = Vec::new();
+    for _ in 0..2 {
+        let seed = bip39::Mnemonic::generate(12)
+            .unwrap()
+            .to_seed_normalized("");
+        let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+        let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+            .await
+            .expect("Failed to create multi mint wallet");
+        multi_mint_wallet
+            .add_mint_with_config(
+                mint_url.clone(),
+                WalletConfig::new()
+                    .with_mint_connector(Arc::new(DirectMintConnection::new(mint_bob.clone()))),
+            )
+            .await
+            .expect("Failed to add mint");
+        multi_mint_wallets.push(multi_mint_wallet);
+    }
+    let wallet_carol = multi_mint_wallets.pop().unwrap();
+    let wallet_alice = multi_mint_wallets.pop().unwrap();
+
+    fund_wallet(wallet_alice.get_wallet(&mint_url).await.unwrap(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let inbox = PaymentRequestInbox::new(wallet_carol.clone());
+    let app = Router::new()
+        .route("/pay", post(pay))
+        .with_state(inbox.clone());
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await });
+
+    let encoded_request = inbox
+        .create_payment_request(
+            Some(Amount::from(21)),
+            CurrencyUnit::Sat,
+            vec![mint_url.clone()],
+            vec![Transport {
+                _type: TransportType::HttpPost,
+                target: format!("http://{address}/pay"),
+                tags: None,
+            }],
+        )
+        .await
+        .expect("Failed to create payment request");
+
+    wallet_alice
+        .pay_payment_request(&encoded_request, None)
+        .await
+        .expect("Failed to pay payment request");
+
+    assert_eq!(
+        wallet_carol.total_balance().await.unwrap(),
+        Amount::from(21)
+    );
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(79)
+    );
+
+    // The request is single use, paying it again is rejected by the inbox
+    assert!(wallet_alice
+        .pay_payment_request(&encoded_request, None)
+        .await
+        .is_err());
+}
+
+/// Tests that wallet operations are recorded in the transaction history:
+/// 1. Alice mints 64 sats, then sends 10 sats which requires a swap
+/// 2. Carol receives the token
+/// 3. Alice's history holds one mint, swap and send entry and can be paginated
+/// 4. Carol's history holds the receive entry
+#[tokio::test]
+async fn test_transaction_history_records_operations() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let token = wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+    wallet_carol
+        .receive(&token.to_string(), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+
+    let by_kind = |kind: TransactionKind| {
+        let wallet = wallet_alice.clone();
+        async move {
+            wallet
+                .list_transactions(
+                    TransactionFilter::new().with_kind(kind),
+                    Pagination::default(),
+                )
+                .await
+                .expect("Failed to list transactions")
+        }
+    };
+
+    let mints = by_kind(TransactionKind::Mint).await;
+    assert_eq!(mints.len(), 1);
+    assert_eq!(mints[0].amount, Amount::from(64));
+    assert_eq!(mints[0].direction, TransactionDirection::Incoming);
+
+    let swaps = by_kind(TransactionKind::Swap).await;
+    assert_eq!(swaps.len(), 1);
+    assert_eq!(swaps[0].fee, Amount::ZERO);
+
+    let sends = by_kind(TransactionKind::Send).await;
+    assert_eq!(sends.len(), 1);
+    assert_eq!(sends[0].amount, Amount::from(10));
+    assert_eq!(sends[0].direction, TransactionDirection::Outgoing);
+
+    let first_page = wallet_alice
+        .list_transactions(TransactionFilter::new(), Pagination::new(0, Some(2)))
+        .await
+        .expect("Failed to list transactions");
+    let second_page = wallet_alice
+        .list_transactions(TransactionFilter::new(), Pagination::new(2, Some(2)))
+        .await
+        .expect("Failed to list transactions");
+    assert_eq!(first_page.len(), 2);
+    assert_eq!(second_page.len(), 1);
+    assert!(first_page.iter().all(|tx| tx.id() != second_page[0].id()));
+
+    let received = wallet_carol
+        .list_transactions(
+            TransactionFilter::new().with_kind(TransactionKind::Receive),
+            Pagination::default(),
+        )
+        .await
+        .expect("Failed to list transactions");
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].amount, Amount::from(10));
+    assert_eq!(
+        wallet_carol
+            .get_transaction(received[0].id())
+            .await
+            .expect("Failed to get transaction"),
+        Some(received[0].clone())
+    );
+}
+
+/// Tests offline token verification:
+/// 1. Alice sends Carol 10 sats, Carol has the mint's keysets cached
+/// 2. Carol's offline report is valid and only leaves the spent state unverified
+/// 3. A token with a tampered signature fails the DLEQ check
+/// 4. A wallet that never contacted the mint cannot vouch for the token
+#[tokio::test]
+async fn test_verify_token_offline() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_dave = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+    let token = wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+
+    wallet_carol
+        .refresh_keysets()
+        .await
+        .expect("Failed to refresh keysets");
+
+    let report = wallet_carol
+        .verify_token_offline(&token)
+        .await
+        .expect("Failed to verify token");
+    assert!(report.is_valid());
+    assert!(report.mint_trusted);
+    assert!(report.keysets_updated_at.is_some());
+    assert_eq!(report.unverified, vec![OfflineCheck::ProofState]);
+    assert_eq!(
+        report.amounts.get(&CurrencyUnit::Sat),
+        Some(&Amount::from(10))
+    );
+    assert!(report.proofs.iter().all(|p| {
+        p.keyset == OfflineKeysetStatus::Active && p.dleq == OfflineDleqStatus::Valid
+    }));
+
+    // Replace a signature so its DLEQ proof no longer matches
+    let keysets = wallet_carol
+        .get_all_mint_keysets()
+        .await
+        .expect("Failed to get keysets");
+    let mut proofs = token.proofs(&keysets).expect("Failed to get proofs");
+    proofs[0].c = SecretKey::generate().public_key();
+    let tampered = cashu::Token::new(
+        wallet_alice.mint_url.clone(),
+        proofs,
+        None,
+        CurrencyUnit::Sat,
+    );
+    let report = wallet_carol
+        .verify_token_offline(&tampered)
+        .await
+        .expect("Failed to verify token");
+    assert!(!report.is_valid());
+    assert!(report
+        .proofs
+        .iter()
+        .any(|p| p.dleq == OfflineDleqStatus::Invalid));
+
+    // Dave's store has never seen the mint
+    let report = wallet_dave
+        .verify_token_offline(&token)
+        .await
+        .expect("Failed to verify token");
+    assert!(!report.is_valid());
+    assert!(!report.mint_trusted);
+    assert!(report.keysets_updated_at.is_none());
+    assert!(report.unverified.contains(&OfflineCheck::Keysets));
+}
+
+/// Tests sending a token verifiable offline:
+/// 1. Alice's stored proofs lose their DLEQ proofs, so an offline send with DLEQ fails
+/// 2. An online send with DLEQ swaps first and every proof of the token carries one
+/// 3. The memo carries the mint's name next to the text
+/// 4. Carol receives the token with its DLEQ proofs verified and the text as memo
+#[tokio::test]
+async fn test_send_with_dleq_and_mint_info_memo() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let stored = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .expect("Failed to get proofs");
+    let stripped = stored
+        .clone()
+        .into_iter()
+        .map(|proof| {
+            cdk::types::ProofInfo::new(
+                cashu::Proof {
+                    dleq: None,
+                    ..proof
+                },
+                wallet_alice.mint_url.clone(),
+                State::Unspent,
+                CurrencyUnit::Sat,
+            )
+            .expect("Failed to create proof info")
+        })
+        .collect();
+    wallet_alice
+        .localstore
+        .update_proofs(stripped, stored.ys().expect("Failed to get ys"))
+        .await
+        .expect("Failed to update proofs");
+
+    let offline = wallet_alice
+        .prepare_send(
+            10.into(),
+            SendOptions {
+                send_kind: cdk::wallet::SendKind::OfflineTolerance(64.into()),
+                include_dleq: true,
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(matches!(offline, Err(cdk::Error::DleqProofNotProvided)));
+
+    let token = wallet_alice
+        .prepare_send(
+            10.into(),
+            SendOptions {
+                memo: Some(SendMemo::for_token("lunch").with_mint_info()),
+                include_dleq: true,
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+
+    let keysets = wallet_carol
+        .load_mint_keysets()
+        .await
+        .expect("Failed to get keysets");
+    let proofs = token.proofs(&keysets).expect("Failed to get proofs");
+    assert!(proofs.iter().all(|proof| proof.dleq.is_some()));
+
+    let memo = token.structured_memo().expect("Memo should be structured");
+    assert_eq!(memo.text.as_deref(), Some("lunch"));
+    assert_eq!(memo.mint_name.as_deref(), Some("pure test mint"));
+
+    let result = wallet_carol
+        .receive_with_result(&token.to_string(), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+    assert_eq!(result.amount_claimed, Amount::from(10));
+    assert!(result.dleq_verified);
+
+    let received = wallet_carol
+        .list_transactions(
+            TransactionFilter::new().with_kind(TransactionKind::Receive),
+            Pagination::default(),
+        )
+        .await
+        .expect("Failed to list transactions");
+    assert_eq!(received[0].memo.as_deref(), Some("lunch"));
+}
+
+/// Tests migration of proofs off inactive keysets:
+/// 1. Alice is funded with 100 sats on a keyset charging 1 sat per input
+/// 2. The mint rotates to a fee-free keyset
+/// 3. Migration with a dust threshold swaps the 64 and 32 sat proofs for 2 sats in fees
+///    and leaves the 4 sat proof in place
+/// 4. With automatic migration enabled, minting also moves the remaining proof
+#[tokio::test]
+async fn test_migrate_inactive_proofs() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            1000,
+        )
+        .await
+        .expect("Failed to rotate keyset");
+    wallet_alice.refresh_keysets().await.unwrap();
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let new_keyset = mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            0,
+        )
+        .await
+        .expect("Failed to rotate keyset");
+
+    wallet_alice.set_keyset_migration(KeysetMigrationOptions::new().dust_threshold(4.into()));
+    let result = wallet_alice
+        .migrate_inactive_proofs()
+        .await
+        .expect("Failed to migrate proofs");
+    assert_eq!(result.proofs_migrated, 2);
+    assert_eq!(result.amount_migrated, Amount::from(96));
+    assert_eq!(result.fee_paid, Amount::from(2));
+    assert_eq!(result.amount_skipped, Amount::from(4));
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(98)
+    );
+
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    assert_eq!(
+        proofs
+            .iter()
+            .filter(|p| p.keyset_id != new_keyset.id)
+            .map(|p| p.amount)
+            .collect::<Vec<_>>(),
+        vec![Amount::from(4)]
+    );
+
+    wallet_alice.set_keyset_migration(KeysetMigrationOptions::new().auto_migrate(true));
+    fund_wallet(wallet_alice.clone(), 10, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(107)
+    );
+    assert!(wallet_alice
+        .get_unspent_proofs()
+        .await
+        .unwrap()
+        .iter()
+        .all(|p| p.keyset_id == new_keyset.id));
+}
+
+/// Tests that mint and receive honour the requested split target
+#[tokio::test]
+async fn test_split_target_denominations() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let amounts = |proofs: cdk::nuts::Proofs| {
+        let mut amounts: Vec<Amount> = proofs.iter().map(|p| p.amount).collect();
+        amounts.sort();
+        amounts
+    };
+
+    // Value: as many proofs of the requested denomination as fit
+    let preview = wallet_alice
+        .preview_split(64.into(), &SplitTarget::Value(8.into()))
+        .await
+        .expect("Failed to preview split");
+    assert_eq!(preview.amounts, vec![Amount::from(8); 8]);
+    assert_eq!(preview.redeem_fee, Amount::ZERO);
+
+    fund_wallet(wallet_alice.clone(), 64, Some(SplitTarget::Value(8.into())))
+        .await
+        .expect("Failed to fund wallet");
+    assert_eq!(
+        amounts(wallet_alice.get_unspent_proofs().await.unwrap()),
+        preview.amounts
+    );
+
+    // Values: requested denominations first, remainder auto split
+    fund_wallet(
+        wallet_carol.clone(),
+        20,
+        Some(SplitTarget::Values(vec![
+            1.into(),
+            1.into(),
+            2.into(),
+            4.into(),
+        ])),
+    )
+    .await
+    .expect("Failed to fund wallet");
+    assert_eq!(
+        amounts(wallet_carol.get_unspent_proofs().await.unwrap()),
+        vec![1, 1, 2, 4, 4, 8]
+            .into_iter()
+            .map(Amount::from)
+            .collect::<Vec<_>>()
+    );
+
+    // Values that are not denominations of the keyset are rejected
+    assert!(fund_wallet(
+        wallet_carol.clone(),
+        6,
+        Some(SplitTarget::Values(vec![3.into(), 3.into()])),
+    )
+    .await
+    .is_err());
+
+    // Receive with a target
+    let token = wallet_alice
+        .prepare_send(16.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+    let wallet_dave = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    wallet_dave
+        .receive(
+            &token.to_string(),
+            ReceiveOptions {
+                amount_split_target: SplitTarget::Value(4.into()),
+                ..Default::default()
+            },
+        )
+        .await
+        .expect("Failed to receive");
+    assert_eq!(
+        amounts(wallet_dave.get_unspent_proofs().await.unwrap()),
+        vec![Amount::from(4); 4]
+    );
+}
+
+/// Tests estimating a melt before paying it with the same quote
+#[tokio::test]
+async fn test_estimate_melt() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let invoice = create_fake_invoice(20_000, "".to_string()).to_string();
+    let estimate = wallet_alice
+        .estimate_melt(invoice.clone(), None)
+        .await
+        .expect("Failed to estimate melt");
+
+    assert_eq!(estimate.amount, Amount::from(20));
+    assert_eq!(
+        estimate.total,
+        estimate.amount + estimate.fee_reserve + estimate.swap_fee
+    );
+    assert_eq!(
+        estimate.remaining_balance,
+        Amount::from(100) - estimate.total
+    );
+    assert!(estimate.proofs.total_amount().unwrap() >= estimate.total);
+
+    // Nothing is reserved by an estimate
+    assert_eq!(
+        wallet_alice.total_balance().await.unwrap(),
+        Amount::from(100)
+    );
+    assert_eq!(
+        wallet_alice.total_reserved_balance().await.unwrap(),
+        Amount::ZERO
+    );
+
+    // A fresh stored quote for the same invoice is reused
+    let second_estimate = wallet_alice
+        .estimate_melt(invoice, None)
+        .await
+        .expect("Failed to estimate melt");
+    assert_eq!(second_estimate.quote.id, estimate.quote.id);
+
+    let melted = wallet_alice
+        .melt_estimate(&estimate)
+        .await
+        .expect("Failed to melt");
+    assert_eq!(melted.amount, Amount::from(20));
+    assert!(wallet_alice.total_balance().await.unwrap() >= estimate.remaining_balance);
+
+    // Estimates fail when the balance does not cover the payment
+    let too_large = create_fake_invoice(1_000_000, "".to_string()).to_string();
+    assert!(matches!(
+        wallet_alice.estimate_melt(too_large, None).await,
+        Err(cdk::Error::InsufficientFunds)
+    ));
+}
+
+/// Tests sweeping the funds of an old seed on two mints into a new wallet
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_sweep_from_seed() {
+    setup_tracing();
+    let mint_one = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mint_two = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+
+    let old_seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let old_wallet_one = create_test_wallet_for_mint_with_seed(mint_one.clone(), old_seed)
+        .await
+        .expect("Failed to create test wallet");
+    let old_wallet_two = create_test_wallet_for_mint_with_seed(mint_two.clone(), old_seed)
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(old_wallet_one.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+    fund_wallet(old_wallet_two.clone(), 32, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    // Part of the old funds were already spent and must not be swept
+    let token = old_wallet_one
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+    let wallet_carol = create_test_wallet_for_mint(mint_one.clone())
+        .await
+        .expect("Failed to create test wallet");
+    wallet_carol
+        .receive(&token.to_string(), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let localstore = Arc::new(cdk_sqlite::wallet::memory::empty().await.unwrap());
+    let multi_mint_wallet = MultiMintWallet::new(localstore, seed, CurrencyUnit::Sat)
+        .await
+        .expect("Failed to create multi mint wallet");
+
+    let mint_one_url = old_wallet_one.mint_url.clone();
+    let mint_two_url = old_wallet_two.mint_url.clone();
+    for (mint_url, mint) in [(&mint_one_url, &mint_one), (&mint_two_url, &mint_two)] {
+        multi_mint_wallet
+            .add_mint_with_config(
+                mint_url.clone(),
+                WalletConfig::new()
+                    .with_mint_connector(Arc::new(DirectMintConnection::new(mint.clone()))),
+            )
+            .await
+            .expect("Failed to add mint");
+    }
+
+    let results = multi_mint_wallet
+        .sweep_from_seed(&old_seed, &[mint_one_url.clone(), mint_two_url.clone()])
+        .await
+        .expect("Failed to sweep old seed");
+
+    assert_eq!(results[&mint_one_url].amount_recovered, Amount::from(54));
+    assert_eq!(results[&mint_two_url].amount_recovered, Amount::from(32));
+    assert_eq!(results[&mint_one_url].fee_paid, Amount::ZERO);
+    assert_eq!(
+        multi_mint_wallet.total_balance().await.unwrap(),
+        Amount::from(86)
+    );
+
+    // The old seed's proofs are spent now and a second sweep finds nothing
+    let swept_again = multi_mint_wallet
+        .sweep_from_seed(&old_seed, &[mint_one_url.clone(), mint_two_url.clone()])
+        .await
+        .expect("Failed to sweep old seed");
+    assert!(swept_again
+        .values()
+        .all(|result| result.amount_recovered == Amount::ZERO));
+
+    let old_states = old_wallet_two
+        .check_proofs_spent(old_wallet_two.get_unspent_proofs().await.unwrap())
+        .await
+        .unwrap();
+    assert!(old_states.iter().all(|state| state.state == State::Spent));
+}
+
+/// Tests receiving tokens whose proofs were already partially spent:
+/// 1. A token with all proofs unspent is claimed in full
+/// 2. After part of a token is spent, receiving it claims only the unspent proofs
+/// 3. A fully spent token fails with `TokenAlreadySpent`
+/// 4. With `all_or_nothing` a partially spent token fails and nothing is claimed
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_receive_partially_spent_token() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_dave = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let keysets = wallet_alice
+        .get_all_mint_keysets()
+        .await
+        .expect("Failed to get keysets");
+    let send = |amount: u64| {
+        let wallet_alice = wallet_alice.clone();
+        async move {
+            wallet_alice
+                .prepare_send(amount.into(), SendOptions::default())
+                .await
+                .expect("Failed to prepare send")
+                .confirm(None)
+                .await
+                .expect("Failed to send")
+        }
+    };
+    let token_of = |proofs: cashu::Proofs| {
+        cashu::Token::new(
+            wallet_alice.mint_url.clone(),
+            proofs,
+            None,
+            CurrencyUnit::Sat,
+        )
+        .to_string()
+    };
+
+    // All proofs unspent
+    let token = send(2).await;
+    let result = wallet_dave
+        .receive_with_result(&token.to_string(), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+    assert_eq!(result.amount_claimed, Amount::from(2));
+    assert_eq!(result.amount_already_spent, Amount::ZERO);
+    assert!(result.failed_proofs.is_empty());
+
+    // Carol claims one proof of the token before Dave receives all of it
+    let token = send(40).await;
+    let proofs = token.proofs(&keysets).expect("Failed to get proofs");
+    assert!(proofs.len() > 1);
+    let spent = proofs[0].clone();
+    wallet_carol
+        .receive(&token_of(vec![spent.clone()]), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+
+    let result = wallet_dave
+        .receive_with_result(&token.to_string(), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+    assert_eq!(result.amount_claimed, Amount::from(40) - spent.amount);
+    assert_eq!(result.amount_already_spent, spent.amount);
+    assert_eq!(result.failed_proofs, vec![(spent, State::Spent)]);
+    assert_eq!(
+        wallet_dave.total_balance().await.unwrap(),
+        Amount::from(42) - result.amount_already_spent
+    );
+
+    // Every proof of the token is spent now
+    let received = wallet_carol
+        .receive(&token.to_string(), ReceiveOptions::default())
+        .await;
+    assert!(matches!(received, Err(cdk::Error::TokenAlreadySpent)));
+
+    // All or nothing
+    let token = send(20).await;
+    let proofs = token.proofs(&keysets).expect("Failed to get proofs");
+    assert!(proofs.len() > 1);
+    wallet_carol
+        .receive(&token_of(vec![proofs[0].clone()]), ReceiveOptions::default())
+        .await
+        .expect("Failed to receive");
+
+    let balance = wallet_dave.total_balance().await.unwrap();
+    let received = wallet_dave
+        .receive_with_result(
+            &token.to_string(),
+            ReceiveOptions {
+                all_or_nothing: true,
+                ..Default::default()
+            },
+        )
+        .await;
+    assert!(matches!(received, Err(cdk::Error::TokenAlreadySpent)));
+    assert_eq!(wallet_dave.total_balance().await.unwrap(), balance);
+}
+
+/// Tests that mint and swap operations interrupted after the mint processed them are finished
+/// from the operation journal by a new wallet, without losing any balance
+#[tokio::test]
+async fn test_recover_interrupted_operations() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let wallet_alice = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    // Same seed and store as Alice's wallet, but the responses of the mint are lost
+    let connector = DirectMintConnection::new(mint_bob.clone());
+    connector.drop_responses(true);
+    let crashing_wallet = WalletBuilder::new()
+        .mint_url(wallet_alice.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_alice.localstore.clone())
+        .seed(seed)
+        .client(connector)
+        .build()
+        .expect("Failed to build wallet");
+
+    // Swap interrupted after the mint signed the outputs
+    let proofs = crashing_wallet
+        .get_unspent_proofs()
+        .await
+        .expect("Failed to get proofs");
+    assert!(crashing_wallet
+        .swap(None, SplitTarget::None, proofs, None, false)
+        .await
+        .is_err());
+
+    // Mint interrupted after the mint signed the outputs
+    let quote = crashing_wallet
+        .mint_quote(Amount::from(36), None)
+        .await
+        .expect("Failed to get mint quote");
+    loop {
+        let state = crashing_wallet
+            .mint_quote_state(&quote.id)
+            .await
+            .expect("Failed to check mint quote")
+            .state;
+        if state == MintQuoteState::Paid {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+    assert!(crashing_wallet
+        .mint(&quote.id, SplitTarget::default(), None)
+        .await
+        .is_err());
+
+    drop(crashing_wallet);
+
+    let interrupted = wallet_alice
+        .interrupted_operations()
+        .await
+        .expect("Failed to read journal");
+    assert_eq!(interrupted.len(), 2);
+
+    let result = wallet_alice
+        .recover_interrupted_operations()
+        .await
+        .expect("Failed to recover operations");
+    assert_eq!(result.completed, 2);
+    assert_eq!(result.rolled_back, 0);
+    assert_eq!(result.pending, 0);
+    assert_eq!(result.amount_recovered, Amount::from(100));
+
+    assert_eq!(
+        wallet_alice
+            .total_balance()
+            .await
+            .expect("Failed to get balance"),
+        Amount::from(100)
+    );
+    assert!(wallet_alice
+        .interrupted_operations()
+        .await
+        .expect("Failed to read journal")
+        .is_empty());
+
+    // The recovered proofs are spendable and the counters are past the recovered outputs
+    let proofs = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .expect("Failed to get proofs");
+    wallet_alice
+        .swap(None, SplitTarget::None, proofs, None, false)
+        .await
+        .expect("Failed to swap recovered proofs");
+    assert_eq!(
+        wallet_alice
+            .total_balance()
+            .await
+            .expect("Failed to get balance"),
+        Amount::from(100)
+    );
+}
+
+/// Tests that the change of a melt interrupted after the mint signed it is recovered from the
+/// journal, when the mint signs fewer change outputs than the wallet provided, and that the
+/// change can be restored from the seed
+#[tokio::test]
+async fn test_recover_interrupted_melt_change() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let wallet_alice = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let connector = DirectMintConnection::new(mint_bob.clone());
+    connector.drop_responses(true);
+    let crashing_wallet = WalletBuilder::new()
+        .mint_url(wallet_alice.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_alice.localstore.clone())
+        .seed(seed)
+        .client(connector)
+        .build()
+        .expect("Failed to build wallet");
+
+    let fake_invoice = create_fake_invoice(1000, "".to_string());
+    let melt_quote = crashing_wallet
+        .melt_quote(fake_invoice.to_string(), None)
+        .await
+        .expect("Failed to get melt quote");
+    let proofs = crashing_wallet
+        .get_unspent_proofs()
+        .await
+        .expect("Failed to get proofs");
+    assert!(crashing_wallet
+        .melt_proofs(&melt_quote.id, proofs)
+        .await
+        .is_err());
+    drop(crashing_wallet);
+
+    let interrupted = wallet_alice
+        .interrupted_operations()
+        .await
+        .expect("Failed to read journal");
+    assert_eq!(interrupted.len(), 1);
+    let change_outputs = interrupted[0].outputs.len();
+    assert_eq!(
+        interrupted[0].counters.len(),
+        change_outputs,
+        "Change outputs are derived from the reserved counters"
+    );
+
+    let result = wallet_alice
+        .recover_interrupted_operations()
+        .await
+        .expect("Failed to recover operations");
+    assert_eq!(result.completed, 1);
+    assert_eq!(result.amount_recovered, Amount::from(98));
+
+    // 98 sats of change, 1 sat paid and 1 sat of fee, are signed on 3 of the 7 blank outputs
+    let change = wallet_alice
+        .get_unspent_proofs()
+        .await
+        .expect("Failed to get proofs");
+    assert_eq!(change.total_amount().unwrap(), Amount::from(98));
+    assert!(change.len() < change_outputs);
+
+    let quote = wallet_alice
+        .localstore
+        .get_melt_quote(&melt_quote.id)
+        .await
+        .expect("Failed to get melt quote")
+        .expect("Melt quote is stored");
+    assert_eq!(quote.state, MeltQuoteState::Paid);
+
+    // A wallet restored from the seed finds the change
+    let wallet_restored = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    wallet_restored.restore().await.expect("Failed to restore");
+    assert_eq!(
+        wallet_restored
+            .total_balance()
+            .await
+            .expect("Failed to get balance"),
+        Amount::from(98)
+    );
+}
+
+/// Tests that a wallet only moves to the new url of its mint when the new url serves its keysets
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_update_mint_url_requires_same_keysets() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let mut wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 100, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let old_mint_url = wallet_alice.mint_url.clone();
+    let new_mint_url = MintUrl::from_str("https://moved.example.com").unwrap();
+
+    // A different mint behind the new url does not serve the keysets of the proofs
+    let mint_carol = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let err = wallet_alice
+        .update_mint_url_with_client(
+            new_mint_url.clone(),
+            Arc::new(DirectMintConnection::new(mint_carol)),
+        )
+        .await
+        .expect_err("Moved to a mint with other keysets");
+    assert!(matches!(err, cdk::Error::MintUrlKeysetMismatch { .. }));
+
+    assert_eq!(wallet_alice.mint_url, old_mint_url);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::from(100));
+    assert!(wallet_alice
+        .localstore
+        .get_proofs(Some(new_mint_url.clone()), None, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+
+    // The same mint behind the new url
+    wallet_alice
+        .update_mint_url_with_client(
+            new_mint_url.clone(),
+            Arc::new(DirectMintConnection::new(mint_bob.clone())),
+        )
+        .await
+        .expect("Failed to move wallet");
+
+    assert_eq!(wallet_alice.mint_url, new_mint_url);
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::from(100));
+    assert!(wallet_alice
+        .localstore
+        .get_proofs(Some(old_mint_url.clone()), None, None, None)
+        .await
+        .unwrap()
+        .is_empty());
+    assert!(wallet_alice
+        .localstore
+        .get_mint_keysets(new_mint_url)
+        .await
+        .unwrap()
+        .is_some());
+
+    // The moved proofs are still spendable
+    let proofs = wallet_alice.get_unspent_proofs().await.unwrap();
+    wallet_alice
+        .swap(None, SplitTarget::default(), proofs, None, false)
+        .await
+        .expect("Failed to swap moved proofs");
+    assert_eq!(wallet_alice.total_balance().await.unwrap(), Amount::from(100));
+}
+
+/// Tests that a wallet restored from its seed recovers its mint quotes from the mint, and only
+/// those
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_recover_mint_quotes() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let seed = bip39::Mnemonic::generate(12)
+        .unwrap()
+        .to_seed_normalized("");
+    let wallet_alice = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    let quote = wallet_alice
+        .mint_quote(Amount::from(64), None)
+        .await
+        .expect("Failed to get mint quote");
+    let second_quote = wallet_alice
+        .mint_quote(Amount::from(16), None)
+        .await
+        .expect("Failed to get mint quote");
+    wallet_carol
+        .mint_quote(Amount::from(32), None)
+        .await
+        .expect("Failed to get mint quote");
+
+    // Each quote is locked to its own key, so the mint can't link them
+    assert_ne!(quote.secret_key, second_quote.secret_key);
+
+    loop {
+        let state = wallet_alice
+            .mint_quote_state(&quote.id)
+            .await
+            .expect("Failed to check mint quote")
+            .state;
+        if state == MintQuoteState::Paid {
+            break;
+        }
+        sleep(Duration::from_millis(100)).await;
+    }
+
+    // Alice lost her storage before minting
+    let wallet_restored = create_test_wallet_for_mint_with_seed(mint_bob.clone(), seed)
+        .await
+        .expect("Failed to create test wallet");
+    let recovered = wallet_restored
+        .recover_mint_quotes()
+        .await
+        .expect("Failed to recover mint quotes");
+    assert_eq!(recovered.len(), 2);
+    assert_eq!(recovered[0].id, quote.id);
+    assert_eq!(recovered[0].state, MintQuoteState::Paid);
+    assert_eq!(recovered[0].amount_paid, Amount::from(64));
+    assert_eq!(recovered[1].id, second_quote.id);
+
+    // Known quotes are not recovered again
+    assert!(wallet_restored
+        .recover_mint_quotes()
+        .await
+        .expect("Failed to recover mint quotes")
+        .is_empty());
+
+    let proofs = wallet_restored
+        .mint(&quote.id, SplitTarget::default(), None)
+        .await
+        .expect("Failed to mint recovered quote");
+    assert_eq!(proofs.total_amount().unwrap(), Amount::from(64));
+
+    // New quotes use keys past the recovered ones
+    let new_quote = wallet_restored
+        .mint_quote(Amount::from(8), None)
+        .await
+        .expect("Failed to get mint quote");
+    assert_ne!(new_quote.secret_key, quote.secret_key);
+    assert_ne!(new_quote.secret_key, second_quote.secret_key);
+}
+
+/// Tests inspecting a token with the keysets cached offline:
+/// 1. Carol prefetched the mint's keysets, her inspection is complete and the DLEQ proofs verify
+/// 2. The keyset rotated out is kept after the next prefetch, as inactive
+/// 3. Dave never contacted the mint, he only sees the structure of the token
+#[tokio::test]
+async fn test_inspect_token_offline() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_dave = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+    let token = wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send");
+
+    wallet_carol
+        .prefetch_keysets()
+        .await
+        .expect("Failed to prefetch keysets");
+    let inspection = wallet_carol
+        .inspect_token(&token)
+        .await
+        .expect("Failed to inspect token");
+    assert!(inspection.is_complete());
+    assert_eq!(inspection.value, Amount::from(10));
+    assert_eq!(inspection.unit, Some(CurrencyUnit::Sat));
+    assert_eq!(
+        inspection.amounts().unwrap().get(&CurrencyUnit::Sat),
+        Some(&Amount::from(10))
+    );
+    assert!(inspection.keysets.iter().all(|keyset| {
+        keyset.status == OfflineKeysetStatus::Active
+            && keyset
+                .proofs
+                .iter()
+                .all(|proof| proof.dleq == OfflineDleqStatus::Valid)
+    }));
+
+    // The keyset of the token is rotated out, Carol keeps it
+    mint_bob
+        .rotate_keyset(
+            CurrencyUnit::Sat,
+            cdk_integration_tests::standard_keyset_amounts(32),
+            0,
+        )
+        .await
+        .expect("Failed to rotate keyset");
+    wallet_carol
+        .prefetch_keysets()
+        .await
+        .expect("Failed to prefetch keysets");
+    let inspection = wallet_carol
+        .inspect_token(&token)
+        .await
+        .expect("Failed to inspect token");
+    assert!(inspection.is_complete());
+    assert!(inspection
+        .keysets
+        .iter()
+        .all(|keyset| keyset.status == OfflineKeysetStatus::Inactive));
+
+    // Dave's store has never seen the mint
+    let inspection = wallet_dave
+        .inspect_token(&token)
+        .await
+        .expect("Failed to inspect token");
+    assert!(!inspection.is_complete());
+    assert_eq!(inspection.value, Amount::from(10));
+    assert!(inspection.amounts().unwrap().is_empty());
+    assert!(inspection.keysets.iter().all(|keyset| {
+        keyset.unit.is_none()
+            && !keyset.keys_cached
+            && keyset.status == OfflineKeysetStatus::Unknown
+    }));
+}
+
+/// Tests receiving a token while the mint is unreachable:
+/// 1. Carol prefetched the mint's keysets, then loses her connection to the mint
+/// 2. Receiving fails, unless deferring is asked, then the token is stored to claim later
+/// 3. Claiming fails while the mint is unreachable and the token is kept
+/// 4. Once the mint is back the token is claimed into the balance
+#[tokio::test]
+async fn test_receive_deferred_while_mint_unreachable() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let wallet_alice = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+    let wallet_carol = create_test_wallet_for_mint(mint_bob.clone())
+        .await
+        .expect("Failed to create test wallet");
+
+    fund_wallet(wallet_alice.clone(), 64, None)
+        .await
+        .expect("Failed to fund wallet");
+    let token = wallet_alice
+        .prepare_send(10.into(), SendOptions::default())
+        .await
+        .expect("Failed to prepare send")
+        .confirm(None)
+        .await
+        .expect("Failed to send")
+        .to_string();
+
+    wallet_carol
+        .prefetch_keysets()
+        .await
+        .expect("Failed to prefetch keysets");
+
+    // Same store as Carol's wallet, started while the mint is unreachable
+    let connector = Arc::new(DirectMintConnection::new(mint_bob.clone()));
+    connector.set_offline(true);
+    let offline_carol = WalletBuilder::new()
+        .mint_url(wallet_carol.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(wallet_carol.localstore.clone())
+        .seed(bip39::Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+        .shared_client(connector.clone())
+        .build()
+        .expect("Failed to build wallet");
+
+    assert!(offline_carol
+        .receive(&token, ReceiveOptions::default())
+        .await
+        .is_err());
+
+    let defer = ReceiveOptions {
+        defer_if_unreachable: true,
+        ..Default::default()
+    };
+    let result = offline_carol
+        .receive_with_result(&token, defer)
+        .await
+        .expect("Failed to defer receive");
+    assert_eq!(result.amount_deferred, Amount::from(10));
+    assert_eq!(result.amount_claimed, Amount::ZERO);
+    assert_eq!(offline_carol.total_balance().await.unwrap(), Amount::ZERO);
+    assert_eq!(offline_carol.deferred_receives().await.unwrap().len(), 1);
+
+    assert!(offline_carol.claim_deferred_receives().await.is_err());
+    assert_eq!(offline_carol.deferred_receives().await.unwrap().len(), 1);
+
+    connector.set_offline(false);
+    let claimed = offline_carol
+        .claim_deferred_receives()
+        .await
+        .expect("Failed to claim deferred receives");
+    assert_eq!(claimed, Amount::from(10));
+    assert_eq!(offline_carol.total_balance().await.unwrap(), claimed);
+    assert!(offline_carol.deferred_receives().await.unwrap().is_empty());
+}
+
+/// Wallet funded with `amount` whose connection to the mint and its LNURL endpoint is returned
+async fn create_auto_melt_wallet(mint: &Mint, amount: u64) -> (Wallet, Arc<DirectMintConnection>) {
+    let funded = create_test_wallet_for_mint(mint.clone())
+        .await
+        .expect("Failed to create test wallet");
+    fund_wallet(funded.clone(), amount, None)
+        .await
+        .expect("Failed to fund wallet");
+
+    let connector = Arc::new(DirectMintConnection::new(mint.clone()));
+    let wallet = WalletBuilder::new()
+        .mint_url(funded.mint_url.clone())
+        .unit(CurrencyUnit::Sat)
+        .localstore(funded.localstore.clone())
+        .seed(bip39::Mnemonic::generate(12).unwrap().to_seed_normalized(""))
+        .shared_client(connector.clone())
+        .build()
+        .expect("Failed to build wallet");
+
+    (wallet, connector)
+}
+
+async fn auto_melt_transactions(wallet: &Wallet) -> Vec<cdk::wallet::types::Transaction> {
+    wallet
+        .list_transactions(
+            TransactionFilter::new().with_kind(TransactionKind::Melt),
+            Pagination::default(),
+        )
+        .await
+        .expect("Failed to list transactions")
+        .into_iter()
+        .filter(|tx| tx.metadata.get(AUTO_MELT_METADATA_KEY).map(String::as_str) == Some("true"))
+        .collect()
+}
+
+/// Test that the balance above the threshold and reserve is paid to a Lightning address
+#[tokio::test]
+async fn test_auto_melt_to_lightning_address() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let (wallet, _connector) = create_auto_melt_wallet(&mint_bob, 1000).await;
+
+    assert!(matches!(
+        wallet.run_auto_melt().await.unwrap(),
+        AutoMeltOutcome::Disabled
+    ));
+
+    wallet
+        .set_auto_melt(
+            Amount::from(600),
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        )
+        .await
+        .expect("Failed to set auto melt");
+    wallet
+        .set_auto_melt_reserve(Amount::from(10))
+        .await
+        .expect("Failed to set auto melt reserve");
+
+    let melted = match wallet.run_auto_melt().await.expect("Auto melt failed") {
+        AutoMeltOutcome::Melted(melted) => melted,
+        outcome => panic!("Expected a melt, got {:?}", outcome),
+    };
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+    assert_eq!(melted.amount, Amount::from(390));
+    assert_eq!(
+        wallet.total_balance().await.unwrap(),
+        Amount::from(610) - melted.fee_paid
+    );
+
+    let transactions = auto_melt_transactions(&wallet).await;
+    assert_eq!(transactions.len(), 1);
+    assert_eq!(transactions[0].amount, Amount::from(390));
+    assert_eq!(
+        transactions[0].metadata.get("lightning_address"),
+        Some(&"alice@example.com".to_string())
+    );
+
+    // Nothing is left above the threshold and reserve
+    assert!(matches!(
+        wallet.run_auto_melt().await.unwrap(),
+        AutoMeltOutcome::BelowThreshold
+    ));
+    assert_eq!(auto_melt_transactions(&wallet).await.len(), 1);
+
+    wallet.clear_auto_melt().await.unwrap();
+    assert!(matches!(
+        wallet.run_auto_melt().await.unwrap(),
+        AutoMeltOutcome::Disabled
+    ));
+}
+
+/// Test that a failed auto melt leaves the balance untouched and backs off before retrying
+#[tokio::test]
+async fn test_auto_melt_backs_off_after_failure() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let (wallet, connector) = create_auto_melt_wallet(&mint_bob, 1000).await;
+
+    wallet
+        .set_auto_melt(
+            Amount::from(500),
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        )
+        .await
+        .expect("Failed to set auto melt");
+
+    connector.set_lnurl_offline(true);
+    assert!(wallet.run_auto_melt().await.is_err());
+    assert_eq!(wallet.total_balance().await.unwrap(), Amount::from(1000));
+    assert!(auto_melt_transactions(&wallet).await.is_empty());
+
+    let state = wallet.auto_melt_state().await.unwrap();
+    assert_eq!(state.failures, 1);
+    assert!(state.last_error.is_some());
+
+    // The endpoint is back, but the next attempt waits for the backoff
+    connector.set_lnurl_offline(false);
+    match wallet.run_auto_melt().await.unwrap() {
+        AutoMeltOutcome::BackingOff { retry_at } => assert_eq!(retry_at, state.retry_at),
+        outcome => panic!("Expected a backoff, got {:?}", outcome),
+    }
+    assert_eq!(wallet.total_balance().await.unwrap(), Amount::from(1000));
+
+    // Setting the policy again retries right away
+    wallet
+        .set_auto_melt(
+            Amount::from(500),
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        )
+        .await
+        .expect("Failed to set auto melt");
+    assert!(matches!(
+        wallet.run_auto_melt().await.expect("Auto melt failed"),
+        AutoMeltOutcome::Melted(_)
+    ));
+    assert_eq!(wallet.auto_melt_state().await.unwrap(), Default::default());
+    assert_eq!(auto_melt_transactions(&wallet).await.len(), 1);
+}
+
+/// Test that an auto melt whose response is lost is not paid twice
+#[tokio::test]
+async fn test_auto_melt_lost_response_is_not_melted_twice() {
+    setup_tracing();
+    let mint_bob = create_and_start_test_mint()
+        .await
+        .expect("Failed to create test mint");
+    let (wallet, connector) = create_auto_melt_wallet(&mint_bob, 1000).await;
+
+    wallet
+        .set_auto_melt(
+            Amount::from(500),
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        )
+        .await
+        .expect("Failed to set auto melt");
+
+    // The mint pays, but the wallet does not learn about it
+    connector.drop_responses(true);
+    assert!(wallet.run_auto_melt().await.is_err());
+    assert_eq!(wallet.auto_melt_state().await.unwrap().failures, 1);
+    connector.drop_responses(false);
+
+    // The spent inputs are no longer part of the balance, the change is recovered
+    wallet
+        .recover_interrupted_operations()
+        .await
+        .expect("Failed to recover operations");
+    let balance = wallet.total_balance().await.unwrap();
+    assert!(balance <= Amount::from(500));
+
+    wallet
+        .set_auto_melt(
+            Amount::from(500),
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        )
+        .await
+        .expect("Failed to set auto melt");
+    assert!(matches!(
+        wallet.run_auto_melt().await.unwrap(),
+        AutoMeltOutcome::BelowThreshold
+    ));
+    assert_eq!(wallet.total_balance().await.unwrap(), balance);
+}
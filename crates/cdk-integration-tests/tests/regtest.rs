@@ -13,6 +13,7 @@
 //! - Requires properly configured LND nodes with TLS certificates and macaroons
 //! - Uses real Bitcoin transactions in regtest mode
 
+use std::str::FromStr;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -20,15 +21,21 @@ use bip39::Mnemonic;
 use cashu::ProofsMethods;
 use cdk::amount::{Amount, SplitTarget};
 use cdk::nuts::{
-    CurrencyUnit, MeltOptions, MeltQuoteState, MintQuoteState, MintRequest, Mpp,
-    NotificationPayload, PreMintSecrets,
+    CurrencyUnit, MeltOptions, MeltQuoteBolt11Request, MeltQuoteState, MintQuoteState,
+    MintRequest, Mpp, NotificationPayload, PreMintSecrets,
 };
 use cdk::wallet::{HttpClient, MintConnector, Wallet, WalletSubscription};
+use cdk_integration_tests::init_regtest::{get_cln_dir, get_temp_dir};
 use cdk_integration_tests::{get_mint_url_from_env, get_second_mint_url_from_env, get_test_client};
 use cdk_sqlite::wallet::{self, memory};
+use cln_rpc::model::requests::{ListpaysRequest, ListpaysStatus};
+use cln_rpc::primitives::Sha256;
 use futures::join;
+use lightning_invoice::Bolt11Invoice;
+use ln_regtest_rs::ln_client::{ClnClient, LightningClient};
 use tokio::time::timeout;
 
+const CLN_URL: &str = "http://127.0.0.1:8085";
 const LDK_URL: &str = "http://127.0.0.1:8089";
 
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
@@ -395,6 +402,124 @@ async fn test_regtest_melt_amountless() {
     assert!(melt.amount == 5.into());
 }
 
+/// Change of a melt routed over an intermediate node matches the amount CLN reports as sent
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_regtest_melt_change_matches_routing_fee() {
+    if get_mint_url_from_env() != CLN_URL {
+        return;
+    }
+
+    let ln_client = get_test_client().await;
+
+    let wallet = Wallet::new(
+        &get_mint_url_from_env(),
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("failed to create new wallet");
+
+    let mint_amount = Amount::from(100);
+
+    let mint_quote = wallet.mint_quote(mint_amount, None).await.unwrap();
+
+    ln_client
+        .pay_invoice(mint_quote.request)
+        .await
+        .expect("failed to pay invoice");
+
+    let proofs = wallet
+        .mint(&mint_quote.id, SplitTarget::default(), None)
+        .await
+        .unwrap();
+
+    // CLN two has no channel with CLN one, the payment is forwarded by LND
+    let temp_dir = get_temp_dir();
+    let cln_two_client = ClnClient::new(get_cln_dir(&temp_dir, "two"), None)
+        .await
+        .unwrap();
+    let invoice = cln_two_client.create_invoice(Some(10)).await.unwrap();
+
+    let melt_quote = wallet.melt_quote(invoice.clone(), None).await.unwrap();
+    let input_fee = wallet.get_proofs_fee(&proofs).await.unwrap().total;
+
+    let melt = wallet.melt_proofs(&melt_quote.id, proofs).await.unwrap();
+    assert_eq!(melt.state, MeltQuoteState::Paid);
+
+    let payment_hash = *Bolt11Invoice::from_str(&invoice)
+        .unwrap()
+        .payment_hash()
+        .as_ref();
+    let mut cln_one_client = cln_rpc::ClnRpc::new(
+        get_cln_dir(&temp_dir, "one")
+            .join("regtest")
+            .join("lightning-rpc"),
+    )
+    .await
+    .unwrap();
+    let pays = cln_one_client
+        .call_typed(&ListpaysRequest {
+            payment_hash: Some(*Sha256::from_bytes_ref(&payment_hash)),
+            bolt11: None,
+            status: Some(ListpaysStatus::COMPLETE),
+            start: None,
+            index: None,
+            limit: None,
+        })
+        .await
+        .unwrap()
+        .pays;
+    let [pay] = pays.as_slice() else {
+        panic!("Expected one completed payment, got {}", pays.len());
+    };
+
+    let amount_sent = Amount::from(pay.amount_sent_msat.unwrap().msat() / 1_000);
+    let change = melt
+        .change
+        .map(|change| change.total_amount().unwrap())
+        .unwrap_or_default();
+
+    assert!(amount_sent >= melt.amount);
+    assert_eq!(change, mint_amount - amount_sent - input_fee);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 1)]
+async fn test_regtest_melt_amountless_requires_amount() {
+    let ln_client = get_test_client().await;
+
+    let wallet = Wallet::new(
+        &get_mint_url_from_env(),
+        CurrencyUnit::Sat,
+        Arc::new(memory::empty().await.unwrap()),
+        Mnemonic::generate(12).unwrap().to_seed_normalized(""),
+        None,
+    )
+    .expect("failed to create new wallet");
+
+    let invoice = ln_client.create_invoice(None).await.unwrap();
+
+    let http_client = HttpClient::new(get_mint_url_from_env().parse().unwrap(), None);
+
+    let quote_request = MeltQuoteBolt11Request {
+        request: cdk::Bolt11Invoice::from_str(&invoice).unwrap(),
+        unit: CurrencyUnit::Sat,
+        options: None,
+    };
+
+    match http_client.post_melt_quote(quote_request).await {
+        Err(cdk::Error::AmountLessNotAllowed) => (),
+        res => panic!("Expected zero-amount invoice without amount to be rejected, got {res:?}"),
+    }
+
+    let melt_quote = wallet
+        .melt_quote(invoice, Some(MeltOptions::new_amountless(5_000)))
+        .await
+        .unwrap();
+
+    assert_eq!(melt_quote.amount, Amount::from(5));
+}
+
 #[tokio::test(flavor = "multi_thread", worker_threads = 1)]
 async fn test_attempt_to_mint_unpaid() {
     let wallet = Wallet::new(
@@ -22,6 +22,17 @@ pub enum Error {
     Anyhow(#[from] anyhow::Error),
 }
 
+/// Failure of a request to the LNbits api
+#[derive(Debug, Error)]
+pub(crate) enum ApiError {
+    /// LNbits answered with an error status
+    #[error("LNbits answered {0}")]
+    Status(reqwest::StatusCode),
+    /// The request failed before LNbits answered, it may still be processed
+    #[error(transparent)]
+    Transport(#[from] reqwest::Error),
+}
+
 impl From<Error> for cdk_common::payment::Error {
     fn from(e: Error) -> Self {
         Self::Lightning(Box::new(e))
@@ -6,6 +6,7 @@ use std::cmp::max;
 use std::pin::Pin;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
@@ -19,22 +20,32 @@ use cdk_common::payment::{
 };
 use cdk_common::util::{hex, unix_time};
 use cdk_common::Bolt11Invoice;
-use error::Error;
+use error::{ApiError, Error};
 use futures::Stream;
 use lnbits_rs::api::invoice::CreateInvoiceRequest;
+use lnbits_rs::api::payment::Payment;
 use lnbits_rs::LNBitsClient;
+use reqwest::{StatusCode, Url};
 use tokio_util::sync::CancellationToken;
 
 pub mod error;
 
+/// Time allowed for LNbits to answer a pay request before the payment is looked up instead
+const PAY_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
 /// LNbits
 #[derive(Clone)]
 pub struct LNbits {
     lnbits_api: LNBitsClient,
+    http_client: reqwest::Client,
+    payments_url: Url,
+    admin_api_key: String,
     fee_reserve: FeeReserve,
     wait_invoice_cancel_token: CancellationToken,
     wait_invoice_is_active: Arc<AtomicBool>,
     settings: SettingsResponse,
+    payment_timeout: Option<Duration>,
+    pay_request_timeout: Duration,
 }
 
 impl std::fmt::Debug for LNbits {
@@ -55,9 +66,15 @@ impl LNbits {
         fee_reserve: FeeReserve,
     ) -> Result<Self, Error> {
         let lnbits_api = LNBitsClient::new("", &admin_api_key, &invoice_api_key, &api_url, None)?;
+        let payments_url = Url::parse(&api_url)
+            .and_then(|api_url| api_url.join("api/v1/payments"))
+            .map_err(|err| Error::Anyhow(err.into()))?;
 
         Ok(Self {
             lnbits_api,
+            http_client: reqwest::Client::new(),
+            payments_url,
+            admin_api_key,
             fee_reserve,
             wait_invoice_cancel_token: CancellationToken::new(),
             wait_invoice_is_active: Arc::new(AtomicBool::new(false)),
@@ -71,9 +88,26 @@ impl LNbits {
                 bolt12: None,
                 custom: std::collections::HashMap::new(),
             },
+            payment_timeout: None,
+            pay_request_timeout: PAY_REQUEST_TIMEOUT,
         })
     }
 
+    /// Set the maximum time the mint waits for an outgoing payment
+    pub fn with_payment_timeout(mut self, payment_timeout: Duration) -> Self {
+        self.payment_timeout = Some(payment_timeout);
+        self
+    }
+
+    /// Set the time allowed for LNbits to answer a pay request
+    ///
+    /// A pay request that is not answered in time is not retried, the payment is looked up by
+    /// its payment hash instead.
+    pub fn with_pay_request_timeout(mut self, pay_request_timeout: Duration) -> Self {
+        self.pay_request_timeout = pay_request_timeout;
+        self
+    }
+
     /// Subscribe to lnbits ws
     pub async fn subscribe_ws(&self) -> Result<(), Error> {
         if rustls::crypto::CryptoProvider::get_default().is_none() {
@@ -135,6 +169,51 @@ impl LNbits {
         }))
     }
 
+    /// Outgoing payment LNbits knows for `payment_hash`, if any
+    ///
+    /// LNbits records a payment under its payment hash before sending it, so the payment is
+    /// found even if the response to the pay request was lost.
+    async fn find_outgoing_payment(&self, payment_hash: &str) -> Result<Option<Payment>, Error> {
+        let url = Url::parse(&format!("{}/{payment_hash}", self.payments_url))
+            .map_err(|err| Error::Anyhow(err.into()))?;
+
+        match self.admin_request(self.http_client.get(url)).await {
+            Ok(body) => {
+                let payment: Payment =
+                    serde_json::from_str(&body).map_err(|err| Error::Anyhow(err.into()))?;
+                // Outgoing payments have a negative amount, a positive one is an invoice of this
+                // wallet
+                Ok((payment.details.amount < 0).then_some(payment))
+            }
+            Err(ApiError::Status(StatusCode::NOT_FOUND)) => Ok(None),
+            Err(err) => Err(Error::Anyhow(err.into())),
+        }
+    }
+
+    /// Ask LNbits to pay `bolt11`
+    async fn send_pay_request(&self, bolt11: &str) -> Result<(), ApiError> {
+        let body = serde_json::json!({ "out": true, "bolt11": bolt11 });
+
+        self.admin_request(self.http_client.post(self.payments_url.clone()).json(&body))
+            .await
+            .map(|_| ())
+    }
+
+    /// Send `request` with the admin key, returning the body of a successful response
+    async fn admin_request(&self, request: reqwest::RequestBuilder) -> Result<String, ApiError> {
+        let response = request
+            .header("X-Api-Key", &self.admin_api_key)
+            .send()
+            .await?;
+
+        let status = response.status();
+        if !status.is_success() {
+            return Err(ApiError::Status(status));
+        }
+
+        Ok(response.text().await?)
+    }
+
     /// Decode a hex payment hash string into a byte array
     fn decode_payment_hash(hash_str: &str) -> Result<[u8; 32], Error> {
         let decoded = hex::decode(hash_str)
@@ -154,6 +233,10 @@ impl MintPayment for LNbits {
         Ok(self.settings.clone())
     }
 
+    fn payment_timeout(&self) -> Option<Duration> {
+        self.payment_timeout
+    }
+
     fn is_wait_invoice_active(&self) -> bool {
         self.wait_invoice_is_active.load(Ordering::SeqCst)
     }
@@ -279,55 +362,76 @@ impl MintPayment for LNbits {
     ) -> Result<MakePaymentResponse, Self::Err> {
         match options {
             OutgoingPaymentOptions::Bolt11(bolt11_options) => {
-                let pay_response = self
-                    .lnbits_api
-                    .pay_invoice(&bolt11_options.bolt11.to_string(), None)
-                    .await
-                    .map_err(|err| {
-                        tracing::error!("Could not pay invoice");
-                        tracing::error!("{}", err.to_string());
-                        Self::Err::Anyhow(anyhow!("Could not pay invoice"))
-                    })?;
-
-                let invoice_info = self
-                    .lnbits_api
-                    .get_payment_info(&pay_response.payment_hash)
-                    .await
-                    .map_err(|err| {
-                        tracing::error!("Could not find invoice");
-                        tracing::error!("{}", err.to_string());
-                        Self::Err::Anyhow(anyhow!("Could not find invoice"))
-                    })?;
+                let payment_hash = *bolt11_options.bolt11.payment_hash().as_ref();
+                let payment_hash_hex = hex::encode(payment_hash);
+
+                // LNbits has no idempotency key for payments, the payment hash identifies the
+                // payment instead. A payment already in flight or made is never sent again.
+                if let Some(payment) = self.find_outgoing_payment(&payment_hash_hex).await? {
+                    if lnbits_to_melt_status(&payment.details.status) != MeltQuoteState::Unpaid {
+                        tracing::warn!(
+                            "LNbits already has a payment for {}, not paying again",
+                            payment_hash_hex
+                        );
+                        return Ok(outgoing_payment_response(payment_hash, &payment, unit)?);
+                    }
+                }
 
-                let status = if invoice_info.paid {
-                    MeltQuoteState::Paid
-                } else {
-                    MeltQuoteState::Unpaid
+                // The pay request is sent once only. If it fails or its response is lost the
+                // outcome is taken from the payment LNbits recorded, never from a retry.
+                let pay_result = tokio::time::timeout(
+                    self.pay_request_timeout,
+                    self.send_pay_request(&bolt11_options.bolt11.to_string()),
+                )
+                .await;
+
+                // Only a client error status means LNbits refused the request. Without an
+                // answer, or with a server error, the payment may still be sent.
+                let rejected = match pay_result {
+                    Ok(Ok(())) => false,
+                    Ok(Err(ApiError::Status(status))) if status.is_client_error() => {
+                        tracing::error!(
+                            "LNbits refused to pay invoice {}: {}",
+                            payment_hash_hex,
+                            status
+                        );
+                        true
+                    }
+                    Ok(Err(err)) => {
+                        tracing::error!("Could not pay invoice {}: {}", payment_hash_hex, err);
+                        false
+                    }
+                    Err(_) => {
+                        tracing::warn!(
+                            "LNbits did not answer the pay request for {} in time",
+                            payment_hash_hex
+                        );
+                        false
+                    }
                 };
 
-                let total_spent_msat = Amount::new(
-                    invoice_info
-                        .details
-                        .amount
-                        .unsigned_abs()
-                        .checked_add(invoice_info.details.fee.unsigned_abs())
-                        .ok_or(Error::AmountOverflow)?,
-                    CurrencyUnit::Msat,
-                );
-
-                let total_spent = total_spent_msat.convert_to(unit)?;
-
-                Ok(MakePaymentResponse {
-                    payment_lookup_id: PaymentIdentifier::PaymentHash(
-                        hex::decode(pay_response.payment_hash)
-                            .map_err(|_| Error::InvalidPaymentHash)?
-                            .try_into()
-                            .map_err(|_| Error::InvalidPaymentHash)?,
-                    ),
-                    payment_proof: Some(invoice_info.details.payment_hash),
-                    status,
-                    total_spent,
-                })
+                match self.find_outgoing_payment(&payment_hash_hex).await {
+                    Ok(Some(payment)) => {
+                        Ok(outgoing_payment_response(payment_hash, &payment, unit)?)
+                    }
+                    // Nothing recorded for a rejected pay request, so nothing was paid
+                    Ok(None) if rejected => {
+                        Err(Self::Err::Anyhow(anyhow!("Could not pay invoice")))
+                    }
+                    // The request may still be processed, the payment is checked again later
+                    Ok(None) | Err(_) => {
+                        tracing::warn!(
+                            "Could not find the LNbits payment for {}, leaving it pending",
+                            payment_hash_hex
+                        );
+                        Ok(MakePaymentResponse {
+                            payment_lookup_id: PaymentIdentifier::PaymentHash(payment_hash),
+                            payment_proof: None,
+                            status: MeltQuoteState::Pending,
+                            total_spent: Amount::new(0, unit.clone()),
+                        })
+                    }
+                }
             }
             OutgoingPaymentOptions::Bolt12(_) => {
                 Err(Self::Err::Anyhow(anyhow!("BOLT12 not supported by LNbits")))
@@ -425,9 +529,13 @@ impl MintPayment for LNbits {
         &self,
         payment_identifier: &PaymentIdentifier,
     ) -> Result<MakePaymentResponse, Self::Err> {
+        // LNbits is queried by payment hash, which is known even if the pay response was lost
+        let PaymentIdentifier::PaymentHash(payment_hash) = payment_identifier else {
+            return Err(Error::InvalidPaymentHash.into());
+        };
+
         let payment = self
-            .lnbits_api
-            .get_payment_info(&payment_identifier.to_string())
+            .find_outgoing_payment(&hex::encode(payment_hash))
             .await
             .map_err(|err| {
                 tracing::error!("Could not check invoice status");
@@ -435,20 +543,49 @@ impl MintPayment for LNbits {
                 Self::Err::Anyhow(anyhow!("Could not check invoice status"))
             })?;
 
-        let pay_response = MakePaymentResponse {
-            payment_lookup_id: payment_identifier.clone(),
-            payment_proof: payment.preimage,
-            status: lnbits_to_melt_status(&payment.details.status),
-            total_spent: Amount::new(
-                payment.details.amount.unsigned_abs() + payment.details.fee.unsigned_abs(),
-                CurrencyUnit::Msat,
-            ),
-        };
-
-        Ok(pay_response)
+        match payment {
+            Some(payment) => Ok(outgoing_payment_response(
+                *payment_hash,
+                &payment,
+                &CurrencyUnit::Msat,
+            )?),
+            // LNbits records a payment before sending it, without a record nothing was paid
+            None => Ok(MakePaymentResponse {
+                payment_lookup_id: payment_identifier.clone(),
+                payment_proof: None,
+                status: MeltQuoteState::Unpaid,
+                total_spent: Amount::new(0, CurrencyUnit::Msat),
+            }),
+        }
     }
 }
 
+/// Payment response for an outgoing LNbits payment, with the amount spent in `unit`
+fn outgoing_payment_response(
+    payment_hash: [u8; 32],
+    payment: &Payment,
+    unit: &CurrencyUnit,
+) -> Result<MakePaymentResponse, Error> {
+    let total_spent_msat = Amount::new(
+        payment
+            .details
+            .amount
+            .unsigned_abs()
+            .checked_add(payment.details.fee.unsigned_abs())
+            .ok_or(Error::AmountOverflow)?,
+        CurrencyUnit::Msat,
+    );
+
+    Ok(MakePaymentResponse {
+        payment_lookup_id: PaymentIdentifier::PaymentHash(payment_hash),
+        payment_proof: payment.preimage.clone(),
+        status: lnbits_to_melt_status(&payment.details.status),
+        total_spent: total_spent_msat
+            .convert_to(unit)
+            .map_err(|err| Error::Anyhow(err.into()))?,
+    })
+}
+
 fn lnbits_to_melt_status(status: &str) -> MeltQuoteState {
     match status {
         "success" => MeltQuoteState::Paid,
@@ -457,3 +594,252 @@ fn lnbits_to_melt_status(status: &str) -> MeltQuoteState {
         _ => MeltQuoteState::Unknown,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use axum::body::{Body, Bytes};
+    use axum::extract::State;
+    use axum::response::{IntoResponse, Response};
+    use axum::routing::{get, post};
+    use axum::{Json, Router};
+    use cdk_common::payment::Bolt11OutgoingPaymentOptions;
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    const INVOICE: &str = "lnbc100n1pnvpufspp5djn8hrq49r8cghwye9kqw752qjncwyfnrprhprpqk43mwcy4yfsqdq5g9kxy7fqd9h8vmmfvdjscqzzsxqyz5vqsp5uhpjt36rj75pl7jq2sshaukzfkt7uulj456s4mh7uy7l6vx7lvxs9qxpqysgqedwz08acmqwtk8g4vkwm2w78suwt2qyzz6jkkwcgrjm3r3hs6fskyhvud4fan3keru7emjm8ygqpcrwtlmhfjfmer3afs5hhwamgr4cqtactdq";
+
+    /// LNbits payments api answering pay requests after `pay_delay`
+    ///
+    /// The payment is recorded as `status` once the pay request arrived, or from the start if
+    /// `recorded` is set. Pay requests are answered with `pay_status`, or their response is cut
+    /// off if `drop_pay_response` is set.
+    #[derive(Clone)]
+    struct MockLnbits {
+        pay_requests: Arc<AtomicUsize>,
+        pay_delay: Duration,
+        pay_status: StatusCode,
+        drop_pay_response: bool,
+        status: Option<&'static str>,
+        recorded: bool,
+    }
+
+    impl MockLnbits {
+        fn new(pay_delay: Duration, status: Option<&'static str>) -> Self {
+            Self {
+                pay_requests: Arc::new(AtomicUsize::new(0)),
+                pay_delay,
+                pay_status: StatusCode::CREATED,
+                drop_pay_response: false,
+                status,
+                recorded: false,
+            }
+        }
+
+        fn payment(&self, payment_hash: &str) -> Option<Value> {
+            let status = self.status?;
+            if !self.recorded && self.pay_requests.load(Ordering::SeqCst) == 0 {
+                return None;
+            }
+
+            Some(json!({
+                "paid": status == "success",
+                "preimage": (status == "success").then_some("00".repeat(32)),
+                "details": {
+                    "status": status,
+                    "checking_id": payment_hash,
+                    "amount": -10_000,
+                    "fee": -1_000,
+                    "memo": "",
+                    "time": 1_700_000_000,
+                    "created_at": 1_700_000_000,
+                    "updated_at": 1_700_000_000,
+                    "bolt11": INVOICE,
+                    "preimage": null,
+                    "payment_hash": payment_hash,
+                    "expiry": 1_700_000_600,
+                    "extra": {},
+                    "wallet_id": "wallet",
+                },
+            }))
+        }
+
+        /// Serve the api, returning the LNbits backend using it
+        async fn serve(&self) -> LNbits {
+            let app = Router::new()
+                .route("/api/v1/payments", post(pay))
+                .route("/api/v1/payments/{payment_hash}", get(payment))
+                .with_state(self.clone());
+            let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+            let address = listener.local_addr().unwrap();
+            tokio::spawn(async move { axum::serve(listener, app).await });
+
+            LNbits::new(
+                "admin".to_string(),
+                "invoice".to_string(),
+                format!("http://{address}"),
+                FeeReserve {
+                    min_fee_reserve: 1.into(),
+                    percent_fee_reserve: 0.02,
+                },
+            )
+            .await
+            .unwrap()
+            .with_pay_request_timeout(Duration::from_millis(200))
+        }
+    }
+
+    async fn pay(State(mock): State<MockLnbits>) -> Response {
+        mock.pay_requests.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(mock.pay_delay).await;
+        if mock.drop_pay_response {
+            let body = futures::stream::once(async {
+                Err::<Bytes, _>(std::io::Error::other("connection dropped"))
+            });
+            return Body::from_stream(body).into_response();
+        }
+
+        let invoice: Bolt11Invoice = INVOICE.parse().unwrap();
+        (
+            mock.pay_status,
+            Json(json!({ "payment_hash": invoice.payment_hash().to_string() })),
+        )
+            .into_response()
+    }
+
+    async fn payment(
+        State(mock): State<MockLnbits>,
+        axum::extract::Path(payment_hash): axum::extract::Path<String>,
+    ) -> Result<Json<Value>, StatusCode> {
+        mock.payment(&payment_hash)
+            .map(Json)
+            .ok_or(StatusCode::NOT_FOUND)
+    }
+
+    async fn make_payment(lnbits: &LNbits) -> Result<MakePaymentResponse, payment::Error> {
+        let options = OutgoingPaymentOptions::Bolt11(Box::new(Bolt11OutgoingPaymentOptions {
+            bolt11: INVOICE.parse().unwrap(),
+            max_fee_amount: None,
+            timeout_secs: None,
+            melt_options: None,
+        }));
+
+        lnbits.make_payment(&CurrencyUnit::Sat, options).await
+    }
+
+    fn payment_hash() -> PaymentIdentifier {
+        let invoice: Bolt11Invoice = INVOICE.parse().unwrap();
+        PaymentIdentifier::PaymentHash(*invoice.payment_hash().as_ref())
+    }
+
+    #[tokio::test]
+    async fn test_pay_timeout_then_success() {
+        let mock = MockLnbits::new(Duration::from_secs(5), Some("success"));
+        let lnbits = mock.serve().await;
+
+        let response = make_payment(&lnbits).await.unwrap();
+
+        assert_eq!(response.status, MeltQuoteState::Paid);
+        assert_eq!(response.payment_lookup_id, payment_hash());
+        assert_eq!(response.total_spent, Amount::new(11, CurrencyUnit::Sat));
+        assert_eq!(response.payment_proof, Some("00".repeat(32)));
+        assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 1);
+
+        let checked = lnbits
+            .check_outgoing_payment(&payment_hash())
+            .await
+            .unwrap();
+        assert_eq!(checked.status, MeltQuoteState::Paid);
+    }
+
+    #[tokio::test]
+    async fn test_pay_timeout_then_failure() {
+        let mock = MockLnbits::new(Duration::from_secs(5), Some("failed"));
+        let lnbits = mock.serve().await;
+
+        let response = make_payment(&lnbits).await.unwrap();
+
+        assert_eq!(response.status, MeltQuoteState::Unpaid);
+        assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 1);
+
+        let checked = lnbits
+            .check_outgoing_payment(&payment_hash())
+            .await
+            .unwrap();
+        assert_eq!(checked.status, MeltQuoteState::Unpaid);
+    }
+
+    #[tokio::test]
+    async fn test_pay_timeout_without_record_stays_pending() {
+        let mock = MockLnbits::new(Duration::from_secs(5), None);
+        let lnbits = mock.serve().await;
+
+        let response = make_payment(&lnbits).await.unwrap();
+
+        assert_eq!(response.status, MeltQuoteState::Pending);
+        assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 1);
+
+        // Without a record LNbits never sent the payment
+        let checked = lnbits
+            .check_outgoing_payment(&payment_hash())
+            .await
+            .unwrap();
+        assert_eq!(checked.status, MeltQuoteState::Unpaid);
+    }
+
+    #[tokio::test]
+    async fn test_refused_pay_request_fails() {
+        let mut mock = MockLnbits::new(Duration::ZERO, None);
+        mock.pay_status = StatusCode::BAD_REQUEST;
+        let lnbits = mock.serve().await;
+
+        assert!(make_payment(&lnbits).await.is_err());
+        assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_pay_request_error_without_record_stays_pending() {
+        // A server error does not tell whether LNbits started the payment
+        let mut mock = MockLnbits::new(Duration::ZERO, None);
+        mock.pay_status = StatusCode::BAD_GATEWAY;
+        let lnbits = mock.serve().await;
+
+        let response = make_payment(&lnbits).await.unwrap();
+        assert_eq!(response.status, MeltQuoteState::Pending);
+
+        // Nor does a pay request whose response was lost
+        let mut mock = MockLnbits::new(Duration::ZERO, None);
+        mock.drop_pay_response = true;
+        let lnbits = mock.serve().await;
+
+        let response = make_payment(&lnbits).await.unwrap();
+        assert_eq!(response.status, MeltQuoteState::Pending);
+        assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_known_payment_not_paid_again() {
+        for status in ["pending", "success"] {
+            let mut mock = MockLnbits::new(Duration::ZERO, Some(status));
+            mock.recorded = true;
+            let lnbits = mock.serve().await;
+
+            let response = make_payment(&lnbits).await.unwrap();
+
+            assert_eq!(response.status, lnbits_to_melt_status(status));
+            assert_eq!(mock.pay_requests.load(Ordering::SeqCst), 0);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_requires_payment_hash() {
+        let lnbits = MockLnbits::new(Duration::ZERO, None).serve().await;
+
+        assert!(lnbits
+            .check_outgoing_payment(&PaymentIdentifier::CustomId("quote".to_string()))
+            .await
+            .is_err());
+    }
+}
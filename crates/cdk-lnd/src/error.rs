@@ -27,6 +27,9 @@ pub enum Error {
     /// Missing last hop in route
     #[error("LND missing last hop in route")]
     MissingLastHop,
+    /// No route to the destination
+    #[error("LND no route to destination")]
+    NoRoute,
     /// Amount overflow
     #[error("Amount overflow")]
     AmountOverflow,
@@ -42,6 +45,9 @@ pub enum Error {
     /// Database Error
     #[error("Database error: {0}")]
     Database(String),
+    /// No preimage was stored for the keysend payment
+    #[error("No preimage stored for the keysend payment")]
+    KeysendPreimageMissing,
 }
 
 impl From<Error> for cdk_common::payment::Error {
@@ -5,23 +5,27 @@
 #![doc = include_str!("../README.md")]
 
 use std::cmp::max;
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::pin::Pin;
 use std::str::FromStr;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::Arc;
+use std::time::Duration;
 
 use anyhow::anyhow;
 use async_trait::async_trait;
 use cdk_common::amount::{Amount, MSAT_IN_SAT};
-use cdk_common::bitcoin::hashes::Hash;
+use cdk_common::bitcoin::hashes::{sha256, Hash};
+use cdk_common::bitcoin::secp256k1::rand;
 use cdk_common::common::FeeReserve;
 use cdk_common::database::DynKVStore;
 use cdk_common::nuts::{CurrencyUnit, MeltOptions, MeltQuoteState};
 use cdk_common::payment::{
-    self, CreateIncomingPaymentResponse, Event, IncomingPaymentOptions, MakePaymentResponse,
-    MintPayment, OutgoingPaymentOptions, PaymentIdentifier, PaymentQuoteResponse, SettingsResponse,
-    WaitPaymentResponse,
+    self, CreateIncomingPaymentResponse, CustomOutgoingPaymentOptions, Event,
+    IncomingPaymentOptions, KeysendSettings, MakePaymentResponse, MintPayment,
+    OutgoingPaymentOptions, PaymentIdentifier, PaymentQuoteResponse, SettingsResponse,
+    WaitPaymentResponse, KEYSEND_METHOD,
 };
 use cdk_common::util::hex;
 use cdk_common::Bolt11Invoice;
@@ -46,6 +50,7 @@ const LND_KV_PRIMARY_NAMESPACE: &str = "cdk_lnd_lightning_backend";
 const LND_KV_SECONDARY_NAMESPACE: &str = "payment_indices";
 const LAST_ADD_INDEX_KV_KEY: &str = "last_add_index";
 const LAST_SETTLE_INDEX_KV_KEY: &str = "last_settle_index";
+const LND_KV_KEYSEND_NAMESPACE: &str = "keysend_preimages";
 
 /// Lnd mint backend
 #[derive(Clone)]
@@ -60,6 +65,7 @@ pub struct Lnd {
     wait_invoice_is_active: Arc<AtomicBool>,
     settings: SettingsResponse,
     unit: CurrencyUnit,
+    payment_timeout: Option<Duration>,
 }
 
 impl std::fmt::Debug for Lnd {
@@ -74,6 +80,12 @@ impl Lnd {
     /// Maximum number of attempts at a partial payment
     pub const MAX_ROUTE_RETRIES: usize = 50;
 
+    /// Seconds to spend on a keysend payment when no payment timeout is set
+    pub const KEYSEND_TIMEOUT_SECS: i32 = 60;
+
+    /// TLV record type carrying the preimage of a keysend payment
+    const KEYSEND_PREIMAGE_RECORD: u64 = 5482373484;
+
     /// Create new [`Lnd`]
     pub async fn new(
         address: String,
@@ -131,12 +143,187 @@ impl Lnd {
                     invoice_description: true,
                 }),
                 bolt12: None,
-                custom: std::collections::HashMap::new(),
+                custom: HashMap::from([(
+                    KEYSEND_METHOD.to_string(),
+                    serde_json::to_string(&KeysendSettings { fee_probe: true })
+                        .map_err(|err| Error::InvalidConfig(err.to_string()))?,
+                )]),
             },
             unit,
+            payment_timeout: None,
         })
     }
 
+    /// Set the maximum time to spend on an outgoing payment
+    ///
+    /// Payments are then sent through the router with `timeout_seconds` and the
+    /// mint stops waiting once it elapses.
+    pub fn with_payment_timeout(mut self, payment_timeout: Duration) -> Self {
+        self.payment_timeout = Some(payment_timeout);
+        self
+    }
+
+    /// Wait for the final update on a payment stream
+    async fn final_payment_update(
+        &self,
+        payment_identifier: &PaymentIdentifier,
+        mut payment_stream: tonic::Streaming<lnrpc::Payment>,
+    ) -> Result<MakePaymentResponse, payment::Error> {
+        while let Some(update_result) = payment_stream.next().await {
+            match update_result {
+                Ok(update) => {
+                    let status = update.status();
+
+                    let response = match status {
+                        PaymentStatus::Unknown => MakePaymentResponse {
+                            payment_lookup_id: payment_identifier.clone(),
+                            payment_proof: Some(update.payment_preimage),
+                            status: MeltQuoteState::Unknown,
+                            total_spent: Amount::new(0, self.unit.clone()),
+                        },
+                        PaymentStatus::InFlight | PaymentStatus::Initiated => {
+                            // Continue waiting for the next update
+                            continue;
+                        }
+                        PaymentStatus::Succeeded => MakePaymentResponse {
+                            payment_lookup_id: payment_identifier.clone(),
+                            payment_proof: Some(update.payment_preimage),
+                            status: MeltQuoteState::Paid,
+                            total_spent: Amount::new(
+                                (update
+                                    .value_sat
+                                    .checked_add(update.fee_sat)
+                                    .ok_or(Error::AmountOverflow)?)
+                                    as u64,
+                                CurrencyUnit::Sat,
+                            ),
+                        },
+                        PaymentStatus::Failed => MakePaymentResponse {
+                            payment_lookup_id: payment_identifier.clone(),
+                            payment_proof: Some(update.payment_preimage),
+                            status: MeltQuoteState::Failed,
+                            total_spent: Amount::new(0, self.unit.clone()),
+                        },
+                    };
+
+                    return Ok(response);
+                }
+                Err(_) => {
+                    // Handle the case where the update itself is an error (e.g., stream failure)
+                    return Err(Error::UnknownPaymentStatus.into());
+                }
+            }
+        }
+
+        // If the stream is exhausted without a final status
+        Err(Error::UnknownPaymentStatus.into())
+    }
+
+    /// Choose the preimage of a keysend payment and store it until the payment is sent
+    ///
+    /// Returns the payment hash, which is the lookup id of the quote, so the payment can be
+    /// checked even if the mint stops while sending it.
+    async fn create_keysend_preimage(&self) -> Result<[u8; 32], Error> {
+        let preimage: [u8; 32] = rand::random();
+        let payment_hash = sha256::Hash::hash(&preimage).to_byte_array();
+
+        let mut tx = self
+            .kv_store
+            .begin_transaction()
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+        tx.kv_write(
+            LND_KV_PRIMARY_NAMESPACE,
+            LND_KV_KEYSEND_NAMESPACE,
+            &hex::encode(payment_hash),
+            &preimage,
+        )
+        .await
+        .map_err(|e| Error::Database(e.to_string()))?;
+        tx.commit()
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?;
+
+        Ok(payment_hash)
+    }
+
+    /// Preimage stored for the keysend payment of `payment_hash`
+    async fn keysend_preimage(&self, payment_hash: &[u8; 32]) -> Result<[u8; 32], Error> {
+        self.kv_store
+            .kv_read(
+                LND_KV_PRIMARY_NAMESPACE,
+                LND_KV_KEYSEND_NAMESPACE,
+                &hex::encode(payment_hash),
+            )
+            .await
+            .map_err(|e| Error::Database(e.to_string()))?
+            .and_then(|preimage| <[u8; 32]>::try_from(preimage.as_slice()).ok())
+            .ok_or(Error::KeysendPreimageMissing)
+    }
+
+    /// Pay a keysend payment
+    ///
+    /// The preimage chosen for the quote is sent to the destination in a custom record, under
+    /// the payment hash the quote is looked up by.
+    #[instrument(skip_all)]
+    async fn pay_keysend(
+        &self,
+        unit: &CurrencyUnit,
+        options: &CustomOutgoingPaymentOptions,
+    ) -> Result<MakePaymentResponse, payment::Error> {
+        let (destination, amount) = options.keysend()?;
+        let amount_msat =
+            Amount::new(amount.into(), unit.clone()).convert_to(&CurrencyUnit::Msat)?;
+        let max_fee_msat = options
+            .max_fee_amount
+            .map(|fee| Amount::new(fee.into(), unit.clone()).convert_to(&CurrencyUnit::Msat))
+            .transpose()?;
+
+        let Some(PaymentIdentifier::PaymentHash(payment_hash)) =
+            options.keysend_request_lookup_id()
+        else {
+            tracing::error!("Keysend payment has no payment hash to pay under");
+            return Err(Error::KeysendPreimageMissing.into());
+        };
+        let preimage = self.keysend_preimage(&payment_hash).await?;
+
+        let pay_req = routerrpc::SendPaymentRequest {
+            dest: destination.serialize().to_vec(),
+            amt_msat: amount_msat.value() as i64,
+            payment_hash: payment_hash.to_vec(),
+            dest_custom_records: HashMap::from([(
+                Self::KEYSEND_PREIMAGE_RECORD,
+                preimage.to_vec(),
+            )]),
+            fee_limit_msat: max_fee_msat.map_or(i64::MAX, |fee| fee.value() as i64),
+            timeout_seconds: self
+                .payment_timeout
+                .map_or(Self::KEYSEND_TIMEOUT_SECS, |timeout| {
+                    i32::try_from(timeout.as_secs()).unwrap_or(i32::MAX).max(1)
+                }),
+            no_inflight_updates: true,
+            ..Default::default()
+        };
+
+        let mut lnd_client = self.lnd_client.clone();
+
+        let payment_stream = lnd_client
+            .router()
+            .send_payment_v2(pay_req)
+            .await
+            .map_err(|err| {
+                tracing::warn!("Keysend payment failed: {}", err);
+                Error::PaymentFailed
+            })?
+            .into_inner();
+
+        self.final_payment_update(
+            &PaymentIdentifier::PaymentHash(payment_hash),
+            payment_stream,
+        )
+        .await
+    }
+
     /// Get last add and settle indices from KV store
     #[instrument(skip_all)]
     async fn get_last_indices(&self) -> Result<(Option<u64>, Option<u64>), Error> {
@@ -196,6 +383,10 @@ impl MintPayment for Lnd {
         Ok(self.settings.clone())
     }
 
+    fn payment_timeout(&self) -> Option<Duration> {
+        self.payment_timeout
+    }
+
     #[instrument(skip_all)]
     fn is_wait_invoice_active(&self) -> bool {
         self.wait_invoice_is_active.load(Ordering::SeqCst)
@@ -361,6 +552,15 @@ impl MintPayment for Lnd {
         match options {
             OutgoingPaymentOptions::Bolt11(bolt11_options) => {
                 let amount_msat = match bolt11_options.melt_options {
+                    Some(MeltOptions::Amountless { amountless }) => {
+                        if let Some(invoice_amount) = bolt11_options.bolt11.amount_milli_satoshis()
+                        {
+                            if invoice_amount != u64::from(amountless.amount_msat) {
+                                return Err(payment::Error::AmountMismatch);
+                            }
+                        }
+                        amountless.amount_msat
+                    }
                     Some(amount) => amount.amount_msat(),
                     None => bolt11_options
                         .bolt11
@@ -391,14 +591,54 @@ impl MintPayment for Lnd {
             OutgoingPaymentOptions::Bolt12(_) => {
                 Err(Self::Err::Anyhow(anyhow!("BOLT12 not supported by LND")))
             }
-            OutgoingPaymentOptions::Custom(_) => Err(payment::Error::UnsupportedPaymentOption),
+            OutgoingPaymentOptions::Custom(custom_options) => {
+                let (destination, amount) = custom_options.keysend()?;
+                let amount = Amount::new(amount.into(), unit.clone());
+                let amount_msat = amount.convert_to(&CurrencyUnit::Msat)?;
+
+                // Probe for a route to estimate the routing fee
+                let route_req = lnrpc::QueryRoutesRequest {
+                    pub_key: hex::encode(destination.serialize()),
+                    amt_msat: amount_msat.value() as i64,
+                    use_mission_control: true,
+                    ..Default::default()
+                };
+
+                let mut lnd_client = self.lnd_client.clone();
+
+                let routes_response = lnd_client
+                    .lightning()
+                    .query_routes(route_req)
+                    .await
+                    .map_err(|err| {
+                        tracing::warn!("Could not find route for keysend: {}", err);
+                        Error::NoRoute
+                    })?
+                    .into_inner();
+
+                let route = routes_response.routes.first().ok_or(Error::NoRoute)?;
+                let routing_fee = Amount::new(route.total_fees_msat as u64, CurrencyUnit::Msat)
+                    .convert_to(unit)?;
+
+                let absolute_fee_reserve: u64 = self.fee_reserve.min_fee_reserve.into();
+                let fee = max(routing_fee.value(), absolute_fee_reserve);
+
+                let payment_hash = self.create_keysend_preimage().await?;
+
+                Ok(PaymentQuoteResponse {
+                    request_lookup_id: Some(PaymentIdentifier::PaymentHash(payment_hash)),
+                    amount,
+                    fee: Amount::new(fee, unit.clone()),
+                    state: MeltQuoteState::Unpaid,
+                })
+            }
         }
     }
 
     #[instrument(skip_all)]
     async fn make_payment(
         &self,
-        _unit: &CurrencyUnit,
+        unit: &CurrencyUnit,
         options: OutgoingPaymentOptions,
     ) -> Result<MakePaymentResponse, Self::Err> {
         match options {
@@ -533,12 +773,49 @@ impl MintPayment for Lnd {
 
                         let max_fee: Option<Amount> = bolt11_options.max_fee_amount;
 
-                        let amount_msat = u64::from(
-                            bolt11_options
-                                .melt_options
-                                .map(|a| a.amount_msat())
-                                .unwrap_or_default(),
-                        );
+                        // LND rejects an explicit amount unless the invoice has none
+                        let amount_msat = match bolt11.amount_milli_satoshis() {
+                            Some(_) => 0,
+                            None => u64::from(
+                                bolt11_options
+                                    .melt_options
+                                    .map(|a| a.amount_msat())
+                                    .ok_or(Error::UnknownInvoiceAmount)?,
+                            ),
+                        };
+
+                        if let Some(timeout) = self.payment_timeout {
+                            // Send through the router so LND gives up once the timeout elapses
+                            let pay_req = routerrpc::SendPaymentRequest {
+                                payment_request: bolt11.to_string(),
+                                amt_msat: amount_msat as i64,
+                                fee_limit_sat: max_fee.map_or(i64::MAX, |f| u64::from(f) as i64),
+                                timeout_seconds: i32::try_from(timeout.as_secs())
+                                    .unwrap_or(i32::MAX)
+                                    .max(1),
+                                no_inflight_updates: true,
+                                ..Default::default()
+                            };
+
+                            let payment_stream = lnd_client
+                                .router()
+                                .send_payment_v2(pay_req)
+                                .await
+                                .map_err(|err| {
+                                    tracing::warn!("Lightning payment failed: {}", err);
+                                    Error::PaymentFailed
+                                })?
+                                .into_inner();
+
+                            return self
+                                .final_payment_update(
+                                    &PaymentIdentifier::PaymentHash(
+                                        *bolt11.payment_hash().as_ref(),
+                                    ),
+                                    payment_stream,
+                                )
+                                .await;
+                        }
 
                         let pay_req = lnrpc::SendRequest {
                             payment_request: bolt11.to_string(),
@@ -588,7 +865,9 @@ impl MintPayment for Lnd {
             OutgoingPaymentOptions::Bolt12(_) => {
                 Err(Self::Err::Anyhow(anyhow!("BOLT12 not supported by LND")))
             }
-            OutgoingPaymentOptions::Custom(_) => Err(payment::Error::UnsupportedPaymentOption),
+            OutgoingPaymentOptions::Custom(custom_options) => {
+                self.pay_keysend(unit, &custom_options).await
+            }
         }
     }
 
@@ -688,7 +967,7 @@ impl MintPayment for Lnd {
 
         let payment_response = lnd_client.router().track_payment_v2(track_request).await;
 
-        let mut payment_stream = match payment_response {
+        let payment_stream = match payment_response {
             Ok(stream) => stream.into_inner(),
             Err(err) => {
                 let err_code = err.code();
@@ -705,53 +984,7 @@ impl MintPayment for Lnd {
             }
         };
 
-        while let Some(update_result) = payment_stream.next().await {
-            match update_result {
-                Ok(update) => {
-                    let status = update.status();
-
-                    let response = match status {
-                        PaymentStatus::Unknown => MakePaymentResponse {
-                            payment_lookup_id: payment_identifier.clone(),
-                            payment_proof: Some(update.payment_preimage),
-                            status: MeltQuoteState::Unknown,
-                            total_spent: Amount::new(0, self.unit.clone()),
-                        },
-                        PaymentStatus::InFlight | PaymentStatus::Initiated => {
-                            // Continue waiting for the next update
-                            continue;
-                        }
-                        PaymentStatus::Succeeded => MakePaymentResponse {
-                            payment_lookup_id: payment_identifier.clone(),
-                            payment_proof: Some(update.payment_preimage),
-                            status: MeltQuoteState::Paid,
-                            total_spent: Amount::new(
-                                (update
-                                    .value_sat
-                                    .checked_add(update.fee_sat)
-                                    .ok_or(Error::AmountOverflow)?)
-                                    as u64,
-                                CurrencyUnit::Sat,
-                            ),
-                        },
-                        PaymentStatus::Failed => MakePaymentResponse {
-                            payment_lookup_id: payment_identifier.clone(),
-                            payment_proof: Some(update.payment_preimage),
-                            status: MeltQuoteState::Failed,
-                            total_spent: Amount::new(0, self.unit.clone()),
-                        },
-                    };
-
-                    return Ok(response);
-                }
-                Err(_) => {
-                    // Handle the case where the update itself is an error (e.g., stream failure)
-                    return Err(Error::UnknownPaymentStatus.into());
-                }
-            }
-        }
-
-        // If the stream is exhausted without a final status
-        Err(Error::UnknownPaymentStatus.into())
+        self.final_payment_update(payment_identifier, payment_stream)
+            .await
     }
 }
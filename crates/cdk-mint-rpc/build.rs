@@ -10,6 +10,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     tonic_build::configure()
         .protoc_arg("--experimental_allow_proto3_optional")
         .type_attribute(".", "#[allow(missing_docs)]")
+        .type_attribute(".", "#[derive(serde::Serialize)]")
         .field_attribute(".", "#[allow(missing_docs)]")
         .compile_protos(&["src/proto/cdk-mint-rpc.proto"], &["src/proto"])?;
 
@@ -1,12 +1,12 @@
 use std::path::PathBuf;
+use std::process::ExitCode;
 
 use anyhow::{anyhow, Result};
-use cdk_mint_rpc::cdk_mint_client::CdkMintClient;
-use cdk_mint_rpc::mint_rpc_cli::subcommands;
-use cdk_mint_rpc::GetInfoRequest;
+use cdk_mint_rpc::mint_rpc_cli::config::{CliConfig, CONFIG_FILE_NAME, DEFAULT_ADDR};
+use cdk_mint_rpc::mint_rpc_cli::exit_code::{exit_code, rpc_status};
+use cdk_mint_rpc::mint_rpc_cli::output::OutputFormat;
+use cdk_mint_rpc::mint_rpc_cli::{client, subcommands};
 use clap::{Parser, Subcommand};
-use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
-use tonic::Request;
 use tracing_subscriber::EnvFilter;
 
 /// Common CLI arguments for CDK binaries
@@ -53,14 +53,30 @@ struct Cli {
     #[command(flatten)]
     common: CommonArgs,
 
-    /// Address of RPC server
-    #[arg(short, long, default_value = "https://127.0.0.1:8086")]
-    addr: String,
+    /// Address of RPC server [default: https://127.0.0.1:8086]
+    #[arg(short, long, env = "CDK_MINT_CLI_ADDR")]
+    addr: Option<String>,
 
     /// Path to working dir
-    #[arg(short, long)]
+    #[arg(short, long, env = "CDK_MINT_CLI_WORK_DIR")]
     work_dir: Option<PathBuf>,
 
+    /// Path to the config file [default: <work-dir>/config.toml]
+    #[arg(short, long, env = "CDK_MINT_CLI_CONFIG")]
+    config: Option<PathBuf>,
+
+    /// Directory with the TLS material (ca.pem, client.pem, client.key) [default: <work-dir>/tls if present]
+    #[arg(long, env = "CDK_MINT_CLI_TLS_DIR")]
+    tls_dir: Option<PathBuf>,
+
+    /// Token sent to authenticate with the RPC server
+    #[arg(long, env = "CDK_MINT_CLI_AUTH_TOKEN", hide_env_values = true)]
+    auth_token: Option<String>,
+
+    /// Output format
+    #[arg(short, long, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
     #[command(subcommand)]
     command: Commands,
 }
@@ -79,6 +95,8 @@ enum Commands {
     UpdateName(subcommands::UpdateNameCommand),
     /// Update icon url
     UpdateIconUrl(subcommands::UpdateIconUrlCommand),
+    /// Update terms of service url
+    UpdateTosUrl(subcommands::UpdateTosUrlCommand),
     /// Add Url
     AddUrl(subcommands::AddUrlCommand),
     /// Remove Url
@@ -91,6 +109,8 @@ enum Commands {
     UpdateNut04(subcommands::UpdateNut04Command),
     /// Update nut05
     UpdateNut05(subcommands::UpdateNut05Command),
+    /// Update nut15
+    UpdateNut15(subcommands::UpdateNut15Command),
     /// Update quote ttl
     UpdateQuoteTtl(subcommands::UpdateQuoteTtlCommand),
     /// Get quote ttl
@@ -99,18 +119,68 @@ enum Commands {
     UpdateNut04QuoteState(subcommands::UpdateNut04QuoteCommand),
     /// Rotate next keyset
     RotateNextKeyset(subcommands::RotateNextKeysetCommand),
+    /// Audit keysets against the seed and issued signatures
+    AuditKeysets,
+    /// Show response cache statistics
+    CacheStats,
+    /// Clear cached responses
+    CacheClear(subcommands::CacheClearCommand),
+    /// List clients banned for double spends
+    ListBannedClients,
+    /// Lift client bans
+    ClearBannedClients(subcommands::ClearBannedClientsCommand),
+    /// Show the status of the payment event streams of the payment backends
+    PaymentStreams,
+    /// Show the status of the background tasks of the mint
+    BackgroundTasks,
+    /// List the melt quotes stuck pending
+    ListStuckQuotes,
+    /// Show the announcement pubkey of the mint
+    AnnouncementKey,
+    /// Move to the next announcement key
+    RotateAnnouncementKey(subcommands::RotateAnnouncementKeyCommand),
+    /// Sign an announcement with the announcement key
+    SignAnnouncement(subcommands::SignAnnouncementCommand),
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    let args: Cli = Cli::parse();
+async fn main() -> ExitCode {
+    let cli: Cli = Cli::parse();
 
     // Initialize logging based on CLI arguments
-    init_logging(args.common.enable_logging, args.common.log_level);
+    init_logging(cli.common.enable_logging, cli.common.log_level);
 
-    let cli = Cli::parse();
+    let output = cli.output;
 
-    let work_dir = match &args.work_dir {
+    match run(cli).await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            let code = exit_code(&err);
+            let status = rpc_status(&err);
+            let message = match status {
+                Some(status) => format!("{}: {}", status.code().description(), status.message()),
+                None => format!("{err:#}"),
+            };
+
+            match output {
+                OutputFormat::Text => eprintln!("Error: {message}"),
+                OutputFormat::Json => eprintln!(
+                    "{}",
+                    serde_json::json!({
+                        "error": message,
+                        "rpc_code": status.map(|status| status.code() as i32),
+                        "exit_code": code,
+                    })
+                ),
+            }
+
+            ExitCode::from(code)
+        }
+    }
+}
+
+async fn run(cli: Cli) -> Result<()> {
+    let work_dir = match &cli.work_dir {
         Some(work_dir) => work_dir.clone(),
         None => {
             let home_dir = home::home_dir().ok_or(anyhow!("Could not find home dir"))?;
@@ -122,66 +192,29 @@ async fn main() -> Result<()> {
     std::fs::create_dir_all(&work_dir)?;
     tracing::debug!("Using work dir: {}", work_dir.display());
 
-    let channel = if work_dir.join("tls").is_dir() {
-        if rustls::crypto::CryptoProvider::get_default().is_none() {
-            let _ = rustls::crypto::ring::default_provider().install_default();
-        }
-
-        // TLS directory exists, configure TLS
-        let server_root_ca_cert = std::fs::read_to_string(work_dir.join("tls/ca.pem"))?;
-        let server_root_ca_cert = Certificate::from_pem(server_root_ca_cert);
-        let client_cert = std::fs::read_to_string(work_dir.join("tls/client.pem"))?;
-        let client_key = std::fs::read_to_string(work_dir.join("tls/client.key"))?;
-        let client_identity = Identity::from_pem(client_cert, client_key);
-        let tls = ClientTlsConfig::new()
-            .ca_certificate(server_root_ca_cert)
-            .identity(client_identity);
-
-        Channel::from_shared(cli.addr.to_string())?
-            .tls_config(tls)?
-            .connect()
-            .await?
-    } else {
-        // No TLS directory, skip TLS configuration
-        Channel::from_shared(cli.addr.to_string())?
-            .connect()
-            .await?
-    };
+    let config = CliConfig::from_file(
+        &cli.config
+            .clone()
+            .unwrap_or_else(|| work_dir.join(CONFIG_FILE_NAME)),
+    )?;
 
-    let mut client = CdkMintClient::new(channel);
+    // Flags and environment variables take precedence over the config file
+    let addr = cli
+        .addr
+        .or(config.addr)
+        .unwrap_or_else(|| DEFAULT_ADDR.to_string());
+    let tls_dir = cli
+        .tls_dir
+        .or(config.tls_dir)
+        .or_else(|| Some(work_dir.join("tls")).filter(|tls_dir| tls_dir.is_dir()));
+    let auth_token = cli.auth_token.or(config.auth_token);
+
+    let mut client = client::connect(&addr, tls_dir.as_deref(), auth_token.as_deref()).await?;
+    let output = cli.output;
 
     match cli.command {
         Commands::GetInfo => {
-            let response = client.get_info(Request::new(GetInfoRequest {})).await?;
-            let info = response.into_inner();
-            println!(
-                "name:             {}",
-                info.name.unwrap_or("None".to_string())
-            );
-            println!(
-                "version:          {}",
-                info.version.unwrap_or("None".to_string())
-            );
-            println!(
-                "description:      {}",
-                info.description.unwrap_or("None".to_string())
-            );
-            println!(
-                "long description: {}",
-                info.long_description.unwrap_or("None".to_string())
-            );
-            println!("motd: {}", info.motd.unwrap_or("None".to_string()));
-            println!("icon_url: {}", info.icon_url.unwrap_or("None".to_string()));
-
-            for url in info.urls {
-                println!("mint_url: {url}");
-            }
-
-            for contact in info.contact {
-                println!("method: {}, info: {}", contact.method, contact.info);
-            }
-            println!("total issued:     {} sat", info.total_issued);
-            println!("total redeemed:   {} sat", info.total_redeemed);
+            subcommands::get_info(&mut client, output).await?;
         }
         Commands::UpdateMotd(sub_command_args) => {
             subcommands::update_motd(&mut client, &sub_command_args).await?;
@@ -198,6 +231,9 @@ async fn main() -> Result<()> {
         Commands::UpdateIconUrl(sub_command_args) => {
             subcommands::update_icon_url(&mut client, &sub_command_args).await?;
         }
+        Commands::UpdateTosUrl(sub_command_args) => {
+            subcommands::update_tos_url(&mut client, &sub_command_args).await?;
+        }
         Commands::AddUrl(sub_command_args) => {
             subcommands::add_url(&mut client, &sub_command_args).await?;
         }
@@ -216,17 +252,53 @@ async fn main() -> Result<()> {
         Commands::UpdateNut05(sub_command_args) => {
             subcommands::update_nut05(&mut client, &sub_command_args).await?;
         }
+        Commands::UpdateNut15(sub_command_args) => {
+            subcommands::update_nut15(&mut client, &sub_command_args).await?;
+        }
         Commands::GetQuoteTtl => {
-            subcommands::get_quote_ttl(&mut client).await?;
+            subcommands::get_quote_ttl(&mut client, output).await?;
         }
         Commands::UpdateQuoteTtl(sub_command_args) => {
             subcommands::update_quote_ttl(&mut client, &sub_command_args).await?;
         }
         Commands::UpdateNut04QuoteState(sub_command_args) => {
-            subcommands::update_nut04_quote_state(&mut client, &sub_command_args).await?;
+            subcommands::update_nut04_quote_state(&mut client, &sub_command_args, output).await?;
         }
         Commands::RotateNextKeyset(sub_command_args) => {
-            subcommands::rotate_next_keyset(&mut client, &sub_command_args).await?;
+            subcommands::rotate_next_keyset(&mut client, &sub_command_args, output).await?;
+        }
+        Commands::AuditKeysets => {
+            subcommands::audit_keysets(&mut client, output).await?;
+        }
+        Commands::CacheStats => {
+            subcommands::cache_stats(&mut client, output).await?;
+        }
+        Commands::CacheClear(sub_command_args) => {
+            subcommands::cache_clear(&mut client, &sub_command_args).await?;
+        }
+        Commands::ListBannedClients => {
+            subcommands::list_banned_clients(&mut client, output).await?;
+        }
+        Commands::ClearBannedClients(sub_command_args) => {
+            subcommands::clear_banned_clients(&mut client, &sub_command_args, output).await?;
+        }
+        Commands::PaymentStreams => {
+            subcommands::payment_streams(&mut client, output).await?;
+        }
+        Commands::BackgroundTasks => {
+            subcommands::background_tasks(&mut client, output).await?;
+        }
+        Commands::ListStuckQuotes => {
+            subcommands::list_stuck_quotes(&mut client, output).await?;
+        }
+        Commands::AnnouncementKey => {
+            subcommands::announcement_key(&mut client, output).await?;
+        }
+        Commands::RotateAnnouncementKey(sub_command_args) => {
+            subcommands::rotate_announcement_key(&mut client, &sub_command_args, output).await?;
+        }
+        Commands::SignAnnouncement(sub_command_args) => {
+            subcommands::sign_announcement(&mut client, &sub_command_args, output).await?;
         }
     }
 
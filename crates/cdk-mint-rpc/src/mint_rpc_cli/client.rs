@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use tonic::metadata::{Ascii, MetadataValue};
+use tonic::service::interceptor::InterceptedService;
+use tonic::service::Interceptor;
+use tonic::transport::{Certificate, Channel, ClientTlsConfig, Identity};
+use tonic::{Request, Status};
+
+use crate::cdk_mint_client::CdkMintClient;
+
+/// RPC client that attaches the configured auth token to every request
+pub type MintClient = CdkMintClient<InterceptedService<Channel, BearerToken>>;
+
+/// Sets the `authorization` metadata of outgoing requests
+#[derive(Debug, Clone, Default)]
+pub struct BearerToken {
+    header: Option<MetadataValue<Ascii>>,
+}
+
+impl BearerToken {
+    /// Creates the interceptor, `None` sends requests without a token
+    pub fn new(token: Option<&str>) -> Result<Self> {
+        let header = token
+            .map(|token| format!("Bearer {token}").parse())
+            .transpose()
+            .context("Auth token is not valid ASCII")?;
+
+        Ok(Self { header })
+    }
+}
+
+impl Interceptor for BearerToken {
+    fn call(&mut self, mut request: Request<()>) -> Result<Request<()>, Status> {
+        if let Some(header) = &self.header {
+            request
+                .metadata_mut()
+                .insert("authorization", header.clone());
+        }
+
+        Ok(request)
+    }
+}
+
+/// Connects to the RPC server at `addr`
+///
+/// When `tls_dir` is given it must contain the server CA (`ca.pem`) and the client
+/// certificate and key (`client.pem`, `client.key`).
+pub async fn connect(
+    addr: &str,
+    tls_dir: Option<&Path>,
+    auth_token: Option<&str>,
+) -> Result<MintClient> {
+    let endpoint = Channel::from_shared(addr.to_string())?;
+
+    let endpoint = match tls_dir {
+        Some(tls_dir) => {
+            if rustls::crypto::CryptoProvider::get_default().is_none() {
+                let _ = rustls::crypto::ring::default_provider().install_default();
+            }
+
+            let server_root_ca_cert = std::fs::read_to_string(tls_dir.join("ca.pem"))?;
+            let server_root_ca_cert = Certificate::from_pem(server_root_ca_cert);
+            let client_cert = std::fs::read_to_string(tls_dir.join("client.pem"))?;
+            let client_key = std::fs::read_to_string(tls_dir.join("client.key"))?;
+            let client_identity = Identity::from_pem(client_cert, client_key);
+            let tls = ClientTlsConfig::new()
+                .ca_certificate(server_root_ca_cert)
+                .identity(client_identity);
+
+            endpoint.tls_config(tls)?
+        }
+        None => endpoint,
+    };
+
+    let channel = endpoint.connect().await?;
+
+    Ok(CdkMintClient::with_interceptor(
+        channel,
+        BearerToken::new(auth_token)?,
+    ))
+}
@@ -0,0 +1,39 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use config::{Config, File, FileFormat};
+use serde::Deserialize;
+
+/// Default address of the RPC server
+pub const DEFAULT_ADDR: &str = "https://127.0.0.1:8086";
+
+/// Name of the config file looked up in the work dir
+pub const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// Connection settings read from the config file
+///
+/// Every setting can be overridden by the matching command line flag or environment variable.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CliConfig {
+    /// Address of the RPC server
+    pub addr: Option<String>,
+    /// Directory holding `ca.pem`, `client.pem` and `client.key`
+    pub tls_dir: Option<PathBuf>,
+    /// Bearer token sent with every request
+    pub auth_token: Option<String>,
+}
+
+impl CliConfig {
+    /// Reads the config file at `path`, an absent file yields the defaults
+    pub fn from_file(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let config = Config::builder()
+            .add_source(File::from(path).format(FileFormat::Toml))
+            .build()?;
+
+        Ok(config.try_deserialize()?)
+    }
+}
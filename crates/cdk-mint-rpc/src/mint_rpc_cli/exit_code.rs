@@ -0,0 +1,35 @@
+/// Exit code for failures that are neither RPC nor connection errors
+pub const GENERAL_ERROR: u8 = 1;
+
+/// Exit code when the RPC server cannot be reached
+pub const CONNECTION_ERROR: u8 = 3;
+
+/// Exit codes of RPC errors are this offset plus the gRPC status code
+pub const RPC_ERROR_OFFSET: u8 = 10;
+
+/// The gRPC status returned by the server, if the command failed with one
+pub fn rpc_status(err: &anyhow::Error) -> Option<&tonic::Status> {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<tonic::Status>())
+}
+
+/// Maps the error a command failed with to the exit code of the process
+///
+/// RPC errors exit with [`RPC_ERROR_OFFSET`] plus their gRPC status code, so `16`
+/// (`10 + 6`) means the entity already exists and `26` (`10 + 16`) that the request was not
+/// authenticated.
+pub fn exit_code(err: &anyhow::Error) -> u8 {
+    if let Some(status) = rpc_status(err) {
+        // gRPC status codes range from 0 to 16
+        return RPC_ERROR_OFFSET + status.code() as u8;
+    }
+
+    if err
+        .chain()
+        .any(|cause| cause.downcast_ref::<tonic::transport::Error>().is_some())
+    {
+        return CONNECTION_ERROR;
+    }
+
+    GENERAL_ERROR
+}
@@ -2,5 +2,13 @@
 //!
 //! This module provides the CLI interface for interacting with the mint server via RPC
 
+/// Connecting to the RPC server
+pub mod client;
+/// Config file of the cli
+pub mod config;
+/// Process exit codes of the cli
+pub mod exit_code;
+/// Output formats of the cli
+pub mod output;
 /// Subcommands for cli
 pub mod subcommands;
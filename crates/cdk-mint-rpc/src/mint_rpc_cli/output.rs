@@ -0,0 +1,32 @@
+use anyhow::Result;
+use clap::ValueEnum;
+use serde::Serialize;
+
+/// How command results are printed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Human readable text
+    #[default]
+    Text,
+    /// The RPC response as a single JSON document, for scripting
+    Json,
+}
+
+impl OutputFormat {
+    /// Prints `response` as JSON when JSON output was requested
+    ///
+    /// Returns `true` if the response was printed, in which case the caller skips its text
+    /// output.
+    pub fn print_json<T>(self, response: &T) -> Result<bool>
+    where
+        T: Serialize,
+    {
+        match self {
+            OutputFormat::Text => Ok(false),
+            OutputFormat::Json => {
+                println!("{}", serde_json::to_string_pretty(response)?);
+                Ok(true)
+            }
+        }
+    }
+}
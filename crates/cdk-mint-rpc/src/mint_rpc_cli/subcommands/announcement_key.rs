@@ -0,0 +1,107 @@
+use anyhow::Result;
+use clap::Args;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::{
+    AnnouncementKeyRequest, AnnouncementKeyResponse, RotateAnnouncementKeyRequest,
+    SignAnnouncementRequest,
+};
+
+/// Command to move to the next announcement key
+///
+/// The previous pubkey stays in the mint info, and valid for announcements, during the grace
+/// period.
+#[derive(Args, Debug)]
+pub struct RotateAnnouncementKeyCommand {
+    /// How long the previous pubkey stays valid, in seconds
+    #[arg(long, default_value_t = 604800)]
+    grace_period: u64,
+}
+
+/// Command to sign an announcement, such as a new MOTD, with the announcement key
+#[derive(Args, Debug)]
+pub struct SignAnnouncementCommand {
+    /// The announcement to sign
+    message: String,
+}
+
+fn print_announcement_key(response: AnnouncementKeyResponse, output: OutputFormat) -> Result<()> {
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    println!("Announcement pubkey: {}", response.pubkey);
+    for previous in response.previous_pubkeys {
+        println!(
+            "Previous pubkey: {}, valid until {}",
+            previous.pubkey, previous.expires_at
+        );
+    }
+
+    Ok(())
+}
+
+/// Executes the announcement_key command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn announcement_key(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .announcement_key(Request::new(AnnouncementKeyRequest {}))
+        .await?
+        .into_inner();
+
+    print_announcement_key(response, output)
+}
+
+/// Executes the rotate_announcement_key command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The grace period of the previous pubkey
+/// * `output` - How to print the result
+pub async fn rotate_announcement_key(
+    client: &mut MintClient,
+    sub_command_args: &RotateAnnouncementKeyCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    let response = client
+        .rotate_announcement_key(Request::new(RotateAnnouncementKeyRequest {
+            grace_period_secs: sub_command_args.grace_period,
+        }))
+        .await?
+        .into_inner();
+
+    print_announcement_key(response, output)
+}
+
+/// Executes the sign_announcement command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The announcement to sign
+/// * `output` - How to print the result
+pub async fn sign_announcement(
+    client: &mut MintClient,
+    sub_command_args: &SignAnnouncementCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    let response = client
+        .sign_announcement(Request::new(SignAnnouncementRequest {
+            message: sub_command_args.message.clone(),
+        }))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    println!("Signature: {}", response.signature);
+    println!("Pubkey: {}", response.pubkey);
+
+    Ok(())
+}
@@ -0,0 +1,68 @@
+use anyhow::Result;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::AuditKeysetsRequest;
+
+/// Executes the audit_keysets command against the mint server
+///
+/// This function asks the mint to recompute every keyset from its seed and derivation path
+/// and prints each keyset with any discrepancy found. Nothing is modified by the audit.
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn audit_keysets(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .audit_keysets(Request::new(AuditKeysetsRequest {}))
+        .await?
+        .into_inner();
+
+    let failed = response
+        .keysets
+        .iter()
+        .filter(|keyset| !keyset.issues.is_empty())
+        .count();
+
+    if output.print_json(&response)? {
+        if failed > 0 {
+            anyhow::bail!("{failed} keyset(s) failed the audit");
+        }
+
+        return Ok(());
+    }
+
+    for keyset in response.keysets {
+        let index = keyset
+            .derivation_path_index
+            .map_or("-".to_string(), |index| index.to_string());
+        let account = keyset
+            .derivation_account
+            .map_or("-".to_string(), |account| account.to_string());
+
+        println!(
+            "{} {} (account {}, index {}){}: {}",
+            keyset.id,
+            keyset.unit,
+            account,
+            index,
+            if keyset.active { " active" } else { "" },
+            if keyset.issues.is_empty() {
+                "ok"
+            } else {
+                "FAILED"
+            }
+        );
+
+        for issue in keyset.issues {
+            println!("  - {issue}");
+        }
+    }
+
+    if failed > 0 {
+        anyhow::bail!("{failed} keyset(s) failed the audit");
+    }
+
+    Ok(())
+}
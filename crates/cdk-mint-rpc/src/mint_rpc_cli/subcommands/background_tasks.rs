@@ -0,0 +1,45 @@
+use anyhow::Result;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::BackgroundTasksRequest;
+
+/// Executes the background_tasks command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn background_tasks(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .background_tasks(Request::new(BackgroundTasksRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    if response.tasks.is_empty() {
+        println!("No background tasks");
+    }
+
+    for task in response.tasks {
+        println!("{}:", task.name);
+        match task.interval_secs {
+            Some(interval) => println!("  interval: {interval}s"),
+            None => println!("  interval: runs until shutdown"),
+        }
+        println!("  running: {}", if task.running { "yes" } else { "no" });
+        println!("  runs: {}", task.runs);
+        match task.last_run {
+            Some(last_run) => println!("  last run: {last_run}"),
+            None => println!("  last run: never"),
+        }
+        if let Some(last_error) = task.last_error {
+            println!("  last error: {last_error}");
+        }
+    }
+
+    Ok(())
+}
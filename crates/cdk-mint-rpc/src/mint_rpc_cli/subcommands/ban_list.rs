@@ -0,0 +1,72 @@
+use anyhow::Result;
+use clap::Args;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::{ClearBannedClientsRequest, ListBannedClientsRequest};
+
+/// Command to lift client bans
+///
+/// Without an IP address the bans of all clients are lifted.
+#[derive(Args, Debug)]
+pub struct ClearBannedClientsCommand {
+    /// Only lift the ban of this IP address
+    ip: Option<String>,
+}
+
+/// Executes the list_banned_clients command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn list_banned_clients(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .list_banned_clients(Request::new(ListBannedClientsRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    if response.clients.is_empty() {
+        println!("No banned clients");
+    }
+
+    for banned in response.clients {
+        println!(
+            "{}: {} double spends, expires in {}s",
+            banned.ip, banned.double_spends, banned.expires_in_secs
+        );
+    }
+
+    Ok(())
+}
+
+/// Executes the clear_banned_clients command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The client whose ban to lift
+/// * `output` - How to print the result
+pub async fn clear_banned_clients(
+    client: &mut MintClient,
+    sub_command_args: &ClearBannedClientsCommand,
+    output: OutputFormat,
+) -> Result<()> {
+    let response = client
+        .clear_banned_clients(Request::new(ClearBannedClientsRequest {
+            ip: sub_command_args.ip.clone(),
+        }))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    println!("Lifted {} ban(s)", response.cleared);
+
+    Ok(())
+}
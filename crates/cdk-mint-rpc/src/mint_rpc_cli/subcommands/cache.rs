@@ -0,0 +1,78 @@
+use anyhow::Result;
+use clap::Args;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::{CacheClearRequest, CacheStatsRequest};
+
+/// Command to clear cached responses
+///
+/// Without arguments the whole cache is cleared.
+#[derive(Args, Debug)]
+pub struct CacheClearCommand {
+    /// Only clear responses of this endpoint (e.g. "/v1/swap")
+    #[arg(short, long, conflicts_with = "request_hash")]
+    endpoint: Option<String>,
+    /// Only clear the response to the request with this hash, as logged when it was cached
+    #[arg(short, long)]
+    request_hash: Option<String>,
+}
+
+/// Executes the cache_stats command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn cache_stats(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .cache_stats(Request::new(CacheStatsRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    let unknown = || "unknown".to_string();
+
+    println!(
+        "entries: {}",
+        response.entries.map_or_else(unknown, |n| n.to_string())
+    );
+    println!(
+        "memory: {}",
+        response
+            .size_bytes
+            .map_or_else(unknown, |bytes| format!("{bytes} bytes"))
+    );
+    println!("hits: {}", response.hits);
+    println!("misses: {}", response.misses);
+    println!(
+        "hit rate: {}",
+        response
+            .hit_rate
+            .map_or_else(|| "-".to_string(), |rate| format!("{:.1}%", rate * 100.0))
+    );
+
+    Ok(())
+}
+
+/// Executes the cache_clear command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The scope of the responses to clear
+pub async fn cache_clear(
+    client: &mut MintClient,
+    sub_command_args: &CacheClearCommand,
+) -> Result<()> {
+    let _response = client
+        .cache_clear(Request::new(CacheClearRequest {
+            endpoint: sub_command_args.endpoint.clone(),
+            request_hash: sub_command_args.request_hash.clone(),
+        }))
+        .await?;
+
+    Ok(())
+}
@@ -0,0 +1,56 @@
+use anyhow::Result;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::GetInfoRequest;
+
+/// Executes the get_info command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn get_info(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let info = client
+        .get_info(Request::new(GetInfoRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&info)? {
+        return Ok(());
+    }
+
+    println!(
+        "name:             {}",
+        info.name.unwrap_or("None".to_string())
+    );
+    println!(
+        "version:          {}",
+        info.version.unwrap_or("None".to_string())
+    );
+    println!(
+        "description:      {}",
+        info.description.unwrap_or("None".to_string())
+    );
+    println!(
+        "long description: {}",
+        info.long_description.unwrap_or("None".to_string())
+    );
+    println!("motd: {}", info.motd.unwrap_or("None".to_string()));
+    println!("icon_url: {}", info.icon_url.unwrap_or("None".to_string()));
+    println!("tos_url: {}", info.tos_url.unwrap_or("None".to_string()));
+
+    for url in info.urls {
+        println!("mint_url: {url}");
+    }
+
+    for contact in info.contact {
+        println!("method: {}, info: {}", contact.method, contact.info);
+    }
+    println!("total issued:     {} sat", info.total_issued);
+    println!("total redeemed:   {} sat", info.total_redeemed);
+    println!("internal settled: {} sat", info.total_internal_settled);
+    println!("overpaid:         {} sat", info.total_overpaid);
+
+    Ok(())
+}
@@ -1,5 +1,21 @@
+/// Module for revealing and rotating the announcement key and signing announcements
+mod announcement_key;
+/// Module for auditing keysets
+mod audit_keysets;
+/// Module for showing the status of the background tasks
+mod background_tasks;
+/// Module for listing and lifting client bans
+mod ban_list;
+/// Module for inspecting and clearing the response cache
+mod cache;
+/// Module for showing the mint info
+mod get_info;
+/// Module for showing the status of the payment streams
+mod payment_streams;
 /// Module for rotating to the next keyset
 mod rotate_next_keyset;
+/// Module for listing melt quotes stuck pending
+mod stuck_quotes;
 /// Module for updating mint contact information
 mod update_contact;
 /// Module for updating the mint's icon URL
@@ -16,14 +32,29 @@ mod update_nut04;
 mod update_nut04_quote;
 /// Module for updating NUT-05 settings (melt process)
 mod update_nut05;
+/// Module for updating NUT-15 settings (multi-part payments)
+mod update_nut15;
 /// Module for updating the mint's short description
 mod update_short_description;
+/// Module for updating the mint's terms of service URL
+mod update_tos_url;
 /// Module for updating quote time-to-live settings
 mod update_ttl;
 /// Module for managing mint URLs
 mod update_urls;
 
+pub use announcement_key::{
+    announcement_key, rotate_announcement_key, sign_announcement, RotateAnnouncementKeyCommand,
+    SignAnnouncementCommand,
+};
+pub use audit_keysets::audit_keysets;
+pub use background_tasks::background_tasks;
+pub use ban_list::{clear_banned_clients, list_banned_clients, ClearBannedClientsCommand};
+pub use cache::{cache_clear, cache_stats, CacheClearCommand};
+pub use get_info::get_info;
+pub use payment_streams::payment_streams;
 pub use rotate_next_keyset::{rotate_next_keyset, RotateNextKeysetCommand};
+pub use stuck_quotes::list_stuck_quotes;
 pub use update_contact::{add_contact, remove_contact, AddContactCommand, RemoveContactCommand};
 pub use update_icon_url::{update_icon_url, UpdateIconUrlCommand};
 pub use update_long_description::{update_long_description, UpdateLongDescriptionCommand};
@@ -32,6 +63,8 @@ pub use update_name::{update_name, UpdateNameCommand};
 pub use update_nut04::{update_nut04, UpdateNut04Command};
 pub use update_nut04_quote::{update_nut04_quote_state, UpdateNut04QuoteCommand};
 pub use update_nut05::{update_nut05, UpdateNut05Command};
+pub use update_nut15::{update_nut15, UpdateNut15Command};
 pub use update_short_description::{update_short_description, UpdateShortDescriptionCommand};
+pub use update_tos_url::{update_tos_url, UpdateTosUrlCommand};
 pub use update_ttl::{get_quote_ttl, update_quote_ttl, UpdateQuoteTtlCommand};
 pub use update_urls::{add_url, remove_url, AddUrlCommand, RemoveUrlCommand};
@@ -0,0 +1,45 @@
+use anyhow::Result;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::PaymentStreamsRequest;
+
+/// Executes the payment_streams command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn payment_streams(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .payment_streams(Request::new(PaymentStreamsRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    if response.streams.is_empty() {
+        println!("No payment streams");
+    }
+
+    for stream in response.streams {
+        println!("{}:", stream.backends.join(", "));
+        println!(
+            "  connected: {}",
+            if stream.connected { "yes" } else { "no" }
+        );
+        println!("  events: {}", stream.events);
+        match stream.last_event {
+            Some(last_event) => println!("  last event: {last_event}"),
+            None => println!("  last event: never"),
+        }
+        println!("  restarts: {}", stream.restarts);
+        if let Some(last_error) = stream.last_error {
+            println!("  last error: {last_error}");
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,9 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
 use crate::RotateNextKeysetRequest;
 
 /// Command to rotate to the next keyset for the mint
@@ -32,9 +32,11 @@ pub struct RotateNextKeysetCommand {
 /// # Arguments
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The arguments specifying how the new keyset should be configured
+/// * `output` - How to print the result
 pub async fn rotate_next_keyset(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &RotateNextKeysetCommand,
+    output: OutputFormat,
 ) -> Result<()> {
     let amounts = if let Some(amounts_str) = &sub_command_args.amounts {
         amounts_str
@@ -55,6 +57,10 @@ pub async fn rotate_next_keyset(
 
     let response = response.into_inner();
 
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
     println!(
         "Rotated to new keyset {} for unit {} with amounts {} and fee of {}",
         response.id,
@@ -0,0 +1,37 @@
+use anyhow::Result;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
+use crate::ListStuckQuotesRequest;
+
+/// Executes the list_stuck_quotes command against the mint server
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `output` - How to print the result
+pub async fn list_stuck_quotes(client: &mut MintClient, output: OutputFormat) -> Result<()> {
+    let response = client
+        .list_stuck_quotes(Request::new(ListStuckQuotesRequest {}))
+        .await?
+        .into_inner();
+
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
+    if response.quotes.is_empty() {
+        println!("No stuck melt quotes");
+    }
+
+    for quote in response.quotes {
+        println!("{}:", quote.quote_id);
+        println!("  amount: {} {}", quote.amount, quote.unit);
+        println!("  pending since: {}", quote.pending_since);
+        if let Some(request_lookup_id) = quote.request_lookup_id {
+            println!("  payment: {request_lookup_id}");
+        }
+    }
+
+    Ok(())
+}
@@ -1,9 +1,8 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
 use crate::UpdateContactRequest;
 
 /// Command to add a contact method to the mint
@@ -26,7 +25,7 @@ pub struct AddContactCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The contact method and information to add
 pub async fn add_contact(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &AddContactCommand,
 ) -> Result<()> {
     let _response = client
@@ -59,7 +58,7 @@ pub struct RemoveContactCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The contact method and information to remove
 pub async fn remove_contact(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &RemoveContactCommand,
 ) -> Result<()> {
     let _response = client
@@ -1,9 +1,8 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
 use crate::UpdateDescriptionRequest;
 
 /// Command to update the mint's long description
@@ -24,7 +23,7 @@ pub struct UpdateLongDescriptionCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The new long description to set
 pub async fn update_long_description(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &UpdateLongDescriptionCommand,
 ) -> Result<()> {
     let _response = client
@@ -1,9 +1,8 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
 use crate::UpdateNameRequest;
 
 /// Command to update the mint's name
@@ -24,7 +23,7 @@ pub struct UpdateNameCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The new name to set for the mint
 pub async fn update_name(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &UpdateNameCommand,
 ) -> Result<()> {
     let _response = client
@@ -1,9 +1,9 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
 use crate::UpdateNut04QuoteRequest;
 
 /// Command to update the state of a NUT-04 quote
@@ -27,9 +27,11 @@ pub struct UpdateNut04QuoteCommand {
 /// # Arguments
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The quote ID and new state to set
+/// * `output` - How to print the result
 pub async fn update_nut04_quote_state(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &UpdateNut04QuoteCommand,
+    output: OutputFormat,
 ) -> Result<()> {
     let response = client
         .update_nut04_quote(Request::new(UpdateNut04QuoteRequest {
@@ -40,6 +42,10 @@ pub async fn update_nut04_quote_state(
 
     let response = response.into_inner();
 
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
     println!("Quote {} updated to {}", response.quote_id, response.state);
 
     Ok(())
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use clap::Args;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::UpdateNut15Request;
+
+/// Command to update NUT-15 (multi-part payment) settings for the mint
+///
+/// NUT-15 lets a wallet pay a single invoice with melt quotes at several mints, each
+/// covering a part of the amount. This command sets the limits of a single part for a
+/// token unit and payment method the mint supports MPP for, and enables or disables
+/// multi-part payments without restarting the mint.
+#[derive(Args, Debug)]
+pub struct UpdateNut15Command {
+    /// The token unit type (e.g., "sat")
+    #[arg(short, long)]
+    #[arg(default_value = "sat")]
+    unit: String,
+    /// The payment method (e.g., "bolt11" for Lightning payments)
+    #[arg(short, long)]
+    #[arg(default_value = "bolt11")]
+    method: String,
+    /// The minimum amount of a single part
+    #[arg(long)]
+    min_amount: Option<u64>,
+    /// The maximum amount of a single part
+    #[arg(long)]
+    max_amount: Option<u64>,
+    /// Whether multi-part payments are disabled (true) or enabled (false)
+    #[arg(long)]
+    disabled: Option<bool>,
+}
+
+/// Executes the update_nut15 command against the mint server
+///
+/// This function sends an RPC request to update the mint's NUT-15 settings for multi-part payments.
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The NUT-15 configuration parameters to update
+pub async fn update_nut15(
+    client: &mut MintClient,
+    sub_command_args: &UpdateNut15Command,
+) -> Result<()> {
+    let _response = client
+        .update_nut15(Request::new(UpdateNut15Request {
+            method: sub_command_args.method.clone(),
+            unit: sub_command_args.unit.clone(),
+            disabled: sub_command_args.disabled,
+            min_amount: sub_command_args.min_amount,
+            max_amount: sub_command_args.max_amount,
+        }))
+        .await?;
+
+    Ok(())
+}
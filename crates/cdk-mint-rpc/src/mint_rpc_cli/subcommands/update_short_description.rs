@@ -1,9 +1,8 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
 use crate::UpdateDescriptionRequest;
 
 /// Command to update the mint's short description
@@ -25,7 +24,7 @@ pub struct UpdateShortDescriptionCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The new short description to set
 pub async fn update_short_description(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &UpdateShortDescriptionCommand,
 ) -> Result<()> {
     let _response = client
@@ -0,0 +1,36 @@
+use anyhow::Result;
+use clap::Args;
+use tonic::Request;
+
+use crate::mint_rpc_cli::client::MintClient;
+use crate::UpdateTosUrlRequest;
+
+/// Command to update the mint's terms of service URL
+///
+/// This command sets the URL of the mint's terms of service, which wallets can
+/// link to. Omitting the URL removes it from the mint info.
+#[derive(Args, Debug)]
+pub struct UpdateTosUrlCommand {
+    /// The http(s) URL of the terms of service
+    tos_url: Option<String>,
+}
+
+/// Executes the update_tos_url command against the mint server
+///
+/// This function sends an RPC request to update the mint's terms of service URL.
+///
+/// # Arguments
+/// * `client` - The RPC client used to communicate with the mint
+/// * `sub_command_args` - The new terms of service URL to set
+pub async fn update_tos_url(
+    client: &mut MintClient,
+    sub_command_args: &UpdateTosUrlCommand,
+) -> Result<()> {
+    let _response = client
+        .update_tos_url(Request::new(UpdateTosUrlRequest {
+            tos_url: sub_command_args.tos_url.clone().unwrap_or_default(),
+        }))
+        .await?;
+
+    Ok(())
+}
@@ -1,9 +1,9 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
+use crate::mint_rpc_cli::output::OutputFormat;
 use crate::{GetQuoteTtlRequest, UpdateQuoteTtlRequest};
 
 /// Command to update the time-to-live (TTL) settings for quotes
@@ -28,7 +28,7 @@ pub struct UpdateQuoteTtlCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The new TTL values to set for quotes
 pub async fn update_quote_ttl(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &UpdateQuoteTtlCommand,
 ) -> Result<()> {
     let _response = client
@@ -53,12 +53,17 @@ pub struct GetQuoteTtlCommand {}
 ///
 /// # Arguments
 /// * `client` - The RPC client used to communicate with the mint
-pub async fn get_quote_ttl(client: &mut CdkMintClient<Channel>) -> Result<()> {
+/// * `output` - How to print the result
+pub async fn get_quote_ttl(client: &mut MintClient, output: OutputFormat) -> Result<()> {
     let response = client
         .get_quote_ttl(Request::new(GetQuoteTtlRequest {}))
         .await?
         .into_inner();
 
+    if output.print_json(&response)? {
+        return Ok(());
+    }
+
     println!("Quote TTL Settings:");
     println!("  Mint TTL: {} seconds", response.mint_ttl);
     println!("  Melt TTL: {} seconds", response.melt_ttl);
@@ -1,9 +1,8 @@
 use anyhow::Result;
 use clap::Args;
-use tonic::transport::Channel;
 use tonic::Request;
 
-use crate::cdk_mint_client::CdkMintClient;
+use crate::mint_rpc_cli::client::MintClient;
 use crate::UpdateUrlRequest;
 
 /// Command to add a URL to the mint's list of endpoints
@@ -24,10 +23,7 @@ pub struct AddUrlCommand {
 /// # Arguments
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The URL to add to the mint
-pub async fn add_url(
-    client: &mut CdkMintClient<Channel>,
-    sub_command_args: &AddUrlCommand,
-) -> Result<()> {
+pub async fn add_url(client: &mut MintClient, sub_command_args: &AddUrlCommand) -> Result<()> {
     let _response = client
         .add_url(Request::new(UpdateUrlRequest {
             url: sub_command_args.url.clone(),
@@ -55,7 +51,7 @@ pub struct RemoveUrlCommand {
 /// * `client` - The RPC client used to communicate with the mint
 /// * `sub_command_args` - The URL to remove from the mint
 pub async fn remove_url(
-    client: &mut CdkMintClient<Channel>,
+    client: &mut MintClient,
     sub_command_args: &RemoveUrlCommand,
 ) -> Result<()> {
     let _response = client
@@ -1,29 +1,38 @@
-use std::net::SocketAddr;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
 use std::str::FromStr;
 use std::sync::Arc;
 
-use cdk::mint::{Mint, MintQuote};
+use cdk::mint::{Mint, MintQuote, ResponseCacheScope};
 use cdk::nuts::nut04::MintMethodSettings;
 use cdk::nuts::nut05::MeltMethodSettings;
-use cdk::nuts::{CurrencyUnit, MintQuoteState, PaymentMethod};
+use cdk::nuts::{CurrencyUnit, MintQuoteState, MppMethodSettings, PaymentMethod};
 use cdk::types::QuoteTTL;
+use cdk::util::validate_http_url;
 use cdk::Amount;
 use cdk_common::payment::WaitPaymentResponse;
 use thiserror::Error;
 use tokio::sync::Notify;
 use tokio::task::JoinHandle;
 use tokio::time::Duration;
+use tonic::service::Interceptor;
 use tonic::transport::{Certificate, Identity, Server, ServerTlsConfig};
 use tonic::{Request, Response, Status};
 
 use crate::cdk_mint_server::{CdkMint, CdkMintServer};
 use crate::{
-    ContactInfo, GetInfoRequest, GetInfoResponse, GetQuoteTtlRequest, GetQuoteTtlResponse,
-    RotateNextKeysetRequest, RotateNextKeysetResponse, UpdateContactRequest,
-    UpdateDescriptionRequest, UpdateIconUrlRequest, UpdateMotdRequest, UpdateNameRequest,
-    UpdateNut04QuoteRequest, UpdateNut04Request, UpdateNut05Request, UpdateQuoteTtlRequest,
-    UpdateResponse, UpdateUrlRequest,
+    AnnouncementKeyRequest, AnnouncementKeyResponse, AuditKeysetsRequest, AuditKeysetsResponse,
+    BackgroundTask, BackgroundTasksRequest, BackgroundTasksResponse, BannedClient,
+    CacheClearRequest, CacheStatsRequest, CacheStatsResponse, ClearBannedClientsRequest,
+    ClearBannedClientsResponse, ContactInfo, GetInfoRequest, GetInfoResponse, GetQuoteTtlRequest,
+    GetQuoteTtlResponse, KeysetAudit, ListBannedClientsRequest, ListBannedClientsResponse,
+    ListStuckQuotesRequest, ListStuckQuotesResponse, PaymentStream, PaymentStreamsRequest,
+    PaymentStreamsResponse, PreviousPubkey, RotateAnnouncementKeyRequest, RotateNextKeysetRequest,
+    RotateNextKeysetResponse, SignAnnouncementRequest, SignAnnouncementResponse, StuckQuote,
+    UpdateContactRequest, UpdateDescriptionRequest, UpdateIconUrlRequest, UpdateMotdRequest,
+    UpdateNameRequest, UpdateNut04QuoteRequest, UpdateNut04Request, UpdateNut05Request,
+    UpdateNut15Request, UpdateQuoteTtlRequest, UpdateResponse, UpdateTosUrlRequest,
+    UpdateUrlRequest,
 };
 
 /// Error
@@ -48,6 +57,42 @@ pub struct MintRPCServer {
     mint: Arc<Mint>,
     shutdown: Arc<Notify>,
     handle: Option<Arc<JoinHandle<Result<(), Error>>>>,
+    auth_token: Option<Arc<str>>,
+}
+
+/// Rejects requests that do not carry the configured bearer token
+#[derive(Clone)]
+struct TokenAuth {
+    token: Option<Arc<str>>,
+}
+
+impl Interceptor for TokenAuth {
+    fn call(&mut self, request: Request<()>) -> Result<Request<()>, Status> {
+        let Some(token) = &self.token else {
+            return Ok(request);
+        };
+
+        let presented = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+
+        match presented {
+            Some(presented) if tokens_match(presented.as_bytes(), token.as_bytes()) => Ok(request),
+            _ => Err(Status::unauthenticated("Missing or invalid auth token")),
+        }
+    }
+}
+
+/// Compares two tokens without short-circuiting on the first differing byte
+fn tokens_match(presented: &[u8], expected: &[u8]) -> bool {
+    presented.len() == expected.len()
+        && presented
+            .iter()
+            .zip(expected)
+            .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+            == 0
 }
 
 impl MintRPCServer {
@@ -63,9 +108,18 @@ impl MintRPCServer {
             mint,
             shutdown: Arc::new(Notify::new()),
             handle: None,
+            auth_token: None,
         })
     }
 
+    /// Requires clients to send `token` as a bearer token in the `authorization` metadata
+    ///
+    /// Passing `None` serves every request, leaving authentication to TLS client certificates.
+    pub fn with_auth_token(mut self, token: Option<String>) -> Self {
+        self.auth_token = token.map(Arc::from);
+        self
+    }
+
     /// Starts the RPC server
     ///
     /// # Arguments
@@ -83,6 +137,10 @@ impl MintRPCServer {
             let _ = rustls::crypto::ring::default_provider().install_default();
         }
 
+        let auth = TokenAuth {
+            token: self.auth_token.clone(),
+        };
+
         let server = match tls_dir {
             Some(tls_dir) => {
                 tracing::info!("TLS configuration found, starting secure server");
@@ -137,11 +195,11 @@ impl MintRPCServer {
 
                 Server::builder()
                     .tls_config(tls_config)?
-                    .add_service(CdkMintServer::new(self.clone()))
+                    .add_service(CdkMintServer::with_interceptor(self.clone(), auth))
             }
             None => {
                 tracing::warn!("No valid TLS configuration found, starting insecure server");
-                Server::builder().add_service(CdkMintServer::new(self.clone()))
+                Server::builder().add_service(CdkMintServer::with_interceptor(self.clone(), auth))
             }
         };
 
@@ -173,6 +231,30 @@ impl MintRPCServer {
         tracing::info!("Mint rpc server stopped");
         Ok(())
     }
+
+    /// Announcement key response for `pubkey`, with the previous pubkeys of the mint info
+    async fn announcement_key_response(
+        &self,
+        pubkey: cdk::nuts::PublicKey,
+    ) -> Result<Response<AnnouncementKeyResponse>, Status> {
+        let mint_info = self
+            .mint
+            .mint_info()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(AnnouncementKeyResponse {
+            pubkey: pubkey.to_string(),
+            previous_pubkeys: mint_info
+                .previous_pubkeys
+                .into_iter()
+                .map(|previous| PreviousPubkey {
+                    pubkey: previous.pubkey.to_string(),
+                    expires_at: previous.expires_at,
+                })
+                .collect(),
+        }))
+    }
 }
 
 impl Drop for MintRPCServer {
@@ -213,6 +295,25 @@ impl CdkMint for MintRPCServer {
         let total_redeemed: Amount = Amount::try_sum(total_redeemed.values().cloned())
             .map_err(|_| Status::internal("Overflow".to_string()))?;
 
+        let total_internal_settled = self
+            .mint
+            .total_internal_settled()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let total_internal_settled: Amount =
+            Amount::try_sum(total_internal_settled.values().cloned())
+                .map_err(|_| Status::internal("Overflow".to_string()))?;
+
+        let total_overpaid = self
+            .mint
+            .total_overpaid()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        let total_overpaid: Amount = Amount::try_sum(total_overpaid.values().cloned())
+            .map_err(|_| Status::internal("Overflow".to_string()))?;
+
         let contact = info
             .contact
             .unwrap_or_default()
@@ -232,8 +333,11 @@ impl CdkMint for MintRPCServer {
             motd: info.motd,
             icon_url: info.icon_url,
             urls: info.urls.unwrap_or_default(),
+            tos_url: info.tos_url,
             total_issued: total_issued.into(),
             total_redeemed: total_redeemed.into(),
+            total_internal_settled: total_internal_settled.into(),
+            total_overpaid: total_overpaid.into(),
         }))
     }
 
@@ -343,12 +447,39 @@ impl CdkMint for MintRPCServer {
         Ok(Response::new(UpdateResponse {}))
     }
 
+    /// Updates the mint's terms of service URL
+    async fn update_tos_url(
+        &self,
+        request: Request<UpdateTosUrlRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let tos_url = request.into_inner().tos_url;
+
+        if !tos_url.is_empty() {
+            validate_http_url(&tos_url).map_err(Status::invalid_argument)?;
+        }
+
+        let mut info = self
+            .mint
+            .mint_info()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        info.tos_url = (!tos_url.is_empty()).then_some(tos_url);
+
+        self.mint
+            .set_mint_info(info)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        Ok(Response::new(UpdateResponse {}))
+    }
+
     /// Adds a URL to the mint's list of URLs
     async fn add_url(
         &self,
         request: Request<UpdateUrlRequest>,
     ) -> Result<Response<UpdateResponse>, Status> {
         let url = request.into_inner().url;
+        validate_http_url(&url).map_err(Status::invalid_argument)?;
         let mut info = self
             .mint
             .mint_info()
@@ -585,6 +716,64 @@ impl CdkMint for MintRPCServer {
         Ok(Response::new(UpdateResponse {}))
     }
 
+    /// Updates the mint's NUT-15 (multi-part payment) settings
+    ///
+    /// Only unit and method pairs the mint already advertises MPP for, i.e. whose payment
+    /// backend supports it, can be updated.
+    async fn update_nut15(
+        &self,
+        request: Request<UpdateNut15Request>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let mut info = self
+            .mint
+            .mint_info()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let mut nut15_settings = info.nuts.nut15.clone();
+
+        let request_inner = request.into_inner();
+
+        let unit = CurrencyUnit::from_str(&request_inner.unit)
+            .map_err(|_| Status::invalid_argument("Invalid unit".to_string()))?;
+
+        let payment_method = PaymentMethod::from_str(&request_inner.method)
+            .map_err(|_| Status::invalid_argument("Invalid method".to_string()))?;
+
+        let current_nut15_settings = nut15_settings
+            .remove_settings(&unit, &payment_method)
+            .ok_or_else(|| {
+                Status::invalid_argument("MPP is not supported for unit payment method pair")
+            })?;
+
+        let updated_method_settings = MppMethodSettings {
+            method: payment_method,
+            unit,
+            min_amount: request_inner
+                .min_amount
+                .map(Amount::from)
+                .or(current_nut15_settings.min_amount),
+            max_amount: request_inner
+                .max_amount
+                .map(Amount::from)
+                .or(current_nut15_settings.max_amount),
+        };
+
+        nut15_settings.methods.push(updated_method_settings);
+
+        if let Some(disabled) = request_inner.disabled {
+            nut15_settings.disabled = disabled;
+        }
+
+        info.nuts.nut15 = nut15_settings;
+
+        self.mint
+            .set_mint_info(info)
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(UpdateResponse {}))
+    }
+
     /// Updates the mint's quote time-to-live settings
     async fn update_quote_ttl(
         &self,
@@ -764,4 +953,234 @@ impl CdkMint for MintRPCServer {
             input_fee_ppk: keyset_info.input_fee_ppk,
         }))
     }
+
+    /// Audits all keysets against the seed and the issued signatures
+    async fn audit_keysets(
+        &self,
+        _request: Request<AuditKeysetsRequest>,
+    ) -> Result<Response<AuditKeysetsResponse>, Status> {
+        let audits = self
+            .mint
+            .audit_keysets()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(AuditKeysetsResponse {
+            keysets: audits
+                .into_iter()
+                .map(|audit| KeysetAudit {
+                    id: audit.id.to_string(),
+                    unit: audit.unit.to_string(),
+                    active: audit.active,
+                    derivation_path_index: audit.derivation_path_index,
+                    derivation_account: audit.derivation_account,
+                    issues: audit.issues.iter().map(|issue| issue.to_string()).collect(),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn cache_stats(
+        &self,
+        _request: Request<CacheStatsRequest>,
+    ) -> Result<Response<CacheStatsResponse>, Status> {
+        let stats = self
+            .mint
+            .response_cache_stats()
+            .await
+            .ok_or_else(|| Status::failed_precondition("No response cache".to_string()))?;
+
+        Ok(Response::new(CacheStatsResponse {
+            entries: stats.entries,
+            size_bytes: stats.size_bytes,
+            hits: stats.hits,
+            misses: stats.misses,
+            hit_rate: stats.hit_rate(),
+        }))
+    }
+
+    async fn cache_clear(
+        &self,
+        request: Request<CacheClearRequest>,
+    ) -> Result<Response<UpdateResponse>, Status> {
+        let request = request.into_inner();
+
+        let scope = match (request.endpoint, request.request_hash) {
+            (None, None) => ResponseCacheScope::All,
+            (Some(endpoint), None) => ResponseCacheScope::Endpoint(endpoint),
+            (None, Some(hash)) => ResponseCacheScope::Request(hash),
+            (Some(_), Some(_)) => {
+                return Err(Status::invalid_argument(
+                    "Only one of endpoint and request hash can be set".to_string(),
+                ))
+            }
+        };
+
+        if !self.mint.clear_response_cache(scope).await {
+            return Err(Status::failed_precondition("No response cache".to_string()));
+        }
+
+        Ok(Response::new(UpdateResponse {}))
+    }
+
+    async fn list_banned_clients(
+        &self,
+        _request: Request<ListBannedClientsRequest>,
+    ) -> Result<Response<ListBannedClientsResponse>, Status> {
+        let clients = self
+            .mint
+            .banned_clients()
+            .into_iter()
+            .map(|client| BannedClient {
+                ip: client.ip.to_string(),
+                double_spends: client.double_spends as u64,
+                expires_in_secs: client.expires_in.as_secs(),
+            })
+            .collect();
+
+        Ok(Response::new(ListBannedClientsResponse { clients }))
+    }
+
+    async fn clear_banned_clients(
+        &self,
+        request: Request<ClearBannedClientsRequest>,
+    ) -> Result<Response<ClearBannedClientsResponse>, Status> {
+        let ip = request
+            .into_inner()
+            .ip
+            .map(|ip| IpAddr::from_str(ip.trim()))
+            .transpose()
+            .map_err(|_| Status::invalid_argument("Invalid IP address".to_string()))?;
+
+        let cleared = self.mint.clear_client_bans(ip);
+
+        Ok(Response::new(ClearBannedClientsResponse {
+            cleared: cleared as u64,
+        }))
+    }
+
+    async fn payment_streams(
+        &self,
+        _request: Request<PaymentStreamsRequest>,
+    ) -> Result<Response<PaymentStreamsResponse>, Status> {
+        let streams = self
+            .mint
+            .payment_stream_status()
+            .into_iter()
+            .map(|status| PaymentStream {
+                backends: status
+                    .keys
+                    .iter()
+                    .map(|key| format!("{} {}", key.unit, key.method))
+                    .collect(),
+                connected: status.connected,
+                events: status.events,
+                last_event: status.last_event,
+                restarts: status.restarts,
+                last_error: status.last_error,
+            })
+            .collect();
+
+        Ok(Response::new(PaymentStreamsResponse { streams }))
+    }
+
+    async fn background_tasks(
+        &self,
+        _request: Request<BackgroundTasksRequest>,
+    ) -> Result<Response<BackgroundTasksResponse>, Status> {
+        let tasks = self
+            .mint
+            .background_task_status()
+            .into_iter()
+            .map(|status| BackgroundTask {
+                name: status.name,
+                interval_secs: status.interval.map(|interval| interval.as_secs()),
+                running: status.running,
+                runs: status.runs,
+                last_run: status.last_run,
+                last_error: status.last_error,
+            })
+            .collect();
+
+        Ok(Response::new(BackgroundTasksResponse { tasks }))
+    }
+
+    /// Lists the melt quotes flagged as stuck pending, longest pending first
+    async fn list_stuck_quotes(
+        &self,
+        _request: Request<ListStuckQuotesRequest>,
+    ) -> Result<Response<ListStuckQuotesResponse>, Status> {
+        let quotes = self
+            .mint
+            .stuck_melt_quotes()
+            .into_iter()
+            .map(|quote| StuckQuote {
+                quote_id: quote.quote_id.to_string(),
+                unit: quote.unit.to_string(),
+                amount: quote.amount.into(),
+                request_lookup_id: quote.request_lookup_id,
+                pending_since: quote.pending_since,
+            })
+            .collect();
+
+        Ok(Response::new(ListStuckQuotesResponse { quotes }))
+    }
+
+    /// Reveals the announcement pubkey and the previous ones still in their grace period
+    async fn announcement_key(
+        &self,
+        _request: Request<AnnouncementKeyRequest>,
+    ) -> Result<Response<AnnouncementKeyResponse>, Status> {
+        let pubkey = self
+            .mint
+            .announcement_pubkey()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        self.announcement_key_response(pubkey).await
+    }
+
+    /// Moves to the next announcement key
+    async fn rotate_announcement_key(
+        &self,
+        request: Request<RotateAnnouncementKeyRequest>,
+    ) -> Result<Response<AnnouncementKeyResponse>, Status> {
+        let request = request.into_inner();
+
+        let pubkey = self
+            .mint
+            .rotate_announcement_key(Duration::from_secs(request.grace_period_secs))
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        self.announcement_key_response(pubkey).await
+    }
+
+    /// Signs an announcement with the announcement key
+    async fn sign_announcement(
+        &self,
+        request: Request<SignAnnouncementRequest>,
+    ) -> Result<Response<SignAnnouncementResponse>, Status> {
+        let request = request.into_inner();
+
+        if request.message.is_empty() {
+            return Err(Status::invalid_argument("message cannot be empty"));
+        }
+
+        let signature = self
+            .mint
+            .sign_announcement(request.message.as_bytes())
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+        let pubkey = self
+            .mint
+            .announcement_pubkey()
+            .await
+            .map_err(|err| Status::internal(err.to_string()))?;
+
+        Ok(Response::new(SignAnnouncementResponse {
+            signature: signature.to_string(),
+            pubkey: pubkey.to_string(),
+        }))
+    }
 }
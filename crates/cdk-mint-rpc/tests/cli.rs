@@ -0,0 +1,305 @@
+//! Runs the `cdk-mint-cli` binary against an in-process RPC server backed by a fake wallet mint
+
+#![allow(clippy::unwrap_used)]
+
+use std::collections::{HashMap, HashSet};
+use std::net::TcpListener;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+use std::sync::Arc;
+use std::time::Duration;
+
+use bip39::Mnemonic;
+use cdk::mint::{MintBuilder, MintMeltLimits};
+use cdk::nuts::nut00::KnownMethod;
+use cdk::nuts::{CurrencyUnit, PaymentMethod};
+use cdk::types::FeeReserve;
+use cdk_fake_wallet::FakeWallet;
+use cdk_mint_rpc::MintRPCServer;
+use serde_json::Value;
+
+struct TestServer {
+    server: MintRPCServer,
+    addr: String,
+    work_dir: PathBuf,
+}
+
+impl TestServer {
+    async fn start(auth_token: Option<&str>) -> Self {
+        let db = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let mut mint_builder = MintBuilder::new(db.clone());
+
+        let fee_reserve = FeeReserve {
+            min_fee_reserve: 1.into(),
+            percent_fee_reserve: 1.0,
+        };
+        let fake_wallet = FakeWallet::new(
+            fee_reserve,
+            HashMap::default(),
+            HashSet::default(),
+            2,
+            CurrencyUnit::Sat,
+        );
+
+        mint_builder
+            .add_payment_processor(
+                CurrencyUnit::Sat,
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                MintMeltLimits::new(1, 10_000),
+                Arc::new(fake_wallet),
+            )
+            .await
+            .unwrap();
+
+        let mnemonic = Mnemonic::generate(12).unwrap();
+        let mint = mint_builder
+            .with_name("cli test mint".to_string())
+            .build_with_seed(db, &mnemonic.to_seed_normalized(""))
+            .await
+            .unwrap();
+
+        // Reserve a free port, the server binds it again once started
+        let port = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap()
+            .port();
+
+        let mut server = MintRPCServer::new("127.0.0.1", port, Arc::new(mint))
+            .unwrap()
+            .with_auth_token(auth_token.map(str::to_string));
+        server.start(None).await.unwrap();
+
+        for _ in 0..50 {
+            if tokio::net::TcpStream::connect(("127.0.0.1", port))
+                .await
+                .is_ok()
+            {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+
+        let work_dir = std::env::temp_dir().join(format!("cdk-mint-cli-test-{port}"));
+        std::fs::create_dir_all(&work_dir).unwrap();
+
+        Self {
+            server,
+            addr: format!("http://127.0.0.1:{port}"),
+            work_dir,
+        }
+    }
+
+    /// Runs the cli with `args`, the address is left to `envs` or the config file when
+    /// `with_addr` is false
+    async fn run_cli(&self, args: &[&str], envs: &[(&str, &str)], with_addr: bool) -> Output {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_cdk-mint-cli"));
+        command
+            .arg("--work-dir")
+            .arg(&self.work_dir)
+            .env_remove("CDK_MINT_CLI_ADDR")
+            .env_remove("CDK_MINT_CLI_AUTH_TOKEN")
+            .env_remove("CDK_MINT_CLI_CONFIG")
+            .env_remove("CDK_MINT_CLI_TLS_DIR")
+            .envs(envs.iter().copied());
+
+        if with_addr {
+            command.arg("--addr").arg(&self.addr);
+        }
+
+        command.args(args);
+
+        // The server runs on this runtime, so the cli must not block it
+        tokio::task::spawn_blocking(move || command.output().unwrap())
+            .await
+            .unwrap()
+    }
+
+    async fn run_json(&self, args: &[&str]) -> Value {
+        let mut full_args = vec!["--output", "json"];
+        full_args.extend_from_slice(args);
+
+        let output = self.run_cli(&full_args, &[], true).await;
+        assert!(
+            output.status.success(),
+            "cli failed: {}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+
+        serde_json::from_slice(&output.stdout).unwrap()
+    }
+
+    async fn stop(self) {
+        self.server.stop().await.unwrap();
+        let _ = std::fs::remove_dir_all(&self.work_dir);
+    }
+}
+
+#[tokio::test]
+async fn test_cli_json_output_and_updates() {
+    let server = TestServer::start(None).await;
+
+    let info = server.run_json(&["get-info"]).await;
+    assert_eq!(info["name"], "cli test mint");
+
+    let output = server
+        .run_cli(&["update-name", "renamed mint"], &[], true)
+        .await;
+    assert!(output.status.success());
+    assert!(output.stdout.is_empty());
+
+    let info = server.run_json(&["get-info"]).await;
+    assert_eq!(info["name"], "renamed mint");
+
+    let ttl = server.run_json(&["get-quote-ttl"]).await;
+    assert!(ttl["mint_ttl"].is_u64());
+
+    let keyset = server
+        .run_json(&[
+            "rotate-next-keyset",
+            "--unit",
+            "sat",
+            "--amounts",
+            "1,2,4,8",
+            "--input-fee-ppk",
+            "100",
+        ])
+        .await;
+    assert_eq!(keyset["unit"], "sat");
+    assert_eq!(keyset["input_fee_ppk"], 100);
+    assert_eq!(keyset["amounts"], serde_json::json!([1, 2, 4, 8]));
+    assert_eq!(keyset["amounts"], serde_json::json!([1, 2, 4, 8]));
+
+    let audit = server.run_json(&["audit-keysets"]).await;
+    let keysets = audit["keysets"].as_array().unwrap();
+    assert!(keysets
+        .iter()
+        .any(|audited| audited["id"] == keyset["id"] && audited["active"] == true));
+
+    // The test mint has no response cache: 10 + gRPC failed precondition (9)
+    let output = server.run_cli(&["cache-clear"], &[], true).await;
+    assert_eq!(output.status.code(), Some(19));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_cli_exit_code_reflects_rpc_error() {
+    let server = TestServer::start(None).await;
+
+    let output = server
+        .run_cli(
+            &[
+                "--output",
+                "json",
+                "update-nut04-quote-state",
+                "not-a-quote-id",
+            ],
+            &[],
+            true,
+        )
+        .await;
+
+    // 10 + gRPC invalid argument (3)
+    assert_eq!(output.status.code(), Some(13));
+    assert!(output.stdout.is_empty());
+
+    let error: Value = serde_json::from_slice(&output.stderr).unwrap();
+    assert_eq!(error["rpc_code"], 3);
+    assert_eq!(error["exit_code"], 13);
+    assert_eq!(
+        error["error"],
+        "Client specified an invalid argument: Invalid quote id"
+    );
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_cli_update_nut15() {
+    let server = TestServer::start(None).await;
+
+    let output = server
+        .run_cli(
+            &["update-nut15", "--min-amount", "100", "--disabled", "true"],
+            &[],
+            true,
+        )
+        .await;
+    assert!(output.status.success());
+
+    // No MPP is advertised for usd: 10 + gRPC invalid argument (3)
+    let output = server
+        .run_cli(
+            &["update-nut15", "--unit", "usd", "--disabled", "false"],
+            &[],
+            true,
+        )
+        .await;
+    assert_eq!(output.status.code(), Some(13));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_cli_connection_error_exit_code() {
+    let server = TestServer::start(None).await;
+
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    let addr = format!("http://127.0.0.1:{port}");
+
+    let output = server
+        .run_cli(&["get-info"], &[("CDK_MINT_CLI_ADDR", &addr)], false)
+        .await;
+    assert_eq!(output.status.code(), Some(3));
+
+    server.stop().await;
+}
+
+#[tokio::test]
+async fn test_cli_auth_token() {
+    let server = TestServer::start(Some("let-me-in")).await;
+
+    // No token: 10 + gRPC unauthenticated (16)
+    let output = server.run_cli(&["get-info"], &[], true).await;
+    assert_eq!(output.status.code(), Some(26));
+
+    let output = server
+        .run_cli(&["--auth-token", "wrong", "get-info"], &[], true)
+        .await;
+    assert_eq!(output.status.code(), Some(26));
+
+    // Token from the environment
+    let output = server
+        .run_cli(
+            &["get-info"],
+            &[("CDK_MINT_CLI_AUTH_TOKEN", "let-me-in")],
+            true,
+        )
+        .await;
+    assert!(output.status.success());
+
+    // Address and token from the config file in the work dir
+    std::fs::write(
+        server.work_dir.join("config.toml"),
+        format!("addr = \"{}\"\nauth_token = \"let-me-in\"\n", server.addr),
+    )
+    .unwrap();
+
+    let output = server
+        .run_cli(&["--output", "json", "get-info"], &[], false)
+        .await;
+    assert!(
+        output.status.success(),
+        "cli failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let info: Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(info["name"], "cli test mint");
+
+    server.stop().await;
+}
@@ -1,6 +1,9 @@
+use std::net::IpAddr;
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::config::{Overrides, Settings};
 
 #[derive(Debug, Parser)]
 #[command(about = "A cashu mint written in rust", author = env!("CARGO_PKG_AUTHORS"), version = env!("CARGO_PKG_VERSION"))]
@@ -32,4 +35,139 @@ pub struct CLIArgs {
         default_value = "true"
     )]
     pub enable_logging: bool,
+    #[arg(
+        long,
+        help = "Start even if the active keysets were not derived from the configured seed",
+        required = false,
+        action = clap::ArgAction::SetTrue
+    )]
+    pub accept_new_seed: bool,
+    #[arg(
+        long,
+        help = "Create a test invoice on startup to check the payment backends work in the unit they are registered for",
+        required = false,
+        action = clap::ArgAction::SetTrue
+    )]
+    pub backend_invoice_selfcheck: bool,
+    #[arg(
+        long,
+        value_name = "ADDRESS",
+        help = "Listen on <address>, overriding the config and environment",
+        required = false
+    )]
+    pub listen: Option<IpAddr>,
+    #[arg(
+        long,
+        help = "Listen on <port>, overriding the config and environment",
+        required = false
+    )]
+    pub port: Option<u16>,
+    #[arg(
+        long,
+        help = "Set up the mint, print a summary of the settings and exit without starting it",
+        required = false,
+        action = clap::ArgAction::SetTrue
+    )]
+    pub dry_run: bool,
+    #[command(subcommand)]
+    pub command: Option<Commands>,
+}
+
+impl CLIArgs {
+    /// Apply the settings given on the command line, which take priority over the config file
+    /// and the environment
+    pub fn apply_to(&self, settings: &mut Settings) {
+        Overrides {
+            listen: self.listen,
+            port: self.port,
+            accept_new_seed: self.accept_new_seed,
+            backend_invoice_selfcheck: self.backend_invoice_selfcheck,
+        }
+        .apply_to(settings);
+    }
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Commands {
+    /// Check the database for inconsistencies and exit, non-zero if any are left
+    VerifyDb {
+        /// Repair the issues that are safe to fix, such as keyset counters that fell behind
+        #[arg(long)]
+        fix: bool,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use super::*;
+    use crate::config::Info;
+    use crate::env_vars::{ENV_LISTEN_HOST, ENV_LISTEN_PORT, ENV_PORT};
+
+    /// The command line wins over `CDK_MINTD_LISTEN_*`, which win over `PORT`, which wins over
+    /// the config file
+    ///
+    /// Runs as a single test since the environment is shared by the test threads.
+    #[test]
+    fn listen_address_precedence() {
+        let config = Info {
+            listen_host: "127.0.0.1".to_string(),
+            listen_port: 8085,
+            ..Default::default()
+        };
+        let address = |info: &Info| info.listen_address().unwrap().to_string();
+
+        assert_eq!(address(&config.clone().from_env()), "127.0.0.1:8085");
+
+        env::set_var(ENV_PORT, "9000");
+        assert_eq!(address(&config.clone().from_env()), "127.0.0.1:9000");
+
+        env::set_var(ENV_LISTEN_PORT, "9100");
+        env::set_var(ENV_LISTEN_HOST, "0.0.0.0");
+        assert_eq!(address(&config.clone().from_env()), "0.0.0.0:9100");
+
+        // An invalid port is ignored
+        env::set_var(ENV_LISTEN_PORT, "http");
+        assert_eq!(address(&config.clone().from_env()), "0.0.0.0:9000");
+
+        let mut settings = Settings {
+            info: config.clone().from_env(),
+            ..Default::default()
+        };
+        CLIArgs::parse_from(["cdk-mintd", "--listen", "::1", "--port", "9200"])
+            .apply_to(&mut settings);
+        assert_eq!(address(&settings.info), "[::1]:9200");
+
+        // Only the port is overridden
+        let mut settings = Settings {
+            info: config.clone().from_env(),
+            ..Default::default()
+        };
+        CLIArgs::parse_from(["cdk-mintd", "--port", "9300"]).apply_to(&mut settings);
+        assert_eq!(address(&settings.info), "0.0.0.0:9300");
+
+        for var in [ENV_PORT, ENV_LISTEN_PORT, ENV_LISTEN_HOST] {
+            env::remove_var(var);
+        }
+    }
+
+    #[test]
+    fn invalid_listen_address() {
+        assert!(CLIArgs::try_parse_from(["cdk-mintd", "--port", "70000"]).is_err());
+        assert!(CLIArgs::try_parse_from(["cdk-mintd", "--listen", "localhost"]).is_err());
+
+        let info = Info {
+            listen_host: "mint.example.com".to_string(),
+            ..Default::default()
+        };
+        assert!(info.listen_address().is_err());
+
+        let info = Info {
+            listen_host: "[::]".to_string(),
+            listen_port: 8085,
+            ..Default::default()
+        };
+        assert_eq!(info.listen_address().unwrap().to_string(), "[::]:8085");
+    }
 }
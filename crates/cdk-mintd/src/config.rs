@@ -1,9 +1,14 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
 use std::path::PathBuf;
+use std::str::FromStr;
 
 use bitcoin::hashes::{sha256, Hash};
-use cdk::nuts::{CurrencyUnit, PublicKey};
+use cdk::mint::MintMeltLimits;
+use cdk::nuts::{CurrencyUnit, PaymentMethod, PublicKey};
+use cdk::util::validate_http_url;
 use cdk::Amount;
-use cdk_axum::cache;
+use cdk_axum::{cache, limits};
 use cdk_common::common::QuoteTTL;
 use config::{Config, ConfigError, File};
 use serde::{Deserialize, Serialize};
@@ -58,8 +63,39 @@ pub struct Info {
     pub signatory_certs: Option<String>,
     pub input_fee_ppk: Option<u64>,
 
+    /// Largest amount signed for a single output, larger outputs are rejected
+    /// and change is split below it (no limit if not set)
+    pub max_amount: Option<u64>,
+
     pub http_cache: cache::Config,
 
+    /// Request body size limit and timeouts
+    #[serde(default)]
+    pub http_limits: limits::Config,
+
+    /// Addresses or networks (CIDR) of reverse proxies whose `Forwarded` and
+    /// `X-Forwarded-For` headers are used to find the client IP
+    #[serde(default)]
+    pub trusted_proxies: Vec<String>,
+
+    /// Ban clients after this many distinct double spends within the ban window
+    /// (bans are disabled if not set)
+    pub ban_threshold: Option<usize>,
+
+    /// Window in seconds in which the double spends of a client are counted
+    /// (defaults to 60)
+    pub ban_window_secs: Option<u64>,
+
+    /// How long in seconds a client stays banned (defaults to 900)
+    pub ban_duration_secs: Option<u64>,
+
+    /// Alert on melt quotes pending for longer than this many seconds
+    /// (alerts are disabled if not set)
+    pub pending_alert_secs: Option<u64>,
+
+    /// URL the stuck melt quote events are posted to as JSON
+    pub pending_alert_webhook: Option<String>,
+
     /// Logging configuration
     #[serde(default)]
     pub logging: LoggingConfig,
@@ -70,11 +106,64 @@ pub struct Info {
     /// This requires `mintd` was built with the `swagger` feature flag.
     pub enable_swagger_ui: Option<bool>,
 
+    /// When set to true, the OpenAPI document is served at
+    /// `[listen_host]:[listen_port]/api-docs/openapi.json`, even if the Swagger UI
+    /// is disabled.
+    ///
+    /// This requires `mintd` was built with the `swagger` feature flag.
+    pub enable_openapi_json: Option<bool>,
+
     /// Optional persisted quote TTL values (seconds) to initialize the database with
     /// when RPC is disabled or on first-run when RPC is enabled.
     /// If not provided, defaults are used.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub quote_ttl: Option<QuoteTTL>,
+
+    /// Maximum number of pending quotes checked with the payment backend
+    /// concurrently on startup (defaults to 8)
+    pub startup_check_concurrency: Option<usize>,
+
+    /// When set to true, pending quotes are reconciled with the payment backend
+    /// in the background after the HTTP listener is bound, so keys and info are
+    /// served immediately
+    pub defer_startup_checks: Option<bool>,
+
+    /// Maximum number of inputs accepted in a single swap or melt request
+    /// (defaults to 1000)
+    pub max_inputs_per_request: Option<usize>,
+
+    /// Maximum number of outputs accepted in a single swap, melt or mint request
+    /// (defaults to 1000)
+    pub max_outputs_per_request: Option<usize>,
+
+    /// Maximum number of Ys accepted in a single NUT-07 check state request
+    /// (defaults to 1000)
+    pub max_check_state_ys_per_request: Option<usize>,
+
+    /// Maximum number of filters accepted in a single websocket subscription,
+    /// the rest are rejected in the response (defaults to 1000)
+    pub max_filters_per_subscription: Option<usize>,
+
+    /// Maximum number of change outputs signed for a melt, the largest
+    /// denominations are signed and the remainder is burnt (defaults to no limit)
+    pub melt_change_max_outputs: Option<usize>,
+
+    /// Smallest denomination signed as melt change, smaller change is burnt
+    /// (defaults to no limit)
+    pub melt_change_min_denomination: Option<u64>,
+
+    /// Start even if the active keysets were not derived from the configured
+    /// seed, replacing them with new keysets (defaults to false)
+    pub accept_new_seed: Option<bool>,
+
+    /// Create a test invoice on startup to check the payment backends work
+    /// in the unit they are registered for (defaults to false)
+    pub backend_invoice_selfcheck: Option<bool>,
+
+    /// Account the keysets are derived under, so one seed can back several
+    /// mints without sharing keys. Can't be changed once the mint has keysets
+    /// (defaults to 0)
+    pub derivation_account: Option<u32>,
 }
 
 impl Default for Info {
@@ -88,14 +177,55 @@ impl Default for Info {
             signatory_url: None,
             signatory_certs: None,
             input_fee_ppk: None,
+            max_amount: None,
             http_cache: cache::Config::default(),
+            http_limits: limits::Config::default(),
+            trusted_proxies: Vec::new(),
+            ban_threshold: None,
+            ban_window_secs: None,
+            ban_duration_secs: None,
+            pending_alert_secs: None,
+            pending_alert_webhook: None,
             enable_swagger_ui: None,
+            enable_openapi_json: None,
             logging: LoggingConfig::default(),
             quote_ttl: None,
+            startup_check_concurrency: None,
+            defer_startup_checks: None,
+            max_inputs_per_request: None,
+            max_outputs_per_request: None,
+            max_check_state_ys_per_request: None,
+            max_filters_per_subscription: None,
+            melt_change_max_outputs: None,
+            melt_change_min_denomination: None,
+            accept_new_seed: None,
+            backend_invoice_selfcheck: None,
+            derivation_account: None,
         }
     }
 }
 
+impl Info {
+    /// Address the mint listens on
+    pub fn listen_address(&self) -> Result<SocketAddr, String> {
+        let host = self.listen_host.trim();
+        // IPv6 addresses may be given with or without brackets
+        let host = host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+            .unwrap_or(host);
+
+        let ip = IpAddr::from_str(host).map_err(|_| {
+            format!(
+                "Invalid listen host '{}': not an IP address",
+                self.listen_host
+            )
+        })?;
+
+        Ok(SocketAddr::new(ip, self.listen_port))
+    }
+}
+
 impl std::fmt::Debug for Info {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         // Use a fallback approach that won't panic
@@ -114,9 +244,34 @@ impl std::fmt::Debug for Info {
             .field("listen_port", &self.listen_port)
             .field("mnemonic", &mnemonic_display)
             .field("input_fee_ppk", &self.input_fee_ppk)
+            .field("max_amount", &self.max_amount)
             .field("http_cache", &self.http_cache)
+            .field("http_limits", &self.http_limits)
+            .field("trusted_proxies", &self.trusted_proxies)
+            .field("ban_threshold", &self.ban_threshold)
+            .field("ban_window_secs", &self.ban_window_secs)
+            .field("ban_duration_secs", &self.ban_duration_secs)
+            .field("pending_alert_secs", &self.pending_alert_secs)
+            .field("pending_alert_webhook", &self.pending_alert_webhook)
             .field("logging", &self.logging)
             .field("enable_swagger_ui", &self.enable_swagger_ui)
+            .field("enable_openapi_json", &self.enable_openapi_json)
+            .field("startup_check_concurrency", &self.startup_check_concurrency)
+            .field("defer_startup_checks", &self.defer_startup_checks)
+            .field("max_inputs_per_request", &self.max_inputs_per_request)
+            .field("max_outputs_per_request", &self.max_outputs_per_request)
+            .field(
+                "max_check_state_ys_per_request",
+                &self.max_check_state_ys_per_request,
+            )
+            .field("melt_change_max_outputs", &self.melt_change_max_outputs)
+            .field(
+                "melt_change_min_denomination",
+                &self.melt_change_min_denomination,
+            )
+            .field("accept_new_seed", &self.accept_new_seed)
+            .field("backend_invoice_selfcheck", &self.backend_invoice_selfcheck)
+            .field("derivation_account", &self.derivation_account)
             .finish()
     }
 }
@@ -162,6 +317,27 @@ impl std::str::FromStr for LnBackend {
     }
 }
 
+impl std::fmt::Display for LnBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LnBackend::None => "none",
+            #[cfg(feature = "cln")]
+            LnBackend::Cln => "cln",
+            #[cfg(feature = "lnbits")]
+            LnBackend::LNbits => "lnbits",
+            #[cfg(feature = "fakewallet")]
+            LnBackend::FakeWallet => "fakewallet",
+            #[cfg(feature = "lnd")]
+            LnBackend::Lnd => "lnd",
+            #[cfg(feature = "ldk-node")]
+            LnBackend::LdkNode => "ldk-node",
+            #[cfg(feature = "grpc-processor")]
+            LnBackend::GrpcProcessor => "grpcprocessor",
+        };
+        write!(f, "{name}")
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ln {
     pub ln_backend: LnBackend,
@@ -170,6 +346,18 @@ pub struct Ln {
     pub max_mint: Amount,
     pub min_melt: Amount,
     pub max_melt: Amount,
+    /// Payment operations (melts and mint quotes) running at once against each
+    /// backend, unlimited if unset
+    #[serde(default)]
+    pub max_concurrent_payments: Option<usize>,
+    /// Seconds a payment operation waits for a free slot before the request is
+    /// rejected with a 503
+    #[serde(default)]
+    pub payment_queue_timeout_secs: Option<u64>,
+    /// Answer a bolt11 melt quote request with the unpaid quote already created
+    /// for the invoice, unit and options (defaults to true)
+    #[serde(default)]
+    pub reuse_melt_quotes: Option<bool>,
 }
 
 impl Default for Ln {
@@ -181,10 +369,104 @@ impl Default for Ln {
             max_mint: 500_000.into(),
             min_melt: 1.into(),
             max_melt: 500_000.into(),
+            max_concurrent_payments: None,
+            payment_queue_timeout_secs: None,
+            reuse_melt_quotes: None,
         }
     }
 }
 
+impl Ln {
+    /// Check the payment settings are usable
+    pub fn validate(&self) -> Result<(), String> {
+        if self.max_concurrent_payments == Some(0) {
+            return Err("max_concurrent_payments must be at least 1".to_string());
+        }
+        Ok(())
+    }
+
+    /// Global mint and melt limits used when a backend does not override them
+    pub fn limits(&self) -> MintMeltLimits {
+        MintMeltLimits {
+            mint_min: self.min_mint,
+            mint_max: self.max_mint,
+            melt_min: self.min_melt,
+            melt_max: self.max_melt,
+        }
+    }
+}
+
+/// Mint and melt limits of a payment backend
+///
+/// Unset fields fall back to the global `[ln]` limits. Overrides can be set for
+/// a payment method (`methods.<method>`), a unit (`units.<unit>`) or a unit and
+/// method pair (`units.<unit>.methods.<method>`), the most specific one wins.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+pub struct PaymentLimits {
+    pub min_mint: Option<Amount>,
+    pub max_mint: Option<Amount>,
+    pub min_melt: Option<Amount>,
+    pub max_melt: Option<Amount>,
+    /// Overrides keyed by payment method name (e.g. `bolt11`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub methods: HashMap<String, PaymentLimits>,
+    /// Overrides keyed by currency unit (e.g. `sat`)
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub units: HashMap<String, PaymentLimits>,
+}
+
+impl PaymentLimits {
+    /// Resolve the limits for `unit` and `method` on top of the global `base` limits
+    pub fn resolve(
+        &self,
+        base: MintMeltLimits,
+        unit: &CurrencyUnit,
+        method: &PaymentMethod,
+    ) -> MintMeltLimits {
+        let unit = unit.to_string();
+        let method = method.to_string();
+
+        let mut limits = base;
+        self.apply(&mut limits);
+
+        if let Some(method_limits) = Self::find(&self.methods, &method) {
+            method_limits.apply(&mut limits);
+        }
+
+        if let Some(unit_limits) = Self::find(&self.units, &unit) {
+            unit_limits.apply(&mut limits);
+
+            if let Some(method_limits) = Self::find(&unit_limits.methods, &method) {
+                method_limits.apply(&mut limits);
+            }
+        }
+
+        limits
+    }
+
+    fn apply(&self, limits: &mut MintMeltLimits) {
+        if let Some(min_mint) = self.min_mint {
+            limits.mint_min = min_mint;
+        }
+        if let Some(max_mint) = self.max_mint {
+            limits.mint_max = max_mint;
+        }
+        if let Some(min_melt) = self.min_melt {
+            limits.melt_min = min_melt;
+        }
+        if let Some(max_melt) = self.max_melt {
+            limits.melt_max = max_melt;
+        }
+    }
+
+    fn find<'a>(overrides: &'a HashMap<String, PaymentLimits>, key: &str) -> Option<&'a Self> {
+        overrides
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, limits)| limits)
+    }
+}
+
 #[cfg(feature = "lnbits")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct LNbits {
@@ -195,6 +477,11 @@ pub struct LNbits {
     pub fee_percent: f32,
     #[serde(default = "default_reserve_fee_min")]
     pub reserve_fee_min: Amount,
+    /// Seconds to wait for an outgoing payment before leaving the melt pending
+    #[serde(default)]
+    pub payment_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
 }
 
 #[cfg(feature = "lnbits")]
@@ -206,6 +493,8 @@ impl Default for LNbits {
             lnbits_api: String::new(),
             fee_percent: 0.02,
             reserve_fee_min: 2.into(),
+            payment_timeout_secs: None,
+            limits: PaymentLimits::default(),
         }
     }
 }
@@ -220,6 +509,11 @@ pub struct Cln {
     pub fee_percent: f32,
     #[serde(default = "default_reserve_fee_min")]
     pub reserve_fee_min: Amount,
+    /// Seconds to wait for an outgoing payment before leaving the melt pending
+    #[serde(default)]
+    pub payment_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
 }
 
 #[cfg(feature = "cln")]
@@ -230,6 +524,8 @@ impl Default for Cln {
             bolt12: true,
             fee_percent: 0.02,
             reserve_fee_min: 2.into(),
+            payment_timeout_secs: None,
+            limits: PaymentLimits::default(),
         }
     }
 }
@@ -249,6 +545,11 @@ pub struct Lnd {
     pub fee_percent: f32,
     #[serde(default = "default_reserve_fee_min")]
     pub reserve_fee_min: Amount,
+    /// Seconds to wait for an outgoing payment before leaving the melt pending
+    #[serde(default)]
+    pub payment_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
 }
 
 #[cfg(feature = "lnd")]
@@ -260,6 +561,8 @@ impl Default for Lnd {
             macaroon_file: PathBuf::new(),
             fee_percent: 0.02,
             reserve_fee_min: 2.into(),
+            payment_timeout_secs: None,
+            limits: PaymentLimits::default(),
         }
     }
 }
@@ -300,6 +603,8 @@ pub struct LdkNode {
     /// Webserver port
     #[serde(default = "default_webserver_port")]
     pub webserver_port: Option<u16>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
 }
 
 #[cfg(feature = "ldk-node")]
@@ -322,6 +627,7 @@ impl Default for LdkNode {
             rgs_url: None,
             webserver_host: default_webserver_host(),
             webserver_port: default_webserver_port(),
+            limits: PaymentLimits::default(),
         }
     }
 }
@@ -356,6 +662,14 @@ pub struct FakeWallet {
     pub min_delay_time: u64,
     #[serde(default = "default_max_delay_time")]
     pub max_delay_time: u64,
+    /// Seconds to wait for an outgoing payment before leaving the melt pending
+    #[serde(default)]
+    pub payment_timeout_secs: Option<u64>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
+    /// Msat value of one unit for each custom unit the fake wallet supports
+    #[serde(default)]
+    pub custom_unit_rates: HashMap<CurrencyUnit, u64>,
 }
 
 #[cfg(feature = "fakewallet")]
@@ -367,6 +681,9 @@ impl Default for FakeWallet {
             reserve_fee_min: 2.into(),
             min_delay_time: 1,
             max_delay_time: 3,
+            payment_timeout_secs: None,
+            limits: PaymentLimits::default(),
+            custom_unit_rates: HashMap::new(),
         }
     }
 }
@@ -403,6 +720,8 @@ pub struct GrpcProcessor {
     pub port: u16,
     #[serde(default)]
     pub tls_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub limits: PaymentLimits,
 }
 
 impl Default for GrpcProcessor {
@@ -412,6 +731,7 @@ impl Default for GrpcProcessor {
             addr: default_grpc_addr(),
             port: default_grpc_port(),
             tls_dir: None,
+            limits: PaymentLimits::default(),
         }
     }
 }
@@ -444,6 +764,15 @@ impl std::str::FromStr for DatabaseEngine {
     }
 }
 
+impl std::fmt::Display for DatabaseEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DatabaseEngine::Sqlite => write!(f, "sqlite"),
+            DatabaseEngine::Postgres => write!(f, "postgres"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct Database {
     pub engine: DatabaseEngine,
@@ -574,6 +903,108 @@ pub struct Settings {
     pub auth: Option<Auth>,
     #[cfg(feature = "prometheus")]
     pub prometheus: Option<Prometheus>,
+    /// Custom currency units the mint issues ecash in
+    #[serde(default)]
+    pub custom_units: Vec<CustomUnit>,
+    /// Config file given on the command line, read again when the settings are reloaded
+    #[serde(skip)]
+    pub config_path: Option<PathBuf>,
+    /// Settings given on the command line, kept when the settings are reloaded
+    #[serde(skip)]
+    pub overrides: Overrides,
+}
+
+/// Settings given on the command line, which take priority over the config file and the
+/// environment
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Overrides {
+    pub listen: Option<IpAddr>,
+    pub port: Option<u16>,
+    pub accept_new_seed: bool,
+    pub backend_invoice_selfcheck: bool,
+}
+
+impl Overrides {
+    /// Apply the overrides to `settings` and remember them for reloads
+    pub fn apply_to(&self, settings: &mut Settings) {
+        if self.accept_new_seed {
+            settings.info.accept_new_seed = Some(true);
+        }
+
+        if self.backend_invoice_selfcheck {
+            settings.info.backend_invoice_selfcheck = Some(true);
+        }
+
+        if let Some(listen) = self.listen {
+            settings.info.listen_host = listen.to_string();
+        }
+
+        if let Some(port) = self.port {
+            settings.info.listen_port = port;
+        }
+
+        settings.overrides = self.clone();
+    }
+}
+
+impl Settings {
+    /// Limits of the configured payment backend
+    pub fn backend_limits(&self) -> Option<&PaymentLimits> {
+        match self.ln.ln_backend {
+            #[cfg(feature = "cln")]
+            LnBackend::Cln => self.cln.as_ref().map(|cln| &cln.limits),
+            #[cfg(feature = "lnbits")]
+            LnBackend::LNbits => self.lnbits.as_ref().map(|lnbits| &lnbits.limits),
+            #[cfg(feature = "fakewallet")]
+            LnBackend::FakeWallet => self.fake_wallet.as_ref().map(|fake| &fake.limits),
+            #[cfg(feature = "lnd")]
+            LnBackend::Lnd => self.lnd.as_ref().map(|lnd| &lnd.limits),
+            #[cfg(feature = "ldk-node")]
+            LnBackend::LdkNode => self.ldk_node.as_ref().map(|ldk_node| &ldk_node.limits),
+            #[cfg(feature = "grpc-processor")]
+            LnBackend::GrpcProcessor => self
+                .grpc_processor
+                .as_ref()
+                .map(|grpc_processor| &grpc_processor.limits),
+            LnBackend::None => None,
+        }
+    }
+
+    /// Mutable limits of the configured payment backend
+    pub fn backend_limits_mut(&mut self) -> Option<&mut PaymentLimits> {
+        match self.ln.ln_backend {
+            #[cfg(feature = "cln")]
+            LnBackend::Cln => self.cln.as_mut().map(|cln| &mut cln.limits),
+            #[cfg(feature = "lnbits")]
+            LnBackend::LNbits => self.lnbits.as_mut().map(|lnbits| &mut lnbits.limits),
+            #[cfg(feature = "fakewallet")]
+            LnBackend::FakeWallet => self.fake_wallet.as_mut().map(|fake| &mut fake.limits),
+            #[cfg(feature = "lnd")]
+            LnBackend::Lnd => self.lnd.as_mut().map(|lnd| &mut lnd.limits),
+            #[cfg(feature = "ldk-node")]
+            LnBackend::LdkNode => self.ldk_node.as_mut().map(|ldk_node| &mut ldk_node.limits),
+            #[cfg(feature = "grpc-processor")]
+            LnBackend::GrpcProcessor => self
+                .grpc_processor
+                .as_mut()
+                .map(|grpc_processor| &mut grpc_processor.limits),
+            LnBackend::None => None,
+        }
+    }
+}
+
+/// Custom currency unit and the metadata advertised for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CustomUnit {
+    /// Unit string, must not clash with a built-in unit
+    pub unit: String,
+    /// Symbol wallets display next to amounts
+    pub symbol: String,
+    /// Number of decimal places of the smallest amount
+    #[serde(default)]
+    pub decimals: u8,
+    /// Description of the unit
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
@@ -584,7 +1015,7 @@ pub struct Prometheus {
     pub port: Option<u16>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Default)]
 pub struct MintInfo {
     /// name of the mint and should be recognizable
     pub name: String,
@@ -604,10 +1035,24 @@ pub struct MintInfo {
     pub contact_email: Option<String>,
     /// URL to the terms of service
     pub tos_url: Option<String>,
+    /// Canonical URLs the mint is reachable at
+    #[serde(default)]
+    pub urls: Vec<String>,
+}
+
+impl MintInfo {
+    /// Check that the advertised URLs are http(s) URLs
+    pub fn validate(&self) -> Result<(), String> {
+        self.tos_url
+            .iter()
+            .filter(|tos_url| !tos_url.is_empty())
+            .chain(self.urls.iter())
+            .try_for_each(|url| validate_http_url(url))
+    }
 }
 
 #[cfg(feature = "management-rpc")]
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Clone, Serialize, Deserialize, Default)]
 pub struct MintManagementRpc {
     /// When this is set to `true` the mint use the config file for the initial set up on first start.
     /// Changes to the `[mint_info]` after this **MUST** be made via the RPC changes to the config file or env vars will be ignored.
@@ -615,6 +1060,24 @@ pub struct MintManagementRpc {
     pub address: Option<String>,
     pub port: Option<u16>,
     pub tls_dir_path: Option<PathBuf>,
+    /// Bearer token clients must present, requests without it are rejected when set
+    pub auth_token: Option<String>,
+}
+
+#[cfg(feature = "management-rpc")]
+impl std::fmt::Debug for MintManagementRpc {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MintManagementRpc")
+            .field("enabled", &self.enabled)
+            .field("address", &self.address)
+            .field("port", &self.port)
+            .field("tls_dir_path", &self.tls_dir_path)
+            .field(
+                "auth_token",
+                &self.auth_token.as_ref().map(|_| "<redacted>"),
+            )
+            .finish()
+    }
 }
 
 impl Settings {
@@ -736,6 +1199,107 @@ mod tests {
         assert!(debug_output.contains("<hashed: "));
     }
 
+    #[cfg(feature = "fakewallet")]
+    #[test]
+    fn test_mint_info_url_validation() {
+        let mut mint_info = MintInfo {
+            tos_url: Some("https://example.com/tos".to_string()),
+            urls: vec![
+                "https://mint.example.com".to_string(),
+                "http://mintxyz.onion".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert!(mint_info.validate().is_ok());
+
+        // An empty tos url is treated as unset
+        mint_info.tos_url = Some(String::new());
+        assert!(mint_info.validate().is_ok());
+
+        mint_info.tos_url = Some("ftp://example.com/tos".to_string());
+        assert!(mint_info.validate().is_err());
+
+        mint_info.tos_url = None;
+        mint_info.urls.push("mint.example.com".to_string());
+        assert!(mint_info.validate().is_err());
+    }
+
+    #[test]
+    fn test_ln_rejects_zero_concurrent_payments() {
+        let mut ln = Ln::default();
+        assert!(ln.validate().is_ok());
+
+        ln.max_concurrent_payments = Some(1);
+        assert!(ln.validate().is_ok());
+
+        ln.max_concurrent_payments = Some(0);
+        assert!(ln.validate().is_err());
+    }
+
+    #[test]
+    fn test_backend_limits_per_unit_and_method() {
+        use std::{env, fs};
+
+        use cdk::nuts::nut00::KnownMethod;
+
+        let temp_dir = env::temp_dir().join("cdk_test_backend_limits");
+        fs::create_dir_all(&temp_dir).expect("Failed to create temp dir");
+        let config_path = temp_dir.join("config.toml");
+
+        let config_content = r#"
+[ln]
+ln_backend = "fakewallet"
+min_mint = 1
+max_mint = 500000
+min_melt = 1
+max_melt = 500000
+
+[fake_wallet]
+supported_units = ["sat", "usd"]
+fee_percent = 0.0
+reserve_fee_min = 0
+
+[fake_wallet.limits.methods.bolt11]
+max_melt = 10000
+
+[fake_wallet.limits.units.usd]
+min_mint = 100
+max_mint = 50000
+
+[fake_wallet.limits.units.usd.methods.bolt11]
+max_melt = 20000
+"#;
+        fs::write(&config_path, config_content).expect("Failed to write config file");
+
+        let settings = Settings::new(Some(&config_path));
+        let fake_wallet = settings.fake_wallet.as_ref().expect("fake wallet config");
+        let base = settings.ln.limits();
+        let bolt11 = PaymentMethod::Known(KnownMethod::Bolt11);
+        let bolt12 = PaymentMethod::Known(KnownMethod::Bolt12);
+
+        let sat_bolt11 = fake_wallet
+            .limits
+            .resolve(base, &CurrencyUnit::Sat, &bolt11);
+        assert_eq!(sat_bolt11.mint_min, 1.into());
+        assert_eq!(sat_bolt11.mint_max, 500_000.into());
+        assert_eq!(sat_bolt11.melt_max, 10_000.into());
+
+        let usd_bolt11 = fake_wallet
+            .limits
+            .resolve(base, &CurrencyUnit::Usd, &bolt11);
+        assert_eq!(usd_bolt11.mint_min, 100.into());
+        assert_eq!(usd_bolt11.mint_max, 50_000.into());
+        assert_eq!(usd_bolt11.melt_max, 20_000.into());
+
+        // Methods without overrides use the global limits
+        let sat_bolt12 = fake_wallet
+            .limits
+            .resolve(base, &CurrencyUnit::Sat, &bolt12);
+        assert_eq!(sat_bolt12, base);
+
+        let _ = fs::remove_dir_all(&temp_dir);
+    }
+
     /// Test that configuration can be loaded purely from environment variables
     /// without requiring a config.toml file with backend sections.
     ///
@@ -962,6 +1526,12 @@ max_melt = 500000
         env::set_var(crate::env_vars::ENV_FAKE_WALLET_RESERVE_FEE_MIN, "0");
         env::set_var(crate::env_vars::ENV_FAKE_WALLET_MIN_DELAY, "0");
         env::set_var(crate::env_vars::ENV_FAKE_WALLET_MAX_DELAY, "5");
+        env::set_var("CDK_MINTD_FAKE_WALLET_MAX_MELT", "10000");
+        env::set_var("CDK_MINTD_FAKE_WALLET_LIMITS_UNIT_MSAT_MAX_MINT", "2000000");
+        env::set_var(
+            "CDK_MINTD_FAKE_WALLET_LIMITS_UNIT_MSAT_METHOD_BOLT11_MIN_MELT",
+            "1000",
+        );
 
         // Load settings and apply environment variables (same as production code)
         let mut settings = Settings::new(Some(&config_path));
@@ -975,6 +1545,10 @@ max_melt = 500000
         assert_eq!(reserve_fee_u64, 0);
         assert_eq!(fakewallet_config.min_delay_time, 0);
         assert_eq!(fakewallet_config.max_delay_time, 5);
+        assert_eq!(fakewallet_config.limits.max_melt, Some(10_000.into()));
+        let msat_limits = &fakewallet_config.limits.units["msat"];
+        assert_eq!(msat_limits.max_mint, Some(2_000_000.into()));
+        assert_eq!(msat_limits.methods["bolt11"].min_melt, Some(1_000.into()));
 
         // Cleanup env vars
         env::remove_var(crate::env_vars::ENV_LN_BACKEND);
@@ -983,6 +1557,9 @@ max_melt = 500000
         env::remove_var(crate::env_vars::ENV_FAKE_WALLET_RESERVE_FEE_MIN);
         env::remove_var(crate::env_vars::ENV_FAKE_WALLET_MIN_DELAY);
         env::remove_var(crate::env_vars::ENV_FAKE_WALLET_MAX_DELAY);
+        env::remove_var("CDK_MINTD_FAKE_WALLET_MAX_MELT");
+        env::remove_var("CDK_MINTD_FAKE_WALLET_LIMITS_UNIT_MSAT_MAX_MINT");
+        env::remove_var("CDK_MINTD_FAKE_WALLET_LIMITS_UNIT_MSAT_METHOD_BOLT11_MIN_MELT");
 
         // Cleanup test file
         let _ = fs::remove_dir_all(&temp_dir);
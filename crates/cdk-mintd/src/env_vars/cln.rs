@@ -10,6 +10,8 @@ pub const ENV_CLN_RPC_PATH: &str = "CDK_MINTD_CLN_RPC_PATH";
 pub const ENV_CLN_BOLT12: &str = "CDK_MINTD_CLN_BOLT12";
 pub const ENV_CLN_FEE_PERCENT: &str = "CDK_MINTD_CLN_FEE_PERCENT";
 pub const ENV_CLN_RESERVE_FEE_MIN: &str = "CDK_MINTD_CLN_RESERVE_FEE_MIN";
+pub const ENV_CLN_PAYMENT_TIMEOUT_SECS: &str = "CDK_MINTD_CLN_PAYMENT_TIMEOUT_SECS";
+pub const ENV_CLN_LIMITS_PREFIX: &str = "CDK_MINTD_CLN";
 
 impl Cln {
     pub fn from_env(mut self) -> Self {
@@ -39,6 +41,15 @@ impl Cln {
             }
         }
 
+        if let Ok(timeout_str) = env::var(ENV_CLN_PAYMENT_TIMEOUT_SECS) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.payment_timeout_secs = Some(timeout);
+            }
+        }
+
+        // Mint and melt limits
+        self.limits = self.limits.from_env(ENV_CLN_LIMITS_PREFIX);
+
         self
     }
 }
@@ -6,6 +6,8 @@ pub const DATABASE_URL_ENV_VAR: &str = "CDK_MINTD_DATABASE_URL"; // Legacy, main
 pub const ENV_URL: &str = "CDK_MINTD_URL";
 pub const ENV_LISTEN_HOST: &str = "CDK_MINTD_LISTEN_HOST";
 pub const ENV_LISTEN_PORT: &str = "CDK_MINTD_LISTEN_PORT";
+/// Port injected by PaaS platforms, overridden by [`ENV_LISTEN_PORT`]
+pub const ENV_PORT: &str = "PORT";
 pub const ENV_SEED: &str = "CDK_MINTD_SEED";
 pub const ENV_MNEMONIC: &str = "CDK_MINTD_MNEMONIC";
 pub const ENV_SIGNATORY_URL: &str = "CDK_MINTD_SIGNATORY_URL";
@@ -14,10 +16,29 @@ pub const ENV_SECONDS_QUOTE_VALID: &str = "CDK_MINTD_SECONDS_QUOTE_VALID";
 pub const ENV_CACHE_SECONDS: &str = "CDK_MINTD_CACHE_SECONDS";
 pub const ENV_EXTEND_CACHE_SECONDS: &str = "CDK_MINTD_EXTEND_CACHE_SECONDS";
 pub const ENV_INPUT_FEE_PPK: &str = "CDK_MINTD_INPUT_FEE_PPK";
+pub const ENV_MAX_AMOUNT: &str = "CDK_MINTD_MAX_AMOUNT";
 pub const ENV_QUOTE_TTL_MINT: &str = "CDK_MINTD_QUOTE_TTL_MINT";
 pub const ENV_QUOTE_TTL_MELT: &str = "CDK_MINTD_QUOTE_TTL_MELT";
+pub const ENV_STARTUP_CHECK_CONCURRENCY: &str = "CDK_MINTD_STARTUP_CHECK_CONCURRENCY";
+pub const ENV_DEFER_STARTUP_CHECKS: &str = "CDK_MINTD_DEFER_STARTUP_CHECKS";
+pub const ENV_MAX_INPUTS_PER_REQUEST: &str = "CDK_MINTD_MAX_INPUTS_PER_REQUEST";
+pub const ENV_MAX_OUTPUTS_PER_REQUEST: &str = "CDK_MINTD_MAX_OUTPUTS_PER_REQUEST";
+pub const ENV_MAX_CHECK_STATE_YS_PER_REQUEST: &str = "CDK_MINTD_MAX_CHECK_STATE_YS_PER_REQUEST";
+pub const ENV_MAX_FILTERS_PER_SUBSCRIPTION: &str = "CDK_MINTD_MAX_FILTERS_PER_SUBSCRIPTION";
+pub const ENV_MELT_CHANGE_MAX_OUTPUTS: &str = "CDK_MINTD_MELT_CHANGE_MAX_OUTPUTS";
+pub const ENV_MELT_CHANGE_MIN_DENOMINATION: &str = "CDK_MINTD_MELT_CHANGE_MIN_DENOMINATION";
+pub const ENV_ACCEPT_NEW_SEED: &str = "CDK_MINTD_ACCEPT_NEW_SEED";
+pub const ENV_BACKEND_INVOICE_SELFCHECK: &str = "CDK_MINTD_BACKEND_INVOICE_SELFCHECK";
+pub const ENV_DERIVATION_ACCOUNT: &str = "CDK_MINTD_DERIVATION_ACCOUNT";
 
 pub const ENV_ENABLE_SWAGGER: &str = "CDK_MINTD_ENABLE_SWAGGER";
+pub const ENV_ENABLE_OPENAPI_JSON: &str = "CDK_MINTD_ENABLE_OPENAPI_JSON";
+pub const ENV_TRUSTED_PROXIES: &str = "CDK_MINTD_TRUSTED_PROXIES";
+pub const ENV_BAN_THRESHOLD: &str = "CDK_MINTD_BAN_THRESHOLD";
+pub const ENV_BAN_WINDOW_SECS: &str = "CDK_MINTD_BAN_WINDOW_SECS";
+pub const ENV_BAN_DURATION_SECS: &str = "CDK_MINTD_BAN_DURATION_SECS";
+pub const ENV_PENDING_ALERT_SECS: &str = "CDK_MINTD_PENDING_ALERT_SECS";
+pub const ENV_PENDING_ALERT_WEBHOOK: &str = "CDK_MINTD_PENDING_ALERT_WEBHOOK";
 pub const ENV_LOGGING_OUTPUT: &str = "CDK_MINTD_LOGGING_OUTPUT";
 pub const ENV_LOGGING_CONSOLE_LEVEL: &str = "CDK_MINTD_LOGGING_CONSOLE_LEVEL";
 pub const ENV_LOGGING_FILE_LEVEL: &str = "CDK_MINTD_LOGGING_FILE_LEVEL";
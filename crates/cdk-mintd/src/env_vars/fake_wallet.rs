@@ -12,6 +12,8 @@ pub const ENV_FAKE_WALLET_FEE_PERCENT: &str = "CDK_MINTD_FAKE_WALLET_FEE_PERCENT
 pub const ENV_FAKE_WALLET_RESERVE_FEE_MIN: &str = "CDK_MINTD_FAKE_WALLET_RESERVE_FEE_MIN";
 pub const ENV_FAKE_WALLET_MIN_DELAY: &str = "CDK_MINTD_FAKE_WALLET_MIN_DELAY";
 pub const ENV_FAKE_WALLET_MAX_DELAY: &str = "CDK_MINTD_FAKE_WALLET_MAX_DELAY";
+pub const ENV_FAKE_WALLET_PAYMENT_TIMEOUT_SECS: &str = "CDK_MINTD_FAKE_WALLET_PAYMENT_TIMEOUT_SECS";
+pub const ENV_FAKE_WALLET_LIMITS_PREFIX: &str = "CDK_MINTD_FAKE_WALLET";
 
 impl FakeWallet {
     pub fn from_env(mut self) -> Self {
@@ -50,6 +52,15 @@ impl FakeWallet {
             }
         }
 
+        if let Ok(timeout_str) = env::var(ENV_FAKE_WALLET_PAYMENT_TIMEOUT_SECS) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.payment_timeout_secs = Some(timeout);
+            }
+        }
+
+        // Mint and melt limits
+        self.limits = self.limits.from_env(ENV_FAKE_WALLET_LIMITS_PREFIX);
+
         self
     }
 }
@@ -12,6 +12,7 @@ pub const ENV_GRPC_PROCESSOR_SUPPORTED_UNITS: &str =
 pub const ENV_GRPC_PROCESSOR_ADDRESS: &str = "CDK_MINTD_GRPC_PAYMENT_PROCESSOR_ADDRESS";
 pub const ENV_GRPC_PROCESSOR_PORT: &str = "CDK_MINTD_GRPC_PAYMENT_PROCESSOR_PORT";
 pub const ENV_GRPC_PROCESSOR_TLS_DIR: &str = "CDK_MINTD_GRPC_PAYMENT_PROCESSOR_TLS_DIR";
+pub const ENV_GRPC_PROCESSOR_LIMITS_PREFIX: &str = "CDK_MINTD_GRPC_PAYMENT_PROCESSOR";
 
 impl GrpcProcessor {
     pub fn from_env(mut self) -> Self {
@@ -39,6 +40,9 @@ impl GrpcProcessor {
             self.tls_dir = Some(tls_dir.into());
         }
 
+        // Mint and melt limits
+        self.limits = self.limits.from_env(ENV_GRPC_PROCESSOR_LIMITS_PREFIX);
+
         self
     }
 }
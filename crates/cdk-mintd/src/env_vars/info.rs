@@ -19,9 +19,13 @@ impl Info {
             self.listen_host = host;
         }
 
-        if let Ok(port_str) = env::var(ENV_LISTEN_PORT) {
-            if let Ok(port) = port_str.parse() {
-                self.listen_port = port;
+        // The mint specific variable wins over the generic one
+        for var in [ENV_PORT, ENV_LISTEN_PORT] {
+            if let Ok(port_str) = env::var(var) {
+                match port_str.trim().parse() {
+                    Ok(port) => self.listen_port = port,
+                    Err(_) => tracing::warn!("Ignoring invalid port '{}' in {}", port_str, var),
+                }
             }
         }
 
@@ -59,12 +63,126 @@ impl Info {
             }
         }
 
+        if let Ok(max_amount_str) = env::var(ENV_MAX_AMOUNT) {
+            if let Ok(max_amount) = max_amount_str.parse() {
+                self.max_amount = Some(max_amount);
+            }
+        }
+
+        if let Ok(concurrency_str) = env::var(ENV_STARTUP_CHECK_CONCURRENCY) {
+            if let Ok(concurrency) = concurrency_str.parse() {
+                self.startup_check_concurrency = Some(concurrency);
+            }
+        }
+
+        if let Ok(defer_str) = env::var(ENV_DEFER_STARTUP_CHECKS) {
+            if let Ok(defer) = defer_str.parse() {
+                self.defer_startup_checks = Some(defer);
+            }
+        }
+
+        if let Ok(max_inputs_str) = env::var(ENV_MAX_INPUTS_PER_REQUEST) {
+            if let Ok(max_inputs) = max_inputs_str.parse() {
+                self.max_inputs_per_request = Some(max_inputs);
+            }
+        }
+
+        if let Ok(max_outputs_str) = env::var(ENV_MAX_OUTPUTS_PER_REQUEST) {
+            if let Ok(max_outputs) = max_outputs_str.parse() {
+                self.max_outputs_per_request = Some(max_outputs);
+            }
+        }
+
+        if let Ok(max_ys_str) = env::var(ENV_MAX_CHECK_STATE_YS_PER_REQUEST) {
+            if let Ok(max_ys) = max_ys_str.parse() {
+                self.max_check_state_ys_per_request = Some(max_ys);
+            }
+        }
+
+        if let Ok(max_filters_str) = env::var(ENV_MAX_FILTERS_PER_SUBSCRIPTION) {
+            if let Ok(max_filters) = max_filters_str.parse() {
+                self.max_filters_per_subscription = Some(max_filters);
+            }
+        }
+
+        if let Ok(max_outputs_str) = env::var(ENV_MELT_CHANGE_MAX_OUTPUTS) {
+            if let Ok(max_outputs) = max_outputs_str.parse() {
+                self.melt_change_max_outputs = Some(max_outputs);
+            }
+        }
+
+        if let Ok(min_denomination_str) = env::var(ENV_MELT_CHANGE_MIN_DENOMINATION) {
+            if let Ok(min_denomination) = min_denomination_str.parse() {
+                self.melt_change_min_denomination = Some(min_denomination);
+            }
+        }
+
+        if let Ok(accept_str) = env::var(ENV_ACCEPT_NEW_SEED) {
+            if let Ok(accept) = accept_str.parse() {
+                self.accept_new_seed = Some(accept);
+            }
+        }
+
+        if let Ok(selfcheck_str) = env::var(ENV_BACKEND_INVOICE_SELFCHECK) {
+            if let Ok(selfcheck) = selfcheck_str.parse() {
+                self.backend_invoice_selfcheck = Some(selfcheck);
+            }
+        }
+
+        if let Ok(account_str) = env::var(ENV_DERIVATION_ACCOUNT) {
+            if let Ok(account) = account_str.parse() {
+                self.derivation_account = Some(account);
+            }
+        }
+
         if let Ok(swagger_str) = env::var(ENV_ENABLE_SWAGGER) {
             if let Ok(enable) = swagger_str.parse() {
                 self.enable_swagger_ui = Some(enable);
             }
         }
 
+        if let Ok(openapi_str) = env::var(ENV_ENABLE_OPENAPI_JSON) {
+            if let Ok(enable) = openapi_str.parse() {
+                self.enable_openapi_json = Some(enable);
+            }
+        }
+
+        if let Ok(proxies_str) = env::var(ENV_TRUSTED_PROXIES) {
+            self.trusted_proxies = proxies_str
+                .split(',')
+                .map(|proxy| proxy.trim().to_string())
+                .filter(|proxy| !proxy.is_empty())
+                .collect();
+        }
+
+        if let Ok(threshold_str) = env::var(ENV_BAN_THRESHOLD) {
+            if let Ok(threshold) = threshold_str.parse() {
+                self.ban_threshold = Some(threshold);
+            }
+        }
+
+        if let Ok(window_str) = env::var(ENV_BAN_WINDOW_SECS) {
+            if let Ok(window) = window_str.parse() {
+                self.ban_window_secs = Some(window);
+            }
+        }
+
+        if let Ok(duration_str) = env::var(ENV_BAN_DURATION_SECS) {
+            if let Ok(duration) = duration_str.parse() {
+                self.ban_duration_secs = Some(duration);
+            }
+        }
+
+        if let Ok(alert_str) = env::var(ENV_PENDING_ALERT_SECS) {
+            if let Ok(alert) = alert_str.parse() {
+                self.pending_alert_secs = Some(alert);
+            }
+        }
+
+        if let Ok(webhook) = env::var(ENV_PENDING_ALERT_WEBHOOK) {
+            self.pending_alert_webhook = Some(webhook);
+        }
+
         // Logging configuration
         if let Ok(output_str) = env::var(ENV_LOGGING_OUTPUT) {
             if let Ok(output) = LoggingOutput::from_str(&output_str) {
@@ -86,6 +204,7 @@ impl Info {
         }
 
         self.http_cache = self.http_cache.from_env();
+        self.http_limits = self.http_limits.from_env();
 
         // Quote TTL from env
         let mut mint_ttl_env: Option<u64> = None;
@@ -21,6 +21,7 @@ pub const LDK_NODE_GOSSIP_SOURCE_TYPE_ENV_VAR: &str = "CDK_MINTD_LDK_NODE_GOSSIP
 pub const LDK_NODE_RGS_URL_ENV_VAR: &str = "CDK_MINTD_LDK_NODE_RGS_URL";
 pub const LDK_NODE_WEBSERVER_HOST_ENV_VAR: &str = "CDK_MINTD_LDK_NODE_WEBSERVER_HOST";
 pub const LDK_NODE_WEBSERVER_PORT_ENV_VAR: &str = "CDK_MINTD_LDK_NODE_WEBSERVER_PORT";
+pub const LDK_NODE_LIMITS_PREFIX_ENV_VAR: &str = "CDK_MINTD_LDK_NODE";
 
 impl LdkNode {
     pub fn from_env(mut self) -> Self {
@@ -98,6 +99,9 @@ impl LdkNode {
             }
         }
 
+        // Mint and melt limits
+        self.limits = self.limits.from_env(LDK_NODE_LIMITS_PREFIX_ENV_VAR);
+
         self
     }
 }
@@ -2,7 +2,7 @@
 
 use std::env;
 
-use crate::config::Ln;
+use crate::config::{Ln, PaymentLimits};
 
 // LN environment variables
 pub const ENV_LN_BACKEND: &str = "CDK_MINTD_LN_BACKEND";
@@ -11,6 +11,18 @@ pub const ENV_LN_MIN_MINT: &str = "CDK_MINTD_LN_MIN_MINT";
 pub const ENV_LN_MAX_MINT: &str = "CDK_MINTD_LN_MAX_MINT";
 pub const ENV_LN_MIN_MELT: &str = "CDK_MINTD_LN_MIN_MELT";
 pub const ENV_LN_MAX_MELT: &str = "CDK_MINTD_LN_MAX_MELT";
+pub const ENV_LN_MAX_CONCURRENT_PAYMENTS: &str = "CDK_MINTD_LN_MAX_CONCURRENT_PAYMENTS";
+pub const ENV_LN_PAYMENT_QUEUE_TIMEOUT_SECS: &str = "CDK_MINTD_LN_PAYMENT_QUEUE_TIMEOUT_SECS";
+pub const ENV_LN_REUSE_MELT_QUOTES: &str = "CDK_MINTD_LN_REUSE_MELT_QUOTES";
+
+// Backend limit environment variables, appended to the backend prefix
+// (e.g. `CDK_MINTD_CLN_MAX_MELT`, `CDK_MINTD_CLN_LIMITS_METHOD_BOLT11_MAX_MELT`,
+// `CDK_MINTD_FAKE_WALLET_LIMITS_UNIT_USD_METHOD_BOLT11_MAX_MINT`)
+pub const ENV_LIMITS_MIN_MINT: &str = "MIN_MINT";
+pub const ENV_LIMITS_MAX_MINT: &str = "MAX_MINT";
+pub const ENV_LIMITS_MIN_MELT: &str = "MIN_MELT";
+pub const ENV_LIMITS_MAX_MELT: &str = "MAX_MELT";
+pub const ENV_LIMITS_OVERRIDES: &str = "LIMITS";
 
 impl Ln {
     pub fn from_env(mut self) -> Self {
@@ -53,6 +65,105 @@ impl Ln {
             }
         }
 
+        // Payment concurrency limit
+        if let Ok(max_str) = env::var(ENV_LN_MAX_CONCURRENT_PAYMENTS) {
+            if let Ok(max) = max_str.parse::<usize>() {
+                self.max_concurrent_payments = Some(max);
+            }
+        }
+
+        if let Ok(timeout_str) = env::var(ENV_LN_PAYMENT_QUEUE_TIMEOUT_SECS) {
+            if let Ok(timeout) = timeout_str.parse::<u64>() {
+                self.payment_queue_timeout_secs = Some(timeout);
+            }
+        }
+
+        if let Ok(reuse_str) = env::var(ENV_LN_REUSE_MELT_QUOTES) {
+            if let Ok(reuse) = reuse_str.parse() {
+                self.reuse_melt_quotes = Some(reuse);
+            }
+        }
+
         self
     }
 }
+
+impl PaymentLimits {
+    /// Read limits for the backend whose env vars start with `prefix`
+    pub fn from_env(mut self, prefix: &str) -> Self {
+        for field in [
+            ENV_LIMITS_MIN_MINT,
+            ENV_LIMITS_MAX_MINT,
+            ENV_LIMITS_MIN_MELT,
+            ENV_LIMITS_MAX_MELT,
+        ] {
+            if let Ok(amount_str) = env::var(format!("{prefix}_{field}")) {
+                self.set_from_env(field, &amount_str);
+            }
+        }
+
+        // Overrides: {prefix}_LIMITS_METHOD_<METHOD>_<FIELD>,
+        // {prefix}_LIMITS_UNIT_<UNIT>_<FIELD> and
+        // {prefix}_LIMITS_UNIT_<UNIT>_METHOD_<METHOD>_<FIELD>
+        let overrides_prefix = format!("{prefix}_{ENV_LIMITS_OVERRIDES}_");
+        for (key, value) in env::vars() {
+            let Some(scope) = key.strip_prefix(&overrides_prefix) else {
+                continue;
+            };
+
+            let Some((scope, field)) = [
+                ENV_LIMITS_MIN_MINT,
+                ENV_LIMITS_MAX_MINT,
+                ENV_LIMITS_MIN_MELT,
+                ENV_LIMITS_MAX_MELT,
+            ]
+            .into_iter()
+            .find_map(|field| {
+                scope
+                    .strip_suffix(field)
+                    .and_then(|scope| scope.strip_suffix('_'))
+                    .map(|scope| (scope, field))
+            }) else {
+                tracing::warn!("Unknown limit env var {key}");
+                continue;
+            };
+
+            let limits = if let Some(unit_scope) = scope.strip_prefix("UNIT_") {
+                match unit_scope.split_once("_METHOD_") {
+                    Some((unit, method)) => self
+                        .units
+                        .entry(unit.to_lowercase())
+                        .or_default()
+                        .methods
+                        .entry(method.to_lowercase())
+                        .or_default(),
+                    None => self.units.entry(unit_scope.to_lowercase()).or_default(),
+                }
+            } else if let Some(method) = scope.strip_prefix("METHOD_") {
+                self.methods.entry(method.to_lowercase()).or_default()
+            } else {
+                tracing::warn!("Unknown limit env var {key}");
+                continue;
+            };
+
+            limits.set_from_env(field, &value);
+        }
+
+        self
+    }
+
+    fn set_from_env(&mut self, field: &str, amount_str: &str) {
+        let Ok(amount) = amount_str.parse::<u64>() else {
+            tracing::warn!("Invalid amount for {field} limit: {amount_str}");
+            return;
+        };
+
+        match field {
+            ENV_LIMITS_MIN_MINT => self.min_mint = Some(amount.into()),
+            ENV_LIMITS_MAX_MINT => self.max_mint = Some(amount.into()),
+            ENV_LIMITS_MIN_MELT => self.min_melt = Some(amount.into()),
+            ENV_LIMITS_MAX_MELT => self.max_melt = Some(amount.into()),
+            _ => {}
+        }
+    }
+}
@@ -10,6 +10,8 @@ pub const ENV_LNBITS_INVOICE_API_KEY: &str = "CDK_MINTD_LNBITS_INVOICE_API_KEY";
 pub const ENV_LNBITS_API: &str = "CDK_MINTD_LNBITS_API";
 pub const ENV_LNBITS_FEE_PERCENT: &str = "CDK_MINTD_LNBITS_FEE_PERCENT";
 pub const ENV_LNBITS_RESERVE_FEE_MIN: &str = "CDK_MINTD_LNBITS_RESERVE_FEE_MIN";
+pub const ENV_LNBITS_PAYMENT_TIMEOUT_SECS: &str = "CDK_MINTD_LNBITS_PAYMENT_TIMEOUT_SECS";
+pub const ENV_LNBITS_LIMITS_PREFIX: &str = "CDK_MINTD_LNBITS";
 
 impl LNbits {
     pub fn from_env(mut self) -> Self {
@@ -37,6 +39,15 @@ impl LNbits {
             }
         }
 
+        if let Ok(timeout_str) = env::var(ENV_LNBITS_PAYMENT_TIMEOUT_SECS) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.payment_timeout_secs = Some(timeout);
+            }
+        }
+
+        // Mint and melt limits
+        self.limits = self.limits.from_env(ENV_LNBITS_LIMITS_PREFIX);
+
         self
     }
 }
@@ -11,6 +11,8 @@ pub const ENV_LND_CERT_FILE: &str = "CDK_MINTD_LND_CERT_FILE";
 pub const ENV_LND_MACAROON_FILE: &str = "CDK_MINTD_LND_MACAROON_FILE";
 pub const ENV_LND_FEE_PERCENT: &str = "CDK_MINTD_LND_FEE_PERCENT";
 pub const ENV_LND_RESERVE_FEE_MIN: &str = "CDK_MINTD_LND_RESERVE_FEE_MIN";
+pub const ENV_LND_PAYMENT_TIMEOUT_SECS: &str = "CDK_MINTD_LND_PAYMENT_TIMEOUT_SECS";
+pub const ENV_LND_LIMITS_PREFIX: &str = "CDK_MINTD_LND";
 
 impl Lnd {
     pub fn from_env(mut self) -> Self {
@@ -38,6 +40,15 @@ impl Lnd {
             }
         }
 
+        if let Ok(timeout_str) = env::var(ENV_LND_PAYMENT_TIMEOUT_SECS) {
+            if let Ok(timeout) = timeout_str.parse() {
+                self.payment_timeout_secs = Some(timeout);
+            }
+        }
+
+        // Mint and melt limits
+        self.limits = self.limits.from_env(ENV_LND_LIMITS_PREFIX);
+
         self
     }
 }
@@ -14,6 +14,7 @@ pub const ENV_MINT_MOTD: &str = "CDK_MINTD_MINT_MOTD";
 pub const ENV_MINT_CONTACT_NOSTR: &str = "CDK_MINTD_MINT_CONTACT_NOSTR";
 pub const ENV_MINT_CONTACT_EMAIL: &str = "CDK_MINTD_MINT_CONTACT_EMAIL";
 pub const ENV_MINT_TOS_URL: &str = "CDK_MINTD_MINT_TOS_URL";
+pub const ENV_MINT_URLS: &str = "CDK_MINTD_MINT_URLS";
 
 impl MintInfo {
     pub fn from_env(mut self) -> Self {
@@ -58,6 +59,15 @@ impl MintInfo {
             self.tos_url = Some(tos_url);
         }
 
+        // Comma-separated list of canonical mint URLs
+        if let Ok(urls) = env::var(ENV_MINT_URLS) {
+            self.urls = urls
+                .split(',')
+                .map(|url| url.trim().to_string())
+                .filter(|url| !url.is_empty())
+                .collect();
+        }
+
         self
     }
 }
@@ -4,18 +4,26 @@
 #[cfg(feature = "auth")]
 use std::collections::HashMap;
 use std::env::{self};
-use std::net::SocketAddr;
 use std::path::{Path, PathBuf};
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 // external crates
 use anyhow::{anyhow, bail, Result};
 use axum::Router;
 use bip39::Mnemonic;
+use cdk::amount::Amount;
 use cdk::cdk_database::{self, KVStore, MintDatabase, MintKeysDatabase};
-use cdk::mint::{Mint, MintBuilder, MintMeltLimits};
+use cdk::mint::{
+    verify_database, BanPolicy, Mint, MintBuilder, PaymentConcurrencyLimit, StartupCheckConfig,
+    DEFAULT_BAN_DURATION, DEFAULT_BAN_WINDOW, DEFAULT_PAYMENT_QUEUE_TIMEOUT,
+    DEFAULT_STARTUP_CHECK_CONCURRENCY,
+};
 use cdk::nuts::nut00::KnownMethod;
+use cdk::nuts::nut06::{
+    DEFAULT_MAX_CHECK_STATE_YS, DEFAULT_MAX_INPUTS_PER_REQUEST, DEFAULT_MAX_OUTPUTS_PER_REQUEST,
+};
 #[cfg(any(
     feature = "cln",
     feature = "lnbits",
@@ -25,19 +33,15 @@ use cdk::nuts::nut00::KnownMethod;
     feature = "grpc-processor"
 ))]
 use cdk::nuts::nut17::SupportedMethods;
+use cdk::nuts::nut17::DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION;
 use cdk::nuts::nut19::{CachedEndpoint, Method as NUT19Method, Path as NUT19Path};
-#[cfg(any(
-    feature = "cln",
-    feature = "lnbits",
-    feature = "lnd",
-    feature = "ldk-node"
-))]
-use cdk::nuts::CurrencyUnit;
 #[cfg(feature = "auth")]
 use cdk::nuts::{AuthRequired, Method, ProtectedEndpoint, RoutePath};
-use cdk::nuts::{ContactInfo, MintVersion, PaymentMethod};
+use cdk::nuts::{
+    ContactInfo, CurrencyUnit, MeltChangePolicy, MintVersion, PaymentMethod, RequestLimits,
+    UnitMetadata,
+};
 use cdk_axum::cache::HttpCache;
-use cdk_common::common::QuoteTTL;
 use cdk_common::database::DynMintDatabase;
 // internal crate modules
 #[cfg(feature = "prometheus")]
@@ -56,21 +60,19 @@ use cli::CLIArgs;
 use config::AuthType;
 use config::{DatabaseEngine, LnBackend};
 use env_vars::ENV_WORK_DIR;
-use setup::LnBackendSetup;
-use tower::ServiceBuilder;
-use tower_http::compression::CompressionLayer;
-use tower_http::decompression::RequestDecompressionLayer;
-use tower_http::trace::TraceLayer;
+use setup::{LnBackendSetup, SetupError};
 use tracing_appender::{non_blocking, rolling};
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 use tracing_subscriber::EnvFilter;
-#[cfg(feature = "swagger")]
-use utoipa::OpenApi;
 
 pub mod cli;
 pub mod config;
 pub mod env_vars;
+pub mod reload;
+pub mod run;
 pub mod setup;
+pub mod summary;
+mod webhook;
 
 const CARGO_PKG_VERSION: Option<&'static str> = option_env!("CARGO_PKG_VERSION");
 
@@ -106,6 +108,56 @@ async fn initial_setup(
     Ok((localstore, keystore, kv))
 }
 
+/// Level of the console output, changed when the settings are reloaded
+static CONSOLE_LEVEL: RwLock<tracing::Level> = RwLock::new(tracing::Level::INFO);
+/// Level of the file output, changed when the settings are reloaded
+static FILE_LEVEL: RwLock<tracing::Level> = RwLock::new(tracing::Level::DEBUG);
+
+/// Console and file log levels of the logging config
+fn log_levels(logging_config: &config::LoggingConfig) -> (tracing::Level, tracing::Level) {
+    let console_level = logging_config
+        .console_level
+        .as_deref()
+        .unwrap_or("info")
+        .parse::<tracing::Level>()
+        .unwrap_or(tracing::Level::INFO);
+    let file_level = logging_config
+        .file_level
+        .as_deref()
+        .unwrap_or("debug")
+        .parse::<tracing::Level>()
+        .unwrap_or(tracing::Level::DEBUG);
+
+    (console_level, file_level)
+}
+
+/// Apply the log levels of the logging config to the running subscriber
+///
+/// Levels more verbose than `debug` are still dropped by the global filter.
+pub fn set_log_levels(logging_config: &config::LoggingConfig) {
+    let (console_level, file_level) = log_levels(logging_config);
+    *CONSOLE_LEVEL
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = console_level;
+    *FILE_LEVEL
+        .write()
+        .unwrap_or_else(|poisoned| poisoned.into_inner()) = file_level;
+}
+
+fn console_enabled(metadata: &tracing::Metadata<'_>) -> bool {
+    *metadata.level()
+        <= *CONSOLE_LEVEL
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn file_enabled(metadata: &tracing::Metadata<'_>) -> bool {
+    *metadata.level()
+        <= *FILE_LEVEL
+            .read()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
 /// Sets up and initializes a tracing subscriber with custom log filtering.
 /// Logs can be configured to output to stdout only, file only, or both.
 /// Returns a guard that must be kept alive and properly dropped on shutdown.
@@ -122,6 +174,9 @@ pub fn setup_tracing(
     let tungstenite = "tungstenite=warn";
     let tokio_postgres = "tokio_postgres=warn";
 
+    set_log_levels(logging_config);
+    let (console_level, file_level) = log_levels(logging_config);
+
     let env_filter = EnvFilter::new(format!(
         "{default_filter},{hyper_filter},{h2_filter},{tower_filter},{tower_http},{rustls},{tungstenite},{tokio_postgres}"
     ));
@@ -130,14 +185,7 @@ pub fn setup_tracing(
     match logging_config.output {
         LoggingOutput::Stderr => {
             // Console output only (stderr)
-            let console_level = logging_config
-                .console_level
-                .as_deref()
-                .unwrap_or("info")
-                .parse::<tracing::Level>()
-                .unwrap_or(tracing::Level::INFO);
-
-            let stderr = std::io::stderr.with_max_level(console_level);
+            let stderr = std::io::stderr.with_filter(console_enabled);
 
             tracing_subscriber::fmt()
                 .with_env_filter(env_filter)
@@ -150,13 +198,6 @@ pub fn setup_tracing(
         }
         LoggingOutput::File => {
             // File output only
-            let file_level = logging_config
-                .file_level
-                .as_deref()
-                .unwrap_or("debug")
-                .parse::<tracing::Level>()
-                .unwrap_or(tracing::Level::DEBUG);
-
             // Create logs directory in work_dir if it doesn't exist
             let logs_dir = work_dir.join("logs");
             std::fs::create_dir_all(&logs_dir)?;
@@ -165,7 +206,7 @@ pub fn setup_tracing(
             let file_appender = rolling::daily(&logs_dir, "cdk-mintd.log");
             let (non_blocking_appender, guard) = non_blocking(file_appender);
 
-            let file_writer = non_blocking_appender.with_max_level(file_level);
+            let file_writer = non_blocking_appender.with_filter(file_enabled);
 
             tracing_subscriber::fmt()
                 .with_env_filter(env_filter)
@@ -182,19 +223,6 @@ pub fn setup_tracing(
         }
         LoggingOutput::Both => {
             // Both console and file output (stderr + file)
-            let console_level = logging_config
-                .console_level
-                .as_deref()
-                .unwrap_or("info")
-                .parse::<tracing::Level>()
-                .unwrap_or(tracing::Level::INFO);
-            let file_level = logging_config
-                .file_level
-                .as_deref()
-                .unwrap_or("debug")
-                .parse::<tracing::Level>()
-                .unwrap_or(tracing::Level::DEBUG);
-
             // Create logs directory in work_dir if it doesn't exist
             let logs_dir = work_dir.join("logs");
             std::fs::create_dir_all(&logs_dir)?;
@@ -204,8 +232,8 @@ pub fn setup_tracing(
             let (non_blocking_appender, guard) = non_blocking(file_appender);
 
             // Combine console output (stderr) and file output
-            let stderr = std::io::stderr.with_max_level(console_level);
-            let file_writer = non_blocking_appender.with_max_level(file_level);
+            let stderr = std::io::stderr.with_filter(console_enabled);
+            let file_writer = non_blocking_appender.with_filter(file_enabled);
 
             tracing_subscriber::fmt()
                 .with_env_filter(env_filter)
@@ -242,8 +270,8 @@ pub async fn get_work_directory(args: &CLIArgs) -> Result<PathBuf> {
 /// Loads the application settings based on a configuration file and environment variables.
 pub fn load_settings(work_dir: &Path, config_path: Option<PathBuf>) -> Result<config::Settings> {
     // get config file name from args
-    let config_file_arg = match config_path {
-        Some(c) => c,
+    let config_file_arg = match &config_path {
+        Some(c) => c.clone(),
         None => work_dir.join("config.toml"),
     };
 
@@ -253,10 +281,17 @@ pub fn load_settings(work_dir: &Path, config_path: Option<PathBuf>) -> Result<co
         tracing::info!("Config file does not exist. Attempting to read env vars");
         config::Settings::default()
     };
+    settings.config_path = config_path;
 
     // This check for any settings defined in ENV VARs
     // ENV VARS will take **priority** over those in the config
-    settings.from_env()
+    let settings = settings.from_env()?;
+
+    settings.mint_info.validate().map_err(|err| anyhow!(err))?;
+    settings.ln.validate().map_err(|err| anyhow!(err))?;
+    settings.info.listen_address().map_err(|err| anyhow!(err))?;
+
+    Ok(settings)
 }
 
 async fn setup_database(
@@ -350,6 +385,80 @@ async fn configure_mint_builder(
     // Configure basic mint information
     let mint_builder = configure_basic_info(settings, mint_builder);
 
+    // Configure custom currency units
+    let mint_builder = configure_custom_units(settings, mint_builder)?;
+
+    let mint_builder = mint_builder.with_startup_check_config(StartupCheckConfig {
+        concurrency: settings
+            .info
+            .startup_check_concurrency
+            .unwrap_or(DEFAULT_STARTUP_CHECK_CONCURRENCY),
+        defer: settings.info.defer_startup_checks.unwrap_or(false),
+    });
+
+    let mint_builder =
+        mint_builder.with_accept_new_seed(settings.info.accept_new_seed.unwrap_or(false));
+
+    let mint_builder = mint_builder
+        .with_backend_invoice_selfcheck(settings.info.backend_invoice_selfcheck.unwrap_or(false));
+
+    let mint_builder =
+        mint_builder.with_derivation_account(settings.info.derivation_account.unwrap_or(0));
+
+    let mint_builder = mint_builder.with_request_limits(RequestLimits {
+        max_inputs: settings
+            .info
+            .max_inputs_per_request
+            .unwrap_or(DEFAULT_MAX_INPUTS_PER_REQUEST),
+        max_outputs: settings
+            .info
+            .max_outputs_per_request
+            .unwrap_or(DEFAULT_MAX_OUTPUTS_PER_REQUEST),
+        max_check_state_ys: settings
+            .info
+            .max_check_state_ys_per_request
+            .unwrap_or(DEFAULT_MAX_CHECK_STATE_YS),
+    });
+
+    let mint_builder = mint_builder.with_max_subscription_filters(
+        settings
+            .info
+            .max_filters_per_subscription
+            .unwrap_or(DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION),
+    );
+
+    let mint_builder = mint_builder.with_melt_change_policy(MeltChangePolicy {
+        max_outputs: settings.info.melt_change_max_outputs,
+        min_denomination: settings.info.melt_change_min_denomination.map(Amount::from),
+    });
+
+    let mint_builder =
+        mint_builder.with_melt_quote_reuse(settings.ln.reuse_melt_quotes.unwrap_or(true));
+
+    let mint_builder = match settings.ln.max_concurrent_payments {
+        Some(max_in_flight) => {
+            mint_builder.with_payment_concurrency_limit(PaymentConcurrencyLimit {
+                max_in_flight,
+                queue_timeout: settings
+                    .ln
+                    .payment_queue_timeout_secs
+                    .map(Duration::from_secs)
+                    .unwrap_or(DEFAULT_PAYMENT_QUEUE_TIMEOUT),
+            })
+        }
+        None => mint_builder,
+    };
+
+    let mint_builder = match ban_policy(&settings.info) {
+        Some(policy) => mint_builder.with_ban_policy(policy),
+        None => mint_builder,
+    };
+
+    let mint_builder = match settings.info.pending_alert_secs {
+        Some(alert_secs) => mint_builder.with_pending_alert(Duration::from_secs(alert_secs)),
+        None => mint_builder,
+    };
+
     // Configure lightning backend
     let mint_builder =
         configure_lightning_backend(settings, mint_builder, runtime, work_dir, kv_store).await?;
@@ -370,6 +479,49 @@ async fn configure_mint_builder(
     Ok(mint_builder)
 }
 
+/// Configures custom currency units and the metadata advertised for them
+fn configure_custom_units(
+    settings: &config::Settings,
+    mut mint_builder: MintBuilder,
+) -> Result<MintBuilder> {
+    for custom_unit in &settings.custom_units {
+        let unit = CurrencyUnit::from_str(&custom_unit.unit)?;
+
+        if !matches!(unit, CurrencyUnit::Custom(_)) {
+            bail!(
+                "Custom unit `{}` clashes with a built-in unit",
+                custom_unit.unit
+            );
+        }
+
+        tracing::info!("Adding custom unit {}", unit);
+
+        mint_builder = mint_builder.with_custom_unit(UnitMetadata {
+            unit,
+            symbol: custom_unit.symbol.clone(),
+            decimals: custom_unit.decimals,
+            description: custom_unit.description.clone(),
+        });
+    }
+
+    Ok(mint_builder)
+}
+
+/// Ban policy of the settings, `None` if bans are disabled
+fn ban_policy(info: &config::Info) -> Option<BanPolicy> {
+    info.ban_threshold.map(|threshold| BanPolicy {
+        threshold,
+        window: info
+            .ban_window_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_BAN_WINDOW),
+        duration: info
+            .ban_duration_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_BAN_DURATION),
+    })
+}
+
 /// Configures basic mint information (name, contact info, descriptions, etc.)
 fn configure_basic_info(settings: &config::Settings, mint_builder: MintBuilder) -> MintBuilder {
     // Add contact information
@@ -437,6 +589,10 @@ fn configure_basic_info(settings: &config::Settings, mint_builder: MintBuilder)
         }
     }
 
+    if !settings.mint_info.urls.is_empty() {
+        builder = builder.with_urls(settings.mint_info.urls.clone());
+    }
+
     builder
 }
 /// Configures Lightning Network backend based on the specified backend type
@@ -447,13 +603,6 @@ async fn configure_lightning_backend(
     work_dir: &Path,
     _kv_store: Option<Arc<dyn KVStore<Err = cdk::cdk_database::Error> + Send + Sync>>,
 ) -> Result<MintBuilder> {
-    let mint_melt_limits = MintMeltLimits {
-        mint_min: settings.ln.min_mint,
-        mint_max: settings.ln.max_mint,
-        melt_min: settings.ln.min_melt,
-        melt_max: settings.ln.max_melt,
-    };
-
     tracing::debug!("Ln backend: {:?}", settings.ln.ln_backend);
 
     match settings.ln.ln_backend {
@@ -462,7 +611,7 @@ async fn configure_lightning_backend(
             let cln_settings = settings
                 .cln
                 .clone()
-                .expect("Config checked at load that cln is some");
+                .ok_or(SetupError::MissingBackendSettings("cln"))?;
             let cln = cln_settings
                 .setup(settings, CurrencyUnit::Msat, None, work_dir, _kv_store)
                 .await?;
@@ -473,14 +622,17 @@ async fn configure_lightning_backend(
                 settings,
                 mint_builder,
                 CurrencyUnit::Sat,
-                mint_melt_limits,
+                &cln_settings.limits,
                 Arc::new(cln),
             )
             .await?;
         }
         #[cfg(feature = "lnbits")]
         LnBackend::LNbits => {
-            let lnbits_settings = settings.clone().lnbits.expect("Checked on config load");
+            let lnbits_settings = settings
+                .lnbits
+                .clone()
+                .ok_or(SetupError::MissingBackendSettings("lnbits"))?;
             let lnbits = lnbits_settings
                 .setup(settings, CurrencyUnit::Sat, None, work_dir, None)
                 .await?;
@@ -491,14 +643,17 @@ async fn configure_lightning_backend(
                 settings,
                 mint_builder,
                 CurrencyUnit::Sat,
-                mint_melt_limits,
+                &lnbits_settings.limits,
                 Arc::new(lnbits),
             )
             .await?;
         }
         #[cfg(feature = "lnd")]
         LnBackend::Lnd => {
-            let lnd_settings = settings.clone().lnd.expect("Checked at config load");
+            let lnd_settings = settings
+                .lnd
+                .clone()
+                .ok_or(SetupError::MissingBackendSettings("lnd"))?;
             let lnd = lnd_settings
                 .setup(settings, CurrencyUnit::Msat, None, work_dir, _kv_store)
                 .await?;
@@ -509,14 +664,17 @@ async fn configure_lightning_backend(
                 settings,
                 mint_builder,
                 CurrencyUnit::Sat,
-                mint_melt_limits,
+                &lnd_settings.limits,
                 Arc::new(lnd),
             )
             .await?;
         }
         #[cfg(feature = "fakewallet")]
         LnBackend::FakeWallet => {
-            let fake_wallet = settings.clone().fake_wallet.expect("Fake wallet defined");
+            let fake_wallet = settings
+                .fake_wallet
+                .clone()
+                .ok_or(SetupError::MissingBackendSettings("fakewallet"))?;
             tracing::info!("Using fake wallet: {:?}", fake_wallet);
 
             for unit in fake_wallet.clone().supported_units {
@@ -530,7 +688,7 @@ async fn configure_lightning_backend(
                     settings,
                     mint_builder,
                     unit.clone(),
-                    mint_melt_limits,
+                    &fake_wallet.limits,
                     Arc::new(fake),
                 )
                 .await?;
@@ -539,9 +697,9 @@ async fn configure_lightning_backend(
         #[cfg(feature = "grpc-processor")]
         LnBackend::GrpcProcessor => {
             let grpc_processor = settings
-                .clone()
                 .grpc_processor
-                .expect("grpc processor config defined");
+                .clone()
+                .ok_or(SetupError::MissingBackendSettings("grpcprocessor"))?;
 
             tracing::info!(
                 "Attempting to start with gRPC payment processor at {}:{}.",
@@ -561,7 +719,7 @@ async fn configure_lightning_backend(
                     settings,
                     mint_builder,
                     unit.clone(),
-                    mint_melt_limits,
+                    &grpc_processor.limits,
                     Arc::new(processor),
                 )
                 .await?;
@@ -569,7 +727,10 @@ async fn configure_lightning_backend(
         }
         #[cfg(feature = "ldk-node")]
         LnBackend::LdkNode => {
-            let ldk_node_settings = settings.clone().ldk_node.expect("Checked at config load");
+            let ldk_node_settings = settings
+                .ldk_node
+                .clone()
+                .ok_or(SetupError::MissingBackendSettings("ldk-node"))?;
             tracing::info!("Using LDK Node backend: {:?}", ldk_node_settings);
 
             let ldk_node = ldk_node_settings
@@ -580,7 +741,7 @@ async fn configure_lightning_backend(
                 settings,
                 mint_builder,
                 CurrencyUnit::Sat,
-                mint_melt_limits,
+                &ldk_node_settings.limits,
                 Arc::new(ldk_node),
             )
             .await?;
@@ -590,7 +751,7 @@ async fn configure_lightning_backend(
                 "Payment backend was not set or feature disabled. {:?}",
                 settings.ln.ln_backend
             );
-            bail!("Lightning backend must be configured");
+            return Err(SetupError::NoPaymentBackend.into());
         }
     };
 
@@ -602,7 +763,7 @@ async fn configure_backend_for_unit(
     settings: &config::Settings,
     mut mint_builder: MintBuilder,
     unit: cdk::nuts::CurrencyUnit,
-    mint_melt_limits: MintMeltLimits,
+    limits: &config::PaymentLimits,
     backend: Arc<dyn MintPayment<Err = cdk_common::payment::Error> + Send + Sync>,
 ) -> Result<MintBuilder> {
     let payment_settings = backend.get_settings().await?;
@@ -624,8 +785,11 @@ async fn configure_backend_for_unit(
         methods.push(PaymentMethod::from(method_name.as_str()));
     }
 
-    // Add all supported payment methods to the mint builder
+    // Add all supported payment methods to the mint builder, each with its own limits
     for method in &methods {
+        let mint_melt_limits = limits.resolve(settings.ln.limits(), &unit, method);
+        tracing::debug!("Limits for {} {}: {:?}", unit, method, mint_melt_limits);
+
         mint_builder
             .add_payment_processor(
                 unit.clone(),
@@ -651,6 +815,10 @@ async fn configure_backend_for_unit(
         mint_builder.set_unit_fee(&unit, input_fee)?;
     }
 
+    if let Some(max_amount) = settings.info.max_amount {
+        mint_builder.set_unit_max_amount(&unit, Amount::from(max_amount))?;
+    }
+
     Ok(mint_builder)
 }
 
@@ -876,353 +1044,30 @@ async fn build_mint(
             .build_with_seed(keystore, &mnemonic.to_seed_normalized(""))
             .await?)
     } else {
-        bail!("No seed nor remote signatory set");
+        Err(SetupError::NoSeed.into())
     }
 }
 
 async fn start_services_with_shutdown(
-    mint: Arc<cdk::mint::Mint>,
+    setup: MintdSetup,
     settings: &config::Settings,
-    _work_dir: &Path,
-    mint_builder_info: cdk::nuts::MintInfo,
+    work_dir: &Path,
     shutdown_signal: impl std::future::Future<Output = ()> + Send + 'static,
     routers: Vec<Router>,
-    #[cfg(feature = "auth")] auth_localstore: Option<cdk_common::database::DynMintAuthDatabase>,
 ) -> Result<()> {
-    let listen_addr = settings.info.listen_host.clone();
-    let listen_port = settings.info.listen_port;
-    let cache: HttpCache = settings.info.http_cache.clone().into();
-
-    #[cfg(feature = "management-rpc")]
-    let mut rpc_enabled = false;
-    #[cfg(not(feature = "management-rpc"))]
-    let rpc_enabled = false;
-
-    #[cfg(feature = "management-rpc")]
-    let mut rpc_server: Option<cdk_mint_rpc::MintRPCServer> = None;
-
-    #[cfg(feature = "management-rpc")]
-    {
-        if let Some(rpc_settings) = settings.mint_management_rpc.clone() {
-            if rpc_settings.enabled {
-                let addr = rpc_settings.address.unwrap_or("127.0.0.1".to_string());
-                let port = rpc_settings.port.unwrap_or(8086);
-                let mut mint_rpc = cdk_mint_rpc::MintRPCServer::new(&addr, port, mint.clone())?;
-
-                let tls_dir = rpc_settings.tls_dir_path.unwrap_or(_work_dir.join("tls"));
-
-                let tls_dir = if tls_dir.exists() {
-                    Some(tls_dir)
-                } else {
-                    tracing::warn!(
-                        "TLS directory does not exist: {}. Starting RPC server in INSECURE mode without TLS encryption",
-                        tls_dir.display()
-                    );
-                    None
-                };
-
-                mint_rpc.start(tls_dir).await?;
-
-                rpc_server = Some(mint_rpc);
-
-                rpc_enabled = true;
-            }
-        }
-    }
-
-    // Determine the desired QuoteTTL from config/env or fall back to defaults
-    let desired_quote_ttl: QuoteTTL = settings.info.quote_ttl.unwrap_or_default();
-
-    if rpc_enabled {
-        if mint.mint_info().await.is_err() {
-            tracing::info!("Mint info not set on mint, setting.");
-            // First boot with RPC enabled: seed from config
-            mint.set_mint_info(mint_builder_info).await?;
-            mint.set_quote_ttl(desired_quote_ttl).await?;
-        } else {
-            // If QuoteTTL has never been persisted, seed it now from config
-            if !mint.quote_ttl_is_persisted().await? {
-                mint.set_quote_ttl(desired_quote_ttl).await?;
-            }
-            // Add/refresh version information without altering stored mint_info fields
-            let mint_version = MintVersion::new(
-                "cdk-mintd".to_string(),
-                CARGO_PKG_VERSION.unwrap_or("Unknown").to_string(),
-            );
-            let mut stored_mint_info = mint.mint_info().await?;
-            stored_mint_info.version = Some(mint_version);
-            mint.set_mint_info(stored_mint_info).await?;
-
-            tracing::info!("Mint info already set, not using config file settings.");
-        }
-    } else {
-        // RPC disabled: config is source of truth on every boot
-        tracing::info!("RPC not enabled, using mint info and quote TTL from config.");
-        let mut mint_builder_info = mint_builder_info;
-
-        if let Ok(mint_info) = mint.mint_info().await {
-            if mint_builder_info.pubkey.is_none() {
-                mint_builder_info.pubkey = mint_info.pubkey;
-            }
-        }
-
-        mint.set_mint_info(mint_builder_info).await?;
-        mint.set_quote_ttl(desired_quote_ttl).await?;
-    }
-
-    let mint_info = mint.mint_info().await?;
-    let nut04_methods = mint_info.nuts.nut04.supported_methods();
-    let nut05_methods = mint_info.nuts.nut05.supported_methods();
-
-    // Get custom payment methods from payment processors
-    let mut custom_methods = mint.get_custom_payment_methods().await?;
-
-    // Add bolt11 if it's supported by any payment processor
-    let bolt11_method = PaymentMethod::Known(KnownMethod::Bolt11);
-    let bolt11_supported =
-        nut04_methods.contains(&&bolt11_method) || nut05_methods.contains(&&bolt11_method);
-    // Add bolt12 if it's supported by any payment processor
-    let bolt12_method = PaymentMethod::Known(KnownMethod::Bolt12);
-    let bolt12_supported =
-        nut04_methods.contains(&&bolt12_method) || nut05_methods.contains(&&bolt12_method);
-
-    if bolt11_supported
-        && !custom_methods.contains(&PaymentMethod::Known(KnownMethod::Bolt11).to_string())
-    {
-        custom_methods.push(PaymentMethod::Known(KnownMethod::Bolt11).to_string());
-    }
-    if bolt12_supported
-        && !custom_methods.contains(&PaymentMethod::Known(KnownMethod::Bolt12).to_string())
-    {
-        custom_methods.push(PaymentMethod::Known(KnownMethod::Bolt12).to_string());
-    }
-
-    tracing::info!("Payment methods: {:?}", custom_methods);
-
-    // Configure auth for custom payment methods if auth is enabled
-    #[cfg(feature = "auth")]
-    if let (Some(ref auth_settings), Some(auth_db)) = (&settings.auth, &auth_localstore) {
-        if auth_settings.auth_enabled {
-            use std::collections::HashMap;
-
-            use cdk::nuts::nut21::{Method, ProtectedEndpoint, RoutePath};
-            use cdk::nuts::AuthRequired;
-
-            use crate::config::AuthType;
-
-            // First, remove all existing payment-method-related endpoints from the database
-            // to ensure old payment methods don't persist when configuration changes
-            let existing_endpoints = auth_db.get_auth_for_endpoints().await?;
-            let payment_method_endpoints_to_remove: Vec<ProtectedEndpoint> = existing_endpoints
-                .keys()
-                .filter(|endpoint| {
-                    matches!(
-                        endpoint.path,
-                        RoutePath::MintQuote(_)
-                            | RoutePath::Mint(_)
-                            | RoutePath::MeltQuote(_)
-                            | RoutePath::Melt(_)
-                    )
-                })
-                .cloned()
-                .collect();
-
-            if !payment_method_endpoints_to_remove.is_empty() {
-                tracing::debug!(
-                    "Removing {} old payment method endpoints from database",
-                    payment_method_endpoints_to_remove.len()
-                );
-                let mut tx = auth_db.begin_transaction().await?;
-                tx.remove_protected_endpoints(payment_method_endpoints_to_remove)
-                    .await?;
-                tx.commit().await?;
-            }
-
-            // Now add endpoints for current payment methods
-            if !custom_methods.is_empty() {
-                let mut protected_endpoints = HashMap::new();
-
-                for method_name in &custom_methods {
-                    tracing::debug!("Adding auth endpoints for payment method: {}", method_name);
-
-                    // Determine auth type based on settings
-                    let mint_quote_auth = match auth_settings.get_mint_quote {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
-
-                    let check_mint_quote_auth = match auth_settings.check_mint_quote {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
-
-                    let mint_auth = match auth_settings.mint {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
-
-                    let melt_quote_auth = match auth_settings.get_melt_quote {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
+    let socket_addr = settings.info.listen_address().map_err(|err| anyhow!(err))?;
+    #[cfg(unix)]
+    let mint = setup.mint.clone();
 
-                    let check_melt_quote_auth = match auth_settings.check_melt_quote {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
-
-                    let melt_auth = match auth_settings.melt {
-                        AuthType::Clear => Some(AuthRequired::Clear),
-                        AuthType::Blind => Some(AuthRequired::Blind),
-                        AuthType::None => None,
-                    };
+    let (mint_service, tasks) = run::start_mint_service(setup, settings, work_dir, routers).await?;
 
-                    // Create endpoints for each payment method operation
-                    if let Some(auth) = mint_quote_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Post,
-                                RoutePath::MintQuote(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                    if let Some(auth) = check_mint_quote_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Get,
-                                RoutePath::MintQuote(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                    if let Some(auth) = mint_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Post,
-                                RoutePath::Mint(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                    if let Some(auth) = melt_quote_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Post,
-                                RoutePath::MeltQuote(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                    if let Some(auth) = check_melt_quote_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Get,
-                                RoutePath::MeltQuote(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                    if let Some(auth) = melt_auth {
-                        protected_endpoints.insert(
-                            ProtectedEndpoint::new(
-                                Method::Post,
-                                RoutePath::Melt(method_name.clone()),
-                            ),
-                            auth,
-                        );
-                    }
-                }
-
-                // Add all custom endpoints in one transaction
-                if !protected_endpoints.is_empty() {
-                    let mut tx = auth_db.begin_transaction().await?;
-                    tx.add_protected_endpoints(protected_endpoints).await?;
-                    tx.commit().await?;
-                }
-            }
-        }
-    }
-
-    let v1_service =
-        cdk_axum::create_mint_router_with_custom_cache(Arc::clone(&mint), cache, custom_methods)
-            .await?;
-
-    let mut mint_service = Router::new()
-        .merge(v1_service)
-        .layer(
-            ServiceBuilder::new()
-                .layer(RequestDecompressionLayer::new())
-                .layer(CompressionLayer::new()),
-        )
-        .layer(TraceLayer::new_for_http());
+    let listener = tokio::net::TcpListener::bind(socket_addr).await?;
 
-    for router in routers {
-        mint_service = mint_service.merge(router);
-    }
+    tracing::info!("listening on {}", listener.local_addr()?);
 
-    #[cfg(feature = "swagger")]
-    {
-        if settings.info.enable_swagger_ui.unwrap_or(false) {
-            mint_service = mint_service.merge(
-                utoipa_swagger_ui::SwaggerUi::new("/swagger-ui")
-                    .url("/api-docs/openapi.json", cdk_axum::ApiDoc::openapi()),
-            );
-        }
-    }
     // Create a broadcast channel to share shutdown signal between services
     let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
 
-    // Start Prometheus server if enabled
-    #[cfg(feature = "prometheus")]
-    let prometheus_handle = {
-        if let Some(prometheus_settings) = &settings.prometheus {
-            if prometheus_settings.enabled {
-                let addr = prometheus_settings
-                    .address
-                    .clone()
-                    .unwrap_or("127.0.0.1".to_string());
-                let port = prometheus_settings.port.unwrap_or(9000);
-
-                let address = format!("{}:{}", addr, port)
-                    .parse()
-                    .expect("Invalid prometheus address");
-
-                let server = cdk_prometheus::PrometheusBuilder::new()
-                    .bind_address(address)
-                    .build_with_cdk_metrics()?;
-
-                let mut shutdown_rx = shutdown_tx.subscribe();
-                let prometheus_shutdown = async move {
-                    let _ = shutdown_rx.recv().await;
-                };
-
-                Some(tokio::spawn(async move {
-                    if let Err(e) = server.start(prometheus_shutdown).await {
-                        tracing::error!("Failed to start prometheus server: {}", e);
-                    }
-                }))
-            } else {
-                None
-            }
-        } else {
-            None
-        }
-    };
-
-    mint.start().await?;
-
-    let socket_addr = SocketAddr::from_str(&format!("{listen_addr}:{listen_port}"))?;
-
-    let listener = tokio::net::TcpListener::bind(socket_addr).await?;
-
-    tracing::info!("listening on {}", listener.local_addr()?);
-
     // Create a task to wait for the shutdown signal and broadcast it
     let shutdown_broadcast_task = {
         let shutdown_tx = shutdown_tx.clone();
@@ -1233,47 +1078,35 @@ async fn start_services_with_shutdown(
         })
     };
 
+    // Reload the settings on SIGHUP until shutdown
+    #[cfg(unix)]
+    let reload_task = {
+        let mut shutdown_rx = shutdown_tx.subscribe();
+        reload::spawn_reload_on_hangup(
+            mint,
+            work_dir.to_path_buf(),
+            settings.clone(),
+            async move {
+                let _ = shutdown_rx.recv().await;
+            },
+        )?
+    };
+
     // Create shutdown future for axum server
     let mut axum_shutdown_rx = shutdown_tx.subscribe();
     let axum_shutdown = async move {
         let _ = axum_shutdown_rx.recv().await;
     };
 
-    // Wait for axum server to complete with custom shutdown signal
-    let axum_result = axum::serve(listener, mint_service).with_graceful_shutdown(axum_shutdown);
-
-    match axum_result.await {
-        Ok(_) => {
-            tracing::info!("Axum server stopped with okay status");
-        }
-        Err(err) => {
-            tracing::warn!("Axum server stopped with error");
-            tracing::error!("{}", err);
-            bail!("Axum exited with error")
-        }
-    }
+    run::serve(mint_service, listener, axum_shutdown).await?;
 
     // Wait for the shutdown broadcast task to complete
     let _ = shutdown_broadcast_task.await;
 
-    // Wait for prometheus server to shutdown if it was started
-    #[cfg(feature = "prometheus")]
-    if let Some(handle) = prometheus_handle {
-        if let Err(e) = handle.await {
-            tracing::warn!("Prometheus server task failed: {}", e);
-        }
-    }
-
-    mint.stop().await?;
-
-    #[cfg(feature = "management-rpc")]
-    {
-        if let Some(rpc_server) = rpc_server {
-            rpc_server.stop().await?;
-        }
-    }
+    #[cfg(unix)]
+    let _ = reload_task.await;
 
-    Ok(())
+    tasks.stop().await
 }
 
 async fn shutdown_signal() {
@@ -1330,15 +1163,74 @@ pub async fn run_mintd(
     result
 }
 
-/// Run mintd with a custom shutdown signal
-pub async fn run_mintd_with_shutdown(
+/// Check the mint database for inconsistencies and print a report
+///
+/// With `fix` the safe repairs are applied. Returns whether the database is consistent
+/// afterwards.
+pub async fn verify_db(
+    work_dir: &Path,
+    settings: &config::Settings,
+    db_password: Option<String>,
+    fix: bool,
+) -> Result<bool> {
+    let (localstore, keystore, _kv) = setup_database(settings, work_dir, db_password).await?;
+
+    let report = verify_database(&localstore, keystore.as_ref(), fix).await?;
+
+    println!(
+        "Checked {} keysets, {} mint quotes and {} melt quotes",
+        report.keysets_checked, report.mint_quotes_checked, report.melt_quotes_checked
+    );
+    for issue in &report.fixed {
+        println!("fixed: {issue}");
+    }
+    for issue in &report.issues {
+        let hint = if issue.is_fixable() {
+            " (run with --fix to repair)"
+        } else {
+            ""
+        };
+        println!("issue: {issue}{hint}");
+    }
+
+    if report.is_ok() {
+        println!("Database is consistent");
+    } else {
+        println!("Found {} issues", report.issues.len());
+    }
+
+    Ok(report.is_ok())
+}
+
+/// Mint built from the settings, before any service is started
+pub struct MintdSetup {
+    pub mint: Arc<cdk::mint::Mint>,
+    /// Mint info of the builder, before the stored mint info is applied
+    pub config_mint_info: cdk::nuts::MintInfo,
+    pub summary: summary::StartupSummary,
+    #[cfg(feature = "auth")]
+    pub auth_localstore: Option<cdk_common::database::DynMintAuthDatabase>,
+}
+
+impl std::fmt::Debug for MintdSetup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MintdSetup")
+            .field("mint", &self.mint)
+            .field("config_mint_info", &self.config_mint_info)
+            .field("summary", &self.summary)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Open the databases, connect the payment backends and build the mint
+///
+/// Does not bind the listener or spawn any background task.
+pub async fn setup_mintd(
     work_dir: &Path,
     settings: &config::Settings,
-    shutdown_signal: impl std::future::Future<Output = ()> + Send + 'static,
     db_password: Option<String>,
     runtime: Option<std::sync::Arc<tokio::runtime::Runtime>>,
-    routers: Vec<Router>,
-) -> Result<()> {
+) -> Result<MintdSetup> {
     let (localstore, keystore, kv) = initial_setup(work_dir, settings, db_password.clone()).await?;
 
     let mint_builder = MintBuilder::new(localstore);
@@ -1376,24 +1268,54 @@ pub async fn run_mintd_with_shutdown(
         setup_authentication(settings, work_dir, mint_builder, db_password).await?;
 
     let config_mint_info = mint_builder.current_mint_info();
+    let summary = summary::StartupSummary::new(work_dir, settings, &config_mint_info)?;
 
     let mint = build_mint(settings, keystore, mint_builder).await?;
 
     tracing::debug!("Mint built from builder.");
 
-    let mint = Arc::new(mint);
-
-    start_services_with_shutdown(
-        mint.clone(),
-        settings,
-        work_dir,
+    Ok(MintdSetup {
+        mint: Arc::new(mint),
         config_mint_info,
-        shutdown_signal,
-        routers,
+        summary,
         #[cfg(feature = "auth")]
         auth_localstore,
-    )
-    .await
+    })
+}
+
+/// Run the whole setup of mintd without starting it
+///
+/// Returns the summary of the settings the mint would start with.
+pub async fn dry_run(
+    work_dir: &Path,
+    settings: &config::Settings,
+    db_password: Option<String>,
+    enable_logging: bool,
+    runtime: Option<std::sync::Arc<tokio::runtime::Runtime>>,
+) -> Result<summary::StartupSummary> {
+    let _guard = if enable_logging {
+        setup_tracing(work_dir, &settings.info.logging)?
+    } else {
+        None
+    };
+
+    let setup = setup_mintd(work_dir, settings, db_password, runtime).await?;
+
+    Ok(setup.summary)
+}
+
+/// Run mintd with a custom shutdown signal
+pub async fn run_mintd_with_shutdown(
+    work_dir: &Path,
+    settings: &config::Settings,
+    shutdown_signal: impl std::future::Future<Output = ()> + Send + 'static,
+    db_password: Option<String>,
+    runtime: Option<std::sync::Arc<tokio::runtime::Runtime>>,
+    routers: Vec<Router>,
+) -> Result<()> {
+    let setup = setup_mintd(work_dir, settings, db_password, runtime).await?;
+
+    start_services_with_shutdown(setup, settings, work_dir, shutdown_signal, routers).await
 }
 
 #[cfg(test)]
@@ -1418,4 +1340,169 @@ mod tests {
         };
         assert!(!auth_config.url.is_empty());
     }
+
+    #[cfg(all(feature = "fakewallet", feature = "sqlite"))]
+    #[tokio::test]
+    async fn test_dry_run_does_not_listen() {
+        let work_dir = env::temp_dir().join("cdk_test_dry_run");
+        let _ = std::fs::remove_dir_all(&work_dir);
+        std::fs::create_dir_all(&work_dir).expect("Failed to create temp dir");
+
+        let port = std::net::TcpListener::bind("127.0.0.1:0")
+            .and_then(|listener| listener.local_addr())
+            .expect("Failed to find a free port")
+            .port();
+
+        let settings = config::Settings {
+            info: config::Info {
+                url: format!("http://127.0.0.1:{port}"),
+                listen_host: "127.0.0.1".to_string(),
+                listen_port: port,
+                mnemonic: Some(
+                    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about"
+                        .to_string(),
+                ),
+                ..Default::default()
+            },
+            ln: config::Ln {
+                ln_backend: LnBackend::FakeWallet,
+                ..Default::default()
+            },
+            fake_wallet: Some(config::FakeWallet::default()),
+            ..Default::default()
+        };
+
+        let summary = dry_run(&work_dir, &settings, None, false, None)
+            .await
+            .expect("Dry run failed");
+
+        assert_eq!(summary.ln_backend, LnBackend::FakeWallet);
+        assert_eq!(summary.listen_address.port(), port);
+        assert!(summary
+            .payment_methods
+            .iter()
+            .any(|method| method.unit == cdk::nuts::CurrencyUnit::Sat
+                && method.method == cdk::nuts::PaymentMethod::Known(KnownMethod::Bolt11)));
+        assert!(work_dir.join("cdk-mintd.sqlite").exists());
+
+        // Nothing was bound to the port
+        std::net::TcpListener::bind(summary.listen_address).expect("Port should be free");
+
+        let _ = std::fs::remove_dir_all(&work_dir);
+    }
+
+    #[cfg(feature = "sqlite")]
+    async fn memory_mint_builder() -> MintBuilder {
+        let localstore = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        MintBuilder::new(localstore)
+    }
+
+    fn setup_error(err: &anyhow::Error) -> &SetupError {
+        err.downcast_ref::<SetupError>()
+            .unwrap_or_else(|| panic!("Not a setup error: {err:?}"))
+    }
+
+    #[cfg(all(feature = "fakewallet", feature = "sqlite"))]
+    #[tokio::test]
+    async fn test_backend_without_settings_fails() {
+        let settings = config::Settings {
+            ln: config::Ln {
+                ln_backend: LnBackend::FakeWallet,
+                ..Default::default()
+            },
+            fake_wallet: None,
+            ..Default::default()
+        };
+
+        let err = configure_lightning_backend(
+            &settings,
+            memory_mint_builder().await,
+            None,
+            &env::temp_dir(),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(
+            setup_error(&err),
+            SetupError::MissingBackendSettings("fakewallet")
+        ));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn test_no_backend_fails() {
+        let settings = config::Settings::default();
+
+        let err = configure_lightning_backend(
+            &settings,
+            memory_mint_builder().await,
+            None,
+            &env::temp_dir(),
+            None,
+        )
+        .await
+        .unwrap_err();
+
+        assert!(matches!(setup_error(&err), SetupError::NoPaymentBackend));
+    }
+
+    #[cfg(all(feature = "fakewallet", feature = "sqlite"))]
+    #[tokio::test]
+    async fn test_build_without_seed_fails() {
+        let settings = config::Settings {
+            ln: config::Ln {
+                ln_backend: LnBackend::FakeWallet,
+                ..Default::default()
+            },
+            fake_wallet: Some(config::FakeWallet::default()),
+            ..Default::default()
+        };
+        let mint_builder = configure_lightning_backend(
+            &settings,
+            memory_mint_builder().await,
+            None,
+            &env::temp_dir(),
+            None,
+        )
+        .await
+        .unwrap();
+        let keystore = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+
+        let err = build_mint(&settings, keystore, mint_builder)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(setup_error(&err), SetupError::NoSeed));
+    }
+
+    #[cfg(feature = "lnd")]
+    #[tokio::test]
+    async fn test_lnd_without_address_fails() {
+        let lnd = config::Lnd {
+            address: String::new(),
+            ..Default::default()
+        };
+
+        let err = lnd
+            .setup(
+                &config::Settings::default(),
+                CurrencyUnit::Msat,
+                None,
+                &env::temp_dir(),
+                None,
+            )
+            .await
+            .err()
+            .unwrap();
+
+        assert!(matches!(
+            setup_error(&err),
+            SetupError::MissingSetting {
+                setting: "address",
+                ..
+            }
+        ));
+    }
 }
@@ -2,11 +2,13 @@
 
 use std::sync::Arc;
 
-use anyhow::Result;
-use cdk_mintd::cli::CLIArgs;
-use cdk_mintd::{get_work_directory, load_settings};
+use anyhow::{anyhow, Result};
+use cdk_mintd::cli::{CLIArgs, Commands};
+use cdk_mintd::run::{build_mint_service, serve};
+use cdk_mintd::{get_work_directory, load_settings, setup_tracing};
 use clap::Parser;
 use tokio::runtime::Runtime;
+use tokio::sync::broadcast;
 
 fn main() -> Result<()> {
     let rt = Arc::new(Runtime::new()?);
@@ -16,7 +18,8 @@ fn main() -> Result<()> {
     rt.block_on(async {
         let args = CLIArgs::parse();
         let work_dir = get_work_directory(&args).await?;
-        let settings = load_settings(&work_dir, args.config)?;
+        let mut settings = load_settings(&work_dir, args.config.clone())?;
+        args.apply_to(&mut settings);
 
         #[cfg(feature = "sqlcipher")]
         let password = Some(CLIArgs::parse().password);
@@ -24,14 +27,73 @@ fn main() -> Result<()> {
         #[cfg(not(feature = "sqlcipher"))]
         let password = None;
 
-        cdk_mintd::run_mintd(
-            &work_dir,
-            &settings,
-            password,
-            args.enable_logging,
-            Some(rt_clone),
-            vec![],
-        )
-        .await
+        if let Some(Commands::VerifyDb { fix }) = args.command {
+            if !cdk_mintd::verify_db(&work_dir, &settings, password, fix).await? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+
+        if args.dry_run {
+            let summary = cdk_mintd::dry_run(
+                &work_dir,
+                &settings,
+                password,
+                args.enable_logging,
+                Some(rt_clone),
+            )
+            .await?;
+            println!("{summary}");
+            return Ok(());
+        }
+
+        let _guard = if args.enable_logging {
+            setup_tracing(&work_dir, &settings.info.logging)?
+        } else {
+            None
+        };
+
+        let (router, mint, tasks) =
+            build_mint_service(&work_dir, &settings, password, Some(rt_clone)).await?;
+
+        let listen_address = settings.info.listen_address().map_err(|err| anyhow!(err))?;
+        let listener = tokio::net::TcpListener::bind(listen_address).await?;
+        tracing::info!("listening on {}", listener.local_addr()?);
+
+        let (shutdown_tx, _) = broadcast::channel::<()>(1);
+
+        // Reload the settings on SIGHUP until shutdown
+        #[cfg(unix)]
+        let reload_task = {
+            let mut shutdown_rx = shutdown_tx.subscribe();
+            cdk_mintd::reload::spawn_reload_on_hangup(
+                mint,
+                work_dir.clone(),
+                settings.clone(),
+                async move {
+                    let _ = shutdown_rx.recv().await;
+                },
+            )?
+        };
+        #[cfg(not(unix))]
+        drop(mint);
+
+        serve(router, listener, async move {
+            tokio::signal::ctrl_c()
+                .await
+                .expect("failed to install CTRL+C handler");
+            tracing::info!("Shutdown signal received");
+            let _ = shutdown_tx.send(());
+        })
+        .await?;
+
+        #[cfg(unix)]
+        let _ = reload_task.await;
+
+        tasks.stop().await?;
+
+        tracing::info!("Mintd shutdown");
+
+        Ok(())
     })
 }
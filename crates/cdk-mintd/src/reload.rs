@@ -0,0 +1,537 @@
+//! Reload of the settings at runtime
+//!
+//! On `SIGHUP` the config file and the environment are read again and the settings that can
+//! change while the mint is running are applied to it:
+//!
+//! - the `[mint_info]` fields and the quote TTLs, unless they are managed over the management RPC
+//! - the mint and melt limits of `[ln]` and of the payment backend
+//! - the client ban settings
+//! - the console and file log levels
+//!
+//! Every applied change is logged with its old and new value. Any other change, such as the
+//! database, the payment backend or the listen address, only takes effect after a restart and is
+//! logged as such.
+
+use std::path::Path;
+
+use anyhow::Result;
+use cdk::mint::Mint;
+use cdk::nuts::{ContactInfo, CurrencyUnit, PaymentMethod};
+use serde_json::Value;
+
+use crate::config::{self, Settings};
+use crate::{ban_policy, load_settings, set_log_levels};
+
+/// Settings that are applied by a reload, by their path in the config
+const RELOADABLE: &[&str] = &[
+    "info.logging.console_level",
+    "info.logging.file_level",
+    "info.ban_threshold",
+    "info.ban_window_secs",
+    "info.ban_duration_secs",
+    "info.quote_ttl",
+    "mint_info",
+    "ln.min_mint",
+    "ln.max_mint",
+    "ln.min_melt",
+    "ln.max_melt",
+    "cln.limits",
+    "lnbits.limits",
+    "lnd.limits",
+    "ldk_node.limits",
+    "fake_wallet.limits",
+    "grpc_processor.limits",
+];
+
+/// Outcome of a reload
+#[derive(Debug, Default)]
+pub struct ReloadReport {
+    /// Changes applied to the running mint, as `path: old -> new`
+    pub applied: Vec<String>,
+    /// Paths of the changed settings that need a restart
+    pub restart_required: Vec<String>,
+}
+
+impl ReloadReport {
+    fn apply(&mut self, path: &str, old: impl std::fmt::Debug, new: impl std::fmt::Debug) {
+        let change = format!("{path}: {old:?} -> {new:?}");
+        tracing::info!("Reloaded {}", change);
+        self.applied.push(change);
+    }
+}
+
+/// Read the config file and the environment again and apply the changes to `mint`
+///
+/// `current` holds the settings the mint runs with and is updated with the applied changes.
+/// This is what the `SIGHUP` handler calls.
+pub async fn reload_from_disk(
+    mint: &Mint,
+    work_dir: &Path,
+    current: &mut Settings,
+) -> Result<ReloadReport> {
+    let mut settings = load_settings(work_dir, current.config_path.clone())?;
+    current.overrides.apply_to(&mut settings);
+
+    reload(mint, current, settings).await
+}
+
+/// Reload the settings of `mint` on every `SIGHUP` until `shutdown` resolves
+#[cfg(unix)]
+pub fn spawn_reload_on_hangup(
+    mint: std::sync::Arc<Mint>,
+    work_dir: std::path::PathBuf,
+    mut settings: Settings,
+    shutdown: impl std::future::Future<Output = ()> + Send + 'static,
+) -> Result<tokio::task::JoinHandle<()>> {
+    let mut hangup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())?;
+
+    Ok(tokio::spawn(async move {
+        tokio::pin!(shutdown);
+        loop {
+            tokio::select! {
+                _ = hangup.recv() => {
+                    tracing::info!("SIGHUP received, reloading settings");
+                    if let Err(err) = reload_from_disk(&mint, &work_dir, &mut settings).await {
+                        tracing::error!("Could not reload settings: {}", err);
+                    }
+                }
+                _ = &mut shutdown => break,
+            }
+        }
+    }))
+}
+
+/// Apply the reloadable changes from `current` to `new` to `mint`
+async fn reload(mint: &Mint, current: &mut Settings, new: Settings) -> Result<ReloadReport> {
+    let mut report = ReloadReport::default();
+
+    reload_logging(&mut report, current, &new);
+    reload_ban_policy(mint, &mut report, current, &new);
+
+    let mut mint_info = mint.mint_info().await?;
+    let old_mint_info = mint_info.clone();
+
+    if rpc_managed(current) {
+        // The config is only used for the initial set up, see `MintManagementRpc::enabled`
+        if current.mint_info != new.mint_info || current.info.quote_ttl != new.info.quote_ttl {
+            tracing::warn!(
+                "Ignoring changes to the mint info and quote TTLs, they are managed over the management RPC"
+            );
+        }
+    } else {
+        reload_mint_info(
+            &mut mint_info,
+            &mut report,
+            &current.mint_info,
+            &new.mint_info,
+        );
+        current.mint_info = new.mint_info.clone();
+
+        if current.info.quote_ttl != new.info.quote_ttl {
+            let quote_ttl = new.info.quote_ttl.unwrap_or_default();
+            mint.set_quote_ttl(quote_ttl).await?;
+            report.apply("info.quote_ttl", current.info.quote_ttl, new.info.quote_ttl);
+            current.info.quote_ttl = new.info.quote_ttl;
+        }
+    }
+
+    reload_limits(&mut mint_info, &mut report, current, &new);
+
+    if mint_info != old_mint_info {
+        mint.set_mint_info(mint_info).await?;
+    }
+
+    report.restart_required = changed_paths(current, &new)?
+        .into_iter()
+        .filter(|path| {
+            !RELOADABLE
+                .iter()
+                .any(|reloadable| is_within(path, reloadable))
+        })
+        .collect();
+
+    for path in &report.restart_required {
+        tracing::warn!("Change to {} requires a restart", path);
+    }
+
+    Ok(report)
+}
+
+/// Whether the mint info and quote TTLs are managed over the management RPC
+fn rpc_managed(_settings: &Settings) -> bool {
+    #[cfg(feature = "management-rpc")]
+    {
+        _settings
+            .mint_management_rpc
+            .as_ref()
+            .is_some_and(|rpc| rpc.enabled)
+    }
+    #[cfg(not(feature = "management-rpc"))]
+    {
+        false
+    }
+}
+
+fn reload_logging(report: &mut ReloadReport, current: &mut Settings, new: &Settings) {
+    let old = &current.info.logging;
+    let logging = &new.info.logging;
+
+    if old.console_level == logging.console_level && old.file_level == logging.file_level {
+        return;
+    }
+
+    set_log_levels(logging);
+
+    if old.console_level != logging.console_level {
+        report.apply(
+            "info.logging.console_level",
+            &old.console_level,
+            &logging.console_level,
+        );
+    }
+    if old.file_level != logging.file_level {
+        report.apply(
+            "info.logging.file_level",
+            &old.file_level,
+            &logging.file_level,
+        );
+    }
+
+    current.info.logging.console_level = logging.console_level.clone();
+    current.info.logging.file_level = logging.file_level.clone();
+}
+
+fn reload_ban_policy(
+    mint: &Mint,
+    report: &mut ReloadReport,
+    current: &mut Settings,
+    new: &Settings,
+) {
+    let policy = ban_policy(&new.info);
+    if ban_policy(&current.info) == policy {
+        return;
+    }
+
+    mint.set_ban_policy(policy);
+
+    let (old, new) = (&mut current.info, &new.info);
+    if old.ban_threshold != new.ban_threshold {
+        report.apply("info.ban_threshold", old.ban_threshold, new.ban_threshold);
+    }
+    if old.ban_window_secs != new.ban_window_secs {
+        report.apply(
+            "info.ban_window_secs",
+            old.ban_window_secs,
+            new.ban_window_secs,
+        );
+    }
+    if old.ban_duration_secs != new.ban_duration_secs {
+        report.apply(
+            "info.ban_duration_secs",
+            old.ban_duration_secs,
+            new.ban_duration_secs,
+        );
+    }
+
+    old.ban_threshold = new.ban_threshold;
+    old.ban_window_secs = new.ban_window_secs;
+    old.ban_duration_secs = new.ban_duration_secs;
+}
+
+/// Apply the changed `[mint_info]` fields, fields changed over RPC are kept otherwise
+fn reload_mint_info(
+    mint_info: &mut cdk::nuts::MintInfo,
+    report: &mut ReloadReport,
+    old: &config::MintInfo,
+    new: &config::MintInfo,
+) {
+    let non_empty = |value: &Option<String>| value.clone().filter(|value| !value.is_empty());
+
+    if old.name != new.name {
+        mint_info.name = non_empty(&Some(new.name.clone()));
+        report.apply("mint_info.name", &old.name, &new.name);
+    }
+    if old.pubkey != new.pubkey {
+        // The pubkey stays the one of the mint if it is removed from the config
+        if let Some(pubkey) = new.pubkey {
+            mint_info.pubkey = Some(pubkey);
+        }
+        report.apply("mint_info.pubkey", old.pubkey, new.pubkey);
+    }
+    if old.description != new.description {
+        mint_info.description = non_empty(&Some(new.description.clone()));
+        report.apply("mint_info.description", &old.description, &new.description);
+    }
+    if old.description_long != new.description_long {
+        mint_info.description_long = non_empty(&new.description_long);
+        report.apply(
+            "mint_info.description_long",
+            &old.description_long,
+            &new.description_long,
+        );
+    }
+    if old.icon_url != new.icon_url {
+        mint_info.icon_url = non_empty(&new.icon_url);
+        report.apply("mint_info.icon_url", &old.icon_url, &new.icon_url);
+    }
+    if old.motd != new.motd {
+        mint_info.motd = non_empty(&new.motd);
+        report.apply("mint_info.motd", &old.motd, &new.motd);
+    }
+    if old.tos_url != new.tos_url {
+        mint_info.tos_url = non_empty(&new.tos_url);
+        report.apply("mint_info.tos_url", &old.tos_url, &new.tos_url);
+    }
+    if old.urls != new.urls {
+        mint_info.urls = Some(new.urls.clone()).filter(|urls| !urls.is_empty());
+        report.apply("mint_info.urls", &old.urls, &new.urls);
+    }
+
+    for (method, path, old, new) in [
+        (
+            "nostr",
+            "mint_info.contact_nostr_public_key",
+            &old.contact_nostr_public_key,
+            &new.contact_nostr_public_key,
+        ),
+        (
+            "email",
+            "mint_info.contact_email",
+            &old.contact_email,
+            &new.contact_email,
+        ),
+    ] {
+        if old == new {
+            continue;
+        }
+
+        let mut contacts = mint_info.contact.take().unwrap_or_default();
+        contacts.retain(|contact| contact.method != method);
+        if let Some(info) = non_empty(new) {
+            contacts.push(ContactInfo::new(method.to_string(), info));
+        }
+        mint_info.contact = Some(contacts).filter(|contacts| !contacts.is_empty());
+
+        report.apply(path, old, new);
+    }
+}
+
+/// Apply the changed mint and melt limits, limits changed over RPC are kept otherwise
+fn reload_limits(
+    mint_info: &mut cdk::nuts::MintInfo,
+    report: &mut ReloadReport,
+    current: &mut Settings,
+    new: &Settings,
+) {
+    let old_limits = current.backend_limits().cloned().unwrap_or_default();
+    let new_limits = new.backend_limits().cloned().unwrap_or_default();
+
+    if current.ln.limits() == new.ln.limits() && old_limits == new_limits {
+        return;
+    }
+
+    let (old_base, new_base) = (current.ln.limits(), new.ln.limits());
+    let changed = |unit: &CurrencyUnit, method: &PaymentMethod| {
+        let old = old_limits.resolve(old_base, unit, method);
+        let new = new_limits.resolve(new_base, unit, method);
+        (old != new).then_some((old, new))
+    };
+
+    for settings in mint_info.nuts.nut04.methods.iter_mut() {
+        if let Some((old, new)) = changed(&settings.unit, &settings.method) {
+            settings.min_amount = Some(new.mint_min);
+            settings.max_amount = Some(new.mint_max);
+            report.apply(
+                &format!("mint limits {} {}", settings.unit, settings.method),
+                (old.mint_min, old.mint_max),
+                (new.mint_min, new.mint_max),
+            );
+        }
+    }
+
+    for settings in mint_info.nuts.nut05.methods.iter_mut() {
+        if let Some((old, new)) = changed(&settings.unit, &settings.method) {
+            settings.min_amount = Some(new.melt_min);
+            settings.max_amount = Some(new.melt_max);
+            report.apply(
+                &format!("melt limits {} {}", settings.unit, settings.method),
+                (old.melt_min, old.melt_max),
+                (new.melt_min, new.melt_max),
+            );
+        }
+    }
+
+    current.ln.min_mint = new.ln.min_mint;
+    current.ln.max_mint = new.ln.max_mint;
+    current.ln.min_melt = new.ln.min_melt;
+    current.ln.max_melt = new.ln.max_melt;
+    if let Some(limits) = current.backend_limits_mut() {
+        *limits = new_limits;
+    }
+}
+
+/// Whether `path` is `prefix` or one of its fields
+fn is_within(path: &str, prefix: &str) -> bool {
+    path.strip_prefix(prefix)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with('.'))
+}
+
+/// Paths of the settings that differ between `old` and `new`
+///
+/// Only the paths are returned, the values may be secrets.
+fn changed_paths(old: &Settings, new: &Settings) -> Result<Vec<String>> {
+    fn diff(path: String, old: &Value, new: &Value, paths: &mut Vec<String>) {
+        match (old, new) {
+            (Value::Object(old), Value::Object(new)) => {
+                let mut keys: Vec<_> = old.keys().chain(new.keys()).collect();
+                keys.sort();
+                keys.dedup();
+
+                for key in keys {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    diff(
+                        path,
+                        old.get(key).unwrap_or(&Value::Null),
+                        new.get(key).unwrap_or(&Value::Null),
+                        paths,
+                    );
+                }
+            }
+            (old, new) if old != new => paths.push(path),
+            _ => {}
+        }
+    }
+
+    let mut paths = Vec::new();
+    diff(
+        String::new(),
+        &serde_json::to_value(old)?,
+        &serde_json::to_value(new)?,
+        &mut paths,
+    );
+
+    Ok(paths)
+}
+
+#[cfg(all(test, feature = "fakewallet", feature = "sqlite"))]
+mod tests {
+    use std::{env, fs};
+
+    use cdk::mint::MintBuilder;
+    use cdk::nuts::nut00::KnownMethod;
+    use cdk::Amount;
+
+    use super::*;
+    use crate::{build_mint, configure_mint_builder, initial_setup};
+
+    const MNEMONIC: &str =
+        "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+    fn config(motd: &str, max_mint: u64, mint_ttl: u64, ban_threshold: usize, port: u16) -> String {
+        format!(
+            r#"
+[info]
+url = "http://127.0.0.1:{port}"
+listen_host = "127.0.0.1"
+listen_port = {port}
+mnemonic = "{MNEMONIC}"
+ban_threshold = {ban_threshold}
+
+[info.quote_ttl]
+mint_ttl = {mint_ttl}
+melt_ttl = 600
+
+[mint_info]
+name = "reload test"
+description = "mint reloaded on SIGHUP"
+motd = "{motd}"
+
+[ln]
+ln_backend = "fakewallet"
+min_mint = 1
+max_mint = {max_mint}
+min_melt = 1
+max_melt = 500000
+
+[fake_wallet]
+supported_units = ["sat"]
+fee_percent = 0.0
+reserve_fee_min = 0
+
+[database]
+engine = "sqlite"
+"#
+        )
+    }
+
+    #[tokio::test]
+    async fn reload_applies_changed_settings() {
+        let work_dir = env::temp_dir().join("cdk_test_reload");
+        let _ = fs::remove_dir_all(&work_dir);
+        fs::create_dir_all(&work_dir).expect("Failed to create temp dir");
+        let config_path = work_dir.join("config.toml");
+
+        fs::write(&config_path, config("hello", 500_000, 300, 20, 8085))
+            .expect("Failed to write config file");
+        let mut settings = load_settings(&work_dir, None).expect("settings");
+
+        let (localstore, keystore, kv) = initial_setup(&work_dir, &settings, None)
+            .await
+            .expect("database");
+        let mint_builder = configure_mint_builder(
+            &settings,
+            MintBuilder::new(localstore),
+            None,
+            &work_dir,
+            Some(kv),
+        )
+        .await
+        .expect("mint builder");
+        let mint_info = mint_builder.current_mint_info();
+        let mint = build_mint(&settings, keystore, mint_builder)
+            .await
+            .expect("mint");
+        mint.set_mint_info(mint_info).await.expect("mint info");
+
+        fs::write(&config_path, config("good bye", 100_000, 120, 5, 9095))
+            .expect("Failed to write config file");
+        let report = reload_from_disk(&mint, &work_dir, &mut settings)
+            .await
+            .expect("reload");
+
+        let mint_info = mint.mint_info().await.expect("mint info");
+        assert_eq!(mint_info.motd.as_deref(), Some("good bye"));
+
+        let bolt11 = PaymentMethod::Known(KnownMethod::Bolt11);
+        let nut04 = mint_info
+            .nuts
+            .nut04
+            .get_settings(&CurrencyUnit::Sat, &bolt11)
+            .expect("bolt11 mint settings");
+        assert_eq!(nut04.max_amount, Some(Amount::from(100_000)));
+
+        assert_eq!(mint.quote_ttl().await.expect("quote ttl").mint_ttl, 120);
+        assert_eq!(settings.info.ban_threshold, Some(5));
+
+        assert!(report
+            .applied
+            .iter()
+            .any(|change| change.starts_with("mint_info.motd")));
+        assert!(report
+            .restart_required
+            .contains(&"info.listen_port".to_string()));
+        assert!(report.restart_required.contains(&"info.url".to_string()));
+
+        // Nothing changed since the last reload
+        let report = reload_from_disk(&mint, &work_dir, &mut settings)
+            .await
+            .expect("reload");
+        assert!(report.applied.is_empty());
+
+        let _ = fs::remove_dir_all(&work_dir);
+    }
+}
@@ -0,0 +1,514 @@
+//! Build the mint service for embedding in another binary
+//!
+//! [`build_mint_service`] does everything `cdk-mintd` does on startup, short of binding the
+//! listener: it builds the mint from the settings, starts it along with the management RPC and
+//! prometheus servers if enabled, and returns the router to serve. It does not install a tracing
+//! subscriber or any signal handler, that is left to the binary embedding it.
+
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use axum::Router;
+use cdk::mint::Mint;
+use cdk::nuts::nut00::KnownMethod;
+use cdk::nuts::{MintVersion, PaymentMethod};
+use cdk_axum::cache::HttpCache;
+use cdk_axum::client_ip::{client_ip_middleware, TrustedProxies};
+use cdk_common::common::QuoteTTL;
+use tokio::net::TcpListener;
+use tokio::sync::broadcast;
+use tower::ServiceBuilder;
+use tower_http::compression::CompressionLayer;
+use tower_http::decompression::RequestDecompressionLayer;
+use tower_http::trace::TraceLayer;
+#[cfg(feature = "swagger")]
+use utoipa::OpenApi;
+
+use crate::config::Settings;
+use crate::webhook::spawn_stuck_quote_webhook;
+use crate::{setup_mintd, MintdSetup, CARGO_PKG_VERSION};
+
+/// Services started along with the mint
+///
+/// They keep running until [`BackgroundTasks::stop`] is called.
+pub struct BackgroundTasks {
+    mint: Arc<Mint>,
+    shutdown_tx: broadcast::Sender<()>,
+    #[cfg(feature = "prometheus")]
+    prometheus_handle: Option<tokio::task::JoinHandle<()>>,
+    webhook_handle: Option<tokio::task::JoinHandle<()>>,
+    #[cfg(feature = "management-rpc")]
+    rpc_server: Option<cdk_mint_rpc::MintRPCServer>,
+}
+
+impl std::fmt::Debug for BackgroundTasks {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BackgroundTasks").finish_non_exhaustive()
+    }
+}
+
+impl BackgroundTasks {
+    /// Stop the mint and the servers started with it
+    pub async fn stop(self) -> Result<()> {
+        let _ = self.shutdown_tx.send(());
+
+        // Wait for prometheus server to shutdown if it was started
+        #[cfg(feature = "prometheus")]
+        if let Some(handle) = self.prometheus_handle {
+            if let Err(e) = handle.await {
+                tracing::warn!("Prometheus server task failed: {}", e);
+            }
+        }
+
+        if let Some(handle) = self.webhook_handle {
+            if let Err(e) = handle.await {
+                tracing::warn!("Stuck quote webhook task failed: {}", e);
+            }
+        }
+
+        self.mint.stop().await?;
+
+        #[cfg(feature = "management-rpc")]
+        {
+            if let Some(rpc_server) = self.rpc_server {
+                rpc_server.stop().await?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Build and start the mint described by `settings`
+///
+/// Returns the router of the mint API, the mint and its background tasks. The router is
+/// served with [`serve`] or merged into a router of the embedding binary.
+pub async fn build_mint_service(
+    work_dir: &Path,
+    settings: &Settings,
+    db_password: Option<String>,
+    runtime: Option<Arc<tokio::runtime::Runtime>>,
+) -> Result<(Router, Arc<Mint>, BackgroundTasks)> {
+    let setup = setup_mintd(work_dir, settings, db_password, runtime).await?;
+    let mint = setup.mint.clone();
+
+    let (router, tasks) = start_mint_service(setup, settings, work_dir, vec![]).await?;
+
+    Ok((router, mint, tasks))
+}
+
+/// Serve `router` on `listener` until `shutdown` resolves
+pub async fn serve(
+    router: Router,
+    listener: TcpListener,
+    shutdown: impl Future<Output = ()> + Send + 'static,
+) -> Result<()> {
+    let result = axum::serve(
+        listener,
+        router.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown)
+    .await;
+
+    match result {
+        Ok(_) => {
+            tracing::info!("Axum server stopped with okay status");
+            Ok(())
+        }
+        Err(err) => {
+            tracing::warn!("Axum server stopped with error");
+            tracing::error!("{}", err);
+            bail!("Axum exited with error")
+        }
+    }
+}
+
+/// Start the mint built by [`setup_mintd`] and build its router, with `routers` merged in
+pub(crate) async fn start_mint_service(
+    setup: MintdSetup,
+    settings: &Settings,
+    work_dir: &Path,
+    routers: Vec<Router>,
+) -> Result<(Router, BackgroundTasks)> {
+    let MintdSetup {
+        mint,
+        config_mint_info: mint_builder_info,
+        summary,
+        #[cfg(feature = "auth")]
+        auth_localstore,
+    } = setup;
+
+    for line in summary.to_string().lines() {
+        tracing::info!("{}", line);
+    }
+
+    let cache: HttpCache = settings.info.http_cache.clone().into();
+    // The router and the mint share the cache, so it can be managed over RPC
+    mint.set_response_cache(Arc::new(cache.clone()));
+
+    #[cfg(feature = "management-rpc")]
+    let mut rpc_enabled = false;
+    #[cfg(not(feature = "management-rpc"))]
+    let rpc_enabled = false;
+
+    #[cfg(feature = "management-rpc")]
+    let mut rpc_server: Option<cdk_mint_rpc::MintRPCServer> = None;
+
+    #[cfg(feature = "management-rpc")]
+    {
+        if let Some(rpc_settings) = settings.mint_management_rpc.clone() {
+            if rpc_settings.enabled {
+                let addr = rpc_settings.address.unwrap_or("127.0.0.1".to_string());
+                let port = rpc_settings.port.unwrap_or(8086);
+                let mut mint_rpc = cdk_mint_rpc::MintRPCServer::new(&addr, port, mint.clone())?
+                    .with_auth_token(rpc_settings.auth_token);
+
+                let tls_dir = rpc_settings.tls_dir_path.unwrap_or(work_dir.join("tls"));
+
+                let tls_dir = if tls_dir.exists() {
+                    Some(tls_dir)
+                } else {
+                    tracing::warn!(
+                        "TLS directory does not exist: {}. Starting RPC server in INSECURE mode without TLS encryption",
+                        tls_dir.display()
+                    );
+                    None
+                };
+
+                mint_rpc.start(tls_dir).await?;
+
+                rpc_server = Some(mint_rpc);
+
+                rpc_enabled = true;
+            }
+        }
+    }
+
+    // Determine the desired QuoteTTL from config/env or fall back to defaults
+    let desired_quote_ttl: QuoteTTL = settings.info.quote_ttl.unwrap_or_default();
+
+    if rpc_enabled {
+        if mint.mint_info().await.is_err() {
+            tracing::info!("Mint info not set on mint, setting.");
+            // First boot with RPC enabled: seed from config
+            mint.set_mint_info(mint_builder_info).await?;
+            mint.set_quote_ttl(desired_quote_ttl).await?;
+        } else {
+            // If QuoteTTL has never been persisted, seed it now from config
+            if !mint.quote_ttl_is_persisted().await? {
+                mint.set_quote_ttl(desired_quote_ttl).await?;
+            }
+            // Add/refresh version information without altering stored mint_info fields
+            let mint_version = MintVersion::new(
+                "cdk-mintd".to_string(),
+                CARGO_PKG_VERSION.unwrap_or("Unknown").to_string(),
+            );
+            let mut stored_mint_info = mint.mint_info().await?;
+            stored_mint_info.version = Some(mint_version);
+            mint.set_mint_info(stored_mint_info).await?;
+
+            tracing::info!("Mint info already set, not using config file settings.");
+        }
+    } else {
+        // RPC disabled: config is source of truth on every boot
+        tracing::info!("RPC not enabled, using mint info and quote TTL from config.");
+        let mut mint_builder_info = mint_builder_info;
+
+        if let Ok(mint_info) = mint.mint_info().await {
+            if mint_builder_info.pubkey.is_none() {
+                mint_builder_info.pubkey = mint_info.pubkey;
+            }
+            mint_builder_info.previous_pubkeys = mint_info.previous_pubkeys;
+        }
+
+        mint.set_mint_info(mint_builder_info).await?;
+        mint.set_quote_ttl(desired_quote_ttl).await?;
+    }
+
+    let mint_info = mint.mint_info().await?;
+    let nut04_methods = mint_info.nuts.nut04.supported_methods();
+    let nut05_methods = mint_info.nuts.nut05.supported_methods();
+
+    // Get custom payment methods from payment processors
+    let mut custom_methods = mint.get_custom_payment_methods().await?;
+
+    // Add bolt11 if it's supported by any payment processor
+    let bolt11_method = PaymentMethod::Known(KnownMethod::Bolt11);
+    let bolt11_supported =
+        nut04_methods.contains(&&bolt11_method) || nut05_methods.contains(&&bolt11_method);
+    // Add bolt12 if it's supported by any payment processor
+    let bolt12_method = PaymentMethod::Known(KnownMethod::Bolt12);
+    let bolt12_supported =
+        nut04_methods.contains(&&bolt12_method) || nut05_methods.contains(&&bolt12_method);
+
+    if bolt11_supported
+        && !custom_methods.contains(&PaymentMethod::Known(KnownMethod::Bolt11).to_string())
+    {
+        custom_methods.push(PaymentMethod::Known(KnownMethod::Bolt11).to_string());
+    }
+    if bolt12_supported
+        && !custom_methods.contains(&PaymentMethod::Known(KnownMethod::Bolt12).to_string())
+    {
+        custom_methods.push(PaymentMethod::Known(KnownMethod::Bolt12).to_string());
+    }
+
+    tracing::info!("Payment methods: {:?}", custom_methods);
+
+    // Configure auth for custom payment methods if auth is enabled
+    #[cfg(feature = "auth")]
+    if let (Some(ref auth_settings), Some(auth_db)) = (&settings.auth, &auth_localstore) {
+        if auth_settings.auth_enabled {
+            use std::collections::HashMap;
+
+            use cdk::nuts::nut21::{Method, ProtectedEndpoint, RoutePath};
+            use cdk::nuts::AuthRequired;
+
+            use crate::config::AuthType;
+
+            // First, remove all existing payment-method-related endpoints from the database
+            // to ensure old payment methods don't persist when configuration changes
+            let existing_endpoints = auth_db.get_auth_for_endpoints().await?;
+            let payment_method_endpoints_to_remove: Vec<ProtectedEndpoint> = existing_endpoints
+                .keys()
+                .filter(|endpoint| {
+                    matches!(
+                        endpoint.path,
+                        RoutePath::MintQuote(_)
+                            | RoutePath::Mint(_)
+                            | RoutePath::MeltQuote(_)
+                            | RoutePath::Melt(_)
+                    )
+                })
+                .cloned()
+                .collect();
+
+            if !payment_method_endpoints_to_remove.is_empty() {
+                tracing::debug!(
+                    "Removing {} old payment method endpoints from database",
+                    payment_method_endpoints_to_remove.len()
+                );
+                let mut tx = auth_db.begin_transaction().await?;
+                tx.remove_protected_endpoints(payment_method_endpoints_to_remove)
+                    .await?;
+                tx.commit().await?;
+            }
+
+            // Now add endpoints for current payment methods
+            if !custom_methods.is_empty() {
+                let mut protected_endpoints = HashMap::new();
+
+                for method_name in &custom_methods {
+                    tracing::debug!("Adding auth endpoints for payment method: {}", method_name);
+
+                    // Determine auth type based on settings
+                    let mint_quote_auth = match auth_settings.get_mint_quote {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    let check_mint_quote_auth = match auth_settings.check_mint_quote {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    let mint_auth = match auth_settings.mint {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    let melt_quote_auth = match auth_settings.get_melt_quote {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    let check_melt_quote_auth = match auth_settings.check_melt_quote {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    let melt_auth = match auth_settings.melt {
+                        AuthType::Clear => Some(AuthRequired::Clear),
+                        AuthType::Blind => Some(AuthRequired::Blind),
+                        AuthType::None => None,
+                    };
+
+                    // Create endpoints for each payment method operation
+                    if let Some(auth) = mint_quote_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Post,
+                                RoutePath::MintQuote(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                    if let Some(auth) = check_mint_quote_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Get,
+                                RoutePath::MintQuote(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                    if let Some(auth) = mint_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Post,
+                                RoutePath::Mint(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                    if let Some(auth) = melt_quote_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Post,
+                                RoutePath::MeltQuote(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                    if let Some(auth) = check_melt_quote_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Get,
+                                RoutePath::MeltQuote(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                    if let Some(auth) = melt_auth {
+                        protected_endpoints.insert(
+                            ProtectedEndpoint::new(
+                                Method::Post,
+                                RoutePath::Melt(method_name.clone()),
+                            ),
+                            auth,
+                        );
+                    }
+                }
+
+                // Add all custom endpoints in one transaction
+                if !protected_endpoints.is_empty() {
+                    let mut tx = auth_db.begin_transaction().await?;
+                    tx.add_protected_endpoints(protected_endpoints).await?;
+                    tx.commit().await?;
+                }
+            }
+        }
+    }
+
+    let v1_service = cdk_axum::create_mint_router_with_limits(
+        Arc::clone(&mint),
+        cache,
+        custom_methods,
+        settings.info.http_limits,
+    )
+    .await?;
+
+    let mut mint_service = Router::new()
+        .merge(v1_service)
+        .layer(
+            ServiceBuilder::new()
+                .layer(RequestDecompressionLayer::new())
+                .layer(CompressionLayer::new()),
+        )
+        .layer(TraceLayer::new_for_http());
+
+    for router in routers {
+        mint_service = mint_service.merge(router);
+    }
+
+    #[cfg(feature = "swagger")]
+    {
+        if settings.info.enable_swagger_ui.unwrap_or(false) {
+            mint_service = mint_service.merge(
+                utoipa_swagger_ui::SwaggerUi::new("/swagger-ui")
+                    .url("/api-docs/openapi.json", cdk_axum::ApiDoc::openapi()),
+            );
+        } else if settings.info.enable_openapi_json.unwrap_or(false) {
+            // The Swagger UI serves the document itself, so only add it without the UI
+            mint_service = mint_service.route(
+                "/api-docs/openapi.json",
+                axum::routing::get(|| async { axum::Json(cdk_axum::ApiDoc::openapi()) }),
+            );
+        }
+    }
+
+    let trusted_proxies =
+        TrustedProxies::new(&settings.info.trusted_proxies).map_err(|err| anyhow!(err))?;
+    let mint_service = mint_service.layer(axum::middleware::from_fn_with_state(
+        trusted_proxies,
+        client_ip_middleware,
+    ));
+
+    // Shared by the background tasks that run until stopped
+    let (shutdown_tx, _) = tokio::sync::broadcast::channel::<()>(1);
+
+    // Start Prometheus server if enabled
+    #[cfg(feature = "prometheus")]
+    let prometheus_handle = {
+        if let Some(prometheus_settings) = &settings.prometheus {
+            if prometheus_settings.enabled {
+                let addr = prometheus_settings
+                    .address
+                    .clone()
+                    .unwrap_or("127.0.0.1".to_string());
+                let port = prometheus_settings.port.unwrap_or(9000);
+
+                let address = format!("{}:{}", addr, port)
+                    .parse()
+                    .map_err(|err| anyhow!("Invalid prometheus address {addr}:{port}: {err}"))?;
+
+                let server = cdk_prometheus::PrometheusBuilder::new()
+                    .bind_address(address)
+                    .build_with_cdk_metrics()?;
+
+                let mut shutdown_rx = shutdown_tx.subscribe();
+                let prometheus_shutdown = async move {
+                    let _ = shutdown_rx.recv().await;
+                };
+
+                Some(tokio::spawn(async move {
+                    if let Err(e) = server.start(prometheus_shutdown).await {
+                        tracing::error!("Failed to start prometheus server: {}", e);
+                    }
+                }))
+            } else {
+                None
+            }
+        } else {
+            None
+        }
+    };
+
+    let webhook_handle = settings
+        .info
+        .pending_alert_webhook
+        .clone()
+        .map(|url| spawn_stuck_quote_webhook(&mint, url, shutdown_tx.subscribe()));
+
+    mint.start().await?;
+
+    Ok((
+        mint_service,
+        BackgroundTasks {
+            mint,
+            shutdown_tx,
+            #[cfg(feature = "prometheus")]
+            prometheus_handle,
+            webhook_handle,
+            #[cfg(feature = "management-rpc")]
+            rpc_server,
+        },
+    ))
+}
@@ -4,11 +4,16 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::Path;
 use std::sync::Arc;
+#[cfg(any(
+    feature = "lnbits",
+    feature = "cln",
+    feature = "lnd",
+    feature = "fakewallet"
+))]
+use std::time::Duration;
 
 #[cfg(feature = "cln")]
 use anyhow::anyhow;
-#[cfg(any(feature = "lnbits", feature = "lnd"))]
-use anyhow::bail;
 use async_trait::async_trait;
 #[cfg(feature = "fakewallet")]
 use bip39::rand::{thread_rng, Rng};
@@ -28,6 +33,46 @@ use crate::config::{self, Settings};
 #[cfg(feature = "cln")]
 use crate::expand_path;
 
+/// Misconfiguration found while setting up the mint
+///
+/// Returned inside the [`anyhow::Error`] of the setup functions, callers can downcast to it.
+#[derive(Debug, thiserror::Error)]
+pub enum SetupError {
+    /// The settings section of the selected payment backend is missing
+    #[error("The {0} payment backend is selected but its settings are missing")]
+    MissingBackendSettings(&'static str),
+    /// A required setting of a payment backend is empty
+    #[error("{backend} {setting} must be set via config or {env} env var")]
+    MissingSetting {
+        /// Payment backend
+        backend: &'static str,
+        /// Name of the setting
+        setting: &'static str,
+        /// Environment variable setting it
+        env: &'static str,
+    },
+    /// The payment backend persists its state but no kv store was given
+    #[error("The {0} payment backend needs a kv store")]
+    MissingKvStore(&'static str),
+    /// No payment backend is selected, or its feature is not compiled in
+    #[error("Lightning backend must be configured")]
+    NoPaymentBackend,
+    /// Neither a seed, a mnemonic nor a remote signatory is set
+    #[error("No seed nor remote signatory set")]
+    NoSeed,
+}
+
+impl SetupError {
+    #[cfg(any(feature = "cln", feature = "lnbits", feature = "lnd"))]
+    fn missing_setting(backend: &'static str, setting: &'static str, env: &'static str) -> Self {
+        Self::MissingSetting {
+            backend,
+            setting,
+            env,
+        }
+    }
+}
+
 #[async_trait]
 pub trait LnBackendSetup {
     async fn setup(
@@ -53,9 +98,9 @@ impl LnBackendSetup for config::Cln {
     ) -> anyhow::Result<cdk_cln::Cln> {
         // Validate required connection field
         if self.rpc_path.as_os_str().is_empty() {
-            return Err(anyhow!(
-                "CLN rpc_path must be set via config or CDK_MINTD_CLN_RPC_PATH env var"
-            ));
+            return Err(
+                SetupError::missing_setting("CLN", "rpc_path", "CDK_MINTD_CLN_RPC_PATH").into(),
+            );
         }
 
         let cln_socket = expand_path(
@@ -73,10 +118,15 @@ impl LnBackendSetup for config::Cln {
         let cln = cdk_cln::Cln::new(
             cln_socket,
             fee_reserve,
-            kv_store.expect("Cln needs kv store"),
+            kv_store.ok_or(SetupError::MissingKvStore("cln"))?,
         )
         .await?;
 
+        let cln = match self.payment_timeout_secs {
+            Some(secs) => cln.with_payment_timeout(Duration::from_secs(secs)),
+            None => cln,
+        };
+
         Ok(cln)
     }
 }
@@ -94,15 +144,28 @@ impl LnBackendSetup for config::LNbits {
     ) -> anyhow::Result<cdk_lnbits::LNbits> {
         // Validate required connection fields
         if self.admin_api_key.is_empty() {
-            bail!("LNbits admin_api_key must be set via config or CDK_MINTD_LNBITS_ADMIN_API_KEY env var");
+            return Err(SetupError::missing_setting(
+                "LNbits",
+                "admin_api_key",
+                "CDK_MINTD_LNBITS_ADMIN_API_KEY",
+            )
+            .into());
         }
         if self.invoice_api_key.is_empty() {
-            bail!("LNbits invoice_api_key must be set via config or CDK_MINTD_LNBITS_INVOICE_API_KEY env var");
+            return Err(SetupError::missing_setting(
+                "LNbits",
+                "invoice_api_key",
+                "CDK_MINTD_LNBITS_INVOICE_API_KEY",
+            )
+            .into());
         }
         if self.lnbits_api.is_empty() {
-            bail!(
-                "LNbits lnbits_api must be set via config or CDK_MINTD_LNBITS_LNBITS_API env var"
-            );
+            return Err(SetupError::missing_setting(
+                "LNbits",
+                "lnbits_api",
+                "CDK_MINTD_LNBITS_LNBITS_API",
+            )
+            .into());
         }
 
         let admin_api_key = &self.admin_api_key;
@@ -121,6 +184,11 @@ impl LnBackendSetup for config::LNbits {
         )
         .await?;
 
+        let lnbits = match self.payment_timeout_secs {
+            Some(secs) => lnbits.with_payment_timeout(Duration::from_secs(secs)),
+            None => lnbits,
+        };
+
         // Use v1 websocket API
         lnbits.subscribe_ws().await?;
 
@@ -141,15 +209,22 @@ impl LnBackendSetup for config::Lnd {
     ) -> anyhow::Result<cdk_lnd::Lnd> {
         // Validate required connection fields
         if self.address.is_empty() {
-            bail!("LND address must be set via config or CDK_MINTD_LND_ADDRESS env var");
+            return Err(
+                SetupError::missing_setting("LND", "address", "CDK_MINTD_LND_ADDRESS").into(),
+            );
         }
         if self.cert_file.as_os_str().is_empty() {
-            bail!("LND cert_file must be set via config or CDK_MINTD_LND_CERT_FILE env var");
+            return Err(
+                SetupError::missing_setting("LND", "cert_file", "CDK_MINTD_LND_CERT_FILE").into(),
+            );
         }
         if self.macaroon_file.as_os_str().is_empty() {
-            bail!(
-                "LND macaroon_file must be set via config or CDK_MINTD_LND_MACAROON_FILE env var"
-            );
+            return Err(SetupError::missing_setting(
+                "LND",
+                "macaroon_file",
+                "CDK_MINTD_LND_MACAROON_FILE",
+            )
+            .into());
         }
 
         let address = &self.address;
@@ -166,10 +241,15 @@ impl LnBackendSetup for config::Lnd {
             cert_file.clone(),
             macaroon_file.clone(),
             fee_reserve,
-            kv_store.expect("Lnd needs kv store"),
+            kv_store.ok_or(SetupError::MissingKvStore("lnd"))?,
         )
         .await?;
 
+        let lnd = match self.payment_timeout_secs {
+            Some(secs) => lnd.with_payment_timeout(Duration::from_secs(secs)),
+            None => lnd,
+        };
+
         Ok(lnd)
     }
 }
@@ -199,9 +279,19 @@ impl LnBackendSetup for config::FakeWallet {
             HashMap::default(),
             HashSet::default(),
             delay_time,
-            unit,
+            unit.clone(),
         );
 
+        let fake_wallet = match self.payment_timeout_secs {
+            Some(secs) => fake_wallet.with_payment_timeout(Duration::from_secs(secs)),
+            None => fake_wallet,
+        };
+
+        let fake_wallet = match self.custom_unit_rates.get(&unit) {
+            Some(msat_per_unit) => fake_wallet.with_custom_unit_rate(unit, *msat_per_unit),
+            None => fake_wallet,
+        };
+
         Ok(fake_wallet)
     }
 }
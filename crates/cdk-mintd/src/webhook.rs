@@ -0,0 +1,57 @@
+//! Posts the stuck melt quote events of the mint to the alert webhook
+
+use std::time::Duration;
+
+use cdk::mint::{Mint, StuckQuoteEvent};
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+
+/// Time allowed for the webhook to answer
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Post each stuck melt quote event of `mint` as JSON to `url`, until `shutdown` is received
+///
+/// Failed posts are logged and not retried, the stuck quotes can still be listed over RPC.
+pub(crate) fn spawn_stuck_quote_webhook(
+    mint: &Mint,
+    url: String,
+    mut shutdown: broadcast::Receiver<()>,
+) -> JoinHandle<()> {
+    let mut events = mint.subscribe_stuck_melt_quotes();
+    let client = reqwest::Client::new();
+
+    tokio::spawn(async move {
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.recv() => break,
+                event = events.recv() => event,
+            };
+
+            match event {
+                Ok(event) => post_event(&client, &url, &event).await,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    tracing::warn!("Stuck quote webhook skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+async fn post_event(client: &reqwest::Client, url: &str, event: &StuckQuoteEvent) {
+    let result = client
+        .post(url)
+        .timeout(WEBHOOK_TIMEOUT)
+        .json(event)
+        .send()
+        .await
+        .and_then(|response| response.error_for_status());
+
+    if let Err(err) = result {
+        tracing::warn!(
+            "Could not post {} event to the stuck quote webhook: {}",
+            event.event_type(),
+            err
+        );
+    }
+}
@@ -0,0 +1,218 @@
+//! Drive a mint built with [`cdk_mintd::run::build_mint_service`] through a mint, swap and melt
+//! over its router, without binding a listener
+#![cfg(all(feature = "fakewallet", feature = "sqlite"))]
+
+use std::env;
+use std::time::Duration;
+
+use axum::body::Body;
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use cdk::amount::{FeeAndAmounts, SplitTarget};
+use cdk::dhke::construct_proofs;
+use cdk::nuts::{
+    CurrencyUnit, KeySet, KeysResponse, MeltQuoteBolt11Request, MeltQuoteBolt11Response,
+    MeltQuoteState, MeltRequest, MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteState,
+    MintRequest, MintResponse, PreMintSecrets, Proofs, SwapRequest, SwapResponse,
+};
+use cdk::Amount;
+use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
+use cdk_mintd::config::{self, LnBackend};
+use cdk_mintd::run::build_mint_service;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use tower::ServiceExt;
+
+const MNEMONIC: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+
+fn settings() -> config::Settings {
+    config::Settings {
+        info: config::Info {
+            url: "http://127.0.0.1:8085".to_string(),
+            listen_host: "127.0.0.1".to_string(),
+            listen_port: 8085,
+            mnemonic: Some(MNEMONIC.to_string()),
+            ..Default::default()
+        },
+        ln: config::Ln {
+            ln_backend: LnBackend::FakeWallet,
+            ..Default::default()
+        },
+        fake_wallet: Some(config::FakeWallet {
+            fee_percent: 0.0,
+            reserve_fee_min: 1.into(),
+            min_delay_time: 1,
+            max_delay_time: 1,
+            ..Default::default()
+        }),
+        ..Default::default()
+    }
+}
+
+async fn request<T, B>(router: &Router, method: Method, uri: &str, body: Option<&B>) -> T
+where
+    T: DeserializeOwned,
+    B: Serialize,
+{
+    let body = match body {
+        Some(body) => Body::from(serde_json::to_vec(body).unwrap()),
+        None => Body::empty(),
+    };
+    let request = Request::builder()
+        .method(method)
+        .uri(uri)
+        .header("content-type", "application/json")
+        .body(body)
+        .unwrap();
+
+    let response = router.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = axum::body::to_bytes(response.into_body(), usize::MAX)
+        .await
+        .unwrap();
+    assert_eq!(
+        status,
+        StatusCode::OK,
+        "{uri} failed: {}",
+        String::from_utf8_lossy(&bytes)
+    );
+
+    serde_json::from_slice(&bytes).unwrap()
+}
+
+/// Blind `amount` for the keyset
+fn blind(keyset: &KeySet, amount: Amount) -> PreMintSecrets {
+    let amounts = keyset
+        .keys
+        .iter()
+        .map(|(amount, _)| amount.to_u64())
+        .collect();
+    PreMintSecrets::random(
+        keyset.id,
+        amount,
+        &SplitTarget::None,
+        &FeeAndAmounts::from((0, amounts)),
+    )
+    .unwrap()
+}
+
+fn unblind(
+    keyset: &KeySet,
+    premint: PreMintSecrets,
+    signatures: Vec<cdk::nuts::BlindSignature>,
+) -> Proofs {
+    construct_proofs(signatures, premint.rs(), premint.secrets(), &keyset.keys).unwrap()
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_mint_swap_melt_over_router() {
+    let work_dir = env::temp_dir().join("cdk_test_mint_service");
+    let _ = std::fs::remove_dir_all(&work_dir);
+    std::fs::create_dir_all(&work_dir).expect("Failed to create temp dir");
+
+    let (router, _mint, tasks) = build_mint_service(&work_dir, &settings(), None, None)
+        .await
+        .expect("Failed to build the mint service");
+
+    let keys: KeysResponse = request(&router, Method::GET, "/v1/keys", None::<&()>).await;
+    let keyset = keys
+        .keysets
+        .into_iter()
+        .find(|keyset| keyset.unit == CurrencyUnit::Sat)
+        .expect("No sat keyset");
+
+    // Mint
+    let quote: MintQuoteBolt11Response<String> = request(
+        &router,
+        Method::POST,
+        "/v1/mint/quote/bolt11",
+        Some(&MintQuoteBolt11Request {
+            amount: Amount::from(100),
+            unit: CurrencyUnit::Sat,
+            description: None,
+            pubkey: None,
+        }),
+    )
+    .await;
+
+    let mut state = quote.state;
+    for _ in 0..30 {
+        if state == MintQuoteState::Paid {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(500)).await;
+        let check: MintQuoteBolt11Response<String> = request(
+            &router,
+            Method::GET,
+            &format!("/v1/mint/quote/bolt11/{}", quote.quote),
+            None::<&()>,
+        )
+        .await;
+        state = check.state;
+    }
+    assert_eq!(state, MintQuoteState::Paid);
+
+    let premint = blind(&keyset, Amount::from(100));
+    let minted: MintResponse = request(
+        &router,
+        Method::POST,
+        "/v1/mint/bolt11",
+        Some(&MintRequest {
+            quote: quote.quote,
+            outputs: premint.blinded_messages(),
+            signature: None,
+        }),
+    )
+    .await;
+    let proofs = unblind(&keyset, premint, minted.signatures);
+    assert_eq!(
+        Amount::try_sum(proofs.iter().map(|proof| proof.amount)).unwrap(),
+        Amount::from(100)
+    );
+
+    // Swap
+    let premint = blind(&keyset, Amount::from(100));
+    let swapped: SwapResponse = request(
+        &router,
+        Method::POST,
+        "/v1/swap",
+        Some(&SwapRequest::new(proofs, premint.blinded_messages())),
+    )
+    .await;
+    let proofs = unblind(&keyset, premint, swapped.signatures);
+
+    // Melt
+    let description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Paid,
+        check_payment_state: MeltQuoteState::Paid,
+        pay_err: false,
+        check_err: false,
+        pay_delay_ms: 0,
+    };
+    let invoice = create_fake_invoice(50_000, serde_json::to_string(&description).unwrap());
+    let melt_quote: MeltQuoteBolt11Response<String> = request(
+        &router,
+        Method::POST,
+        "/v1/melt/quote/bolt11",
+        Some(&MeltQuoteBolt11Request {
+            request: invoice,
+            unit: CurrencyUnit::Sat,
+            options: None,
+        }),
+    )
+    .await;
+    assert_eq!(melt_quote.amount, Amount::from(50));
+
+    let melted: MeltQuoteBolt11Response<String> = request(
+        &router,
+        Method::POST,
+        "/v1/melt/bolt11",
+        Some(&MeltRequest::new(melt_quote.quote, proofs, None)),
+    )
+    .await;
+    assert_eq!(melted.state, MeltQuoteState::Paid);
+
+    tasks.stop().await.expect("Failed to stop the mint");
+    let _ = std::fs::remove_dir_all(&work_dir);
+}
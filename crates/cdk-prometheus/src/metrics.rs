@@ -37,6 +37,18 @@ pub struct CdkMetrics {
     mint_operations_total: IntCounterVec,
     mint_in_flight_requests: IntGaugeVec,
     mint_operation_duration: HistogramVec,
+
+    // Payment backend metrics
+    payment_backend_requests: IntGaugeVec,
+
+    // Abuse metrics
+    double_spend_rejections_total: IntCounterVec,
+    banned_requests_total: IntCounter,
+    banned_clients: IntGauge,
+
+    // Stuck quote metrics
+    stuck_melt_quotes_total: IntCounter,
+    stuck_melt_quotes: IntGauge,
 }
 
 impl CdkMetrics {
@@ -68,6 +80,17 @@ impl CdkMetrics {
         let (mint_operations_total, mint_operation_duration, mint_in_flight_requests) =
             Self::create_mint_metrics(&registry)?;
 
+        // Create and register payment backend metrics
+        let payment_backend_requests = Self::create_payment_backend_metrics(&registry)?;
+
+        // Create and register abuse metrics
+        let (double_spend_rejections_total, banned_requests_total, banned_clients) =
+            Self::create_abuse_metrics(&registry)?;
+
+        // Create and register stuck quote metrics
+        let (stuck_melt_quotes_total, stuck_melt_quotes) =
+            Self::create_stuck_quote_metrics(&registry)?;
+
         Ok(Self {
             registry,
             http_requests_total,
@@ -84,6 +107,12 @@ impl CdkMetrics {
             mint_operations_total,
             mint_in_flight_requests,
             mint_operation_duration,
+            payment_backend_requests,
+            double_spend_rejections_total,
+            banned_requests_total,
+            banned_clients,
+            stuck_melt_quotes_total,
+            stuck_melt_quotes,
         })
     }
 
@@ -267,6 +296,78 @@ impl CdkMetrics {
         ))
     }
 
+    /// Create and register payment backend metrics
+    ///
+    /// # Errors
+    /// Returns an error if any of the metrics cannot be created or registered
+    fn create_payment_backend_metrics(registry: &Registry) -> crate::Result<IntGaugeVec> {
+        let payment_backend_requests = IntGaugeVec::new(
+            prometheus::Opts::new(
+                "cdk_mint_payment_backend_requests",
+                "Number of payment operations in flight or queued per payment backend",
+            ),
+            &["backend", "state"],
+        )?;
+        registry.register(Box::new(payment_backend_requests.clone()))?;
+
+        Ok(payment_backend_requests)
+    }
+
+    /// Create and register abuse metrics
+    ///
+    /// # Errors
+    /// Returns an error if any of the metrics cannot be created or registered
+    fn create_abuse_metrics(
+        registry: &Registry,
+    ) -> crate::Result<(IntCounterVec, IntCounter, IntGauge)> {
+        let double_spend_rejections_total = IntCounterVec::new(
+            prometheus::Opts::new(
+                "cdk_mint_double_spend_rejections_total",
+                "Requests rejected for spending spent proofs or reusing signed outputs",
+            ),
+            &["kind"],
+        )?;
+        registry.register(Box::new(double_spend_rejections_total.clone()))?;
+
+        let banned_requests_total = IntCounter::new(
+            "cdk_mint_banned_requests_total",
+            "Requests rejected because the client is banned",
+        )?;
+        registry.register(Box::new(banned_requests_total.clone()))?;
+
+        let banned_clients = IntGauge::new(
+            "cdk_mint_banned_clients",
+            "Number of clients currently banned",
+        )?;
+        registry.register(Box::new(banned_clients.clone()))?;
+
+        Ok((
+            double_spend_rejections_total,
+            banned_requests_total,
+            banned_clients,
+        ))
+    }
+
+    /// Create and register stuck quote metrics
+    ///
+    /// # Errors
+    /// Returns an error if any of the metrics cannot be created or registered
+    fn create_stuck_quote_metrics(registry: &Registry) -> crate::Result<(IntCounter, IntGauge)> {
+        let stuck_melt_quotes_total = IntCounter::new(
+            "cdk_mint_stuck_melt_quotes_total",
+            "Melt quotes flagged as pending for longer than the alert threshold",
+        )?;
+        registry.register(Box::new(stuck_melt_quotes_total.clone()))?;
+
+        let stuck_melt_quotes = IntGauge::new(
+            "cdk_mint_stuck_melt_quotes",
+            "Number of melt quotes currently flagged as stuck",
+        )?;
+        registry.register(Box::new(stuck_melt_quotes.clone()))?;
+
+        Ok((stuck_melt_quotes_total, stuck_melt_quotes))
+    }
+
     /// Get the metrics registry
     #[must_use]
     pub fn registry(&self) -> Arc<Registry> {
@@ -348,6 +449,47 @@ impl CdkMetrics {
             .with_label_values(&[operation])
             .dec();
     }
+
+    /// Increment payment operations of `backend` in `state` (`in_flight` or `queued`)
+    pub fn inc_payment_backend_requests(&self, backend: &str, state: &str) {
+        self.payment_backend_requests
+            .with_label_values(&[backend, state])
+            .inc();
+    }
+
+    /// Decrement payment operations of `backend` in `state` (`in_flight` or `queued`)
+    pub fn dec_payment_backend_requests(&self, backend: &str, state: &str) {
+        self.payment_backend_requests
+            .with_label_values(&[backend, state])
+            .dec();
+    }
+
+    /// Record a request rejected as a double spend of `kind`
+    pub fn record_double_spend_rejection(&self, kind: &str) {
+        self.double_spend_rejections_total
+            .with_label_values(&[kind])
+            .inc();
+    }
+
+    /// Record a request rejected because the client is banned
+    pub fn record_banned_request(&self) {
+        self.banned_requests_total.inc();
+    }
+
+    /// Set the number of clients currently banned
+    pub fn set_banned_clients(&self, count: i64) {
+        self.banned_clients.set(count);
+    }
+
+    /// Record a melt quote flagged as stuck
+    pub fn record_stuck_melt_quote(&self) {
+        self.stuck_melt_quotes_total.inc();
+    }
+
+    /// Set the number of melt quotes currently flagged as stuck
+    pub fn set_stuck_melt_quotes(&self, count: i64) {
+        self.stuck_melt_quotes.set(count);
+    }
 }
 
 impl Default for CdkMetrics {
@@ -420,6 +562,41 @@ pub mod global {
         METRICS.dec_in_flight_requests(operation);
     }
 
+    /// Increment payment backend operations using the global metrics instance
+    pub fn inc_payment_backend_requests(backend: &str, state: &str) {
+        METRICS.inc_payment_backend_requests(backend, state);
+    }
+
+    /// Decrement payment backend operations using the global metrics instance
+    pub fn dec_payment_backend_requests(backend: &str, state: &str) {
+        METRICS.dec_payment_backend_requests(backend, state);
+    }
+
+    /// Record a double spend rejection using the global metrics instance
+    pub fn record_double_spend_rejection(kind: &str) {
+        METRICS.record_double_spend_rejection(kind);
+    }
+
+    /// Record a request of a banned client using the global metrics instance
+    pub fn record_banned_request() {
+        METRICS.record_banned_request();
+    }
+
+    /// Set the number of banned clients using the global metrics instance
+    pub fn set_banned_clients(count: i64) {
+        METRICS.set_banned_clients(count);
+    }
+
+    /// Record a stuck melt quote using the global metrics instance
+    pub fn record_stuck_melt_quote() {
+        METRICS.record_stuck_melt_quote();
+    }
+
+    /// Set the number of stuck melt quotes using the global metrics instance
+    pub fn set_stuck_melt_quotes(count: i64) {
+        METRICS.set_stuck_melt_quotes(count);
+    }
+
     /// Get the metrics registry from the global instance
     pub fn registry() -> std::sync::Arc<prometheus::Registry> {
         METRICS.registry()
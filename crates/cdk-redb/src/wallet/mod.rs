@@ -583,14 +583,102 @@ impl WalletDatabase<database::Error> for WalletRedbDatabase {
         Ok(())
     }
 
+    #[instrument(skip(self, added, transaction))]
+    async fn update_proofs_with_transaction(
+        &self,
+        added: Vec<ProofInfo>,
+        removed_ys: Vec<PublicKey>,
+        transaction: Transaction,
+    ) -> Result<(), database::Error> {
+        let id = transaction.id();
+        let write_txn = self.db.begin_write().map_err(Error::from)?;
+        {
+            let mut table = write_txn.open_table(PROOFS_TABLE).map_err(Error::from)?;
+
+            for proof_info in added.iter() {
+                table
+                    .insert(
+                        proof_info.y.to_bytes().as_slice(),
+                        serde_json::to_string(&proof_info)
+                            .map_err(Error::from)?
+                            .as_str(),
+                    )
+                    .map_err(Error::from)?;
+            }
+
+            for y in removed_ys.iter() {
+                table.remove(y.to_bytes().as_slice()).map_err(Error::from)?;
+            }
+        }
+        {
+            let mut table = write_txn
+                .open_table(TRANSACTIONS_TABLE)
+                .map_err(Error::from)?;
+            table
+                .insert(
+                    id.as_slice(),
+                    serde_json::to_string(&transaction)
+                        .map_err(Error::from)?
+                        .as_str(),
+                )
+                .map_err(Error::from)?;
+        }
+        write_txn.commit().map_err(Error::from)?;
+        Ok(())
+    }
+
     #[instrument(skip(self))]
     async fn update_mint_url(
         &self,
         old_mint_url: MintUrl,
         new_mint_url: MintUrl,
     ) -> Result<(), database::Error> {
+        if old_mint_url == new_mint_url {
+            return Ok(());
+        }
+
         let write_txn = self.db.begin_write().map_err(Error::from)?;
 
+        // Move the mint, keeping the stored mint info unless the new url is already known
+        {
+            let mut table = write_txn.open_table(MINTS_TABLE).map_err(Error::from)?;
+            let mint_info = table
+                .remove(old_mint_url.to_string().as_str())
+                .map_err(Error::from)?
+                .map(|mint_info| mint_info.value().to_string());
+
+            if let Some(mint_info) = mint_info {
+                if table
+                    .get(new_mint_url.to_string().as_str())
+                    .map_err(Error::from)?
+                    .is_none()
+                {
+                    table
+                        .insert(new_mint_url.to_string().as_str(), mint_info.as_str())
+                        .map_err(Error::from)?;
+                }
+            }
+        }
+
+        // Move the keysets of the mint
+        {
+            let mut table = write_txn
+                .open_multimap_table(MINT_KEYSETS_TABLE)
+                .map_err(Error::from)?;
+            let keyset_ids: Vec<Vec<u8>> = table
+                .remove_all(old_mint_url.to_string().as_str())
+                .map_err(Error::from)?
+                .flatten()
+                .map(|keyset_id| keyset_id.value().to_vec())
+                .collect();
+
+            for keyset_id in keyset_ids {
+                table
+                    .insert(new_mint_url.to_string().as_str(), keyset_id.as_slice())
+                    .map_err(Error::from)?;
+            }
+        }
+
         // Update proofs table
         {
             let read_table = write_txn.open_table(PROOFS_TABLE).map_err(Error::from)?;
@@ -658,6 +746,40 @@ impl WalletDatabase<database::Error> for WalletRedbDatabase {
             }
         }
 
+        // Update transactions
+        {
+            let mut table = write_txn
+                .open_table(TRANSACTIONS_TABLE)
+                .map_err(Error::from)?;
+
+            let transactions: Vec<Transaction> = table
+                .iter()
+                .map_err(Error::from)?
+                .flatten()
+                .filter_map(|(_, transaction)| {
+                    let mut transaction: Transaction =
+                        serde_json::from_str(transaction.value()).ok()?;
+                    if transaction.mint_url == old_mint_url {
+                        transaction.mint_url = new_mint_url.clone();
+                        Some(transaction)
+                    } else {
+                        None
+                    }
+                })
+                .collect();
+
+            for transaction in transactions {
+                table
+                    .insert(
+                        transaction.id().as_slice(),
+                        serde_json::to_string(&transaction)
+                            .map_err(Error::from)?
+                            .as_str(),
+                    )
+                    .map_err(Error::from)?;
+            }
+        }
+
         write_txn.commit().map_err(Error::from)?;
         Ok(())
     }
@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use cdk_common::dhke::{blind_message, construct_proofs};
+use cdk_common::nuts::{BlindedMessage, CurrencyUnit, Proof};
+use cdk_common::secret::Secret;
+use cdk_common::Amount;
+use cdk_signatory::db_signatory::DbSignatory;
+use cdk_signatory::signatory::Signatory;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+
+const BATCH_SIZES: [usize; 3] = [64, 256, 1024];
+
+async fn setup(count: usize) -> (DbSignatory, Vec<BlindedMessage>, Vec<Proof>) {
+    let localstore = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+    let signatory = DbSignatory::new(
+        localstore,
+        &[0u8; 32],
+        HashMap::from([(CurrencyUnit::Sat, (0, 32))]),
+        HashMap::new(),
+    )
+    .await
+    .unwrap();
+
+    let keyset = signatory
+        .keysets()
+        .await
+        .unwrap()
+        .keysets
+        .into_iter()
+        .find(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+        .unwrap();
+
+    let mut blinded_messages = Vec::with_capacity(count);
+    let mut rs = Vec::with_capacity(count);
+    let mut secrets = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let secret = Secret::generate();
+        let (blinded_secret, r) = blind_message(secret.as_bytes(), None).unwrap();
+        blinded_messages.push(BlindedMessage::new(Amount::ONE, keyset.id, blinded_secret));
+        rs.push(r);
+        secrets.push(secret);
+    }
+
+    let signatures = signatory
+        .blind_sign(blinded_messages.clone())
+        .await
+        .unwrap();
+    let proofs = construct_proofs(signatures, rs, secrets, &keyset.keys).unwrap();
+
+    (signatory, blinded_messages, proofs)
+}
+
+fn bench_signatory(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let mut verify = c.benchmark_group("verify_proofs");
+    for count in BATCH_SIZES {
+        let (signatory, _, proofs) = rt.block_on(setup(count));
+
+        verify.bench_with_input(BenchmarkId::new("batch", count), &proofs, |b, proofs| {
+            b.iter(|| {
+                rt.block_on(signatory.verify_proofs(proofs.clone()))
+                    .unwrap()
+            })
+        });
+
+        // One proof per call never reaches the parallel path, which makes it the sequential
+        // baseline
+        verify.bench_with_input(
+            BenchmarkId::new("one_by_one", count),
+            &proofs,
+            |b, proofs| {
+                b.iter(|| {
+                    rt.block_on(async {
+                        for proof in proofs {
+                            signatory.verify_proofs(vec![proof.clone()]).await.unwrap();
+                        }
+                    })
+                })
+            },
+        );
+    }
+    verify.finish();
+
+    let mut sign = c.benchmark_group("blind_sign");
+    for count in BATCH_SIZES {
+        let (signatory, blinded_messages, _) = rt.block_on(setup(count));
+
+        sign.bench_with_input(
+            BenchmarkId::new("batch", count),
+            &blinded_messages,
+            |b, blinded_messages| {
+                b.iter(|| {
+                    rt.block_on(signatory.blind_sign(blinded_messages.clone()))
+                        .unwrap()
+                })
+            },
+        );
+
+        sign.bench_with_input(
+            BenchmarkId::new("one_by_one", count),
+            &blinded_messages,
+            |b, blinded_messages| {
+                b.iter(|| {
+                    rt.block_on(async {
+                        for blinded_message in blinded_messages {
+                            signatory
+                                .blind_sign(vec![blinded_message.clone()])
+                                .await
+                                .unwrap();
+                        }
+                    })
+                })
+            },
+        );
+    }
+    sign.finish();
+}
+
+criterion_group!(benches, bench_signatory);
+criterion_main!(benches);
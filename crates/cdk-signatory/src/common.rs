@@ -18,6 +18,7 @@ pub async fn init_keysets(
     localstore: &Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
     supported_units: &HashMap<CurrencyUnit, (u64, u8)>,
     custom_paths: &HashMap<CurrencyUnit, DerivationPath>,
+    derivation_account: u32,
 ) -> Result<(HashMap<Id, MintKeySet>, Vec<CurrencyUnit>), Error> {
     let mut active_keysets: HashMap<Id, MintKeySet> = HashMap::new();
     let mut active_keyset_units: Vec<CurrencyUnit> = vec![];
@@ -57,22 +58,16 @@ pub async fn init_keysets(
                 .collect();
 
             if let Some((input_fee_ppk, max_order)) = supported_units.get(&unit) {
+                let keyset = generate_keyset_from_info(secp_ctx, xpriv, &highest_index_keyset);
+
+                // A keyset derived from another seed is never reused, its ecash can't be signed
                 if !keysets.is_empty()
                     && highest_index_keyset.input_fee_ppk == *input_fee_ppk
                     && highest_index_keyset.amounts.len() == (*max_order as usize)
+                    && keyset.id == highest_index_keyset.id
                 {
                     tracing::debug!("Current highest index keyset matches expect fee and max order. Setting active");
                     let id = highest_index_keyset.id;
-                    let keyset = MintKeySet::generate_from_xpriv(
-                        secp_ctx,
-                        xpriv,
-                        &highest_index_keyset.amounts,
-                        highest_index_keyset.unit.clone(),
-                        highest_index_keyset.derivation_path.clone(),
-                        highest_index_keyset.input_fee_ppk,
-                        highest_index_keyset.final_expiry,
-                        cdk_common::nut02::KeySetVersion::Version00,
-                    );
                     active_keysets.insert(id, keyset);
                     let mut keyset_info = highest_index_keyset;
                     keyset_info.active = true;
@@ -80,17 +75,30 @@ pub async fn init_keysets(
                     active_keyset_units.push(unit.clone());
                     tx.set_active_keyset(unit, id).await?;
                 } else {
-                    // Check to see if there are not keysets by this unit
+                    let derivation_path_index = tx.reserve_derivation_path_index(&unit).await?;
+
+                    // Keysets without an index predate the counter and may use index 0
                     let derivation_path_index = if keysets.is_empty() {
-                        1
+                        derivation_path_index.max(1)
                     } else {
-                        highest_index_keyset.derivation_path_index.unwrap_or(0) + 1
+                        derivation_path_index
                     };
 
                     let derivation_path = match custom_paths.get(&unit) {
                         Some(path) => path.clone(),
-                        None => derivation_path_from_unit(unit.clone(), derivation_path_index)
-                            .ok_or(Error::UnsupportedUnit)?,
+                        None => derivation_path_for_account(
+                            derivation_account,
+                            unit.clone(),
+                            derivation_path_index,
+                        )
+                        .ok_or(Error::UnsupportedUnit)?,
+                    };
+
+                    // The amounts of a keyset stored without them could not be recovered
+                    let amounts = if highest_index_keyset.amounts.is_empty() {
+                        power_of_two_amounts(u32::from(*max_order))
+                    } else {
+                        highest_index_keyset.amounts.clone()
                     };
 
                     let (keyset, keyset_info) = create_new_keyset(
@@ -99,7 +107,7 @@ pub async fn init_keysets(
                         derivation_path,
                         Some(derivation_path_index),
                         unit.clone(),
-                        &highest_index_keyset.amounts,
+                        &amounts,
                         *input_fee_ppk,
                         // TODO: add Mint settings for a final expiry of newly generated keysets
                         None,
@@ -120,6 +128,122 @@ pub async fn init_keysets(
     Ok((active_keysets, active_keyset_units))
 }
 
+/// Regenerate the [`MintKeySet`] described by a stored [`MintKeySetInfo`] from `xpriv`
+pub fn generate_keyset_from_info<C>(
+    secp_ctx: &Secp256k1<C>,
+    xpriv: Xpriv,
+    keyset_info: &MintKeySetInfo,
+) -> MintKeySet
+where
+    C: secp256k1::Signing,
+{
+    MintKeySet::generate_from_xpriv(
+        secp_ctx,
+        xpriv,
+        &keyset_info.amounts,
+        keyset_info.unit.clone(),
+        keyset_info.derivation_path.clone(),
+        keyset_info.input_fee_ppk,
+        keyset_info.final_expiry,
+        keyset_info.id.get_version(),
+    )
+}
+
+/// Returns the ids of the active keysets in the database that were not derived from `xpriv`
+///
+/// Any id returned means the mint was started with a different seed than the one its keysets
+/// were created with.
+pub async fn find_foreign_active_keysets(
+    xpriv: Xpriv,
+    secp_ctx: &Secp256k1<All>,
+    localstore: &Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
+) -> Result<Vec<Id>, Error> {
+    let active_keysets = localstore.get_active_keysets().await?;
+
+    Ok(localstore
+        .get_keyset_infos()
+        .await?
+        .into_iter()
+        .filter(|info| active_keysets.get(&info.unit) == Some(&info.id))
+        .filter(|info| generate_keyset_from_info(secp_ctx, xpriv, info).id != info.id)
+        .map(|info| info.id)
+        .collect())
+}
+
+/// Highest maximum order of the keysets stored before their amounts were kept
+const LEGACY_MAX_ORDER: u32 = 64;
+
+/// The first `max_order` powers of two, the amounts of a keyset with that maximum order
+pub fn power_of_two_amounts(max_order: u32) -> Vec<u64> {
+    (0..max_order).map(|n| 2_u64.pow(n)).collect()
+}
+
+/// Recovers and stores the amounts of the keysets stored without them
+///
+/// Keysets created before their amounts were kept always signed the powers of two up to a
+/// maximum order, so their amounts are the powers of two that derive the keyset id from `xpriv`.
+/// Returns the ids of the recovered keysets, keysets of another seed are left without amounts.
+pub async fn recover_keyset_amounts(
+    xpriv: Xpriv,
+    secp_ctx: &Secp256k1<All>,
+    localstore: &Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
+) -> Result<Vec<Id>, Error> {
+    let mut recovered = vec![];
+
+    for mut info in localstore.get_keyset_infos().await? {
+        if !info.amounts.is_empty() {
+            continue;
+        }
+
+        let amounts = (1..=LEGACY_MAX_ORDER)
+            .map(power_of_two_amounts)
+            .find(|amounts| {
+                info.amounts = amounts.clone();
+                generate_keyset_from_info(secp_ctx, xpriv, &info).id == info.id
+            });
+
+        let Some(amounts) = amounts else {
+            tracing::warn!("Could not recover the amounts of keyset {}", info.id);
+            continue;
+        };
+
+        info.amounts = amounts;
+        recovered.push(info.id);
+
+        let mut tx = localstore.begin_transaction().await?;
+        tx.add_keyset_info(info).await?;
+        tx.commit().await?;
+    }
+
+    Ok(recovered)
+}
+
+/// Returns the active keysets in the database derived under another account than
+/// `derivation_account`, with the account they were derived under
+///
+/// Keysets of units with a custom derivation path are not checked.
+pub async fn find_other_account_active_keysets(
+    derivation_account: u32,
+    localstore: &Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
+    custom_paths: &HashMap<CurrencyUnit, DerivationPath>,
+) -> Result<Vec<(Id, u32)>, Error> {
+    let active_keysets = localstore.get_active_keysets().await?;
+
+    Ok(localstore
+        .get_keyset_infos()
+        .await?
+        .into_iter()
+        .filter(|info| active_keysets.get(&info.unit) == Some(&info.id))
+        .filter(|info| info.derivation_path_index.is_some())
+        .filter(|info| !custom_paths.contains_key(&info.unit))
+        .filter_map(|info| {
+            derivation_path_account(&info.derivation_path)
+                .filter(|account| *account != derivation_account)
+                .map(|account| (info.id, account))
+        })
+        .collect())
+}
+
 /// Generate new [`MintKeySetInfo`] from path
 #[tracing::instrument(skip_all)]
 #[allow(clippy::too_many_arguments)]
@@ -159,12 +283,41 @@ pub fn create_new_keyset<C: secp256k1::Signing>(
     (keyset, keyset_info)
 }
 
-pub fn derivation_path_from_unit(unit: CurrencyUnit, index: u32) -> Option<DerivationPath> {
+/// Derivation path of the keyset `index` of `unit` under `account`
+///
+/// The account comes first, so mints sharing a seed but not an account never share keys.
+pub fn derivation_path_for_account(
+    account: u32,
+    unit: CurrencyUnit,
+    index: u32,
+) -> Option<DerivationPath> {
     let unit_index = unit.derivation_index()?;
 
     Some(DerivationPath::from(vec![
-        ChildNumber::from_hardened_idx(0).expect("0 is a valid index"),
+        ChildNumber::from_hardened_idx(account).ok()?,
         ChildNumber::from_hardened_idx(unit_index).expect("0 is a valid index"),
         ChildNumber::from_hardened_idx(index).expect("0 is a valid index"),
     ]))
 }
+
+/// Purpose of the derivation paths of announcement keys, kept apart from the keyset paths
+pub const ANNOUNCEMENT_DERIVATION_PURPOSE: u32 = 129373;
+
+/// Derivation path of the announcement key `index` of `account`: `m/129373'/<account>'/<index>'`
+pub fn announcement_derivation_path(account: u32, index: u32) -> Option<DerivationPath> {
+    Some(DerivationPath::from(vec![
+        ChildNumber::from_hardened_idx(ANNOUNCEMENT_DERIVATION_PURPOSE).expect("valid index"),
+        ChildNumber::from_hardened_idx(account).ok()?,
+        ChildNumber::from_hardened_idx(index).ok()?,
+    ]))
+}
+
+/// Account of a derivation path laid out by [`derivation_path_for_account`]
+pub fn derivation_path_account(derivation_path: &DerivationPath) -> Option<u32> {
+    match derivation_path.as_ref() {
+        [ChildNumber::Hardened { index }, ChildNumber::Hardened { .. }, ChildNumber::Hardened { .. }] => {
+            Some(*index)
+        }
+        _ => None,
+    }
+}
@@ -5,16 +5,68 @@ use std::collections::HashMap;
 use std::sync::Arc;
 
 use bitcoin::bip32::{DerivationPath, Xpriv};
+use bitcoin::secp256k1::schnorr::Signature;
 use bitcoin::secp256k1::{self, Secp256k1};
 use cdk_common::dhke::{sign_message, verify_message};
 use cdk_common::mint::MintKeySetInfo;
-use cdk_common::nuts::{BlindSignature, BlindedMessage, CurrencyUnit, Id, MintKeySet, Proof};
-use cdk_common::{database, Error, PublicKey};
+use cdk_common::nuts::{
+    BlindSignature, BlindedMessage, CurrencyUnit, Id, KeySet, Keys, MintKeySet, Proof,
+};
+use cdk_common::{database, Error, PublicKey, SecretKey};
 use tokio::sync::RwLock;
 use tracing::instrument;
 
-use crate::common::{create_new_keyset, derivation_path_from_unit, init_keysets};
-use crate::signatory::{RotateKeyArguments, Signatory, SignatoryKeySet, SignatoryKeysets};
+use crate::common::{
+    announcement_derivation_path, create_new_keyset, derivation_path_account,
+    derivation_path_for_account, find_foreign_active_keysets, find_other_account_active_keysets,
+    generate_keyset_from_info, init_keysets, recover_keyset_amounts,
+};
+use crate::signatory::{
+    KeysetAudit, KeysetAuditIssue, RotateKeyArguments, Signatory, SignatoryKeySet, SignatoryKeysets,
+};
+
+/// Batches smaller than this are processed inline, as the cost of handing them to the blocking
+/// thread pool outweighs the work itself.
+const PARALLEL_BATCH_THRESHOLD: usize = 16;
+
+/// Applies `f` to every job and returns the results in input order.
+///
+/// Large batches are processed in parallel on the blocking thread pool so the EC operations do not
+/// stall the async runtime. All jobs are evaluated, and the error reported is the one of the first
+/// failing job, exactly as a sequential pass would report it.
+async fn batch_map<T, R, F>(jobs: Vec<T>, f: F) -> Result<Vec<R>, Error>
+where
+    T: Send + 'static,
+    R: Send + 'static,
+    F: Fn(T) -> Result<R, Error> + Send + Sync + 'static,
+{
+    #[cfg(not(target_arch = "wasm32"))]
+    if jobs.len() >= PARALLEL_BATCH_THRESHOLD {
+        use rayon::prelude::*;
+
+        return tokio::task::spawn_blocking(move || {
+            jobs.into_par_iter().map(f).collect::<Vec<_>>()
+        })
+        .await
+        .map_err(|_| Error::Internal)?
+        .into_iter()
+        .collect();
+    }
+
+    jobs.into_iter().map(f).collect()
+}
+
+/// Options of a [`DbSignatory`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DbSignatoryOptions {
+    /// Account under which the keysets are derived from the seed
+    ///
+    /// Mints sharing a seed must use different accounts to not share keys. The account of an
+    /// existing mint can't be changed.
+    pub derivation_account: u32,
+    /// Replace the active keysets not derived from the seed instead of refusing to start
+    pub accept_new_seed: bool,
+}
 
 /// In-memory Signatory
 ///
@@ -25,10 +77,13 @@ use crate::signatory::{RotateKeyArguments, Signatory, SignatoryKeySet, Signatory
 #[allow(missing_debug_implementations)]
 pub struct DbSignatory {
     keysets: RwLock<HashMap<Id, (MintKeySetInfo, MintKeySet)>>,
+    /// Keysets that can't be derived from the seed anymore, served from their stored public keys
+    retired_keysets: RwLock<HashMap<Id, SignatoryKeySet>>,
     active_keysets: RwLock<HashMap<CurrencyUnit, Id>>,
     localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
     secp_ctx: Secp256k1<secp256k1::All>,
     custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+    derivation_account: u32,
     xpriv: Xpriv,
     xpub: PublicKey,
 }
@@ -36,24 +91,132 @@ pub struct DbSignatory {
 impl DbSignatory {
     /// Creates a new MemorySignatory instance
     ///
+    /// Fails with [`Error::SeedKeysetMismatch`] if an active keyset in the database was not
+    /// derived from `seed`, see [`DbSignatory::new_accepting_new_seed`].
+    ///
     /// # Panics
     ///
     /// Panics if the seed produces an invalid master key (should never happen with valid entropy).
     pub async fn new(
+        localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
+        seed: &[u8],
+        supported_units: HashMap<CurrencyUnit, (u64, u8)>,
+        custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+    ) -> Result<Self, Error> {
+        Self::new_with_options(
+            localstore,
+            seed,
+            supported_units,
+            custom_paths,
+            DbSignatoryOptions::default(),
+        )
+        .await
+    }
+
+    /// Creates a new MemorySignatory instance, even if the seed changed
+    ///
+    /// Active keysets not derived from `seed` are replaced by new keysets. Ecash issued under the
+    /// old keysets can no longer be redeemed, so this is only meant for a deliberate seed change.
+    pub async fn new_accepting_new_seed(
+        localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
+        seed: &[u8],
+        supported_units: HashMap<CurrencyUnit, (u64, u8)>,
+        custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+    ) -> Result<Self, Error> {
+        Self::new_with_options(
+            localstore,
+            seed,
+            supported_units,
+            custom_paths,
+            DbSignatoryOptions {
+                accept_new_seed: true,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// Creates a new MemorySignatory instance with the given [`DbSignatoryOptions`]
+    ///
+    /// Fails with [`Error::DerivationAccountMismatch`] if an active keyset in the database was
+    /// derived under another account, even if a new seed is accepted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the seed produces an invalid master key (should never happen with valid entropy).
+    pub async fn new_with_options(
         localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync>,
         seed: &[u8],
         mut supported_units: HashMap<CurrencyUnit, (u64, u8)>,
         custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+        options: DbSignatoryOptions,
     ) -> Result<Self, Error> {
+        let DbSignatoryOptions {
+            derivation_account,
+            accept_new_seed,
+        } = options;
+
+        if derivation_path_for_account(derivation_account, CurrencyUnit::Sat, 0).is_none() {
+            return Err(Error::Custom(format!(
+                "Derivation account {derivation_account} is not a valid hardened index"
+            )));
+        }
+
         let secp_ctx = Secp256k1::new();
         let xpriv = Xpriv::new_master(bitcoin::Network::Bitcoin, seed).expect("RNG busted");
 
+        // Operators can compare this across restarts without exposing the seed
+        tracing::info!(
+            "Master key fingerprint: {}, derivation account: {}",
+            xpriv.fingerprint(&secp_ctx),
+            derivation_account
+        );
+
+        let recovered = recover_keyset_amounts(xpriv, &secp_ctx, &localstore).await?;
+        if !recovered.is_empty() {
+            tracing::info!("Recovered the amounts of keysets {:?}", recovered);
+        }
+
+        // Checked first, as keysets of another account are still derived from the seed
+        let other_account_keysets =
+            find_other_account_active_keysets(derivation_account, &localstore, &custom_paths)
+                .await?;
+        if let Some((id, account)) = other_account_keysets.first() {
+            tracing::error!(
+                "Active keysets {:?} were derived under another account than {}",
+                other_account_keysets,
+                derivation_account
+            );
+            return Err(Error::DerivationAccountMismatch(
+                *id,
+                *account,
+                derivation_account,
+            ));
+        }
+
+        let foreign_keysets = find_foreign_active_keysets(xpriv, &secp_ctx, &localstore).await?;
+        if let Some(id) = foreign_keysets.first() {
+            if !accept_new_seed {
+                tracing::error!(
+                    "Active keysets {:?} were not derived from the configured seed",
+                    foreign_keysets
+                );
+                return Err(Error::SeedKeysetMismatch(*id));
+            }
+
+            tracing::warn!(
+                "Active keysets {:?} were not derived from the configured seed, replacing them",
+                foreign_keysets
+            );
+        }
+
         let (mut active_keysets, active_keyset_units) = init_keysets(
             xpriv,
             &secp_ctx,
             &localstore,
             &supported_units,
             &custom_paths,
+            derivation_account,
         )
         .await?;
 
@@ -63,10 +226,13 @@ impl DbSignatory {
         // Create new keysets for supported units that aren't covered by the current keysets
         for (unit, (fee, max_order)) in supported_units {
             if !active_keyset_units.contains(&unit) {
+                let path_index = tx.reserve_derivation_path_index(&unit).await?;
+
                 let derivation_path = match custom_paths.get(&unit) {
                     Some(path) => path.clone(),
                     None => {
-                        derivation_path_from_unit(unit.clone(), 0).ok_or(Error::UnsupportedUnit)?
+                        derivation_path_for_account(derivation_account, unit.clone(), path_index)
+                            .ok_or(Error::UnsupportedUnit)?
                     }
                 };
 
@@ -78,7 +244,7 @@ impl DbSignatory {
                     &secp_ctx,
                     xpriv,
                     derivation_path,
-                    Some(0),
+                    Some(path_index),
                     unit.clone(),
                     &amounts,
                     fee,
@@ -97,9 +263,11 @@ impl DbSignatory {
 
         let keys = Self {
             keysets: Default::default(),
+            retired_keysets: Default::default(),
             active_keysets: Default::default(),
             localstore,
             custom_paths,
+            derivation_account,
             xpub: xpriv.to_keypair(&secp_ctx).public_key().into(),
             secp_ctx,
             xpriv,
@@ -116,17 +284,54 @@ impl DbSignatory {
     ///
     /// Any operation performed with keysets, are done through this trait and never to the database
     /// directly.
+    ///
+    /// The public keys of every keyset are stored the first time it is loaded. Keysets that can't
+    /// be derived from the seed anymore, like the ones of a replaced seed, are served from their
+    /// stored public keys so wallets can still verify and restore their ecash.
     async fn reload_keys_from_db(&self) -> Result<(), Error> {
         let mut keysets = self.keysets.write().await;
+        let mut retired_keysets = self.retired_keysets.write().await;
         let mut active_keysets = self.active_keysets.write().await;
         keysets.clear();
+        retired_keysets.clear();
         active_keysets.clear();
 
         let db_active_keysets = self.localstore.get_active_keysets().await?;
+        let mut unstored_keys = vec![];
 
         for mut info in self.localstore.get_keyset_infos().await? {
             let id = info.id;
             let keyset = self.generate_keyset(&info);
+            let stored_keys = self.localstore.get_keyset_keys(&id).await?;
+
+            if keyset.id != id {
+                match stored_keys {
+                    Some(keys) => {
+                        retired_keysets.insert(
+                            id,
+                            SignatoryKeySet {
+                                id,
+                                unit: info.unit,
+                                active: false,
+                                keys,
+                                amounts: info.amounts,
+                                input_fee_ppk: info.input_fee_ppk,
+                                final_expiry: info.final_expiry,
+                            },
+                        );
+                    }
+                    None => tracing::warn!(
+                        "Keyset {} is not derived from the seed and its keys were not stored",
+                        id
+                    ),
+                }
+                continue;
+            }
+
+            if stored_keys.is_none() {
+                unstored_keys.push((id, Keys::from(keyset.keys.clone())));
+            }
+
             info.active = db_active_keysets.get(&info.unit) == Some(&info.id);
             if info.active {
                 active_keysets.insert(info.unit.clone(), id);
@@ -134,20 +339,31 @@ impl DbSignatory {
             keysets.insert(id, (info, keyset));
         }
 
+        if !unstored_keys.is_empty() {
+            let mut tx = self.localstore.begin_transaction().await?;
+            for (id, keys) in unstored_keys {
+                tx.add_keyset_keys(&id, &keys).await?;
+            }
+            tx.commit().await?;
+        }
+
         Ok(())
     }
 
     fn generate_keyset(&self, keyset_info: &MintKeySetInfo) -> MintKeySet {
-        MintKeySet::generate_from_xpriv(
-            &self.secp_ctx,
-            self.xpriv,
-            &keyset_info.amounts,
-            keyset_info.unit.clone(),
-            keyset_info.derivation_path.clone(),
-            keyset_info.input_fee_ppk,
-            keyset_info.final_expiry,
-            keyset_info.id.get_version(),
-        )
+        generate_keyset_from_info(&self.secp_ctx, self.xpriv, keyset_info)
+    }
+
+    fn announcement_key(&self, index: u32) -> Result<SecretKey, Error> {
+        let path = announcement_derivation_path(self.derivation_account, index)
+            .ok_or(Error::AnnouncementKeyUnsupported)?;
+
+        let xpriv = self
+            .xpriv
+            .derive_priv(&self.secp_ctx, &path)
+            .map_err(|_| Error::AnnouncementKeyUnsupported)?;
+
+        Ok(xpriv.private_key.into())
     }
 }
 
@@ -162,49 +378,74 @@ impl Signatory for DbSignatory {
         &self,
         blinded_messages: Vec<BlindedMessage>,
     ) -> Result<Vec<BlindSignature>, Error> {
-        let keysets = self.keysets.read().await;
+        let jobs = {
+            let keysets = self.keysets.read().await;
 
-        blinded_messages
-            .into_iter()
-            .map(|blinded_message| {
-                let BlindedMessage {
-                    amount,
-                    blinded_secret,
-                    keyset_id,
-                    ..
-                } = blinded_message;
-
-                let (info, key) = keysets.get(&keyset_id).ok_or(Error::UnknownKeySet)?;
-                if !info.active {
-                    return Err(Error::InactiveKeyset);
-                }
+            blinded_messages
+                .into_iter()
+                .map(|blinded_message| {
+                    let (info, key) = keysets
+                        .get(&blinded_message.keyset_id)
+                        .ok_or(Error::UnknownKeySet)?;
+                    if !info.active {
+                        return Err(Error::InactiveKeyset);
+                    }
 
-                let key_pair = key.keys.get(&amount).ok_or(Error::UnknownKeySet)?;
-                let c = sign_message(&key_pair.secret_key, &blinded_secret)?;
+                    let key_pair = key
+                        .keys
+                        .get(&blinded_message.amount)
+                        .ok_or(Error::UnknownKeySet)?;
 
-                let blinded_signature = BlindSignature::new(
-                    amount,
-                    c,
-                    keyset_id,
-                    &blinded_message.blinded_secret,
-                    key_pair.secret_key.clone(),
-                )?;
+                    Ok((key_pair.secret_key.clone(), blinded_message))
+                })
+                .collect::<Vec<Result<_, Error>>>()
+        };
 
-                Ok(blinded_signature)
-            })
-            .collect::<Result<Vec<_>, _>>()
+        batch_map(jobs, |job| {
+            let (secret_key, blinded_message) = job?;
+            let BlindedMessage {
+                amount,
+                blinded_secret,
+                keyset_id,
+                ..
+            } = blinded_message;
+
+            let c = sign_message(&secret_key, &blinded_secret)?;
+
+            Ok(BlindSignature::new(
+                amount,
+                c,
+                keyset_id,
+                &blinded_secret,
+                secret_key,
+            )?)
+        })
+        .await
     }
 
     #[tracing::instrument(skip_all)]
     async fn verify_proofs(&self, proofs: Vec<Proof>) -> Result<(), Error> {
-        let keysets = self.keysets.read().await;
+        let jobs = {
+            let keysets = self.keysets.read().await;
+
+            proofs
+                .into_iter()
+                .map(|proof| {
+                    let (_, key) = keysets.get(&proof.keyset_id).ok_or(Error::UnknownKeySet)?;
+                    let key_pair = key.keys.get(&proof.amount).ok_or(Error::UnknownKeySet)?;
+                    Ok((key_pair.secret_key.clone(), proof))
+                })
+                .collect::<Vec<Result<_, Error>>>()
+        };
 
-        proofs.into_iter().try_for_each(|proof| {
-            let (_, key) = keysets.get(&proof.keyset_id).ok_or(Error::UnknownKeySet)?;
-            let key_pair = key.keys.get(&proof.amount).ok_or(Error::UnknownKeySet)?;
-            verify_message(&key_pair.secret_key, proof.c, proof.secret.as_bytes())?;
+        batch_map(jobs, |job| {
+            let (secret_key, proof) = job?;
+            verify_message(&secret_key, proof.c, proof.secret.as_bytes())?;
             Ok(())
         })
+        .await?;
+
+        Ok(())
     }
 
     #[tracing::instrument(skip_all)]
@@ -217,6 +458,7 @@ impl Signatory for DbSignatory {
                 .await
                 .values()
                 .map(|k| k.into())
+                .chain(self.retired_keysets.read().await.values().cloned())
                 .collect::<Vec<_>>(),
         })
     }
@@ -225,24 +467,17 @@ impl Signatory for DbSignatory {
     /// Generate new keyset
     #[tracing::instrument(skip(self))]
     async fn rotate_keyset(&self, args: RotateKeyArguments) -> Result<SignatoryKeySet, Error> {
-        let path_index = if let Some(current_keyset_id) =
-            self.localstore.get_active_keyset_id(&args.unit).await?
-        {
-            let keyset_info = self
-                .localstore
-                .get_keyset_info(&current_keyset_id)
-                .await?
-                .ok_or(Error::UnknownKeySet)?;
-
-            keyset_info.derivation_path_index.unwrap_or(1) + 1
-        } else {
-            1
-        };
+        // The index is reserved in the same transaction that stores the keyset, so a crash can
+        // never hand the same index out twice
+        let mut tx = self.localstore.begin_transaction().await?;
+        let path_index = tx.reserve_derivation_path_index(&args.unit).await?.max(1);
 
         let derivation_path = match self.custom_paths.get(&args.unit) {
             Some(path) => path.clone(),
-            None => derivation_path_from_unit(args.unit.clone(), path_index)
-                .ok_or(Error::UnsupportedUnit)?,
+            None => {
+                derivation_path_for_account(self.derivation_account, args.unit.clone(), path_index)
+                    .ok_or(Error::UnsupportedUnit)?
+            }
         };
 
         let (keyset, info) = create_new_keyset(
@@ -257,7 +492,6 @@ impl Signatory for DbSignatory {
             None,
         );
         let id = info.id;
-        let mut tx = self.localstore.begin_transaction().await?;
         tx.add_keyset_info(info.clone()).await?;
         tx.set_active_keyset(args.unit, id).await?;
         tx.commit().await?;
@@ -266,6 +500,65 @@ impl Signatory for DbSignatory {
 
         Ok((&(info, keyset)).into())
     }
+
+    /// Recomputes the keys of every stored keyset from the seed and its derivation path, and
+    /// checks that no derivation path is used twice and that path indexes only grow over time
+    #[tracing::instrument(skip(self))]
+    async fn audit_keysets(&self) -> Result<Vec<KeysetAudit>, Error> {
+        let active_keysets = self.localstore.get_active_keysets().await?;
+        let mut infos = self.localstore.get_keyset_infos().await?;
+        infos.sort_by_key(|info| (info.valid_from, info.derivation_path_index));
+
+        let mut audits = Vec::with_capacity(infos.len());
+
+        for (position, info) in infos.iter().enumerate() {
+            let mut issues = vec![];
+
+            let keyset = SignatoryKeySet::from(&(info.clone(), self.generate_keyset(info)));
+            if KeySet::from(keyset).verify_id().is_err() {
+                issues.push(KeysetAuditIssue::IdMismatch);
+            }
+
+            if let Some(other) = infos
+                .iter()
+                .find(|other| other.id != info.id && other.derivation_path == info.derivation_path)
+            {
+                issues.push(KeysetAuditIssue::DerivationPathReused(other.id));
+            }
+
+            if let Some(index) = info.derivation_path_index {
+                if let Some(previous) = infos[..position].iter().find(|previous| {
+                    previous.unit == info.unit
+                        && previous
+                            .derivation_path_index
+                            .is_some_and(|previous_index| previous_index >= index)
+                }) {
+                    issues.push(KeysetAuditIssue::IndexNotIncreasing(previous.id));
+                }
+            }
+
+            audits.push(KeysetAudit {
+                id: info.id,
+                unit: info.unit.clone(),
+                active: active_keysets.get(&info.unit) == Some(&info.id),
+                derivation_path_index: info.derivation_path_index,
+                derivation_account: derivation_path_account(&info.derivation_path),
+                issues,
+            });
+        }
+
+        Ok(audits)
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn announcement_pubkey(&self, index: u32) -> Result<PublicKey, Error> {
+        Ok(self.announcement_key(index)?.public_key())
+    }
+
+    #[tracing::instrument(skip(self, message))]
+    async fn sign_announcement(&self, index: u32, message: Vec<u8>) -> Result<Signature, Error> {
+        Ok(self.announcement_key(index)?.sign(&message)?)
+    }
 }
 
 #[cfg(test)]
@@ -277,6 +570,7 @@ mod test {
     use cdk_common::{Amount, MintKeySet, PublicKey};
 
     use super::*;
+    use crate::common::derivation_path_for_account;
 
     #[test]
     fn mint_mod_generate_keyset_from_seed() {
@@ -286,7 +580,7 @@ mod test {
             seed,
             &[1, 2],
             CurrencyUnit::Sat,
-            derivation_path_from_unit(CurrencyUnit::Sat, 0).unwrap(),
+            derivation_path_for_account(0, CurrencyUnit::Sat, 0).unwrap(),
             0,
             None,
             cdk_common::nut02::KeySetVersion::Version00,
@@ -333,7 +627,7 @@ mod test {
             xpriv,
             &[1, 2],
             CurrencyUnit::Sat,
-            derivation_path_from_unit(CurrencyUnit::Sat, 0).unwrap(),
+            derivation_path_for_account(0, CurrencyUnit::Sat, 0).unwrap(),
             0,
             None,
             cdk_common::nut02::KeySetVersion::Version00,
@@ -369,4 +663,455 @@ mod test {
 
         assert_eq!(amounts_and_pubkeys, expected_amounts_and_pubkeys);
     }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn audit_keysets_detects_derivation_path_drift() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let signatory = DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            HashMap::from([(CurrencyUnit::Sat, (0, 32))]),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let rotated = signatory
+            .rotate_keyset(RotateKeyArguments {
+                unit: CurrencyUnit::Sat,
+                amounts: vec![1, 2, 4],
+                input_fee_ppk: 0,
+            })
+            .await
+            .unwrap();
+
+        let audits = signatory.audit_keysets().await.unwrap();
+        assert!(audits.iter().all(|audit| audit.is_ok()));
+        let rotated_audit = audits.iter().find(|audit| audit.id == rotated.id).unwrap();
+        assert!(rotated_audit.active);
+        assert_eq!(rotated_audit.derivation_path_index, Some(1));
+
+        // Point the rotated keyset back at the path of the first sat keyset
+        let mut info = localstore
+            .get_keyset_info(&rotated.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let first = audits
+            .iter()
+            .find(|audit| audit.unit == CurrencyUnit::Sat && audit.id != rotated.id)
+            .unwrap();
+        info.derivation_path = derivation_path_for_account(0, CurrencyUnit::Sat, 0).unwrap();
+        info.derivation_path_index = Some(0);
+        let mut tx = localstore.begin_transaction().await.unwrap();
+        tx.add_keyset_info(info).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let audits = signatory.audit_keysets().await.unwrap();
+        let rotated_audit = audits.iter().find(|audit| audit.id == rotated.id).unwrap();
+        assert!(rotated_audit.issues.contains(&KeysetAuditIssue::IdMismatch));
+        assert!(rotated_audit
+            .issues
+            .contains(&KeysetAuditIssue::DerivationPathReused(first.id)));
+        assert!(rotated_audit
+            .issues
+            .contains(&KeysetAuditIssue::IndexNotIncreasing(first.id)));
+
+        // The next rotation never reuses an index that was handed out before
+        let next = signatory
+            .rotate_keyset(RotateKeyArguments {
+                unit: CurrencyUnit::Sat,
+                amounts: vec![1, 2, 4],
+                input_fee_ppk: 0,
+            })
+            .await
+            .unwrap();
+        let next_info = localstore.get_keyset_info(&next.id).await.unwrap().unwrap();
+        assert_eq!(next_info.derivation_path_index, Some(2));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn refuses_to_start_with_different_seed() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let supported_units = HashMap::from([(CurrencyUnit::Sat, (0, 32))]);
+
+        let signatory = DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units.clone(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        let active_before = signatory.keysets().await.unwrap().keysets;
+
+        // Restarting with the same seed keeps the keysets
+        DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units.clone(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        assert!(matches!(
+            DbSignatory::new(
+                localstore.clone(),
+                &[1u8; 32],
+                supported_units.clone(),
+                HashMap::new(),
+            )
+            .await,
+            Err(Error::SeedKeysetMismatch(_))
+        ));
+        assert_eq!(
+            localstore.get_active_keysets().await.unwrap().len(),
+            active_before.iter().filter(|keyset| keyset.active).count()
+        );
+
+        // An explicit seed change replaces the active keysets
+        let signatory = DbSignatory::new_accepting_new_seed(
+            localstore.clone(),
+            &[1u8; 32],
+            supported_units,
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        let sat_keyset = signatory
+            .keysets()
+            .await
+            .unwrap()
+            .keysets
+            .into_iter()
+            .find(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+            .unwrap();
+        assert!(active_before
+            .iter()
+            .all(|keyset| keyset.id != sat_keyset.id));
+
+        // The new seed is accepted on the next restart without the flag
+        DbSignatory::new(
+            localstore,
+            &[1u8; 32],
+            HashMap::from([(CurrencyUnit::Sat, (0, 32))]),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn serves_keys_of_keysets_of_a_replaced_seed() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let supported_units = HashMap::from([(CurrencyUnit::Sat, (0, 4))]);
+
+        let signatory = DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units.clone(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        signatory
+            .rotate_keyset(RotateKeyArguments {
+                unit: CurrencyUnit::Sat,
+                amounts: vec![1, 2, 4, 8],
+                input_fee_ppk: 100,
+            })
+            .await
+            .unwrap();
+        let old_keysets = signatory.keysets().await.unwrap().keysets;
+
+        let signatory = DbSignatory::new_accepting_new_seed(
+            localstore,
+            &[1u8; 32],
+            supported_units,
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        let keysets = signatory.keysets().await.unwrap().keysets;
+
+        // The old keysets are still served, inactive and with the keys they had
+        for old in old_keysets {
+            let served = keysets.iter().find(|keyset| keyset.id == old.id).unwrap();
+            assert!(!served.active);
+            assert_eq!(served.keys, old.keys);
+            assert_eq!(served.input_fee_ppk, old.input_fee_ppk);
+            assert!(KeySet::from(served).verify_id().is_ok());
+        }
+        assert!(keysets
+            .iter()
+            .any(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn recovers_amounts_of_keysets_stored_without_them() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let supported_units = HashMap::from([(CurrencyUnit::Sat, (0, 8))]);
+
+        let signatory = DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units.clone(),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+        let sat_keyset = signatory
+            .keysets()
+            .await
+            .unwrap()
+            .keysets
+            .into_iter()
+            .find(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+            .unwrap();
+
+        // Keysets created before the amounts were stored have none
+        let mut info = localstore
+            .get_keyset_info(&sat_keyset.id)
+            .await
+            .unwrap()
+            .unwrap();
+        info.amounts = vec![];
+        let mut tx = localstore.begin_transaction().await.unwrap();
+        tx.add_keyset_info(info).await.unwrap();
+        tx.commit().await.unwrap();
+
+        let signatory = DbSignatory::new(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units,
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let info = localstore
+            .get_keyset_info(&sat_keyset.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(info.amounts, sat_keyset.amounts);
+
+        // The keyset is still the active one and serves the same keys
+        let served = signatory
+            .keysets()
+            .await
+            .unwrap()
+            .keysets
+            .into_iter()
+            .find(|keyset| keyset.id == sat_keyset.id)
+            .unwrap();
+        assert!(served.active);
+        assert_eq!(served.keys, sat_keyset.keys);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn accounts_of_one_seed_derive_disjoint_keys() {
+        let supported_units = HashMap::from([(CurrencyUnit::Sat, (0, 32))]);
+        let mut account_keys = vec![];
+
+        for derivation_account in [0, 1] {
+            let localstore: Arc<
+                dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync,
+            > = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+            let signatory = DbSignatory::new_with_options(
+                localstore,
+                &[0u8; 32],
+                supported_units.clone(),
+                HashMap::new(),
+                DbSignatoryOptions {
+                    derivation_account,
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+            signatory
+                .rotate_keyset(RotateKeyArguments {
+                    unit: CurrencyUnit::Sat,
+                    amounts: vec![1, 2, 4],
+                    input_fee_ppk: 0,
+                })
+                .await
+                .unwrap();
+
+            let audits = signatory.audit_keysets().await.unwrap();
+            assert!(audits.iter().all(|audit| audit.is_ok()));
+            assert!(audits
+                .iter()
+                .all(|audit| audit.derivation_account == Some(derivation_account)));
+
+            let keys: HashSet<PublicKey> = signatory
+                .keysets()
+                .await
+                .unwrap()
+                .keysets
+                .into_iter()
+                .flat_map(|keyset| keyset.keys.values().copied().collect::<Vec<_>>())
+                .collect();
+            account_keys.push(keys);
+        }
+
+        assert!(account_keys[0].is_disjoint(&account_keys[1]));
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn announcement_keys_are_apart_from_keysets() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let signatory = DbSignatory::new(
+            localstore,
+            &[0u8; 32],
+            HashMap::from([(CurrencyUnit::Sat, (0, 32))]),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let first = signatory.announcement_pubkey(0).await.unwrap();
+        let second = signatory.announcement_pubkey(1).await.unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first, signatory.announcement_pubkey(0).await.unwrap());
+
+        let keyset_keys: HashSet<PublicKey> = signatory
+            .keysets()
+            .await
+            .unwrap()
+            .keysets
+            .into_iter()
+            .flat_map(|keyset| keyset.keys.values().copied().collect::<Vec<_>>())
+            .collect();
+        assert!(!keyset_keys.contains(&first));
+        assert!(!keyset_keys.contains(&second));
+
+        let signature = signatory
+            .sign_announcement(1, b"maintenance at noon".to_vec())
+            .await
+            .unwrap();
+        assert!(second.verify(b"maintenance at noon", &signature).is_ok());
+        assert!(first.verify(b"maintenance at noon", &signature).is_err());
+        assert!(second
+            .verify(b"maintenance at midnight", &signature)
+            .is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn refuses_to_start_with_different_account() {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let supported_units = HashMap::from([(CurrencyUnit::Sat, (0, 32))]);
+        let options = DbSignatoryOptions {
+            derivation_account: 7,
+            ..Default::default()
+        };
+
+        DbSignatory::new_with_options(
+            localstore.clone(),
+            &[0u8; 32],
+            supported_units.clone(),
+            HashMap::new(),
+            options,
+        )
+        .await
+        .unwrap();
+
+        // Not even an explicit seed change allows switching accounts
+        for accept_new_seed in [false, true] {
+            assert!(matches!(
+                DbSignatory::new_with_options(
+                    localstore.clone(),
+                    &[0u8; 32],
+                    supported_units.clone(),
+                    HashMap::new(),
+                    DbSignatoryOptions {
+                        derivation_account: 0,
+                        accept_new_seed,
+                    },
+                )
+                .await,
+                Err(Error::DerivationAccountMismatch(_, 7, 0))
+            ));
+        }
+
+        DbSignatory::new_with_options(
+            localstore,
+            &[0u8; 32],
+            supported_units,
+            HashMap::new(),
+            options,
+        )
+        .await
+        .unwrap();
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[tokio::test]
+    async fn verify_proofs_fails_on_invalid_proof_in_batch() {
+        use cdk_common::dhke::{blind_message, construct_proofs};
+        use cdk_common::secret::Secret;
+
+        let localstore = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let signatory = DbSignatory::new(
+            localstore,
+            &[0u8; 32],
+            HashMap::from([(CurrencyUnit::Sat, (0, 32))]),
+            HashMap::new(),
+        )
+        .await
+        .unwrap();
+
+        let keyset = signatory
+            .keysets()
+            .await
+            .unwrap()
+            .keysets
+            .into_iter()
+            .find(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+            .unwrap();
+
+        // Large enough to take the parallel path
+        let count = PARALLEL_BATCH_THRESHOLD * 4;
+        let mut blinded_messages = Vec::with_capacity(count);
+        let mut rs = Vec::with_capacity(count);
+        let mut secrets = Vec::with_capacity(count);
+        for _ in 0..count {
+            let secret = Secret::generate();
+            let (blinded_secret, r) = blind_message(secret.as_bytes(), None).unwrap();
+            blinded_messages.push(BlindedMessage::new(Amount::ONE, keyset.id, blinded_secret));
+            rs.push(r);
+            secrets.push(secret);
+        }
+
+        let signatures = signatory.blind_sign(blinded_messages).await.unwrap();
+        assert_eq!(signatures.len(), count);
+        let mut proofs = construct_proofs(signatures, rs, secrets, &keyset.keys).unwrap();
+
+        signatory.verify_proofs(proofs.clone()).await.unwrap();
+
+        // An invalid signature in the middle fails the whole batch, and is reported over a
+        // later failure even though the batch is not processed in order
+        proofs[count / 2].secret = Secret::generate();
+        proofs[count - 1].amount = Amount::from(1 << 40);
+
+        assert!(matches!(
+            signatory.verify_proofs(proofs).await,
+            Err(Error::DHKE(cdk_common::dhke::Error::TokenNotVerified))
+        ));
+    }
 }
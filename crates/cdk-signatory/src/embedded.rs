@@ -2,11 +2,14 @@
 //! run the Signatory in another thread, isolated form the main CDK, communicating through messages
 use std::sync::Arc;
 
-use cdk_common::{BlindSignature, BlindedMessage, Error, Proof};
+use bitcoin::secp256k1::schnorr::Signature;
+use cdk_common::{BlindSignature, BlindedMessage, Error, Proof, PublicKey};
 use tokio::sync::{mpsc, oneshot};
 use tokio::task::JoinHandle;
 
-use crate::signatory::{RotateKeyArguments, Signatory, SignatoryKeySet, SignatoryKeysets};
+use crate::signatory::{
+    KeysetAudit, RotateKeyArguments, Signatory, SignatoryKeySet, SignatoryKeysets,
+};
 
 enum Request {
     BlindSign(
@@ -23,6 +26,9 @@ enum Request {
             oneshot::Sender<Result<SignatoryKeySet, Error>>,
         ),
     ),
+    AuditKeysets(oneshot::Sender<Result<Vec<KeysetAudit>, Error>>),
+    AnnouncementPubkey((u32, oneshot::Sender<Result<PublicKey, Error>>)),
+    SignAnnouncement((u32, Vec<u8>, oneshot::Sender<Result<Signature, Error>>)),
 }
 
 /// Creates a service-like to wrap an implementation of the Signatory
@@ -88,6 +94,24 @@ impl Service {
                         tracing::error!("Error sending response: {:?}", err);
                     }
                 }
+                Request::AuditKeysets(response) => {
+                    let output = handler.audit_keysets().await;
+                    if let Err(err) = response.send(output) {
+                        tracing::error!("Error sending response: {:?}", err);
+                    }
+                }
+                Request::AnnouncementPubkey((index, response)) => {
+                    let output = handler.announcement_pubkey(index).await;
+                    if let Err(err) = response.send(output) {
+                        tracing::error!("Error sending response: {:?}", err);
+                    }
+                }
+                Request::SignAnnouncement((index, message, response)) => {
+                    let output = handler.sign_announcement(index, message).await;
+                    if let Err(err) = response.send(output) {
+                        tracing::error!("Error sending response: {:?}", err);
+                    }
+                }
             }
         }
     }
@@ -145,4 +169,37 @@ impl Signatory for Service {
 
         rx.await.map_err(|e| Error::RecvError(e.to_string()))?
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn audit_keysets(&self) -> Result<Vec<KeysetAudit>, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.pipeline
+            .send(Request::AuditKeysets(tx))
+            .await
+            .map_err(|e| Error::SendError(e.to_string()))?;
+
+        rx.await.map_err(|e| Error::RecvError(e.to_string()))?
+    }
+
+    #[tracing::instrument(skip(self))]
+    async fn announcement_pubkey(&self, index: u32) -> Result<PublicKey, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.pipeline
+            .send(Request::AnnouncementPubkey((index, tx)))
+            .await
+            .map_err(|e| Error::SendError(e.to_string()))?;
+
+        rx.await.map_err(|e| Error::RecvError(e.to_string()))?
+    }
+
+    #[tracing::instrument(skip(self, message))]
+    async fn sign_announcement(&self, index: u32, message: Vec<u8>) -> Result<Signature, Error> {
+        let (tx, rx) = oneshot::channel();
+        self.pipeline
+            .send(Request::SignAnnouncement((index, message, tx)))
+            .await
+            .map_err(|e| Error::SendError(e.to_string()))?;
+
+        rx.await.map_err(|e| Error::RecvError(e.to_string()))?
+    }
 }
@@ -384,6 +384,7 @@ impl TryInto<cdk_common::KeySetInfo> for KeySet {
             active: self.active,
             input_fee_ppk: self.input_fee_ppk,
             final_expiry: self.final_expiry,
+            max_amount: None,
         })
     }
 }
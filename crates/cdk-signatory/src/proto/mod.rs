@@ -4,3 +4,140 @@ tonic::include_proto!("signatory");
 
 pub mod client;
 pub mod server;
+
+#[cfg(all(test, feature = "sqlite"))]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use cdk_common::dhke::{blind_message, unblind_message};
+    use cdk_common::secret::Secret;
+    use cdk_common::{database, Amount, BlindedMessage, CurrencyUnit, KeySet, Proof};
+    use tokio::net::TcpListener;
+    use tokio_stream::wrappers::TcpListenerStream;
+
+    use super::client::SignatoryRpcClient;
+    use super::server::start_grpc_server_with_incoming;
+    use crate::db_signatory::DbSignatory;
+    use crate::signatory::{RotateKeyArguments, Signatory};
+
+    /// Runs a [`DbSignatory`] behind the gRPC server on a local socket and returns a client for it
+    async fn remote_signatory() -> (Arc<DbSignatory>, SignatoryRpcClient) {
+        let localstore: Arc<dyn database::MintKeysDatabase<Err = database::Error> + Send + Sync> =
+            Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let signatory = Arc::new(
+            DbSignatory::new(
+                localstore,
+                &[0u8; 32],
+                HashMap::from([(CurrencyUnit::Sat, (0, 8))]),
+                HashMap::new(),
+            )
+            .await
+            .unwrap(),
+        );
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(start_grpc_server_with_incoming(
+            signatory.clone(),
+            TcpListenerStream::new(listener),
+        ));
+
+        let client = SignatoryRpcClient::new(format!("http://{addr}"), None::<String>)
+            .await
+            .unwrap();
+
+        (signatory, client)
+    }
+
+    #[tokio::test]
+    async fn remote_signatory_signs_batches_like_the_local_one() {
+        let (local, remote) = remote_signatory().await;
+
+        let keysets = remote.keysets().await.unwrap();
+        let local_keysets = local.keysets().await.unwrap();
+        assert_eq!(keysets.pubkey, local_keysets.pubkey);
+        assert_eq!(
+            keysets
+                .keysets
+                .iter()
+                .map(|k| (k.id, k.active))
+                .collect::<Vec<_>>(),
+            local_keysets
+                .keysets
+                .iter()
+                .map(|k| (k.id, k.active))
+                .collect::<Vec<_>>()
+        );
+        let keyset = keysets
+            .keysets
+            .iter()
+            .find(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+            .unwrap();
+        assert!(KeySet::from(keyset).verify_id().is_ok());
+
+        let amounts = [1u64, 2, 4, 8, 16, 32, 64, 128];
+        let secrets: Vec<_> = amounts.iter().map(|_| Secret::generate()).collect();
+        let blinded: Vec<_> = secrets
+            .iter()
+            .map(|secret| blind_message(secret.as_bytes(), None).unwrap())
+            .collect();
+        let messages: Vec<_> = amounts
+            .iter()
+            .zip(&blinded)
+            .map(|(amount, (b, _))| BlindedMessage::new(Amount::from(*amount), keyset.id, *b))
+            .collect();
+
+        // The whole batch is signed in a single call, in order
+        let signatures = remote.blind_sign(messages.clone()).await.unwrap();
+        assert_eq!(signatures.len(), messages.len());
+
+        let mut proofs = Vec::new();
+        for ((signature, message), (secret, (_, r))) in signatures
+            .iter()
+            .zip(&messages)
+            .zip(secrets.iter().zip(&blinded))
+        {
+            let mint_pubkey = keyset.keys.amount_key(message.amount).unwrap();
+            assert_eq!(signature.amount, message.amount);
+            signature
+                .verify_dleq(mint_pubkey, message.blinded_secret)
+                .unwrap();
+
+            let c = unblind_message(&signature.c, r, &mint_pubkey).unwrap();
+            proofs.push(Proof::new(message.amount, keyset.id, secret.clone(), c));
+        }
+
+        remote.verify_proofs(proofs.clone()).await.unwrap();
+        local.verify_proofs(proofs.clone()).await.unwrap();
+
+        // A forged signature is rejected by the remote signatory
+        let mut forged = proofs[0].clone();
+        forged.c = proofs[1].c;
+        assert!(remote.verify_proofs(vec![forged]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn remote_rotation_is_seen_by_the_local_signatory() {
+        let (local, remote) = remote_signatory().await;
+
+        let rotated = remote
+            .rotate_keyset(RotateKeyArguments {
+                unit: CurrencyUnit::Sat,
+                amounts: vec![1, 2, 4, 8],
+                input_fee_ppk: 100,
+            })
+            .await
+            .unwrap();
+
+        let keysets = local.keysets().await.unwrap().keysets;
+        let active: Vec<_> = keysets
+            .iter()
+            .filter(|keyset| keyset.active && keyset.unit == CurrencyUnit::Sat)
+            .collect();
+        assert_eq!(active.len(), 1);
+        assert_eq!(active[0].id, rotated.id);
+        assert_eq!(active[0].keys, rotated.keys);
+        assert_eq!(active[0].input_fee_ppk, 100);
+    }
+}
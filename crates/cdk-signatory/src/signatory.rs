@@ -6,6 +6,9 @@
 //! There is an in memory implementation, when the keys are stored in memory, in the same process,
 //! but it is isolated from the rest of the application, and they communicate through a channel with
 //! the defined API.
+use std::fmt;
+
+use bitcoin::secp256k1::schnorr::Signature;
 use cdk_common::error::Error;
 use cdk_common::mint::MintKeySetInfo;
 use cdk_common::{
@@ -134,6 +137,67 @@ impl From<&(MintKeySetInfo, MintKeySet)> for SignatoryKeySet {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Discrepancy found while auditing a keyset
+pub enum KeysetAuditIssue {
+    /// The keys derived from the seed and derivation path do not hash to the keyset id
+    IdMismatch,
+    /// Another keyset is derived from the same path, so both share their private keys
+    DerivationPathReused(Id),
+    /// The derivation path index is not above the one of an older keyset of the same unit
+    IndexNotIncreasing(Id),
+    /// The keys served to wallets differ from the keys held by the signatory
+    AdvertisedKeysMismatch,
+    /// Signatures were issued with a keyset above the active keyset's derivation path index
+    IssuedAboveActiveIndex(Id),
+}
+
+impl fmt::Display for KeysetAuditIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IdMismatch => write!(f, "keys do not match the keyset id"),
+            Self::DerivationPathReused(id) => {
+                write!(f, "derivation path is also used by keyset {id}")
+            }
+            Self::IndexNotIncreasing(id) => write!(
+                f,
+                "derivation path index is not above the one of older keyset {id}"
+            ),
+            Self::AdvertisedKeysMismatch => {
+                write!(f, "advertised keys differ from the signatory keys")
+            }
+            Self::IssuedAboveActiveIndex(id) => write!(
+                f,
+                "signatures issued above the derivation path index of active keyset {id}"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Audit result of a single keyset
+pub struct KeysetAudit {
+    /// The keyset Id
+    pub id: Id,
+    /// The Currency Unit
+    pub unit: CurrencyUnit,
+    /// Whether the keyset is active
+    pub active: bool,
+    /// Derivation path index, if known to the signatory
+    pub derivation_path_index: Option<u32>,
+    /// Account the keyset was derived under, if known to the signatory
+    pub derivation_account: Option<u32>,
+    /// Discrepancies found, empty when the keyset is consistent
+    pub issues: Vec<KeysetAuditIssue>,
+}
+
+impl KeysetAudit {
+    /// Whether no discrepancy was found
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
 #[async_trait::async_trait]
 /// Signatory trait
 pub trait Signatory {
@@ -158,4 +222,48 @@ pub trait Signatory {
     /// Add current keyset to inactive keysets
     /// Generate new keyset
     async fn rotate_keyset(&self, args: RotateKeyArguments) -> Result<SignatoryKeySet, Error>;
+
+    /// Audit all the keysets
+    ///
+    /// By default only checks that the keys of every keyset match its id. Signatories holding the
+    /// seed recompute the keys from the stored derivation paths and check the path indexes.
+    async fn audit_keysets(&self) -> Result<Vec<KeysetAudit>, Error> {
+        Ok(self
+            .keysets()
+            .await?
+            .keysets
+            .into_iter()
+            .map(|keyset| {
+                let issues = match KeySet::from(&keyset).verify_id() {
+                    Ok(()) => vec![],
+                    Err(_) => vec![KeysetAuditIssue::IdMismatch],
+                };
+
+                KeysetAudit {
+                    id: keyset.id,
+                    unit: keyset.unit,
+                    active: keyset.active,
+                    derivation_path_index: None,
+                    derivation_account: None,
+                    issues,
+                }
+            })
+            .collect())
+    }
+
+    /// Pubkey of the announcement key `index`, advertised in the mint info
+    ///
+    /// Signatories that can't derive announcement keys fail with
+    /// [`Error::AnnouncementKeyUnsupported`].
+    async fn announcement_pubkey(&self, index: u32) -> Result<PublicKey, Error> {
+        let _ = index;
+        Err(Error::AnnouncementKeyUnsupported)
+    }
+
+    /// Sign an announcement of the mint (BIP-340 over the sha256 of `message`) with the
+    /// announcement key `index`
+    async fn sign_announcement(&self, index: u32, message: Vec<u8>) -> Result<Signature, Error> {
+        let _ = (index, message);
+        Err(Error::AnnouncementKeyUnsupported)
+    }
 }
@@ -4,6 +4,7 @@ use std::time::Instant;
 
 use cdk_common::database::Error;
 
+use crate::column_as_number;
 use crate::database::DatabaseExecutor;
 use crate::stmt::query;
 
@@ -69,6 +70,49 @@ where
     result
 }
 
+/// Schema version of a database with every migration of `db_prefix` applied
+pub fn schema_version(db_prefix: &str, migrations: &[(&str, &str, &str)]) -> u32 {
+    let count = migrations
+        .iter()
+        .filter(|(prefix, _, _)| prefix.is_empty() || *prefix == db_prefix)
+        .count();
+
+    u32::try_from(count).unwrap_or(u32::MAX)
+}
+
+/// Refuses a SQLite database whose `user_version` is newer than `supported_version`
+///
+/// Such a database was migrated by a newer version, whose schema this version can't safely use.
+pub async fn check_user_version<C>(conn: &C, supported_version: u32) -> Result<(), Error>
+where
+    C: DatabaseExecutor,
+{
+    let user_version = query("PRAGMA user_version")?
+        .pluck(conn)
+        .await?
+        .map(|version| Ok::<u32, Error>(column_as_number!(version)))
+        .transpose()?
+        .unwrap_or_default();
+
+    if user_version > supported_version {
+        return Err(Error::NewerSchemaVersion(user_version, supported_version));
+    }
+
+    Ok(())
+}
+
+/// Records `version` as the `user_version` of a SQLite database
+pub async fn set_user_version<C>(conn: &C, version: u32) -> Result<(), Error>
+where
+    C: DatabaseExecutor,
+{
+    query(&format!("PRAGMA user_version = {version}"))?
+        .execute(conn)
+        .await?;
+
+    Ok(())
+}
+
 /// Migrates the migration generated by `build.rs`
 #[inline(always)]
 pub async fn migrate<C>(
@@ -119,6 +119,60 @@ where
     Ok(())
 }
 
+/// Generic implementation of kv_compare_and_swap for transactions
+///
+/// The comparison is part of the write statement, so concurrent writers can't both succeed.
+#[cfg(feature = "mint")]
+pub(crate) async fn kv_compare_and_swap_in_transaction<RM>(
+    conn: &ConnectionWithTransaction<RM::Connection, PooledResource<RM>>,
+    primary_namespace: &str,
+    secondary_namespace: &str,
+    key: &str,
+    expected: Option<&[u8]>,
+    value: &[u8],
+) -> Result<bool, Error>
+where
+    RM: DatabasePool,
+{
+    // Validate parameters according to KV store requirements
+    validate_kvstore_params(primary_namespace, secondary_namespace, Some(key))?;
+
+    let current_time = unix_time();
+
+    let affected = match expected {
+        None => query(
+            r#"
+            INSERT INTO kv_store
+            (primary_namespace, secondary_namespace, key, value, created_time, updated_time)
+            VALUES (:primary_namespace, :secondary_namespace, :key, :value, :created_time, :updated_time)
+            ON CONFLICT(primary_namespace, secondary_namespace, key)
+            DO NOTHING
+            "#,
+        )?
+        .bind("created_time", current_time as i64),
+        Some(expected) => query(
+            r#"
+            UPDATE kv_store
+            SET value = :value, updated_time = :updated_time
+            WHERE primary_namespace = :primary_namespace
+            AND secondary_namespace = :secondary_namespace
+            AND key = :key
+            AND value = :expected
+            "#,
+        )?
+        .bind("expected", expected.to_vec()),
+    }
+    .bind("primary_namespace", primary_namespace.to_owned())
+    .bind("secondary_namespace", secondary_namespace.to_owned())
+    .bind("key", key.to_owned())
+    .bind("value", value.to_vec())
+    .bind("updated_time", current_time as i64)
+    .execute(conn)
+    .await?;
+
+    Ok(affected == 1)
+}
+
 /// Generic implementation of kv_list for transactions
 #[cfg(feature = "mint")]
 pub(crate) async fn kv_list_in_transaction<RM>(
@@ -11,7 +11,10 @@ use cdk_common::{mint, Amount, PaymentMethod};
 use super::{SQLMintDatabase, SQLTransaction};
 use crate::pool::DatabasePool;
 use crate::stmt::{query, Column};
-use crate::{column_as_nullable_string, column_as_number, column_as_string, unpack_into};
+use crate::{
+    column_as_nullable_number, column_as_nullable_string, column_as_number, column_as_string,
+    unpack_into,
+};
 
 fn sql_row_to_completed_operation(row: Vec<Column>) -> Result<mint::Operation, Error> {
     unpack_into!(
@@ -22,7 +25,8 @@ fn sql_row_to_completed_operation(row: Vec<Column>) -> Result<mint::Operation, E
             total_issued,
             total_redeemed,
             fee_collected,
-            payment_method
+            payment_method,
+            change_burnt
         ) = row
     );
 
@@ -48,7 +52,7 @@ fn sql_row_to_completed_operation(row: Vec<Column>) -> Result<mint::Operation, E
         .transpose()
         .map_err(|e| Error::Internal(format!("Invalid payment method: {e}")))?;
 
-    Ok(mint::Operation::new(
+    let mut operation = mint::Operation::new(
         operation_id,
         operation_kind,
         total_issued,
@@ -56,7 +60,13 @@ fn sql_row_to_completed_operation(row: Vec<Column>) -> Result<mint::Operation, E
         fee_collected,
         Some(completed_at),
         payment_method,
-    ))
+    );
+    let change_burnt: Option<u64> = column_as_nullable_number!(change_burnt);
+    if let Some(change_burnt) = change_burnt {
+        operation.set_change_burnt(Amount::from(change_burnt));
+    }
+
+    Ok(operation)
 }
 
 #[async_trait]
@@ -74,9 +84,9 @@ where
         query(
             r#"
             INSERT INTO completed_operations
-            (operation_id, operation_kind, completed_at, total_issued, total_redeemed, fee_collected, payment_amount, payment_fee, payment_method)
+            (operation_id, operation_kind, completed_at, total_issued, total_redeemed, fee_collected, payment_amount, payment_fee, payment_method, change_burnt)
             VALUES
-            (:operation_id, :operation_kind, :completed_at, :total_issued, :total_redeemed, :fee_collected, :payment_amount, :payment_fee, :payment_method)
+            (:operation_id, :operation_kind, :completed_at, :total_issued, :total_redeemed, :fee_collected, :payment_amount, :payment_fee, :payment_method, :change_burnt)
             "#,
         )?
         .bind("operation_id", operation.id().to_string())
@@ -88,6 +98,7 @@ where
         .bind("payment_amount", operation.payment_amount().map(|a| a.to_u64() as i64))
         .bind("payment_fee", operation.payment_fee().map(|a| a.to_u64() as i64))
         .bind("payment_method", operation.payment_method().map(|m| m.to_string()))
+        .bind("change_burnt", operation.change_burnt().map(|a| a.to_u64() as i64))
         .execute(&self.inner)
         .await?;
 
@@ -134,7 +145,8 @@ where
                 total_issued,
                 total_redeemed,
                 fee_collected,
-                payment_method
+                payment_method,
+                change_burnt
             FROM
                 completed_operations
             WHERE
@@ -162,7 +174,8 @@ where
                 total_issued,
                 total_redeemed,
                 fee_collected,
-                payment_method
+                payment_method,
+                change_burnt
             FROM
                 completed_operations
             WHERE
@@ -189,7 +202,8 @@ where
                 total_issued,
                 total_redeemed,
                 fee_collected,
-                payment_method
+                payment_method,
+                change_burnt
             FROM
                 completed_operations
             ORDER BY completed_at DESC
@@ -1,13 +1,13 @@
 //! Keys database implementation
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::str::FromStr;
 
 use async_trait::async_trait;
 use bitcoin::bip32::DerivationPath;
 use cdk_common::database::{Error, MintKeyDatabaseTransaction, MintKeysDatabase};
 use cdk_common::mint::MintKeySetInfo;
-use cdk_common::{CurrencyUnit, Id};
+use cdk_common::{Amount, CurrencyUnit, Id, Keys, PublicKey};
 
 use super::{SQLMintDatabase, SQLTransaction};
 use crate::database::ConnectionWithTransaction;
@@ -33,9 +33,12 @@ pub(crate) fn sql_row_to_keyset_info(row: Vec<Column>) -> Result<MintKeySetInfo,
         ) = row
     );
 
-    let amounts = column_as_nullable_string!(amounts)
-        .and_then(|str| serde_json::from_str(&str).ok())
-        .ok_or_else(|| Error::Database("amounts field is required".to_string().into()))?;
+    // Keysets stored before the amounts were kept have none, the signatory recovers them
+    let amounts = match column_as_nullable_string!(amounts) {
+        Some(amounts) => serde_json::from_str(&amounts)
+            .map_err(|_| Error::Database("invalid keyset amounts".to_string().into()))?,
+        None => vec![],
+    };
 
     Ok(MintKeySetInfo {
         id: column_as_string!(id, Id::from_str, Id::from_bytes),
@@ -107,6 +110,68 @@ where
 
         Ok(())
     }
+
+    async fn reserve_derivation_path_index(&mut self, unit: &CurrencyUnit) -> Result<u32, Error> {
+        let reserved: Option<u32> = query(
+            r#"SELECT derivation_path_index FROM keyset_counter WHERE unit = :unit FOR UPDATE"#,
+        )?
+        .bind("unit", unit.to_string())
+        .pluck(&self.inner)
+        .await?
+        .map(|index| Ok::<_, Error>(column_as_number!(index)))
+        .transpose()?;
+
+        // Keysets stored before the counter existed, or edited by hand, still count as used
+        let highest: Option<u32> =
+            query(r#"SELECT MAX(derivation_path_index) FROM keyset WHERE unit = :unit"#)?
+                .bind("unit", unit.to_string())
+                .pluck(&self.inner)
+                .await?
+                .map(|index| Ok::<_, Error>(column_as_nullable_number!(index)))
+                .transpose()?
+                .flatten();
+
+        let index = match reserved.max(highest) {
+            Some(last) => last.checked_add(1).ok_or_else(|| {
+                Error::Internal(format!("Derivation path index exhausted for {unit}"))
+            })?,
+            None => 0,
+        };
+
+        query(
+            r#"
+            INSERT INTO keyset_counter (unit, derivation_path_index)
+            VALUES (:unit, :derivation_path_index)
+            ON CONFLICT(unit) DO UPDATE SET
+                derivation_path_index = excluded.derivation_path_index
+            "#,
+        )?
+        .bind("unit", unit.to_string())
+        .bind("derivation_path_index", index)
+        .execute(&self.inner)
+        .await?;
+
+        Ok(index)
+    }
+
+    async fn add_keyset_keys(&mut self, id: &Id, keys: &Keys) -> Result<(), Error> {
+        for (amount, pubkey) in keys.iter() {
+            query(
+                r#"
+                INSERT INTO keyset_public_key (keyset_id, amount, pubkey)
+                VALUES (:keyset_id, :amount, :pubkey)
+                ON CONFLICT(keyset_id, amount) DO NOTHING
+                "#,
+            )?
+            .bind("keyset_id", id.to_string())
+            .bind("amount", amount.to_i64())
+            .bind("pubkey", pubkey.to_bytes().to_vec())
+            .execute(&self.inner)
+            .await?;
+        }
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -211,6 +276,27 @@ where
         .map(sql_row_to_keyset_info)
         .collect::<Result<Vec<_>, _>>()?)
     }
+
+    async fn get_keyset_keys(&self, id: &Id) -> Result<Option<Keys>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let keys =
+            query(r#"SELECT amount, pubkey FROM keyset_public_key WHERE keyset_id = :keyset_id"#)?
+                .bind("keyset_id", id.to_string())
+                .fetch_all(&*conn)
+                .await?
+                .into_iter()
+                .map(|row| {
+                    unpack_into!(let (amount, pubkey) = row);
+                    let amount: u64 = column_as_number!(amount);
+                    Ok((
+                        Amount::from(amount),
+                        column_as_string!(pubkey, PublicKey::from_hex, PublicKey::from_slice),
+                    ))
+                })
+                .collect::<Result<BTreeMap<_, _>, Error>>()?;
+
+        Ok((!keys.is_empty()).then(|| Keys::new(keys)))
+    }
 }
 
 #[cfg(test)]
@@ -238,5 +324,22 @@ mod test {
             let keyset = result.unwrap();
             assert_eq!(keyset.amounts.len(), 32);
         }
+
+        #[test]
+        fn keyset_without_amounts() {
+            let result = sql_row_to_keyset_info(vec![
+                Column::Text("0083a60439303340".to_owned()),
+                Column::Text("sat".to_owned()),
+                Column::Integer(0),
+                Column::Integer(1749844864),
+                Column::Null,
+                Column::Text("0'/0'/0'".to_owned()),
+                Column::Integer(0),
+                Column::Null,
+                Column::Integer(0),
+            ]);
+            assert!(result.is_ok());
+            assert!(result.unwrap().amounts.is_empty());
+        }
     }
 }
@@ -67,6 +67,25 @@ where
         crate::keyvalue::kv_list_in_transaction(&self.inner, primary_namespace, secondary_namespace)
             .await
     }
+
+    async fn kv_compare_and_swap(
+        &mut self,
+        primary_namespace: &str,
+        secondary_namespace: &str,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+    ) -> Result<bool, Error> {
+        crate::keyvalue::kv_compare_and_swap_in_transaction(
+            &self.inner,
+            primary_namespace,
+            secondary_namespace,
+            key,
+            expected,
+            value,
+        )
+        .await
+    }
 }
 
 #[async_trait]
@@ -0,0 +1,10 @@
+-- Melts settled against a mint quote of the same mint, without a lightning payment
+CREATE TABLE IF NOT EXISTS internal_settlement (
+    melt_quote_id TEXT PRIMARY KEY NOT NULL,
+    mint_quote_id TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    unit TEXT NOT NULL,
+    created_time BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_internal_settlement_created_time ON internal_settlement(created_time);
@@ -0,0 +1,4 @@
+-- Index the payment request of melt quotes
+-- A melt quote request for an invoice that already has an unpaid quote reuses
+-- it, which looks up the melt quotes of the request.
+CREATE INDEX IF NOT EXISTS idx_melt_quote_request ON melt_quote(request);
@@ -0,0 +1,3 @@
+-- Index the pubkey of mint quotes
+-- Wallets recovering their quotes list the mint quotes locked to a pubkey.
+CREATE INDEX IF NOT EXISTS idx_mint_quote_pubkey ON mint_quote(pubkey);
@@ -0,0 +1,2 @@
+-- Record the change of melts that was owed but not signed
+ALTER TABLE completed_operations ADD COLUMN change_burnt BIGINT;
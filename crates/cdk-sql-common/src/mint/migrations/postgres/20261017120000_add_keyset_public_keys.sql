@@ -0,0 +1,8 @@
+-- Public keys of every keyset ever created, so keysets that can no longer be derived
+-- from the seed are still served to wallets
+CREATE TABLE IF NOT EXISTS keyset_public_key (
+    keyset_id TEXT NOT NULL,
+    amount BIGINT NOT NULL,
+    pubkey BYTEA NOT NULL,
+    PRIMARY KEY (keyset_id, amount)
+);
@@ -0,0 +1,11 @@
+-- Highest derivation path index handed out per unit, so indexes are never reused
+CREATE TABLE IF NOT EXISTS keyset_counter (
+    unit TEXT PRIMARY KEY NOT NULL,
+    derivation_path_index INTEGER NOT NULL
+);
+
+INSERT INTO keyset_counter (unit, derivation_path_index)
+SELECT unit, MAX(derivation_path_index)
+FROM keyset
+WHERE derivation_path_index IS NOT NULL
+GROUP BY unit;
@@ -0,0 +1,7 @@
+-- Index the payment request of mint quotes
+-- Internal settlement looks up the mint quote of a melt by its request; the
+-- sqlite index was lost when the table was rebuilt for bolt12 and postgres
+-- never had one, so the lookup was a table scan.
+-- request_lookup_id is already unique for mint quotes. Melt quotes keep a
+-- non-unique index, as several quotes may share a lookup id.
+CREATE INDEX IF NOT EXISTS idx_mint_quote_request ON mint_quote(request);
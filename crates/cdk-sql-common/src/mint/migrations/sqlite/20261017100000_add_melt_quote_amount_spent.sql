@@ -0,0 +1,2 @@
+-- Amount spent by a paid melt quote, the quote amount and the lightning fee paid
+ALTER TABLE melt_quote ADD COLUMN amount_spent INTEGER;
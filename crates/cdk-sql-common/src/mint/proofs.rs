@@ -10,7 +10,7 @@ use cdk_common::nut00::ProofsMethods;
 use cdk_common::quote_id::QuoteId;
 use cdk_common::secret::Secret;
 use cdk_common::util::unix_time;
-use cdk_common::{Amount, Id, Proof, Proofs, PublicKey, State};
+use cdk_common::{Amount, Id, Proof, Proofs, PublicKey, State, Witness};
 
 use super::{SQLMintDatabase, SQLTransaction};
 use crate::database::DatabaseExecutor;
@@ -484,6 +484,37 @@ where
         Ok(ys.iter().map(|y| current_states.remove(y)).collect())
     }
 
+    async fn get_proofs_states_with_witness(
+        &self,
+        ys: &[PublicKey],
+    ) -> Result<Vec<Option<(State, Option<Witness>)>>, Self::Err> {
+        if ys.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let mut current = query(r#"SELECT y, state, witness FROM proof WHERE y IN (:ys)"#)?
+            .bind_vec("ys", ys.iter().map(|y| y.to_bytes().to_vec()).collect())
+            .fetch_all(&*conn)
+            .await?
+            .into_iter()
+            .map(|row| {
+                unpack_into!(let (y, state, witness) = row);
+
+                Ok((
+                    column_as_string!(y, PublicKey::from_hex, PublicKey::from_slice),
+                    (
+                        column_as_string!(state, State::from_str),
+                        column_as_nullable_string!(witness)
+                            .and_then(|w| serde_json::from_str(&w).ok()),
+                    ),
+                ))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+
+        Ok(ys.iter().map(|y| current.remove(y)).collect())
+    }
+
     async fn get_proofs_by_keyset_id(
         &self,
         keyset_id: &Id,
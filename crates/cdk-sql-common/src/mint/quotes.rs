@@ -8,7 +8,8 @@ use cdk_common::database::{
     self, Acquired, ConversionError, Error, MintQuotesDatabase, MintQuotesTransaction,
 };
 use cdk_common::mint::{
-    self, IncomingPayment, Issuance, MeltPaymentRequest, MeltQuote, MintQuote, Operation,
+    self, IncomingPayment, InternalSettlement, Issuance, MeltPaymentRequest, MeltQuote, MintQuote,
+    Operation,
 };
 use cdk_common::payment::PaymentIdentifier;
 use cdk_common::quote_id::QuoteId;
@@ -266,7 +267,8 @@ where
             paid_time,
             payment_method,
             options,
-            request_lookup_id_kind
+            request_lookup_id_kind,
+            amount_spent
         FROM
             melt_quote
         WHERE
@@ -308,7 +310,8 @@ where
             paid_time,
             payment_method,
             options,
-            request_lookup_id_kind
+            request_lookup_id_kind,
+            amount_spent
         FROM
             melt_quote
         WHERE
@@ -361,7 +364,8 @@ where
             paid_time,
             payment_method,
             options,
-            request_lookup_id_kind
+            request_lookup_id_kind,
+            amount_spent
         FROM
             melt_quote
         WHERE
@@ -464,7 +468,8 @@ fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<mint::MeltQuote, Error> {
                 paid_time,
                 payment_method,
                 options,
-                request_lookup_id_kind
+                request_lookup_id_kind,
+                amount_spent
         ) = row
     );
 
@@ -478,6 +483,7 @@ fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<mint::MeltQuote, Error> {
     let options = options.and_then(|o| serde_json::from_str(&o).ok());
     let created_time: i64 = column_as_number!(created_time);
     let paid_time = column_as_nullable_number!(paid_time);
+    let amount_spent: Option<u64> = column_as_nullable_number!(amount_spent);
     let payment_method = PaymentMethod::from_str(&column_as_string!(payment_method))?;
 
     let state =
@@ -532,10 +538,34 @@ fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<mint::MeltQuote, Error> {
         options,
         created_time as u64,
         paid_time,
+        amount_spent,
         payment_method,
     ))
 }
 
+fn sql_row_to_internal_settlement(row: Vec<Column>) -> Result<InternalSettlement, Error> {
+    unpack_into!(
+        let (
+            melt_quote_id,
+            mint_quote_id,
+            amount,
+            unit,
+            created_time
+        ) = row
+    );
+
+    let amount: u64 = column_as_number!(amount);
+    let unit = column_as_string!(unit, CurrencyUnit::from_str);
+    let created_time: i64 = column_as_number!(created_time);
+
+    Ok(InternalSettlement {
+        melt_quote_id: QuoteId::from_str(&column_as_string!(melt_quote_id))?,
+        mint_quote_id: QuoteId::from_str(&column_as_string!(mint_quote_id))?,
+        amount: Amount::from(amount).with_unit(unit),
+        created_time: created_time as u64,
+    })
+}
+
 #[async_trait]
 impl<RM> MintQuotesTransaction for SQLTransaction<RM>
 where
@@ -884,6 +914,20 @@ where
         Ok(())
     }
 
+    async fn update_melt_quote_amount_spent(
+        &mut self,
+        quote: &mut Acquired<mint::MeltQuote>,
+        amount_spent: Amount<CurrencyUnit>,
+    ) -> Result<(), Self::Err> {
+        query(r#"UPDATE melt_quote SET amount_spent = :amount_spent WHERE id = :id"#)?
+            .bind("amount_spent", amount_spent.value() as i64)
+            .bind("id", quote.id.to_string())
+            .execute(&self.inner)
+            .await?;
+        quote.amount_spent = Some(amount_spent);
+        Ok(())
+    }
+
     async fn update_melt_quote_request_lookup_id(
         &mut self,
         quote: &mut Acquired<mint::MeltQuote>,
@@ -996,6 +1040,29 @@ where
             .await
             .map(|quote| quote.map(|inner| inner.into()))
     }
+
+    async fn add_internal_settlement(
+        &mut self,
+        settlement: &InternalSettlement,
+    ) -> Result<(), Self::Err> {
+        query(
+            r#"
+            INSERT INTO internal_settlement
+            (melt_quote_id, mint_quote_id, amount, unit, created_time)
+            VALUES
+            (:melt_quote_id, :mint_quote_id, :amount, :unit, :created_time)
+            "#,
+        )?
+        .bind("melt_quote_id", settlement.melt_quote_id.to_string())
+        .bind("mint_quote_id", settlement.mint_quote_id.to_string())
+        .bind("amount", settlement.amount.clone().to_i64())
+        .bind("unit", settlement.amount.unit().to_string())
+        .bind("created_time", settlement.created_time as i64)
+        .execute(&self.inner)
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
@@ -1084,6 +1151,50 @@ where
         Ok(mint_quotes)
     }
 
+    async fn get_mint_quotes_by_pubkey(
+        &self,
+        pubkey: &PublicKey,
+    ) -> Result<Vec<MintQuote>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let mut mint_quotes = query(
+            r#"
+            SELECT
+                id,
+                amount,
+                unit,
+                request,
+                expiry,
+                request_lookup_id,
+                pubkey,
+                created_time,
+                amount_paid,
+                amount_issued,
+                payment_method,
+                request_lookup_id_kind
+            FROM
+                mint_quote
+            WHERE
+                pubkey = :pubkey
+            ORDER BY created_time
+            "#,
+        )?
+        .bind("pubkey", pubkey.to_string())
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(|row| sql_row_to_mint_quote(row, vec![], vec![]))
+        .collect::<Result<Vec<_>, _>>()?;
+
+        for quote in mint_quotes.as_mut_slice() {
+            let payments = get_mint_quote_payments(&*conn, &quote.id).await?;
+            let issuance = get_mint_quote_issuance(&*conn, &quote.id).await?;
+            quote.issuance = issuance;
+            quote.payments = payments;
+        }
+
+        Ok(mint_quotes)
+    }
+
     async fn get_melt_quote(
         &self,
         quote_id: &QuoteId,
@@ -1131,7 +1242,8 @@ where
                 paid_time,
                 payment_method,
                 options,
-                request_lookup_id_kind
+                request_lookup_id_kind,
+                amount_spent
             FROM
                 melt_quote
             "#,
@@ -1142,4 +1254,89 @@ where
         .map(sql_row_to_melt_quote)
         .collect::<Result<Vec<_>, _>>()?)
     }
+
+    async fn get_melt_quotes_by_request(
+        &self,
+        request: &MeltPaymentRequest,
+    ) -> Result<Vec<mint::MeltQuote>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        query(
+            r#"
+            SELECT
+                id,
+                unit,
+                amount,
+                request,
+                fee_reserve,
+                expiry,
+                state,
+                payment_preimage,
+                request_lookup_id,
+                created_time,
+                paid_time,
+                payment_method,
+                options,
+                request_lookup_id_kind,
+                amount_spent
+            FROM
+                melt_quote
+            WHERE
+                request = :request
+            "#,
+        )?
+        .bind("request", serde_json::to_string(request)?)
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(sql_row_to_melt_quote)
+        .collect::<Result<Vec<_>, _>>()
+    }
+
+    async fn get_internal_settlement(
+        &self,
+        melt_quote_id: &QuoteId,
+    ) -> Result<Option<InternalSettlement>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        query(
+            r#"
+            SELECT
+                melt_quote_id,
+                mint_quote_id,
+                amount,
+                unit,
+                created_time
+            FROM
+                internal_settlement
+            WHERE
+                melt_quote_id = :melt_quote_id
+            "#,
+        )?
+        .bind("melt_quote_id", melt_quote_id.to_string())
+        .fetch_one(&*conn)
+        .await?
+        .map(sql_row_to_internal_settlement)
+        .transpose()
+    }
+
+    async fn get_internal_settlements(&self) -> Result<Vec<InternalSettlement>, Self::Err> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        query(
+            r#"
+            SELECT
+                melt_quote_id,
+                mint_quote_id,
+                amount,
+                unit,
+                created_time
+            FROM
+                internal_settlement
+            ORDER BY created_time
+            "#,
+        )?
+        .fetch_all(&*conn)
+        .await?
+        .into_iter()
+        .map(sql_row_to_internal_settlement)
+        .collect()
+    }
 }
@@ -248,6 +248,73 @@ where
             .map(|y| blinded_signatures.remove(y))
             .collect())
     }
+
+    async fn get_blind_signatures_for_quote_by_operation(
+        &mut self,
+        quote_id: &QuoteId,
+    ) -> Result<HashMap<uuid::Uuid, Vec<(PublicKey, BlindSignature)>>, Self::Err> {
+        let mut outputs: HashMap<uuid::Uuid, Vec<(PublicKey, BlindSignature)>> = HashMap::new();
+
+        for mut row in query(
+            r#"SELECT
+                keyset_id,
+                amount,
+                c,
+                dleq_e,
+                dleq_s,
+                blinded_message,
+                operation_id
+            FROM
+                blind_signature
+            WHERE quote_id = :quote_id AND c IS NOT NULL AND operation_id IS NOT NULL
+            "#,
+        )?
+        .bind("quote_id", quote_id.to_string())
+        .fetch_all(&self.inner)
+        .await?
+        {
+            let operation_id = column_as_string!(&row.pop().ok_or(Error::InvalidDbResponse)?);
+            let operation_id = uuid::Uuid::parse_str(&operation_id)
+                .map_err(|e| Error::Internal(format!("Invalid operation_id UUID: {e}")))?;
+            let blinded_message = column_as_string!(
+                &row.pop().ok_or(Error::InvalidDbResponse)?,
+                PublicKey::from_hex,
+                PublicKey::from_slice
+            );
+
+            outputs
+                .entry(operation_id)
+                .or_default()
+                .push((blinded_message, sql_row_to_blind_signature(row)?));
+        }
+
+        Ok(outputs)
+    }
+
+    async fn raise_total_issued(
+        &mut self,
+        keyset_id: &Id,
+        amount: Amount,
+    ) -> Result<(), Self::Err> {
+        query(
+            r#"
+            INSERT INTO keyset_amounts (keyset_id, total_issued, total_redeemed)
+            VALUES (:keyset_id, :amount, 0)
+            ON CONFLICT (keyset_id)
+            DO UPDATE SET total_issued = CASE
+                WHEN keyset_amounts.total_issued < EXCLUDED.total_issued
+                THEN EXCLUDED.total_issued
+                ELSE keyset_amounts.total_issued
+            END
+            "#,
+        )?
+        .bind("amount", u64::from(amount) as i64)
+        .bind("keyset_id", keyset_id.to_string())
+        .execute(&self.inner)
+        .await?;
+
+        Ok(())
+    }
 }
 
 #[async_trait]
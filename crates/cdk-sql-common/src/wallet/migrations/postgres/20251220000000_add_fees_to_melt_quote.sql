@@ -0,0 +1,2 @@
+-- Add fee breakdown to melt_quote table
+ALTER TABLE melt_quote ADD COLUMN fees TEXT;
@@ -0,0 +1,10 @@
+-- Add kind to transactions table
+ALTER TABLE transactions ADD COLUMN kind TEXT;
+
+-- Backfill kind for existing transactions: mint and melt entries carry a quote id
+UPDATE transactions SET kind = 'Mint' WHERE direction = 'Incoming' AND quote_id IS NOT NULL;
+UPDATE transactions SET kind = 'Receive' WHERE direction = 'Incoming' AND quote_id IS NULL;
+UPDATE transactions SET kind = 'Melt' WHERE direction = 'Outgoing' AND quote_id IS NOT NULL;
+UPDATE transactions SET kind = 'Send' WHERE direction = 'Outgoing' AND quote_id IS NULL;
+
+CREATE INDEX IF NOT EXISTS kind_index ON transactions(kind);
@@ -0,0 +1,2 @@
+-- Pubkeys the mint rotated away from, kept to verify its announcements
+ALTER TABLE mint ADD COLUMN previous_pubkeys TEXT;
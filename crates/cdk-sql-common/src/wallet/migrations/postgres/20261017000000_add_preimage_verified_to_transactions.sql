@@ -0,0 +1,2 @@
+-- Add preimage_verified to transactions table
+ALTER TABLE transactions ADD COLUMN preimage_verified BOOL NOT NULL DEFAULT FALSE;
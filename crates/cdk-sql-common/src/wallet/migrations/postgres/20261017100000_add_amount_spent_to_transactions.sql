@@ -0,0 +1,2 @@
+-- Add amount_spent to transactions table
+ALTER TABLE transactions ADD COLUMN amount_spent BIGINT;
@@ -0,0 +1,3 @@
+-- Removing a mint didn't cascade to its keysets, since SQLite doesn't enforce foreign keys
+DELETE FROM keyset
+WHERE mint_url NOT IN (SELECT mint_url FROM mint);
@@ -0,0 +1,2 @@
+-- Add the largest amount the mint signs to the keyset table
+ALTER TABLE keyset ADD COLUMN max_amount INTEGER;
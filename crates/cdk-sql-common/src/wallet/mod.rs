@@ -11,14 +11,16 @@ use cdk_common::database::{ConversionError, Error, WalletDatabase};
 use cdk_common::mint_url::MintUrl;
 use cdk_common::nuts::{MeltQuoteState, MintQuoteState};
 use cdk_common::secret::Secret;
-use cdk_common::wallet::{self, MintQuote, Transaction, TransactionDirection, TransactionId};
+use cdk_common::wallet::{
+    self, MintQuote, Transaction, TransactionDirection, TransactionId, TransactionKind,
+};
 use cdk_common::{
     database, Amount, CurrencyUnit, Id, KeySet, KeySetInfo, Keys, MintInfo, PaymentMethod, Proof,
     ProofDleq, PublicKey, SecretKey, SpendingConditions, State,
 };
 use tracing::instrument;
 
-use crate::common::migrate;
+use crate::common::{check_user_version, migrate, schema_version, set_user_version};
 use crate::database::{ConnectionWithTransaction, DatabaseExecutor};
 use crate::pool::{DatabasePool, Pool, PooledResource};
 use crate::stmt::{query, Column};
@@ -59,7 +61,8 @@ where
             unit,
             active,
             input_fee_ppk,
-            final_expiry
+            final_expiry,
+            max_amount
         FROM
             keyset
         WHERE id = :id
@@ -159,7 +162,8 @@ where
             state,
             expiry,
             payment_preimage,
-            payment_method
+            payment_method,
+            fees
         FROM
             melt_quote
         WHERE
@@ -226,6 +230,157 @@ where
         .collect::<Vec<_>>())
 }
 
+#[inline]
+async fn update_proofs_inner<T>(
+    executor: &T,
+    added: Vec<ProofInfo>,
+    removed_ys: Vec<PublicKey>,
+) -> Result<(), Error>
+where
+    T: DatabaseExecutor,
+{
+    for proof in added {
+        query(
+                r#"
+    INSERT INTO proof
+    (y, mint_url, state, spending_condition, unit, amount, keyset_id, secret, c, witness, dleq_e, dleq_s, dleq_r)
+    VALUES
+    (:y, :mint_url, :state, :spending_condition, :unit, :amount, :keyset_id, :secret, :c, :witness, :dleq_e, :dleq_s, :dleq_r)
+    ON CONFLICT(y) DO UPDATE SET
+        mint_url = excluded.mint_url,
+        state = excluded.state,
+        spending_condition = excluded.spending_condition,
+        unit = excluded.unit,
+        amount = excluded.amount,
+        keyset_id = excluded.keyset_id,
+        secret = excluded.secret,
+        c = excluded.c,
+        witness = excluded.witness,
+        dleq_e = excluded.dleq_e,
+        dleq_s = excluded.dleq_s,
+        dleq_r = excluded.dleq_r
+    ;
+            "#,
+            )?
+            .bind("y", proof.y.to_bytes().to_vec())
+            .bind("mint_url", proof.mint_url.to_string())
+            .bind("state", proof.state.to_string())
+            .bind(
+                "spending_condition",
+                proof
+                    .spending_condition
+                    .map(|s| serde_json::to_string(&s).ok()),
+            )
+            .bind("unit", proof.unit.to_string())
+            .bind("amount", u64::from(proof.proof.amount) as i64)
+            .bind("keyset_id", proof.proof.keyset_id.to_string())
+            .bind("secret", proof.proof.secret.to_string())
+            .bind("c", proof.proof.c.to_bytes().to_vec())
+            .bind(
+                "witness",
+                proof
+                    .proof
+                    .witness
+                    .and_then(|w| serde_json::to_string(&w).ok()),
+            )
+            .bind(
+                "dleq_e",
+                proof.proof.dleq.as_ref().map(|dleq| dleq.e.to_secret_bytes().to_vec()),
+            )
+            .bind(
+                "dleq_s",
+                proof.proof.dleq.as_ref().map(|dleq| dleq.s.to_secret_bytes().to_vec()),
+            )
+            .bind(
+                "dleq_r",
+                proof.proof.dleq.as_ref().map(|dleq| dleq.r.to_secret_bytes().to_vec()),
+            )
+            .execute(executor)
+            .await?;
+    }
+
+    if !removed_ys.is_empty() {
+        query(r#"DELETE FROM proof WHERE y IN (:ys)"#)?
+            .bind_vec(
+                "ys",
+                removed_ys.iter().map(|y| y.to_bytes().to_vec()).collect(),
+            )
+            .execute(executor)
+            .await?;
+    }
+
+    Ok(())
+}
+
+#[inline]
+async fn add_transaction_inner<T>(executor: &T, transaction: Transaction) -> Result<(), Error>
+where
+    T: DatabaseExecutor,
+{
+    let mint_url = transaction.mint_url.to_string();
+    let direction = transaction.direction.to_string();
+    let unit = transaction.unit.to_string();
+    let amount = u64::from(transaction.amount) as i64;
+    let fee = u64::from(transaction.fee) as i64;
+    let ys = transaction
+        .ys
+        .iter()
+        .flat_map(|y| y.to_bytes().to_vec())
+        .collect::<Vec<_>>();
+
+    let id = transaction.id();
+
+    query(
+               r#"
+   INSERT INTO transactions
+   (id, mint_url, direction, unit, amount, fee, ys, timestamp, memo, metadata, quote_id, payment_request, payment_proof, payment_method, kind, preimage_verified, amount_spent)
+   VALUES
+   (:id, :mint_url, :direction, :unit, :amount, :fee, :ys, :timestamp, :memo, :metadata, :quote_id, :payment_request, :payment_proof, :payment_method, :kind, :preimage_verified, :amount_spent)
+   ON CONFLICT(id) DO UPDATE SET
+       mint_url = excluded.mint_url,
+       direction = excluded.direction,
+       unit = excluded.unit,
+       amount = excluded.amount,
+       fee = excluded.fee,
+       timestamp = excluded.timestamp,
+       memo = excluded.memo,
+       metadata = excluded.metadata,
+       quote_id = excluded.quote_id,
+       payment_request = excluded.payment_request,
+       payment_proof = excluded.payment_proof,
+       payment_method = excluded.payment_method,
+       kind = excluded.kind,
+       preimage_verified = excluded.preimage_verified,
+       amount_spent = excluded.amount_spent
+   ;
+           "#,
+           )?
+           .bind("id", id.as_slice().to_vec())
+           .bind("mint_url", mint_url)
+           .bind("direction", direction)
+           .bind("unit", unit)
+           .bind("amount", amount)
+           .bind("fee", fee)
+           .bind("ys", ys)
+           .bind("timestamp", transaction.timestamp as i64)
+           .bind("memo", transaction.memo)
+           .bind(
+               "metadata",
+               serde_json::to_string(&transaction.metadata).map_err(Error::from)?,
+           )
+           .bind("quote_id", transaction.quote_id)
+           .bind("payment_request", transaction.payment_request)
+           .bind("payment_proof", transaction.payment_proof)
+           .bind("payment_method", transaction.payment_method.map(|pm| pm.to_string()))
+           .bind("kind", transaction.kind.map(|k| k.to_string()))
+           .bind("preimage_verified", transaction.preimage_verified)
+           .bind("amount_spent", transaction.amount_spent.map(|a| a.to_i64()))
+           .execute(executor)
+           .await?;
+
+    Ok(())
+}
+
 impl<RM> SQLWalletDatabase<RM>
 where
     RM: DatabasePool + 'static,
@@ -242,16 +397,75 @@ where
     }
 
     /// Migrate [`WalletSqliteDatabase`]
+    ///
+    /// SQLite databases record their schema version in `user_version`, so files migrated by a
+    /// newer version are refused instead of being used with a schema this version doesn't know.
     async fn migrate(conn: PooledResource<RM>) -> Result<(), Error> {
         let tx = ConnectionWithTransaction::new(conn).await?;
+        let is_sqlite = RM::Connection::name() == "sqlite";
+        let schema_version = schema_version(RM::Connection::name(), migrations::MIGRATIONS);
+
+        if is_sqlite {
+            check_user_version(&tx, schema_version).await?;
+        }
+
         migrate(&tx, RM::Connection::name(), migrations::MIGRATIONS).await?;
         // Update any existing keys with missing keyset_u32 values
         Self::add_keyset_u32(&tx).await?;
+        Self::check_integrity(&tx, is_sqlite).await?;
+
+        if is_sqlite {
+            set_user_version(&tx, schema_version).await?;
+        }
+
         tx.commit().await?;
 
         Ok(())
     }
 
+    /// Fails if the migrated database breaks a foreign key or stores proofs under a keyset of
+    /// another unit
+    async fn check_integrity<T>(conn: &T, is_sqlite: bool) -> Result<(), Error>
+    where
+        T: DatabaseExecutor,
+    {
+        // Postgres enforces foreign keys, SQLite only checks them on request
+        if is_sqlite {
+            if let Some(violation) =
+                query(r#"SELECT "table", parent FROM pragma_foreign_key_check"#)?
+                    .fetch_one(conn)
+                    .await?
+            {
+                unpack_into!(let (table, parent) = violation);
+                return Err(Error::IntegrityCheck(format!(
+                    "a row of `{}` references a missing row of `{}`",
+                    column_as_string!(table),
+                    column_as_string!(parent)
+                )));
+            }
+        }
+
+        if let Some(keyset_id) = query(
+            r#"
+            SELECT
+                proof.keyset_id
+            FROM proof
+            JOIN keyset ON keyset.id = proof.keyset_id
+            WHERE proof.unit != keyset.unit
+            "#,
+        )?
+        .pluck(conn)
+        .await?
+        {
+            return Err(Error::IntegrityCheck(format!(
+                "proofs of keyset {} are stored under another unit than the keyset",
+                column_as_string!(keyset_id)
+            )));
+        }
+
+        Ok(())
+    }
+
     async fn add_keyset_u32<T>(conn: &T) -> Result<(), Error>
     where
         T: DatabaseExecutor,
@@ -344,7 +558,8 @@ where
                   state,
                   expiry,
                   payment_preimage,
-                  payment_method
+                  payment_method,
+                  fees
               FROM
                   melt_quote
               "#,
@@ -373,7 +588,8 @@ where
                 motd,
                 urls,
                 mint_time,
-                tos_url
+                tos_url,
+                previous_pubkeys
             FROM
                 mint
             WHERE mint_url = :mint_url
@@ -404,6 +620,7 @@ where
                     urls,
                     mint_time,
                     tos_url,
+                    previous_pubkeys,
                     mint_url
                 FROM
                     mint
@@ -437,7 +654,8 @@ where
                 unit,
                 active,
                 input_fee_ppk,
-                final_expiry
+                final_expiry,
+                max_amount
             FROM
                 keyset
             WHERE mint_url = :mint_url
@@ -681,7 +899,10 @@ where
                 quote_id,
                 payment_request,
                 payment_proof,
-                payment_method
+                payment_method,
+                kind,
+                preimage_verified,
+                amount_spent
             FROM
                 transactions
             WHERE
@@ -719,7 +940,10 @@ where
                 quote_id,
                 payment_request,
                 payment_proof,
-                payment_method
+                payment_method,
+                kind,
+                preimage_verified,
+                amount_spent
             FROM
                 transactions
             "#,
@@ -748,75 +972,7 @@ where
         let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
         let tx = ConnectionWithTransaction::new(conn).await?;
 
-        for proof in added {
-            query(
-                r#"
-    INSERT INTO proof
-    (y, mint_url, state, spending_condition, unit, amount, keyset_id, secret, c, witness, dleq_e, dleq_s, dleq_r)
-    VALUES
-    (:y, :mint_url, :state, :spending_condition, :unit, :amount, :keyset_id, :secret, :c, :witness, :dleq_e, :dleq_s, :dleq_r)
-    ON CONFLICT(y) DO UPDATE SET
-        mint_url = excluded.mint_url,
-        state = excluded.state,
-        spending_condition = excluded.spending_condition,
-        unit = excluded.unit,
-        amount = excluded.amount,
-        keyset_id = excluded.keyset_id,
-        secret = excluded.secret,
-        c = excluded.c,
-        witness = excluded.witness,
-        dleq_e = excluded.dleq_e,
-        dleq_s = excluded.dleq_s,
-        dleq_r = excluded.dleq_r
-    ;
-            "#,
-            )?
-            .bind("y", proof.y.to_bytes().to_vec())
-            .bind("mint_url", proof.mint_url.to_string())
-            .bind("state", proof.state.to_string())
-            .bind(
-                "spending_condition",
-                proof
-                    .spending_condition
-                    .map(|s| serde_json::to_string(&s).ok()),
-            )
-            .bind("unit", proof.unit.to_string())
-            .bind("amount", u64::from(proof.proof.amount) as i64)
-            .bind("keyset_id", proof.proof.keyset_id.to_string())
-            .bind("secret", proof.proof.secret.to_string())
-            .bind("c", proof.proof.c.to_bytes().to_vec())
-            .bind(
-                "witness",
-                proof
-                    .proof
-                    .witness
-                    .and_then(|w| serde_json::to_string(&w).ok()),
-            )
-            .bind(
-                "dleq_e",
-                proof.proof.dleq.as_ref().map(|dleq| dleq.e.to_secret_bytes().to_vec()),
-            )
-            .bind(
-                "dleq_s",
-                proof.proof.dleq.as_ref().map(|dleq| dleq.s.to_secret_bytes().to_vec()),
-            )
-            .bind(
-                "dleq_r",
-                proof.proof.dleq.as_ref().map(|dleq| dleq.r.to_secret_bytes().to_vec()),
-            )
-            .execute(&tx)
-            .await?;
-        }
-
-        if !removed_ys.is_empty() {
-            query(r#"DELETE FROM proof WHERE y IN (:ys)"#)?
-                .bind_vec(
-                    "ys",
-                    removed_ys.iter().map(|y| y.to_bytes().to_vec()).collect(),
-                )
-                .execute(&tx)
-                .await?;
-        }
+        update_proofs_inner(&tx, added, removed_ys).await?;
 
         tx.commit().await?;
 
@@ -844,60 +1000,25 @@ where
     async fn add_transaction(&self, transaction: Transaction) -> Result<(), database::Error> {
         let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
 
-        let mint_url = transaction.mint_url.to_string();
-        let direction = transaction.direction.to_string();
-        let unit = transaction.unit.to_string();
-        let amount = u64::from(transaction.amount) as i64;
-        let fee = u64::from(transaction.fee) as i64;
-        let ys = transaction
-            .ys
-            .iter()
-            .flat_map(|y| y.to_bytes().to_vec())
-            .collect::<Vec<_>>();
+        add_transaction_inner(&*conn, transaction).await?;
 
-        let id = transaction.id();
+        Ok(())
+    }
 
-        query(
-               r#"
-   INSERT INTO transactions
-   (id, mint_url, direction, unit, amount, fee, ys, timestamp, memo, metadata, quote_id, payment_request, payment_proof, payment_method)
-   VALUES
-   (:id, :mint_url, :direction, :unit, :amount, :fee, :ys, :timestamp, :memo, :metadata, :quote_id, :payment_request, :payment_proof, :payment_method)
-   ON CONFLICT(id) DO UPDATE SET
-       mint_url = excluded.mint_url,
-       direction = excluded.direction,
-       unit = excluded.unit,
-       amount = excluded.amount,
-       fee = excluded.fee,
-       timestamp = excluded.timestamp,
-       memo = excluded.memo,
-       metadata = excluded.metadata,
-       quote_id = excluded.quote_id,
-       payment_request = excluded.payment_request,
-       payment_proof = excluded.payment_proof,
-       payment_method = excluded.payment_method
-   ;
-           "#,
-           )?
-           .bind("id", id.as_slice().to_vec())
-           .bind("mint_url", mint_url)
-           .bind("direction", direction)
-           .bind("unit", unit)
-           .bind("amount", amount)
-           .bind("fee", fee)
-           .bind("ys", ys)
-           .bind("timestamp", transaction.timestamp as i64)
-           .bind("memo", transaction.memo)
-           .bind(
-               "metadata",
-               serde_json::to_string(&transaction.metadata).map_err(Error::from)?,
-           )
-           .bind("quote_id", transaction.quote_id)
-           .bind("payment_request", transaction.payment_request)
-           .bind("payment_proof", transaction.payment_proof)
-           .bind("payment_method", transaction.payment_method.map(|pm| pm.to_string()))
-           .execute(&*conn)
-           .await?;
+    #[instrument(skip(self, added, transaction))]
+    async fn update_proofs_with_transaction(
+        &self,
+        added: Vec<ProofInfo>,
+        removed_ys: Vec<PublicKey>,
+        transaction: Transaction,
+    ) -> Result<(), database::Error> {
+        let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let tx = ConnectionWithTransaction::new(conn).await?;
+
+        update_proofs_inner(&tx, added, removed_ys).await?;
+        add_transaction_inner(&tx, transaction).await?;
+
+        tx.commit().await?;
 
         Ok(())
     }
@@ -908,9 +1029,28 @@ where
         old_mint_url: MintUrl,
         new_mint_url: MintUrl,
     ) -> Result<(), database::Error> {
+        if old_mint_url == new_mint_url {
+            return Ok(());
+        }
+
         let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
         let tx = ConnectionWithTransaction::new(conn).await?;
-        let tables = ["mint_quote", "proof"];
+
+        // Keep the stored mint info unless the new url is already known
+        query(
+            r#"
+            UPDATE mint
+            SET mint_url = :new_mint_url
+            WHERE mint_url = :old_mint_url
+            AND NOT EXISTS (SELECT 1 FROM mint WHERE mint_url = :new_mint_url)
+            "#,
+        )?
+        .bind("new_mint_url", new_mint_url.to_string())
+        .bind("old_mint_url", old_mint_url.to_string())
+        .execute(&tx)
+        .await?;
+
+        let tables = ["keyset", "mint_quote", "proof", "transactions"];
 
         for table in &tables {
             query(&format!(
@@ -926,6 +1066,11 @@ where
             .await?;
         }
 
+        query(r#"DELETE FROM mint WHERE mint_url = :old_mint_url"#)?
+            .bind("old_mint_url", old_mint_url.to_string())
+            .execute(&tx)
+            .await?;
+
         tx.commit().await?;
 
         Ok(())
@@ -996,6 +1141,7 @@ where
             motd,
             time,
             tos_url,
+            previous_pubkeys,
         ) = match mint_info {
             Some(mint_info) => {
                 let MintInfo {
@@ -1011,6 +1157,7 @@ where
                     motd,
                     time,
                     tos_url,
+                    previous_pubkeys,
                 } = mint_info;
 
                 (
@@ -1026,10 +1173,11 @@ where
                     motd,
                     time,
                     tos_url,
+                    serde_json::to_string(&previous_pubkeys).ok(),
                 )
             }
             None => (
-                None, None, None, None, None, None, None, None, None, None, None, None,
+                None, None, None, None, None, None, None, None, None, None, None, None, None,
             ),
         };
 
@@ -1038,12 +1186,12 @@ where
    INSERT INTO mint
    (
        mint_url, name, pubkey, version, description, description_long,
-       contact, nuts, icon_url, urls, motd, mint_time, tos_url
+       contact, nuts, icon_url, urls, motd, mint_time, tos_url, previous_pubkeys
    )
    VALUES
    (
        :mint_url, :name, :pubkey, :version, :description, :description_long,
-       :contact, :nuts, :icon_url, :urls, :motd, :mint_time, :tos_url
+       :contact, :nuts, :icon_url, :urls, :motd, :mint_time, :tos_url, :previous_pubkeys
    )
    ON CONFLICT(mint_url) DO UPDATE SET
        name = excluded.name,
@@ -1057,7 +1205,8 @@ where
        urls = excluded.urls,
        motd = excluded.motd,
        mint_time = excluded.mint_time,
-       tos_url = excluded.tos_url
+       tos_url = excluded.tos_url,
+       previous_pubkeys = excluded.previous_pubkeys
    ;
            "#,
         )?
@@ -1074,6 +1223,7 @@ where
         .bind("motd", motd)
         .bind("mint_time", time.map(|v| v as i64))
         .bind("tos_url", tos_url)
+        .bind("previous_pubkeys", previous_pubkeys)
         .execute(&*conn)
         .await?;
 
@@ -1083,12 +1233,21 @@ where
     #[instrument(skip(self))]
     async fn remove_mint(&self, mint_url: MintUrl) -> Result<(), database::Error> {
         let conn = self.pool.get().map_err(|e| Error::Database(Box::new(e)))?;
+        let tx = ConnectionWithTransaction::new(conn).await?;
+
+        // SQLite doesn't enforce foreign keys, so the keysets aren't removed by the cascade
+        query(r#"DELETE FROM keyset WHERE mint_url=:mint_url"#)?
+            .bind("mint_url", mint_url.to_string())
+            .execute(&tx)
+            .await?;
 
         query(r#"DELETE FROM mint WHERE mint_url=:mint_url"#)?
             .bind("mint_url", mint_url.to_string())
-            .execute(&*conn)
+            .execute(&tx)
             .await?;
 
+        tx.commit().await?;
+
         Ok(())
     }
 
@@ -1105,12 +1264,13 @@ where
             query(
                 r#"
         INSERT INTO keyset
-        (mint_url, id, unit, active, input_fee_ppk, final_expiry, keyset_u32)
+        (mint_url, id, unit, active, input_fee_ppk, final_expiry, keyset_u32, max_amount)
         VALUES
-        (:mint_url, :id, :unit, :active, :input_fee_ppk, :final_expiry, :keyset_u32)
+        (:mint_url, :id, :unit, :active, :input_fee_ppk, :final_expiry, :keyset_u32, :max_amount)
         ON CONFLICT(id) DO UPDATE SET
             active = excluded.active,
-            input_fee_ppk = excluded.input_fee_ppk
+            input_fee_ppk = excluded.input_fee_ppk,
+            max_amount = excluded.max_amount
         "#,
             )?
             .bind("mint_url", mint_url.to_string())
@@ -1120,6 +1280,7 @@ where
             .bind("input_fee_ppk", keyset.input_fee_ppk as i64)
             .bind("final_expiry", keyset.final_expiry.map(|v| v as i64))
             .bind("keyset_u32", u32::from(keyset.id))
+            .bind("max_amount", keyset.max_amount.map(|v| u64::from(v) as i64))
             .execute(&tx)
             .await?;
         }
@@ -1188,9 +1349,9 @@ where
         query(
             r#"
  INSERT INTO melt_quote
- (id, unit, amount, request, fee_reserve, state, expiry, payment_method)
+ (id, unit, amount, request, fee_reserve, state, expiry, payment_method, fees)
  VALUES
- (:id, :unit, :amount, :request, :fee_reserve, :state, :expiry, :payment_method)
+ (:id, :unit, :amount, :request, :fee_reserve, :state, :expiry, :payment_method, :fees)
  ON CONFLICT(id) DO UPDATE SET
      unit = excluded.unit,
      amount = excluded.amount,
@@ -1198,7 +1359,8 @@ where
      fee_reserve = excluded.fee_reserve,
      state = excluded.state,
      expiry = excluded.expiry,
-     payment_method = excluded.payment_method
+     payment_method = excluded.payment_method,
+     fees = excluded.fees
  ;
          "#,
         )?
@@ -1210,6 +1372,14 @@ where
         .bind("state", quote.state.to_string())
         .bind("expiry", quote.expiry as i64)
         .bind("payment_method", quote.payment_method.to_string())
+        .bind(
+            "fees",
+            quote
+                .fees
+                .map(|fees| serde_json::to_string(&fees))
+                .transpose()
+                .map_err(Error::from)?,
+        )
         .execute(&*conn)
         .await?;
 
@@ -1329,7 +1499,8 @@ fn sql_row_to_mint_info(row: Vec<Column>) -> Result<MintInfo, Error> {
             motd,
             urls,
             mint_time,
-            tos_url
+            tos_url,
+            previous_pubkeys
         ) = row
     );
 
@@ -1349,6 +1520,11 @@ fn sql_row_to_mint_info(row: Vec<Column>) -> Result<MintInfo, Error> {
         motd: column_as_nullable_string!(motd),
         time: column_as_nullable_number!(mint_time).map(|t| t),
         tos_url: column_as_nullable_string!(tos_url),
+        previous_pubkeys: column_as_nullable_string!(previous_pubkeys, |v| serde_json::from_str(
+            &v
+        )
+        .ok())
+        .unwrap_or_default(),
     })
 }
 
@@ -1360,7 +1536,8 @@ fn sql_row_to_keyset(row: Vec<Column>) -> Result<KeySetInfo, Error> {
             unit,
             active,
             input_fee_ppk,
-            final_expiry
+            final_expiry,
+            max_amount
         ) = row
     );
 
@@ -1370,6 +1547,7 @@ fn sql_row_to_keyset(row: Vec<Column>) -> Result<KeySetInfo, Error> {
         active: matches!(active, Column::Integer(1)),
         input_fee_ppk: column_as_nullable_number!(input_fee_ppk).unwrap_or(0),
         final_expiry: column_as_nullable_number!(final_expiry),
+        max_amount: column_as_nullable_number!(max_amount).map(|v: u64| Amount::from(v)),
     })
 }
 
@@ -1425,7 +1603,8 @@ fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<wallet::MeltQuote, Error> {
             state,
             expiry,
             payment_preimage,
-            row_method
+            row_method,
+            fees
         ) = row
     );
 
@@ -1445,6 +1624,10 @@ fn sql_row_to_melt_quote(row: Vec<Column>) -> Result<wallet::MeltQuote, Error> {
         expiry: column_as_number!(expiry),
         payment_preimage: column_as_nullable_string!(payment_preimage),
         payment_method,
+        fees: column_as_nullable_string!(fees)
+            .map(|fees| serde_json::from_str(&fees))
+            .transpose()
+            .map_err(Error::from)?,
     })
 }
 
@@ -1523,7 +1706,10 @@ fn sql_row_to_transaction(row: Vec<Column>) -> Result<Transaction, Error> {
             quote_id,
             payment_request,
             payment_proof,
-            payment_method
+            payment_method,
+            kind,
+            preimage_verified,
+            amount_spent
         ) = row
     );
 
@@ -1553,6 +1739,9 @@ fn sql_row_to_transaction(row: Vec<Column>) -> Result<Transaction, Error> {
             .map(|v| PaymentMethod::from_str(&v))
             .transpose()
             .map_err(Error::from)?,
+        kind: column_as_nullable_string!(kind, |v| TransactionKind::from_str(&v).ok()),
+        preimage_verified: matches!(preimage_verified, Column::Integer(1)),
+        amount_spent: column_as_nullable_number!(amount_spent).map(|v: u64| Amount::from(v)),
     })
 }
 
@@ -386,4 +386,147 @@ mod tests {
         // Verify that fully paid and issued quote is not returned
         assert!(!quote_ids.contains(&"quote_fully_paid"));
     }
+
+    /// Runs an SQL dump against a database file, creating it if missing
+    async fn load_dump(file: &str, dump: &str) {
+        use cdk_sql_common::pool::Pool;
+        use cdk_sql_common::stmt::query;
+
+        use crate::common::{Config, SqliteConnectionManager};
+
+        #[cfg(not(feature = "sqlcipher"))]
+        let config: Config = file.into();
+        #[cfg(feature = "sqlcipher")]
+        let config: Config = (file, "test").into();
+
+        let pool = Pool::<SqliteConnectionManager>::new(config);
+        let conn = pool.get().expect("valid connection");
+
+        query(dump)
+            .expect("query")
+            .batch(&*conn)
+            .await
+            .expect("create fixture db failed");
+    }
+
+    async fn open(file: &str) -> Result<WalletSqliteDatabase, cdk_common::database::Error> {
+        #[cfg(not(feature = "sqlcipher"))]
+        let db = WalletSqliteDatabase::new(file).await;
+
+        #[cfg(feature = "sqlcipher")]
+        let db = WalletSqliteDatabase::new((file, "test")).await;
+
+        db
+    }
+
+    fn temp_file() -> String {
+        std::env::temp_dir()
+            .join(format!("cdk-wallet-{}.sqlite", uuid::Uuid::new_v4()))
+            .to_string_lossy()
+            .to_string()
+    }
+
+    /// Each fixture is a database written by the version before a migration was added
+    #[tokio::test]
+    async fn open_previous_versions_and_migrate() {
+        use cdk_common::mint_url::MintUrl;
+        use cdk_common::{CurrencyUnit, Id};
+
+        for fixture in [include_str!("../../tests/wallet-v29.sql")] {
+            let file = temp_file();
+            load_dump(&file, fixture).await;
+
+            let db = open(&file).await;
+            assert!(db.is_ok(), "Failed with {:?}", db.unwrap_err());
+            let db = db.unwrap();
+
+            let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+            let keyset_id = Id::from_str("00bf1340cbf71165").unwrap();
+
+            let unspent = db
+                .get_proofs(
+                    Some(mint_url.clone()),
+                    Some(CurrencyUnit::Sat),
+                    Some(vec![State::Unspent]),
+                    None,
+                )
+                .await
+                .unwrap();
+            assert_eq!(unspent.len(), 2);
+            assert_eq!(
+                db.get_proofs(None, None, None, None).await.unwrap().len(),
+                4
+            );
+            assert!(db.get_keys(&keyset_id).await.unwrap().is_some());
+            assert_eq!(db.increment_keyset_counter(&keyset_id, 0).await.unwrap(), 7);
+            assert!(db.get_mint_quote("mint-quote-1").await.unwrap().is_some());
+            assert!(db.get_melt_quote("melt-quote-1").await.unwrap().is_some());
+            assert_eq!(
+                db.list_transactions(None, None, None).await.unwrap().len(),
+                1
+            );
+
+            // The keyset of a removed mint is dropped instead of failing the integrity check
+            assert_eq!(
+                db.get_mint_keysets(mint_url).await.unwrap().unwrap().len(),
+                1
+            );
+            assert!(db
+                .get_keyset_by_id(&Id::from_str("00916bbf7ef91a37").unwrap())
+                .await
+                .unwrap()
+                .is_none());
+
+            drop(db);
+
+            // Reopening a migrated database is a no-op
+            assert!(open(&file).await.is_ok());
+
+            let _ = std::fs::remove_file(&file);
+        }
+    }
+
+    #[tokio::test]
+    async fn refuses_database_of_newer_version() {
+        let file = temp_file();
+        drop(open(&file).await.unwrap());
+
+        load_dump(&file, "PRAGMA user_version = 100000").await;
+
+        let res = open(&file).await;
+        assert!(
+            matches!(
+                res,
+                Err(cdk_common::database::Error::NewerSchemaVersion(100000, _))
+            ),
+            "unexpected {res:?}"
+        );
+
+        let _ = std::fs::remove_file(&file);
+    }
+
+    #[tokio::test]
+    async fn refuses_proofs_of_another_unit_than_their_keyset() {
+        let file = temp_file();
+        drop(open(&file).await.unwrap());
+
+        load_dump(
+            &file,
+            r#"
+            INSERT INTO mint (mint_url) VALUES ('https://mint.example.com');
+            INSERT INTO keyset (id, mint_url, unit, active) VALUES ('00bf1340cbf71165', 'https://mint.example.com', 'sat', 1);
+            INSERT INTO proof (y, mint_url, state, unit, amount, keyset_id, secret, c)
+            VALUES (X'02', 'https://mint.example.com', 'UNSPENT', 'usd', 1, '00bf1340cbf71165', 'secret', X'02');
+            "#,
+        )
+        .await;
+
+        let res = open(&file).await;
+        assert!(
+            matches!(res, Err(cdk_common::database::Error::IntegrityCheck(_))),
+            "unexpected {res:?}"
+        );
+
+        let _ = std::fs::remove_file(&file);
+    }
 }
@@ -0,0 +1,145 @@
+PRAGMA foreign_keys=OFF;
+BEGIN TRANSACTION;
+CREATE TABLE key (
+    id TEXT PRIMARY KEY,
+    keys TEXT NOT NULL
+, keyset_u32 INTEGER);
+INSERT INTO "key" VALUES('00bf1340cbf71165','{"1":"031b84c5567b126440995d3ed5aaba0565d71e1834604819ff9c17f5e9d5dd078f","2":"024d4b6cd1361032ca9bd2aeb9d900aa4d45d9ead80ac9423374c451a7254d0766","4":"02531fe6068134503d2723133227c867ac8fa6c83c537e9a44c3c5bdbdcb1fe337","8":"03462779ad4aad39514614751a71085f2f10e1c7a593e4e030efb5b8721ce55b0b"}',1299527654);
+CREATE TABLE "keyset" (
+    id TEXT PRIMARY KEY,
+    mint_url TEXT NOT NULL,
+    keyset_u32 INTEGER,
+    unit TEXT NOT NULL,
+    active BOOL NOT NULL,
+    input_fee_ppk INTEGER,
+    final_expiry INTEGER DEFAULT NULL,
+    FOREIGN KEY(mint_url) REFERENCES mint(mint_url) ON UPDATE CASCADE ON DELETE CASCADE
+);
+INSERT INTO "keyset" VALUES('00bf1340cbf71165','https://mint.example.com',1299527654,'sat',1,100,NULL);
+INSERT INTO "keyset" VALUES('00916bbf7ef91a37','https://removed.example.com',NULL,'sat',1,0,NULL);
+CREATE TABLE keyset_counter (
+    keyset_id TEXT PRIMARY KEY,
+    counter INTEGER NOT NULL DEFAULT 0
+);
+INSERT INTO "keyset_counter" VALUES('00bf1340cbf71165',7);
+CREATE TABLE kv_store (
+    primary_namespace TEXT NOT NULL,
+    secondary_namespace TEXT NOT NULL,
+    key TEXT NOT NULL,
+    value BLOB NOT NULL,
+    created_time INTEGER NOT NULL,
+    updated_time INTEGER NOT NULL,
+    PRIMARY KEY (primary_namespace, secondary_namespace, key)
+);
+INSERT INTO "kv_store" VALUES('fixture','wallet','key',X'76616C7565',1792180068,1792180068);
+CREATE TABLE melt_quote (
+    id TEXT PRIMARY KEY,
+    unit TEXT NOT NULL,
+    amount INTEGER NOT NULL,
+    request TEXT NOT NULL,
+    fee_reserve INTEGER NOT NULL,
+    expiry INTEGER NOT NULL
+, state TEXT CHECK ( state IN ('UNPAID', 'PENDING', 'PAID' ) ) NOT NULL DEFAULT 'UNPAID', payment_preimage TEXT, payment_method TEXT NOT NULL DEFAULT 'bolt11', fees TEXT);
+INSERT INTO "melt_quote" VALUES('melt-quote-1','sat',4,'lnbc40n1fixture',1,1900000000,'PAID',NULL,'bolt11',NULL);
+CREATE TABLE migrations (
+               name TEXT PRIMARY KEY,
+               applied_at TIMESTAMP DEFAULT CURRENT_TIMESTAMP
+           );
+INSERT INTO "migrations" VALUES('1_fix_sqlx_migration.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240612132920_init.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240618200350_quote_state.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240626091921_nut04_state.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240710144711_input_fee.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240810214105_mint_icon_url.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240810233905_update_mint_url.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240902151515_icon_url.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20240902210905_mint_time.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20241011125207_mint_urls.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20241108092756_wallet_mint_quote_secretkey.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250214135017_mint_tos.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250310111513_drop_nostr_last_checked.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250314082116_allow_pending_spent.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250323152040_wallet_dleq_proofs.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250401120000_add_transactions_table.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250616144830_add_keyset_expiry.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250707093445_bolt12.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250729111701_keyset_v2_u32.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250812084621_keyset_plus_one.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250831215438_melt_quote_method.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20250906200000_add_transaction_quote_id.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251005120000_add_payment_info_to_transactions.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251021000000_pending_quotes_optimization.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251111000000_keyset_counter_table.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251215000000_add_kv_store.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251216000000_add_payment_method_to_transactions.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20251220000000_add_fees_to_melt_quote.sql','2026-10-16 19:47:48');
+INSERT INTO "migrations" VALUES('20261016000000_add_kind_to_transactions.sql','2026-10-16 19:47:48');
+CREATE TABLE mint (
+    mint_url TEXT PRIMARY KEY,
+    name TEXT,
+    pubkey BLOB,
+    version TEXT,
+    description TEXT,
+    description_long TEXT,
+    contact TEXT,
+    nuts TEXT,
+    motd TEXT
+, icon_url TEXT, mint_time INTEGER, urls TEXT, tos_url TEXT);
+INSERT INTO "mint" VALUES('https://mint.example.com',NULL,NULL,NULL,'fixture',NULL,NULL,'{"4":{"methods":[],"disabled":false},"5":{"methods":[],"disabled":false},"7":{"supported":false},"8":{"supported":false},"9":{"supported":false},"10":{"supported":false},"11":{"supported":false},"12":{"supported":false},"14":{"supported":false},"17":{"supported":[]},"19":{"ttl":null,"cached_endpoints":[]},"20":{"supported":false}}',NULL,NULL,NULL,NULL,NULL);
+CREATE TABLE "mint_quote" (
+    id TEXT PRIMARY KEY,
+    mint_url TEXT NOT NULL,
+    payment_method TEXT NOT NULL DEFAULT 'bolt11',
+    amount INTEGER,
+    unit TEXT NOT NULL,
+    request TEXT NOT NULL,
+    state TEXT NOT NULL,
+    expiry INTEGER NOT NULL,
+    amount_paid INTEGER NOT NULL DEFAULT 0,
+    amount_issued INTEGER NOT NULL DEFAULT 0,
+    secret_key TEXT
+, created_time INTEGER NOT NULL DEFAULT 0);
+INSERT INTO "mint_quote" VALUES('mint-quote-1','https://mint.example.com','bolt11',10,'sat','lnbc100n1fixture','UNPAID',1900000000,0,0,NULL,0);
+CREATE TABLE "proof" (
+y BLOB PRIMARY KEY,
+mint_url TEXT NOT NULL,
+state TEXT CHECK ( state IN ('SPENT', 'UNSPENT', 'PENDING', 'RESERVED', 'PENDING_SPENT' ) ) NOT NULL,
+spending_condition TEXT,
+unit TEXT NOT NULL,
+amount INTEGER NOT NULL,
+keyset_id TEXT NOT NULL,
+secret TEXT NOT NULL,
+c BLOB NOT NULL,
+witness TEXT
+, dleq_e BLOB, dleq_s BLOB, dleq_r BLOB);
+INSERT INTO "proof" VALUES(X'024D4CBFB78044F0FBB81BC512D98BF809C8810453C37E851AC0509E56F59A6FCB','https://mint.example.com','UNSPENT',NULL,'sat',8,'00bf1340cbf71165','0000000000000000000000000000000000000000000000000000000000000001',X'03FF8ADAB52623BCB2717FC71D7EDC6F55E98396E6C234DFF01F307A12B2AF1C99',NULL,NULL,NULL,NULL);
+INSERT INTO "proof" VALUES(X'026FCF9CD489D2C1EF52F67BF201BE833446271BFE5475070AB66C8A14B6542E4B','https://mint.example.com','UNSPENT',NULL,'sat',2,'00bf1340cbf71165','0000000000000000000000000000000000000000000000000000000000000002',X'03D793631AF7AA0E709439DD47FC001ACD0B0727670B6670EA528AC83CB0127F4A',NULL,NULL,NULL,NULL);
+INSERT INTO "proof" VALUES(X'0211771D10272121C7D26F549CB4BFF1C3818DA9E8FA11653ABCC5A41085195465','https://mint.example.com','RESERVED',NULL,'sat',1,'00bf1340cbf71165','0000000000000000000000000000000000000000000000000000000000000003',X'02A8397A935F0DFCEBA6BA9618F6451EF4D80637ABF4E6AF2669FBC9DE6A8FD2AC',NULL,NULL,NULL,NULL);
+INSERT INTO "proof" VALUES(X'02DE211F7687CA26336BAC3D7C61C6B9991B309DBD24FBA5F72332AC8D6D5A19BD','https://mint.example.com','SPENT',NULL,'sat',4,'00bf1340cbf71165','0000000000000000000000000000000000000000000000000000000000000004',X'0257EB3638F51F4DC5C8D5A7324B47DF99E816CFCC5B5EB1245BC8C98029F9E674',NULL,NULL,NULL,NULL);
+CREATE TABLE transactions (
+    id BLOB PRIMARY KEY,
+    mint_url TEXT NOT NULL,
+    direction TEXT CHECK (direction IN ('Incoming', 'Outgoing')) NOT NULL,
+    amount INTEGER NOT NULL,
+    fee INTEGER NOT NULL,
+    unit TEXT NOT NULL,
+    ys BLOB NOT NULL,
+    timestamp INTEGER NOT NULL,
+    memo TEXT,
+    metadata TEXT
+, quote_id TEXT, payment_request TEXT, payment_proof TEXT, payment_method TEXT, kind TEXT);
+INSERT INTO "transactions" VALUES(X'A73DE5B95322D24EC33D9917066F2E31026EABF6E3169CC951C68876C9F193D6','https://mint.example.com','Incoming',10,0,'sat',X'024D4CBFB78044F0FBB81BC512D98BF809C8810453C37E851AC0509E56F59A6FCB026FCF9CD489D2C1EF52F67BF201BE833446271BFE5475070AB66C8A14B6542E4B',1760000000,'fixture','{}','mint-quote-1',NULL,NULL,'bolt11','Mint');
+CREATE INDEX melt_quote_state_index ON melt_quote(state);
+CREATE INDEX mint_url_index ON transactions(mint_url);
+CREATE INDEX direction_index ON transactions(direction);
+CREATE INDEX unit_index ON transactions(unit);
+CREATE INDEX timestamp_index ON transactions(timestamp);
+CREATE UNIQUE INDEX keyset_u32_unique ON key(keyset_u32);
+CREATE INDEX idx_mint_quote_pending
+ON mint_quote(payment_method, amount_issued);
+CREATE INDEX idx_kv_store_namespaces
+ON kv_store (primary_namespace, secondary_namespace);
+CREATE INDEX idx_kv_store_updated_time
+ON kv_store (updated_time);
+CREATE INDEX kind_index ON transactions(kind);
+COMMIT;
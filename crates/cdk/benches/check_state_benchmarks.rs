@@ -0,0 +1,132 @@
+//! Database lookups of a NUT-07 state check
+//!
+//! Looks up the states and witnesses of a full check state request of 1000 Ys, half of them
+//! known to the mint, the way a state check can be answered:
+//!
+//! - one state and one proof lookup per Y
+//! - a batched state lookup followed by a batched proof lookup for the witnesses
+//! - a single batched lookup of states and witnesses, as [`Mint::check_state`] does
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use cdk::cdk_database::{MintDatabase, MintProofsDatabase};
+use cdk::mint::{Mint, MintBuilder, MintMeltLimits};
+use cdk::nuts::nut00::KnownMethod;
+use cdk::nuts::nut06::DEFAULT_MAX_CHECK_STATE_YS;
+use cdk::nuts::{
+    CurrencyUnit, P2PKWitness, PaymentMethod, Proof, Proofs, PublicKey, SecretKey, Witness,
+};
+use cdk::secret::Secret;
+use cdk::types::FeeReserve;
+use cdk::Amount;
+use cdk_common::mint::Operation;
+use cdk_fake_wallet::FakeWallet;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+
+type Db = Arc<cdk_sqlite::MintSqliteDatabase>;
+
+async fn create_mint(db: Db) -> Mint {
+    let fake_wallet = FakeWallet::new(
+        FeeReserve {
+            min_fee_reserve: 1.into(),
+            percent_fee_reserve: 1.0,
+        },
+        HashMap::default(),
+        HashSet::default(),
+        2,
+        CurrencyUnit::Sat,
+    );
+
+    let mut mint_builder = MintBuilder::new(db.clone());
+    mint_builder
+        .add_payment_processor(
+            CurrencyUnit::Sat,
+            PaymentMethod::Known(KnownMethod::Bolt11),
+            MintMeltLimits::new(1, 10_000),
+            Arc::new(fake_wallet),
+        )
+        .await
+        .unwrap();
+
+    mint_builder.build_with_seed(db, &[0u8; 32]).await.unwrap()
+}
+
+/// Stores `count` proofs of the active keyset, every other one with a witness
+async fn add_proofs(db: &Db, mint: &Mint, count: usize) -> Proofs {
+    let keyset_id = mint.pubkeys().keysets[0].id;
+    let proofs: Proofs = (0..count)
+        .map(|i| Proof {
+            amount: Amount::from(1),
+            keyset_id,
+            secret: Secret::generate(),
+            c: SecretKey::generate().public_key(),
+            witness: (i % 2 == 0).then(|| {
+                Witness::P2PKWitness(P2PKWitness {
+                    signatures: vec!["signature".to_string()],
+                })
+            }),
+            dleq: None,
+        })
+        .collect();
+
+    let mut tx = db.begin_transaction().await.unwrap();
+    tx.add_proofs(
+        proofs.clone(),
+        None,
+        &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+    )
+    .await
+    .unwrap();
+    tx.commit().await.unwrap();
+
+    proofs
+}
+
+async fn per_y_lookups(db: &Db, ys: &[PublicKey]) {
+    for y in ys {
+        let state = db.get_proofs_states(&[*y]).await.unwrap();
+        if state[0].is_some() {
+            db.get_proofs_by_ys(&[*y]).await.unwrap();
+        }
+    }
+}
+
+async fn state_then_proof_lookups(db: &Db, ys: &[PublicKey]) {
+    let states = db.get_proofs_states(ys).await.unwrap();
+    let known: Vec<_> = ys
+        .iter()
+        .zip(states)
+        .filter_map(|(y, state)| state.map(|_| *y))
+        .collect();
+    db.get_proofs_by_ys(&known).await.unwrap();
+}
+
+async fn single_lookup(db: &Db, ys: &[PublicKey]) {
+    db.get_proofs_states_with_witness(ys).await.unwrap();
+}
+
+fn bench_check_state(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let db: Db = rt.block_on(async { Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap()) });
+    let mint = rt.block_on(create_mint(db.clone()));
+
+    let known = rt.block_on(add_proofs(&db, &mint, DEFAULT_MAX_CHECK_STATE_YS / 2));
+    let mut ys: Vec<_> = known.iter().map(|p| p.y().unwrap()).collect();
+    ys.extend((ys.len()..DEFAULT_MAX_CHECK_STATE_YS).map(|_| SecretKey::generate().public_key()));
+
+    let mut group = c.benchmark_group("check state lookups of 1000 ys");
+    group.bench_function("per y", |b| b.iter(|| rt.block_on(per_y_lookups(&db, &ys))));
+    group.bench_function("states then proofs", |b| {
+        b.iter(|| rt.block_on(state_then_proof_lookups(&db, &ys)))
+    });
+    group.bench_function("single query", |b| {
+        b.iter(|| rt.block_on(single_lookup(&db, &ys)))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_check_state);
+criterion_main!(benches);
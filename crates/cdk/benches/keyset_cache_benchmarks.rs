@@ -0,0 +1,182 @@
+//! Keyset lookups of a swap
+//!
+//! Runs the keyset dependent steps of a swap (input verification, fees, output verification and
+//! blind signing) against a mint with many rotated keysets, and reports how many times the keys
+//! database is queried per swap. The keysets are served from memory, so it should be zero.
+
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use cdk::amount::SplitTarget;
+use cdk::cdk_database::{self, MintKeysDatabase};
+use cdk::dhke::construct_proofs;
+use cdk::mint::{Mint, MintBuilder, MintKeySetInfo, MintMeltLimits};
+use cdk::nuts::nut00::KnownMethod;
+use cdk::nuts::{CurrencyUnit, Id, Keys, PaymentMethod, PreMintSecrets, Proofs, ProofsMethods};
+use cdk::types::FeeReserve;
+use cdk::Amount;
+use cdk_common::database::MintKeyDatabaseTransaction;
+use cdk_fake_wallet::FakeWallet;
+use criterion::{criterion_group, criterion_main, Criterion};
+use tokio::runtime::Runtime;
+
+/// Number of keysets rotated out before the benchmark
+const ROTATIONS: usize = 50;
+
+/// Swaps run to count the keys database queries
+const COUNTED_SWAPS: usize = 100;
+
+/// Keys database counting the queries made to it
+struct CountingKeysDatabase {
+    inner: Arc<dyn MintKeysDatabase<Err = cdk_database::Error> + Send + Sync>,
+    queries: AtomicUsize,
+}
+
+impl CountingKeysDatabase {
+    fn count(&self) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[async_trait]
+impl MintKeysDatabase for CountingKeysDatabase {
+    type Err = cdk_database::Error;
+
+    async fn begin_transaction<'a>(
+        &'a self,
+    ) -> Result<
+        Box<dyn MintKeyDatabaseTransaction<'a, Self::Err> + Send + Sync + 'a>,
+        cdk_database::Error,
+    > {
+        self.count();
+        self.inner.begin_transaction().await
+    }
+
+    async fn get_active_keyset_id(&self, unit: &CurrencyUnit) -> Result<Option<Id>, Self::Err> {
+        self.count();
+        self.inner.get_active_keyset_id(unit).await
+    }
+
+    async fn get_active_keysets(&self) -> Result<HashMap<CurrencyUnit, Id>, Self::Err> {
+        self.count();
+        self.inner.get_active_keysets().await
+    }
+
+    async fn get_keyset_info(&self, id: &Id) -> Result<Option<MintKeySetInfo>, Self::Err> {
+        self.count();
+        self.inner.get_keyset_info(id).await
+    }
+
+    async fn get_keyset_infos(&self) -> Result<Vec<MintKeySetInfo>, Self::Err> {
+        self.count();
+        self.inner.get_keyset_infos().await
+    }
+
+    async fn get_keyset_keys(&self, id: &Id) -> Result<Option<Keys>, Self::Err> {
+        self.count();
+        self.inner.get_keyset_keys(id).await
+    }
+}
+
+async fn create_mint(keystore: Arc<CountingKeysDatabase>) -> Mint {
+    let db = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+    let fake_wallet = FakeWallet::new(
+        FeeReserve {
+            min_fee_reserve: 1.into(),
+            percent_fee_reserve: 1.0,
+        },
+        HashMap::default(),
+        HashSet::default(),
+        2,
+        CurrencyUnit::Sat,
+    );
+
+    let mut mint_builder = MintBuilder::new(db);
+    mint_builder
+        .add_payment_processor(
+            CurrencyUnit::Sat,
+            PaymentMethod::Known(KnownMethod::Bolt11),
+            MintMeltLimits::new(1, 10_000),
+            Arc::new(fake_wallet),
+        )
+        .await
+        .unwrap();
+
+    mint_builder
+        .build_with_seed(keystore, &[0u8; 32])
+        .await
+        .unwrap()
+}
+
+/// Signs `amount` with the active keyset and returns the proofs
+async fn mint_proofs(mint: &Mint, amount: Amount) -> Proofs {
+    let keyset = mint.pubkeys().keysets.remove(0);
+    let pre_mint = premint(mint, keyset.id, amount);
+    let signatures = mint.blind_sign(pre_mint.blinded_messages()).await.unwrap();
+
+    construct_proofs(signatures, pre_mint.rs(), pre_mint.secrets(), &keyset.keys).unwrap()
+}
+
+fn premint(mint: &Mint, keyset_id: Id, amount: Amount) -> PreMintSecrets {
+    let keyset = mint.get_keyset_info(&keyset_id).unwrap();
+    let fee_and_amounts = (keyset.input_fee_ppk, keyset.amounts).into();
+
+    PreMintSecrets::random(keyset_id, amount, &SplitTarget::default(), &fee_and_amounts).unwrap()
+}
+
+/// The keyset dependent steps of a swap of `inputs` into `outputs`
+async fn swap_keyset_steps(mint: &Mint, inputs: &Proofs, outputs: &PreMintSecrets) {
+    let input_verification = mint.verify_inputs(inputs).await.unwrap();
+    let outputs = outputs.blinded_messages();
+    mint.verify_outputs_balance_inputs(&input_verification, inputs, &outputs)
+        .await
+        .unwrap();
+    mint.blind_sign(outputs).await.unwrap();
+}
+
+fn bench_swap_keysets(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+
+    let keystore = Arc::new(CountingKeysDatabase {
+        inner: rt.block_on(async { Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap()) }),
+        queries: AtomicUsize::new(0),
+    });
+    let mint = rt.block_on(create_mint(keystore.clone()));
+
+    // Inputs spread over many inactive keysets, outputs of the active one
+    let mut inputs = Proofs::new();
+    for _ in 0..ROTATIONS {
+        inputs.extend(rt.block_on(mint_proofs(&mint, Amount::from(4))));
+        rt.block_on(mint.rotate_keyset(
+            CurrencyUnit::Sat,
+            (0..32).map(|x| 2u64.pow(x)).collect(),
+            0,
+        ))
+        .unwrap();
+    }
+    let active_id = mint.pubkeys().keysets[0].id;
+    let outputs = premint(&mint, active_id, inputs.total_amount().unwrap());
+
+    let queries_before = keystore.queries.load(Ordering::Relaxed);
+    rt.block_on(async {
+        for _ in 0..COUNTED_SWAPS {
+            swap_keyset_steps(&mint, &inputs, &outputs).await;
+        }
+    });
+    let queries = keystore.queries.load(Ordering::Relaxed) - queries_before;
+    println!(
+        "keys database queries per swap of {} inputs over {} keysets: {}",
+        inputs.len(),
+        ROTATIONS,
+        queries as f64 / COUNTED_SWAPS as f64
+    );
+
+    c.bench_function("swap keyset steps", |b| {
+        b.iter(|| rt.block_on(swap_keyset_steps(&mint, &inputs, &outputs)))
+    });
+}
+
+criterion_group!(benches, bench_swap_keysets);
+criterion_main!(benches);
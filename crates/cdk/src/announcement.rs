@@ -0,0 +1,63 @@
+//! Mint announcements
+//!
+//! Mints sign their announcements (MOTD changes, maintenance notices) with the pubkey of their
+//! [`MintInfo`], a wallet checks them before showing them to the user.
+
+use bitcoin::secp256k1::schnorr::Signature;
+
+use crate::error::Error;
+use crate::nuts::MintInfo;
+use crate::util::unix_time;
+
+/// Verify that `message` was signed by the mint of `mint_info`
+///
+/// The signature is a BIP-340 signature over the sha256 of `message`, made with the current pubkey
+/// of the mint or with a previous one that is still in its grace period.
+pub fn verify_mint_announcement(
+    mint_info: &MintInfo,
+    message: &[u8],
+    signature: &Signature,
+) -> Result<(), Error> {
+    if mint_info
+        .announcement_pubkeys(unix_time())
+        .any(|pubkey| pubkey.verify(message, signature).is_ok())
+    {
+        Ok(())
+    } else {
+        Err(Error::InvalidAnnouncementSignature)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nuts::{PreviousPubkey, SecretKey};
+
+    #[test]
+    fn previous_pubkey_verifies_until_it_expires() {
+        let current = SecretKey::generate();
+        let previous = SecretKey::generate();
+        let now = unix_time();
+
+        let mut mint_info = MintInfo {
+            pubkey: Some(current.public_key()),
+            previous_pubkeys: vec![PreviousPubkey {
+                pubkey: previous.public_key(),
+                expires_at: now + 60,
+            }],
+            ..Default::default()
+        };
+
+        let signature = current.sign(b"motd").unwrap();
+        assert!(verify_mint_announcement(&mint_info, b"motd", &signature).is_ok());
+
+        let signature = previous.sign(b"motd").unwrap();
+        assert!(verify_mint_announcement(&mint_info, b"motd", &signature).is_ok());
+
+        mint_info.previous_pubkeys[0].expires_at = now - 1;
+        assert!(verify_mint_announcement(&mint_info, b"motd", &signature).is_err());
+
+        let signature = SecretKey::generate().sign(b"motd").unwrap();
+        assert!(verify_mint_announcement(&mint_info, b"motd", &signature).is_err());
+    }
+}
@@ -3,7 +3,9 @@ use std::fmt::Debug;
 use std::hash::Hash;
 use std::ops::Deref;
 
-use cdk_common::nut17::NotificationId;
+use cdk_common::nut17::{
+    InternalSettlementNotification, MeltProgressNotification, MintInfoNotification, NotificationId,
+};
 use cdk_common::pub_sub::Event;
 use cdk_common::{
     MeltQuoteBolt11Response, MintQuoteBolt11Response, MintQuoteBolt12Response, NotificationPayload,
@@ -104,6 +106,33 @@ where
     }
 }
 
+impl<T> From<InternalSettlementNotification<T>> for MintEvent<T>
+where
+    T: Clone + Eq + PartialEq,
+{
+    fn from(value: InternalSettlementNotification<T>) -> Self {
+        Self(NotificationPayload::InternalSettlement(value))
+    }
+}
+
+impl<T> From<MeltProgressNotification<T>> for MintEvent<T>
+where
+    T: Clone + Eq + PartialEq,
+{
+    fn from(value: MeltProgressNotification<T>) -> Self {
+        Self(NotificationPayload::MeltProgress(value))
+    }
+}
+
+impl<T> From<MintInfoNotification> for MintEvent<T>
+where
+    T: Clone + Eq + PartialEq,
+{
+    fn from(value: MintInfoNotification) -> Self {
+        Self(NotificationPayload::MintInfo(value))
+    }
+}
+
 impl<T> Event for MintEvent<T>
 where
     T: Clone + Serialize + DeserializeOwned + Debug + Ord + Hash + Send + Sync + Eq + PartialEq,
@@ -122,6 +151,13 @@ where
                 NotificationId::MintQuoteBolt12(r.quote.to_owned())
             }
             NotificationPayload::ProofState(p) => NotificationId::ProofState(p.y.to_owned()),
+            NotificationPayload::InternalSettlement(s) => {
+                NotificationId::InternalSettlement(s.melt_quote.to_owned())
+            }
+            NotificationPayload::MeltProgress(p) => {
+                NotificationId::MeltProgress(p.quote.to_owned())
+            }
+            NotificationPayload::MintInfo(_) => NotificationId::MintInfo,
         }]
     }
 }
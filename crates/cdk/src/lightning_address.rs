@@ -6,11 +6,18 @@
 //!
 //! Lightning addresses are converted to LNURL-pay endpoints following the spec:
 //! <https://domain.com/.well-known/lnurlp/user>
+//!
+//! Bech32 encoded LNURLs (LUD-01) point to the LNURL-pay endpoint directly. Invoices returned by
+//! an endpoint are checked to be for the requested amount and to commit to the metadata of the
+//! endpoint (LUD-06) before they are paid.
 
 use std::str::FromStr;
 use std::sync::Arc;
 
-use lightning_invoice::Bolt11Invoice;
+use bitcoin::bech32;
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use lightning_invoice::{Bolt11Invoice, Bolt11InvoiceDescriptionRef};
 use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use tracing::instrument;
@@ -46,6 +53,18 @@ pub enum Error {
     /// Failed to parse invoice
     #[error("Failed to parse invoice: {0}")]
     InvoiceParse(String),
+    /// Invalid bech32 encoded LNURL
+    #[error("Invalid LNURL: {0}")]
+    InvalidLnurl(String),
+    /// Comment longer than the service accepts
+    #[error("Comment of {length} characters is longer than the {max} characters allowed")]
+    CommentTooLong { length: usize, max: u64 },
+    /// Invoice is not for the requested amount
+    #[error("Invoice amount {invoice:?} msat does not match the requested {expected} msat")]
+    InvoiceAmountMismatch { expected: u64, invoice: Option<u64> },
+    /// Invoice does not commit to the metadata of the service
+    #[error("Invoice description hash does not match the LNURL metadata")]
+    DescriptionHashMismatch,
 }
 
 /// Lightning address - represents a user@domain.com address
@@ -64,6 +83,72 @@ impl LightningAddress {
         let url_str = format!("https://{}/.well-known/lnurlp/{}", self.domain, self.user);
         Ok(Url::parse(&url_str)?)
     }
+}
+
+impl FromStr for LightningAddress {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let trimmed = s.trim();
+
+        // Parse Lightning address (user@domain)
+        if !trimmed.contains('@') {
+            return Err(Error::InvalidFormat("must contain '@'".to_string()));
+        }
+
+        let parts: Vec<&str> = trimmed.split('@').collect();
+        if parts.len() != 2 {
+            return Err(Error::InvalidFormat("must be user@domain".to_string()));
+        }
+
+        let user = parts[0].trim();
+        let domain = parts[1].trim();
+
+        if user.is_empty() || domain.is_empty() {
+            return Err(Error::InvalidFormat(
+                "user and domain must not be empty".to_string(),
+            ));
+        }
+
+        Ok(LightningAddress {
+            user: user.to_string(),
+            domain: domain.to_string(),
+        })
+    }
+}
+
+impl std::fmt::Display for LightningAddress {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}@{}", self.user, self.domain)
+    }
+}
+
+/// LNURL-pay endpoint, given as a Lightning address or a bech32 encoded LNURL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum LnurlPayEndpoint {
+    /// Lightning address (user@domain.com)
+    Address(LightningAddress),
+    /// URL decoded from a bech32 encoded LNURL
+    Lnurl(Url),
+}
+
+/// Invoice returned by a LNURL-pay endpoint
+#[derive(Debug, Clone)]
+pub(crate) struct LnurlPayInvoice {
+    /// Invoice to pay
+    pub invoice: Bolt11Invoice,
+    /// Success action to show once the invoice is paid
+    pub success_action: Option<SuccessAction>,
+}
+
+impl LnurlPayEndpoint {
+    /// URL of the LNURL-pay endpoint
+    fn to_url(&self) -> Result<Url, Error> {
+        match self {
+            Self::Address(address) => address.to_url(),
+            Self::Lnurl(url) => Ok(url.clone()),
+        }
+    }
 
     /// Fetch the LNURL-pay metadata from the service
     #[instrument(skip(client))]
@@ -73,7 +158,7 @@ impl LightningAddress {
     ) -> Result<LnurlPayResponse, Error> {
         let url = self.to_url()?;
 
-        tracing::debug!("Fetching Lightning address pay data from: {}", url);
+        tracing::debug!("Fetching LNURL-pay data from: {}", url);
 
         // Make HTTP GET request to fetch the pay request data
         let lnurl_response = client.fetch_lnurl_pay_request(url.as_str()).await?;
@@ -86,89 +171,79 @@ impl LightningAddress {
         Ok(lnurl_response)
     }
 
-    /// Request an invoice from the Lightning address service with a specific amount
+    /// Request an invoice from the LNURL-pay service with a specific amount
+    ///
+    /// The invoice is verified to be for `amount_msat` and to commit to the metadata of the
+    /// service.
     #[instrument(skip(client))]
     pub(crate) async fn request_invoice(
         &self,
         client: &Arc<dyn MintConnector + Send + Sync>,
         amount_msat: Amount,
-    ) -> Result<Bolt11Invoice, Error> {
+        comment: Option<&str>,
+    ) -> Result<LnurlPayInvoice, Error> {
         let pay_data = self.fetch_pay_request_data(client).await?;
 
-        // Validate amount is within acceptable range
-        let amount_msat_u64: u64 = amount_msat.into();
-        if amount_msat_u64 < pay_data.min_sendable {
-            return Err(Error::AmountBelowMinimum {
-                amount: amount_msat_u64,
-                min: pay_data.min_sendable,
-            });
-        }
-        if amount_msat_u64 > pay_data.max_sendable {
-            return Err(Error::AmountAboveMaximum {
-                amount: amount_msat_u64,
-                max: pay_data.max_sendable,
-            });
-        }
-
-        // Build callback URL with amount parameter
-        let mut callback_url = Url::parse(&pay_data.callback)?;
-
-        callback_url
-            .query_pairs_mut()
-            .append_pair("amount", &amount_msat_u64.to_string());
+        let amount_msat: u64 = amount_msat.into();
+        let callback_url = pay_data.callback_url(amount_msat, comment)?;
 
         tracing::debug!("Requesting invoice from callback: {}", callback_url);
 
         // Fetch the invoice
         let invoice_response = client.fetch_lnurl_invoice(callback_url.as_str()).await?;
 
-        // Check for errors
-        if let Some(ref reason) = invoice_response.reason {
-            return Err(Error::Service(reason.clone()));
-        }
-
-        // Parse and return the invoice
-        let pr = invoice_response.pr.ok_or(Error::NoInvoice)?;
-
-        Bolt11Invoice::from_str(&pr).map_err(|e| Error::InvoiceParse(e.to_string()))
+        pay_data.verify_invoice_response(invoice_response, amount_msat)
     }
 }
 
-impl FromStr for LightningAddress {
+impl FromStr for LnurlPayEndpoint {
     type Err = Error;
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let trimmed = s.trim();
+        let trimmed = match trimmed.get(..10) {
+            Some(scheme) if scheme.eq_ignore_ascii_case("lightning:") => &trimmed[10..],
+            _ => trimmed,
+        };
 
-        // Parse Lightning address (user@domain)
-        if !trimmed.contains('@') {
-            return Err(Error::InvalidFormat("must contain '@'".to_string()));
+        match trimmed.get(..6) {
+            Some(prefix) if prefix.eq_ignore_ascii_case("lnurl1") => {
+                decode_lnurl(trimmed).map(Self::Lnurl)
+            }
+            _ => LightningAddress::from_str(trimmed).map(Self::Address),
         }
+    }
+}
 
-        let parts: Vec<&str> = trimmed.split('@').collect();
-        if parts.len() != 2 {
-            return Err(Error::InvalidFormat("must be user@domain".to_string()));
+impl std::fmt::Display for LnurlPayEndpoint {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Address(address) => write!(f, "{address}"),
+            Self::Lnurl(url) => write!(f, "{url}"),
         }
+    }
+}
 
-        let user = parts[0].trim();
-        let domain = parts[1].trim();
-
-        if user.is_empty() || domain.is_empty() {
-            return Err(Error::InvalidFormat(
-                "user and domain must not be empty".to_string(),
-            ));
-        }
+/// Decode a bech32 encoded LNURL to its URL
+///
+/// Only HTTPS URLs are accepted, and plain HTTP for onion services (LUD-01).
+fn decode_lnurl(lnurl: &str) -> Result<Url, Error> {
+    let (hrp, data) = bech32::decode(lnurl).map_err(|e| Error::InvalidLnurl(e.to_string()))?;
 
-        Ok(LightningAddress {
-            user: user.to_string(),
-            domain: domain.to_string(),
-        })
+    if !hrp.as_str().eq_ignore_ascii_case("lnurl") {
+        return Err(Error::InvalidLnurl(format!("unexpected prefix {hrp}")));
     }
-}
 
-impl std::fmt::Display for LightningAddress {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}@{}", self.user, self.domain)
+    let url = String::from_utf8(data).map_err(|e| Error::InvalidLnurl(e.to_string()))?;
+    let url = Url::parse(&url)?;
+
+    let onion = url.host_str().is_some_and(|host| host.ends_with(".onion"));
+    match url.scheme() {
+        "https" => Ok(url),
+        "http" if onion => Ok(url),
+        scheme => Err(Error::InvalidLnurl(format!(
+            "{scheme} URLs are not allowed"
+        ))),
     }
 }
 
@@ -190,6 +265,121 @@ pub struct LnurlPayResponse {
     pub tag: Option<String>,
     /// Optional error reason
     pub reason: Option<String>,
+    /// Maximum length of a comment, comments are not accepted if missing (LUD-12)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_allowed: Option<u64>,
+}
+
+impl LnurlPayResponse {
+    /// Callback URL requesting an invoice for `amount_msat`
+    fn callback_url(&self, amount_msat: u64, comment: Option<&str>) -> Result<Url, Error> {
+        if let Some(tag) = self.tag.as_deref().filter(|tag| *tag != "payRequest") {
+            return Err(Error::Service(format!("not a pay request: {tag}")));
+        }
+
+        // Validate amount is within acceptable range
+        if amount_msat < self.min_sendable {
+            return Err(Error::AmountBelowMinimum {
+                amount: amount_msat,
+                min: self.min_sendable,
+            });
+        }
+        if amount_msat > self.max_sendable {
+            return Err(Error::AmountAboveMaximum {
+                amount: amount_msat,
+                max: self.max_sendable,
+            });
+        }
+
+        // Build callback URL with amount parameter
+        let mut callback_url = Url::parse(&self.callback)?;
+
+        callback_url
+            .query_pairs_mut()
+            .append_pair("amount", &amount_msat.to_string());
+
+        if let Some(comment) = comment.filter(|comment| !comment.is_empty()) {
+            let length = comment.chars().count();
+            let max = self.comment_allowed.unwrap_or_default();
+            if length as u64 > max {
+                return Err(Error::CommentTooLong { length, max });
+            }
+
+            callback_url
+                .query_pairs_mut()
+                .append_pair("comment", comment);
+        }
+
+        Ok(callback_url)
+    }
+
+    /// Verify the response of the callback for `amount_msat`
+    fn verify_invoice_response(
+        &self,
+        response: LnurlPayInvoiceResponse,
+        amount_msat: u64,
+    ) -> Result<LnurlPayInvoice, Error> {
+        // Check for errors
+        if let Some(ref reason) = response.reason {
+            return Err(Error::Service(reason.clone()));
+        }
+
+        let pr = response.pr.ok_or(Error::NoInvoice)?;
+        let invoice =
+            Bolt11Invoice::from_str(&pr).map_err(|e| Error::InvoiceParse(e.to_string()))?;
+
+        let invoice_amount = invoice.amount_milli_satoshis();
+        if invoice_amount != Some(amount_msat) {
+            return Err(Error::InvoiceAmountMismatch {
+                expected: amount_msat,
+                invoice: invoice_amount,
+            });
+        }
+
+        let metadata_hash = Sha256Hash::hash(self.metadata.as_bytes());
+        match invoice.description() {
+            Bolt11InvoiceDescriptionRef::Hash(hash) if hash.0 == metadata_hash => {}
+            _ => return Err(Error::DescriptionHashMismatch),
+        }
+
+        let success_action = response.success_action.and_then(|success_action| {
+            serde_json::from_value(success_action)
+                .inspect_err(|e| tracing::debug!("Ignoring unknown success action: {}", e))
+                .ok()
+        });
+
+        Ok(LnurlPayInvoice {
+            invoice,
+            success_action,
+        })
+    }
+}
+
+/// Action to show the payer once a LNURL-pay invoice is paid (LUD-09)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "tag", rename_all = "lowercase")]
+pub enum SuccessAction {
+    /// Message to show
+    Message {
+        /// The message
+        message: String,
+    },
+    /// URL to open
+    Url {
+        /// Description of the URL
+        description: String,
+        /// The URL
+        url: String,
+    },
+    /// Message encrypted with the payment preimage (LUD-10), it is not decrypted
+    Aes {
+        /// Description of the message
+        description: String,
+        /// Base64 encoded encrypted message
+        ciphertext: String,
+        /// Base64 encoded initialization vector
+        iv: String,
+    },
 }
 
 /// LNURL-pay invoice response from the callback
@@ -198,6 +388,7 @@ pub struct LnurlPayInvoiceResponse {
     /// The BOLT11 payment request (invoice)
     pub pr: Option<String>,
     /// Optional success action
+    #[serde(rename = "successAction", alias = "success_action")]
     pub success_action: Option<serde_json::Value>,
     /// Optional routes (deprecated)
     pub routes: Option<Vec<serde_json::Value>>,
@@ -207,8 +398,63 @@ pub struct LnurlPayInvoiceResponse {
 
 #[cfg(test)]
 mod tests {
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+
     use super::*;
 
+    const METADATA: &str =
+        r#"[["text/plain","Pay alice"],["text/identifier","alice@example.com"]]"#;
+
+    /// Pay request as served by the `.well-known/lnurlp` endpoint of a service
+    fn pay_request() -> LnurlPayResponse {
+        serde_json::from_value(serde_json::json!({
+            "callback": "https://example.com/lnurlp/alice/callback?id=7",
+            "minSendable": 1_000,
+            "maxSendable": 1_000_000,
+            "metadata": METADATA,
+            "tag": "payRequest",
+            "commentAllowed": 10
+        }))
+        .unwrap()
+    }
+
+    fn invoice(amount_msat: u64, description_hash: Option<Sha256Hash>) -> String {
+        let private_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let builder = InvoiceBuilder::new(Currency::Bitcoin)
+            .payment_hash(Sha256Hash::hash(&[1]))
+            .payment_secret(PaymentSecret([42u8; 32]))
+            .amount_milli_satoshis(amount_msat)
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(144);
+
+        let builder = match description_hash {
+            Some(hash) => builder.description_hash(hash),
+            None => builder.description("Pay alice".to_string()),
+        };
+
+        builder
+            .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &private_key))
+            .unwrap()
+            .to_string()
+    }
+
+    /// Response of the callback of a service
+    fn invoice_response(pr: String, success_action: serde_json::Value) -> LnurlPayInvoiceResponse {
+        serde_json::from_value(serde_json::json!({
+            "pr": pr,
+            "routes": [],
+            "successAction": success_action
+        }))
+        .unwrap()
+    }
+
+    fn lnurl(url: &str) -> String {
+        bech32::encode::<bech32::Bech32>(bech32::Hrp::parse("lnurl").unwrap(), url.as_bytes())
+            .unwrap()
+            .to_uppercase()
+    }
+
     #[test]
     fn test_lightning_address_parsing() {
         let addr = LightningAddress::from_str("satoshi@bitcoin.org").unwrap();
@@ -235,4 +481,193 @@ mod tests {
         assert!(LightningAddress::from_str("user@").is_err());
         assert!(LightningAddress::from_str("user").is_err());
     }
+
+    #[test]
+    fn test_lnurl_pay_endpoint_parsing() {
+        let endpoint = LnurlPayEndpoint::from_str("alice@example.com").unwrap();
+        assert_eq!(
+            endpoint.to_url().unwrap().as_str(),
+            "https://example.com/.well-known/lnurlp/alice"
+        );
+
+        let url = "https://example.com/lnurlp/alice?id=7";
+        let endpoint = LnurlPayEndpoint::from_str(&lnurl(url)).unwrap();
+        assert_eq!(endpoint.to_url().unwrap().as_str(), url);
+
+        // Lowercase and with the lightning: scheme
+        let endpoint =
+            LnurlPayEndpoint::from_str(&format!("lightning:{}", lnurl(url).to_lowercase()))
+                .unwrap();
+        assert_eq!(endpoint, LnurlPayEndpoint::Lnurl(Url::parse(url).unwrap()));
+
+        // Plain HTTP only for onion services
+        assert!(matches!(
+            LnurlPayEndpoint::from_str(&lnurl("http://example.com/lnurlp/alice")),
+            Err(Error::InvalidLnurl(_))
+        ));
+        assert!(LnurlPayEndpoint::from_str(&lnurl("http://alice.onion/lnurlp")).is_ok());
+
+        assert!(matches!(
+            LnurlPayEndpoint::from_str("lnurl1invalid"),
+            Err(Error::InvalidLnurl(_))
+        ));
+        assert!(LnurlPayEndpoint::from_str("invalid").is_err());
+    }
+
+    #[test]
+    fn test_callback_url() {
+        let pay_request = pay_request();
+
+        let url = pay_request.callback_url(21_000, None).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/lnurlp/alice/callback?id=7&amount=21000"
+        );
+
+        let url = pay_request.callback_url(21_000, Some("thanks")).unwrap();
+        assert_eq!(
+            url.as_str(),
+            "https://example.com/lnurlp/alice/callback?id=7&amount=21000&comment=thanks"
+        );
+
+        assert!(matches!(
+            pay_request.callback_url(999, None),
+            Err(Error::AmountBelowMinimum {
+                amount: 999,
+                min: 1_000
+            })
+        ));
+        assert!(matches!(
+            pay_request.callback_url(1_000_001, None),
+            Err(Error::AmountAboveMaximum { .. })
+        ));
+        assert!(pay_request.callback_url(1_000, None).is_ok());
+        assert!(pay_request.callback_url(1_000_000, None).is_ok());
+    }
+
+    #[test]
+    fn test_callback_url_comment_limit() {
+        let mut pay_request = pay_request();
+
+        assert!(pay_request.callback_url(21_000, Some("ten chars!")).is_ok());
+        assert!(matches!(
+            pay_request.callback_url(21_000, Some("eleven chars")),
+            Err(Error::CommentTooLong {
+                length: 12,
+                max: 10
+            })
+        ));
+
+        // Comments are rejected by services that don't accept them
+        pay_request.comment_allowed = None;
+        assert!(matches!(
+            pay_request.callback_url(21_000, Some("hi")),
+            Err(Error::CommentTooLong { length: 2, max: 0 })
+        ));
+        assert!(pay_request.callback_url(21_000, Some("")).is_ok());
+    }
+
+    #[test]
+    fn test_callback_url_rejects_other_requests() {
+        let mut pay_request = pay_request();
+        pay_request.tag = Some("withdrawRequest".to_string());
+
+        assert!(matches!(
+            pay_request.callback_url(21_000, None),
+            Err(Error::Service(_))
+        ));
+    }
+
+    #[test]
+    fn test_verify_invoice_response() {
+        let pay_request = pay_request();
+        let metadata_hash = Sha256Hash::hash(METADATA.as_bytes());
+
+        let response = invoice_response(
+            invoice(21_000, Some(metadata_hash)),
+            serde_json::json!({"tag": "message", "message": "Thanks!"}),
+        );
+        let pay_invoice = pay_request
+            .verify_invoice_response(response, 21_000)
+            .unwrap();
+        assert_eq!(pay_invoice.invoice.amount_milli_satoshis(), Some(21_000));
+        assert_eq!(
+            pay_invoice.success_action,
+            Some(SuccessAction::Message {
+                message: "Thanks!".to_string()
+            })
+        );
+
+        let response = invoice_response(
+            invoice(21_000, Some(metadata_hash)),
+            serde_json::json!({
+                "tag": "url",
+                "description": "Your receipt",
+                "url": "https://example.com/receipt"
+            }),
+        );
+        assert!(matches!(
+            pay_request
+                .verify_invoice_response(response, 21_000)
+                .unwrap()
+                .success_action,
+            Some(SuccessAction::Url { .. })
+        ));
+
+        // Unknown success actions are ignored
+        let response = invoice_response(
+            invoice(21_000, Some(metadata_hash)),
+            serde_json::json!({"tag": "unknown"}),
+        );
+        assert_eq!(
+            pay_request
+                .verify_invoice_response(response, 21_000)
+                .unwrap()
+                .success_action,
+            None
+        );
+    }
+
+    #[test]
+    fn test_verify_invoice_response_rejects_wrong_invoices() {
+        let pay_request = pay_request();
+        let metadata_hash = Sha256Hash::hash(METADATA.as_bytes());
+
+        let response = invoice_response(
+            invoice(21_001, Some(metadata_hash)),
+            serde_json::Value::Null,
+        );
+        assert!(matches!(
+            pay_request.verify_invoice_response(response, 21_000),
+            Err(Error::InvoiceAmountMismatch {
+                expected: 21_000,
+                invoice: Some(21_001)
+            })
+        ));
+
+        let response = invoice_response(
+            invoice(21_000, Some(Sha256Hash::hash(b"other metadata"))),
+            serde_json::Value::Null,
+        );
+        assert!(matches!(
+            pay_request.verify_invoice_response(response, 21_000),
+            Err(Error::DescriptionHashMismatch)
+        ));
+
+        let response = invoice_response(invoice(21_000, None), serde_json::Value::Null);
+        assert!(matches!(
+            pay_request.verify_invoice_response(response, 21_000),
+            Err(Error::DescriptionHashMismatch)
+        ));
+
+        let response: LnurlPayInvoiceResponse = serde_json::from_value(serde_json::json!({
+            "status": "ERROR",
+            "reason": "amount too low"
+        }))
+        .unwrap();
+        assert!(matches!(
+            pay_request.verify_invoice_response(response, 21_000),
+            Err(Error::Service(reason)) if reason == "amount too low"
+        ));
+    }
 }
@@ -0,0 +1,196 @@
+//! Mint announcements
+//!
+//! The mint signs its announcements (MOTD changes, maintenance notices) with a key the signatory
+//! derives apart from the keysets. Its pubkey is the NUT-06 pubkey, and after a rotation the
+//! previous pubkey stays in the mint info for a grace period so earlier announcements still verify.
+
+use std::time::Duration;
+
+use bitcoin::secp256k1::schnorr::Signature;
+use cdk_common::database::DynMintDatabase;
+use cdk_common::util::unix_time;
+use tracing::instrument;
+
+use super::{
+    Mint, CDK_MINT_CONFIG_KV_KEY, CDK_MINT_CONFIG_SECONDARY_NAMESPACE, CDK_MINT_PRIMARY_NAMESPACE,
+};
+use crate::nuts::{MintInfo, PreviousPubkey, PublicKey};
+use crate::Error;
+
+const CDK_MINT_ANNOUNCEMENT_KV_KEY: &str = "announcement_key";
+
+/// Time the signatory pubkey, advertised before the announcement key existed, stays in the mint
+/// info once a mint is upgraded
+pub(super) const LEGACY_PUBKEY_GRACE_PERIOD: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+impl Mint {
+    /// Index of the announcement key in use, `0` until the first rotation
+    pub(super) async fn announcement_key_index(localstore: &DynMintDatabase) -> Result<u32, Error> {
+        match localstore
+            .kv_read(
+                CDK_MINT_PRIMARY_NAMESPACE,
+                CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+                CDK_MINT_ANNOUNCEMENT_KV_KEY,
+            )
+            .await?
+        {
+            Some(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            None => Ok(0),
+        }
+    }
+
+    /// Pubkey of the announcement key in use
+    #[instrument(skip_all)]
+    pub async fn announcement_pubkey(&self) -> Result<PublicKey, Error> {
+        let index = Self::announcement_key_index(&self.localstore).await?;
+        self.signatory.announcement_pubkey(index).await
+    }
+
+    /// Sign an announcement with the announcement key in use
+    ///
+    /// Wallets check it with [`crate::announcement::verify_mint_announcement`].
+    #[instrument(skip_all)]
+    pub async fn sign_announcement(&self, message: &[u8]) -> Result<Signature, Error> {
+        let index = Self::announcement_key_index(&self.localstore).await?;
+        self.signatory
+            .sign_announcement(index, message.to_vec())
+            .await
+    }
+
+    /// Move to the next announcement key and advertise it in the mint info
+    ///
+    /// The pubkey advertised until now is kept in the mint info for `grace_period`, previous
+    /// pubkeys past their grace period are dropped. Returns the new pubkey.
+    #[instrument(skip(self))]
+    pub async fn rotate_announcement_key(
+        &self,
+        grace_period: Duration,
+    ) -> Result<PublicKey, Error> {
+        loop {
+            let mut tx = self.localstore.begin_transaction().await?;
+
+            let current = tx
+                .kv_read(
+                    CDK_MINT_PRIMARY_NAMESPACE,
+                    CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+                    CDK_MINT_ANNOUNCEMENT_KV_KEY,
+                )
+                .await?;
+            let index: u32 = match &current {
+                Some(bytes) => serde_json::from_slice(bytes)?,
+                None => 0,
+            };
+            let next_index = index + 1;
+            let pubkey = self.signatory.announcement_pubkey(next_index).await?;
+
+            // Another rotation moved the index since it was read
+            if !tx
+                .kv_compare_and_swap(
+                    CDK_MINT_PRIMARY_NAMESPACE,
+                    CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+                    CDK_MINT_ANNOUNCEMENT_KV_KEY,
+                    current.as_deref(),
+                    &serde_json::to_vec(&next_index)?,
+                )
+                .await?
+            {
+                tx.rollback().await?;
+                continue;
+            }
+
+            let mut mint_info: MintInfo = serde_json::from_slice(
+                &tx.kv_read(
+                    CDK_MINT_PRIMARY_NAMESPACE,
+                    CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+                    CDK_MINT_CONFIG_KV_KEY,
+                )
+                .await?
+                .ok_or(Error::CouldNotGetMintInfo)?,
+            )?;
+
+            let now = unix_time();
+            mint_info
+                .previous_pubkeys
+                .retain(|previous| previous.expires_at > now);
+            if let Some(previous) = mint_info.pubkey.replace(pubkey) {
+                mint_info.previous_pubkeys.push(PreviousPubkey {
+                    pubkey: previous,
+                    expires_at: now + grace_period.as_secs(),
+                });
+            }
+
+            tx.kv_write(
+                CDK_MINT_PRIMARY_NAMESPACE,
+                CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+                CDK_MINT_CONFIG_KV_KEY,
+                &serde_json::to_vec(&mint_info)?,
+            )
+            .await?;
+            tx.commit().await?;
+
+            tracing::info!("Rotated announcement key to index {}", next_index);
+
+            return Ok(pubkey);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::announcement::verify_mint_announcement;
+    use crate::test_helpers::mint::create_test_mint;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn announcement_key_is_the_mint_info_pubkey() {
+        let mint = create_test_mint().await.unwrap();
+
+        let mint_info = mint.mint_info().await.unwrap();
+        let pubkey = mint.announcement_pubkey().await.unwrap();
+        assert_eq!(mint_info.pubkey, Some(pubkey));
+        assert!(mint_info.previous_pubkeys.is_empty());
+
+        let signature = mint
+            .sign_announcement(b"new fees from monday")
+            .await
+            .unwrap();
+        assert!(verify_mint_announcement(&mint_info, b"new fees from monday", &signature).is_ok());
+        assert!(matches!(
+            verify_mint_announcement(&mint_info, b"no new fees", &signature),
+            Err(Error::InvalidAnnouncementSignature)
+        ));
+    }
+
+    #[tokio::test]
+    async fn rotated_announcement_key_keeps_previous_for_grace_period() {
+        let mint = create_test_mint().await.unwrap();
+
+        let before = mint.announcement_pubkey().await.unwrap();
+        let old_signature = mint.sign_announcement(b"maintenance").await.unwrap();
+
+        let pubkey = mint
+            .rotate_announcement_key(Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_ne!(pubkey, before);
+        assert_eq!(mint.announcement_pubkey().await.unwrap(), pubkey);
+
+        let mint_info = mint.mint_info().await.unwrap();
+        assert_eq!(mint_info.pubkey, Some(pubkey));
+        assert_eq!(mint_info.previous_pubkeys.len(), 1);
+        assert_eq!(mint_info.previous_pubkeys[0].pubkey, before);
+
+        let new_signature = mint.sign_announcement(b"maintenance").await.unwrap();
+        assert!(verify_mint_announcement(&mint_info, b"maintenance", &new_signature).is_ok());
+        assert!(verify_mint_announcement(&mint_info, b"maintenance", &old_signature).is_ok());
+
+        // Without a grace period the previous key is gone on the next read
+        mint.rotate_announcement_key(Duration::ZERO).await.unwrap();
+        let mint_info = mint.mint_info().await.unwrap();
+        assert_eq!(mint_info.previous_pubkeys.len(), 1);
+        assert_eq!(mint_info.previous_pubkeys[0].pubkey, before);
+        assert!(verify_mint_announcement(&mint_info, b"maintenance", &new_signature).is_err());
+    }
+}
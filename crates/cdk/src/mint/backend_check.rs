@@ -0,0 +1,360 @@
+//! Payment backend self-check
+//!
+//! A backend configured with another unit than the one it is registered for, e.g. msat while the
+//! keysets are sat, creates every invoice off by the conversion factor. Before serving requests
+//! the mint checks that the unit of each backend converts to the registered unit. If enabled with
+//! [`MintBuilder::with_backend_invoice_selfcheck`](super::MintBuilder::with_backend_invoice_selfcheck),
+//! it also creates a tiny invoice, cancelled right away, to confirm the amount round-trips.
+//!
+//! A backend that can't be reached is only logged, so a node that is briefly down doesn't keep
+//! the mint from starting.
+
+use std::str::FromStr;
+
+use cdk_common::nut00::KnownMethod;
+use cdk_common::payment::{Bolt11IncomingPaymentOptions, DynMintPayment, IncomingPaymentOptions};
+use cdk_common::util::unix_time;
+use cdk_common::{Amount, Bolt11Invoice, CurrencyUnit, PaymentMethod};
+
+use super::{Error, Mint};
+use crate::types::PaymentProcessorKey;
+
+/// Seconds before the self-check invoice expires, if the backend can't cancel it
+const SELFCHECK_INVOICE_EXPIRY: u64 = 60;
+
+/// Description of the self-check invoice
+const SELFCHECK_INVOICE_DESCRIPTION: &str = "cdk payment backend self-check";
+
+impl Mint {
+    /// Check that every payment backend works in the unit it is registered for
+    ///
+    /// Fails with [`Error::PaymentBackendSelfCheck`] for the first backend whose unit can't be
+    /// converted to the registered unit, or, with the invoice self-check enabled, whose BOLT11
+    /// invoices are not for the amount asked. Backends that can't be reached are skipped.
+    pub async fn check_payment_backends(&self) -> Result<(), Error> {
+        for (key, processor) in self.payment_processors.iter() {
+            match check_payment_backend(key, processor, self.backend_invoice_selfcheck).await {
+                Ok(()) => tracing::debug!("Payment backend for {:?} passed its self-check", key),
+                Err(SelfCheckError::Unreachable(reason)) => tracing::warn!(
+                    "Skipping the self-check of the payment backend for {:?}: {}",
+                    key,
+                    reason
+                ),
+                Err(SelfCheckError::Failed(reason)) => {
+                    return Err(Error::PaymentBackendSelfCheck(
+                        key.unit.clone(),
+                        key.method.clone(),
+                        reason,
+                    ))
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Why a payment backend did not pass its self-check
+enum SelfCheckError {
+    /// The backend could not be asked, e.g. because the node is down
+    Unreachable(String),
+    /// The backend does not work in the unit it is registered for
+    Failed(String),
+}
+
+async fn check_payment_backend(
+    key: &PaymentProcessorKey,
+    processor: &DynMintPayment,
+    invoice_selfcheck: bool,
+) -> Result<(), SelfCheckError> {
+    let settings = processor.get_settings().await.map_err(|err| {
+        SelfCheckError::Unreachable(format!("could not get the backend settings: {err}"))
+    })?;
+    let backend_unit = CurrencyUnit::from_str(&settings.unit).map_err(|err| {
+        SelfCheckError::Failed(format!("unknown backend unit `{}`: {err}", settings.unit))
+    })?;
+
+    if Amount::new(1, backend_unit.clone())
+        .convert_to(&key.unit)
+        .is_err()
+    {
+        return Err(SelfCheckError::Failed(format!(
+            "the backend unit `{backend_unit}` can't be converted to `{}`",
+            key.unit
+        )));
+    }
+
+    if !invoice_selfcheck
+        || key.method != PaymentMethod::Known(KnownMethod::Bolt11)
+        || settings.bolt11.is_none()
+    {
+        return Ok(());
+    }
+
+    let amount = Amount::new(1, key.unit.clone());
+    let response = processor
+        .create_incoming_payment_request(
+            &key.unit,
+            IncomingPaymentOptions::Bolt11(Bolt11IncomingPaymentOptions {
+                description: Some(SELFCHECK_INVOICE_DESCRIPTION.to_string()),
+                amount: amount.clone().into(),
+                unix_expiry: Some(unix_time() + SELFCHECK_INVOICE_EXPIRY),
+            }),
+        )
+        .await
+        .map_err(|err| {
+            SelfCheckError::Unreachable(format!("could not create a test invoice: {err}"))
+        })?;
+
+    let result = check_invoice_amount(&response.request, &amount).map_err(SelfCheckError::Failed);
+
+    if let Err(err) = processor
+        .cancel_incoming_payment_request(&response.request_lookup_id)
+        .await
+    {
+        tracing::warn!(
+            "Could not cancel the self-check invoice of the {:?} backend: {}",
+            key,
+            err
+        );
+    }
+
+    result
+}
+
+/// Checks the invoice created for `amount` is for the same amount in msat
+///
+/// Units without a fixed conversion to msat, e.g. fiat, are valued by the backend, so only a
+/// non-zero invoice amount is required for them.
+fn check_invoice_amount(request: &str, amount: &Amount<CurrencyUnit>) -> Result<(), String> {
+    let invoice = Bolt11Invoice::from_str(request)
+        .map_err(|err| format!("the test invoice could not be parsed: {err}"))?;
+    let invoice_msat = invoice
+        .amount_milli_satoshis()
+        .ok_or_else(|| "the test invoice has no amount".to_string())?;
+
+    match amount.convert_to(&CurrencyUnit::Msat) {
+        Ok(expected) if expected.value() != invoice_msat => Err(format!(
+            "a test invoice for {} was created for {} msat instead of {} msat",
+            amount.display_with_unit(),
+            invoice_msat,
+            expected.value()
+        )),
+        Ok(_) => Ok(()),
+        Err(_) if invoice_msat == 0 => Err(format!(
+            "a test invoice for {} was created for 0 msat",
+            amount.display_with_unit()
+        )),
+        Err(_) => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{HashMap, HashSet};
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+
+    use async_trait::async_trait;
+    use cdk_common::payment::{
+        self, CreateIncomingPaymentResponse, Event, MakePaymentResponse, MintPayment,
+        OutgoingPaymentOptions, PaymentIdentifier, PaymentQuoteResponse, SettingsResponse,
+        WaitPaymentResponse,
+    };
+    use cdk_fake_wallet::FakeWallet;
+    use futures::{Stream, StreamExt};
+
+    use super::*;
+    use crate::mint::{MintBuilder, MintMeltLimits};
+    use crate::types::FeeReserve;
+
+    /// Payment backend whose node went down after the mint was built
+    struct UnreachableBackend {
+        reachable: AtomicBool,
+    }
+
+    #[async_trait]
+    impl MintPayment for UnreachableBackend {
+        type Err = payment::Error;
+
+        async fn get_settings(&self) -> Result<SettingsResponse, Self::Err> {
+            if !self.reachable.load(Ordering::SeqCst) {
+                return Err(payment::Error::Custom("connection refused".to_string()));
+            }
+
+            Ok(SettingsResponse {
+                unit: CurrencyUnit::Sat.to_string(),
+                bolt11: Some(payment::Bolt11Settings {
+                    mpp: false,
+                    amountless: false,
+                    invoice_description: true,
+                }),
+                bolt12: None,
+                custom: HashMap::new(),
+            })
+        }
+
+        async fn create_incoming_payment_request(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: IncomingPaymentOptions,
+        ) -> Result<CreateIncomingPaymentResponse, Self::Err> {
+            Err(payment::Error::Custom("connection refused".to_string()))
+        }
+
+        async fn get_payment_quote(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: OutgoingPaymentOptions,
+        ) -> Result<PaymentQuoteResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn make_payment(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: OutgoingPaymentOptions,
+        ) -> Result<MakePaymentResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn wait_payment_event(
+            &self,
+        ) -> Result<Pin<Box<dyn Stream<Item = Event> + Send>>, Self::Err> {
+            unimplemented!()
+        }
+
+        fn is_wait_invoice_active(&self) -> bool {
+            false
+        }
+
+        fn cancel_wait_invoice(&self) {}
+
+        async fn check_incoming_payment_status(
+            &self,
+            _payment_identifier: &PaymentIdentifier,
+        ) -> Result<Vec<WaitPaymentResponse>, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn check_outgoing_payment(
+            &self,
+            _payment_identifier: &PaymentIdentifier,
+        ) -> Result<MakePaymentResponse, Self::Err> {
+            unimplemented!()
+        }
+    }
+
+    fn fake_wallet(unit: CurrencyUnit) -> FakeWallet {
+        FakeWallet::new(
+            FeeReserve {
+                min_fee_reserve: 1.into(),
+                percent_fee_reserve: 1.0,
+            },
+            HashMap::default(),
+            HashSet::default(),
+            0,
+            unit,
+        )
+    }
+
+    async fn mint_with_backend(
+        unit: CurrencyUnit,
+        backend: DynMintPayment,
+        invoice_selfcheck: bool,
+    ) -> Mint {
+        let db = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let mut mint_builder =
+            MintBuilder::new(db.clone()).with_backend_invoice_selfcheck(invoice_selfcheck);
+        mint_builder
+            .add_payment_processor(
+                unit,
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                MintMeltLimits::new(1, 10_000),
+                backend,
+            )
+            .await
+            .unwrap();
+
+        mint_builder.build_with_seed(db, &[0u8; 32]).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn backend_in_the_registered_unit_passes() {
+        let backend = Arc::new(fake_wallet(CurrencyUnit::Sat));
+        let mint = mint_with_backend(CurrencyUnit::Sat, backend, true).await;
+        mint.check_payment_backends().await.unwrap();
+
+        // A msat backend converting the sat amounts it is given is fine too
+        let backend = Arc::new(fake_wallet(CurrencyUnit::Msat));
+        let mint = mint_with_backend(CurrencyUnit::Sat, backend, true).await;
+        mint.check_payment_backends().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn backend_reading_amounts_in_another_unit_fails() {
+        let backend =
+            Arc::new(fake_wallet(CurrencyUnit::Msat).with_invoice_unit(CurrencyUnit::Msat));
+        let mint = mint_with_backend(CurrencyUnit::Sat, backend, true).await;
+
+        let err = mint.check_payment_backends().await.unwrap_err();
+        assert!(
+            matches!(&err, Error::PaymentBackendSelfCheck(unit, _, reason)
+                if *unit == CurrencyUnit::Sat && reason.contains("1 msat instead of 1000 msat")),
+            "{err}"
+        );
+    }
+
+    #[tokio::test]
+    async fn invoice_round_trip_is_opt_in() {
+        let backend =
+            Arc::new(fake_wallet(CurrencyUnit::Msat).with_invoice_unit(CurrencyUnit::Msat));
+        let mint = mint_with_backend(CurrencyUnit::Sat, backend, false).await;
+
+        // Only the unit is checked, no test invoice is created
+        mint.check_payment_backends().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn backend_unit_not_convertible_fails() {
+        for invoice_selfcheck in [false, true] {
+            let backend = Arc::new(fake_wallet(CurrencyUnit::Usd));
+            let mint = mint_with_backend(CurrencyUnit::Sat, backend, invoice_selfcheck).await;
+
+            assert!(matches!(
+                mint.check_payment_backends().await,
+                Err(Error::PaymentBackendSelfCheck(..))
+            ));
+        }
+    }
+
+    #[tokio::test]
+    async fn unreachable_backend_does_not_block_startup() {
+        let backend = Arc::new(UnreachableBackend {
+            reachable: AtomicBool::new(true),
+        });
+        let mint = mint_with_backend(CurrencyUnit::Sat, backend.clone(), true).await;
+
+        // The test invoice can't be created
+        mint.check_payment_backends().await.unwrap();
+
+        // The settings can't be fetched either
+        backend.reachable.store(false, Ordering::SeqCst);
+        mint.check_payment_backends().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn self_check_invoice_is_cancelled() {
+        let backend = fake_wallet(CurrencyUnit::Sat);
+        let mut payments = backend.wait_payment_event().await.unwrap();
+        let mint = mint_with_backend(CurrencyUnit::Sat, Arc::new(backend), true).await;
+
+        mint.check_payment_backends().await.unwrap();
+
+        // The fake wallet pays every invoice it creates, unless it was cancelled
+        let paid =
+            tokio::time::timeout(std::time::Duration::from_millis(500), payments.next()).await;
+        assert!(paid.is_err());
+    }
+}
@@ -0,0 +1,600 @@
+//! Temporary bans of clients replaying spent proofs
+//!
+//! Requests spending proofs that are already spent, or asking to sign outputs that are already
+//! signed, are counted per client IP, IPv6 clients by their /64 prefix. A client reaching the threshold of the [`BanPolicy`] within
+//! its window is banned for the ban duration, and the server rejects its mint, melt and swap
+//! requests before they are verified.
+//!
+//! Only distinct requests are counted: a wallet retrying the same stale request does not get
+//! closer to a ban, only a client sending many different double spends does. Requests are told
+//! apart by the proofs they spend, or by the outputs of requests without inputs, so a retry with
+//! new outputs or a reordered body is still the same request. The list is kept in memory and is
+//! lost on restart.
+
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv6Addr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::{Hash, HashEngine};
+use cdk_common::error::ErrorCode;
+use cdk_common::PublicKey;
+#[cfg(feature = "prometheus")]
+use cdk_prometheus::global;
+
+/// Default number of distinct double spends that gets a client banned
+pub const DEFAULT_BAN_THRESHOLD: usize = 20;
+/// Default window in which the double spends of a client are counted
+pub const DEFAULT_BAN_WINDOW: Duration = Duration::from_secs(60);
+/// Default time a client stays banned
+pub const DEFAULT_BAN_DURATION: Duration = Duration::from_secs(15 * 60);
+
+/// Above this many tracked clients or bans, clients without recent double spends and expired
+/// bans are dropped
+const MAX_TRACKED_CLIENTS: usize = 10_000;
+
+/// When a client replaying spent proofs gets banned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BanPolicy {
+    /// Distinct double spends within the window that get a client banned
+    pub threshold: usize,
+    /// Window in which the double spends are counted
+    pub window: Duration,
+    /// How long a client stays banned
+    pub duration: Duration,
+}
+
+impl Default for BanPolicy {
+    fn default() -> Self {
+        Self {
+            threshold: DEFAULT_BAN_THRESHOLD,
+            window: DEFAULT_BAN_WINDOW,
+            duration: DEFAULT_BAN_DURATION,
+        }
+    }
+}
+
+/// A client currently banned
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BannedClient {
+    /// IP address of the client, the /64 network address for IPv6 clients
+    pub ip: IpAddr,
+    /// Distinct double spends that got the client banned
+    pub double_spends: usize,
+    /// Time left until the ban expires
+    pub expires_in: Duration,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Ban {
+    until: Instant,
+    double_spends: usize,
+}
+
+#[derive(Debug, Default)]
+struct BanState {
+    policy: Option<BanPolicy>,
+    /// Recent double spends of each client, by time and hash of the request keys
+    double_spends: HashMap<IpAddr, VecDeque<(Instant, Sha256Hash)>>,
+    bans: HashMap<IpAddr, Ban>,
+}
+
+/// Double spend counts and bans of the clients, disabled unless configured
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BanList {
+    state: Arc<Mutex<BanState>>,
+}
+
+/// Hash identifying a request by its keys, whatever their order
+fn hash_request_keys(request_keys: &[PublicKey]) -> Sha256Hash {
+    let mut request_keys = request_keys.to_vec();
+    request_keys.sort();
+
+    let mut engine = Sha256Hash::engine();
+    for key in request_keys {
+        engine.input(&key.to_bytes());
+    }
+    Sha256Hash::from_engine(engine)
+}
+
+/// Address the double spends and ban of `client` are kept under
+///
+/// A host usually gets a whole IPv6 /64, so its clients are counted together rather than per
+/// address they can rotate through.
+fn client_key(client: IpAddr) -> IpAddr {
+    match client.to_canonical() {
+        IpAddr::V6(ip) => IpAddr::V6(Ipv6Addr::from(u128::from(ip) & (!0u128 << 64))),
+        ip => ip,
+    }
+}
+
+/// Label of the double spend metrics for `code`, `None` if it is not a double spend
+fn double_spend_kind(code: ErrorCode) -> Option<&'static str> {
+    match code {
+        ErrorCode::TokenAlreadySpent => Some("token_already_spent"),
+        ErrorCode::BlindedMessageAlreadySigned => Some("blinded_message_already_signed"),
+        _ => None,
+    }
+}
+
+impl BanList {
+    pub(crate) fn new(policy: BanPolicy) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(BanState {
+                policy: Some(policy),
+                ..Default::default()
+            })),
+        }
+    }
+
+    /// Replace the ban policy, `None` disables bans and lifts the current ones
+    ///
+    /// Bans and double spends counted so far are kept when the policy changes.
+    pub(crate) fn set_policy(&self, policy: Option<BanPolicy>) {
+        let mut state = self.state();
+        state.policy = policy;
+
+        if policy.is_none() {
+            state.double_spends.clear();
+            state.bans.clear();
+
+            #[cfg(feature = "prometheus")]
+            global::set_banned_clients(0);
+        }
+    }
+
+    /// Whether a ban policy is configured
+    pub(crate) fn enabled(&self) -> bool {
+        self.state().policy.is_some()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BanState> {
+        // The state stays consistent even if a holder panicked
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Record a request of `client` rejected with `code`, identified by `request_keys`
+    ///
+    /// Returns `true` if the client got banned by this request.
+    pub(crate) fn record_rejection(
+        &self,
+        client: IpAddr,
+        code: ErrorCode,
+        request_keys: &[PublicKey],
+    ) -> bool {
+        let Some(kind) = double_spend_kind(code) else {
+            return false;
+        };
+
+        #[cfg(feature = "prometheus")]
+        global::record_double_spend_rejection(kind);
+
+        tracing::debug!("Client {} sent a double spend: {}", client, kind);
+
+        self.record_double_spend(client, hash_request_keys(request_keys), Instant::now())
+    }
+
+    fn record_double_spend(&self, client: IpAddr, request: Sha256Hash, now: Instant) -> bool {
+        let client = client_key(client);
+        let mut state = self.state();
+        let Some(policy) = state.policy else {
+            return false;
+        };
+
+        if state.bans.get(&client).is_some_and(|ban| ban.until > now) {
+            return false;
+        }
+
+        let recent = |at: &Instant| now.saturating_duration_since(*at) < policy.window;
+
+        if state.double_spends.len() >= MAX_TRACKED_CLIENTS {
+            state
+                .double_spends
+                .retain(|_, double_spends| double_spends.back().is_some_and(|(at, _)| recent(at)));
+        }
+
+        let double_spends = state.double_spends.entry(client).or_default();
+        while double_spends.front().is_some_and(|(at, _)| !recent(at)) {
+            double_spends.pop_front();
+        }
+
+        // A retried request is not counted again
+        if double_spends.iter().any(|(_, hash)| *hash == request) {
+            return false;
+        }
+        double_spends.push_back((now, request));
+
+        let count = double_spends.len();
+        if count < policy.threshold {
+            return false;
+        }
+
+        state.double_spends.remove(&client);
+
+        if state.bans.len() >= MAX_TRACKED_CLIENTS {
+            state.bans.retain(|_, ban| ban.until > now);
+        }
+        if state.bans.len() >= MAX_TRACKED_CLIENTS {
+            // Still full of live bans, make room by lifting the one expiring first
+            let expiring = state
+                .bans
+                .iter()
+                .min_by_key(|(_, ban)| ban.until)
+                .map(|(ip, _)| *ip);
+            if let Some(expiring) = expiring {
+                state.bans.remove(&expiring);
+            }
+        }
+
+        state.bans.insert(
+            client,
+            Ban {
+                until: now + policy.duration,
+                double_spends: count,
+            },
+        );
+
+        tracing::warn!(
+            "Banning client {} for {:?} after {} double spends within {:?}",
+            client,
+            policy.duration,
+            count,
+            policy.window
+        );
+
+        #[cfg(feature = "prometheus")]
+        global::set_banned_clients(state.bans.len() as i64);
+
+        true
+    }
+
+    /// Time left until the ban of `client` expires, `None` if it is not banned
+    pub(crate) fn ban(&self, client: &IpAddr) -> Option<Duration> {
+        self.ban_at(client, Instant::now())
+    }
+
+    fn ban_at(&self, client: &IpAddr, now: Instant) -> Option<Duration> {
+        let client = client_key(*client);
+        let mut state = self.state();
+        state.policy?;
+
+        let ban = *state.bans.get(&client)?;
+
+        if ban.until <= now {
+            state.bans.remove(&client);
+
+            #[cfg(feature = "prometheus")]
+            global::set_banned_clients(state.bans.len() as i64);
+
+            return None;
+        }
+
+        #[cfg(feature = "prometheus")]
+        global::record_banned_request();
+
+        Some(ban.until - now)
+    }
+
+    /// Clients currently banned
+    pub(crate) fn banned(&self) -> Vec<BannedClient> {
+        self.banned_at(Instant::now())
+    }
+
+    fn banned_at(&self, now: Instant) -> Vec<BannedClient> {
+        let mut state = self.state();
+        state.bans.retain(|_, ban| ban.until > now);
+
+        #[cfg(feature = "prometheus")]
+        global::set_banned_clients(state.bans.len() as i64);
+
+        let mut banned: Vec<_> = state
+            .bans
+            .iter()
+            .map(|(ip, ban)| BannedClient {
+                ip: *ip,
+                double_spends: ban.double_spends,
+                expires_in: ban.until - now,
+            })
+            .collect();
+        banned.sort_by_key(|client| client.ip);
+        banned
+    }
+
+    /// Drop expired bans and double spends outside the window
+    ///
+    /// Returns the number of bans dropped.
+    pub(crate) fn sweep(&self) -> usize {
+        self.sweep_at(Instant::now())
+    }
+
+    fn sweep_at(&self, now: Instant) -> usize {
+        let mut state = self.state();
+        let Some(policy) = state.policy else {
+            return 0;
+        };
+
+        let bans = state.bans.len();
+        state.bans.retain(|_, ban| ban.until > now);
+        state.double_spends.retain(|_, double_spends| {
+            double_spends.retain(|(at, _)| now.saturating_duration_since(*at) < policy.window);
+            !double_spends.is_empty()
+        });
+
+        #[cfg(feature = "prometheus")]
+        global::set_banned_clients(state.bans.len() as i64);
+
+        bans - state.bans.len()
+    }
+
+    /// Lift the ban of `client`, or of all clients if `None`
+    ///
+    /// An IPv6 client lifts the ban of its whole /64.
+    ///
+    /// The double spends counted so far are forgotten too. Returns the number of bans lifted.
+    pub(crate) fn clear(&self, client: Option<IpAddr>) -> usize {
+        let mut state = self.state();
+
+        let cleared = match client {
+            Some(client) => {
+                let client = client_key(client);
+                state.double_spends.remove(&client);
+                usize::from(state.bans.remove(&client).is_some())
+            }
+            None => {
+                state.double_spends.clear();
+                state.bans.drain().count()
+            }
+        };
+
+        #[cfg(feature = "prometheus")]
+        global::set_banned_clients(state.bans.len() as i64);
+
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+
+    use super::*;
+
+    fn policy() -> BanPolicy {
+        BanPolicy {
+            threshold: 3,
+            window: Duration::from_secs(60),
+            duration: Duration::from_secs(600),
+        }
+    }
+
+    fn ip(ip: &str) -> IpAddr {
+        IpAddr::from_str(ip).unwrap()
+    }
+
+    fn request(n: u8) -> Sha256Hash {
+        Sha256Hash::hash(&[n])
+    }
+
+    fn key(n: u8) -> PublicKey {
+        cdk_common::SecretKey::from_slice(&[n; 32])
+            .unwrap()
+            .public_key()
+    }
+
+    #[test]
+    fn bans_after_threshold_within_window() {
+        let list = BanList::new(policy());
+        let client = ip("203.0.113.7");
+        let start = Instant::now();
+
+        assert!(!list.record_double_spend(client, request(1), start));
+        assert!(!list.record_double_spend(client, request(2), start));
+        assert_eq!(list.ban_at(&client, start), None);
+
+        assert!(list.record_double_spend(client, request(3), start));
+        assert_eq!(list.ban_at(&client, start), Some(Duration::from_secs(600)));
+        assert_eq!(list.ban_at(&ip("203.0.113.8"), start), None);
+
+        // The ban expires
+        let later = start + Duration::from_secs(601);
+        assert_eq!(list.ban_at(&client, later), None);
+        assert!(list.banned_at(later).is_empty());
+    }
+
+    #[test]
+    fn retried_request_is_counted_once() {
+        let list = BanList::new(policy());
+        let client = ip("203.0.113.7");
+        let start = Instant::now();
+
+        for _ in 0..10 {
+            assert!(!list.record_double_spend(client, request(1), start));
+        }
+        assert_eq!(list.ban_at(&client, start), None);
+    }
+
+    #[test]
+    fn double_spends_outside_window_are_forgotten() {
+        let list = BanList::new(policy());
+        let client = ip("203.0.113.7");
+        let start = Instant::now();
+
+        list.record_double_spend(client, request(1), start);
+        list.record_double_spend(client, request(2), start);
+
+        let later = start + Duration::from_secs(61);
+        assert!(!list.record_double_spend(client, request(3), later));
+        assert_eq!(list.ban_at(&client, later), None);
+    }
+
+    #[test]
+    fn list_and_clear_bans() {
+        let list = BanList::new(policy());
+        let now = Instant::now();
+
+        for client in ["203.0.113.7", "203.0.113.8"] {
+            for n in 0..3 {
+                list.record_double_spend(ip(client), request(n), now);
+            }
+        }
+
+        let banned = list.banned_at(now);
+        assert_eq!(banned.len(), 2);
+        assert_eq!(banned[0].ip, ip("203.0.113.7"));
+        assert_eq!(banned[0].double_spends, 3);
+
+        assert_eq!(list.clear(Some(ip("203.0.113.7"))), 1);
+        assert_eq!(list.clear(Some(ip("203.0.113.7"))), 0);
+        assert_eq!(list.ban_at(&ip("203.0.113.7"), now), None);
+
+        assert_eq!(list.clear(None), 1);
+        assert!(list.banned_at(now).is_empty());
+    }
+
+    #[test]
+    fn sweep_drops_expired_state() {
+        let list = BanList::new(policy());
+        let start = Instant::now();
+
+        for n in 0..3 {
+            list.record_double_spend(ip("203.0.113.7"), request(n), start);
+        }
+        list.record_double_spend(ip("203.0.113.8"), request(1), start);
+
+        assert_eq!(list.sweep_at(start + Duration::from_secs(61)), 0);
+        assert!(list.state().double_spends.is_empty());
+        assert_eq!(list.banned_at(start).len(), 1);
+
+        assert_eq!(list.sweep_at(start + Duration::from_secs(601)), 1);
+        assert!(list.state().bans.is_empty());
+    }
+
+    #[test]
+    fn only_double_spends_are_counted() {
+        let list = BanList::new(BanPolicy {
+            threshold: 1,
+            ..policy()
+        });
+        let client = ip("203.0.113.7");
+
+        let keys = [key(1)];
+        assert!(!list.record_rejection(client, ErrorCode::TokenPending, &keys));
+        assert!(list.record_rejection(client, ErrorCode::TokenAlreadySpent, &keys));
+        assert!(list.ban(&client).is_some());
+    }
+
+    #[test]
+    fn request_keys_are_counted_in_any_order() {
+        let list = BanList::new(policy());
+        let client = ip("203.0.113.7");
+
+        // A retry listing the same spent proofs in another order is the same request
+        for keys in [[key(1), key(2)], [key(2), key(1)]] {
+            list.record_rejection(client, ErrorCode::TokenAlreadySpent, &keys);
+        }
+        list.record_rejection(client, ErrorCode::TokenAlreadySpent, &[key(1)]);
+        assert_eq!(list.ban(&client), None);
+
+        assert!(list.record_rejection(client, ErrorCode::TokenAlreadySpent, &[key(3)]));
+    }
+
+    #[test]
+    fn policy_can_be_replaced() {
+        let list = BanList::default();
+        let client = ip("203.0.113.7");
+        let now = Instant::now();
+
+        list.set_policy(Some(policy()));
+        for n in 0..3 {
+            list.record_double_spend(client, request(n), now);
+        }
+        assert!(list.ban_at(&client, now).is_some());
+
+        // Disabling bans lifts the current ones
+        list.set_policy(None);
+        assert_eq!(list.ban_at(&client, now), None);
+        assert!(!list.record_double_spend(client, request(4), now));
+    }
+
+    #[test]
+    fn ipv6_clients_are_counted_by_prefix() {
+        let list = BanList::new(policy());
+        let now = Instant::now();
+
+        // Rotating addresses within the /64 does not evade the ban
+        for (n, client) in ["2001:db8:1:2::1", "2001:db8:1:2::2", "2001:db8:1:2:ffff::3"]
+            .into_iter()
+            .enumerate()
+        {
+            list.record_double_spend(ip(client), request(n as u8), now);
+        }
+
+        assert!(list.ban_at(&ip("2001:db8:1:2::abcd"), now).is_some());
+        assert_eq!(list.ban_at(&ip("2001:db8:1:3::1"), now), None);
+
+        let banned = list.banned_at(now);
+        assert_eq!(banned.len(), 1);
+        assert_eq!(banned[0].ip, ip("2001:db8:1:2::"));
+
+        assert_eq!(list.clear(Some(ip("2001:db8:1:2::1"))), 1);
+        assert_eq!(list.ban_at(&ip("2001:db8:1:2::2"), now), None);
+    }
+
+    #[test]
+    fn ipv4_mapped_clients_are_counted_as_ipv4() {
+        let list = BanList::new(BanPolicy {
+            threshold: 1,
+            ..policy()
+        });
+        let now = Instant::now();
+
+        assert!(list.record_double_spend(ip("::ffff:203.0.113.7"), request(1), now));
+        assert!(list.ban_at(&ip("203.0.113.7"), now).is_some());
+        assert_eq!(list.ban_at(&ip("203.0.113.8"), now), None);
+    }
+
+    #[test]
+    fn bans_are_bounded() {
+        let list = BanList::new(BanPolicy {
+            threshold: 1,
+            ..policy()
+        });
+        let start = Instant::now();
+
+        let first = IpAddr::from([10, 0, 0, 0]);
+        list.record_double_spend(first, request(1), start);
+
+        let later = start + Duration::from_secs(1);
+        for n in 1..=MAX_TRACKED_CLIENTS as u32 {
+            list.record_double_spend(IpAddr::from(n.to_be_bytes()), request(1), later);
+        }
+
+        // The ban expiring first made room for the last one
+        assert_eq!(list.state().bans.len(), MAX_TRACKED_CLIENTS);
+        assert_eq!(list.ban_at(&first, later), None);
+        assert!(list
+            .ban_at(
+                &IpAddr::from((MAX_TRACKED_CLIENTS as u32).to_be_bytes()),
+                later
+            )
+            .is_some());
+
+        // Expired bans are dropped before live ones
+        let expired = start + Duration::from_secs(602);
+        assert!(list.record_double_spend(ip("203.0.113.7"), request(1), expired));
+        assert_eq!(list.state().bans.len(), 1);
+    }
+
+    #[test]
+    fn disabled_without_policy() {
+        let list = BanList::default();
+        let client = ip("203.0.113.7");
+
+        assert!(!list.enabled());
+        assert!(!list.record_rejection(client, ErrorCode::TokenAlreadySpent, &[key(1)]));
+        assert_eq!(list.ban(&client), None);
+    }
+}
@@ -2,29 +2,35 @@
 
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Duration;
 
 use bitcoin::bip32::DerivationPath;
 use cdk_common::database::{DynMintDatabase, MintKeysDatabase};
-use cdk_common::error::Error;
+use cdk_common::error::{Error, MintBuilderError};
 use cdk_common::nut00::KnownMethod;
 use cdk_common::nut04::MintMethodOptions;
 use cdk_common::nut05::MeltMethodOptions;
-use cdk_common::payment::DynMintPayment;
+use cdk_common::payment::{DynMintPayment, KeysendSettings, KEYSEND_METHOD};
 #[cfg(feature = "auth")]
 use cdk_common::{database::DynMintAuthDatabase, nut21, nut22};
 use cdk_signatory::signatory::Signatory;
 
-use super::nut17::SupportedMethods;
+use super::nut17::{SupportedMethods, DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION};
 use super::nut19::{self, CachedEndpoint};
 use super::Nuts;
 use crate::amount::Amount;
 use crate::cdk_database;
-use crate::mint::Mint;
+use crate::mint::ban_list::BanList;
+use crate::mint::payment_limiter::PaymentLimiter;
+use crate::mint::stuck_quotes::StuckQuoteMonitor;
+use crate::mint::{
+    BanPolicy, Mint, PaymentConcurrencyLimit, StartupCheckConfig, DEFAULT_MELT_PROGRESS_INTERVAL,
+};
 #[cfg(feature = "auth")]
 use crate::nuts::ProtectedEndpoint;
 use crate::nuts::{
-    ContactInfo, CurrencyUnit, MeltMethodSettings, MintInfo, MintMethodSettings, MintVersion,
-    MppMethodSettings, PaymentMethod,
+    ContactInfo, CurrencyUnit, MeltChangePolicy, MeltMethodSettings, MintInfo, MintMethodSettings,
+    MintVersion, MppMethodSettings, PaymentMethod, RequestLimits, UnitMetadata,
 };
 use crate::types::PaymentProcessorKey;
 
@@ -36,7 +42,20 @@ pub struct MintBuilder {
     auth_localstore: Option<DynMintAuthDatabase>,
     payment_processors: HashMap<PaymentProcessorKey, DynMintPayment>,
     supported_units: HashMap<CurrencyUnit, (u64, u8)>,
+    max_amounts: HashMap<CurrencyUnit, Amount>,
     custom_paths: HashMap<CurrencyUnit, DerivationPath>,
+    startup_check_config: StartupCheckConfig,
+    backend_invoice_selfcheck: bool,
+    request_limits: RequestLimits,
+    melt_change_policy: MeltChangePolicy,
+    max_subscription_filters: usize,
+    reuse_melt_quotes: bool,
+    melt_progress_interval: Duration,
+    payment_concurrency_limit: Option<PaymentConcurrencyLimit>,
+    ban_policy: Option<BanPolicy>,
+    pending_alert: Option<Duration>,
+    accept_new_seed: bool,
+    derivation_account: u32,
 }
 
 impl std::fmt::Debug for MintBuilder {
@@ -71,7 +90,20 @@ impl MintBuilder {
             auth_localstore: None,
             payment_processors: HashMap::new(),
             supported_units: HashMap::new(),
+            max_amounts: HashMap::new(),
             custom_paths: HashMap::new(),
+            startup_check_config: StartupCheckConfig::default(),
+            backend_invoice_selfcheck: false,
+            request_limits: RequestLimits::default(),
+            melt_change_policy: MeltChangePolicy::default(),
+            max_subscription_filters: DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION,
+            reuse_melt_quotes: true,
+            melt_progress_interval: DEFAULT_MELT_PROGRESS_INTERVAL,
+            payment_concurrency_limit: None,
+            ban_policy: None,
+            pending_alert: None,
+            accept_new_seed: false,
+            derivation_account: 0,
         }
     }
 
@@ -245,6 +277,128 @@ impl MintBuilder {
         self
     }
 
+    /// Set how pending quotes are reconciled with payment backends on startup
+    pub fn with_startup_check_config(mut self, startup_check_config: StartupCheckConfig) -> Self {
+        self.startup_check_config = startup_check_config;
+        self
+    }
+
+    /// Set whether the payment backend self-check of [`Mint::start`] creates a tiny test invoice
+    /// to confirm BOLT11 amounts round-trip, see [`Mint::check_payment_backends`] (default: false)
+    ///
+    /// The invoice is cancelled right away where the backend supports it, otherwise it is left
+    /// to expire.
+    pub fn with_backend_invoice_selfcheck(mut self, backend_invoice_selfcheck: bool) -> Self {
+        self.backend_invoice_selfcheck = backend_invoice_selfcheck;
+        self
+    }
+
+    /// Allow [`MintBuilder::build_with_seed`] to start with a seed other than the one the active
+    /// keysets were derived from
+    ///
+    /// The active keysets are then replaced, so ecash issued under them can no longer be redeemed.
+    pub fn with_accept_new_seed(mut self, accept_new_seed: bool) -> Self {
+        self.accept_new_seed = accept_new_seed;
+        self
+    }
+
+    /// Set the account [`MintBuilder::build_with_seed`] derives keysets under
+    ///
+    /// Mints sharing a seed but not an account never share keys. The account of an existing
+    /// mint can't be changed.
+    pub fn with_derivation_account(mut self, derivation_account: u32) -> Self {
+        self.derivation_account = derivation_account;
+        self
+    }
+
+    /// Set the maximum number of inputs, outputs and checked Ys accepted in a single request
+    pub fn with_request_limits(mut self, request_limits: RequestLimits) -> Self {
+        self.request_limits = request_limits;
+        self.mint_info.nuts.request_limits = Some(request_limits);
+        self
+    }
+
+    /// Set the limits on the change signed for melts
+    ///
+    /// Change owed beyond the limits is kept by the mint and recorded as burnt with the
+    /// completed melt. The policy is advertised in the mint info when it restricts anything.
+    pub fn with_melt_change_policy(mut self, melt_change_policy: MeltChangePolicy) -> Self {
+        self.melt_change_policy = melt_change_policy;
+        if !melt_change_policy.is_unrestricted() {
+            self.mint_info.nuts.melt_change = Some(melt_change_policy);
+        }
+        self
+    }
+
+    /// Set the maximum number of filters accepted in a single websocket subscription
+    ///
+    /// Filters beyond the limit are listed as rejected in the subscription response, so a wallet
+    /// watching many proofs subscribes to them in batches.
+    pub fn with_max_subscription_filters(mut self, max_subscription_filters: usize) -> Self {
+        self.max_subscription_filters = max_subscription_filters;
+        self.mint_info.nuts.nut17.max_filters = Some(max_subscription_filters);
+        self
+    }
+
+    /// Answer a bolt11 melt quote request with the existing quote of the invoice (default)
+    ///
+    /// An unpaid, unexpired quote with the same unit and options is returned instead of creating
+    /// a new one, so wallets retrying quote creation don't end up with several quotes to execute.
+    pub fn with_melt_quote_reuse(mut self, reuse_melt_quotes: bool) -> Self {
+        self.reuse_melt_quotes = reuse_melt_quotes;
+        self
+    }
+
+    /// Limit the payment operations running at once against each payment backend
+    ///
+    /// Melts and mint quote creation over the limit wait for a free slot, up to the queue timeout
+    /// of the limit, and then fail with [`Error::PaymentBackendBusy`].
+    pub fn with_payment_concurrency_limit(mut self, limit: PaymentConcurrencyLimit) -> Self {
+        self.payment_concurrency_limit = Some(limit);
+        self
+    }
+
+    /// Temporarily ban clients that keep sending double spends
+    ///
+    /// See [`Mint::record_client_rejection`] for how double spends are counted.
+    pub fn with_ban_policy(mut self, policy: BanPolicy) -> Self {
+        self.ban_policy = Some(policy);
+        self
+    }
+
+    /// Alert on melt quotes pending for longer than `alert_after`
+    ///
+    /// See [`Mint::check_stuck_melt_quotes`].
+    pub fn with_pending_alert(mut self, alert_after: Duration) -> Self {
+        self.pending_alert = Some(alert_after);
+        self
+    }
+
+    /// Set the interval between the `attempting` progress events published over NUT-17 while a
+    /// melt awaits its payment (default: [`DEFAULT_MELT_PROGRESS_INTERVAL`])
+    ///
+    /// A zero interval disables the `attempting` events, the `pending` event is still published.
+    pub fn with_melt_progress_interval(mut self, interval: Duration) -> Self {
+        self.melt_progress_interval = interval;
+        self
+    }
+
+    /// Add a custom currency unit
+    ///
+    /// A keyset is created for the unit even when no payment processor is
+    /// configured for it, and its metadata is advertised in the mint info.
+    pub fn with_custom_unit(mut self, metadata: UnitMetadata) -> Self {
+        self.supported_units
+            .entry(metadata.unit.clone())
+            .or_insert((0, 32));
+        self.mint_info
+            .nuts
+            .units
+            .retain(|existing| existing.unit != metadata.unit);
+        self.mint_info.nuts.units.push(metadata);
+        self
+    }
+
     /// Add payment processor
     pub async fn add_payment_processor(
         &mut self,
@@ -258,6 +412,10 @@ impl MintBuilder {
             method: method.clone(),
         };
 
+        if self.payment_processors.contains_key(&key) {
+            return Err(MintBuilderError::DuplicatePaymentBackend(unit, method).into());
+        }
+
         let settings = payment_processor.get_settings().await?;
 
         match method {
@@ -266,10 +424,7 @@ impl MintBuilder {
                 if let Some(ref bolt11_settings) = settings.bolt11 {
                     // Add MPP support if available
                     if bolt11_settings.mpp {
-                        let mpp_settings = MppMethodSettings {
-                            method: method.clone(),
-                            unit: unit.clone(),
-                        };
+                        let mpp_settings = MppMethodSettings::new(method.clone(), unit.clone());
 
                         let mut mpp = self.mint_info.nuts.nut15.clone();
                         mpp.methods.push(mpp_settings);
@@ -332,17 +487,26 @@ impl MintBuilder {
             // Handle custom methods
             PaymentMethod::Custom(_) => {
                 // Check if this custom method is supported by the payment processor
-                if settings.custom.contains_key(method.as_str()) {
-                    // Add to NUT04 (mint)
-                    let mint_method_settings = MintMethodSettings {
-                        method: method.clone(),
-                        unit: unit.clone(),
-                        min_amount: Some(limits.mint_min),
-                        max_amount: Some(limits.mint_max),
-                        options: Some(MintMethodOptions::Custom {}),
+                if let Some(method_settings) = settings.custom.get(method.as_str()) {
+                    // Keysend payments can only be sent, so it is melt only
+                    let keysend_settings = if method.as_str() == KEYSEND_METHOD {
+                        Some(serde_json::from_str::<KeysendSettings>(method_settings)?)
+                    } else {
+                        None
                     };
-                    self.mint_info.nuts.nut04.methods.push(mint_method_settings);
-                    self.mint_info.nuts.nut04.disabled = false;
+
+                    if keysend_settings.is_none() {
+                        // Add to NUT04 (mint)
+                        let mint_method_settings = MintMethodSettings {
+                            method: method.clone(),
+                            unit: unit.clone(),
+                            min_amount: Some(limits.mint_min),
+                            max_amount: Some(limits.mint_max),
+                            options: Some(MintMethodOptions::Custom {}),
+                        };
+                        self.mint_info.nuts.nut04.methods.push(mint_method_settings);
+                        self.mint_info.nuts.nut04.disabled = false;
+                    }
 
                     // Add to NUT05 (melt)
                     let melt_method_settings = MeltMethodSettings {
@@ -350,7 +514,9 @@ impl MintBuilder {
                         unit: unit.clone(),
                         min_amount: Some(limits.melt_min),
                         max_amount: Some(limits.melt_max),
-                        options: None, // No custom-specific options in NUT05 yet
+                        options: keysend_settings.map(|keysend| MeltMethodOptions::Keysend {
+                            fee_probe: keysend.fee_probe,
+                        }),
                     };
                     self.mint_info.nuts.nut05.methods.push(melt_method_settings);
                     self.mint_info.nuts.nut05.disabled = false;
@@ -379,42 +545,158 @@ impl MintBuilder {
         Ok(())
     }
 
+    /// Sets the largest amount the mint signs for a single output of a given unit
+    ///
+    /// Outputs above it are rejected and melt change is split into smaller amounts. Unlike the
+    /// max order of the keyset, this doesn't change the keys, so it can be changed without a
+    /// rotation. The unit **MUST** already have been added with a ln backend
+    pub fn set_unit_max_amount(
+        &mut self,
+        unit: &CurrencyUnit,
+        max_amount: Amount,
+    ) -> Result<(), Error> {
+        if !self.supported_units.contains_key(unit) {
+            return Err(Error::UnsupportedUnit);
+        }
+
+        self.max_amounts.insert(unit.clone(), max_amount);
+
+        Ok(())
+    }
+
+    /// Check the mint can serve what its info advertises
+    fn validate(&self) -> Result<(), MintBuilderError> {
+        if self.supported_units.is_empty() {
+            return Err(MintBuilderError::NoPaymentBackend);
+        }
+
+        if self
+            .payment_concurrency_limit
+            .as_ref()
+            .is_some_and(|limit| limit.max_in_flight == 0)
+        {
+            return Err(MintBuilderError::ZeroPaymentConcurrency);
+        }
+
+        let nuts = &self.mint_info.nuts;
+        let advertised = nuts
+            .nut04
+            .methods
+            .iter()
+            .map(|settings| (&settings.unit, &settings.method))
+            .chain(
+                nuts.nut05
+                    .methods
+                    .iter()
+                    .map(|settings| (&settings.unit, &settings.method)),
+            );
+        for (unit, method) in advertised {
+            let key = PaymentProcessorKey {
+                unit: unit.clone(),
+                method: method.clone(),
+            };
+            if !self.payment_processors.contains_key(&key) {
+                return Err(MintBuilderError::NoBackendForMethod(
+                    unit.clone(),
+                    method.clone(),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
     /// Build the mint with the provided signatory
+    ///
+    /// Fails with a [`MintBuilderError`] if the mint has no unit, or if its info advertises a
+    /// unit and method no payment backend was added for.
     pub async fn build_with_signatory(
         self,
         signatory: Arc<dyn Signatory + Send + Sync>,
     ) -> Result<Mint, Error> {
+        self.validate()?;
+
+        let startup_check_config = self.startup_check_config;
+        let backend_invoice_selfcheck = self.backend_invoice_selfcheck;
+        let request_limits = self.request_limits;
+        let melt_change_policy = self.melt_change_policy;
+        let max_subscription_filters = self.max_subscription_filters;
+        let reuse_melt_quotes = self.reuse_melt_quotes;
+        let melt_progress_interval = self.melt_progress_interval;
+        let max_amounts = self.max_amounts;
+        let payment_limiter = self
+            .payment_concurrency_limit
+            .map(|limit| PaymentLimiter::new(limit, self.payment_processors.keys()))
+            .unwrap_or_default();
+        let ban_list = self.ban_policy.map(BanList::new).unwrap_or_default();
+        let stuck_quotes = StuckQuoteMonitor::new(self.pending_alert);
+
         #[cfg(feature = "auth")]
-        if let Some(auth_localstore) = self.auth_localstore {
-            return Mint::new_with_auth(
+        let mut mint = if let Some(auth_localstore) = self.auth_localstore {
+            Mint::new_with_auth(
                 self.mint_info,
                 signatory,
                 self.localstore,
                 auth_localstore,
                 self.payment_processors,
             )
-            .await;
-        }
-        Mint::new(
+            .await?
+        } else {
+            Mint::new(
+                self.mint_info,
+                signatory,
+                self.localstore,
+                self.payment_processors,
+            )
+            .await?
+        };
+        #[cfg(not(feature = "auth"))]
+        let mut mint = Mint::new(
             self.mint_info,
             signatory,
             self.localstore,
             self.payment_processors,
         )
-        .await
+        .await?;
+
+        mint.startup_check_config = startup_check_config;
+        mint.backend_invoice_selfcheck = backend_invoice_selfcheck;
+        mint.request_limits = request_limits;
+        mint.melt_change_policy = melt_change_policy;
+        mint.max_subscription_filters = max_subscription_filters;
+        mint.reuse_melt_quotes = reuse_melt_quotes;
+        mint.melt_progress_interval = melt_progress_interval;
+        mint.max_amounts = max_amounts;
+        mint.payment_limiter = payment_limiter;
+        mint.ban_list = ban_list;
+        mint.stuck_quotes = stuck_quotes;
+
+        Ok(mint)
     }
 
     /// Build the mint with the provided keystore and seed
+    ///
+    /// Fails like [`MintBuilder::build_with_signatory`], or with
+    /// [`MintBuilderError::EmptySeed`].
     pub async fn build_with_seed(
         self,
         keystore: Arc<dyn MintKeysDatabase<Err = cdk_database::Error> + Send + Sync>,
         seed: &[u8],
     ) -> Result<Mint, Error> {
-        let in_memory_signatory = cdk_signatory::db_signatory::DbSignatory::new(
+        if seed.is_empty() {
+            return Err(MintBuilderError::EmptySeed.into());
+        }
+        self.validate()?;
+
+        let in_memory_signatory = cdk_signatory::db_signatory::DbSignatory::new_with_options(
             keystore,
             seed,
             self.supported_units.clone(),
             HashMap::new(),
+            cdk_signatory::db_signatory::DbSignatoryOptions {
+                derivation_account: self.derivation_account,
+                accept_new_seed: self.accept_new_seed,
+            },
         )
         .await?;
 
@@ -772,6 +1054,49 @@ mod tests {
         assert!(melt_method.options.is_none());
     }
 
+    #[tokio::test]
+    async fn test_add_payment_processor_keysend() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let mut builder = MintBuilder::new(localstore);
+
+        let mut custom_methods = HashMap::new();
+        custom_methods.insert(
+            KEYSEND_METHOD.to_string(),
+            serde_json::to_string(&KeysendSettings { fee_probe: true }).unwrap(),
+        );
+
+        let settings = SettingsResponse {
+            unit: "sat".to_string(),
+            bolt11: None,
+            bolt12: None,
+            custom: custom_methods,
+        };
+
+        let payment_processor = Arc::new(MockPaymentProcessor { settings });
+        let unit = CurrencyUnit::Sat;
+        let method = PaymentMethod::from(KEYSEND_METHOD);
+        let limits = MintMeltLimits::new(1, 10000);
+
+        builder
+            .add_payment_processor(unit.clone(), method.clone(), limits, payment_processor)
+            .await
+            .unwrap();
+
+        let mint_info = builder.current_mint_info();
+
+        // Keysend can only be melted
+        assert!(mint_info.nuts.nut04.methods.is_empty());
+
+        assert_eq!(mint_info.nuts.nut05.methods.len(), 1);
+        let melt_method = &mint_info.nuts.nut05.methods[0];
+        assert_eq!(melt_method.method, method);
+        assert_eq!(melt_method.unit, unit);
+        assert_eq!(
+            melt_method.options,
+            Some(MeltMethodOptions::Keysend { fee_probe: true })
+        );
+    }
+
     #[tokio::test]
     async fn test_add_payment_processor_custom_not_supported() {
         let localstore = Arc::new(memory::empty().await.unwrap());
@@ -859,4 +1184,232 @@ mod tests {
         assert_eq!(mint_info.nuts.nut04.methods.len(), 2);
         assert_eq!(mint_info.nuts.nut05.methods.len(), 2);
     }
+
+    #[tokio::test]
+    async fn test_payment_processor_limits_per_unit() {
+        use cdk_common::MintQuoteBolt11Request;
+
+        use crate::mint::MintQuoteRequest;
+
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let mut builder = MintBuilder::new(localstore.clone());
+        let method = PaymentMethod::Known(KnownMethod::Bolt11);
+
+        for (unit, limits) in [
+            (CurrencyUnit::Sat, MintMeltLimits::new(1, 10_000)),
+            (CurrencyUnit::Usd, MintMeltLimits::new(100, 1_000)),
+        ] {
+            let settings = SettingsResponse {
+                unit: unit.to_string(),
+                bolt11: Some(Bolt11Settings {
+                    mpp: false,
+                    amountless: false,
+                    invoice_description: false,
+                }),
+                bolt12: None,
+                custom: HashMap::new(),
+            };
+
+            builder
+                .add_payment_processor(
+                    unit,
+                    method.clone(),
+                    limits,
+                    Arc::new(MockPaymentProcessor { settings }),
+                )
+                .await
+                .unwrap();
+        }
+
+        let melt_settings = builder
+            .current_mint_info()
+            .nuts
+            .nut05
+            .get_settings(&CurrencyUnit::Usd, &method)
+            .unwrap();
+        assert_eq!(melt_settings.max_amount, Some(1_000.into()));
+
+        let mint = builder
+            .build_with_seed(localstore, &[0u8; 32])
+            .await
+            .unwrap();
+
+        let quote_request = |amount: u64, unit: CurrencyUnit| {
+            MintQuoteRequest::Bolt11(MintQuoteBolt11Request {
+                amount: amount.into(),
+                unit,
+                description: None,
+                pubkey: None,
+            })
+        };
+
+        // 5_000 is within the sat range but above the usd maximum
+        assert!(mint
+            .check_mint_request_acceptable(&quote_request(5_000, CurrencyUnit::Sat))
+            .await
+            .is_ok());
+        assert!(matches!(
+            mint.check_mint_request_acceptable(&quote_request(5_000, CurrencyUnit::Usd))
+                .await,
+            Err(Error::AmountOutofLimitRange(..))
+        ));
+
+        // 10 is within the sat range but below the usd minimum
+        assert!(mint
+            .check_mint_request_acceptable(&quote_request(10, CurrencyUnit::Sat))
+            .await
+            .is_ok());
+        assert!(matches!(
+            mint.check_mint_request_acceptable(&quote_request(10, CurrencyUnit::Usd))
+                .await,
+            Err(Error::AmountOutofLimitRange(..))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_custom_unit_gets_keyset_and_metadata() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let gold = CurrencyUnit::Custom("gold".to_string());
+        let metadata = UnitMetadata {
+            unit: gold.clone(),
+            symbol: "Au".to_string(),
+            decimals: 3,
+            description: Some("Grams of gold".to_string()),
+        };
+
+        let builder = MintBuilder::new(localstore.clone())
+            .with_custom_unit(metadata.clone())
+            .with_custom_unit(metadata.clone());
+
+        assert_eq!(builder.current_mint_info().nuts.units, vec![metadata]);
+
+        let mint = builder
+            .build_with_seed(localstore, &[0u8; 32])
+            .await
+            .unwrap();
+
+        assert!(mint
+            .keysets()
+            .keysets
+            .iter()
+            .any(|keyset| keyset.active && keyset.unit == gold));
+    }
+
+    fn bolt11_processor(unit: &CurrencyUnit) -> Arc<MockPaymentProcessor> {
+        Arc::new(MockPaymentProcessor {
+            settings: SettingsResponse {
+                unit: unit.to_string(),
+                bolt11: Some(Bolt11Settings {
+                    mpp: false,
+                    amountless: false,
+                    invoice_description: false,
+                }),
+                bolt12: None,
+                custom: HashMap::new(),
+            },
+        })
+    }
+
+    async fn bolt11_builder(localstore: DynMintDatabase) -> MintBuilder {
+        let mut builder = MintBuilder::new(localstore);
+        builder
+            .add_payment_processor(
+                CurrencyUnit::Sat,
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                MintMeltLimits::new(1, 10_000),
+                bolt11_processor(&CurrencyUnit::Sat),
+            )
+            .await
+            .unwrap();
+        builder
+    }
+
+    #[tokio::test]
+    async fn test_build_without_units_fails() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+
+        let result = MintBuilder::new(localstore.clone())
+            .build_with_seed(localstore, &[0u8; 32])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MintBuilder(MintBuilderError::NoPaymentBackend))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_payment_processor_fails() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let mut builder = bolt11_builder(localstore).await;
+
+        let result = builder
+            .add_payment_processor(
+                CurrencyUnit::Sat,
+                PaymentMethod::Known(KnownMethod::Bolt11),
+                MintMeltLimits::new(1, 10_000),
+                bolt11_processor(&CurrencyUnit::Sat),
+            )
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MintBuilder(MintBuilderError::DuplicatePaymentBackend(unit, _)))
+                if unit == CurrencyUnit::Sat
+        ));
+        // The first backend is still advertised once
+        assert_eq!(builder.current_mint_info().nuts.nut04.methods.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_advertised_method_without_backend_fails() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let builder = bolt11_builder(localstore.clone()).await;
+
+        // Mint info advertising usd, e.g. stored by a previous run with another backend
+        let mut mint_info = builder.current_mint_info();
+        let mut usd = mint_info.nuts.nut04.methods[0].clone();
+        usd.unit = CurrencyUnit::Usd;
+        mint_info.nuts.nut04.methods.push(usd);
+
+        let result = builder
+            .with_mint_info(mint_info)
+            .build_with_seed(localstore, &[0u8; 32])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MintBuilder(MintBuilderError::NoBackendForMethod(unit, _)))
+                if unit == CurrencyUnit::Usd
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_zero_payment_concurrency_fails() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let builder = bolt11_builder(localstore.clone()).await;
+
+        let result = builder
+            .with_payment_concurrency_limit(PaymentConcurrencyLimit::new(0))
+            .build_with_seed(localstore, &[0u8; 32])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MintBuilder(MintBuilderError::ZeroPaymentConcurrency))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_build_with_empty_seed_fails() {
+        let localstore = Arc::new(memory::empty().await.unwrap());
+        let builder = bolt11_builder(localstore.clone()).await;
+
+        let result = builder.build_with_seed(localstore, &[]).await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MintBuilder(MintBuilderError::EmptySeed))
+        ));
+    }
 }
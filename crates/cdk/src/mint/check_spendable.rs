@@ -1,5 +1,3 @@
-use std::collections::HashMap;
-
 use tracing::instrument;
 
 use super::{CheckStateRequest, CheckStateResponse, Mint, ProofState, State};
@@ -7,48 +5,45 @@ use crate::Error;
 
 impl Mint {
     /// Check state
+    ///
+    /// Requests with more Ys than the configured request limit are rejected before the database
+    /// is queried. States and witnesses are read in a single lookup.
     #[instrument(skip_all)]
     pub async fn check_state(
         &self,
         check_state: &CheckStateRequest,
     ) -> Result<CheckStateResponse, Error> {
-        let states = self.localstore.get_proofs_states(&check_state.ys).await?;
+        let max_ys = self.request_limits.max_check_state_ys;
+        if check_state.ys.len() > max_ys {
+            tracing::debug!(
+                "Check state attempted with {} Ys, maximum is {}",
+                check_state.ys.len(),
+                max_ys
+            );
+            return Err(Error::MaxInputsExceeded(check_state.ys.len(), max_ys));
+        }
+
+        let states = self
+            .localstore
+            .get_proofs_states_with_witness(&check_state.ys)
+            .await?;
 
         if check_state.ys.len() != states.len() {
             tracing::error!("Database did not return states for all proofs");
             return Err(Error::UnknownPaymentState);
         }
 
-        // Collect ys that need witness fetching (where state.is_some())
-        let ys_needing_witness: Vec<_> = check_state
-            .ys
-            .iter()
-            .zip(states.iter())
-            .filter_map(|(y, state)| state.as_ref().map(|_| *y))
-            .collect();
-
-        // Build a lookup map for witnesses (only query if there are ys to fetch)
-        let witness_map: HashMap<_, _> = if ys_needing_witness.is_empty() {
-            HashMap::new()
-        } else {
-            self.localstore
-                .get_proofs_by_ys(&ys_needing_witness)
-                .await?
-                .into_iter()
-                .flatten()
-                .filter_map(|p| p.y().ok().map(|y| (y, p.witness)))
-                .collect()
-        };
-
-        // Construct response without additional queries
         let proof_states = check_state
             .ys
             .iter()
-            .zip(states.iter())
-            .map(|(y, state)| ProofState {
-                y: *y,
-                state: state.unwrap_or(State::Unspent),
-                witness: witness_map.get(y).cloned().flatten(),
+            .zip(states)
+            .map(|(y, state)| {
+                let (state, witness) = state.unwrap_or((State::Unspent, None));
+                ProofState {
+                    y: *y,
+                    state,
+                    witness,
+                }
             })
             .collect();
 
@@ -57,3 +52,61 @@ impl Mint {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use cdk_common::nut06::RequestLimits;
+    use cdk_common::Amount;
+
+    use super::*;
+    use crate::nuts::nut00::ProofsMethods;
+    use crate::nuts::SecretKey;
+    use crate::test_helpers::mint::{create_test_mint, mint_test_proofs};
+
+    #[tokio::test]
+    async fn test_check_state_over_the_limit_is_rejected() {
+        let mut mint = create_test_mint().await.unwrap();
+        let proofs = mint_test_proofs(&mint, Amount::from(7)).await.unwrap();
+        let ys = proofs.ys().unwrap();
+        assert_eq!(ys.len(), 3);
+
+        mint.request_limits = RequestLimits {
+            max_check_state_ys: 2,
+            ..Default::default()
+        };
+
+        let err = mint
+            .check_state(&CheckStateRequest { ys: ys.clone() })
+            .await
+            .expect_err("Check state over the limit should be rejected");
+        assert_eq!(err.to_string(), Error::MaxInputsExceeded(3, 2).to_string());
+
+        // Exactly at the limit is allowed
+        let response = mint
+            .check_state(&CheckStateRequest {
+                ys: ys[..2].to_vec(),
+            })
+            .await
+            .expect("Check state at the limit should succeed");
+        assert_eq!(response.states.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_check_state_keeps_request_order() {
+        let mint = create_test_mint().await.unwrap();
+        let proofs = mint_test_proofs(&mint, Amount::from(3)).await.unwrap();
+        let unknown = SecretKey::generate().public_key();
+        let ys = vec![proofs.ys().unwrap()[1], unknown, proofs.ys().unwrap()[0]];
+
+        let response = mint
+            .check_state(&CheckStateRequest { ys: ys.clone() })
+            .await
+            .unwrap();
+
+        assert_eq!(response.states.iter().map(|s| s.y).collect::<Vec<_>>(), ys);
+        assert!(response
+            .states
+            .iter()
+            .all(|s| s.state == State::Unspent && s.witness.is_none()));
+    }
+}
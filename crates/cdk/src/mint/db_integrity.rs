@@ -0,0 +1,438 @@
+//! Database integrity checks
+//!
+//! The checks only use the mint database traits, so they work the same for every backend. They
+//! read the whole database and are meant to be run offline, e.g. before upgrading the mint. Only
+//! repairs that can not lose funds are applied: a keyset counter that fell behind the stored
+//! signatures is raised, everything else is reported for the operator to investigate.
+
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+use cdk_common::database::{self, DynMintDatabase, MintKeysDatabase};
+use cdk_common::mint::MintQuote;
+use cdk_common::nuts::{MeltQuoteState, State};
+use tracing::instrument;
+
+use crate::mint::{MeltQuote, QuoteId};
+use crate::nuts::{Id, PublicKey};
+use crate::{Amount, Error};
+
+/// Inconsistency found in the mint database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DbIntegrityIssue {
+    /// Blind signatures were issued by a keyset missing from the keys database
+    UnknownKeysetSignatures {
+        /// Keyset id referenced by the signatures
+        keyset_id: Id,
+        /// Number of signatures
+        count: usize,
+    },
+    /// The proofs of a keyset can not be read, e.g. because a state is not a known value
+    UnreadableProofs {
+        /// Keyset of the proofs
+        keyset_id: Id,
+        /// Database error
+        error: String,
+    },
+    /// A proof used to pay a melt quote that is paid is still unspent
+    UnspentProofOfPaidMelt {
+        /// Melt quote
+        quote_id: QuoteId,
+        /// Y of the proof
+        y: PublicKey,
+    },
+    /// A melt quote stores a payment preimage but is not paid
+    PreimageOfUnpaidMelt {
+        /// Melt quote
+        quote_id: QuoteId,
+        /// State of the quote
+        state: MeltQuoteState,
+    },
+    /// A mint quote issued more than was paid to it
+    MintQuoteOverIssued {
+        /// Mint quote
+        quote_id: QuoteId,
+    },
+    /// The total issued counter of a keyset is below the amount of its stored signatures
+    IssuedCounterBehind {
+        /// Keyset of the counter
+        keyset_id: Id,
+        /// Counter value
+        counter: Amount,
+        /// Amount of the stored signatures
+        signed: Amount,
+    },
+}
+
+impl DbIntegrityIssue {
+    /// Whether the issue can be repaired without risk
+    pub fn is_fixable(&self) -> bool {
+        matches!(self, Self::IssuedCounterBehind { .. })
+    }
+}
+
+impl fmt::Display for DbIntegrityIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::UnknownKeysetSignatures { keyset_id, count } => write!(
+                f,
+                "{count} blind signatures reference unknown keyset {keyset_id}"
+            ),
+            Self::UnreadableProofs { keyset_id, error } => {
+                write!(f, "proofs of keyset {keyset_id} can not be read: {error}")
+            }
+            Self::UnspentProofOfPaidMelt { quote_id, y } => write!(
+                f,
+                "proof {y} of paid melt quote {quote_id} is unspent"
+            ),
+            Self::PreimageOfUnpaidMelt { quote_id, state } => write!(
+                f,
+                "melt quote {quote_id} stores a preimage but is {state}"
+            ),
+            Self::MintQuoteOverIssued { quote_id } => {
+                write!(f, "mint quote {quote_id} issued more than was paid")
+            }
+            Self::IssuedCounterBehind {
+                keyset_id,
+                counter,
+                signed,
+            } => write!(
+                f,
+                "total issued of keyset {keyset_id} is {counter} but its signatures amount to {signed}"
+            ),
+        }
+    }
+}
+
+/// Result of a database integrity check
+#[derive(Debug, Clone, Default)]
+pub struct DbIntegrityReport {
+    /// Number of keysets checked, including unknown keysets referenced by signatures or proofs
+    pub keysets_checked: usize,
+    /// Number of melt quotes checked
+    pub melt_quotes_checked: usize,
+    /// Number of mint quotes checked
+    pub mint_quotes_checked: usize,
+    /// Issues left in the database
+    pub issues: Vec<DbIntegrityIssue>,
+    /// Issues that were repaired
+    pub fixed: Vec<DbIntegrityIssue>,
+}
+
+impl DbIntegrityReport {
+    /// Whether no issue is left
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty()
+    }
+}
+
+/// Check the mint database for inconsistencies
+///
+/// With `fix`, the fixable issues are repaired in a single transaction and reported as fixed.
+#[instrument(skip_all)]
+pub async fn verify_database(
+    localstore: &DynMintDatabase,
+    keystore: &(dyn MintKeysDatabase<Err = database::Error> + Send + Sync),
+    fix: bool,
+) -> Result<DbIntegrityReport, Error> {
+    let mut report = DbIntegrityReport::default();
+
+    let known_keysets = keystore
+        .get_keyset_infos()
+        .await?
+        .into_iter()
+        .map(|keyset| keyset.id)
+        .collect::<BTreeSet<_>>();
+    let total_issued = localstore.get_total_issued().await?;
+    let total_redeemed = localstore.get_total_redeemed().await?;
+
+    // Keysets only known from the counters were used by signatures or proofs at some point
+    let keysets = known_keysets
+        .iter()
+        .chain(total_issued.keys())
+        .chain(total_redeemed.keys())
+        .copied()
+        .collect::<BTreeSet<_>>();
+    report.keysets_checked = keysets.len();
+
+    for keyset_id in keysets {
+        check_keyset(
+            localstore,
+            &keyset_id,
+            known_keysets.contains(&keyset_id),
+            &total_issued,
+            &mut report.issues,
+        )
+        .await?;
+    }
+
+    let melt_quotes = localstore.get_melt_quotes().await?;
+    report.melt_quotes_checked = melt_quotes.len();
+    for quote in melt_quotes {
+        check_melt_quote(localstore, &quote, &mut report.issues).await?;
+    }
+
+    let mint_quotes = localstore.get_mint_quotes().await?;
+    report.mint_quotes_checked = mint_quotes.len();
+    for quote in mint_quotes {
+        check_mint_quote(&quote, &mut report.issues);
+    }
+
+    if fix {
+        let (fixable, issues) = report
+            .issues
+            .into_iter()
+            .partition::<Vec<_>, _>(DbIntegrityIssue::is_fixable);
+        report.issues = issues;
+
+        if !fixable.is_empty() {
+            let mut tx = localstore.begin_transaction().await?;
+            for issue in &fixable {
+                if let DbIntegrityIssue::IssuedCounterBehind {
+                    keyset_id, signed, ..
+                } = issue
+                {
+                    tx.raise_total_issued(keyset_id, *signed).await?;
+                }
+            }
+            tx.commit().await?;
+
+            tracing::info!("Repaired {} database issues", fixable.len());
+        }
+
+        report.fixed = fixable;
+    }
+
+    Ok(report)
+}
+
+async fn check_keyset(
+    localstore: &DynMintDatabase,
+    keyset_id: &Id,
+    known: bool,
+    total_issued: &HashMap<Id, Amount>,
+    issues: &mut Vec<DbIntegrityIssue>,
+) -> Result<(), Error> {
+    let signatures = localstore
+        .get_blind_signatures_for_keyset(keyset_id)
+        .await?;
+
+    if !known && !signatures.is_empty() {
+        issues.push(DbIntegrityIssue::UnknownKeysetSignatures {
+            keyset_id: *keyset_id,
+            count: signatures.len(),
+        });
+    }
+
+    let signed = Amount::try_sum(signatures.iter().map(|signature| signature.amount))?;
+    let counter = total_issued.get(keyset_id).copied().unwrap_or(Amount::ZERO);
+    if counter < signed {
+        issues.push(DbIntegrityIssue::IssuedCounterBehind {
+            keyset_id: *keyset_id,
+            counter,
+            signed,
+        });
+    }
+
+    if let Err(err) = localstore.get_proofs_by_keyset_id(keyset_id).await {
+        issues.push(DbIntegrityIssue::UnreadableProofs {
+            keyset_id: *keyset_id,
+            error: err.to_string(),
+        });
+    }
+
+    Ok(())
+}
+
+async fn check_melt_quote(
+    localstore: &DynMintDatabase,
+    quote: &MeltQuote,
+    issues: &mut Vec<DbIntegrityIssue>,
+) -> Result<(), Error> {
+    if quote.payment_preimage.is_some() && quote.state != MeltQuoteState::Paid {
+        issues.push(DbIntegrityIssue::PreimageOfUnpaidMelt {
+            quote_id: quote.id.clone(),
+            state: quote.state,
+        });
+    }
+
+    if quote.state == MeltQuoteState::Paid {
+        let ys = localstore.get_proof_ys_by_quote_id(&quote.id).await?;
+        let states = localstore.get_proofs_states(&ys).await?;
+
+        for (y, state) in ys.into_iter().zip(states) {
+            if state == Some(State::Unspent) {
+                issues.push(DbIntegrityIssue::UnspentProofOfPaidMelt {
+                    quote_id: quote.id.clone(),
+                    y,
+                });
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn check_mint_quote(quote: &MintQuote, issues: &mut Vec<DbIntegrityIssue>) {
+    if quote.amount_issued() > quote.amount_paid() {
+        issues.push(DbIntegrityIssue::MintQuoteOverIssued {
+            quote_id: quote.id.clone(),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use bitcoin::bip32::DerivationPath;
+    use cdk_common::mint::{MeltPaymentRequest, MintKeySetInfo, Operation};
+    use cdk_common::nuts::{BlindSignature, CurrencyUnit, PaymentMethod, Proof};
+    use cdk_common::secret::Secret;
+    use cdk_common::SecretKey;
+
+    use super::*;
+
+    async fn setup() -> (Arc<cdk_sqlite::mint::MintSqliteDatabase>, Id) {
+        let db = Arc::new(cdk_sqlite::mint::memory::empty().await.unwrap());
+        let keyset_id = Id::from_str("00916bbf7ef91a36").unwrap();
+
+        let mut tx = MintKeysDatabase::begin_transaction(db.as_ref())
+            .await
+            .unwrap();
+        tx.add_keyset_info(MintKeySetInfo {
+            id: keyset_id,
+            unit: CurrencyUnit::Sat,
+            active: true,
+            valid_from: 0,
+            final_expiry: None,
+            derivation_path: DerivationPath::from_str("m/0'/0'/0'").unwrap(),
+            derivation_path_index: Some(0),
+            input_fee_ppk: 0,
+            amounts: vec![1, 2, 4, 8],
+        })
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        (db, keyset_id)
+    }
+
+    fn signature(keyset_id: Id, amount: u64) -> BlindSignature {
+        BlindSignature {
+            amount: Amount::from(amount),
+            keyset_id,
+            c: SecretKey::generate().public_key(),
+            dleq: None,
+        }
+    }
+
+    fn melt_quote(state: MeltQuoteState, preimage: Option<&str>) -> MeltQuote {
+        let mut quote = MeltQuote::new(
+            MeltPaymentRequest::Custom {
+                method: "test".to_string(),
+                request: "request".to_string(),
+            },
+            CurrencyUnit::Sat,
+            cdk_common::Amount::new(8, CurrencyUnit::Sat),
+            cdk_common::Amount::new(1, CurrencyUnit::Sat),
+            0,
+            None,
+            None,
+            PaymentMethod::Custom("test".to_string()),
+        );
+        quote.state = state;
+        quote.payment_preimage = preimage.map(str::to_string);
+        quote
+    }
+
+    #[tokio::test]
+    async fn consistent_database() {
+        let (db, keyset_id) = setup().await;
+        let localstore: DynMintDatabase = db.clone();
+
+        let mut tx = localstore.begin_transaction().await.unwrap();
+        tx.add_blind_signatures(
+            &[SecretKey::generate().public_key()],
+            &[signature(keyset_id, 8)],
+            None,
+        )
+        .await
+        .unwrap();
+        tx.add_melt_quote(melt_quote(MeltQuoteState::Paid, Some("preimage")))
+            .await
+            .unwrap();
+        tx.commit().await.unwrap();
+
+        let report = verify_database(&localstore, db.as_ref(), false)
+            .await
+            .unwrap();
+        assert!(report.is_ok(), "{:?}", report.issues);
+        assert_eq!(report.keysets_checked, 1);
+        assert_eq!(report.melt_quotes_checked, 1);
+    }
+
+    #[tokio::test]
+    async fn reports_inconsistencies() {
+        let (db, keyset_id) = setup().await;
+        let localstore: DynMintDatabase = db.clone();
+        let unknown_keyset_id = Id::from_str("001711afb1de20cb").unwrap();
+
+        let paid_quote = melt_quote(MeltQuoteState::Paid, None);
+        let unpaid_quote = melt_quote(MeltQuoteState::Unpaid, Some("preimage"));
+        let proof = Proof {
+            amount: Amount::from(8),
+            keyset_id,
+            secret: Secret::generate(),
+            c: SecretKey::generate().public_key(),
+            witness: None,
+            dleq: None,
+        };
+        let y = proof.y().unwrap();
+
+        let mut tx = localstore.begin_transaction().await.unwrap();
+        tx.add_blind_signatures(
+            &[SecretKey::generate().public_key()],
+            &[signature(unknown_keyset_id, 4)],
+            None,
+        )
+        .await
+        .unwrap();
+        tx.add_melt_quote(paid_quote.clone()).await.unwrap();
+        tx.add_melt_quote(unpaid_quote.clone()).await.unwrap();
+        tx.add_proofs(
+            vec![proof],
+            Some(paid_quote.id.clone()),
+            &Operation::new_swap(Amount::ZERO, Amount::ZERO, Amount::ZERO),
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        let report = verify_database(&localstore, db.as_ref(), true)
+            .await
+            .unwrap();
+        assert_eq!(report.keysets_checked, 2);
+        assert!(report.fixed.is_empty());
+        assert_eq!(report.issues.len(), 3);
+        assert!(report
+            .issues
+            .contains(&DbIntegrityIssue::UnknownKeysetSignatures {
+                keyset_id: unknown_keyset_id,
+                count: 1,
+            }));
+        assert!(report
+            .issues
+            .contains(&DbIntegrityIssue::UnspentProofOfPaidMelt {
+                quote_id: paid_quote.id,
+                y,
+            }));
+        assert!(report
+            .issues
+            .contains(&DbIntegrityIssue::PreimageOfUnpaidMelt {
+                quote_id: unpaid_quote.id,
+                state: MeltQuoteState::Unpaid,
+            }));
+    }
+}
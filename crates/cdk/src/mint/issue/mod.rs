@@ -1,5 +1,9 @@
+use std::collections::HashSet;
+use std::str::FromStr;
+
 use cdk_common::database::Acquired;
 use cdk_common::mint::{MintQuote, Operation};
+use cdk_common::mint_url::MintUrl;
 use cdk_common::nut00::KnownMethod;
 use cdk_common::payment::{
     Bolt11IncomingPaymentOptions, Bolt12IncomingPaymentOptions, CustomIncomingPaymentOptions,
@@ -8,21 +12,26 @@ use cdk_common::payment::{
 use cdk_common::quote_id::QuoteId;
 use cdk_common::util::unix_time;
 use cdk_common::{
-    database, ensure_cdk, Amount, CurrencyUnit, Error, MintQuoteBolt11Request,
+    database, ensure_cdk, Amount, BlindSignature, CurrencyUnit, Error, MintQuoteBolt11Request,
     MintQuoteBolt11Response, MintQuoteBolt12Request, MintQuoteBolt12Response,
-    MintQuoteCustomRequest, MintQuoteCustomResponse, MintQuoteState, MintRequest, MintResponse,
-    NotificationPayload, PaymentMethod, PublicKey,
+    MintQuoteCustomRequest, MintQuoteCustomResponse, MintQuoteState, MintQuotesPubkey,
+    MintQuotesRequest, MintQuotesResponse, MintRequest, MintResponse, NotificationPayload,
+    PaymentMethod, PublicKey, MAX_MINT_QUOTES_PUBKEYS,
 };
 #[cfg(feature = "prometheus")]
 use cdk_prometheus::METRICS;
 use tracing::instrument;
 
 use crate::mint::Verification;
+use crate::types::PaymentProcessorKey;
 use crate::Mint;
 
 #[cfg(feature = "auth")]
 mod auth;
 
+/// Seconds a signed [`MintQuotesRequest`] is accepted before or after its timestamp
+const MINT_QUOTES_REQUEST_WINDOW: u64 = 300;
+
 /// Request for creating a mint quote
 ///
 /// This enum represents the different types of payment requests that can be used
@@ -173,6 +182,8 @@ impl From<MintQuoteResponse> for MintQuoteBolt11Response<String> {
                 pubkey: bolt11_response.pubkey,
                 amount: bolt11_response.amount,
                 unit: bolt11_response.unit,
+                amount_paid: bolt11_response.amount_paid,
+                partial: bolt11_response.partial,
             },
             _ => panic!("Expected Bolt11 response"),
         }
@@ -329,6 +340,14 @@ impl Mint {
                 }
             };
 
+            let _permit = self
+                .payment_limiter
+                .acquire(&PaymentProcessorKey::new(
+                    unit.clone(),
+                    payment_method.clone(),
+                ))
+                .await?;
+
             let create_invoice_response = ln
                 .create_incoming_payment_request(&unit, payment_options)
                 .await
@@ -421,6 +440,53 @@ impl Mint {
         result
     }
 
+    /// Lists the mint quotes locked to the pubkeys of a signed request
+    ///
+    /// The request must be signed by each of its pubkeys within [`MINT_QUOTES_REQUEST_WINDOW`]
+    /// of the time of the mint, so a leaked request cannot be replayed for long, and for one of
+    /// the urls of the mint, so it cannot be replayed to another mint. A mint without configured
+    /// urls refuses every request. At most [`MAX_MINT_QUOTES_PUBKEYS`]
+    /// pubkeys are accepted. Quotes created without a pubkey are never listed. The states are
+    /// those stored by the mint, the payment backend is not queried.
+    #[instrument(skip_all)]
+    pub async fn mint_quotes_by_pubkeys(
+        &self,
+        request: &MintQuotesRequest,
+    ) -> Result<MintQuotesResponse<QuoteId>, Error> {
+        if request.pubkeys.len() > MAX_MINT_QUOTES_PUBKEYS {
+            return Err(cdk_common::nut20::Error::TooManyPubkeys(MAX_MINT_QUOTES_PUBKEYS).into());
+        }
+
+        request.verify_signatures()?;
+
+        if unix_time().abs_diff(request.timestamp) > MINT_QUOTES_REQUEST_WINDOW {
+            return Err(cdk_common::nut20::Error::StaleTimestamp.into());
+        }
+
+        let urls = self.mint_info().await?.urls.unwrap_or_default();
+        if !urls
+            .iter()
+            .filter_map(|url| MintUrl::from_str(url).ok())
+            .any(|url| url == request.mint_url)
+        {
+            return Err(cdk_common::nut20::Error::MintUrlMismatch.into());
+        }
+
+        let mut seen = HashSet::new();
+        let mut quotes = Vec::new();
+        for MintQuotesPubkey { pubkey, .. } in &request.pubkeys {
+            if !seen.insert(pubkey) {
+                continue;
+            }
+
+            for quote in self.localstore.get_mint_quotes_by_pubkey(pubkey).await? {
+                quotes.push(quote.try_into()?);
+            }
+        }
+
+        Ok(MintQuotesResponse { quotes })
+    }
+
     /// Marks a mint quote as paid based on the payment request ID
     ///
     /// Looks up the mint quote by the payment request ID and marks it as paid
@@ -566,6 +632,39 @@ impl Mint {
         result
     }
 
+    /// Returns the stored signatures when `mint_request` repeats an issuance that already
+    /// succeeded for its quote
+    ///
+    /// This lets a wallet that lost the response of a successful mint request retry it. The
+    /// outputs must be exactly those of one previous issuance, any other overlap is not a replay.
+    async fn replayed_mint_signatures(
+        tx: &mut Box<dyn database::MintTransaction<database::Error> + Send + Sync>,
+        mint_request: &MintRequest<QuoteId>,
+    ) -> Result<Option<Vec<BlindSignature>>, Error> {
+        let issuances = tx
+            .get_blind_signatures_for_quote_by_operation(&mint_request.quote)
+            .await?;
+
+        for outputs in issuances.into_values() {
+            if outputs.len() != mint_request.outputs.len() {
+                continue;
+            }
+
+            let mut signatures: std::collections::HashMap<_, _> = outputs.into_iter().collect();
+            let replayed = mint_request
+                .outputs
+                .iter()
+                .map(|output| signatures.remove(&output.blinded_secret))
+                .collect::<Option<Vec<_>>>();
+
+            if replayed.is_some() {
+                return Ok(replayed);
+            }
+        }
+
+        Ok(None)
+    }
+
     /// Processes a mint request to issue new tokens
     ///
     /// This function:
@@ -576,6 +675,9 @@ impl Mint {
     /// 5. Updates the quote status
     /// 6. Broadcasts a notification about the status change
     ///
+    /// A request repeating the exact outputs of a previous successful issuance for the same
+    /// quote returns the stored signatures instead of an error, so wallets can safely retry.
+    ///
     /// # Arguments
     /// * `mint_request` - The mint request containing blinded outputs to sign
     ///
@@ -590,6 +692,8 @@ impl Mint {
         #[cfg(feature = "prometheus")]
         METRICS.inc_in_flight_requests("process_mint_request");
         let result = async {
+            self.check_request_limits(0, mint_request.outputs.len())?;
+
             let mut mint_quote = self
                 .localstore
                 .get_mint_quote(&mint_request.quote)
@@ -611,6 +715,22 @@ impl Mint {
             .await?
             .ok_or(Error::UnknownQuote)?;
 
+        if mint_quote.state() != MintQuoteState::Unpaid {
+            if let Some(signatures) = Self::replayed_mint_signatures(&mut tx, &mint_request).await? {
+                if let Some(pubkey) = mint_quote.pubkey {
+                    mint_request.verify_signature(pubkey)?;
+                }
+
+                tx.rollback().await?;
+                tracing::debug!(
+                    "Returning stored signatures for replayed mint request on quote {}",
+                    mint_request.quote
+                );
+
+                return Ok(MintResponse { signatures });
+            }
+        }
+
         match mint_quote.state() {
             MintQuoteState::Unpaid => {
                 return Err(Error::UnpaidQuote);
@@ -635,11 +755,20 @@ impl Mint {
         let mint_amount = if mint_quote.payment_method.is_bolt11() {
             let quote_amount = mint_quote.amount.clone().ok_or(Error::AmountUndefined)?;
 
-            if quote_amount != mint_quote.amount_mintable() {
-                tracing::error!("The quote amount {} does not equal the amount paid {}.", quote_amount, mint_quote.amount_mintable());
+            if mint_quote.amount_mintable() < quote_amount {
+                tracing::error!("The quote amount {} is more than the amount paid {}.", quote_amount, mint_quote.amount_mintable());
                 return Err(Error::IncorrectQuoteAmount);
             }
 
+            if mint_quote.amount_mintable() > quote_amount {
+                tracing::warn!(
+                    "Mint quote {} was overpaid by {}, issuing the quoted amount {}",
+                    mint_quote.id,
+                    mint_quote.amount_overpaid(),
+                    quote_amount
+                );
+            }
+
             quote_amount
         } else if mint_quote.payment_method.is_bolt12() {
             let zero = Amount::new(0, mint_quote.unit.clone());
@@ -701,7 +830,14 @@ impl Mint {
         let amount_issued = mint_request.total_amount()?.with_unit(unit.clone());
         let operation = Operation::new_mint(amount_issued.clone().into(), mint_quote.payment_method.clone());
 
-        tx.add_blinded_messages(Some(&mint_request.quote), &mint_request.outputs, &operation).await?;
+        // The unique constraint on the blinded message rejects outputs reserved or signed by a
+        // concurrent request that slipped past `verify_outputs`
+        tx.add_blinded_messages(Some(&mint_request.quote), &mint_request.outputs, &operation)
+            .await
+            .map_err(|err| match err {
+                database::Error::Duplicate => Error::BlindedMessageAlreadySigned,
+                err => Error::Database(err),
+            })?;
 
         tx.add_blind_signatures(
             &mint_request
@@ -712,8 +848,11 @@ impl Mint {
             &blind_signatures,
             Some(mint_request.quote.clone()),
         )
-            .await?;
-
+            .await
+            .map_err(|err| match err {
+                database::Error::Duplicate => Error::BlindedMessageAlreadySigned,
+                err => Error::Database(err),
+            })?;
 
         mint_quote.add_issuance(amount_issued)?;
         tx.update_mint_quote(&mut mint_quote).await?;
@@ -745,3 +884,388 @@ impl Mint {
         result
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cdk_common::MintQuoteBolt11Request;
+    use cdk_fake_wallet::FakeIncomingPaymentDescription;
+
+    use super::*;
+    use crate::test_helpers::mint::{create_test_blinded_messages, create_test_mint};
+
+    /// Creates a paid bolt11 mint quote and a request minting it in full
+    async fn paid_mint_request(mint: &Mint, amount: Amount) -> MintRequest<QuoteId> {
+        let quote: MintQuoteBolt11Response<QuoteId> = mint
+            .get_mint_quote(
+                MintQuoteBolt11Request {
+                    amount,
+                    unit: CurrencyUnit::Sat,
+                    description: None,
+                    pubkey: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        loop {
+            let check: MintQuoteBolt11Response<QuoteId> = mint
+                .check_mint_quote(&quote.quote)
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            if check.state == MintQuoteState::Paid {
+                break;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+
+        let (outputs, _) = create_test_blinded_messages(mint, amount).await.unwrap();
+
+        MintRequest {
+            quote: quote.quote,
+            outputs,
+            signature: None,
+        }
+    }
+
+    /// Creates a bolt11 mint quote for `amount` whose invoice is paid with `amount_received`
+    async fn mint_quote_paid_with(
+        mint: &Mint,
+        amount: Amount,
+        amount_received: Amount,
+    ) -> MintQuoteBolt11Response<QuoteId> {
+        let description =
+            serde_json::to_string(&FakeIncomingPaymentDescription { amount_received }).unwrap();
+        let quote: MintQuoteBolt11Response<QuoteId> = mint
+            .get_mint_quote(
+                MintQuoteBolt11Request {
+                    amount,
+                    unit: CurrencyUnit::Sat,
+                    description: Some(description),
+                    pubkey: None,
+                }
+                .into(),
+            )
+            .await
+            .unwrap()
+            .try_into()
+            .unwrap();
+
+        loop {
+            let check: MintQuoteBolt11Response<QuoteId> = mint
+                .check_mint_quote(&quote.quote)
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+
+            if check.amount_paid.is_some() {
+                return check;
+            }
+
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+    }
+
+    fn signature_points(response: &MintResponse) -> Vec<PublicKey> {
+        response.signatures.iter().map(|s| s.c).collect()
+    }
+
+    #[tokio::test]
+    async fn test_mint_request_replay_returns_stored_signatures() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let request = paid_mint_request(&mint, amount).await;
+
+        let first = mint.process_mint_request(request.clone()).await.unwrap();
+        let replayed = mint.process_mint_request(request.clone()).await.unwrap();
+
+        assert_eq!(signature_points(&first), signature_points(&replayed));
+        assert_eq!(first.signatures.len(), request.outputs.len());
+
+        // The replay did not issue anything new
+        let quote = mint
+            .localstore
+            .get_mint_quote(&request.quote)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.state(), MintQuoteState::Issued);
+        assert_eq!(quote.amount_issued(), amount.with_unit(CurrencyUnit::Sat));
+    }
+
+    // Rejected requests drop their transaction, whose rollback is spawned onto the runtime
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mint_request_replay_with_different_outputs_is_rejected() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let request = paid_mint_request(&mint, amount).await;
+
+        mint.process_mint_request(request.clone()).await.unwrap();
+
+        // One output swapped for a fresh one
+        let (fresh, _) = create_test_blinded_messages(&mint, request.outputs[0].amount)
+            .await
+            .unwrap();
+        let mut partial = request.clone();
+        partial.outputs[0] = fresh[0].clone();
+        assert!(matches!(
+            mint.process_mint_request(partial).await,
+            Err(Error::IssuedQuote)
+        ));
+
+        // A subset of the issued outputs
+        let mut subset = request.clone();
+        subset.outputs.pop();
+        assert!(matches!(
+            mint.process_mint_request(subset).await,
+            Err(Error::IssuedQuote)
+        ));
+
+        // The issued outputs repeated
+        let mut repeated = request.clone();
+        repeated.outputs.extend(request.outputs.clone());
+        assert!(mint.process_mint_request(repeated).await.is_err());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_concurrent_duplicate_mint_requests_issue_once() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let request = paid_mint_request(&mint, amount).await;
+
+        let (first, second) = tokio::join!(
+            mint.process_mint_request(request.clone()),
+            mint.process_mint_request(request.clone())
+        );
+        let (first, second) = (first.unwrap(), second.unwrap());
+
+        assert_eq!(signature_points(&first), signature_points(&second));
+
+        let quote = mint
+            .localstore
+            .get_mint_quote(&request.quote)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.amount_issued(), amount.with_unit(CurrencyUnit::Sat));
+    }
+
+    #[tokio::test]
+    async fn test_mint_quote_paid_exact_amount() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let quote = mint_quote_paid_with(&mint, amount, amount).await;
+
+        assert_eq!(quote.state, MintQuoteState::Paid);
+        assert_eq!(quote.amount_paid, Some(amount));
+        assert!(!quote.partial);
+
+        let (outputs, _) = create_test_blinded_messages(&mint, amount).await.unwrap();
+        mint.process_mint_request(MintRequest {
+            quote: quote.quote.clone(),
+            outputs,
+            signature: None,
+        })
+        .await
+        .unwrap();
+
+        let quote = mint
+            .localstore
+            .get_mint_quote(&quote.quote)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.state(), MintQuoteState::Issued);
+        assert!(mint.total_overpaid().await.unwrap().is_empty());
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mint_quote_overpaid_issues_quoted_amount() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let quote = mint_quote_paid_with(&mint, amount, Amount::from(110)).await;
+
+        assert_eq!(quote.state, MintQuoteState::Paid);
+        assert_eq!(quote.amount_paid, Some(Amount::from(110)));
+        assert!(!quote.partial);
+
+        // The excess is not issued
+        let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(110))
+            .await
+            .unwrap();
+        assert!(matches!(
+            mint.process_mint_request(MintRequest {
+                quote: quote.quote.clone(),
+                outputs,
+                signature: None,
+            })
+            .await,
+            Err(Error::TransactionUnbalanced(..))
+        ));
+
+        let (outputs, _) = create_test_blinded_messages(&mint, amount).await.unwrap();
+        mint.process_mint_request(MintRequest {
+            quote: quote.quote.clone(),
+            outputs,
+            signature: None,
+        })
+        .await
+        .unwrap();
+
+        let quote = mint
+            .localstore
+            .get_mint_quote(&quote.quote)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.state(), MintQuoteState::Issued);
+        assert_eq!(quote.amount_issued(), amount.with_unit(CurrencyUnit::Sat));
+        assert_eq!(
+            mint.total_overpaid().await.unwrap().get(&CurrencyUnit::Sat),
+            Some(&Amount::from(10))
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_mint_quote_underpaid_is_partial() {
+        let mint = create_test_mint().await.unwrap();
+        let amount = Amount::from(100);
+        let quote = mint_quote_paid_with(&mint, amount, Amount::from(60)).await;
+
+        assert_eq!(quote.state, MintQuoteState::Unpaid);
+        assert_eq!(quote.amount_paid, Some(Amount::from(60)));
+        assert!(quote.partial);
+
+        let (outputs, _) = create_test_blinded_messages(&mint, amount).await.unwrap();
+        assert!(matches!(
+            mint.process_mint_request(MintRequest {
+                quote: quote.quote.clone(),
+                outputs,
+                signature: None,
+            })
+            .await,
+            Err(Error::UnpaidQuote)
+        ));
+        assert!(mint.total_overpaid().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_mint_quotes_by_pubkeys() {
+        let mint = create_test_mint().await.unwrap();
+        let mint_url = MintUrl::from_str("https://test-mint").unwrap();
+        let secret_keys = [
+            cdk_common::SecretKey::generate(),
+            cdk_common::SecretKey::generate(),
+        ];
+
+        let mut quote_ids = Vec::new();
+        for pubkey in secret_keys
+            .iter()
+            .map(|secret_key| Some(secret_key.public_key()))
+            .chain([None])
+        {
+            let quote: MintQuoteBolt11Response<QuoteId> = mint
+                .get_mint_quote(
+                    MintQuoteBolt11Request {
+                        amount: Amount::from(100),
+                        unit: CurrencyUnit::Sat,
+                        description: None,
+                        pubkey,
+                    }
+                    .into(),
+                )
+                .await
+                .unwrap()
+                .try_into()
+                .unwrap();
+            quote_ids.push(quote.quote);
+        }
+
+        // Only the quotes locked to the pubkeys are listed, with their pubkey
+        let request = MintQuotesRequest::new(&secret_keys, mint_url.clone(), unix_time()).unwrap();
+        let response = mint.mint_quotes_by_pubkeys(&request).await.unwrap();
+        assert_eq!(response.quotes.len(), 2);
+        for (status, (quote_id, secret_key)) in response
+            .quotes
+            .iter()
+            .zip(quote_ids.iter().zip(&secret_keys))
+        {
+            assert_eq!(&status.quote, quote_id);
+            assert_eq!(status.pubkey, secret_key.public_key());
+            assert_eq!(status.method, PaymentMethod::BOLT11);
+            assert_eq!(status.amount, Some(Amount::from(100)));
+        }
+
+        // Stale requests are refused
+        let stale =
+            MintQuotesRequest::new(&secret_keys, mint_url.clone(), unix_time() - 3_600).unwrap();
+        assert!(matches!(
+            mint.mint_quotes_by_pubkeys(&stale).await,
+            Err(Error::NUT20(cdk_common::nut20::Error::StaleTimestamp))
+        ));
+
+        // As are requests for another mint
+        let other_mint = MintUrl::from_str("https://other-mint").unwrap();
+        let replayed = MintQuotesRequest::new(&secret_keys, other_mint, unix_time()).unwrap();
+        assert!(matches!(
+            mint.mint_quotes_by_pubkeys(&replayed).await,
+            Err(Error::NUT20(cdk_common::nut20::Error::MintUrlMismatch))
+        ));
+
+        // requests not signed by one of the pubkeys
+        let mut forged = MintQuotesRequest::new(
+            &[cdk_common::SecretKey::generate()],
+            mint_url.clone(),
+            unix_time(),
+        )
+        .unwrap();
+        forged.pubkeys[0].pubkey = secret_keys[0].public_key();
+        assert!(matches!(
+            mint.mint_quotes_by_pubkeys(&forged).await,
+            Err(Error::NUT20(_))
+        ));
+
+        // and requests with too many pubkeys
+        let many: Vec<_> = (0..=MAX_MINT_QUOTES_PUBKEYS)
+            .map(|_| cdk_common::SecretKey::generate())
+            .collect();
+        let too_many = MintQuotesRequest::new(&many, mint_url, unix_time()).unwrap();
+        assert!(matches!(
+            mint.mint_quotes_by_pubkeys(&too_many).await,
+            Err(Error::NUT20(cdk_common::nut20::Error::TooManyPubkeys(_)))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_mint_quotes_by_pubkeys_without_urls() {
+        let mint = create_test_mint().await.unwrap();
+        let mut mint_info = mint.mint_info().await.unwrap();
+        mint_info.urls = None;
+        mint.set_mint_info(mint_info).await.unwrap();
+
+        // Without urls the mint can't tell a request signed for another mint, so none is
+        // accepted, whatever url it was signed for
+        let secret_keys = [cdk_common::SecretKey::generate()];
+        for mint_url in ["https://test-mint", "https://other-mint"] {
+            let request = MintQuotesRequest::new(
+                &secret_keys,
+                MintUrl::from_str(mint_url).unwrap(),
+                unix_time(),
+            )
+            .unwrap();
+            assert!(matches!(
+                mint.mint_quotes_by_pubkeys(&request).await,
+                Err(Error::NUT20(cdk_common::nut20::Error::MintUrlMismatch))
+            ));
+        }
+    }
+}
@@ -40,6 +40,7 @@ impl Mint {
                             active: key.active,
                             input_fee_ppk: key.input_fee_ppk,
                             final_expiry: key.final_expiry,
+                            max_amount: None,
                         })
                     } else {
                         None
@@ -0,0 +1,49 @@
+//! In-memory keysets of the mint
+//!
+//! The keysets are loaded from the signatory when the mint starts and reloaded after every
+//! rotation. Lookups by id are served from an index, so verifying inputs and outputs, computing
+//! fees and signing never reach the database for keys.
+
+use std::collections::HashMap;
+use std::ops::Deref;
+
+use cdk_signatory::signatory::SignatoryKeySet;
+
+use super::Id;
+
+/// Snapshot of the keysets served by the mint, indexed by id
+///
+/// A snapshot is never modified: rotations replace it as a whole, so a reader sees either the
+/// keysets before or after the rotation, never a mix with stale active flags.
+#[derive(Debug, Default)]
+pub(crate) struct KeysetCache {
+    keysets: Vec<SignatoryKeySet>,
+    index: HashMap<Id, usize>,
+}
+
+impl KeysetCache {
+    /// Keyset with the given id
+    pub(crate) fn get(&self, id: &Id) -> Option<&SignatoryKeySet> {
+        self.index.get(id).map(|position| &self.keysets[*position])
+    }
+}
+
+impl From<Vec<SignatoryKeySet>> for KeysetCache {
+    fn from(keysets: Vec<SignatoryKeySet>) -> Self {
+        let index = keysets
+            .iter()
+            .enumerate()
+            .map(|(position, keyset)| (keyset.id, position))
+            .collect();
+
+        Self { keysets, index }
+    }
+}
+
+impl Deref for KeysetCache {
+    type Target = [SignatoryKeySet];
+
+    fn deref(&self) -> &Self::Target {
+        &self.keysets
+    }
+}
@@ -1,13 +1,22 @@
-use cdk_signatory::signatory::RotateKeyArguments;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+
+use cdk_common::nut17::MintInfoNotification;
+use cdk_common::util::unix_time;
+use cdk_signatory::signatory::{KeysetAudit, KeysetAuditIssue, RotateKeyArguments};
 use tracing::instrument;
 
 use super::{
     CurrencyUnit, Id, KeySet, KeySetInfo, KeysResponse, KeysetResponse, Mint, MintKeySetInfo,
+    ResponseCacheScope,
 };
-use crate::Error;
+use crate::{Amount, Error};
 
 #[cfg(feature = "auth")]
 mod auth;
+mod cache;
+
+pub(crate) use cache::KeysetCache;
 
 impl Mint {
     /// Retrieve the public keys of the active keyset for distribution to wallet
@@ -16,8 +25,7 @@ impl Mint {
     pub fn keyset_pubkeys(&self, keyset_id: &Id) -> Result<KeysResponse, Error> {
         self.keysets
             .load()
-            .iter()
-            .find(|keyset| &keyset.id == keyset_id)
+            .get(keyset_id)
             .ok_or(Error::UnknownKeySet)
             .map(|key| KeysResponse {
                 keysets: vec![key.into()],
@@ -54,6 +62,7 @@ impl Mint {
                     active: k.active,
                     input_fee_ppk: k.input_fee_ppk,
                     final_expiry: k.final_expiry,
+                    max_amount: self.max_amount(&k.unit),
                 })
                 .collect(),
         }
@@ -62,11 +71,7 @@ impl Mint {
     /// Get keysets
     #[instrument(skip(self))]
     pub fn keyset(&self, id: &Id) -> Option<KeySet> {
-        self.keysets
-            .load()
-            .iter()
-            .find(|key| &key.id == id)
-            .map(|x| x.into())
+        self.keysets.load().get(id).map(|x| x.into())
     }
 
     /// Add current keyset to inactive keysets
@@ -88,8 +93,90 @@ impl Mint {
             .await?;
 
         let new_keyset = self.signatory.keysets().await?;
-        self.keysets.store(new_keyset.keysets.into());
+        self.keysets.store(Arc::new(new_keyset.keysets.into()));
+
+        // Cached responses may hold signatures of the rotated keyset
+        self.clear_response_cache(ResponseCacheScope::All).await;
+
+        self.pubsub_manager.mint_info_change(MintInfoNotification {
+            changed: BTreeMap::new(),
+            active_keysets: self.active_keyset_ids(),
+            time: unix_time(),
+        });
 
         Ok(result.into())
     }
+
+    /// Ids of the active keysets served to wallets
+    pub(crate) fn active_keyset_ids(&self) -> Vec<Id> {
+        self.keysets
+            .load()
+            .iter()
+            .filter(|keyset| keyset.active && keyset.unit != CurrencyUnit::Auth)
+            .map(|keyset| keyset.id)
+            .collect()
+    }
+
+    /// Audit all keysets for inconsistencies that could lead to key reuse
+    ///
+    /// Extends the signatory audit by comparing the keys served to wallets with the signatory
+    /// keys, and by flagging keysets above the active derivation path index that have issued
+    /// signatures. Nothing is modified, so it is safe to run before and after a migration.
+    #[instrument(skip(self))]
+    pub async fn audit_keysets(&self) -> Result<Vec<KeysetAudit>, Error> {
+        let mut audits = self.signatory.audit_keysets().await?;
+        let signatory_keysets = self.signatory.keysets().await?.keysets;
+        let advertised_keysets = self.keysets.load();
+        let total_issued = self.localstore.get_total_issued().await?;
+
+        let active_indexes = audits
+            .iter()
+            .filter(|audit| audit.active)
+            .map(|audit| (audit.unit.clone(), (audit.id, audit.derivation_path_index)))
+            .collect::<HashMap<_, _>>();
+
+        for audit in audits.iter_mut() {
+            let signatory_keys = signatory_keysets
+                .iter()
+                .find(|keyset| keyset.id == audit.id)
+                .map(|keyset| &keyset.keys);
+            let advertised_keys = advertised_keysets
+                .iter()
+                .find(|keyset| keyset.id == audit.id)
+                .map(|keyset| &keyset.keys);
+
+            if signatory_keys.is_none() || signatory_keys != advertised_keys {
+                audit.issues.push(KeysetAuditIssue::AdvertisedKeysMismatch);
+            }
+
+            let has_issued = total_issued
+                .get(&audit.id)
+                .is_some_and(|amount| *amount > Amount::ZERO);
+
+            if let (Some((active_id, Some(active_index))), Some(index)) =
+                (active_indexes.get(&audit.unit), audit.derivation_path_index)
+            {
+                if index > *active_index && has_issued {
+                    audit
+                        .issues
+                        .push(KeysetAuditIssue::IssuedAboveActiveIndex(*active_id));
+                }
+            }
+
+            if !audit.is_ok() {
+                tracing::warn!(
+                    "Keyset {} failed audit: {}",
+                    audit.id,
+                    audit
+                        .issues
+                        .iter()
+                        .map(|issue| issue.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                );
+            }
+        }
+
+        Ok(audits)
+    }
 }
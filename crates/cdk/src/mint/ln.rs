@@ -83,6 +83,7 @@ impl Mint {
 
                 match new_quote.add_payment(amount_paid, payment.payment_id.clone(), None) {
                     Ok(()) => {
+                        Self::log_mint_quote_payment_mismatch(&new_quote);
                         tx.update_mint_quote(&mut new_quote).await?;
                         if let Some(pubsub_manager) = pubsub_manager.as_ref() {
                             pubsub_manager.mint_quote_payment(&new_quote, new_quote.amount_paid());
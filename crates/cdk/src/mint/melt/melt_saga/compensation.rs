@@ -3,6 +3,8 @@
 //! When a saga step fails, compensating actions are executed in reverse order (LIFO)
 //! to undo all completed steps and restore the database to its pre-saga state.
 
+use std::fmt;
+
 use async_trait::async_trait;
 use cdk_common::database::DynMintDatabase;
 use cdk_common::{Error, PublicKey, QuoteId};
@@ -21,6 +23,30 @@ pub trait CompensatingAction: Send + Sync {
     fn name(&self) -> &'static str;
 }
 
+/// Cleanup that failed after a melt step had already failed
+///
+/// Reports both the error that triggered the cleanup and the cleanup failures to the log. The
+/// triggering error is the one returned to the client.
+#[derive(Debug)]
+pub struct MeltCleanupError<'a> {
+    /// Error that triggered the cleanup
+    pub cause: &'a Error,
+    /// Name and error of each failed cleanup step
+    pub failures: Vec<(&'static str, Error)>,
+}
+
+impl fmt::Display for MeltCleanupError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}; cleanup failed:", self.cause)?;
+        for (step, err) in &self.failures {
+            write!(f, " {step}: {err}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for MeltCleanupError<'_> {}
+
 /// Compensation action to remove melt setup and reset quote state.
 ///
 /// This compensation is used when payment fails or finalization fails after
@@ -1,20 +1,24 @@
 use std::collections::VecDeque;
+use std::future::Future;
 use std::sync::Arc;
+use std::time::Duration;
 
 use cdk_common::database::mint::MeltRequestInfo;
 use cdk_common::database::DynMintDatabase;
-use cdk_common::mint::{MeltSagaState, Operation, Saga, SagaStateEnum};
+use cdk_common::mint::{InternalSettlement, MeltSagaState, Operation, Saga, SagaStateEnum};
 use cdk_common::nut00::KnownMethod;
+use cdk_common::nut17::MeltProgress;
 use cdk_common::nuts::MeltQuoteState;
 use cdk_common::{Amount, CurrencyUnit, Error, ProofsMethods, PublicKey, QuoteId, State};
 #[cfg(feature = "prometheus")]
 use cdk_prometheus::METRICS;
 use tokio::sync::Mutex;
+use tokio::time::Instant;
 use tracing::instrument;
 
-use self::compensation::{CompensatingAction, RemoveMeltSetup};
+use self::compensation::{CompensatingAction, MeltCleanupError, RemoveMeltSetup};
 use self::state::{Initial, PaymentConfirmed, SettlementDecision, SetupComplete};
-use crate::cdk_payment::MakePaymentResponse;
+use crate::cdk_payment::{DynMintPayment, MakePaymentResponse};
 use crate::mint::melt::shared;
 use crate::mint::subscription::PubSubManager;
 use crate::mint::verification::Verification;
@@ -186,7 +190,12 @@ impl MeltSaga<Initial> {
     /// - `PendingQuote`: Quote is already in Pending state
     /// - `PaidQuote`: Quote has already been paid
     /// - `TokenAlreadySpent`: Input proofs have already been spent
-    /// - `UnitMismatch`: Input unit doesn't match quote unit
+    /// - `UnitMismatch`: Input unit or change keyset unit doesn't match quote unit
+    /// - `UnknownKeySet`, `InactiveKeyset`: Change outputs reference an unknown or inactive keyset
+    /// - `MultipleUnits`, `MultipleChangeKeysets`: Change outputs reference more than one keyset
+    /// - `DuplicateOutputs`: The same blinded message appears more than once in the change outputs
+    /// - `BlindedMessageAlreadySigned`: Change outputs are already signed or reserved by another
+    ///   request
     #[instrument(skip_all)]
     pub async fn setup_melt(
         self,
@@ -210,6 +219,15 @@ impl MeltSaga<Initial> {
                 }
             };
 
+        // Change is signed with the keyset the outputs reference, so reject change for any other
+        // than one active keyset of the quote unit before anything is stored
+        if let Some(outputs) = melt_request.outputs().as_ref().filter(|o| !o.is_empty()) {
+            if let Err(err) = self.mint.verify_change_outputs_keyset(outputs, &quote.unit) {
+                tx.rollback().await?;
+                return Err(err);
+            }
+        }
+
         // Calculate fee to create Operation with actual amounts
         let fee_breakdown = self.mint.get_proofs_fee(melt_request.inputs()).await?;
 
@@ -291,10 +309,15 @@ impl MeltSaga<Initial> {
         let inputs_fee = inputs_fee_breakdown.total.with_unit(quote.unit.clone());
         let fee_reserve = quote.fee_reserve();
 
-        let required_total = quote
-            .amount()
-            .checked_add(&fee_reserve)?
-            .checked_add(&inputs_fee)?;
+        let required_total =
+            match shared::melt_required_amount(&quote.amount(), &fee_reserve, &inputs_fee) {
+                Ok(required_total) => required_total,
+                Err(err) => {
+                    tracing::warn!("Melt request for quote {} overflowed: {}", quote.id, err);
+                    tx.rollback().await?;
+                    return Err(err);
+                }
+            };
 
         if input_amount < required_total.clone() {
             tracing::info!(
@@ -309,7 +332,7 @@ impl MeltSaga<Initial> {
             return Err(Error::TransactionUnbalanced(
                 input_amount.to_u64(),
                 quote.amount().value(),
-                inputs_fee.checked_add(&fee_reserve)?.value(),
+                required_total.value() - quote.amount().value(),
             ));
         }
 
@@ -339,13 +362,22 @@ impl MeltSaga<Initial> {
         )
         .await?;
 
-        // Add change output blinded messages
-        tx.add_blinded_messages(
-            Some(melt_request.quote_id()),
-            melt_request.outputs().as_ref().unwrap_or(&Vec::new()),
-            &operation,
-        )
-        .await?;
+        // Add change output blinded messages, the unique constraint on the blinded message makes
+        // this the authoritative check against concurrent requests reusing them
+        if let Err(err) = tx
+            .add_blinded_messages(
+                Some(melt_request.quote_id()),
+                melt_request.outputs().as_ref().unwrap_or(&Vec::new()),
+                &operation,
+            )
+            .await
+        {
+            tx.rollback().await?;
+            return Err(match err {
+                cdk_common::database::Error::Duplicate => Error::BlindedMessageAlreadySigned,
+                _ => Error::Database(err),
+            });
+        }
 
         // Get blinded secrets for compensation
         let blinded_secrets: Vec<PublicKey> = melt_request
@@ -468,8 +500,7 @@ impl MeltSaga<SetupComplete> {
             Err(err) => {
                 tx.rollback().await?;
                 tracing::debug!("Error checking for mint quote: {}", err);
-                self.compensate_all().await?;
-                return Err(Error::Internal);
+                return Err(self.compensate_all(Error::Internal).await);
             }
         };
 
@@ -479,8 +510,7 @@ impl MeltSaga<SetupComplete> {
             && mint_quote.payment_method == crate::mint::PaymentMethod::Known(KnownMethod::Bolt11)
         {
             tx.rollback().await?;
-            self.compensate_all().await?;
-            return Err(Error::RequestAlreadyPaid);
+            return Err(self.compensate_all(Error::RequestAlreadyPaid).await);
         }
 
         let inputs_amount_quote_unit = melt_request
@@ -499,8 +529,7 @@ impl MeltSaga<SetupComplete> {
                     amount
                 );
                 tx.rollback().await?;
-                self.compensate_all().await?;
-                return Err(Error::InsufficientFunds);
+                return Err(self.compensate_all(Error::InsufficientFunds).await);
             }
         }
 
@@ -523,9 +552,17 @@ impl MeltSaga<SetupComplete> {
         mint_quote.add_payment(amount.clone(), self.state_data.quote.id.to_string(), None)?;
         tx.update_mint_quote(&mut mint_quote).await?;
 
+        let settlement = InternalSettlement::new(
+            self.state_data.quote.id.clone(),
+            mint_quote.id.clone(),
+            amount.clone(),
+        );
+        tx.add_internal_settlement(&settlement).await?;
+
         tx.commit().await?;
         self.pubsub
             .mint_quote_payment(&mint_quote, mint_quote.amount_paid());
+        self.pubsub.internal_settlement(settlement);
 
         tracing::info!(
             "Melt quote {} paid Mint quote {}",
@@ -581,11 +618,15 @@ impl MeltSaga<SetupComplete> {
     ///
     /// - `PaymentFailed`: Payment confirmed as failed/unpaid
     /// - `PendingQuote`: Payment is pending (will be resolved by startup check)
+    /// - `PaymentPending`: Payment did not settle within the backend's payment timeout
+    ///   (will be resolved by the next quote status check or startup check)
     #[instrument(skip_all)]
     pub async fn make_payment(
         self,
         settlement: SettlementDecision,
     ) -> Result<MeltSaga<PaymentConfirmed>, Error> {
+        let settled_internally = matches!(settlement, SettlementDecision::Internal { .. });
+
         let payment_result = match settlement {
             SettlementDecision::Internal { amount } => {
                 tracing::info!(
@@ -639,14 +680,27 @@ impl MeltSaga<SetupComplete> {
                     tx.commit().await?;
                 }
 
-                // Make payment with idempotent verification
-                let payment_response = match ln
-                    .make_payment(
-                        &self.state_data.quote.unit,
-                        self.state_data.quote.clone().try_into()?,
-                    )
-                    .await
-                {
+                self.pubsub.melt_progress(
+                    &self.state_data.quote.id,
+                    MeltProgress::Pending,
+                    Duration::ZERO,
+                );
+
+                // Make payment, giving up waiting once the backend's payment timeout elapses.
+                // The quote stays pending and is finalized by a later payment status check.
+                let Some(payment) = self
+                    .with_progress_heartbeats(self.pay_with_timeout(Arc::clone(ln)))
+                    .await?
+                else {
+                    tracing::warn!(
+                        "LN payment for quote {} did not settle in time, proofs remain pending",
+                        self.state_data.quote.id
+                    );
+                    return Err(Error::PaymentPending);
+                };
+
+                // Verify the payment outcome idempotently
+                let payment_response = match payment {
                     Ok(pay)
                         if pay.status == MeltQuoteState::Unknown
                             || pay.status == MeltQuoteState::Failed =>
@@ -720,14 +774,14 @@ impl MeltSaga<SetupComplete> {
                             "Lightning payment for quote {} failed.",
                             self.state_data.quote.id
                         );
-                        self.compensate_all().await?;
-                        return Err(Error::PaymentFailed);
+                        return Err(self.compensate_all(Error::PaymentFailed).await);
                     }
                     MeltQuoteState::Unknown => {
                         tracing::warn!(
                             "LN payment unknown, proofs remain pending for quote: {}",
                             self.state_data.quote.id
                         );
+                        self.record_payment_lookup_id(&payment_response).await?;
                         return Err(Error::PaymentFailed);
                     }
                     MeltQuoteState::Pending => {
@@ -735,6 +789,7 @@ impl MeltSaga<SetupComplete> {
                             "LN payment pending, proofs remain pending for quote: {}",
                             self.state_data.quote.id
                         );
+                        self.record_payment_lookup_id(&payment_response).await?;
                         return Err(Error::PendingQuote);
                     }
                 }
@@ -757,12 +812,88 @@ impl MeltSaga<SetupComplete> {
                 input_ys: self.state_data.input_ys,
                 blinded_messages: self.state_data.blinded_messages,
                 payment_result,
+                settled_internally,
                 operation: self.state_data.operation,
                 fee_breakdown: self.state_data.fee_breakdown,
             },
         })
     }
 
+    /// Awaits `payment`, publishing an `attempting` progress event of the quote at every melt
+    /// progress interval until it completes
+    async fn with_progress_heartbeats<F>(&self, payment: F) -> F::Output
+    where
+        F: Future,
+    {
+        let interval = self.mint.melt_progress_interval;
+        if interval.is_zero() {
+            return payment.await;
+        }
+
+        let started = Instant::now();
+        let mut heartbeat = tokio::time::interval_at(started + interval, interval);
+        tokio::pin!(payment);
+
+        loop {
+            tokio::select! {
+                output = &mut payment => return output,
+                _ = heartbeat.tick() => {
+                    self.pubsub.melt_progress(
+                        &self.state_data.quote.id,
+                        MeltProgress::Attempting,
+                        started.elapsed(),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Helper to pay the quote with the LN backend
+    ///
+    /// If the backend has a payment timeout the payment runs on its own task and
+    /// `None` is returned once the timeout elapses. The task is left running so the
+    /// backend is never interrupted in the middle of a payment.
+    async fn pay_with_timeout(
+        &self,
+        ln: DynMintPayment,
+    ) -> Result<Option<Result<MakePaymentResponse, cdk_common::payment::Error>>, Error> {
+        let unit = self.state_data.quote.unit.clone();
+        let options = self.state_data.quote.clone().try_into()?;
+
+        let Some(timeout) = ln.payment_timeout() else {
+            return Ok(Some(ln.make_payment(&unit, options).await));
+        };
+
+        let payment = tokio::spawn(async move { ln.make_payment(&unit, options).await });
+
+        match tokio::time::timeout(timeout, payment).await {
+            Ok(Ok(result)) => Ok(Some(result)),
+            Ok(Err(err)) => Ok(Some(Err(cdk_common::payment::Error::Custom(
+                err.to_string(),
+            )))),
+            Err(_) => Ok(None),
+        }
+    }
+
+    /// Stores the payment id of a payment left pending if the quote had none
+    ///
+    /// Keysend and bolt12 payment hashes are only known once the payment is sent,
+    /// without them the pending payment could not be checked later.
+    async fn record_payment_lookup_id(&self, payment: &MakePaymentResponse) -> Result<(), Error> {
+        if self.state_data.quote.request_lookup_id.is_some() {
+            return Ok(());
+        }
+
+        let mut tx = self.db.begin_transaction().await?;
+        let mut quote =
+            shared::load_melt_quotes_exclusively(&mut tx, &self.state_data.quote.id).await?;
+        tx.update_melt_quote_request_lookup_id(&mut quote, &payment.payment_lookup_id)
+            .await?;
+        tx.commit().await?;
+
+        Ok(())
+    }
+
     /// Helper to check payment state with LN backend
     async fn check_payment_state(
         &self,
@@ -806,6 +937,9 @@ impl MeltSaga<PaymentConfirmed> {
     /// If inputs > total_spent:
     /// - If change outputs were provided: sign them and return
     /// - If no change outputs: change is burnt (logged as info)
+    /// - Change beyond the outputs or the melt change policy of the mint is burnt
+    ///
+    /// Burnt change is recorded with the completed operation.
     ///
     /// # Success
     ///
@@ -883,7 +1017,15 @@ impl MeltSaga<PaymentConfirmed> {
                 err
             );
 
-            tx.rollback().await?;
+            if let Err(rollback_err) = tx.rollback().await {
+                tracing::error!(
+                    "{}",
+                    MeltCleanupError {
+                        cause: &err,
+                        failures: vec![("rollback", rollback_err.into())],
+                    }
+                );
+            }
             return Err(err);
         }
 
@@ -904,7 +1046,7 @@ impl MeltSaga<PaymentConfirmed> {
                 &self.state_data.quote.id,
                 inputs_amount.clone(),
                 total_spent.clone(),
-                inputs_fee,
+                inputs_fee.clone(),
                 change_outputs,
             )
             .await?
@@ -921,17 +1063,42 @@ impl MeltSaga<PaymentConfirmed> {
         let mut operation = self.state_data.operation;
         let change_amount = change
             .as_ref()
-            .map(|c| Amount::try_sum(c.iter().map(|a| a.amount)).expect("Change cannot overflow"))
+            .map(|c| Amount::try_sum(c.iter().map(|a| a.amount)))
+            .transpose()
+            .map_err(|_| Error::AmountOverflow)?
             .unwrap_or_default();
 
         operation.add_change(change_amount);
 
+        // Change owed but not signed, for lack of outputs or by the change policy. The melt
+        // is complete at this point, so the accounting does not fail it.
+        let change_owed: Amount =
+            super::shared::melt_change_amount(&inputs_amount, &total_spent, &inputs_fee)
+                .ok()
+                .flatten()
+                .map(Into::into)
+                .unwrap_or_default();
+        let change_burnt = change_owed.checked_sub(change_amount).unwrap_or_default();
+        if change_burnt > Amount::ZERO {
+            tracing::info!(
+                "Melt {} burnt {} of change owed",
+                self.state_data.quote.id,
+                change_burnt
+            );
+        }
+        operation.set_change_burnt(change_burnt);
+
         // Set payment details for melt operation
         // payment_amount = the Lightning invoice amount
         // payment_fee = actual fee paid (total_spent - invoice_amount)
-        let payment_fee = total_spent.checked_sub(&self.state_data.quote.amount())?;
+        let payment_fee = total_spent
+            .checked_sub(&self.state_data.quote.amount())
+            .map_err(|_| Error::AmountOverflow)?;
 
-        operation.set_payment_details(self.state_data.quote.amount().into(), payment_fee.into());
+        operation.set_payment_details(
+            self.state_data.quote.amount().into(),
+            payment_fee.clone().into(),
+        );
 
         tx.add_completed_operation(&operation, &self.state_data.fee_breakdown.per_keyset)
             .await?;
@@ -961,6 +1128,11 @@ impl MeltSaga<PaymentConfirmed> {
             METRICS.record_mint_operation("melt_bolt11", true);
         }
 
+        let mut fees = self
+            .mint
+            .melt_quote_fees(&self.state_data.quote, Some(payment_fee.into()));
+        fees.internal = self.state_data.settled_internally;
+
         let response = MeltQuoteBolt11Response {
             amount: self.state_data.quote.amount().into(),
             payment_preimage,
@@ -971,6 +1143,8 @@ impl MeltSaga<PaymentConfirmed> {
             expiry: self.state_data.quote.expiry,
             request: Some(self.state_data.quote.request.to_string()),
             unit: Some(self.state_data.quote.unit.clone()),
+            fees: Some(fees),
+            amount_spent: Some(total_spent.into()),
         };
 
         Ok(response)
@@ -984,12 +1158,14 @@ impl<S> MeltSaga<S> {
     /// after compensation has been triggered.
     ///
     /// This is called internally by saga methods when they need to compensate.
+    /// Returns `cause`, the error that triggered the compensation; failed
+    /// compensations are logged together with it.
     #[instrument(skip_all)]
-    async fn compensate_all(self) -> Result<(), Error> {
+    async fn compensate_all(self, cause: Error) -> Error {
         let mut compensations = self.compensations.lock().await;
 
         if compensations.is_empty() {
-            return Ok(());
+            return cause;
         }
 
         #[cfg(feature = "prometheus")]
@@ -999,19 +1175,30 @@ impl<S> MeltSaga<S> {
             METRICS.record_error();
         }
 
-        tracing::warn!("Running {} compensating actions", compensations.len());
+        tracing::warn!(
+            "Running {} compensating actions after: {}",
+            compensations.len(),
+            cause
+        );
 
+        let mut failures = Vec::new();
         while let Some(compensation) = compensations.pop_front() {
             tracing::debug!("Running compensation: {}", compensation.name());
             if let Err(e) = compensation.execute(&self.db, &self.pubsub).await {
-                tracing::error!(
-                    "Compensation {} failed: {}. Continuing...",
-                    compensation.name(),
-                    e
-                );
+                failures.push((compensation.name(), e));
             }
         }
 
-        Ok(())
+        if !failures.is_empty() {
+            tracing::error!(
+                "{}",
+                MeltCleanupError {
+                    cause: &cause,
+                    failures,
+                }
+            );
+        }
+
+        cause
     }
 }
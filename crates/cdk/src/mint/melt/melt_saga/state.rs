@@ -36,6 +36,8 @@ pub struct PaymentConfirmed {
     #[allow(dead_code)] // Stored for completeness, accessed from DB in finalize
     pub blinded_messages: Vec<BlindedMessage>,
     pub payment_result: MakePaymentResponse,
+    /// Whether the payment was settled against a mint quote of this mint
+    pub settled_internally: bool,
     pub operation: Operation,
     pub fee_breakdown: crate::fees::ProofsFeeBreakdown,
 }
@@ -1,4 +1,5 @@
 use std::str::FromStr;
+use std::time::Duration;
 
 use cdk_common::melt::MeltQuoteRequest;
 use cdk_common::mint::MeltPaymentRequest;
@@ -10,12 +11,13 @@ use cdk_common::payment::{
 };
 use cdk_common::quote_id::QuoteId;
 use cdk_common::{
-    MeltOptions, MeltQuoteBolt12Request, MeltQuoteCustomRequest, SpendingConditionVerification,
+    MeltOptions, MeltQuoteBolt12Request, MeltQuoteCustomRequest, MeltQuoteFees,
+    SpendingConditionVerification,
 };
 #[cfg(feature = "prometheus")]
 use cdk_prometheus::METRICS;
 use lightning::offers::offer::Offer;
-use tracing::instrument;
+use tracing::{instrument, Instrument};
 
 use super::{
     CurrencyUnit, MeltQuote, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest, Mint,
@@ -34,7 +36,16 @@ mod tests;
 
 use melt_saga::MeltSaga;
 
+/// Default interval between the `attempting` progress events of a melt awaiting its payment
+pub const DEFAULT_MELT_PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
 impl Mint {
+    /// Reject melt requests with more inputs or change outputs than allowed
+    fn check_melt_request_limits(&self, melt_request: &MeltRequest<QuoteId>) -> Result<(), Error> {
+        let output_count = melt_request.outputs().as_ref().map_or(0, Vec::len);
+        self.check_request_limits(melt_request.inputs().len(), output_count)
+    }
+
     #[instrument(skip_all)]
     async fn check_melt_request_acceptable(
         &self,
@@ -63,12 +74,24 @@ impl Mint {
                     return Err(Error::InternalMultiPartMeltQuote);
                 }
                 // Verify MPP is enabled for unit and method
-                if !nut15
-                    .methods
-                    .into_iter()
-                    .any(|m| m.method == method && m.unit == unit)
-                {
-                    return Err(Error::MppUnitMethodNotSupported(unit, method));
+                let mpp_settings = nut15.get_settings(&unit, &method).ok_or_else(|| {
+                    Error::MppUnitMethodNotSupported(unit.clone(), method.clone())
+                })?;
+
+                // Verify the part is within the advertised part limits
+                let part_amount = Amount::from(amount.value());
+                if !mpp_settings.is_within_limits(part_amount) {
+                    tracing::error!(
+                        "MPP part amount out of range: {} is not within {} and {}",
+                        amount,
+                        mpp_settings.min_amount.unwrap_or_default(),
+                        mpp_settings.max_amount.unwrap_or_default(),
+                    );
+                    return Err(Error::AmountOutofLimitRange(
+                        mpp_settings.min_amount.unwrap_or_default(),
+                        mpp_settings.max_amount.unwrap_or_default(),
+                        part_amount,
+                    ));
                 }
             }
             Some(MeltOptions::Amountless { amountless: _ }) => {
@@ -106,6 +129,78 @@ impl Mint {
         }
     }
 
+    /// Check that a bolt11 melt request has a defined amount to pay
+    ///
+    /// A zero-amount invoice can only be paid with the amountless option,
+    /// and an amountless option on an invoice with an amount must match it.
+    fn check_bolt11_melt_amount(melt_request: &MeltQuoteBolt11Request) -> Result<(), Error> {
+        if melt_request.request.amount_milli_satoshis().is_none()
+            && !matches!(melt_request.options, Some(MeltOptions::Amountless { .. }))
+        {
+            return Err(Error::InvoiceAmountUndefined);
+        }
+
+        ensure_cdk!(
+            melt_request.amount_msat()? > Amount::ZERO,
+            Error::InvoiceAmountUndefined
+        );
+
+        Ok(())
+    }
+
+    /// Fee breakdown for a melt quote
+    ///
+    /// The input fee is taken from the active keyset of the quote unit.
+    /// `ln_fee_paid` should only be given once the melt has completed.
+    pub(crate) fn melt_quote_fees(
+        &self,
+        quote: &MeltQuote,
+        ln_fee_paid: Option<Amount>,
+    ) -> MeltQuoteFees {
+        let input_fee_ppk = self
+            .get_active_keysets()
+            .get(&quote.unit)
+            .and_then(|id| self.get_keyset_info(id))
+            .map(|info| info.input_fee_ppk)
+            .unwrap_or_default();
+
+        MeltQuoteFees {
+            ln_fee_reserve: quote.fee_reserve().into(),
+            input_fee_ppk,
+            ln_fee_paid,
+            internal: false,
+        }
+    }
+
+    /// Unpaid, unexpired bolt11 melt quote matching `melt_request`, if any
+    ///
+    /// Wallets retrying quote creation would otherwise get several quotes for one invoice, and
+    /// executing more than one of them fails after the proofs are marked pending. The unit and
+    /// options have to match too, amountless and MPP quotes for the same invoice may differ.
+    async fn reusable_bolt11_melt_quote(
+        &self,
+        melt_request: &MeltQuoteBolt11Request,
+    ) -> Result<Option<MeltQuote>, Error> {
+        let request = MeltPaymentRequest::Bolt11 {
+            bolt11: melt_request.request.clone(),
+        };
+        let now = unix_time();
+
+        Ok(self
+            .localstore
+            .get_melt_quotes_by_request(&request)
+            .await?
+            .into_iter()
+            .filter(|quote| {
+                quote.state == MeltQuoteState::Unpaid
+                    && quote.expiry > now
+                    && quote.unit == melt_request.unit
+                    && quote.options == melt_request.options
+                    && quote.payment_method == PaymentMethod::Known(KnownMethod::Bolt11)
+            })
+            .max_by_key(|quote| quote.expiry))
+    }
+
     /// Get melt quote for BOLT11, BOLT12, or Custom payment methods
     ///
     /// This function accepts a `MeltQuoteRequest` enum and delegates to the
@@ -132,6 +227,34 @@ impl Mint {
         &self,
         melt_request: &MeltQuoteBolt11Request,
     ) -> Result<MeltQuoteBolt11Response<QuoteId>, Error> {
+        Self::check_bolt11_melt_amount(melt_request)?;
+
+        if self.reuse_melt_quotes {
+            if let Some(quote) = self.reusable_bolt11_melt_quote(melt_request).await? {
+                // The settings may have changed since the quote was created
+                self.check_melt_request_acceptable(
+                    quote.amount(),
+                    PaymentMethod::Known(KnownMethod::Bolt11),
+                    melt_request.request.to_string(),
+                    quote.options,
+                )
+                .await?;
+
+                tracing::debug!(
+                    "Reusing melt quote {} for request of {}",
+                    quote.id,
+                    melt_request.request
+                );
+
+                let fees = self.melt_quote_fees(&quote, None);
+
+                return Ok(MeltQuoteBolt11Response {
+                    fees: Some(fees),
+                    ..quote.into()
+                });
+            }
+        }
+
         #[cfg(feature = "prometheus")]
         METRICS.inc_in_flight_requests("get_melt_bolt11_quote");
         let MeltQuoteBolt11Request {
@@ -214,6 +337,9 @@ impl Mint {
             PaymentMethod::Known(KnownMethod::Bolt11),
         );
 
+        // A quote whose amount and fee reserve overflow could never be melted
+        quote.total_needed()?;
+
         tracing::debug!(
             "New {} melt quote {} for {} {} with request id {:?}",
             quote.payment_method,
@@ -227,7 +353,12 @@ impl Mint {
         tx.add_melt_quote(quote.clone()).await?;
         tx.commit().await?;
 
-        Ok(quote.into())
+        let fees = self.melt_quote_fees(&quote, None);
+
+        Ok(MeltQuoteBolt11Response {
+            fees: Some(fees),
+            ..quote.into()
+        })
     }
 
     /// Implementation of get_melt_bolt12_quote
@@ -311,6 +442,9 @@ impl Mint {
             PaymentMethod::Known(KnownMethod::Bolt12),
         );
 
+        // A quote whose amount and fee reserve overflow could never be melted
+        quote.total_needed()?;
+
         tracing::debug!(
             "New {} melt quote {} for {} {} with request id {:?}",
             quote.payment_method,
@@ -330,7 +464,12 @@ impl Mint {
             METRICS.record_mint_operation("get_melt_bolt11_quote", true);
         }
 
-        Ok(quote.into())
+        let fees = self.melt_quote_fees(&quote, None);
+
+        Ok(MeltQuoteBolt11Response {
+            fees: Some(fees),
+            ..quote.into()
+        })
     }
 
     /// Implementation of get_melt_custom_quote
@@ -357,7 +496,7 @@ impl Mint {
             ))
             .ok_or_else(|| {
                 tracing::info!("Could not get payment processor for {}, {} ", unit, method);
-                Error::UnsupportedUnit
+                Error::UnsupportedPaymentMethod
             })?;
 
         // Convert extra serde_json::Value to JSON string if not null
@@ -431,6 +570,9 @@ impl Mint {
             PaymentMethod::from(method.as_str()),
         );
 
+        // A quote whose amount and fee reserve overflow could never be melted
+        quote.total_needed()?;
+
         tracing::debug!(
             "New {} melt quote {} for {} {} with request id {:?}",
             method,
@@ -450,7 +592,12 @@ impl Mint {
             METRICS.record_mint_operation("get_melt_custom_quote", true);
         }
 
-        Ok(quote.into())
+        let fees = self.melt_quote_fees(&quote, None);
+
+        Ok(MeltQuoteBolt11Response {
+            fees: Some(fees),
+            ..quote.into()
+        })
     }
 
     /// Check melt quote status
@@ -504,6 +651,21 @@ impl Mint {
 
         let change = (!blind_signatures.is_empty()).then_some(blind_signatures);
 
+        // The fee paid is known from the amount spent, recorded once the quote is paid
+        let ln_fee_paid = quote
+            .amount_spent
+            .as_ref()
+            .and_then(|spent| spent.checked_sub(&quote.amount()).ok())
+            .map(Into::into);
+        let mut fees = self.melt_quote_fees(&quote, ln_fee_paid);
+        if quote.state == MeltQuoteState::Paid {
+            fees.internal = self
+                .localstore
+                .get_internal_settlement(&quote.id)
+                .await?
+                .is_some();
+        }
+
         let response = MeltQuoteBolt11Response {
             quote: quote.id.clone(),
             state: quote.state,
@@ -514,6 +676,8 @@ impl Mint {
             change,
             request: Some(quote.request.to_string()),
             unit: Some(quote.unit.clone()),
+            fees: Some(fees),
+            amount_spent: quote.amount_spent.map(Into::into),
         };
 
         #[cfg(feature = "prometheus")]
@@ -535,11 +699,13 @@ impl Mint {
     /// Melt
     ///
     /// Uses MeltSaga typestate pattern for atomic transaction handling with automatic rollback on failure.
-    #[instrument(skip_all)]
+    #[instrument(skip_all, fields(quote_id = %melt_request.quote()))]
     pub async fn melt(
         &self,
         melt_request: &MeltRequest<QuoteId>,
     ) -> Result<MeltQuoteBolt11Response<QuoteId>, Error> {
+        self.check_melt_request_limits(melt_request)?;
+
         // Verify spending conditions (NUT-10/NUT-11/NUT-14), i.e. P2PK
         // and HTLC (including SIGALL)
         melt_request.verify_spending_conditions()?;
@@ -556,6 +722,15 @@ impl Mint {
             .await?
             .ok_or(Error::UnknownQuote)?;
 
+        // Holds a slot of the payment backend until the melt is finalized
+        let _permit = self
+            .payment_limiter
+            .acquire(&PaymentProcessorKey::new(
+                quote.unit.clone(),
+                quote.payment_method.clone(),
+            ))
+            .await?;
+
         let init_saga = MeltSaga::new(
             std::sync::Arc::new(self.clone()),
             self.localstore.clone(),
@@ -564,7 +739,7 @@ impl Mint {
 
         // Step 1: Setup (TX1 - reserves inputs and outputs)
         let setup_saga = init_saga
-            .setup_melt(melt_request, verification, quote.payment_method)
+            .setup_melt(melt_request, verification, quote.payment_method.clone())
             .await?;
 
         // Step 2: Attempt internal settlement (returns saga + SettlementDecision)
@@ -572,7 +747,22 @@ impl Mint {
         let (setup_saga, settlement) = setup_saga.attempt_internal_settlement(melt_request).await?;
 
         // Step 3: Make payment (internal or external)
-        let payment_saga = setup_saga.make_payment(settlement).await?;
+        let payment_saga = match setup_saga.make_payment(settlement).await {
+            Ok(payment_saga) => payment_saga,
+            Err(Error::PaymentPending) => {
+                // The payment did not settle within the backend's payment timeout.
+                // Respond with the pending quote, it is finalized by a later status
+                // check or on startup.
+                let fees = self.melt_quote_fees(&quote, None);
+
+                return Ok(MeltQuoteBolt11Response {
+                    state: MeltQuoteState::Pending,
+                    fees: Some(fees),
+                    ..quote.into()
+                });
+            }
+            Err(err) => return Err(err),
+        };
 
         // Step 4: Finalize (TX2 - marks spent, issues change)
         payment_saga.finalize().await
@@ -583,10 +773,13 @@ impl Mint {
     /// This method is called when the client includes the `Prefer: respond-async` header.
     /// It performs the setup phase (TX1) to validate and reserve proofs, then spawns a
     /// background task to complete the payment and finalization phases.
+    #[instrument(skip_all, fields(quote_id = %melt_request.quote()))]
     pub async fn melt_async(
         &self,
         melt_request: &MeltRequest<QuoteId>,
     ) -> Result<MeltQuoteBolt11Response<QuoteId>, Error> {
+        self.check_melt_request_limits(melt_request)?;
+
         let verification = self.verify_inputs(melt_request.inputs()).await?;
 
         // Get the quote first for payment_method and to return with PENDING state
@@ -597,6 +790,15 @@ impl Mint {
             .await?
             .ok_or(Error::UnknownQuote)?;
 
+        // Taken before setup so a busy backend rejects the request without side effects
+        let permit = self
+            .payment_limiter
+            .acquire(&PaymentProcessorKey::new(
+                quote.unit.clone(),
+                quote.payment_method.clone(),
+            ))
+            .await?;
+
         let init_saga = MeltSaga::new(
             std::sync::Arc::new(self.clone()),
             self.localstore.clone(),
@@ -607,63 +809,70 @@ impl Mint {
             .setup_melt(melt_request, verification, quote.payment_method.clone())
             .await?;
 
-        // Spawn background task to complete the melt operation
+        // Spawn background task to complete the melt operation, in the span of the request
         let melt_request_clone = melt_request.clone();
         let quote_id_clone = quote_id.clone();
-        tokio::spawn(async move {
-            tracing::debug!(
-                "Starting background melt completion for quote: {}",
-                quote_id_clone
-            );
-
-            // Step 2: Attempt internal settlement
-            match setup_saga
-                .attempt_internal_settlement(&melt_request_clone)
-                .await
-            {
-                Ok((setup_saga, settlement)) => {
-                    // Step 3: Make payment
-                    match setup_saga.make_payment(settlement).await {
-                        Ok(payment_saga) => {
-                            // Step 4: Finalize
-                            match payment_saga.finalize().await {
-                                Ok(_) => {
-                                    tracing::info!(
-                                        "Background melt completed successfully for quote: {}",
-                                        quote_id_clone
-                                    );
-                                }
-                                Err(e) => {
-                                    tracing::error!(
-                                        "Failed to finalize melt for quote {}: {}",
-                                        quote_id_clone,
-                                        e
-                                    );
+        tokio::spawn(
+            async move {
+                let _permit = permit;
+
+                tracing::debug!(
+                    "Starting background melt completion for quote: {}",
+                    quote_id_clone
+                );
+
+                // Step 2: Attempt internal settlement
+                match setup_saga
+                    .attempt_internal_settlement(&melt_request_clone)
+                    .await
+                {
+                    Ok((setup_saga, settlement)) => {
+                        // Step 3: Make payment
+                        match setup_saga.make_payment(settlement).await {
+                            Ok(payment_saga) => {
+                                // Step 4: Finalize
+                                match payment_saga.finalize().await {
+                                    Ok(_) => {
+                                        tracing::info!(
+                                            "Background melt completed successfully for quote: {}",
+                                            quote_id_clone
+                                        );
+                                    }
+                                    Err(e) => {
+                                        tracing::error!(
+                                            "Failed to finalize melt for quote {}: {}",
+                                            quote_id_clone,
+                                            e
+                                        );
+                                    }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            tracing::error!(
-                                "Failed to make payment for quote {}: {}",
-                                quote_id_clone,
-                                e
-                            );
+                            Err(e) => {
+                                tracing::error!(
+                                    "Failed to make payment for quote {}: {}",
+                                    quote_id_clone,
+                                    e
+                                );
+                            }
                         }
                     }
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Failed internal settlement for quote {}: {}",
-                        quote_id_clone,
-                        e
-                    );
+                    Err(e) => {
+                        tracing::error!(
+                            "Failed internal settlement for quote {}: {}",
+                            quote_id_clone,
+                            e
+                        );
+                    }
                 }
             }
-        });
+            .in_current_span(),
+        );
 
         debug_assert!(quote.state == MeltQuoteState::Pending);
 
         // Return immediately with the quote in PENDING state
+        let fees = self.melt_quote_fees(&quote, None);
+
         Ok(MeltQuoteBolt11Response {
             quote: quote_id,
             amount: quote.amount().into(),
@@ -674,6 +883,8 @@ impl Mint {
             change: None,
             request: Some(quote.request.to_string()),
             unit: Some(quote.unit),
+            fees: Some(fees),
+            amount_spent: None,
         })
     }
 }
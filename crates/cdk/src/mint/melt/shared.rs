@@ -9,15 +9,15 @@
 use cdk_common::database::{self, Acquired, DynMintDatabase};
 use cdk_common::nuts::{BlindSignature, BlindedMessage, MeltQuoteState, State};
 use cdk_common::{Amount, CurrencyUnit, Error, PublicKey, QuoteId};
-use cdk_signatory::signatory::SignatoryKeySet;
 
+use crate::mint::keysets::KeysetCache;
 use crate::mint::subscription::PubSubManager;
 use crate::mint::MeltQuote;
 use crate::Mint;
 
 /// Retrieves fee and amount configuration for the keyset matching the change outputs.
 ///
-/// Searches active keysets for one matching the first output's keyset_id.
+/// Searches the keysets for the one matching the first output's keyset_id.
 /// Used during change calculation for melts.
 ///
 /// # Arguments
@@ -29,23 +29,64 @@ use crate::Mint;
 ///
 /// Fee per thousand and allowed amounts for the keyset, or default if not found
 pub fn get_keyset_fee_and_amounts(
-    keysets: &arc_swap::ArcSwap<Vec<SignatoryKeySet>>,
+    keysets: &arc_swap::ArcSwap<KeysetCache>,
     outputs: &[BlindedMessage],
 ) -> cdk_common::amount::FeeAndAmounts {
-    keysets
-        .load()
-        .iter()
-        .filter_map(|keyset| {
-            if keyset.active && Some(keyset.id) == outputs.first().map(|x| x.keyset_id) {
-                Some((keyset.input_fee_ppk, keyset.amounts.clone()).into())
-            } else {
-                None
-            }
+    outputs
+        .first()
+        .and_then(|output| {
+            keysets
+                .load()
+                .get(&output.keyset_id)
+                .map(|keyset| (keyset.input_fee_ppk, keyset.amounts.clone()).into())
         })
-        .next()
         .unwrap_or_else(|| (0, (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>()).into())
 }
 
+/// Total the inputs of a melt have to cover: the quote amount, the fee reserve and the input fee.
+///
+/// # Errors
+///
+/// Returns `AmountOverflow` if the total does not fit in an amount.
+pub fn melt_required_amount(
+    quote_amount: &Amount<CurrencyUnit>,
+    fee_reserve: &Amount<CurrencyUnit>,
+    inputs_fee: &Amount<CurrencyUnit>,
+) -> Result<Amount<CurrencyUnit>, Error> {
+    quote_amount
+        .checked_add(fee_reserve)
+        .and_then(|amount| amount.checked_add(inputs_fee))
+        .map_err(|_| Error::AmountOverflow)
+}
+
+/// Change owed for a melt: the inputs less the amount spent and the input fee.
+///
+/// Returns `None` when nothing is owed, including when the backend reports spending more than
+/// the inputs cover. The payment has already happened at that point, so the melt completes
+/// without change instead of failing.
+///
+/// # Errors
+///
+/// Returns `AmountOverflow` if the amount spent, converted to the unit of the inputs, and the
+/// input fee do not fit in an amount.
+pub fn melt_change_amount(
+    inputs_amount: &Amount<CurrencyUnit>,
+    total_spent: &Amount<CurrencyUnit>,
+    inputs_fee: &Amount<CurrencyUnit>,
+) -> Result<Option<Amount<CurrencyUnit>>, Error> {
+    // Backends should return total_spent in the quote's unit, but we convert defensively.
+    let spent = total_spent
+        .convert_to(inputs_amount.unit())
+        .and_then(|total_spent| total_spent.checked_add(inputs_fee))
+        .map_err(|_| Error::AmountOverflow)?;
+
+    match inputs_amount.checked_sub(&spent) {
+        Ok(change) if change.value() > 0 => Ok(Some(change)),
+        // Over paid melts are already logged by `finalize_melt_core`
+        Ok(_) | Err(_) => Ok(None),
+    }
+}
+
 /// Rolls back a melt quote by removing all setup artifacts and resetting state.
 ///
 /// This function is used by both:
@@ -166,7 +207,8 @@ pub async fn rollback_melt_quote(
 ///
 /// This function handles the complete change workflow:
 /// 1. Calculate change target amount
-/// 2. Split into denominations based on keyset configuration
+/// 2. Split into denominations based on keyset configuration, limited by the
+///    melt change policy of the mint
 /// 3. Sign change outputs (external call to blind_sign)
 /// 4. Store signatures in database (new transaction)
 ///
@@ -178,6 +220,13 @@ pub async fn rollback_melt_quote(
 /// - Open a new transaction to store signatures
 /// - Return the new transaction for the caller to commit
 ///
+/// # Change Keyset
+///
+/// The change is signed with the keyset the outputs reference, which was checked to be an
+/// active keyset of the quote unit when the melt was set up. If that keyset was rotated out
+/// while the payment was in flight, the change is signed with the active keyset of the same
+/// unit instead, and the wallet unblinds it with the keys of the keyset in the signatures.
+///
 /// # Arguments
 ///
 /// * `mint` - Mint instance (for keysets and blind_sign)
@@ -207,7 +256,7 @@ pub async fn process_melt_change(
     inputs_amount: Amount<CurrencyUnit>,
     total_spent: Amount<CurrencyUnit>,
     inputs_fee: Amount<CurrencyUnit>,
-    change_outputs: Vec<BlindedMessage>,
+    mut change_outputs: Vec<BlindedMessage>,
 ) -> Result<
     (
         Option<Vec<BlindSignature>>,
@@ -216,32 +265,80 @@ pub async fn process_melt_change(
     Error,
 > {
     // Check if change is needed
-    let needs_change = inputs_amount > total_spent;
+    let change_target = match melt_change_amount(&inputs_amount, &total_spent, &inputs_fee)? {
+        Some(change_target) if !change_outputs.is_empty() => change_target,
+        _ => {
+            // No change needed - open transaction and return empty result
+            let tx = db.begin_transaction().await?;
+            return Ok((None, tx));
+        }
+    };
 
-    if !needs_change || change_outputs.is_empty() {
-        // No change needed - open transaction and return empty result
+    let change_keyset = change_outputs
+        .first()
+        .and_then(|output| mint.get_keyset_info(&output.keyset_id));
+    let signing_keyset_id = match &change_keyset {
+        Some(keyset) if keyset.active => Some(keyset.id),
+        Some(keyset) => mint
+            .keysets
+            .load()
+            .iter()
+            .find(|active| active.active && active.unit == keyset.unit)
+            .map(|active| active.id),
+        None => None,
+    };
+
+    let Some(signing_keyset_id) = signing_keyset_id else {
+        tracing::warn!(
+            "No active keyset to sign the change of melt {}, completing without change",
+            quote_id
+        );
         let tx = db.begin_transaction().await?;
         return Ok((None, tx));
+    };
+
+    if change_keyset.is_some_and(|keyset| keyset.id != signing_keyset_id) {
+        tracing::warn!(
+            "Change keyset of melt {} was rotated out, signing the change with {}",
+            quote_id,
+            signing_keyset_id
+        );
+        for output in change_outputs.iter_mut() {
+            output.keyset_id = signing_keyset_id;
+        }
     }
 
-    let change_target: Amount = inputs_amount
-        .checked_sub(&total_spent)?
-        .checked_sub(&inputs_fee)?
-        .into();
+    let change_target: Amount = change_target.into();
 
-    // Get keyset configuration
-    let fee_and_amounts = get_keyset_fee_and_amounts(&mint.keysets, &change_outputs);
+    // Get keyset configuration, change is never signed above the max amount of the unit
+    let max_amount = change_outputs
+        .first()
+        .and_then(|output| mint.get_keyset_info(&output.keyset_id))
+        .and_then(|keyset| mint.max_amount(&keyset.unit));
+    let fee_and_amounts =
+        get_keyset_fee_and_amounts(&mint.keysets, &change_outputs).with_max_amount(max_amount);
 
     // Split change into denominations
     let mut amounts: Vec<Amount> = change_target.split(&fee_and_amounts);
 
-    if change_outputs.len() < amounts.len() {
+    // Apply the change policy of the mint, the remainder is burnt
+    let policy = mint.melt_change_policy();
+    if let Some(min_denomination) = policy.min_denomination {
+        amounts.retain(|amount| *amount >= min_denomination);
+    }
+
+    let max_outputs = policy
+        .max_outputs
+        .map_or(change_outputs.len(), |max| max.min(change_outputs.len()));
+
+    if max_outputs < amounts.len() {
         tracing::debug!(
-            "Providing change requires {} blinded messages, but only {} provided",
+            "Providing change requires {} blinded messages, but only {} are signed",
             amounts.len(),
-            change_outputs.len()
+            max_outputs
         );
         amounts.sort_by(|a, b| b.cmp(a));
+        amounts.truncate(max_outputs);
     }
 
     // Prepare blinded messages with amounts
@@ -263,7 +360,11 @@ pub async fn process_melt_change(
         .collect();
 
     tx.add_blind_signatures(&blinded_secrets, &change_sigs, Some(quote_id.clone()))
-        .await?;
+        .await
+        .map_err(|err| match err {
+            database::Error::Duplicate => Error::BlindedMessageAlreadySigned,
+            err => Error::Database(err),
+        })?;
 
     Ok((Some(change_sigs), tx))
 }
@@ -424,13 +525,6 @@ pub async fn finalize_melt_core(
 
     // This can only happen on backends where we cannot set the max fee (e.g., LNbits).
     // LNbits does not allow setting a fee limit, so payments can exceed the fee reserve.
-    debug_assert!(
-        net_inputs >= total_spent,
-        "Over paid melt quote {}: net_inputs ({}) < total_spent ({}). Payment already complete, finalizing with no change.",
-        quote.id,
-        net_inputs.display_with_unit(),
-        total_spent.display_with_unit(),
-    );
     if net_inputs < total_spent {
         tracing::error!(
             "Over paid melt quote {}: net_inputs ({}) < total_spent ({}). Payment already complete, finalizing with no change.",
@@ -444,6 +538,8 @@ pub async fn finalize_melt_core(
     // Update quote state to Paid
     tx.update_melt_quote_state(quote, MeltQuoteState::Paid, payment_preimage.clone())
         .await?;
+    tx.update_melt_quote_amount_spent(quote, total_spent.clone())
+        .await?;
 
     // Update payment lookup ID if changed
     if quote.request_lookup_id.as_ref() != Some(payment_lookup_id) {
@@ -0,0 +1,116 @@
+//! Tests for the checked amount arithmetic of melts
+//!
+//! Quote, fee and change amounts come from the request and the payment backend,
+//! so overflows are reported as errors and a backend reporting more spent than
+//! the inputs cover completes the melt without change.
+
+use cdk_common::nuts::CurrencyUnit;
+use cdk_common::{Amount, QuoteId};
+
+use crate::mint::melt::shared::{melt_change_amount, melt_required_amount, process_melt_change};
+use crate::test_helpers::mint::{create_test_blinded_messages, create_test_mint};
+use crate::Error;
+
+fn sat(value: u64) -> Amount<CurrencyUnit> {
+    Amount::new(value, CurrencyUnit::Sat)
+}
+
+#[test]
+fn test_required_amount_overflow() {
+    assert_eq!(
+        melt_required_amount(&sat(4_000), &sat(40), &sat(2)).unwrap(),
+        sat(4_042)
+    );
+
+    assert!(matches!(
+        melt_required_amount(&sat(u64::MAX - 10), &sat(10), &sat(1)),
+        Err(Error::AmountOverflow)
+    ));
+    assert!(matches!(
+        melt_required_amount(&sat(u64::MAX), &sat(1), &sat(0)),
+        Err(Error::AmountOverflow)
+    ));
+    assert_eq!(
+        melt_required_amount(&sat(u64::MAX - 11), &sat(10), &sat(1)).unwrap(),
+        sat(u64::MAX)
+    );
+}
+
+#[test]
+fn test_change_amount() {
+    assert_eq!(
+        melt_change_amount(&sat(100), &sat(90), &sat(2)).unwrap(),
+        Some(sat(8))
+    );
+    assert_eq!(
+        melt_change_amount(&sat(100), &sat(98), &sat(2)).unwrap(),
+        None
+    );
+}
+
+#[test]
+fn test_change_amount_overflow() {
+    assert!(matches!(
+        melt_change_amount(&sat(u64::MAX), &sat(u64::MAX), &sat(1)),
+        Err(Error::AmountOverflow)
+    ));
+    assert_eq!(
+        melt_change_amount(&sat(u64::MAX), &sat(u64::MAX - 1), &sat(1)).unwrap(),
+        None
+    );
+}
+
+/// Test: A backend reporting more spent than the inputs cover owes no change
+#[test]
+fn test_change_amount_overspent() {
+    // Spent more than the inputs
+    assert_eq!(
+        melt_change_amount(&sat(100), &sat(150), &sat(0)).unwrap(),
+        None
+    );
+    // Spent less than the inputs, but more than the inputs less the input fee
+    assert_eq!(
+        melt_change_amount(&sat(100), &sat(98), &sat(5)).unwrap(),
+        None
+    );
+}
+
+/// Test: Processing the change of an over spent melt signs nothing instead of failing
+#[tokio::test]
+async fn test_overspent_melt_change_not_signed() {
+    let mint = create_test_mint().await.unwrap();
+    let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(8))
+        .await
+        .unwrap();
+
+    for (total_spent, inputs_fee) in [(150, 0), (98, 5), (u64::MAX - 1, 1)] {
+        let (change, tx) = process_melt_change(
+            &mint,
+            &mint.localstore(),
+            &QuoteId::new_uuid(),
+            sat(100),
+            sat(total_spent),
+            sat(inputs_fee),
+            outputs.clone(),
+        )
+        .await
+        .unwrap();
+        tx.rollback().await.unwrap();
+
+        assert!(change.is_none());
+    }
+
+    let err = process_melt_change(
+        &mint,
+        &mint.localstore(),
+        &QuoteId::new_uuid(),
+        sat(100),
+        sat(u64::MAX),
+        sat(1),
+        outputs,
+    )
+    .await
+    .err()
+    .unwrap();
+    assert!(matches!(err, Error::AmountOverflow));
+}
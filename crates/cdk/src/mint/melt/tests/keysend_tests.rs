@@ -0,0 +1,136 @@
+//! Tests for melting to a node pubkey with the keysend custom method
+
+use cdk_common::melt::MeltQuoteRequest;
+use cdk_common::mint::{MeltPaymentRequest, MeltQuote};
+use cdk_common::nuts::{CurrencyUnit, MeltQuoteCustomRequest};
+use cdk_common::payment::{
+    KeysendExtra, OutgoingPaymentOptions, PaymentIdentifier, KEYSEND_METHOD,
+};
+use cdk_common::{Amount, PaymentMethod};
+
+use crate::test_helpers::mint::create_test_mint;
+use crate::Error;
+
+const DESTINATION: &str = "02a1633cafcc01ebfb6d78e39f687a1f0995c62fc95f51ead10a02ee0be551b5dc";
+
+/// Test: A backend without keysend support rejects the quote
+#[tokio::test]
+async fn test_keysend_quote_rejected_without_backend_support() {
+    let mint = create_test_mint().await.unwrap();
+
+    let err = mint
+        .get_melt_quote(MeltQuoteRequest::Custom(MeltQuoteCustomRequest {
+            method: KEYSEND_METHOD.to_string(),
+            request: DESTINATION.to_string(),
+            unit: CurrencyUnit::Sat,
+            extra: serde_json::json!({ "amount": 100 }),
+        }))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(err, Error::UnsupportedPaymentMethod),
+        "unexpected error {err:?}"
+    );
+}
+
+/// Test: The quoted amount is passed back to the backend when paying
+#[test]
+fn test_keysend_payment_options_carry_quote_amount() {
+    let quote = MeltQuote::new(
+        MeltPaymentRequest::Custom {
+            method: KEYSEND_METHOD.to_string(),
+            request: DESTINATION.to_string(),
+        },
+        CurrencyUnit::Sat,
+        Amount::new(100, CurrencyUnit::Sat),
+        Amount::new(2, CurrencyUnit::Sat),
+        0,
+        None,
+        None,
+        PaymentMethod::from(KEYSEND_METHOD),
+    );
+
+    let OutgoingPaymentOptions::Custom(options) = OutgoingPaymentOptions::try_from(quote).unwrap()
+    else {
+        panic!("Expected custom payment options");
+    };
+
+    let (destination, amount) = options.keysend().unwrap();
+    assert_eq!(destination.to_string(), DESTINATION);
+    assert_eq!(amount, Amount::from(100));
+    assert_eq!(options.max_fee_amount, Some(Amount::from(2)));
+}
+
+/// Test: The lookup id the backend created for the quote is passed back when paying
+#[test]
+fn test_keysend_payment_options_carry_request_lookup_id() {
+    let lookup_id = PaymentIdentifier::Label("cdk-keysend-test".to_string());
+    let quote = MeltQuote::new(
+        MeltPaymentRequest::Custom {
+            method: KEYSEND_METHOD.to_string(),
+            request: DESTINATION.to_string(),
+        },
+        CurrencyUnit::Sat,
+        Amount::new(100, CurrencyUnit::Sat),
+        Amount::new(2, CurrencyUnit::Sat),
+        0,
+        Some(lookup_id.clone()),
+        None,
+        PaymentMethod::from(KEYSEND_METHOD),
+    );
+
+    let OutgoingPaymentOptions::Custom(options) = OutgoingPaymentOptions::try_from(quote).unwrap()
+    else {
+        panic!("Expected custom payment options");
+    };
+
+    assert_eq!(options.keysend_request_lookup_id(), Some(lookup_id));
+}
+
+/// Test: A keysend payment needs a valid destination and an amount
+#[test]
+fn test_keysend_payment_options_validation() {
+    let mut quote = MeltQuote::new(
+        MeltPaymentRequest::Custom {
+            method: KEYSEND_METHOD.to_string(),
+            request: "not a pubkey".to_string(),
+        },
+        CurrencyUnit::Sat,
+        Amount::new(100, CurrencyUnit::Sat),
+        Amount::new(2, CurrencyUnit::Sat),
+        0,
+        None,
+        None,
+        PaymentMethod::from(KEYSEND_METHOD),
+    );
+
+    let OutgoingPaymentOptions::Custom(options) =
+        OutgoingPaymentOptions::try_from(quote.clone()).unwrap()
+    else {
+        panic!("Expected custom payment options");
+    };
+    assert!(options.keysend().is_err());
+
+    quote.request = MeltPaymentRequest::Custom {
+        method: KEYSEND_METHOD.to_string(),
+        request: DESTINATION.to_string(),
+    };
+    let OutgoingPaymentOptions::Custom(mut options) =
+        OutgoingPaymentOptions::try_from(quote).unwrap()
+    else {
+        panic!("Expected custom payment options");
+    };
+
+    options.extra_json = None;
+    assert!(options.keysend().is_err());
+
+    options.extra_json = Some(
+        serde_json::to_string(&KeysendExtra {
+            amount: Amount::ZERO,
+            request_lookup_id: None,
+        })
+        .unwrap(),
+    );
+    assert!(options.keysend().is_err());
+}
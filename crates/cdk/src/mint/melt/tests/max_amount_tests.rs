@@ -0,0 +1,161 @@
+//! Tests for the largest amount the mint signs for a single output
+//!
+//! Outputs above the max amount are rejected before anything is signed, and
+//! melt change whose natural split has a larger amount is split below it.
+
+use cdk_common::amount::SplitTarget;
+use cdk_common::melt::MeltQuoteRequest;
+use cdk_common::nuts::{
+    CurrencyUnit, MeltQuoteBolt11Request, MeltQuoteState, MeltRequest, PreMintSecrets, SwapRequest,
+};
+use cdk_common::Amount;
+use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
+
+use crate::mint::Mint;
+use crate::nuts::nut00::ProofsMethods;
+use crate::test_helpers::mint::{
+    assert_proofs_state, create_test_blinded_messages, create_test_mint_with_max_amount,
+    mint_test_proofs,
+};
+use crate::Error;
+
+const MAX_AMOUNT: u64 = 256;
+
+async fn create_max_amount_mint() -> Mint {
+    create_test_mint_with_max_amount(Amount::from(MAX_AMOUNT))
+        .await
+        .unwrap()
+}
+
+/// Test: The max amount is advertised with the keysets of the unit
+#[tokio::test]
+async fn test_keysets_advertise_max_amount() {
+    let mint = create_max_amount_mint().await;
+
+    let keysets = mint.keysets().keysets;
+    assert!(!keysets.is_empty());
+    assert!(keysets
+        .iter()
+        .all(|keyset| keyset.max_amount == Some(Amount::from(MAX_AMOUNT))));
+}
+
+/// Test: A swap with an output above the max amount is rejected and its
+/// inputs stay unspent, the same swap split below the max amount succeeds
+#[tokio::test]
+async fn test_swap_rejects_output_above_max_amount() {
+    let mint = create_max_amount_mint().await;
+
+    let inputs = mint_test_proofs(&mint, Amount::from(1000)).await.unwrap();
+    assert!(inputs
+        .iter()
+        .all(|proof| proof.amount <= Amount::from(MAX_AMOUNT)));
+
+    // The natural split of 1000 has a 512 output
+    let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(1000))
+        .await
+        .unwrap();
+
+    let err = mint
+        .process_swap_request(SwapRequest::new(inputs.clone(), outputs))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::OutputAmountNotAllowed(amount, max) if amount == Amount::from(512) && max == Amount::from(MAX_AMOUNT)),
+        "unexpected error {err:?}"
+    );
+    assert_proofs_state(&mint, &inputs.ys().unwrap(), None).await;
+
+    let keyset_id = mint.get_active_keysets()[&CurrencyUnit::Sat];
+    let fee_and_amounts = cdk_common::amount::FeeAndAmounts::from((
+        0,
+        (0..32).map(|x| 2u64.pow(x)).collect::<Vec<_>>(),
+    ))
+    .with_max_amount(Some(Amount::from(MAX_AMOUNT)));
+    let outputs = PreMintSecrets::random(
+        keyset_id,
+        Amount::from(1000),
+        &SplitTarget::None,
+        &fee_and_amounts,
+    )
+    .unwrap()
+    .blinded_messages();
+
+    let response = mint
+        .process_swap_request(SwapRequest::new(inputs, outputs))
+        .await
+        .unwrap();
+    assert!(response
+        .signatures
+        .iter()
+        .all(|sig| sig.amount <= Amount::from(MAX_AMOUNT)));
+}
+
+/// Test: Melt change whose natural split has an amount above the max amount
+/// is returned as several outputs of at most the max amount
+#[tokio::test]
+async fn test_melt_change_split_below_max_amount() {
+    let mint = create_max_amount_mint().await;
+
+    let proofs = mint_test_proofs(&mint, Amount::from(10_000)).await.unwrap();
+
+    let description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Paid,
+        check_payment_state: MeltQuoteState::Paid,
+        pay_err: false,
+        check_err: false,
+        pay_delay_ms: 0,
+    };
+    let invoice = create_fake_invoice(4_000_000, serde_json::to_string(&description).unwrap());
+    let quote = mint
+        .get_melt_quote(MeltQuoteRequest::Bolt11(MeltQuoteBolt11Request {
+            request: invoice,
+            unit: CurrencyUnit::Sat,
+            options: None,
+        }))
+        .await
+        .unwrap();
+
+    // Up to 6000 sats of change, naturally split into a 4096 output
+    let change_amount = Amount::from(6000);
+    let keyset_id = mint.get_active_keysets()[&CurrencyUnit::Sat];
+    let outputs = PreMintSecrets::from_seed_blank_with_max_amount(
+        keyset_id,
+        0,
+        &[7u8; 64],
+        change_amount,
+        Some(Amount::from(MAX_AMOUNT)),
+    )
+    .unwrap()
+    .blinded_messages();
+
+    let response = mint
+        .melt(&MeltRequest::new(
+            quote.quote.clone(),
+            proofs,
+            Some(outputs),
+        ))
+        .await
+        .unwrap();
+    assert_eq!(response.state, MeltQuoteState::Paid);
+
+    let ln_fee_paid = response
+        .fees
+        .and_then(|fees| fees.ln_fee_paid)
+        .expect("Completed melt should report fee paid");
+    let change = response.change.expect("Melt should return change");
+
+    assert!(change
+        .iter()
+        .all(|sig| sig.amount <= Amount::from(MAX_AMOUNT)));
+    assert!(
+        change
+            .iter()
+            .filter(|sig| sig.amount == Amount::from(MAX_AMOUNT))
+            .count()
+            > 1
+    );
+    assert_eq!(
+        Amount::try_sum(change.iter().map(|sig| sig.amount)).unwrap(),
+        Amount::from(10_000 - 4_000) - ln_fee_paid
+    );
+}
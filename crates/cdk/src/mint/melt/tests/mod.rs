@@ -1,5 +1,11 @@
+mod amount_overflow_tests;
 mod htlc_sigall_spending_conditions_tests;
 mod htlc_spending_conditions_tests;
+mod keysend_tests;
 mod locktime_spending_conditions_tests;
+mod max_amount_tests;
+mod mpp_limits_tests;
 mod p2pk_sigall_spending_conditions_tests;
 mod p2pk_spending_conditions_tests;
+mod quote_reuse_tests;
+mod stuck_quote_tests;
@@ -0,0 +1,115 @@
+//! Tests for the NUT-15 part limits and disabled flag
+//!
+//! A multi-part melt quote is checked against the part limits the mint
+//! advertises for its unit and method, and rejected while MPP is disabled.
+
+use cdk_common::melt::MeltQuoteRequest;
+use cdk_common::nut00::KnownMethod;
+use cdk_common::nuts::{CurrencyUnit, MeltQuoteBolt11Request};
+use cdk_common::{Amount, MeltOptions, PaymentMethod};
+use cdk_fake_wallet::create_fake_invoice;
+
+use crate::mint::Mint;
+use crate::test_helpers::mint::create_test_mint;
+use crate::Error;
+
+const MIN_PART: u64 = 1_000;
+const MAX_PART: u64 = 5_000;
+
+/// Creates a test mint advertising the part limits for bolt11 in sat
+async fn create_mpp_limits_mint() -> Mint {
+    let mint = create_test_mint().await.unwrap();
+
+    let mut mint_info = mint.mint_info().await.unwrap();
+    let settings = mint_info
+        .nuts
+        .nut15
+        .methods
+        .iter_mut()
+        .find(|settings| {
+            settings.unit == CurrencyUnit::Sat
+                && settings.method == PaymentMethod::Known(KnownMethod::Bolt11)
+        })
+        .expect("Fake wallet advertises MPP for bolt11");
+    settings.min_amount = Some(Amount::from(MIN_PART));
+    settings.max_amount = Some(Amount::from(MAX_PART));
+    mint.set_mint_info(mint_info).await.unwrap();
+
+    mint
+}
+
+fn mpp_quote_request(part_amount: u64) -> MeltQuoteRequest {
+    MeltQuoteRequest::Bolt11(MeltQuoteBolt11Request {
+        request: create_fake_invoice(10_000_000, "mpp".to_string()),
+        unit: CurrencyUnit::Sat,
+        options: Some(MeltOptions::new_mpp(part_amount * 1000)),
+    })
+}
+
+/// Test: A part just below the advertised minimum is rejected, a part at the
+/// minimum is quoted
+#[tokio::test]
+async fn test_mpp_part_below_min_amount_rejected() {
+    let mint = create_mpp_limits_mint().await;
+
+    let err = mint
+        .get_melt_quote(mpp_quote_request(MIN_PART - 1))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::AmountOutofLimitRange(min, max, amount)
+            if min == Amount::from(MIN_PART)
+                && max == Amount::from(MAX_PART)
+                && amount == Amount::from(MIN_PART - 1)),
+        "unexpected error {err:?}"
+    );
+    assert!(mint.localstore.get_melt_quotes().await.unwrap().is_empty());
+
+    let quote = mint
+        .get_melt_quote(mpp_quote_request(MIN_PART))
+        .await
+        .unwrap();
+    assert_eq!(quote.amount, Amount::from(MIN_PART));
+}
+
+/// Test: A part above the advertised maximum is rejected
+#[tokio::test]
+async fn test_mpp_part_above_max_amount_rejected() {
+    let mint = create_mpp_limits_mint().await;
+
+    let err = mint
+        .get_melt_quote(mpp_quote_request(MAX_PART + 1))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::AmountOutofLimitRange(..)),
+        "unexpected error {err:?}"
+    );
+}
+
+/// Test: No multi-part quote is issued while MPP is disabled, and quotes are
+/// issued again once it is re-enabled
+#[tokio::test]
+async fn test_mpp_disabled_rejects_parts() {
+    let mint = create_mpp_limits_mint().await;
+
+    let mut mint_info = mint.mint_info().await.unwrap();
+    mint_info.nuts.nut15.disabled = true;
+    mint.set_mint_info(mint_info.clone()).await.unwrap();
+
+    let err = mint
+        .get_melt_quote(mpp_quote_request(MIN_PART))
+        .await
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::MppUnitMethodNotSupported(..)),
+        "unexpected error {err:?}"
+    );
+
+    mint_info.nuts.nut15.disabled = false;
+    mint.set_mint_info(mint_info).await.unwrap();
+
+    mint.get_melt_quote(mpp_quote_request(MIN_PART))
+        .await
+        .unwrap();
+}
@@ -0,0 +1,146 @@
+//! Tests for answering repeated bolt11 melt quote requests with the existing quote
+
+use cdk_common::melt::MeltQuoteRequest;
+use cdk_common::nuts::{CurrencyUnit, MeltQuoteBolt11Request, MeltQuoteState};
+use cdk_common::MeltOptions;
+use cdk_fake_wallet::{
+    create_fake_amountless_invoice, create_fake_invoice, FakeInvoiceDescription,
+};
+
+use crate::test_helpers::mint::create_test_mint;
+use crate::types::QuoteTTL;
+
+fn fake_invoice(amount_msat: u64) -> lightning_invoice::Bolt11Invoice {
+    let description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Paid,
+        check_payment_state: MeltQuoteState::Paid,
+        pay_err: false,
+        check_err: false,
+        pay_delay_ms: 0,
+    };
+
+    create_fake_invoice(amount_msat, serde_json::to_string(&description).unwrap())
+}
+
+fn melt_quote_request(
+    invoice: &lightning_invoice::Bolt11Invoice,
+    options: Option<MeltOptions>,
+) -> MeltQuoteRequest {
+    MeltQuoteRequest::Bolt11(MeltQuoteBolt11Request {
+        request: invoice.clone(),
+        unit: CurrencyUnit::Sat,
+        options,
+    })
+}
+
+/// Test: Retrying the same quote request returns the quote created first
+#[tokio::test]
+async fn test_identical_retry_reuses_quote() {
+    let mint = create_test_mint().await.unwrap();
+    let invoice = fake_invoice(9_000);
+
+    let first = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+    let second = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+
+    assert_eq!(first.quote, second.quote);
+    assert_eq!(first.amount, second.amount);
+    assert_eq!(first.fee_reserve, second.fee_reserve);
+    assert_eq!(first.expiry, second.expiry);
+    assert_eq!(mint.localstore.get_melt_quotes().await.unwrap().len(), 1);
+
+    // Another invoice gets its own quote
+    let other = mint
+        .get_melt_quote(melt_quote_request(&fake_invoice(9_000), None))
+        .await
+        .unwrap();
+    assert_ne!(other.quote, first.quote);
+}
+
+/// Test: Requests for the same invoice with other options get their own quote
+#[tokio::test]
+async fn test_differing_options_create_new_quote() {
+    let mint = create_test_mint().await.unwrap();
+    let invoice = create_fake_amountless_invoice("amountless".to_string());
+
+    let first = mint
+        .get_melt_quote(melt_quote_request(
+            &invoice,
+            Some(MeltOptions::new_amountless(9_000)),
+        ))
+        .await
+        .unwrap();
+    let other_amount = mint
+        .get_melt_quote(melt_quote_request(
+            &invoice,
+            Some(MeltOptions::new_amountless(5_000)),
+        ))
+        .await
+        .unwrap();
+    let retry = mint
+        .get_melt_quote(melt_quote_request(
+            &invoice,
+            Some(MeltOptions::new_amountless(9_000)),
+        ))
+        .await
+        .unwrap();
+
+    assert_ne!(first.quote, other_amount.quote);
+    assert_eq!(other_amount.amount, 5.into());
+    assert_eq!(first.quote, retry.quote);
+    assert_eq!(mint.localstore.get_melt_quotes().await.unwrap().len(), 2);
+}
+
+/// Test: An expired quote is not reused
+#[tokio::test]
+async fn test_expired_quote_not_reused() {
+    let mint = create_test_mint().await.unwrap();
+    let invoice = fake_invoice(9_000);
+
+    // Quotes expire as soon as they are created
+    mint.set_quote_ttl(QuoteTTL::new(10_000, 0)).await.unwrap();
+    let expired = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+
+    mint.set_quote_ttl(QuoteTTL::new(10_000, 10_000))
+        .await
+        .unwrap();
+    let retried = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+    assert_ne!(expired.quote, retried.quote);
+
+    let again = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+    assert_eq!(retried.quote, again.quote);
+}
+
+/// Test: Quote reuse can be turned off
+#[tokio::test]
+async fn test_reuse_disabled_creates_new_quote() {
+    let mut mint = create_test_mint().await.unwrap();
+    mint.reuse_melt_quotes = false;
+    let invoice = fake_invoice(9_000);
+
+    let first = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+    let second = mint
+        .get_melt_quote(melt_quote_request(&invoice, None))
+        .await
+        .unwrap();
+
+    assert_ne!(first.quote, second.quote);
+    assert_eq!(mint.localstore.get_melt_quotes().await.unwrap().len(), 2);
+}
@@ -0,0 +1,79 @@
+//! Tests for the alerts on melt quotes stuck pending
+
+use std::time::Duration;
+
+use cdk_common::melt::MeltQuoteRequest;
+use cdk_common::nuts::{CurrencyUnit, MeltQuoteBolt11Request, MeltQuoteState, MeltRequest};
+use cdk_common::Amount;
+use cdk_fake_wallet::{create_fake_invoice, FakeInvoiceDescription};
+
+use crate::mint::stuck_quotes::StuckQuoteMonitor;
+use crate::mint::StuckQuoteEvent;
+use crate::test_helpers::mint::{create_test_mint_with_payment_timeout, mint_test_proofs};
+
+/// Test: A melt left pending by a slow payment is flagged as stuck, and cleared
+/// once the status check finalizes it
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn test_pending_melt_flagged_and_cleared() {
+    let mut mint = create_test_mint_with_payment_timeout(Duration::from_millis(200))
+        .await
+        .unwrap();
+    mint.stuck_quotes = StuckQuoteMonitor::new(Some(Duration::ZERO));
+    let mut events = mint.subscribe_stuck_melt_quotes();
+
+    let proofs = mint_test_proofs(&mint, Amount::from(10_000)).await.unwrap();
+
+    let description = FakeInvoiceDescription {
+        pay_invoice_state: MeltQuoteState::Paid,
+        check_payment_state: MeltQuoteState::Paid,
+        pay_err: false,
+        check_err: false,
+        pay_delay_ms: 1_500,
+    };
+    let invoice = create_fake_invoice(4_000_000, serde_json::to_string(&description).unwrap());
+    let quote = mint
+        .get_melt_quote(MeltQuoteRequest::Bolt11(MeltQuoteBolt11Request {
+            request: invoice,
+            unit: CurrencyUnit::Sat,
+            options: None,
+        }))
+        .await
+        .unwrap();
+
+    // Nothing is pending before the melt
+    assert!(mint.check_stuck_melt_quotes().await.unwrap().is_empty());
+
+    let response = mint
+        .melt(&MeltRequest::new(quote.quote.clone(), proofs, None))
+        .await
+        .unwrap();
+    assert_eq!(response.state, MeltQuoteState::Pending);
+
+    let flagged = mint.check_stuck_melt_quotes().await.unwrap();
+    let [StuckQuoteEvent::Stuck(stuck)] = flagged.as_slice() else {
+        panic!("Expected the pending quote to be flagged, got {flagged:?}");
+    };
+    assert_eq!(stuck.quote_id, quote.quote);
+    assert_eq!(stuck.amount, Amount::from(4_000));
+    assert_eq!(mint.stuck_melt_quotes(), vec![stuck.clone()]);
+    assert_eq!(events.recv().await.unwrap(), flagged[0]);
+
+    // Flagged once only
+    assert!(mint.check_stuck_melt_quotes().await.unwrap().is_empty());
+
+    // The status check finalizes the melt once the payment settles
+    tokio::time::sleep(Duration::from_millis(2_000)).await;
+    let status = mint.check_melt_quote(&quote.quote).await.unwrap();
+    assert_eq!(status.state, MeltQuoteState::Paid);
+
+    let cleared = mint.check_stuck_melt_quotes().await.unwrap();
+    assert_eq!(
+        cleared,
+        vec![StuckQuoteEvent::Cleared {
+            quote_id: quote.quote,
+            state: MeltQuoteState::Paid,
+        }]
+    );
+    assert!(mint.stuck_melt_quotes().is_empty());
+    assert_eq!(events.recv().await.unwrap(), cleared[0]);
+}
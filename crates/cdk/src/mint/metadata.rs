@@ -0,0 +1,151 @@
+//! Mint metadata
+//!
+//! Small persistent values of mint extensions (delivery cursors, checkpoints, epochs), kept in
+//! the KV store so they don't need a schema change each.
+
+use tracing::instrument;
+
+use super::{Mint, CDK_MINT_PRIMARY_NAMESPACE};
+use crate::Error;
+
+const CDK_MINT_METADATA_SECONDARY_NAMESPACE: &str = "metadata";
+
+impl Mint {
+    /// Get the metadata value stored under `key`
+    #[instrument(skip(self))]
+    pub async fn get_metadata(&self, key: &str) -> Result<Option<Vec<u8>>, Error> {
+        Ok(self
+            .localstore
+            .kv_read(
+                CDK_MINT_PRIMARY_NAMESPACE,
+                CDK_MINT_METADATA_SECONDARY_NAMESPACE,
+                key,
+            )
+            .await?)
+    }
+
+    /// Set the metadata value of `key`, the last write wins
+    #[instrument(skip(self, value))]
+    pub async fn set_metadata(&self, key: &str, value: &[u8]) -> Result<(), Error> {
+        let mut tx = self.localstore.begin_transaction().await?;
+        tx.kv_write(
+            CDK_MINT_PRIMARY_NAMESPACE,
+            CDK_MINT_METADATA_SECONDARY_NAMESPACE,
+            key,
+            value,
+        )
+        .await?;
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Set the metadata value of `key` only if it is currently `expected`
+    ///
+    /// `None` expects the key to be unset. Returns `false` if another writer got there first.
+    #[instrument(skip(self, expected, value))]
+    pub async fn compare_and_swap_metadata(
+        &self,
+        key: &str,
+        expected: Option<&[u8]>,
+        value: &[u8],
+    ) -> Result<bool, Error> {
+        let mut tx = self.localstore.begin_transaction().await?;
+        let swapped = tx
+            .kv_compare_and_swap(
+                CDK_MINT_PRIMARY_NAMESPACE,
+                CDK_MINT_METADATA_SECONDARY_NAMESPACE,
+                key,
+                expected,
+                value,
+            )
+            .await?;
+
+        if swapped {
+            tx.commit().await?;
+        } else {
+            tx.rollback().await?;
+        }
+
+        Ok(swapped)
+    }
+
+    /// Get the cursor stored under `key`
+    #[instrument(skip(self))]
+    pub async fn metadata_cursor(&self, key: &str) -> Result<Option<u64>, Error> {
+        match self.get_metadata(key).await? {
+            Some(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Move the cursor stored under `key` from `current` to `next`
+    ///
+    /// Returns `false` if the cursor is no longer at `current`, the caller should read it again.
+    #[instrument(skip(self))]
+    pub async fn advance_metadata_cursor(
+        &self,
+        key: &str,
+        current: Option<u64>,
+        next: u64,
+    ) -> Result<bool, Error> {
+        let expected = current.map(|c| serde_json::to_vec(&c)).transpose()?;
+        let next = serde_json::to_vec(&next)?;
+
+        self.compare_and_swap_metadata(key, expected.as_deref(), &next)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::test_helpers::mint::create_test_mint;
+
+    #[tokio::test]
+    async fn metadata_last_write_wins() {
+        let mint = create_test_mint().await.unwrap();
+
+        assert_eq!(mint.get_metadata("report_epoch").await.unwrap(), None);
+
+        mint.set_metadata("report_epoch", b"1").await.unwrap();
+        mint.set_metadata("report_epoch", b"2").await.unwrap();
+
+        assert_eq!(
+            mint.get_metadata("report_epoch").await.unwrap(),
+            Some(b"2".to_vec())
+        );
+    }
+
+    #[tokio::test]
+    async fn metadata_cursor_advances_once_per_value() {
+        let mint = create_test_mint().await.unwrap();
+
+        assert_eq!(mint.metadata_cursor("pay_index").await.unwrap(), None);
+        assert!(mint
+            .advance_metadata_cursor("pay_index", None, 5)
+            .await
+            .unwrap());
+
+        // A writer that read the cursor before it moved loses
+        assert!(!mint
+            .advance_metadata_cursor("pay_index", None, 3)
+            .await
+            .unwrap());
+        assert!(!mint
+            .advance_metadata_cursor("pay_index", Some(4), 6)
+            .await
+            .unwrap());
+
+        assert!(mint
+            .advance_metadata_cursor("pay_index", Some(5), 6)
+            .await
+            .unwrap());
+        assert_eq!(mint.metadata_cursor("pay_index").await.unwrap(), Some(6));
+    }
+
+    #[tokio::test]
+    async fn metadata_invalid_key() {
+        let mint = create_test_mint().await.unwrap();
+
+        assert!(mint.set_metadata("not a key", b"1").await.is_err());
+    }
+}
@@ -1,26 +1,28 @@
 //! Cashu Mint
 
 use std::collections::HashMap;
+use std::net::IpAddr;
 use std::sync::Arc;
 use std::time::Duration;
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use cdk_common::common::{PaymentProcessorKey, QuoteTTL};
 #[cfg(feature = "auth")]
 use cdk_common::database::DynMintAuthDatabase;
 use cdk_common::database::{self, Acquired, DynMintDatabase};
+use cdk_common::nut17::{MintInfoNotification, DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION};
 use cdk_common::nuts::{BlindSignature, BlindedMessage, CurrencyUnit, Id};
 use cdk_common::payment::{DynMintPayment, WaitPaymentResponse};
 pub use cdk_common::quote_id::QuoteId;
+use cdk_common::util::unix_time;
 #[cfg(feature = "prometheus")]
 use cdk_prometheus::global;
-use cdk_signatory::signatory::{Signatory, SignatoryKeySet};
-use futures::StreamExt;
+use cdk_signatory::signatory::Signatory;
 #[cfg(feature = "auth")]
 use nut21::ProtectedEndpoint;
 use subscription::PubSubManager;
-use tokio::sync::{Mutex, Notify};
-use tokio::task::{JoinHandle, JoinSet};
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use tracing::instrument;
 
 use crate::error::Error;
@@ -30,24 +32,59 @@ use crate::Amount;
 #[cfg(feature = "auth")]
 use crate::OidcClient;
 
+mod announcement;
+
+use announcement::LEGACY_PUBKEY_GRACE_PERIOD;
 #[cfg(feature = "auth")]
 pub(crate) mod auth;
+mod backend_check;
+mod ban_list;
 mod builder;
 mod check_spendable;
+mod db_integrity;
 mod issue;
 mod keysets;
 mod ln;
 mod melt;
+mod metadata;
+mod payment_limiter;
+mod payment_streams;
 mod proofs;
+mod response_cache;
 mod saga_recovery;
+mod scheduler;
 mod start_up_check;
+mod stuck_quotes;
 mod subscription;
 mod swap;
 mod verification;
 
+use ban_list::BanList;
+pub use ban_list::{
+    BanPolicy, BannedClient, DEFAULT_BAN_DURATION, DEFAULT_BAN_THRESHOLD, DEFAULT_BAN_WINDOW,
+};
 pub use builder::{MintBuilder, MintMeltLimits};
 pub use cdk_common::mint::{MeltQuote, MintKeySetInfo, MintQuote};
+pub use cdk_signatory::signatory::{KeysetAudit, KeysetAuditIssue};
+pub use db_integrity::{verify_database, DbIntegrityIssue, DbIntegrityReport};
 pub use issue::{MintQuoteRequest, MintQuoteResponse};
+use keysets::KeysetCache;
+pub use melt::DEFAULT_MELT_PROGRESS_INTERVAL;
+use payment_limiter::PaymentLimiter;
+pub use payment_limiter::{PaymentConcurrencyLimit, DEFAULT_PAYMENT_QUEUE_TIMEOUT};
+pub use payment_streams::PaymentStreamStatus;
+use payment_streams::{PaymentStreamBackoff, PaymentStreams};
+pub use response_cache::{DynResponseCache, ResponseCache, ResponseCacheScope, ResponseCacheStats};
+pub use scheduler::{
+    BackgroundTaskStatus, BAN_LIST_SWEEP_INTERVAL, PENDING_MINT_QUOTE_CHECK_INTERVAL,
+    STUCK_MELT_QUOTE_CHECK_INTERVAL,
+};
+use scheduler::{BackgroundTasks, TaskSchedule, TaskScheduler};
+pub use start_up_check::{
+    StartupCheckConfig, StartupCheckSummary, DEFAULT_STARTUP_CHECK_CONCURRENCY,
+};
+use stuck_quotes::StuckQuoteMonitor;
+pub use stuck_quotes::{StuckMeltQuote, StuckQuoteEvent};
 pub use verification::Verification;
 
 const CDK_MINT_PRIMARY_NAMESPACE: &str = "cdk_mint";
@@ -74,10 +111,38 @@ pub struct Mint {
     pubsub_manager: Arc<PubSubManager>,
     #[cfg(feature = "auth")]
     oidc_client: Option<OidcClient>,
-    /// In-memory keyset
-    keysets: Arc<ArcSwap<Vec<SignatoryKeySet>>>,
+    /// In-memory keysets, indexed by id
+    keysets: Arc<ArcSwap<KeysetCache>>,
     /// Background task management
     task_state: Arc<Mutex<TaskState>>,
+    /// Startup check settings
+    startup_check_config: StartupCheckConfig,
+    /// Create a test invoice in the payment backend self-check on startup
+    backend_invoice_selfcheck: bool,
+    /// Maximum number of inputs and outputs per request
+    request_limits: RequestLimits,
+    /// Limits on the change signed for melts
+    melt_change_policy: MeltChangePolicy,
+    /// Maximum number of filters accepted in a single websocket subscription
+    max_subscription_filters: usize,
+    /// Largest amount signed for a single output, per unit
+    max_amounts: HashMap<CurrencyUnit, Amount>,
+    /// Cache of the HTTP responses, if the server has one
+    response_cache: Arc<ArcSwapOption<DynResponseCache>>,
+    /// Concurrency limits of the payment backends
+    payment_limiter: PaymentLimiter,
+    /// Clients banned for replaying spent proofs
+    ban_list: BanList,
+    /// Melt quotes pending for longer than the alert threshold
+    stuck_quotes: StuckQuoteMonitor,
+    /// Status of the payment event streams of the payment backends
+    payment_streams: PaymentStreams,
+    /// Status of the background tasks
+    background_tasks: BackgroundTasks,
+    /// Answer repeated bolt11 melt quote requests with the existing unpaid quote
+    reuse_melt_quotes: bool,
+    /// Interval between the progress events of a melt awaiting its payment
+    melt_progress_interval: Duration,
 }
 
 impl std::fmt::Debug for Mint {
@@ -89,10 +154,10 @@ impl std::fmt::Debug for Mint {
 /// State for managing background tasks
 #[derive(Default)]
 struct TaskState {
-    /// Shutdown signal for all background tasks
-    shutdown_notify: Option<Arc<Notify>>,
-    /// Handle to the main supervisor task
-    supervisor_handle: Option<JoinHandle<Result<(), Error>>>,
+    /// Scheduler of the background tasks, while the mint is started
+    scheduler: Option<TaskScheduler>,
+    /// Handle to deferred startup checks, if they are still running
+    startup_check_handle: Option<JoinHandle<StartupCheckSummary>>,
 }
 
 impl Mint {
@@ -161,10 +226,19 @@ impl Mint {
                 .count()
         );
 
+        let announcement_pubkey = match signatory
+            .announcement_pubkey(Self::announcement_key_index(&localstore).await?)
+            .await
+        {
+            Ok(pubkey) => Some(pubkey),
+            Err(Error::AnnouncementKeyUnsupported) => None,
+            Err(err) => return Err(err),
+        };
+
         // Persist missing pubkey early to avoid losing it on next boot and ensure stable identity across restarts
         let mut computed_info = mint_info;
         if computed_info.pubkey.is_none() {
-            computed_info.pubkey = Some(keysets.pubkey);
+            computed_info.pubkey = Some(announcement_pubkey.unwrap_or(keysets.pubkey));
         }
 
         match localstore
@@ -178,7 +252,17 @@ impl Mint {
             Some(bytes) => {
                 let mut stored: MintInfo = serde_json::from_slice(&bytes)?;
                 let mut mutated = false;
-                if stored.pubkey.is_none() && computed_info.pubkey.is_some() {
+                // The signatory xpub was advertised before the announcement key existed
+                let legacy_pubkey =
+                    announcement_pubkey.is_some() && stored.pubkey == Some(keysets.pubkey);
+                if (stored.pubkey.is_none() || legacy_pubkey) && computed_info.pubkey.is_some() {
+                    // Announcements signed by the signatory key keep verifying for a while
+                    if let Some(previous) = stored.pubkey.filter(|_| legacy_pubkey) {
+                        stored.previous_pubkeys.push(PreviousPubkey {
+                            pubkey: previous,
+                            expires_at: unix_time() + LEGACY_PUBKEY_GRACE_PERIOD.as_secs(),
+                        });
+                    }
                     stored.pubkey = computed_info.pubkey;
                     mutated = true;
                 }
@@ -225,8 +309,22 @@ impl Mint {
             payment_processors,
             #[cfg(feature = "auth")]
             auth_localstore,
-            keysets: Arc::new(ArcSwap::new(keysets.keysets.into())),
+            keysets: Arc::new(ArcSwap::from_pointee(keysets.keysets.into())),
             task_state: Arc::new(Mutex::new(TaskState::default())),
+            startup_check_config: StartupCheckConfig::default(),
+            backend_invoice_selfcheck: false,
+            request_limits: RequestLimits::default(),
+            melt_change_policy: MeltChangePolicy::default(),
+            max_subscription_filters: DEFAULT_MAX_FILTERS_PER_SUBSCRIPTION,
+            max_amounts: HashMap::new(),
+            response_cache: Arc::new(ArcSwapOption::empty()),
+            payment_limiter: PaymentLimiter::default(),
+            ban_list: BanList::default(),
+            stuck_quotes: StuckQuoteMonitor::default(),
+            payment_streams: PaymentStreams::default(),
+            background_tasks: BackgroundTasks::default(),
+            reuse_melt_quotes: true,
+            melt_progress_interval: DEFAULT_MELT_PROGRESS_INTERVAL,
         })
     }
 
@@ -245,6 +343,12 @@ impl Mint {
     /// Currently manages:
     /// - Payment processor initialization and startup
     /// - Invoice payment monitoring across all configured payment processors
+    /// - Periodic checks of pending mint quotes and sweeps of expired client bans, see
+    ///   [`Mint::background_task_status`]
+    /// - Periodic checks for melt quotes stuck pending, if an alert threshold is set, see
+    ///   [`Mint::check_stuck_melt_quotes`]
+    /// - Startup reconciliation of pending quotes, deferred to a background task
+    ///   when [`StartupCheckConfig::defer`] is set
     pub async fn start(&self) -> Result<(), Error> {
         // Recover from incomplete swap sagas
         // This cleans up incomplete swap operations using persisted saga state
@@ -253,19 +357,14 @@ impl Mint {
             // Don't fail startup
         }
 
-        // Recover from incomplete melt sagas
-        // This cleans up incomplete melt operations using persisted saga state
-        // Now includes checking payment status with LN backend to determine
-        // whether to finalize (if paid) or compensate (if failed/unpaid)
-        if let Err(e) = self.recover_from_incomplete_melt_sagas().await {
-            tracing::error!("Failed to recover incomplete melt sagas: {}", e);
-            // Don't fail startup
+        if !self.startup_check_config.defer {
+            self.run_startup_checks().await;
         }
 
         let mut task_state = self.task_state.lock().await;
 
         // Prevent starting if already running
-        if task_state.shutdown_notify.is_some() {
+        if task_state.scheduler.is_some() {
             return Err(Error::Internal); // Already started
         }
 
@@ -296,29 +395,66 @@ impl Mint {
 
         tracing::info!("Payment processor startup completed");
 
-        // Create shutdown signal
-        let shutdown_notify = Arc::new(Notify::new());
+        self.check_payment_backends().await?;
 
-        // Clone required components for the background task
-        let payment_processors = self.payment_processors.clone();
-        let localstore = Arc::clone(&self.localstore);
-        let pubsub_manager = Arc::clone(&self.pubsub_manager);
-        let shutdown_clone = shutdown_notify.clone();
+        let mut scheduler = TaskScheduler::new(self.background_tasks.clone());
 
-        // Spawn the supervisor task
-        let supervisor_handle = tokio::spawn(async move {
+        scheduler.spawn(
+            "payment_streams",
             Self::wait_for_paid_invoices(
-                &payment_processors,
-                localstore,
-                pubsub_manager,
-                shutdown_clone,
-            )
-            .await
-        });
+                Arc::clone(&self.payment_processors),
+                Arc::clone(&self.localstore),
+                Arc::clone(&self.pubsub_manager),
+                scheduler.shutdown_signal(),
+                self.payment_streams.clone(),
+                PaymentStreamBackoff::default(),
+            ),
+        );
+
+        // Catches payments missed while a payment stream was down
+        let mint = self.clone();
+        scheduler.spawn_periodic(
+            "pending_mint_quotes",
+            TaskSchedule::every(PENDING_MINT_QUOTE_CHECK_INTERVAL),
+            move || {
+                let mint = mint.clone();
+                async move { mint.check_pending_mint_quotes().await.map(|_| ()) }
+            },
+        );
+
+        let ban_list = self.ban_list.clone();
+        scheduler.spawn_periodic(
+            "ban_list_sweep",
+            TaskSchedule::every(BAN_LIST_SWEEP_INTERVAL),
+            move || {
+                let ban_list = ban_list.clone();
+                async move {
+                    ban_list.sweep();
+                    Ok(())
+                }
+            },
+        );
+
+        if self.stuck_quotes.alert_after().is_some() {
+            let mint = self.clone();
+            scheduler.spawn_periodic(
+                "stuck_melt_quotes",
+                TaskSchedule::every(STUCK_MELT_QUOTE_CHECK_INTERVAL),
+                move || {
+                    let mint = mint.clone();
+                    async move { mint.check_stuck_melt_quotes().await.map(|_| ()) }
+                },
+            );
+        }
+
+        task_state.scheduler = Some(scheduler);
 
-        // Store the handles
-        task_state.shutdown_notify = Some(shutdown_notify);
-        task_state.supervisor_handle = Some(supervisor_handle);
+        if self.startup_check_config.defer {
+            tracing::info!("Running startup checks in the background");
+            let mint = self.clone();
+            task_state.startup_check_handle =
+                Some(tokio::spawn(async move { mint.run_startup_checks().await }));
+        }
 
         // Give the background task a tiny bit of time to start waiting
         tokio::time::sleep(std::time::Duration::from_millis(10)).await;
@@ -339,18 +475,18 @@ impl Mint {
     pub async fn stop(&self) -> Result<(), Error> {
         let mut task_state = self.task_state.lock().await;
 
-        // Take the handles out of the state
-        let shutdown_notify = task_state.shutdown_notify.take();
-        let supervisor_handle = task_state.supervisor_handle.take();
+        let scheduler = task_state.scheduler.take();
+
+        // Unfinished startup checks are safe to abandon, they resume on next start
+        if let Some(startup_check_handle) = task_state.startup_check_handle.take() {
+            startup_check_handle.abort();
+        }
 
         // If nothing to stop, return early
-        let (shutdown_notify, supervisor_handle) = match (shutdown_notify, supervisor_handle) {
-            (Some(notify), Some(handle)) => (notify, handle),
-            _ => {
-                tracing::debug!("Stop called but no background services were running");
-                // Still try to stop payment processors
-                return self.stop_payment_processors().await;
-            }
+        let Some(scheduler) = scheduler else {
+            tracing::debug!("Stop called but no background services were running");
+            // Still try to stop payment processors
+            return self.stop_payment_processors().await;
         };
 
         // Drop the lock before waiting
@@ -358,20 +494,11 @@ impl Mint {
 
         tracing::info!("Stopping mint background services...");
 
-        // Signal shutdown
-        shutdown_notify.notify_waiters();
-
-        // Wait for supervisor to complete
-        let result = match supervisor_handle.await {
-            Ok(result) => {
-                tracing::info!("Mint background services stopped");
-                result
-            }
-            Err(join_error) => {
-                tracing::error!("Background service task panicked: {:?}", join_error);
-                Err(Error::Internal)
-            }
-        };
+        // Signal shutdown and wait for all background tasks to stop
+        let result = scheduler.shutdown().await;
+        if result.is_ok() {
+            tracing::info!("Mint background services stopped");
+        }
 
         // Stop all payment processors
         self.stop_payment_processors().await?;
@@ -379,6 +506,13 @@ impl Mint {
         result
     }
 
+    /// Status of the background tasks
+    ///
+    /// Empty until the mint is started.
+    pub fn background_task_status(&self) -> Vec<BackgroundTaskStatus> {
+        self.background_tasks.snapshot()
+    }
+
     /// Stop all payment processors
     async fn stop_payment_processors(&self) -> Result<(), Error> {
         tracing::info!("Stopping payment processors...");
@@ -513,6 +647,18 @@ impl Mint {
             mint_info
         };
 
+        // Always advertise the request limits that are enforced
+        let mut mint_info = mint_info;
+        mint_info.nuts.request_limits = Some(self.request_limits);
+        mint_info.nuts.melt_change =
+            (!self.melt_change_policy.is_unrestricted()).then_some(self.melt_change_policy);
+        mint_info.nuts.nut17.max_filters = Some(self.max_subscription_filters);
+
+        let now = unix_time();
+        mint_info
+            .previous_pubkeys
+            .retain(|previous| previous.expires_at > now);
+
         Ok(mint_info)
     }
 
@@ -520,6 +666,7 @@ impl Mint {
     #[instrument(skip_all)]
     pub async fn set_mint_info(&self, mint_info: MintInfo) -> Result<(), Error> {
         tracing::info!("Updating mint info");
+        let previous_mint_info = self.mint_info().await.unwrap_or_default();
         let mint_info_bytes = serde_json::to_vec(&mint_info)?;
         let mut tx = self.localstore.begin_transaction().await?;
         tx.kv_write(
@@ -530,9 +677,94 @@ impl Mint {
         )
         .await?;
         tx.commit().await?;
+
+        let notification = MintInfoNotification::new(
+            &previous_mint_info,
+            &self.mint_info().await?,
+            self.active_keyset_ids(),
+            unix_time(),
+        )?;
+        if !notification.changed.is_empty() {
+            self.pubsub_manager.mint_info_change(notification);
+        }
+
         Ok(())
     }
 
+    /// Attach the cache of the HTTP responses
+    ///
+    /// The cache is cleared by the mint when keysets change.
+    pub fn set_response_cache(&self, cache: DynResponseCache) {
+        self.response_cache.store(Some(Arc::new(cache)));
+    }
+
+    /// Statistics of the response cache, `None` if no cache is attached
+    pub async fn response_cache_stats(&self) -> Option<ResponseCacheStats> {
+        match self.response_cache.load_full() {
+            Some(cache) => Some(cache.stats().await),
+            None => None,
+        }
+    }
+
+    /// Remove responses from the response cache
+    ///
+    /// Returns `false` if no cache is attached.
+    #[instrument(skip(self))]
+    pub async fn clear_response_cache(&self, scope: ResponseCacheScope) -> bool {
+        let Some(cache) = self.response_cache.load_full() else {
+            return false;
+        };
+
+        tracing::info!("Clearing response cache: {:?}", scope);
+        cache.clear(&scope).await;
+        true
+    }
+
+    /// Whether a [`BanPolicy`] is configured
+    pub fn ban_list_enabled(&self) -> bool {
+        self.ban_list.enabled()
+    }
+
+    /// Record a request of `client` rejected with `code`
+    ///
+    /// Requests spending spent proofs or reusing signed outputs count towards a ban of the
+    /// client, if a [`BanPolicy`] is configured. `request_keys` are the Ys of the request's
+    /// inputs, or the blinded secrets of its outputs if it has no inputs, so a retried request is
+    /// only counted once.
+    pub fn record_client_rejection(
+        &self,
+        client: IpAddr,
+        code: cdk_common::error::ErrorCode,
+        request_keys: &[PublicKey],
+    ) {
+        self.ban_list.record_rejection(client, code, request_keys);
+    }
+
+    /// Time left until the ban of `client` expires, `None` if it is not banned
+    pub fn client_ban(&self, client: &IpAddr) -> Option<Duration> {
+        self.ban_list.ban(client)
+    }
+
+    /// Replace the ban policy, `None` disables bans and lifts the current ones
+    pub fn set_ban_policy(&self, policy: Option<BanPolicy>) {
+        self.ban_list.set_policy(policy);
+    }
+
+    /// Clients currently banned
+    pub fn banned_clients(&self) -> Vec<BannedClient> {
+        self.ban_list.banned()
+    }
+
+    /// Lift the ban of `client`, or of all clients if `None`
+    ///
+    /// Returns the number of bans lifted.
+    #[instrument(skip(self))]
+    pub fn clear_client_bans(&self, client: Option<IpAddr>) -> usize {
+        let cleared = self.ban_list.clear(client);
+        tracing::info!("Lifted {} client bans", cleared);
+        cleared
+    }
+
     /// Get quote ttl
     #[instrument(skip_all)]
     pub async fn quote_ttl(&self) -> Result<QuoteTTL, Error> {
@@ -591,120 +823,6 @@ impl Mint {
         Ok(quote_ttl_bytes.is_some())
     }
 
-    #[instrument(skip_all)]
-    async fn wait_for_paid_invoices(
-        payment_processors: &HashMap<PaymentProcessorKey, DynMintPayment>,
-        localstore: DynMintDatabase,
-        pubsub_manager: Arc<PubSubManager>,
-        shutdown: Arc<Notify>,
-    ) -> Result<(), Error> {
-        let mut join_set = JoinSet::new();
-
-        // Group processors by unique instance (using Arc pointer equality)
-        let mut seen_processors = Vec::new();
-        for (key, processor) in payment_processors {
-            // Skip if processor is already active
-            if processor.is_wait_invoice_active() {
-                continue;
-            }
-
-            // Skip if we've already spawned a task for this processor instance
-            if seen_processors.iter().any(|p| Arc::ptr_eq(p, processor)) {
-                continue;
-            }
-
-            seen_processors.push(Arc::clone(processor));
-
-            tracing::info!("Starting payment wait task for {:?}", key);
-
-            // Clone for the spawned task
-            let processor = Arc::clone(processor);
-            let localstore = Arc::clone(&localstore);
-            let pubsub_manager = Arc::clone(&pubsub_manager);
-            let shutdown = Arc::clone(&shutdown);
-
-            join_set.spawn(async move {
-                let result = Self::wait_for_processor_payments(
-                    processor,
-                    localstore,
-                    pubsub_manager,
-                    shutdown,
-                )
-                .await;
-
-                if let Err(e) = result {
-                    tracing::error!("Payment processor task failed: {:?}", e);
-                }
-            });
-        }
-
-        // If no payment processors, just wait for shutdown
-        if join_set.is_empty() {
-            shutdown.notified().await;
-        } else {
-            // Wait for shutdown or all tasks to complete
-            loop {
-                tokio::select! {
-                    _ = shutdown.notified() => {
-                        tracing::info!("Shutting down payment processors");
-                        break;
-                    }
-                    Some(result) = join_set.join_next() => {
-                        if let Err(e) = result {
-                            tracing::warn!("Task panicked: {:?}", e);
-                        }
-                    }
-                    else => break, // All tasks completed
-                }
-            }
-        }
-
-        join_set.shutdown().await;
-        Ok(())
-    }
-
-    /// Handles payment waiting for a single processor
-    #[instrument(skip_all)]
-    async fn wait_for_processor_payments(
-        processor: DynMintPayment,
-        localstore: DynMintDatabase,
-        pubsub_manager: Arc<PubSubManager>,
-        shutdown: Arc<Notify>,
-    ) -> Result<(), Error> {
-        loop {
-            tokio::select! {
-                _ = shutdown.notified() => {
-                    processor.cancel_wait_invoice();
-                    break;
-                }
-                result = processor.wait_payment_event() => {
-                    match result {
-                        Ok(mut stream) => {
-                            while let Some(event) = stream.next().await {
-                                match event {
-                                    cdk_common::payment::Event::PaymentReceived(wait_payment_response) => {
-                                        if let Err(e) = Self::handle_payment_notification(
-                                            &localstore,
-                                            &pubsub_manager,
-                                            wait_payment_response,
-                                        ).await {
-                                            tracing::warn!("Payment notification error: {:?}", e);
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                        Err(e) => {
-                            tracing::warn!("Failed to get payment stream: {}", e);
-                            tokio::time::sleep(Duration::from_secs(5)).await;
-                        }
-                    }
-                }
-            }
-        }
-        Ok(())
-    }
-
     /// Handle payment notification without needing full Mint instance
     /// This is a helper function that can be called with just the required components
     #[instrument(skip_all)]
@@ -792,6 +910,7 @@ impl Mint {
                     None,
                 ) {
                     Ok(()) => {
+                        Self::log_mint_quote_payment_mismatch(mint_quote);
                         tx.update_mint_quote(mint_quote).await?;
                         pubsub_manager.mint_quote_payment(mint_quote, mint_quote.amount_paid());
                     }
@@ -812,6 +931,27 @@ impl Mint {
         Ok(())
     }
 
+    /// Log a bolt11 mint quote that received less or more than its amount
+    fn log_mint_quote_payment_mismatch(mint_quote: &MintQuote) {
+        if mint_quote.is_partially_paid() {
+            tracing::warn!(
+                "Mint quote {} is partially paid, received {} of {}",
+                mint_quote.id,
+                mint_quote.amount_paid(),
+                mint_quote
+                    .amount
+                    .clone()
+                    .unwrap_or_else(|| Amount::new(0, mint_quote.unit.clone()))
+            );
+        } else if mint_quote.amount_overpaid().value() > 0 {
+            tracing::warn!(
+                "Mint quote {} is overpaid by {}, only its amount will be issued",
+                mint_quote.id,
+                mint_quote.amount_overpaid()
+            );
+        }
+    }
+
     /// Fee required for proof set
     #[instrument(skip_all)]
     pub async fn get_proofs_fee(
@@ -820,15 +960,14 @@ impl Mint {
     ) -> Result<crate::fees::ProofsFeeBreakdown, Error> {
         let mut proofs_per_keyset = HashMap::new();
         let mut fee_per_keyset = HashMap::new();
+        let keysets = self.keysets.load();
 
         for proof in proofs {
             if let std::collections::hash_map::Entry::Vacant(e) =
                 fee_per_keyset.entry(proof.keyset_id)
             {
-                let mint_keyset_info = self
-                    .get_keyset_info(&proof.keyset_id)
-                    .ok_or(Error::UnknownKeySet)?;
-                e.insert(mint_keyset_info.input_fee_ppk);
+                let keyset = keysets.get(&proof.keyset_id).ok_or(Error::UnknownKeySet)?;
+                e.insert(keyset.input_fee_ppk);
             }
 
             proofs_per_keyset
@@ -859,17 +998,22 @@ impl Mint {
 
     /// Get keyset info
     pub fn get_keyset_info(&self, id: &Id) -> Option<MintKeySetInfo> {
-        self.keysets
-            .load()
-            .iter()
-            .filter_map(|keyset| {
-                if keyset.id == *id {
-                    Some(keyset.into())
-                } else {
-                    None
-                }
-            })
-            .next()
+        self.keysets.load().get(id).map(Into::into)
+    }
+
+    /// Limits on the change signed for melts
+    pub fn melt_change_policy(&self) -> MeltChangePolicy {
+        self.melt_change_policy
+    }
+
+    /// Maximum number of filters accepted in a single websocket subscription
+    pub fn max_subscription_filters(&self) -> usize {
+        self.max_subscription_filters
+    }
+
+    /// Largest amount signed for a single output with the keyset of `unit`
+    pub fn max_amount(&self, unit: &CurrencyUnit) -> Option<Amount> {
+        self.max_amounts.get(unit).copied()
     }
 
     /// Blind Sign
@@ -925,21 +1069,37 @@ impl Mint {
         global::inc_in_flight_requests("restore");
 
         let result = async {
-            let output_len = request.outputs.len();
+            let RestoreRequest {
+                outputs: mut request_outputs,
+                offset,
+                limit,
+            } = request;
+
+            // A paginated request is answered for its first `limit` outputs
+            let limit = limit.map(|limit| limit.clamp(1, MAX_RESTORE_PAGE_SIZE));
+            let offset = limit.map(|_| offset.unwrap_or(0));
+            let next_offset = match (offset, limit) {
+                (Some(offset), Some(limit)) if request_outputs.len() as u64 > limit => {
+                    request_outputs.truncate(limit as usize);
+                    Some(offset + limit)
+                }
+                _ => None,
+            };
+
+            let output_len = request_outputs.len();
 
             let mut outputs = Vec::with_capacity(output_len);
             let mut signatures = Vec::with_capacity(output_len);
 
             // Build a position map to track original request order for verification
-            let position_map: HashMap<PublicKey, usize> = request
-                .outputs
+            let position_map: HashMap<PublicKey, usize> = request_outputs
                 .iter()
                 .enumerate()
                 .map(|(idx, output)| (output.blinded_secret, idx))
                 .collect();
 
             let blinded_message: Vec<PublicKey> =
-                request.outputs.iter().map(|b| b.blinded_secret).collect();
+                request_outputs.iter().map(|b| b.blinded_secret).collect();
 
             let blinded_signatures = self
                 .localstore
@@ -951,7 +1111,7 @@ impl Mint {
             }
 
             for (blinded_message, blinded_signature) in
-                request.outputs.into_iter().zip(blinded_signatures)
+                request_outputs.into_iter().zip(blinded_signatures)
             {
                 if let Some(blinded_signature) = blinded_signature {
                     outputs.push(blinded_message);
@@ -986,6 +1146,9 @@ impl Mint {
                 outputs,
                 signatures: signatures.clone(),
                 promises: Some(signatures),
+                offset,
+                limit,
+                next_offset,
             })
         }
         .await;
@@ -1043,6 +1206,47 @@ impl Mint {
 
         total_redeemed
     }
+
+    /// Total amount settled internally between melt and mint quotes, per unit
+    ///
+    /// These amounts were redeemed and issued without any lightning payment.
+    #[instrument(skip_all)]
+    pub async fn total_internal_settled(&self) -> Result<HashMap<CurrencyUnit, Amount>, Error> {
+        let mut total_settled: HashMap<CurrencyUnit, Amount> = HashMap::new();
+
+        for settlement in self.localstore.get_internal_settlements().await? {
+            let total = total_settled
+                .entry(settlement.amount.unit().clone())
+                .or_default();
+            *total = total
+                .checked_add(settlement.amount.into())
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        Ok(total_settled)
+    }
+
+    /// Total amount received above the quoted amount of bolt11 mint quotes, per unit
+    ///
+    /// Only the quoted amounts are issued, so the excess is not backed by any ecash.
+    #[instrument(skip_all)]
+    pub async fn total_overpaid(&self) -> Result<HashMap<CurrencyUnit, Amount>, Error> {
+        let mut total_overpaid: HashMap<CurrencyUnit, Amount> = HashMap::new();
+
+        for quote in self.localstore.get_mint_quotes().await? {
+            let overpaid = quote.amount_overpaid();
+            if overpaid.value() == 0 {
+                continue;
+            }
+
+            let total = total_overpaid.entry(quote.unit.clone()).or_default();
+            *total = total
+                .checked_add(overpaid.into())
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        Ok(total_overpaid)
+    }
 }
 
 #[cfg(test)]
@@ -1127,6 +1331,78 @@ mod tests {
         );
     }
 
+    #[tokio::test]
+    async fn mint_mod_upgrade_keeps_signatory_pubkey_as_previous() {
+        let mut supported_units = HashMap::new();
+        supported_units.insert(CurrencyUnit::default(), (0, 32));
+        let localstore = Arc::new(
+            new_with_state(
+                HashMap::new(),
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                vec![],
+                MintInfo::default(),
+            )
+            .await
+            .unwrap(),
+        );
+        let signatory = Arc::new(
+            cdk_signatory::db_signatory::DbSignatory::new(
+                localstore.clone(),
+                b"seed",
+                supported_units,
+                HashMap::new(),
+            )
+            .await
+            .unwrap(),
+        );
+
+        // Mint info stored by a version advertising the signatory pubkey
+        let signatory_pubkey = signatory.keysets().await.unwrap().pubkey;
+        let stored = MintInfo {
+            pubkey: Some(signatory_pubkey),
+            ..MintInfo::default()
+        };
+        let db: DynMintDatabase = localstore.clone();
+        let mut tx = db.begin_transaction().await.unwrap();
+        tx.kv_write(
+            CDK_MINT_PRIMARY_NAMESPACE,
+            CDK_MINT_CONFIG_SECONDARY_NAMESPACE,
+            CDK_MINT_CONFIG_KV_KEY,
+            &serde_json::to_vec(&stored).unwrap(),
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        for _ in 0..2 {
+            let mint = Mint::new(
+                MintInfo::default(),
+                signatory.clone(),
+                localstore.clone(),
+                HashMap::new(),
+            )
+            .await
+            .unwrap();
+
+            // The announcement key is advertised, the signatory key is kept for the grace
+            // period once, however often the mint restarts
+            let mint_info = mint.mint_info().await.unwrap();
+            assert_eq!(
+                mint_info.pubkey,
+                Some(mint.announcement_pubkey().await.unwrap())
+            );
+            assert_eq!(mint_info.previous_pubkeys.len(), 1);
+            assert_eq!(mint_info.previous_pubkeys[0].pubkey, signatory_pubkey);
+            assert!(
+                mint_info.previous_pubkeys[0].expires_at
+                    > unix_time() + LEGACY_PUBKEY_GRACE_PERIOD.as_secs() - 60
+            );
+        }
+    }
+
     #[tokio::test]
     async fn mint_mod_rotate_keyset() {
         let mut supported_units = HashMap::new();
@@ -1158,6 +1434,157 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn mint_mod_rotation_replaces_cached_keysets() {
+        let mut supported_units = HashMap::new();
+        supported_units.insert(CurrencyUnit::default(), (0, 32));
+
+        let config = MintConfig::<'_> {
+            supported_units,
+            ..Default::default()
+        };
+        let mint = create_mint(config).await;
+
+        let old_id = mint.keysets().keysets[0].id;
+        let point = PublicKey::from_hex(
+            "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2",
+        )
+        .unwrap();
+        let output = |keyset_id| BlindedMessage::new(Amount::from(1), keyset_id, point);
+        let proof = |keyset_id| {
+            Proof::new(
+                Amount::from(1),
+                keyset_id,
+                crate::secret::Secret::generate(),
+                point,
+            )
+        };
+
+        assert!(mint.verify_outputs_keyset(&[output(old_id)]).is_ok());
+        assert_eq!(
+            mint.get_proofs_fee(&vec![proof(old_id)])
+                .await
+                .unwrap()
+                .total,
+            Amount::ZERO
+        );
+
+        let rotated = mint
+            .rotate_keyset(CurrencyUnit::default(), vec![1, 2], 1000)
+            .await
+            .unwrap();
+
+        // The lookups see the rotated keysets, without a stale active flag for the old one
+        assert!(!mint.get_keyset_info(&old_id).unwrap().active);
+        assert!(mint.get_keyset_info(&rotated.id).unwrap().active);
+        assert!(matches!(
+            mint.verify_outputs_keyset(&[output(old_id)]),
+            Err(Error::InactiveKeyset)
+        ));
+        assert_eq!(
+            mint.verify_outputs_keyset(&[output(rotated.id)]).unwrap(),
+            CurrencyUnit::default()
+        );
+        assert_eq!(
+            mint.get_proofs_fee(&vec![proof(rotated.id)])
+                .await
+                .unwrap()
+                .total,
+            Amount::from(1)
+        );
+
+        // Proofs of the old keyset are still accepted as inputs
+        assert_eq!(
+            mint.verify_inputs_keyset(&vec![proof(old_id), proof(rotated.id)])
+                .await
+                .unwrap(),
+            CurrencyUnit::default()
+        );
+        assert_eq!(mint.keyset(&old_id).unwrap().id, old_id);
+        assert!(mint.keyset_pubkeys(&rotated.id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn mint_mod_publishes_mint_info_changes() {
+        let mut supported_units = HashMap::new();
+        supported_units.insert(CurrencyUnit::default(), (0, 32));
+
+        let config = MintConfig::<'_> {
+            supported_units,
+            ..Default::default()
+        };
+        let mint = create_mint(config).await;
+        let mint_info = mint.mint_info().await.unwrap();
+
+        let mut subscription = mint
+            .pubsub_manager()
+            .subscribe(cdk_common::subscription::Params {
+                kind: cdk_common::nut17::Kind::MintInfo,
+                filters: vec![],
+                id: Arc::new("mint_info".into()),
+            })
+            .unwrap();
+
+        // Setting the same info publishes nothing
+        mint.set_mint_info(mint_info.clone()).await.unwrap();
+
+        mint.set_mint_info(mint_info.motd("maintenance tonight"))
+            .await
+            .unwrap();
+        let NotificationPayload::MintInfo(notification) =
+            subscription.recv().await.unwrap().into_inner()
+        else {
+            panic!("Expected a mint info notification");
+        };
+        assert_eq!(
+            notification.changed.into_iter().collect::<Vec<_>>(),
+            vec![("motd".to_string(), "maintenance tonight".into())]
+        );
+        assert_eq!(notification.active_keysets, mint.active_keyset_ids());
+
+        let rotated = mint
+            .rotate_keyset(CurrencyUnit::default(), vec![1, 2], 0)
+            .await
+            .unwrap();
+        let NotificationPayload::MintInfo(notification) =
+            subscription.recv().await.unwrap().into_inner()
+        else {
+            panic!("Expected a mint info notification");
+        };
+        assert!(notification.changed.is_empty());
+        assert!(notification.active_keysets.contains(&rotated.id));
+    }
+
+    #[tokio::test]
+    async fn mint_mod_audit_keysets() {
+        let mut supported_units = HashMap::new();
+        supported_units.insert(CurrencyUnit::default(), (0, 32));
+
+        let config = MintConfig::<'_> {
+            supported_units,
+            ..Default::default()
+        };
+        let mint = create_mint(config).await;
+
+        let rotated = mint
+            .rotate_keyset(CurrencyUnit::default(), vec![1, 2], 0)
+            .await
+            .expect("test");
+
+        let audits = mint.audit_keysets().await.unwrap();
+
+        // Sat and auth keysets, plus the rotated one
+        assert_eq!(audits.len(), 3);
+        assert!(audits.iter().all(|audit| audit.is_ok()));
+
+        let rotated_audit = audits
+            .iter()
+            .find(|audit| audit.id == rotated.id)
+            .expect("rotated keyset is audited");
+        assert!(rotated_audit.active);
+        assert_eq!(rotated_audit.derivation_path_index, Some(1));
+    }
+
     #[tokio::test]
     async fn test_mint_keyset_gen() {
         let seed = bip39::Mnemonic::from_str(
@@ -1207,4 +1634,84 @@ mod tests {
         mint.start().await.expect("Should be able to restart");
         mint.stop().await.expect("Final stop should work");
     }
+
+    #[tokio::test]
+    async fn restore_paginated() {
+        let mint = crate::test_helpers::mint::create_test_mint().await.unwrap();
+        let keyset_id = Id::from_str("00916bbf7ef91a36").unwrap();
+
+        let outputs = (0..5)
+            .map(|_| {
+                BlindedMessage::new(
+                    Amount::from(1),
+                    keyset_id,
+                    SecretKey::generate().public_key(),
+                )
+            })
+            .collect::<Vec<_>>();
+        let signature = |output: &BlindedMessage| BlindSignature {
+            amount: output.amount,
+            keyset_id,
+            c: SecretKey::generate().public_key(),
+            dleq: None,
+        };
+
+        // Only the outputs at positions 1 and 3 were signed
+        let mut tx = mint.localstore.begin_transaction().await.unwrap();
+        tx.add_blind_signatures(
+            &[outputs[1].blinded_secret, outputs[3].blinded_secret],
+            &[signature(&outputs[1]), signature(&outputs[3])],
+            None,
+        )
+        .await
+        .unwrap();
+        tx.commit().await.unwrap();
+
+        // Without pagination the whole request is answered
+        let response = mint
+            .restore(RestoreRequest::new(outputs.clone()))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.outputs,
+            vec![outputs[1].clone(), outputs[3].clone()]
+        );
+        assert_eq!(
+            (response.offset, response.limit, response.next_offset),
+            (None, None, None)
+        );
+
+        let response = mint
+            .restore(RestoreRequest::paginated(outputs.clone(), 0, 2))
+            .await
+            .unwrap();
+        assert_eq!(response.outputs, vec![outputs[1].clone()]);
+        assert_eq!(
+            (response.offset, response.limit, response.next_offset),
+            (Some(0), Some(2), Some(2))
+        );
+
+        let response = mint
+            .restore(RestoreRequest::paginated(outputs[2..].to_vec(), 2, 2))
+            .await
+            .unwrap();
+        assert_eq!(response.outputs, vec![outputs[3].clone()]);
+        assert_eq!(response.next_offset, Some(4));
+
+        // The last page has no next offset
+        let response = mint
+            .restore(RestoreRequest::paginated(outputs[4..].to_vec(), 4, 2))
+            .await
+            .unwrap();
+        assert!(response.outputs.is_empty());
+        assert_eq!(response.next_offset, None);
+
+        // Page sizes are capped
+        let response = mint
+            .restore(RestoreRequest::paginated(outputs.clone(), 0, u64::MAX))
+            .await
+            .unwrap();
+        assert_eq!(response.limit, Some(MAX_RESTORE_PAGE_SIZE));
+        assert_eq!(response.next_offset, None);
+    }
 }
@@ -0,0 +1,173 @@
+//! Concurrency limits for payment backends
+//!
+//! Each payment backend gets a semaphore bounding the payment operations (melts and mint quote
+//! creation) running against it at once. Requests over the limit wait for at most the queue
+//! timeout and then fail with [`Error::PaymentBackendBusy`], so a burst of requests does not pile
+//! onto a small lightning node.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use cdk_common::common::PaymentProcessorKey;
+#[cfg(feature = "prometheus")]
+use cdk_prometheus::global;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::Error;
+
+/// Default time a payment operation waits for a free slot
+pub const DEFAULT_PAYMENT_QUEUE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Concurrency limit of the payment operations of each payment backend
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PaymentConcurrencyLimit {
+    /// Payment operations running at once against a backend
+    pub max_in_flight: usize,
+    /// How long an operation waits for a free slot before failing
+    pub queue_timeout: Duration,
+}
+
+impl PaymentConcurrencyLimit {
+    /// Limit of `max_in_flight` operations with the default queue timeout
+    pub fn new(max_in_flight: usize) -> Self {
+        Self {
+            max_in_flight,
+            queue_timeout: DEFAULT_PAYMENT_QUEUE_TIMEOUT,
+        }
+    }
+}
+
+/// Semaphores of the payment backends, unlimited unless configured
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PaymentLimiter {
+    queue_timeout: Duration,
+    backends: Arc<HashMap<PaymentProcessorKey, Arc<Semaphore>>>,
+}
+
+/// Slot of a running payment operation, released on drop
+#[derive(Debug)]
+pub(crate) struct PaymentPermit {
+    _permit: OwnedSemaphorePermit,
+    #[cfg(feature = "prometheus")]
+    backend: String,
+}
+
+impl Drop for PaymentPermit {
+    fn drop(&mut self) {
+        #[cfg(feature = "prometheus")]
+        global::dec_payment_backend_requests(&self.backend, "in_flight");
+    }
+}
+
+impl PaymentLimiter {
+    /// Limiter with a semaphore for each of `backends`
+    pub(crate) fn new<'a>(
+        limit: PaymentConcurrencyLimit,
+        backends: impl IntoIterator<Item = &'a PaymentProcessorKey>,
+    ) -> Self {
+        Self {
+            queue_timeout: limit.queue_timeout,
+            backends: Arc::new(
+                backends
+                    .into_iter()
+                    .map(|key| (key.clone(), Arc::new(Semaphore::new(limit.max_in_flight))))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Wait for a slot of the backend of `key`
+    ///
+    /// Returns `None` if the backend is not limited.
+    pub(crate) async fn acquire(
+        &self,
+        key: &PaymentProcessorKey,
+    ) -> Result<Option<PaymentPermit>, Error> {
+        let Some(semaphore) = self.backends.get(key) else {
+            return Ok(None);
+        };
+
+        #[cfg(feature = "prometheus")]
+        let backend = format!("{}:{}", key.unit, key.method);
+        #[cfg(feature = "prometheus")]
+        global::inc_payment_backend_requests(&backend, "queued");
+
+        let permit =
+            tokio::time::timeout(self.queue_timeout, Arc::clone(semaphore).acquire_owned()).await;
+
+        #[cfg(feature = "prometheus")]
+        global::dec_payment_backend_requests(&backend, "queued");
+
+        match permit {
+            Ok(Ok(permit)) => {
+                #[cfg(feature = "prometheus")]
+                global::inc_payment_backend_requests(&backend, "in_flight");
+
+                Ok(Some(PaymentPermit {
+                    _permit: permit,
+                    #[cfg(feature = "prometheus")]
+                    backend,
+                }))
+            }
+            // The semaphores are never closed
+            Ok(Err(_)) => Err(Error::Internal),
+            Err(_) => {
+                tracing::warn!(
+                    "Payment backend {} {} is busy, rejecting request after {:?}",
+                    key.unit,
+                    key.method,
+                    self.queue_timeout
+                );
+                Err(Error::PaymentBackendBusy(
+                    self.queue_timeout.as_secs().max(1),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::nuts::nut00::KnownMethod;
+    use crate::nuts::{CurrencyUnit, PaymentMethod};
+
+    fn key() -> PaymentProcessorKey {
+        PaymentProcessorKey::new(CurrencyUnit::Sat, PaymentMethod::Known(KnownMethod::Bolt11))
+    }
+
+    #[tokio::test]
+    async fn waits_for_slot_then_rejects() {
+        let limiter = PaymentLimiter::new(
+            PaymentConcurrencyLimit {
+                max_in_flight: 1,
+                queue_timeout: Duration::from_millis(50),
+            },
+            [&key()],
+        );
+
+        let permit = limiter.acquire(&key()).await.unwrap();
+        assert!(permit.is_some());
+
+        assert!(matches!(
+            limiter.acquire(&key()).await,
+            Err(Error::PaymentBackendBusy(1))
+        ));
+
+        // A queued request gets the slot once it is released
+        let queued = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { limiter.acquire(&key()).await.map(|p| p.is_some()) }
+        });
+        tokio::time::sleep(Duration::from_millis(10)).await;
+        drop(permit);
+        assert!(queued.await.unwrap().unwrap());
+    }
+
+    #[tokio::test]
+    async fn unlimited_backend() {
+        let limiter = PaymentLimiter::default();
+        assert!(limiter.acquire(&key()).await.unwrap().is_none());
+    }
+}
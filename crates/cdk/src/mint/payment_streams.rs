@@ -0,0 +1,474 @@
+//! Supervision of the payment event streams of the payment backends
+//!
+//! Each payment backend gets its own task listening to its payment events. When the stream of a
+//! backend ends, fails or panics, the task logs it with the backend identity and opens the
+//! stream again after a bounded backoff, without affecting the streams of the other backends.
+//! The status of each stream is kept so operators can detect one that is silently dead.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use cdk_common::common::PaymentProcessorKey;
+use cdk_common::database::DynMintDatabase;
+use cdk_common::payment::{DynMintPayment, Event};
+use cdk_common::util::unix_time;
+use futures::StreamExt;
+use tokio::sync::Notify;
+use tokio::task::JoinSet;
+use tracing::instrument;
+
+use super::scheduler::panic_message;
+use super::subscription::PubSubManager;
+use super::Mint;
+use crate::Error;
+
+/// Bounds of the delay before a payment stream is opened again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct PaymentStreamBackoff {
+    /// Delay after the first failure, reset once the stream delivers an event
+    pub min: Duration,
+    /// Longest delay, reached by doubling the delay after each failure
+    pub max: Duration,
+}
+
+impl Default for PaymentStreamBackoff {
+    fn default() -> Self {
+        Self {
+            min: Duration::from_secs(1),
+            max: Duration::from_secs(60),
+        }
+    }
+}
+
+/// Status of the payment event stream of a payment backend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PaymentStreamStatus {
+    /// Payment processor keys served by the backend
+    pub keys: Vec<PaymentProcessorKey>,
+    /// Whether the stream is open
+    pub connected: bool,
+    /// Number of payment events received
+    pub events: u64,
+    /// Unix time of the last payment event
+    pub last_event: Option<u64>,
+    /// Number of times the stream was opened again
+    pub restarts: u64,
+    /// Why the stream was last closed
+    pub last_error: Option<String>,
+}
+
+/// Status of the payment streams, one entry per backend
+#[derive(Debug, Clone, Default)]
+pub(crate) struct PaymentStreams {
+    streams: Arc<RwLock<Vec<PaymentStreamStatus>>>,
+}
+
+impl PaymentStreams {
+    /// Replace the tracked streams with one for each of `backends`
+    fn reset(&self, backends: Vec<Vec<PaymentProcessorKey>>) {
+        let mut streams = self.streams.write().unwrap_or_else(|e| e.into_inner());
+        *streams = backends
+            .into_iter()
+            .map(|keys| PaymentStreamStatus {
+                keys,
+                connected: false,
+                events: 0,
+                last_event: None,
+                restarts: 0,
+                last_error: None,
+            })
+            .collect();
+    }
+
+    fn update(&self, index: usize, f: impl FnOnce(&mut PaymentStreamStatus)) {
+        let mut streams = self.streams.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(status) = streams.get_mut(index) {
+            f(status);
+        }
+    }
+
+    fn events(&self, index: usize) -> u64 {
+        self.streams
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(index)
+            .map_or(0, |status| status.events)
+    }
+
+    /// Snapshot of the status of all streams
+    pub(crate) fn snapshot(&self) -> Vec<PaymentStreamStatus> {
+        self.streams
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+}
+
+impl Mint {
+    /// Status of the payment event stream of each payment backend
+    ///
+    /// Empty until the mint is started.
+    pub fn payment_stream_status(&self) -> Vec<PaymentStreamStatus> {
+        self.payment_streams.snapshot()
+    }
+
+    /// Listen to the payment events of all backends until `shutdown` is notified
+    #[instrument(skip_all)]
+    pub(crate) async fn wait_for_paid_invoices(
+        payment_processors: Arc<HashMap<PaymentProcessorKey, DynMintPayment>>,
+        localstore: DynMintDatabase,
+        pubsub_manager: Arc<PubSubManager>,
+        shutdown: Arc<Notify>,
+        streams: PaymentStreams,
+        backoff: PaymentStreamBackoff,
+    ) -> Result<(), Error> {
+        // Group processors by unique instance (using Arc pointer equality)
+        let mut backends: Vec<(DynMintPayment, Vec<PaymentProcessorKey>)> = Vec::new();
+        for (key, processor) in payment_processors.iter() {
+            // Skip if processor is already active
+            if processor.is_wait_invoice_active() {
+                continue;
+            }
+
+            match backends.iter_mut().find(|(p, _)| Arc::ptr_eq(p, processor)) {
+                Some((_, keys)) => keys.push(key.clone()),
+                None => backends.push((Arc::clone(processor), vec![key.clone()])),
+            }
+        }
+
+        streams.reset(backends.iter().map(|(_, keys)| keys.clone()).collect());
+
+        let mut join_set = JoinSet::new();
+        for (index, (processor, keys)) in backends.iter().enumerate() {
+            tracing::info!("Starting payment wait task for {:?}", keys);
+
+            join_set.spawn(Self::supervise_payment_stream(
+                Arc::clone(processor),
+                keys.clone(),
+                index,
+                streams.clone(),
+                Arc::clone(&localstore),
+                Arc::clone(&pubsub_manager),
+                backoff,
+            ));
+        }
+
+        // Supervisors never return, so this only ends on shutdown
+        shutdown.notified().await;
+        tracing::info!("Shutting down payment processors");
+
+        for (processor, _) in &backends {
+            processor.cancel_wait_invoice();
+        }
+        join_set.shutdown().await;
+
+        Ok(())
+    }
+
+    /// Keep the payment stream of one backend open, restarting it when it stops
+    async fn supervise_payment_stream(
+        processor: DynMintPayment,
+        keys: Vec<PaymentProcessorKey>,
+        index: usize,
+        streams: PaymentStreams,
+        localstore: DynMintDatabase,
+        pubsub_manager: Arc<PubSubManager>,
+        backoff: PaymentStreamBackoff,
+    ) {
+        let mut delay = backoff.min;
+
+        loop {
+            let events_before = streams.events(index);
+
+            // Running the stream in its own task turns a panic into an error, dropping the set
+            // aborts it when the supervisor is shut down
+            let mut attempt = JoinSet::new();
+            attempt.spawn(Self::wait_for_processor_payments(
+                Arc::clone(&processor),
+                index,
+                streams.clone(),
+                Arc::clone(&localstore),
+                Arc::clone(&pubsub_manager),
+            ));
+
+            let reason = match attempt.join_next().await {
+                Some(Ok(Ok(()))) => "payment stream ended".to_string(),
+                Some(Ok(Err(e))) => e.to_string(),
+                Some(Err(join_error)) if join_error.is_panic() => {
+                    format!("payment stream panicked: {}", panic_message(join_error))
+                }
+                Some(Err(join_error)) => join_error.to_string(),
+                None => "payment stream task missing".to_string(),
+            };
+
+            if streams.events(index) != events_before {
+                delay = backoff.min;
+            }
+
+            tracing::error!(
+                "Payment stream of {:?} stopped: {}, restarting in {:?}",
+                keys,
+                reason,
+                delay
+            );
+
+            streams.update(index, |status| {
+                status.connected = false;
+                status.restarts += 1;
+                status.last_error = Some(reason);
+            });
+
+            tokio::time::sleep(delay).await;
+            delay = (delay * 2).min(backoff.max);
+        }
+    }
+
+    /// Handle the payment events of a processor until its stream ends
+    #[instrument(skip_all)]
+    async fn wait_for_processor_payments(
+        processor: DynMintPayment,
+        index: usize,
+        streams: PaymentStreams,
+        localstore: DynMintDatabase,
+        pubsub_manager: Arc<PubSubManager>,
+    ) -> Result<(), Error> {
+        let mut stream = processor.wait_payment_event().await?;
+        streams.update(index, |status| status.connected = true);
+
+        while let Some(event) = stream.next().await {
+            streams.update(index, |status| {
+                status.events += 1;
+                status.last_event = Some(unix_time());
+            });
+
+            match event {
+                Event::PaymentReceived(wait_payment_response) => {
+                    if let Err(e) = Self::handle_payment_notification(
+                        &localstore,
+                        &pubsub_manager,
+                        wait_payment_response,
+                    )
+                    .await
+                    {
+                        tracing::warn!("Payment notification error: {:?}", e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::pin::Pin;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use async_trait::async_trait;
+    use cdk_common::nuts::{CurrencyUnit, PaymentMethod};
+    use cdk_common::payment::{
+        self, CreateIncomingPaymentResponse, IncomingPaymentOptions, MakePaymentResponse,
+        MintPayment, OutgoingPaymentOptions, PaymentIdentifier, PaymentQuoteResponse,
+        SettingsResponse, WaitPaymentResponse,
+    };
+    use cdk_common::Amount;
+    use futures::{stream, Stream};
+
+    use super::*;
+    use crate::test_helpers::mint::create_test_mint;
+
+    /// Payment backend whose stream ends after a few events
+    struct FlakyPaymentProcessor {
+        events: usize,
+        /// Whether the stream stays open after the events
+        healthy: bool,
+        /// Open the stream this many times before panicking instead
+        panic_after: Option<usize>,
+        opened: AtomicUsize,
+    }
+
+    impl FlakyPaymentProcessor {
+        fn new(events: usize, healthy: bool, panic_after: Option<usize>) -> Self {
+            Self {
+                events,
+                healthy,
+                panic_after,
+                opened: AtomicUsize::new(0),
+            }
+        }
+    }
+
+    #[async_trait]
+    impl MintPayment for FlakyPaymentProcessor {
+        type Err = payment::Error;
+
+        async fn get_settings(&self) -> Result<SettingsResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn create_incoming_payment_request(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: IncomingPaymentOptions,
+        ) -> Result<CreateIncomingPaymentResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn get_payment_quote(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: OutgoingPaymentOptions,
+        ) -> Result<PaymentQuoteResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn make_payment(
+            &self,
+            _unit: &CurrencyUnit,
+            _options: OutgoingPaymentOptions,
+        ) -> Result<MakePaymentResponse, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn wait_payment_event(
+            &self,
+        ) -> Result<Pin<Box<dyn Stream<Item = Event> + Send>>, Self::Err> {
+            let opened = self.opened.fetch_add(1, Ordering::SeqCst);
+            if self.panic_after.is_some_and(|n| opened >= n) {
+                panic!("backend exploded");
+            }
+
+            let events = stream::iter((0..self.events).map(move |i| {
+                Event::PaymentReceived(WaitPaymentResponse {
+                    payment_identifier: PaymentIdentifier::CustomId(format!("{opened}-{i}")),
+                    payment_amount: Amount::new(1, CurrencyUnit::Sat),
+                    payment_id: format!("{opened}-{i}"),
+                })
+            }));
+
+            if self.healthy {
+                Ok(Box::pin(events.chain(stream::pending())))
+            } else {
+                Ok(Box::pin(events))
+            }
+        }
+
+        fn is_wait_invoice_active(&self) -> bool {
+            false
+        }
+
+        fn cancel_wait_invoice(&self) {}
+
+        async fn check_incoming_payment_status(
+            &self,
+            _payment_identifier: &PaymentIdentifier,
+        ) -> Result<Vec<WaitPaymentResponse>, Self::Err> {
+            unimplemented!()
+        }
+
+        async fn check_outgoing_payment(
+            &self,
+            _payment_identifier: &PaymentIdentifier,
+        ) -> Result<MakePaymentResponse, Self::Err> {
+            unimplemented!()
+        }
+    }
+
+    async fn run_backends(processors: Vec<(CurrencyUnit, Arc<FlakyPaymentProcessor>)>) -> Mint {
+        let mint = create_test_mint().await.unwrap();
+
+        let processors: HashMap<PaymentProcessorKey, DynMintPayment> = processors
+            .into_iter()
+            .map(|(unit, processor)| {
+                (
+                    PaymentProcessorKey::new(unit, PaymentMethod::BOLT11),
+                    processor as DynMintPayment,
+                )
+            })
+            .collect();
+
+        let shutdown = Arc::new(Notify::new());
+        let handle = tokio::spawn(Mint::wait_for_paid_invoices(
+            Arc::new(processors),
+            mint.localstore(),
+            mint.pubsub_manager(),
+            Arc::clone(&shutdown),
+            mint.payment_streams.clone(),
+            PaymentStreamBackoff {
+                min: Duration::from_millis(10),
+                max: Duration::from_millis(20),
+            },
+        ));
+
+        tokio::time::sleep(Duration::from_millis(300)).await;
+
+        shutdown.notify_waiters();
+        handle.await.unwrap().unwrap();
+
+        mint
+    }
+
+    fn status_of(mint: &Mint, unit: CurrencyUnit) -> PaymentStreamStatus {
+        mint.payment_stream_status()
+            .into_iter()
+            .find(|status| status.keys.iter().any(|key| key.unit == unit))
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn failing_stream_is_restarted_without_affecting_others() {
+        let flaky = Arc::new(FlakyPaymentProcessor::new(2, false, None));
+        let healthy = Arc::new(FlakyPaymentProcessor::new(3, true, None));
+
+        let mint = run_backends(vec![
+            (CurrencyUnit::Sat, Arc::clone(&flaky)),
+            (CurrencyUnit::Usd, Arc::clone(&healthy)),
+        ])
+        .await;
+
+        let flaky_status = status_of(&mint, CurrencyUnit::Sat);
+        assert!(flaky_status.restarts >= 2, "{flaky_status:?}");
+        assert!(flaky_status.events >= 6);
+        assert!(flaky_status.last_event.is_some());
+        assert_eq!(
+            flaky_status.last_error.as_deref(),
+            Some("payment stream ended")
+        );
+        assert!(flaky.opened.load(Ordering::SeqCst) >= 3);
+
+        let healthy_status = status_of(&mint, CurrencyUnit::Usd);
+        assert_eq!(healthy_status.restarts, 0);
+        assert!(healthy_status.connected);
+        assert_eq!(healthy_status.events, 3);
+        assert!(healthy_status.last_event.is_some());
+        assert_eq!(healthy.opened.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn panicking_stream_is_restarted_without_affecting_others() {
+        let panicking = Arc::new(FlakyPaymentProcessor::new(1, false, Some(1)));
+        let healthy = Arc::new(FlakyPaymentProcessor::new(1, true, None));
+
+        let mint = run_backends(vec![
+            (CurrencyUnit::Sat, Arc::clone(&panicking)),
+            (CurrencyUnit::Usd, Arc::clone(&healthy)),
+        ])
+        .await;
+
+        let panicking_status = status_of(&mint, CurrencyUnit::Sat);
+        assert!(panicking_status.restarts >= 2, "{panicking_status:?}");
+        assert!(!panicking_status.connected);
+        assert_eq!(
+            panicking_status.last_error.as_deref(),
+            Some("payment stream panicked: backend exploded")
+        );
+        // The events of the first stream were handled before the panics
+        assert_eq!(panicking_status.events, 1);
+
+        let healthy_status = status_of(&mint, CurrencyUnit::Usd);
+        assert_eq!(healthy_status.restarts, 0);
+        assert!(healthy_status.connected);
+    }
+}
@@ -0,0 +1,54 @@
+//! Response cache control
+//!
+//! The HTTP server may cache responses to identical requests (NUT-19). The mint holds a handle
+//! to that cache so operators can inspect and clear it, and so it is cleared when keysets
+//! change.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+/// Responses to remove from a [`ResponseCache`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResponseCacheScope {
+    /// All cached responses
+    All,
+    /// Responses of an endpoint, by path (e.g. `/v1/swap`)
+    Endpoint(String),
+    /// Response to a single request, by hex encoded request hash
+    Request(String),
+}
+
+/// Statistics of a [`ResponseCache`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResponseCacheStats {
+    /// Number of cached responses, if the backend can tell
+    pub entries: Option<u64>,
+    /// Size of the cached responses in bytes, if the backend can tell
+    pub size_bytes: Option<u64>,
+    /// Requests answered from the cache
+    pub hits: u64,
+    /// Requests not found in the cache
+    pub misses: u64,
+}
+
+impl ResponseCacheStats {
+    /// Share of requests answered from the cache, `None` before the first request
+    pub fn hit_rate(&self) -> Option<f64> {
+        let total = self.hits + self.misses;
+        (total > 0).then(|| self.hits as f64 / total as f64)
+    }
+}
+
+/// Cache of responses served by the mint
+#[async_trait]
+pub trait ResponseCache {
+    /// Current statistics
+    async fn stats(&self) -> ResponseCacheStats;
+
+    /// Remove the responses in `scope`
+    async fn clear(&self, scope: &ResponseCacheScope);
+}
+
+/// Shared response cache handle
+pub type DynResponseCache = Arc<dyn ResponseCache + Send + Sync>;
@@ -58,6 +58,10 @@ pub(crate) async fn process_melt_saga_outcome(
             let mut tx = db.begin_transaction().await?;
             tx.delete_saga(&saga.operation_id).await?;
             tx.commit().await?;
+
+            quote.state = MeltQuoteState::Paid;
+            quote.payment_preimage = payment_response.payment_proof.clone();
+            quote.amount_spent = payment_response.total_spent.convert_to(&quote.unit).ok();
         }
         MeltQuoteState::Unpaid | MeltQuoteState::Failed => {
             tracing::info!(
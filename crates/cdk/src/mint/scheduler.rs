@@ -0,0 +1,402 @@
+//! Scheduling of the mint's background tasks
+//!
+//! Background tasks are registered on a [`TaskScheduler`] by name, either as periodic tasks run
+//! every interval plus a random jitter, or as tasks running until shutdown. Every run is spawned
+//! on its own, so a run that fails or panics is logged and recorded as the last error of its task
+//! without stopping the task or its siblings. All tasks share one shutdown signal, and shutting
+//! the scheduler down waits for all of them to stop.
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use cdk_common::util::unix_time;
+use tokio::sync::Notify;
+use tokio::task::{JoinError, JoinSet};
+
+use crate::Error;
+
+/// Interval between runs of the pending mint quote check
+pub const PENDING_MINT_QUOTE_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+/// Interval between sweeps of expired client bans
+pub const BAN_LIST_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+/// Interval between checks for melt quotes stuck pending
+pub const STUCK_MELT_QUOTE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// When a periodic task runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TaskSchedule {
+    /// Time between the end of a run and the start of the next one
+    pub interval: Duration,
+    /// Upper bound of the random delay added to each interval
+    pub jitter: Duration,
+}
+
+impl TaskSchedule {
+    /// Schedule with a jitter of a tenth of `interval`
+    pub fn every(interval: Duration) -> Self {
+        Self {
+            interval,
+            jitter: interval / 10,
+        }
+    }
+
+    /// Delay before the next run
+    fn next_delay(&self) -> Duration {
+        let jitter_ms = self.jitter.as_millis() as u64;
+        if jitter_ms == 0 {
+            return self.interval;
+        }
+
+        let mut bytes = [0u8; 8];
+        if getrandom::getrandom(&mut bytes).is_err() {
+            return self.interval;
+        }
+
+        self.interval + Duration::from_millis(u64::from_le_bytes(bytes) % (jitter_ms + 1))
+    }
+}
+
+/// Status of a background task
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackgroundTaskStatus {
+    /// Name of the task
+    pub name: String,
+    /// Time between runs, `None` for a task running until shutdown
+    pub interval: Option<Duration>,
+    /// Whether the task is running
+    pub running: bool,
+    /// Number of completed runs
+    pub runs: u64,
+    /// Unix time the last run ended
+    pub last_run: Option<u64>,
+    /// Why the last failed run failed
+    pub last_error: Option<String>,
+}
+
+/// Status of the background tasks, one entry per task
+#[derive(Debug, Clone, Default)]
+pub(crate) struct BackgroundTasks {
+    tasks: Arc<RwLock<Vec<BackgroundTaskStatus>>>,
+}
+
+impl BackgroundTasks {
+    fn register(&self, name: &str, interval: Option<Duration>) -> usize {
+        let mut tasks = self.tasks.write().unwrap_or_else(|e| e.into_inner());
+        tasks.push(BackgroundTaskStatus {
+            name: name.to_string(),
+            interval,
+            running: true,
+            runs: 0,
+            last_run: None,
+            last_error: None,
+        });
+        tasks.len() - 1
+    }
+
+    fn update(&self, index: usize, f: impl FnOnce(&mut BackgroundTaskStatus)) {
+        let mut tasks = self.tasks.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(status) = tasks.get_mut(index) {
+            f(status);
+        }
+    }
+
+    fn clear(&self) {
+        self.tasks
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .clear();
+    }
+
+    /// Snapshot of the status of all tasks
+    pub(crate) fn snapshot(&self) -> Vec<BackgroundTaskStatus> {
+        self.tasks.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+}
+
+/// Message of a panicked task
+pub(crate) fn panic_message(join_error: JoinError) -> String {
+    if !join_error.is_panic() {
+        return join_error.to_string();
+    }
+
+    let panic = join_error.into_panic();
+    panic
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| panic.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| "unknown panic".to_string())
+}
+
+/// Runs the background tasks of a started mint until it is stopped
+pub(crate) struct TaskScheduler {
+    shutdown: Arc<Notify>,
+    stopping: Arc<AtomicBool>,
+    tasks: JoinSet<Result<(), Error>>,
+    status: BackgroundTasks,
+}
+
+impl TaskScheduler {
+    /// Scheduler reporting to `status`, which is cleared of the tasks of a previous run
+    pub(crate) fn new(status: BackgroundTasks) -> Self {
+        status.clear();
+
+        Self {
+            shutdown: Arc::new(Notify::new()),
+            stopping: Arc::new(AtomicBool::new(false)),
+            tasks: JoinSet::new(),
+            status,
+        }
+    }
+
+    /// Signal notified when the scheduler shuts down
+    ///
+    /// Tasks spawned with [`TaskScheduler::spawn`] have to return once it is notified.
+    pub(crate) fn shutdown_signal(&self) -> Arc<Notify> {
+        Arc::clone(&self.shutdown)
+    }
+
+    /// Run `task` until it returns
+    ///
+    /// An error or panic of the task is recorded and returned by [`TaskScheduler::shutdown`].
+    pub(crate) fn spawn<F>(&mut self, name: &str, task: F)
+    where
+        F: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let index = self.status.register(name, None);
+        let status = self.status.clone();
+        let name = name.to_string();
+
+        self.tasks.spawn(async move {
+            let (result, error) = match tokio::spawn(task).await {
+                Ok(Ok(())) => (Ok(()), None),
+                Ok(Err(err)) => {
+                    tracing::error!("Background task {} failed: {}", name, err);
+                    let message = err.to_string();
+                    (Err(err), Some(message))
+                }
+                Err(join_error) => {
+                    let message = panic_message(join_error);
+                    tracing::error!("Background task {} panicked: {}", name, message);
+                    (Err(Error::Internal), Some(format!("panicked: {message}")))
+                }
+            };
+
+            status.update(index, |status| {
+                status.running = false;
+                status.runs += 1;
+                status.last_run = Some(unix_time());
+                status.last_error = error;
+            });
+
+            result
+        });
+    }
+
+    /// Run `task` on `schedule` until shutdown
+    ///
+    /// The first run starts after the first interval. A run in progress when the scheduler shuts
+    /// down is completed.
+    pub(crate) fn spawn_periodic<F, Fut>(&mut self, name: &str, schedule: TaskSchedule, task: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<(), Error>> + Send + 'static,
+    {
+        let index = self.status.register(name, Some(schedule.interval));
+        let status = self.status.clone();
+        let shutdown = Arc::clone(&self.shutdown);
+        let stopping = Arc::clone(&self.stopping);
+        let name = name.to_string();
+
+        self.tasks.spawn(async move {
+            let shutdown_signal = shutdown.notified();
+            tokio::pin!(shutdown_signal);
+            shutdown_signal.as_mut().enable();
+
+            while !stopping.load(Ordering::Acquire) {
+                tokio::select! {
+                    _ = &mut shutdown_signal => break,
+                    _ = tokio::time::sleep(schedule.next_delay()) => {}
+                }
+
+                tracing::debug!("Running background task {}", name);
+
+                let error = match tokio::spawn(task()).await {
+                    Ok(Ok(())) => None,
+                    Ok(Err(err)) => {
+                        tracing::warn!("Background task {} failed: {}", name, err);
+                        Some(err.to_string())
+                    }
+                    Err(join_error) => {
+                        let message = panic_message(join_error);
+                        tracing::error!("Background task {} panicked: {}", name, message);
+                        Some(format!("panicked: {message}"))
+                    }
+                };
+
+                status.update(index, |status| {
+                    status.runs += 1;
+                    status.last_run = Some(unix_time());
+                    if error.is_some() {
+                        status.last_error = error;
+                    }
+                });
+            }
+
+            status.update(index, |status| status.running = false);
+
+            Ok(())
+        });
+    }
+
+    /// Signal all tasks to stop and wait until they did
+    ///
+    /// Returns the first error of a task that failed or panicked.
+    pub(crate) async fn shutdown(mut self) -> Result<(), Error> {
+        self.stopping.store(true, Ordering::Release);
+        self.shutdown.notify_waiters();
+
+        let mut result = Ok(());
+        while let Some(joined) = self.tasks.join_next().await {
+            let task_result = joined.unwrap_or_else(|join_error| {
+                tracing::error!("Background task panicked: {:?}", join_error);
+                Err(Error::Internal)
+            });
+
+            if result.is_ok() {
+                result = task_result;
+            }
+        }
+
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicU64;
+
+    use super::*;
+
+    fn schedule() -> TaskSchedule {
+        TaskSchedule {
+            interval: Duration::from_millis(10),
+            jitter: Duration::from_millis(5),
+        }
+    }
+
+    fn status_of(status: &BackgroundTasks, name: &str) -> BackgroundTaskStatus {
+        status
+            .snapshot()
+            .into_iter()
+            .find(|status| status.name == name)
+            .unwrap()
+    }
+
+    #[test]
+    fn jitter_stays_within_bounds() {
+        let schedule = schedule();
+        for _ in 0..100 {
+            let delay = schedule.next_delay();
+            assert!(delay >= schedule.interval);
+            assert!(delay <= schedule.interval + schedule.jitter);
+        }
+
+        let without_jitter = TaskSchedule {
+            interval: Duration::from_secs(1),
+            jitter: Duration::ZERO,
+        };
+        assert_eq!(without_jitter.next_delay(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn tasks_stop_on_shutdown() {
+        let status = BackgroundTasks::default();
+        let mut scheduler = TaskScheduler::new(status.clone());
+
+        let runs = Arc::new(AtomicU64::new(0));
+        scheduler.spawn_periodic("counter", schedule(), {
+            let runs = Arc::clone(&runs);
+            move || {
+                let runs = Arc::clone(&runs);
+                async move {
+                    runs.fetch_add(1, Ordering::SeqCst);
+                    Ok(())
+                }
+            }
+        });
+
+        let shutdown = scheduler.shutdown_signal();
+        scheduler.spawn("until_shutdown", async move {
+            shutdown.notified().await;
+            Ok(())
+        });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(status.snapshot().iter().all(|status| status.running));
+
+        tokio::time::timeout(Duration::from_secs(5), scheduler.shutdown())
+            .await
+            .expect("tasks stop on shutdown")
+            .unwrap();
+
+        let stopped_at = runs.load(Ordering::SeqCst);
+        assert!(stopped_at > 0);
+        assert!(status.snapshot().iter().all(|status| !status.running));
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(runs.load(Ordering::SeqCst), stopped_at);
+    }
+
+    #[tokio::test]
+    async fn panics_do_not_stop_siblings() {
+        let status = BackgroundTasks::default();
+        let mut scheduler = TaskScheduler::new(status.clone());
+
+        scheduler.spawn_periodic("panicking", schedule(), || async {
+            panic!("boom");
+        });
+        scheduler.spawn_periodic("failing", schedule(), || async { Err(Error::Internal) });
+        scheduler.spawn_periodic("healthy", schedule(), || async { Ok(()) });
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        let panicking = status_of(&status, "panicking");
+        assert!(panicking.running);
+        assert!(panicking.runs > 1);
+        assert_eq!(panicking.last_error.as_deref(), Some("panicked: boom"));
+
+        let failing = status_of(&status, "failing");
+        assert!(failing.runs > 1);
+        assert_eq!(failing.last_error, Some(Error::Internal.to_string()));
+
+        let healthy = status_of(&status, "healthy");
+        assert!(healthy.runs > 1);
+        assert!(healthy.last_run.is_some());
+        assert_eq!(healthy.last_error, None);
+
+        scheduler.shutdown().await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn task_panic_is_returned_on_shutdown() {
+        let status = BackgroundTasks::default();
+        let mut scheduler = TaskScheduler::new(status.clone());
+
+        scheduler.spawn("panicking", async { panic!("supervisor down") });
+        scheduler.spawn_periodic("healthy", schedule(), || async { Ok(()) });
+
+        tokio::time::sleep(Duration::from_millis(50)).await;
+
+        let panicking = status_of(&status, "panicking");
+        assert!(!panicking.running);
+        assert_eq!(
+            panicking.last_error.as_deref(),
+            Some("panicked: supervisor down")
+        );
+        assert!(status_of(&status, "healthy").running);
+
+        assert!(matches!(scheduler.shutdown().await, Err(Error::Internal)));
+    }
+}
@@ -3,16 +3,94 @@
 //! These checks are need in the case the mint was offline and the lightning node was node.
 //! These ensure that the status of the mint or melt quote matches in the mint db and on the node.
 
+use std::ops::AddAssign;
 use std::str::FromStr;
 
-use cdk_common::mint::{OperationKind, Saga};
-use cdk_common::QuoteId;
+use cdk_common::mint::{MeltPaymentRequest, OperationKind, Saga};
+use cdk_common::payment::KEYSEND_METHOD;
+use cdk_common::util::unix_time;
+use cdk_common::{MintQuoteState, QuoteId};
+use futures::stream::{self, StreamExt};
 
 use super::{Error, Mint};
 use crate::mint::swap::swap_saga::compensation::{CompensatingAction, RemoveSwapSetup};
-use crate::mint::{MeltQuote, MeltQuoteState};
+use crate::mint::{MeltQuote, MeltQuoteState, MintQuote};
 use crate::types::PaymentProcessorKey;
 
+/// Default number of quotes checked against the payment backend at once during startup
+pub const DEFAULT_STARTUP_CHECK_CONCURRENCY: usize = 8;
+
+/// Configuration for the startup checks run by [`Mint::start`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StartupCheckConfig {
+    /// Maximum number of concurrent payment backend lookups
+    pub concurrency: usize,
+    /// Run the backend reconciliation in a background task instead of before
+    /// [`Mint::start`] returns, so the mint can serve requests immediately
+    pub defer: bool,
+}
+
+impl Default for StartupCheckConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: DEFAULT_STARTUP_CHECK_CONCURRENCY,
+            defer: false,
+        }
+    }
+}
+
+impl StartupCheckConfig {
+    /// Concurrency limit, never less than one
+    pub fn concurrency(&self) -> usize {
+        self.concurrency.max(1)
+    }
+}
+
+/// Result of checking a single quote or saga at startup
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum StartupCheckOutcome {
+    /// The payment completed and the quote was settled
+    Settled,
+    /// The payment failed or was never sent and the quote was reset
+    Reset,
+    /// Nothing changed, the quote is still pending or was already final
+    Unchanged,
+}
+
+/// Summary of a startup check pass
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StartupCheckSummary {
+    /// Number of quotes or sagas checked
+    pub checked: usize,
+    /// Number of quotes settled as paid
+    pub settled: usize,
+    /// Number of quotes reset after a failed or missing payment
+    pub reset: usize,
+    /// Number of checks that returned an error
+    pub failed: usize,
+}
+
+impl StartupCheckSummary {
+    fn record(&mut self, result: Result<StartupCheckOutcome, Error>) {
+        self.checked += 1;
+        match result {
+            Ok(StartupCheckOutcome::Settled) => self.settled += 1,
+            Ok(StartupCheckOutcome::Reset) => self.reset += 1,
+            Ok(StartupCheckOutcome::Unchanged) => (),
+            Err(_) => self.failed += 1,
+        }
+    }
+}
+
+impl AddAssign for StartupCheckSummary {
+    fn add_assign(&mut self, other: Self) {
+        self.checked += other.checked;
+        self.settled += other.settled;
+        self.reset += other.reset;
+        self.failed += other.failed;
+    }
+}
+
 impl Mint {
     /// Get incomplete melt saga by quote_id
     async fn get_melt_saga_by_quote_id(&self, quote_id: &str) -> Result<Option<Saga>, Error> {
@@ -206,7 +284,7 @@ impl Mint {
     /// 3. Recovery compensated (returned proofs) instead of finalizing
     ///
     /// Now we check the LN backend payment status before deciding whether to compensate or finalize.
-    pub async fn recover_from_incomplete_melt_sagas(&self) -> Result<(), Error> {
+    pub async fn recover_from_incomplete_melt_sagas(&self) -> Result<StartupCheckSummary, Error> {
         let incomplete_sagas = self
             .localstore
             .get_incomplete_sagas(OperationKind::Melt)
@@ -214,367 +292,427 @@ impl Mint {
 
         if incomplete_sagas.is_empty() {
             tracing::info!("No incomplete melt sagas found to recover.");
-            return Ok(());
+            return Ok(StartupCheckSummary::default());
         }
 
         let total_sagas = incomplete_sagas.len();
-        tracing::info!("Found {} incomplete melt sagas to recover.", total_sagas);
+        tracing::info!(
+            "Found {} incomplete melt sagas to recover (concurrency {}).",
+            total_sagas,
+            self.startup_check_config.concurrency()
+        );
 
-        for saga in incomplete_sagas {
-            tracing::info!(
-                "Recovering melt saga {} in state '{}' (created: {}, updated: {})",
-                saga.operation_id,
-                saga.state.state(),
-                saga.created_at,
-                saga.updated_at
-            );
+        let summary = stream::iter(incomplete_sagas)
+            .map(|saga| async move {
+                let operation_id = saga.operation_id;
+                (operation_id, self.recover_melt_saga(saga).await)
+            })
+            .buffer_unordered(self.startup_check_config.concurrency())
+            .fold(
+                StartupCheckSummary::default(),
+                |mut summary, (operation_id, result)| async move {
+                    summary.record(result.inspect_err(|err| {
+                        tracing::error!(
+                            "Failed to recover melt saga {}: {}. Will retry on next recovery cycle.",
+                            operation_id,
+                            err
+                        );
+                    }));
+                    summary
+                },
+            )
+            .await;
 
-            // Look up input_ys and blinded_secrets from the proof and blind_signature tables
-            let input_ys = self
-                .localstore
-                .get_proof_ys_by_operation_id(&saga.operation_id)
-                .await?;
-            let blinded_secrets = self
-                .localstore
-                .get_blinded_secrets_by_operation_id(&saga.operation_id)
-                .await?;
+        tracing::info!(
+            "Melt saga recovery complete: {} checked, {} settled, {} reset, {} failed.",
+            summary.checked,
+            summary.settled,
+            summary.reset,
+            summary.failed
+        );
 
-            // Get quote_id from saga (new field added for efficient lookup)
-            let quote_id = match saga.quote_id {
-                Some(ref qid) => qid.clone(),
-                None => {
-                    tracing::warn!(
-                        "Saga {} has no quote_id (old saga format) - attempting fallback lookup",
-                        saga.operation_id
-                    );
+        Ok(summary)
+    }
 
-                    // Fallback: Find quote by matching input_ys (for backward compatibility)
-                    let melt_quotes = match self.localstore.get_melt_quotes().await {
-                        Ok(quotes) => quotes,
-                        Err(e) => {
-                            tracing::error!(
-                                "Failed to get melt quotes for saga {}: {}",
-                                saga.operation_id,
-                                e
-                            );
-                            continue;
-                        }
-                    };
+    /// Recover a single incomplete melt saga
+    ///
+    /// Errors are returned to the caller instead of aborting the whole recovery
+    /// pass, so one unreachable backend or broken quote does not block the rest.
+    async fn recover_melt_saga(&self, saga: Saga) -> Result<StartupCheckOutcome, Error> {
+        tracing::info!(
+            "Recovering melt saga {} in state '{}' (created: {}, updated: {})",
+            saga.operation_id,
+            saga.state.state(),
+            saga.created_at,
+            saga.updated_at
+        );
 
-                    let mut quote_id_found = None;
-                    for quote in melt_quotes {
-                        let mut tx = self.localstore.begin_transaction().await?;
-                        let proof_ys = tx.get_proof_ys_by_quote_id(&quote.id).await?;
-                        tx.rollback().await?;
+        // Look up input_ys and blinded_secrets from the proof and blind_signature tables
+        let input_ys = self
+            .localstore
+            .get_proof_ys_by_operation_id(&saga.operation_id)
+            .await?;
+        let blinded_secrets = self
+            .localstore
+            .get_blinded_secrets_by_operation_id(&saga.operation_id)
+            .await?;
 
-                        if !input_ys.is_empty()
-                            && !proof_ys.is_empty()
-                            && input_ys.iter().any(|y| proof_ys.contains(y))
-                        {
-                            quote_id_found = Some(quote.id.clone());
-                            break;
-                        }
+        // Get quote_id from saga (new field added for efficient lookup)
+        let quote_id = match saga.quote_id {
+            Some(ref qid) => qid.clone(),
+            None => {
+                tracing::warn!(
+                    "Saga {} has no quote_id (old saga format) - attempting fallback lookup",
+                    saga.operation_id
+                );
+
+                // Fallback: Find quote by matching input_ys (for backward compatibility)
+                let melt_quotes = self.localstore.get_melt_quotes().await?;
+
+                let mut quote_id_found = None;
+                for quote in melt_quotes {
+                    let mut tx = self.localstore.begin_transaction().await?;
+                    let proof_ys = tx.get_proof_ys_by_quote_id(&quote.id).await?;
+                    tx.rollback().await?;
+
+                    if !input_ys.is_empty()
+                        && !proof_ys.is_empty()
+                        && input_ys.iter().any(|y| proof_ys.contains(y))
+                    {
+                        quote_id_found = Some(quote.id.clone());
+                        break;
                     }
+                }
 
-                    match quote_id_found {
-                        Some(qid) => qid.to_string(),
-                        None => {
-                            tracing::warn!(
-                                "Could not find quote_id for saga {} - may have been cleaned up already. Deleting orphaned saga.",
-                                saga.operation_id
-                            );
+                match quote_id_found {
+                    Some(qid) => qid.to_string(),
+                    None => {
+                        tracing::warn!(
+                            "Could not find quote_id for saga {} - may have been cleaned up already. Deleting orphaned saga.",
+                            saga.operation_id
+                        );
 
-                            let mut delete_tx = self.localstore.begin_transaction().await?;
-                            if let Err(e) = delete_tx.delete_saga(&saga.operation_id).await {
-                                tracing::error!(
-                                    "Failed to delete orphaned saga {}: {}",
-                                    saga.operation_id,
-                                    e
-                                );
-                                delete_tx.rollback().await?;
-                            } else {
-                                delete_tx.commit().await?;
-                            }
-                            continue;
-                        }
+                        self.delete_orphaned_saga(&saga).await?;
+                        return Ok(StartupCheckOutcome::Unchanged);
                     }
                 }
-            };
+            }
+        };
 
-            // Get the quote from database
-            let quote_id_parsed = match QuoteId::from_str(&quote_id) {
-                Ok(id) => id,
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to parse quote_id '{}' for saga {}: {:?}. Skipping saga.",
-                        quote_id,
-                        saga.operation_id,
-                        e
-                    );
-                    continue;
-                }
-            };
+        // Get the quote from database
+        let quote_id_parsed = QuoteId::from_str(&quote_id).map_err(|e| {
+            tracing::error!(
+                "Failed to parse quote_id '{}' for saga {}: {:?}. Skipping saga.",
+                quote_id,
+                saga.operation_id,
+                e
+            );
+            Error::UnknownQuote
+        })?;
 
-            let mut quote = match self.localstore.get_melt_quote(&quote_id_parsed).await {
-                Ok(Some(q)) => q,
-                Ok(None) => {
-                    tracing::warn!(
-                        "Quote {} for saga {} not found - may have been cleaned up. Deleting orphaned saga.",
-                        quote_id,
-                        saga.operation_id
-                    );
+        let mut quote = match self.localstore.get_melt_quote(&quote_id_parsed).await? {
+            Some(q) => q,
+            None => {
+                tracing::warn!(
+                    "Quote {} for saga {} not found - may have been cleaned up. Deleting orphaned saga.",
+                    quote_id,
+                    saga.operation_id
+                );
 
-                    let mut delete_tx = self.localstore.begin_transaction().await?;
-                    if let Err(e) = delete_tx.delete_saga(&saga.operation_id).await {
-                        tracing::error!(
-                            "Failed to delete orphaned saga {}: {}",
-                            saga.operation_id,
-                            e
+                self.delete_orphaned_saga(&saga).await?;
+                return Ok(StartupCheckOutcome::Unchanged);
+            }
+        };
+
+        // Check saga state to determine if payment was attempted
+        // SetupComplete means setup transaction committed but payment NOT yet attempted
+        // PaymentAttempted means payment was attempted - must check LN backend
+        let should_compensate = match &saga.state {
+            cdk_common::mint::SagaStateEnum::Melt(state) => {
+                match state {
+                    cdk_common::mint::MeltSagaState::SetupComplete => {
+                        // Setup complete but payment never attempted - always compensate
+                        tracing::info!(
+                            "Saga {} in SetupComplete state - payment never attempted, will compensate",
+                            saga.operation_id
                         );
-                        delete_tx.rollback().await?;
-                    } else {
-                        delete_tx.commit().await?;
+                        true
                     }
-                    continue;
-                }
-                Err(e) => {
-                    tracing::error!(
-                        "Failed to get quote {} for saga {}: {}. Skipping saga.",
-                        quote_id,
-                        saga.operation_id,
-                        e
-                    );
-                    continue;
-                }
-            };
+                    cdk_common::mint::MeltSagaState::PaymentAttempted => {
+                        // Payment was attempted - check for internal settlement first, then LN backend
+                        tracing::info!(
+                            "Saga {} in PaymentAttempted state - checking for internal or external payment",
+                            saga.operation_id
+                        );
 
-            // Check saga state to determine if payment was attempted
-            // SetupComplete means setup transaction committed but payment NOT yet attempted
-            // PaymentAttempted means payment was attempted - must check LN backend
-            let should_compensate = match &saga.state {
-                cdk_common::mint::SagaStateEnum::Melt(state) => {
-                    match state {
-                        cdk_common::mint::MeltSagaState::SetupComplete => {
-                            // Setup complete but payment never attempted - always compensate
-                            tracing::info!(
-                                "Saga {} in SetupComplete state - payment never attempted, will compensate",
-                                saga.operation_id
-                            );
-                            true
-                        }
-                        cdk_common::mint::MeltSagaState::PaymentAttempted => {
-                            // Payment was attempted - check for internal settlement first, then LN backend
+                        // Check if this was an internal settlement by looking for a mint quote
+                        // that was paid by this melt quote
+                        let is_internal_settlement = match self
+                            .localstore
+                            .get_mint_quote_by_request(&quote.request.to_string())
+                            .await
+                        {
+                            Ok(Some(mint_quote)) => {
+                                // Check if this mint quote was paid by our melt quote
+                                let melt_quote_id_str = quote.id.to_string();
+                                mint_quote.payment_ids().contains(&&melt_quote_id_str)
+                            }
+                            Ok(None) => false,
+                            Err(e) => {
+                                tracing::warn!(
+                                    "Error checking for internal settlement for saga {}: {}",
+                                    saga.operation_id,
+                                    e
+                                );
+                                false
+                            }
+                        };
+
+                        if is_internal_settlement {
+                            // Internal settlement was completed - finalize directly
                             tracing::info!(
-                                "Saga {} in PaymentAttempted state - checking for internal or external payment",
+                                "Saga {} was internal settlement - will finalize directly",
                                 saga.operation_id
                             );
 
-                            // Check if this was an internal settlement by looking for a mint quote
-                            // that was paid by this melt quote
-                            let is_internal_settlement = match self
-                                .localstore
-                                .get_mint_quote_by_request(&quote.request.to_string())
-                                .await
-                            {
-                                Ok(Some(mint_quote)) => {
-                                    // Check if this mint quote was paid by our melt quote
-                                    let melt_quote_id_str = quote.id.to_string();
-                                    mint_quote.payment_ids().contains(&&melt_quote_id_str)
-                                }
-                                Ok(None) => false,
-                                Err(e) => {
-                                    tracing::warn!(
-                                        "Error checking for internal settlement for saga {}: {}",
-                                        saga.operation_id,
-                                        e
-                                    );
-                                    false
-                                }
-                            };
-
-                            if is_internal_settlement {
-                                // Internal settlement was completed - finalize directly
-                                tracing::info!(
-                                    "Saga {} was internal settlement - will finalize directly",
-                                    saga.operation_id
-                                );
-
-                                // Get payment info for finalization
-                                let total_spent = quote.amount();
-                                let payment_lookup_id =
-                                    quote.request_lookup_id.clone().unwrap_or_else(|| {
-                                        cdk_common::payment::PaymentIdentifier::CustomId(
-                                            quote.id.to_string(),
-                                        )
-                                    });
-
-                                if let Err(err) = self
-                                    .finalize_paid_melt_quote(
-                                        &quote,
-                                        total_spent,
-                                        None, // No preimage for internal settlement
-                                        &payment_lookup_id,
+                            // Get payment info for finalization
+                            let total_spent = quote.amount();
+                            let payment_lookup_id =
+                                quote.request_lookup_id.clone().unwrap_or_else(|| {
+                                    cdk_common::payment::PaymentIdentifier::CustomId(
+                                        quote.id.to_string(),
                                     )
-                                    .await
-                                {
-                                    tracing::error!(
-                                        "Failed to finalize internal settlement saga {}: {}. Will retry on next recovery cycle.",
-                                        saga.operation_id,
-                                        err
-                                    );
-                                    continue;
-                                }
-
-                                // Delete saga after successful finalization
-                                let mut tx = self.localstore.begin_transaction().await?;
-                                if let Err(e) = tx.delete_saga(&saga.operation_id).await {
-                                    tracing::error!(
-                                        "Failed to delete saga {}: {}. Will retry on next recovery cycle.",
-                                        saga.operation_id,
-                                        e
-                                    );
-                                    tx.rollback().await?;
-                                    continue;
-                                }
-                                tx.commit().await?;
-                                tracing::info!(
-                                    "Successfully recovered and finalized internal settlement saga {}",
-                                    saga.operation_id
-                                );
-
-                                continue; // Skip to next saga
+                                });
+
+                            self.finalize_paid_melt_quote(
+                                &quote,
+                                total_spent,
+                                None, // No preimage for internal settlement
+                                &payment_lookup_id,
+                            )
+                            .await?;
+
+                            // Delete saga after successful finalization
+                            let mut tx = self.localstore.begin_transaction().await?;
+                            if let Err(e) = tx.delete_saga(&saga.operation_id).await {
+                                tx.rollback().await?;
+                                return Err(e.into());
                             }
+                            tx.commit().await?;
+                            tracing::info!(
+                                "Successfully recovered and finalized internal settlement saga {}",
+                                saga.operation_id
+                            );
 
-                            false // Will check LN payment status below
+                            return Ok(StartupCheckOutcome::Settled);
                         }
-                    }
-                }
-                _ => {
-                    continue; // Skip non-melt sagas
-                }
-            };
-
-            let should_compensate = if should_compensate {
-                true
-            } else if quote.request_lookup_id.is_none() {
-                // Fallback: No request_lookup_id means payment likely never sent
-                tracing::info!(
-                    "Saga {} for quote {} has no request_lookup_id - payment never sent, will compensate",
-                    saga.operation_id,
-                    quote_id
-                );
-                true
-            } else {
-                // Payment was attempted - check LN backend status
-                tracing::info!(
-                    "Saga {} for quote {} has request_lookup_id - checking payment status with LN backend",
-                    saga.operation_id,
-                    quote_id
-                );
 
-                match self.check_melt_payment_status(&quote).await {
-                    Ok(payment_response) => {
-                        match payment_response.status {
-                            MeltQuoteState::Paid => {
-                                if let Err(err) = super::saga_recovery::process_melt_saga_outcome(
-                                    &saga,
-                                    &mut quote,
-                                    &payment_response,
-                                    &self.localstore,
-                                    &self.pubsub_manager,
-                                    self,
-                                )
-                                .await
-                                {
-                                    tracing::error!(
-                                        "Failed to process paid melt saga {}: {}. Will retry on next recovery cycle.",
-                                        saga.operation_id,
-                                        err
-                                    );
-                                    continue;
-                                }
-                                continue; // Saga handled
-                            }
-                            MeltQuoteState::Unpaid | MeltQuoteState::Failed => {
-                                if let Err(err) = super::saga_recovery::process_melt_saga_outcome(
-                                    &saga,
-                                    &mut quote,
-                                    &payment_response,
-                                    &self.localstore,
-                                    &self.pubsub_manager,
-                                    self,
-                                )
-                                .await
-                                {
-                                    tracing::error!(
-                                        "Failed to process failed melt saga {}: {}. Will retry on next recovery cycle.",
-                                        saga.operation_id,
-                                        err
-                                    );
-                                    continue;
-                                }
-                                continue; // Saga handled
-                            }
-                            MeltQuoteState::Pending | MeltQuoteState::Unknown => {
-                                // Payment still pending - skip for check_pending_melt_quotes
-                                tracing::info!(
-                                    "Saga {} for quote {} - payment {} on LN backend, skipping",
-                                    saga.operation_id,
-                                    quote_id,
-                                    payment_response.status
-                                );
-                                continue; // Skip this saga
-                            }
-                        }
-                    }
-                    Err(err) => {
-                        // LN backend unavailable - skip this saga, will retry on next recovery cycle
-                        tracing::warn!(
-                            "Failed to check payment status for saga {} quote {}: {}. Skipping for now, will retry on next recovery cycle.",
-                            saga.operation_id,
-                            quote_id,
-                            err
-                        );
-                        continue; // Skip this saga
+                        false // Will check LN payment status below
                     }
                 }
-            };
+            }
+            _ => {
+                return Ok(StartupCheckOutcome::Unchanged); // Skip non-melt sagas
+            }
+        };
 
-            // Compensate if needed
-            if should_compensate {
-                tracing::info!(
-                    "Compensating melt saga {} (removing {} proofs, {} change outputs)",
-                    saga.operation_id,
-                    input_ys.len(),
-                    blinded_secrets.len()
-                );
+        let should_compensate = if should_compensate {
+            true
+        } else if quote.request_lookup_id.is_none()
+            && matches!(
+                &quote.request,
+                MeltPaymentRequest::Custom { method, .. } if method == KEYSEND_METHOD
+            )
+        {
+            // A keysend quote created before the backend returned lookup ids can't be checked,
+            // the payment may have been sent
+            tracing::warn!(
+                "Saga {} for keysend quote {} has no request_lookup_id - payment status can't be checked, leaving it pending for manual review",
+                saga.operation_id,
+                quote_id
+            );
+            return Ok(StartupCheckOutcome::Unchanged);
+        } else if quote.request_lookup_id.is_none() {
+            // Fallback: No request_lookup_id means payment likely never sent
+            tracing::info!(
+                "Saga {} for quote {} has no request_lookup_id - payment never sent, will compensate",
+                saga.operation_id,
+                quote_id
+            );
+            true
+        } else {
+            // Payment was attempted - check LN backend status
+            tracing::info!(
+                "Saga {} for quote {} has request_lookup_id - checking payment status with LN backend",
+                saga.operation_id,
+                quote_id
+            );
 
-                if let Err(err) = super::melt::shared::rollback_melt_quote(
-                    &self.localstore,
-                    &self.pubsub_manager,
-                    &quote_id_parsed,
-                    &input_ys,
-                    &blinded_secrets,
-                    &saga.operation_id,
-                )
-                .await
-                {
-                    tracing::error!(
-                        "Failed to rollback melt quote {} for saga {}: {}",
-                        quote_id_parsed,
+            // LN backend unavailable - the saga is left in place for the next recovery cycle
+            let payment_response = self.check_melt_payment_status(&quote).await?;
+
+            match payment_response.status {
+                MeltQuoteState::Paid | MeltQuoteState::Unpaid | MeltQuoteState::Failed => {
+                    super::saga_recovery::process_melt_saga_outcome(
+                        &saga,
+                        &mut quote,
+                        &payment_response,
+                        &self.localstore,
+                        &self.pubsub_manager,
+                        self,
+                    )
+                    .await?;
+
+                    return Ok(if payment_response.status == MeltQuoteState::Paid {
+                        StartupCheckOutcome::Settled
+                    } else {
+                        StartupCheckOutcome::Reset
+                    });
+                }
+                MeltQuoteState::Pending | MeltQuoteState::Unknown => {
+                    // Payment still pending - leave it for the next recovery cycle
+                    tracing::info!(
+                        "Saga {} for quote {} - payment {} on LN backend, skipping",
                         saga.operation_id,
-                        err
+                        quote_id,
+                        payment_response.status
                     );
+                    return Ok(StartupCheckOutcome::Unchanged);
                 }
             }
+        };
+
+        // Compensate if needed
+        if should_compensate {
+            tracing::info!(
+                "Compensating melt saga {} (removing {} proofs, {} change outputs)",
+                saga.operation_id,
+                input_ys.len(),
+                blinded_secrets.len()
+            );
+
+            super::melt::shared::rollback_melt_quote(
+                &self.localstore,
+                &self.pubsub_manager,
+                &quote_id_parsed,
+                &input_ys,
+                &blinded_secrets,
+                &saga.operation_id,
+            )
+            .await?;
+
+            return Ok(StartupCheckOutcome::Reset);
+        }
+
+        Ok(StartupCheckOutcome::Unchanged)
+    }
+
+    /// Delete a saga whose quote no longer exists
+    async fn delete_orphaned_saga(&self, saga: &Saga) -> Result<(), Error> {
+        let mut delete_tx = self.localstore.begin_transaction().await?;
+        if let Err(e) = delete_tx.delete_saga(&saga.operation_id).await {
+            tracing::error!(
+                "Failed to delete orphaned saga {}: {}",
+                saga.operation_id,
+                e
+            );
+            delete_tx.rollback().await?;
+            return Err(e.into());
+        }
+        delete_tx.commit().await?;
+        Ok(())
+    }
+
+    /// Check unpaid, unexpired mint quotes against their payment backend
+    ///
+    /// Payments received while the mint was offline are not replayed by every
+    /// backend's payment stream, so each pending quote is looked up once at
+    /// startup. Lookups run concurrently, bounded by the configured limit.
+    pub async fn check_pending_mint_quotes(&self) -> Result<StartupCheckSummary, Error> {
+        let now = unix_time();
+        let pending_quotes: Vec<MintQuote> = self
+            .localstore
+            .get_mint_quotes()
+            .await?
+            .into_iter()
+            .filter(|quote| quote.state() == MintQuoteState::Unpaid && quote.expiry > now)
+            .collect();
+
+        if pending_quotes.is_empty() {
+            tracing::info!("No pending mint quotes to check.");
+            return Ok(StartupCheckSummary::default());
         }
 
         tracing::info!(
-            "Successfully recovered {} incomplete melt sagas.",
-            total_sagas
+            "Checking {} pending mint quotes with payment backends (concurrency {}).",
+            pending_quotes.len(),
+            self.startup_check_config.concurrency()
         );
 
-        Ok(())
+        let summary = stream::iter(pending_quotes)
+            .map(|mut quote| async move {
+                let amount_paid = quote.amount_paid();
+                let result = self.check_mint_quote_paid(&mut quote).await.map(|()| {
+                    if quote.amount_paid() > amount_paid {
+                        StartupCheckOutcome::Settled
+                    } else {
+                        StartupCheckOutcome::Unchanged
+                    }
+                });
+                (quote.id, result)
+            })
+            .buffer_unordered(self.startup_check_config.concurrency())
+            .fold(
+                StartupCheckSummary::default(),
+                |mut summary, (quote_id, result)| async move {
+                    summary.record(result.inspect_err(|err| {
+                        tracing::warn!("Failed to check pending mint quote {}: {}", quote_id, err);
+                    }));
+                    summary
+                },
+            )
+            .await;
+
+        tracing::info!(
+            "Pending mint quote check complete: {} checked, {} settled, {} failed.",
+            summary.checked,
+            summary.settled,
+            summary.failed
+        );
+
+        Ok(summary)
+    }
+
+    /// Run the startup checks that reconcile quote state with payment backends
+    ///
+    /// Failures are logged and never abort startup; whatever could not be
+    /// reconciled is retried on the next start.
+    pub async fn run_startup_checks(&self) -> StartupCheckSummary {
+        let mut summary = StartupCheckSummary::default();
+
+        // Recover from incomplete melt sagas
+        // This cleans up incomplete melt operations using persisted saga state
+        // Now includes checking payment status with LN backend to determine
+        // whether to finalize (if paid) or compensate (if failed/unpaid)
+        match self.recover_from_incomplete_melt_sagas().await {
+            Ok(melt_summary) => summary += melt_summary,
+            Err(e) => tracing::error!("Failed to recover incomplete melt sagas: {}", e),
+        }
+
+        match self.check_pending_mint_quotes().await {
+            Ok(mint_summary) => summary += mint_summary,
+            Err(e) => tracing::error!("Failed to check pending mint quotes: {}", e),
+        }
+
+        tracing::info!(
+            "Startup checks complete: {} checked, {} settled, {} reset, {} failed.",
+            summary.checked,
+            summary.settled,
+            summary.reset,
+            summary.failed
+        );
+
+        summary
     }
 
     /// Handle pending melt quote by resuming the saga
@@ -0,0 +1,357 @@
+//! Alerts for melt quotes stuck pending
+//!
+//! A melt quote is pending while its payment is in flight. A quote pending for longer than the
+//! alert threshold points at a stuck HTLC or at the mint and the payment backend disagreeing on
+//! the payment, which needs an operator. The periodic check flags such a quote once: it logs a
+//! warning, counts it in the metrics and sends a [`StuckQuoteEvent::Stuck`] to the subscribers.
+//! When a flagged quote leaves the pending state, by reconciliation with the backend or by an
+//! operator, a [`StuckQuoteEvent::Cleared`] is sent so alerts can resolve themselves.
+//!
+//! Flagged quotes are kept in memory, after a restart the quotes still pending are flagged again.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cdk_common::mint::OperationKind;
+use cdk_common::nuts::{CurrencyUnit, MeltQuoteState};
+use cdk_common::util::unix_time;
+use cdk_common::{Amount, QuoteId};
+#[cfg(feature = "prometheus")]
+use cdk_prometheus::global;
+use serde::Serialize;
+use tokio::sync::broadcast;
+use tracing::instrument;
+
+use super::Mint;
+use crate::Error;
+
+/// Events kept for subscribers that fall behind
+const EVENT_CHANNEL_CAPACITY: usize = 64;
+
+/// A melt quote pending for longer than the alert threshold
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StuckMeltQuote {
+    /// Id of the quote
+    pub quote_id: QuoteId,
+    /// Unit of the quote
+    pub unit: CurrencyUnit,
+    /// Amount of the quote
+    pub amount: Amount,
+    /// Id the payment backend knows the payment by, if known
+    pub request_lookup_id: Option<String>,
+    /// Unix time the melt started, or the quote was created if unknown
+    pub pending_since: u64,
+}
+
+/// Change of a stuck melt quote, sent to the subscribers of the mint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "event")]
+pub enum StuckQuoteEvent {
+    /// A melt quote has been pending for longer than the alert threshold
+    #[serde(rename = "melt.stuck")]
+    Stuck(StuckMeltQuote),
+    /// A melt quote flagged as stuck is no longer pending
+    #[serde(rename = "melt.stuck_cleared")]
+    Cleared {
+        /// Id of the quote
+        quote_id: QuoteId,
+        /// State the quote was resolved to
+        state: MeltQuoteState,
+    },
+}
+
+impl StuckQuoteEvent {
+    /// Type of the event, `melt.stuck` or `melt.stuck_cleared`
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            Self::Stuck(_) => "melt.stuck",
+            Self::Cleared { .. } => "melt.stuck_cleared",
+        }
+    }
+}
+
+/// Melt quotes flagged as stuck, disabled unless an alert threshold is configured
+#[derive(Debug, Clone)]
+pub(crate) struct StuckQuoteMonitor {
+    alert_after: Option<Duration>,
+    stuck: Arc<Mutex<HashMap<QuoteId, StuckMeltQuote>>>,
+    events: broadcast::Sender<StuckQuoteEvent>,
+}
+
+impl Default for StuckQuoteMonitor {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+impl StuckQuoteMonitor {
+    pub(crate) fn new(alert_after: Option<Duration>) -> Self {
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            alert_after,
+            stuck: Arc::new(Mutex::new(HashMap::new())),
+            events,
+        }
+    }
+
+    pub(crate) fn alert_after(&self) -> Option<Duration> {
+        self.alert_after
+    }
+
+    pub(crate) fn subscribe(&self) -> broadcast::Receiver<StuckQuoteEvent> {
+        self.events.subscribe()
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, HashMap<QuoteId, StuckMeltQuote>> {
+        // The flagged quotes stay consistent even if a holder panicked
+        self.stuck
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Quotes currently flagged as stuck, longest pending first
+    pub(crate) fn stuck(&self) -> Vec<StuckMeltQuote> {
+        let mut stuck: Vec<_> = self.state().values().cloned().collect();
+        stuck.sort_by_key(|quote| quote.pending_since);
+        stuck
+    }
+
+    /// Flag the `pending` quotes over the threshold and clear the flagged ones no longer pending
+    ///
+    /// `states` holds the current state of the quotes, for the cleared events.
+    pub(crate) fn update(
+        &self,
+        pending: Vec<StuckMeltQuote>,
+        states: &HashMap<QuoteId, MeltQuoteState>,
+        now: u64,
+    ) -> Vec<StuckQuoteEvent> {
+        let Some(alert_after) = self.alert_after else {
+            return Vec::new();
+        };
+
+        let mut stuck = self.state();
+        let mut events = Vec::new();
+
+        let still_pending: Vec<QuoteId> =
+            pending.iter().map(|quote| quote.quote_id.clone()).collect();
+        let cleared: Vec<QuoteId> = stuck
+            .keys()
+            .filter(|quote_id| !still_pending.contains(quote_id))
+            .cloned()
+            .collect();
+        for quote_id in cleared {
+            stuck.remove(&quote_id);
+            let state = states
+                .get(&quote_id)
+                .copied()
+                .unwrap_or(MeltQuoteState::Unknown);
+            tracing::info!("Melt quote {} is no longer stuck, now {}", quote_id, state);
+            events.push(StuckQuoteEvent::Cleared { quote_id, state });
+        }
+
+        for quote in pending {
+            if stuck.contains_key(&quote.quote_id)
+                || now.saturating_sub(quote.pending_since) < alert_after.as_secs()
+            {
+                continue;
+            }
+
+            tracing::warn!(
+                "Melt quote {} of {} {} has been pending for {}s",
+                quote.quote_id,
+                quote.amount,
+                quote.unit,
+                now.saturating_sub(quote.pending_since)
+            );
+
+            #[cfg(feature = "prometheus")]
+            global::record_stuck_melt_quote();
+
+            stuck.insert(quote.quote_id.clone(), quote.clone());
+            events.push(StuckQuoteEvent::Stuck(quote));
+        }
+
+        #[cfg(feature = "prometheus")]
+        global::set_stuck_melt_quotes(stuck.len() as i64);
+
+        for event in &events {
+            // Nobody listening is fine, the quotes can still be listed
+            let _ = self.events.send(event.clone());
+        }
+
+        events
+    }
+}
+
+impl Mint {
+    /// Flag the melt quotes pending for longer than the alert threshold as stuck
+    ///
+    /// Flagged quotes that are no longer pending are cleared. Returns the events sent to the
+    /// subscribers, see [`Mint::subscribe_stuck_melt_quotes`]. Does nothing unless an alert
+    /// threshold is configured.
+    #[instrument(skip_all)]
+    pub async fn check_stuck_melt_quotes(&self) -> Result<Vec<StuckQuoteEvent>, Error> {
+        if self.stuck_quotes.alert_after().is_none() {
+            return Ok(Vec::new());
+        }
+
+        // A pending quote is pending since its melt started
+        let melt_started: HashMap<String, u64> = self
+            .localstore
+            .get_incomplete_sagas(OperationKind::Melt)
+            .await?
+            .into_iter()
+            .filter_map(|saga| Some((saga.quote_id?, saga.created_at)))
+            .collect();
+
+        let quotes = self.localstore.get_melt_quotes().await?;
+        let states = quotes
+            .iter()
+            .map(|quote| (quote.id.clone(), quote.state))
+            .collect();
+        let pending = quotes
+            .into_iter()
+            .filter(|quote| quote.state == MeltQuoteState::Pending)
+            .map(|quote| StuckMeltQuote {
+                pending_since: melt_started
+                    .get(&quote.id.to_string())
+                    .copied()
+                    .unwrap_or(quote.created_time),
+                amount: Amount::from(quote.amount().value()),
+                request_lookup_id: quote.request_lookup_id.as_ref().map(|id| id.to_string()),
+                unit: quote.unit,
+                quote_id: quote.id,
+            })
+            .collect();
+
+        Ok(self.stuck_quotes.update(pending, &states, unix_time()))
+    }
+
+    /// Melt quotes currently flagged as stuck, longest pending first
+    pub fn stuck_melt_quotes(&self) -> Vec<StuckMeltQuote> {
+        self.stuck_quotes.stuck()
+    }
+
+    /// Subscribe to melt quotes being flagged as stuck and cleared
+    pub fn subscribe_stuck_melt_quotes(&self) -> broadcast::Receiver<StuckQuoteEvent> {
+        self.stuck_quotes.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALERT_AFTER: Duration = Duration::from_secs(600);
+    const NOW: u64 = 1_000_000;
+
+    fn pending(quote_id: &QuoteId, pending_for: u64) -> StuckMeltQuote {
+        StuckMeltQuote {
+            quote_id: quote_id.clone(),
+            unit: CurrencyUnit::Sat,
+            amount: Amount::from(100),
+            request_lookup_id: None,
+            pending_since: NOW - pending_for,
+        }
+    }
+
+    #[test]
+    fn quotes_are_flagged_once_past_the_threshold() {
+        let monitor = StuckQuoteMonitor::new(Some(ALERT_AFTER));
+        let mut events = monitor.subscribe();
+        let stuck_id = QuoteId::new_uuid();
+        let recent_id = QuoteId::new_uuid();
+
+        let flagged = monitor.update(
+            vec![pending(&stuck_id, 601), pending(&recent_id, 60)],
+            &HashMap::new(),
+            NOW,
+        );
+        assert_eq!(
+            flagged,
+            vec![StuckQuoteEvent::Stuck(pending(&stuck_id, 601))]
+        );
+        assert_eq!(events.try_recv().unwrap(), flagged[0]);
+        assert_eq!(monitor.stuck(), vec![pending(&stuck_id, 601)]);
+
+        // Still pending, not flagged again
+        let flagged = monitor.update(
+            vec![pending(&stuck_id, 601), pending(&recent_id, 60)],
+            &HashMap::new(),
+            NOW,
+        );
+        assert!(flagged.is_empty());
+        assert!(events.try_recv().is_err());
+    }
+
+    #[test]
+    fn resolved_quotes_are_cleared() {
+        let monitor = StuckQuoteMonitor::new(Some(ALERT_AFTER));
+        let paid_id = QuoteId::new_uuid();
+        let deleted_id = QuoteId::new_uuid();
+
+        monitor.update(
+            vec![pending(&paid_id, 700), pending(&deleted_id, 700)],
+            &HashMap::new(),
+            NOW,
+        );
+        assert_eq!(monitor.stuck().len(), 2);
+
+        let states = HashMap::from([(paid_id.clone(), MeltQuoteState::Paid)]);
+        let mut cleared = monitor.update(Vec::new(), &states, NOW);
+        cleared.sort_by_key(|event| match event {
+            StuckQuoteEvent::Cleared { state, .. } => *state == MeltQuoteState::Paid,
+            StuckQuoteEvent::Stuck(_) => false,
+        });
+
+        assert_eq!(
+            cleared,
+            vec![
+                StuckQuoteEvent::Cleared {
+                    quote_id: deleted_id,
+                    state: MeltQuoteState::Unknown,
+                },
+                StuckQuoteEvent::Cleared {
+                    quote_id: paid_id,
+                    state: MeltQuoteState::Paid,
+                },
+            ]
+        );
+        assert!(monitor.stuck().is_empty());
+    }
+
+    #[test]
+    fn disabled_without_threshold() {
+        let monitor = StuckQuoteMonitor::default();
+
+        let flagged = monitor.update(
+            vec![pending(&QuoteId::new_uuid(), 100_000)],
+            &HashMap::new(),
+            NOW,
+        );
+
+        assert!(flagged.is_empty());
+        assert!(monitor.stuck().is_empty());
+    }
+
+    #[test]
+    fn events_serialize_with_their_type() {
+        let quote_id = QuoteId::new_uuid();
+
+        let stuck = serde_json::to_value(StuckQuoteEvent::Stuck(pending(&quote_id, 700))).unwrap();
+        assert_eq!(stuck["event"], "melt.stuck");
+        assert_eq!(stuck["quote_id"], quote_id.to_string());
+        assert_eq!(stuck["pending_since"], NOW - 700);
+
+        let cleared = StuckQuoteEvent::Cleared {
+            quote_id,
+            state: MeltQuoteState::Failed,
+        };
+        assert_eq!(cleared.event_type(), "melt.stuck_cleared");
+        assert_eq!(
+            serde_json::to_value(cleared).unwrap()["event"],
+            "melt.stuck_cleared"
+        );
+    }
+}
@@ -3,11 +3,15 @@
 use std::collections::HashMap;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::time::Duration;
 
 use cdk_common::common::PaymentProcessorKey;
 use cdk_common::database::DynMintDatabase;
-use cdk_common::mint::MintQuote;
-use cdk_common::nut17::NotificationId;
+use cdk_common::mint::{InternalSettlement, MintQuote};
+use cdk_common::nut17::{
+    InternalSettlementNotification, MeltProgress, MeltProgressNotification, MintInfoNotification,
+    NotificationId,
+};
 use cdk_common::payment::DynMintPayment;
 use cdk_common::pub_sub::{Pubsub, Spec, Subscriber};
 use cdk_common::subscription::SubId;
@@ -19,6 +23,7 @@ use cdk_common::{
 
 use super::Mint;
 use crate::event::MintEvent;
+use crate::util::unix_time;
 
 /// Mint subtopics
 #[derive(Clone)]
@@ -96,6 +101,23 @@ impl MintPubSubSpec {
                         to_return.push(mint_quote);
                     }
                 }
+                NotificationId::InternalSettlement(uuid) => {
+                    if let Some(settlement) = self
+                        .db
+                        .get_internal_settlement(uuid)
+                        .await
+                        .map_err(|e| e.to_string())?
+                    {
+                        let settlement: InternalSettlementNotification<QuoteId> = settlement.into();
+                        to_return.push(settlement.into());
+                    }
+                }
+                NotificationId::MeltProgress(_) => {
+                    // Progress is not stored, the current state is served by the quote topic
+                }
+                NotificationId::MintInfo => {
+                    // Only changes are published, the current info is served by `/v1/info`
+                }
             }
         }
 
@@ -191,7 +213,8 @@ impl PubSubManager {
     pub fn mint_quote_payment(&self, mint_quote: &MintQuote, total_paid: Amount<CurrencyUnit>) {
         match mint_quote.payment_method {
             cdk_common::PaymentMethod::Known(cdk_common::nut00::KnownMethod::Bolt11) => {
-                self.mint_quote_bolt11_status(mint_quote.clone(), MintQuoteState::Paid);
+                // A partially paid quote stays unpaid
+                self.mint_quote_bolt11_status(mint_quote.clone(), mint_quote.state());
             }
             cdk_common::PaymentMethod::Known(cdk_common::nut00::KnownMethod::Bolt12) => {
                 self.mint_quote_bolt12_status(
@@ -249,6 +272,27 @@ impl PubSubManager {
         quote.change = change;
         self.publish(quote);
     }
+
+    /// Helper function to emit the progress of a melt while its payment is made
+    pub fn melt_progress(&self, quote_id: &QuoteId, progress: MeltProgress, elapsed: Duration) {
+        self.publish(MeltProgressNotification {
+            quote: quote_id.clone(),
+            progress,
+            elapsed_ms: u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX),
+            time: unix_time(),
+        });
+    }
+
+    /// Helper function to emit an internal settlement between a melt and a mint quote
+    pub fn internal_settlement(&self, settlement: InternalSettlement) {
+        let event: InternalSettlementNotification<QuoteId> = settlement.into();
+        self.publish(event);
+    }
+
+    /// Helper function to emit a change of the mint info or the active keysets
+    pub fn mint_info_change(&self, notification: MintInfoNotification) {
+        self.publish(notification);
+    }
 }
 
 impl Deref for PubSubManager {
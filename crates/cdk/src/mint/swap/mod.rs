@@ -22,6 +22,8 @@ impl Mint {
         #[cfg(feature = "prometheus")]
         METRICS.inc_in_flight_requests("process_swap_request");
 
+        self.check_request_limits(swap_request.inputs().len(), swap_request.outputs().len())?;
+
         swap_request.input_amount()?;
         swap_request.output_amount()?;
 
@@ -51,6 +53,21 @@ impl Mint {
             err
         })?;
 
+        // Reject a unit mismatch or wrong fee math before the inputs are marked pending
+        self.verify_outputs_balance_inputs(
+            &input_verification,
+            input_proofs,
+            swap_request.outputs(),
+        )
+        .await
+        .map_err(|err| {
+            #[cfg(feature = "prometheus")]
+            self.record_swap_failure("process_swap_request");
+
+            tracing::debug!("Swap is not balanced: {:?}", err);
+            err
+        })?;
+
         // Step 1: Initialize the swap saga
         let init_saga = SwapSaga::new(self, self.localstore.clone(), self.pubsub_manager.clone());
 
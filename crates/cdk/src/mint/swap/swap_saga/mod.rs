@@ -139,7 +139,9 @@ impl<'a> SwapSaga<'a, Initial> {
     ///
     /// - `TokenPending`: Proofs are already pending or blinded messages are duplicates
     /// - `TokenAlreadySpent`: Proofs have already been spent
-    /// - `DuplicateOutputs`: Output blinded messages already exist
+    /// - `DuplicateOutputs`: The same blinded message appears more than once in the outputs
+    /// - `BlindedMessageAlreadySigned`: Output blinded messages are already signed or reserved
+    ///   by another request
     #[instrument(skip_all)]
     pub async fn setup_swap(
         self,
@@ -220,7 +222,7 @@ impl<'a> SwapSaga<'a, Initial> {
         {
             tx.rollback().await?;
             return Err(match err {
-                database::Error::Duplicate => Error::DuplicateOutputs,
+                database::Error::Duplicate => Error::BlindedMessageAlreadySigned,
                 _ => Error::Database(err),
             });
         }
@@ -294,7 +294,8 @@ async fn test_swap_saga_duplicate_inputs() {
 ///
 /// # What This Tests
 /// - Database detects and rejects duplicate blinded message additions
-/// - setup_swap() fails with DuplicateOutputs error
+/// - setup_swap() fails with DuplicateOutputs error when the same blinded
+///   message appears twice in one request
 /// - Transaction is rolled back, leaving no partial state
 ///
 /// # Attack Vector
@@ -330,7 +331,68 @@ async fn test_swap_saga_duplicate_outputs() {
         )
         .await;
 
-    assert!(result.is_err(), "Setup should fail with duplicate outputs");
+    assert!(
+        matches!(result, Err(cdk_common::Error::DuplicateOutputs)),
+        "Setup should fail with duplicate outputs"
+    );
+}
+
+/// Tests that outputs reserved by one swap cannot be reused by a concurrent swap.
+///
+/// # What This Tests
+/// - The first swap reserves its outputs but has not signed them yet, so the
+///   already-signed check of a second swap does not see them
+/// - The unique constraint on the blinded message rejects the second swap with
+///   BlindedMessageAlreadySigned
+/// - The second swap's inputs are not left pending
+///
+/// # Attack Vector
+/// Two racing requests with the same blinded message must not both be signed.
+#[tokio::test]
+async fn test_swap_saga_outputs_reserved_by_other_swap() {
+    let mint = create_test_mint().await.unwrap();
+
+    let amount = Amount::from(100);
+    let (first_inputs, first_verification) = create_swap_inputs(&mint, amount).await;
+    let (second_inputs, second_verification) = create_swap_inputs(&mint, amount).await;
+
+    let (output_blinded_messages, _) = create_test_blinded_messages(&mint, amount).await.unwrap();
+
+    let db = mint.localstore();
+    let pubsub = mint.pubsub_manager();
+
+    let _first = SwapSaga::new(&mint, db.clone(), pubsub.clone())
+        .setup_swap(
+            &first_inputs,
+            &output_blinded_messages,
+            None,
+            first_verification,
+        )
+        .await
+        .expect("First setup should succeed");
+
+    let result = SwapSaga::new(&mint, db.clone(), pubsub)
+        .setup_swap(
+            &second_inputs,
+            &output_blinded_messages,
+            None,
+            second_verification,
+        )
+        .await;
+
+    assert!(
+        matches!(result, Err(cdk_common::Error::BlindedMessageAlreadySigned)),
+        "Second setup should fail with BlindedMessageAlreadySigned"
+    );
+
+    let states = db
+        .get_proofs_states(&second_inputs.ys().unwrap())
+        .await
+        .unwrap();
+    assert!(
+        states.iter().all(Option::is_none),
+        "Second swap inputs should not be reserved"
+    );
 }
 
 /// Tests that unbalanced swap requests are rejected (outputs > inputs).
@@ -545,6 +607,92 @@ async fn test_swap_saga_both_empty() {
     assert!(result.is_err(), "Empty swap should be rejected");
 }
 
+/// Tests that swaps exceeding the configured request limits are rejected early.
+///
+/// # What This Tests
+/// - A swap with one input more than `max_inputs` fails with MaxInputsExceeded
+/// - A swap with one output more than `max_outputs` fails with MaxOutputsExceeded
+/// - Neither request touches the database
+/// - A swap exactly at the limits succeeds
+///
+/// # Success Criteria
+/// - Proofs are not marked pending or spent
+/// - No saga is persisted and no outputs are signed
+#[tokio::test]
+async fn test_swap_request_limits() {
+    use cdk_common::mint::OperationKind;
+    use cdk_common::nuts::{RequestLimits, SwapRequest};
+
+    let mut mint = create_test_mint().await.unwrap();
+    let db = mint.localstore();
+
+    // 7 sats are split into 3 proofs and 3 outputs
+    let amount = Amount::from(7);
+    let (input_proofs, _) = create_swap_inputs(&mint, amount).await;
+    let (output_blinded_messages, _) = create_test_blinded_messages(&mint, amount).await.unwrap();
+    assert_eq!(input_proofs.len(), 3);
+    assert_eq!(output_blinded_messages.len(), 3);
+
+    let ys = input_proofs.ys().unwrap();
+    let blinded_secrets: Vec<_> = output_blinded_messages
+        .iter()
+        .map(|o| o.blinded_secret)
+        .collect();
+    let swap_request = SwapRequest::new(input_proofs.clone(), output_blinded_messages.clone());
+
+    for (limits, expected_error) in [
+        (
+            RequestLimits {
+                max_inputs: 2,
+                max_outputs: 3,
+                ..Default::default()
+            },
+            crate::Error::MaxInputsExceeded(3, 2),
+        ),
+        (
+            RequestLimits {
+                max_inputs: 3,
+                max_outputs: 2,
+                ..Default::default()
+            },
+            crate::Error::MaxOutputsExceeded(3, 2),
+        ),
+    ] {
+        mint.request_limits = limits;
+
+        let err = mint
+            .process_swap_request(swap_request.clone())
+            .await
+            .expect_err("Swap over the limit should be rejected");
+        assert_eq!(err.to_string(), expected_error.to_string());
+
+        let states = db.get_proofs_states(&ys).await.unwrap();
+        assert!(
+            states.iter().all(Option::is_none),
+            "Inputs should not be recorded"
+        );
+        let signatures = db.get_blind_signatures(&blinded_secrets).await.unwrap();
+        assert!(
+            signatures.iter().all(Option::is_none),
+            "Outputs should not be signed"
+        );
+        let incomplete = db.get_incomplete_sagas(OperationKind::Swap).await.unwrap();
+        assert!(incomplete.is_empty(), "No saga should be persisted");
+    }
+
+    // Exactly at the limits is allowed
+    mint.request_limits = RequestLimits {
+        max_inputs: 3,
+        max_outputs: 3,
+        ..Default::default()
+    };
+    let response = mint
+        .process_swap_request(swap_request)
+        .await
+        .expect("Swap at the limit should succeed");
+    assert_eq!(response.signatures.len(), 3);
+}
+
 /// Tests that a saga dropped without finalize does not auto-cleanup.
 ///
 /// # What This Tests
@@ -0,0 +1,77 @@
+//! Balance tests for swaps with input fees
+//!
+//! These tests verify that the mint rejects swaps whose outputs are not the
+//! inputs less the input fee before the inputs are marked pending, and that
+//! the error reports the expected output total.
+
+use cdk_common::nuts::SwapRequest;
+use cdk_common::Amount;
+
+use crate::nuts::nut00::ProofsMethods;
+use crate::test_helpers::mint::{
+    assert_proofs_state, create_test_blinded_messages, create_test_mint_with_input_fee,
+    mint_test_proofs,
+};
+use crate::Error;
+
+/// Test: fee rounding boundaries
+///
+/// 10 inputs at 100 ppk pay 1 sat, 11 inputs pay 2 sats as the fee is rounded up.
+/// For each, outputs one below and one above the expected total are rejected with
+/// that total, the inputs stay unspent, and the exact total is accepted.
+#[tokio::test]
+async fn test_swap_fee_rounding_boundaries() {
+    let mint = create_test_mint_with_input_fee(100).await.unwrap();
+
+    // 1023 = 10 proofs, 2047 = 11 proofs
+    for (input_amount, expected_fee) in [(1023u64, 1u64), (2047, 2)] {
+        let inputs = mint_test_proofs(&mint, Amount::from(input_amount))
+            .await
+            .unwrap();
+        let expected_output = input_amount - expected_fee;
+
+        for output_amount in [expected_output - 1, expected_output + 1] {
+            let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(output_amount))
+                .await
+                .unwrap();
+
+            let err = mint
+                .process_swap_request(SwapRequest::new(inputs.clone(), outputs))
+                .await
+                .unwrap_err();
+
+            assert!(
+                matches!(err, Error::TransactionUnbalanced(i, o, f) if i == input_amount && o == output_amount && f == expected_fee),
+                "unexpected error {err:?}"
+            );
+            assert_eq!(
+                err.expected_output_amount(),
+                Some(Amount::from(expected_output))
+            );
+            assert_proofs_state(&mint, &inputs.ys().unwrap(), None).await;
+        }
+
+        let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(expected_output))
+            .await
+            .unwrap();
+        mint.process_swap_request(SwapRequest::new(inputs, outputs))
+            .await
+            .expect("balanced swap");
+    }
+}
+
+/// Test: a swap without outputs reports the expected output total
+#[tokio::test]
+async fn test_swap_without_outputs_reports_expected_output() {
+    let mint = create_test_mint_with_input_fee(100).await.unwrap();
+
+    let inputs = mint_test_proofs(&mint, Amount::from(1023)).await.unwrap();
+
+    let err = mint
+        .process_swap_request(SwapRequest::new(inputs.clone(), vec![]))
+        .await
+        .unwrap_err();
+
+    assert_eq!(err.expected_output_amount(), Some(Amount::from(1022)));
+    assert_proofs_state(&mint, &inputs.ys().unwrap(), None).await;
+}
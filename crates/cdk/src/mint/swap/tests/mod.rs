@@ -1,4 +1,6 @@
+mod fee_balance_tests;
 mod htlc_sigall_spending_conditions_tests;
 mod htlc_spending_conditions_tests;
 mod p2pk_sigall_spending_conditions_tests;
 mod p2pk_spending_conditions_tests;
+mod unknown_keyset_tests;
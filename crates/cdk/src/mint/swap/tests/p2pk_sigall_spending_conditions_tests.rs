@@ -1405,3 +1405,75 @@ async fn test_sig_all_should_reject_if_the_output_amounts_are_swapped() {
     );
     println!("✓ Swap succeeded after restoring original amounts!");
 }
+
+/// Test: P2PK multisig (2-of-3) with SIG_ALL signed by co-signers separately
+///
+/// Each co-signer signs their own copy of the swap request; the combined
+/// request carries both signatures on the first input and is accepted.
+#[tokio::test]
+async fn test_p2pk_sig_all_multisig_combined_signatures() {
+    let test_mint = TestMintHelper::new().await.unwrap();
+    let mint = test_mint.mint();
+
+    let (_alice_secret, alice_pubkey) = create_test_keypair();
+    let (bob_secret, bob_pubkey) = create_test_keypair();
+    let (carol_secret, carol_pubkey) = create_test_keypair();
+
+    let input_amount = Amount::from(10);
+    let input_proofs = test_mint.mint_proofs(input_amount).await.unwrap();
+
+    let spending_conditions = SpendingConditions::new_p2pk(
+        alice_pubkey,
+        Some(
+            Conditions::new(
+                None,
+                Some(vec![bob_pubkey, carol_pubkey]),
+                None,
+                Some(2),
+                Some(SigFlag::SigAll),
+                None,
+            )
+            .unwrap(),
+        ),
+    );
+
+    let split_amounts = test_mint.split_amount(input_amount).unwrap();
+    let (p2pk_outputs, blinding_factors, secrets) = unzip3(
+        split_amounts
+            .iter()
+            .map(|&amt| test_mint.create_blinded_message(amt, &spending_conditions))
+            .collect(),
+    );
+    let swap_request = cdk_common::nuts::SwapRequest::new(input_proofs, p2pk_outputs);
+    let swap_response = mint.process_swap_request(swap_request).await.unwrap();
+    let p2pk_proofs = construct_proofs(
+        swap_response.signatures,
+        blinding_factors,
+        secrets,
+        &test_mint.public_keys_of_the_active_sat_keyset,
+    )
+    .unwrap();
+
+    let (new_outputs, _) = create_test_blinded_messages(mint, input_amount)
+        .await
+        .unwrap();
+    let swap_request = cdk_common::nuts::SwapRequest::new(p2pk_proofs, new_outputs);
+
+    let mut bob_signed = swap_request.clone();
+    bob_signed.sign_sig_all(bob_secret).unwrap();
+    let mut carol_signed = swap_request;
+    carol_signed.sign_sig_all(carol_secret).unwrap();
+
+    let result = mint.process_swap_request(bob_signed.clone()).await;
+    assert!(result.is_err(), "One signature should not satisfy 2-of-3");
+
+    bob_signed
+        .combine_sig_all_signatures(&carol_signed)
+        .unwrap();
+    let result = mint.process_swap_request(bob_signed).await;
+    assert!(
+        result.is_ok(),
+        "Combined signatures should satisfy 2-of-3: {:?}",
+        result.err()
+    );
+}
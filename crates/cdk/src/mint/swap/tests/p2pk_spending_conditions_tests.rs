@@ -768,3 +768,106 @@ async fn test_p2pk_duplicate_signatures() {
         result.err()
     );
 }
+
+/// Test: P2PK multisig (2-of-3) signed by co-signers separately
+///
+/// Each co-signer signs their own copy of the proofs; the wallet combines the
+/// witnesses and the mint accepts the combined proofs. A witness holding two
+/// signatures by the same key is rejected as a duplicate.
+#[tokio::test]
+async fn test_p2pk_multisig_combined_signatures() {
+    let test_mint = TestMintHelper::new().await.unwrap();
+    let mint = test_mint.mint();
+
+    let (alice_secret, alice_pubkey) = create_test_keypair();
+    let (_bob_secret, bob_pubkey) = create_test_keypair();
+    let (carol_secret, carol_pubkey) = create_test_keypair();
+
+    let input_amount = Amount::from(10);
+    let input_proofs = test_mint.mint_proofs(input_amount).await.unwrap();
+
+    let spending_conditions = SpendingConditions::new_p2pk(
+        alice_pubkey,
+        Some(
+            Conditions::new(
+                None,
+                Some(vec![bob_pubkey, carol_pubkey]),
+                None,
+                Some(2),
+                None,
+                None,
+            )
+            .unwrap(),
+        ),
+    );
+
+    let split_amounts = test_mint.split_amount(input_amount).unwrap();
+    let (p2pk_outputs, blinding_factors, secrets) = unzip3(
+        split_amounts
+            .iter()
+            .map(|&amt| test_mint.create_blinded_message(amt, &spending_conditions))
+            .collect(),
+    );
+    let swap_request = cdk_common::nuts::SwapRequest::new(input_proofs, p2pk_outputs);
+    let swap_response = mint.process_swap_request(swap_request).await.unwrap();
+    let p2pk_proofs = construct_proofs(
+        swap_response.signatures,
+        blinding_factors,
+        secrets,
+        &test_mint.public_keys_of_the_active_sat_keyset,
+    )
+    .unwrap();
+    assert!(p2pk_proofs.len() > 1);
+
+    // A signature by the same key twice is rejected as a duplicate
+    let (new_outputs, _) = create_test_blinded_messages(mint, input_amount)
+        .await
+        .unwrap();
+    let mut duplicate = p2pk_proofs.clone();
+    for proof in duplicate.iter_mut() {
+        let msg = proof.secret.to_bytes();
+        let signatures = (0..2)
+            .map(|_| alice_secret.sign(&msg).unwrap().to_string())
+            .collect();
+        proof.witness = Some(cdk_common::nuts::Witness::P2PKWitness(
+            cdk_common::nuts::nut11::P2PKWitness { signatures },
+        ));
+    }
+    let result = mint
+        .process_swap_request(cdk_common::nuts::SwapRequest::new(
+            duplicate,
+            new_outputs.clone(),
+        ))
+        .await;
+    assert!(
+        matches!(
+            result,
+            Err(crate::Error::NUT11(
+                cdk_common::nuts::nut11::Error::DuplicateSignature
+            ))
+        ),
+        "Should fail with a duplicate signature error, got {:?}",
+        result
+    );
+
+    // Alice and Carol sign their own copies, the wallet combines them
+    let mut alice_signed = p2pk_proofs.clone();
+    let mut carol_signed = p2pk_proofs;
+    for (alice_proof, carol_proof) in alice_signed.iter_mut().zip(carol_signed.iter_mut()) {
+        alice_proof.sign_p2pk(alice_secret.clone()).unwrap();
+        carol_proof.sign_p2pk(carol_secret.clone()).unwrap();
+        alice_proof.combine_p2pk_signatures(carol_proof).unwrap();
+    }
+
+    let result = mint
+        .process_swap_request(cdk_common::nuts::SwapRequest::new(
+            alice_signed,
+            new_outputs,
+        ))
+        .await;
+    assert!(
+        result.is_ok(),
+        "Combined signatures should satisfy 2-of-3: {:?}",
+        result.err()
+    );
+}
@@ -0,0 +1,53 @@
+//! Tests for swaps with inputs of unknown keysets
+//!
+//! Proofs of keysets the mint never issued, usually proofs of another mint,
+//! are rejected with the offending keyset ids before anything is stored.
+
+use std::str::FromStr;
+
+use cdk_common::nuts::{Id, SwapRequest};
+use cdk_common::Amount;
+
+use crate::nuts::nut00::ProofsMethods;
+use crate::test_helpers::mint::{create_test_blinded_messages, create_test_mint, mint_test_proofs};
+use crate::Error;
+
+/// Test: fabricated keyset ids among otherwise valid inputs are all reported
+/// and none of the inputs is recorded
+#[tokio::test]
+async fn test_swap_rejects_inputs_of_unknown_keysets() {
+    let mint = create_test_mint().await.unwrap();
+
+    // 127 = 7 proofs
+    let mut inputs = mint_test_proofs(&mint, Amount::from(127)).await.unwrap();
+    let foreign_keyset = Id::from_str("009a1f293253e41e").unwrap();
+    let other_foreign_keyset = Id::from_str("00ad268c4d1f5826").unwrap();
+    inputs[3].keyset_id = other_foreign_keyset;
+    inputs[4].keyset_id = foreign_keyset;
+
+    let (outputs, _) = create_test_blinded_messages(&mint, Amount::from(127))
+        .await
+        .unwrap();
+
+    let err = mint
+        .process_swap_request(SwapRequest::new(inputs.clone(), outputs))
+        .await
+        .unwrap_err();
+
+    match err {
+        Error::UnknownKeyset(mut ids) => {
+            ids.sort();
+            let mut expected = vec![foreign_keyset, other_foreign_keyset];
+            expected.sort();
+            assert_eq!(ids, expected);
+        }
+        err => panic!("unexpected error {err:?}"),
+    }
+
+    let states = mint
+        .localstore()
+        .get_proofs_states(&inputs.ys().unwrap())
+        .await
+        .unwrap();
+    assert!(states.iter().all(Option::is_none));
+}
@@ -1,4 +1,4 @@
-use std::collections::HashSet;
+use std::collections::{BTreeSet, HashSet};
 
 use cdk_common::{Amount, BlindedMessage, CurrencyUnit, Id, Proofs, ProofsMethods, PublicKey};
 use tracing::instrument;
@@ -52,6 +52,39 @@ impl Mint {
         Ok(())
     }
 
+    /// Verify that a request does not exceed the configured number of inputs and outputs
+    ///
+    /// This is cheap and must run before any signature verification or database
+    /// access so oversized requests are rejected without side effects.
+    #[instrument(skip_all)]
+    pub fn check_request_limits(
+        &self,
+        input_count: usize,
+        output_count: usize,
+    ) -> Result<(), Error> {
+        let limits = self.request_limits;
+
+        if input_count > limits.max_inputs {
+            tracing::debug!(
+                "Request attempted with {} inputs, maximum is {}",
+                input_count,
+                limits.max_inputs
+            );
+            return Err(Error::MaxInputsExceeded(input_count, limits.max_inputs));
+        }
+
+        if output_count > limits.max_outputs {
+            tracing::debug!(
+                "Request attempted with {} outputs, maximum is {}",
+                output_count,
+                limits.max_outputs
+            );
+            return Err(Error::MaxOutputsExceeded(output_count, limits.max_outputs));
+        }
+
+        Ok(())
+    }
+
     /// Verify output keyset
     ///
     /// Checks that the outputs are all of the same unit and the keyset is active
@@ -60,9 +93,10 @@ impl Mint {
         let mut keyset_units = HashSet::new();
 
         let output_keyset_ids: HashSet<Id> = outputs.iter().map(|p| p.keyset_id).collect();
+        let keysets = self.keysets.load();
 
         for id in &output_keyset_ids {
-            match self.get_keyset_info(id) {
+            match keysets.get(id) {
                 Some(keyset) => {
                     if !keyset.active {
                         tracing::debug!(
@@ -71,7 +105,7 @@ impl Mint {
                         );
                         return Err(Error::InactiveKeyset);
                     }
-                    keyset_units.insert(keyset.unit);
+                    keyset_units.insert(keyset.unit.clone());
                 }
                 None => {
                     tracing::debug!(
@@ -97,30 +131,118 @@ impl Mint {
         keyset_units.into_iter().next().ok_or(Error::Internal)
     }
 
+    /// Verify no output is above the largest amount signed for `unit`
+    #[instrument(skip_all)]
+    pub fn verify_output_amounts(
+        &self,
+        outputs: &[BlindedMessage],
+        unit: &CurrencyUnit,
+    ) -> Result<(), Error> {
+        let Some(max_amount) = self.max_amount(unit) else {
+            return Ok(());
+        };
+
+        if let Some(output) = outputs.iter().find(|output| output.amount > max_amount) {
+            tracing::debug!(
+                "Transaction attempted with output of {} above max amount {}.",
+                output.amount,
+                max_amount
+            );
+            return Err(Error::OutputAmountNotAllowed(output.amount, max_amount));
+        }
+
+        Ok(())
+    }
+
+    /// Verify the keyset of melt change outputs
+    ///
+    /// The change of a melt is signed with the keyset the wallet requested, so all change
+    /// outputs must reference one known, active keyset of the quote `unit`. Outputs across
+    /// keysets of different units are rejected with [`Error::MultipleUnits`], across keysets of
+    /// the same unit with [`Error::MultipleChangeKeysets`]. Returns the requested keyset.
+    #[instrument(skip_all)]
+    pub fn verify_change_outputs_keyset(
+        &self,
+        outputs: &[BlindedMessage],
+        unit: &CurrencyUnit,
+    ) -> Result<Id, Error> {
+        let keyset_ids: HashSet<Id> = outputs.iter().map(|o| o.keyset_id).collect();
+        let cached_keysets = self.keysets.load();
+
+        let mut keysets = Vec::with_capacity(keyset_ids.len());
+        for id in &keyset_ids {
+            match cached_keysets.get(id) {
+                Some(keyset) => keysets.push(keyset),
+                None => {
+                    tracing::debug!("Melt attempted with unknown keyset in change: {}.", id);
+                    return Err(Error::UnknownKeySet);
+                }
+            }
+        }
+
+        let [keyset] = keysets.as_slice() else {
+            let units: HashSet<_> = keysets.iter().map(|k| &k.unit).collect();
+            tracing::debug!(
+                "Melt attempted with change across keysets {:?}.",
+                keyset_ids
+            );
+            return Err(if units.len() > 1 {
+                Error::MultipleUnits
+            } else {
+                Error::MultipleChangeKeysets
+            });
+        };
+
+        if !keyset.active {
+            tracing::debug!(
+                "Melt attempted with inactive keyset in change: {}.",
+                keyset.id
+            );
+            return Err(Error::InactiveKeyset);
+        }
+
+        if &keyset.unit != unit {
+            tracing::debug!(
+                "Melt attempted with change in {} for a {} quote.",
+                keyset.unit,
+                unit
+            );
+            return Err(Error::UnitMismatch);
+        }
+
+        Ok(keyset.id)
+    }
+
     /// Verify input keyset
     ///
-    /// Checks that the inputs are all of the same unit
+    /// Checks that the inputs are all of known keysets of the same unit. Inputs of keysets the
+    /// mint never issued, typically proofs of another mint, are rejected with
+    /// [`Error::UnknownKeyset`] listing every unknown keyset id.
     #[instrument(skip_all)]
     pub async fn verify_inputs_keyset(&self, inputs: &Proofs) -> Result<CurrencyUnit, Error> {
         let mut keyset_units = HashSet::new();
+        let mut unknown_keyset_ids = Vec::new();
 
-        let inputs_keyset_ids: HashSet<Id> = inputs.iter().map(|p| p.keyset_id).collect();
+        let inputs_keyset_ids: BTreeSet<Id> = inputs.iter().map(|p| p.keyset_id).collect();
+        let keysets = self.keysets.load();
 
-        for id in &inputs_keyset_ids {
-            match self.get_keyset_info(id) {
+        for id in inputs_keyset_ids {
+            match keysets.get(&id) {
                 Some(keyset) => {
-                    keyset_units.insert(keyset.unit);
-                }
-                None => {
-                    tracing::debug!(
-                        "Transaction attempted with unknown keyset in inputs: {}.",
-                        id
-                    );
-                    return Err(Error::UnknownKeySet);
+                    keyset_units.insert(keyset.unit.clone());
                 }
+                None => unknown_keyset_ids.push(id),
             }
         }
 
+        if !unknown_keyset_ids.is_empty() {
+            tracing::debug!(
+                "Transaction attempted with unknown keysets in inputs: {:?}.",
+                unknown_keyset_ids
+            );
+            return Err(Error::UnknownKeyset(unknown_keyset_ids));
+        }
+
         // Check that all proofs are the same unit
         // in the future it maybe possible to support multiple units but unsupported for
         // now
@@ -180,6 +302,7 @@ impl Mint {
         self.check_output_already_signed(tx, outputs).await?;
 
         let unit = self.verify_outputs_keyset(outputs)?;
+        self.verify_output_amounts(outputs, &unit)?;
 
         let amount = Amount::try_sum(outputs.iter().map(|o| o.amount))?.with_unit(unit);
 
@@ -201,6 +324,40 @@ impl Mint {
         Ok(Verification { amount })
     }
 
+    /// Verifies that the outputs balance the inputs before anything is written
+    ///
+    /// Only the output keysets and amounts are checked, so a transaction with a unit mismatch or
+    /// off fee math fails before its inputs are marked pending. The error of an unbalanced
+    /// transaction carries the expected output total, see [`Error::expected_output_amount`]. The
+    /// outputs are verified in full when the transaction is set up.
+    #[instrument(skip_all)]
+    pub async fn verify_outputs_balance_inputs(
+        &self,
+        input_verification: &Verification,
+        inputs: &Proofs,
+        outputs: &[BlindedMessage],
+    ) -> Result<(), Error> {
+        if outputs.is_empty() {
+            let fee = self.get_proofs_fee(inputs).await?.total;
+            return Err(Error::TransactionUnbalanced(
+                input_verification.amount.value(),
+                0,
+                fee.into(),
+            ));
+        }
+
+        let unit = self.verify_outputs_keyset(outputs)?;
+        self.verify_output_amounts(outputs, &unit)?;
+        let amount = Amount::try_sum(outputs.iter().map(|o| o.amount))?.with_unit(unit);
+
+        self.verify_transaction_balanced(
+            input_verification.clone(),
+            Verification { amount },
+            inputs,
+        )
+        .await
+    }
+
     /// Verify that inputs and outputs are valid and balanced
     #[instrument(skip_all)]
     pub async fn verify_transaction_balanced(
@@ -8,17 +8,19 @@ use std::sync::Arc;
 use std::time::Duration;
 
 use bip39::Mnemonic;
-use cdk_common::amount::SplitTarget;
+use cdk_common::amount::{FeeAndAmounts, SplitTarget};
 use cdk_common::dhke::construct_proofs;
 use cdk_common::nut00::KnownMethod;
-use cdk_common::nuts::{BlindedMessage, CurrencyUnit, Id, PaymentMethod, PreMintSecrets, Proofs};
+use cdk_common::nuts::{
+    BlindedMessage, CurrencyUnit, Id, PaymentMethod, PreMintSecrets, Proofs, PublicKey, State,
+};
 use cdk_common::{
     Amount, MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteState, MintRequest,
 };
 use cdk_fake_wallet::FakeWallet;
 use tokio::time::sleep;
 
-use crate::mint::{Mint, MintBuilder, MintMeltLimits};
+use crate::mint::{Mint, MintBuilder, MintMeltLimits, PaymentConcurrencyLimit};
 use crate::types::{FeeReserve, QuoteTTL};
 use crate::Error;
 
@@ -74,32 +76,111 @@ pub(crate) fn should_fail_for(operation: &str) -> bool {
 /// }
 /// ```
 pub async fn create_test_mint() -> Result<Mint, Error> {
-    let db = Arc::new(cdk_sqlite::mint::memory::empty().await?);
+    create_test_mint_with_backend(
+        CurrencyUnit::Sat,
+        create_fake_backend(CurrencyUnit::Sat),
+        None,
+        None,
+    )
+    .await
+}
 
-    let mut mint_builder = MintBuilder::new(db.clone());
+/// Creates and starts a test mint that signs no sat output above `max_amount`.
+pub async fn create_test_mint_with_max_amount(max_amount: Amount) -> Result<Mint, Error> {
+    create_test_mint_with_backend(
+        CurrencyUnit::Sat,
+        create_fake_backend(CurrencyUnit::Sat),
+        None,
+        Some(max_amount),
+    )
+    .await
+}
+
+/// Creates and starts a test mint whose active sat keyset charges
+/// `input_fee_ppk` per input.
+pub async fn create_test_mint_with_input_fee(input_fee_ppk: u64) -> Result<Mint, Error> {
+    let mint = create_test_mint().await?;
+    mint.rotate_keyset(
+        CurrencyUnit::Sat,
+        (0..32).map(|x| 2u64.pow(x)).collect(),
+        input_fee_ppk,
+    )
+    .await?;
+    Ok(mint)
+}
+
+/// Creates and starts a test mint running at most `limit` payment operations
+/// against its fake Lightning backend at once.
+pub async fn create_test_mint_with_payment_concurrency_limit(
+    limit: PaymentConcurrencyLimit,
+) -> Result<Mint, Error> {
+    create_test_mint_with_backend(
+        CurrencyUnit::Sat,
+        create_fake_backend(CurrencyUnit::Sat),
+        Some(limit),
+        None,
+    )
+    .await
+}
+
+/// Creates and starts a test mint whose fake Lightning backend stops waiting
+/// for outgoing payments after `payment_timeout`.
+pub async fn create_test_mint_with_payment_timeout(
+    payment_timeout: Duration,
+) -> Result<Mint, Error> {
+    create_test_mint_with_backend(
+        CurrencyUnit::Sat,
+        create_fake_backend(CurrencyUnit::Sat).with_payment_timeout(payment_timeout),
+        None,
+        None,
+    )
+    .await
+}
+
+/// Creates and starts a test mint that only issues the custom `unit`, backed
+/// by a fake Lightning backend valuing one unit at `msat_per_unit`.
+pub async fn create_test_mint_with_custom_unit(
+    unit: CurrencyUnit,
+    msat_per_unit: u64,
+) -> Result<Mint, Error> {
+    let backend =
+        create_fake_backend(unit.clone()).with_custom_unit_rate(unit.clone(), msat_per_unit);
+
+    create_test_mint_with_backend(unit, backend, None, None).await
+}
 
+fn create_fake_backend(unit: CurrencyUnit) -> FakeWallet {
     let fee_reserve = FeeReserve {
         min_fee_reserve: 1.into(),
         percent_fee_reserve: 1.0,
     };
 
-    let ln_fake_backend = FakeWallet::new(
-        fee_reserve.clone(),
-        HashMap::default(),
-        HashSet::default(),
-        2,
-        CurrencyUnit::Sat,
-    );
+    FakeWallet::new(fee_reserve, HashMap::default(), HashSet::default(), 2, unit)
+}
+
+async fn create_test_mint_with_backend(
+    unit: CurrencyUnit,
+    ln_fake_backend: FakeWallet,
+    payment_concurrency_limit: Option<PaymentConcurrencyLimit>,
+    max_amount: Option<Amount>,
+) -> Result<Mint, Error> {
+    let db = Arc::new(cdk_sqlite::mint::memory::empty().await?);
+
+    let mut mint_builder = MintBuilder::new(db.clone());
 
     mint_builder
         .add_payment_processor(
-            CurrencyUnit::Sat,
+            unit.clone(),
             PaymentMethod::Known(KnownMethod::Bolt11),
             MintMeltLimits::new(1, 10_000),
             Arc::new(ln_fake_backend),
         )
         .await?;
 
+    if let Some(max_amount) = max_amount {
+        mint_builder.set_unit_max_amount(&unit, max_amount)?;
+    }
+
     let mnemonic = Mnemonic::generate(12).map_err(|e| Error::Custom(e.to_string()))?;
 
     mint_builder = mint_builder
@@ -107,6 +188,10 @@ pub async fn create_test_mint() -> Result<Mint, Error> {
         .with_description("test mint for unit tests".to_string())
         .with_urls(vec!["https://test-mint".to_string()]);
 
+    if let Some(limit) = payment_concurrency_limit {
+        mint_builder = mint_builder.with_payment_concurrency_limit(limit);
+    }
+
     let quote_ttl = QuoteTTL::new(10000, 10000);
 
     let mint = mint_builder
@@ -132,12 +217,21 @@ pub async fn create_test_mint() -> Result<Mint, Error> {
 /// * `mint` - The test mint to use for creating proofs
 /// * `amount` - The total amount to create proofs for
 pub async fn mint_test_proofs(mint: &Mint, amount: Amount) -> Result<Proofs, Error> {
+    mint_test_proofs_for_unit(mint, amount, CurrencyUnit::Sat).await
+}
+
+/// Creates test proofs in `unit` by performing a mock mint operation.
+pub async fn mint_test_proofs_for_unit(
+    mint: &Mint,
+    amount: Amount,
+    unit: CurrencyUnit,
+) -> Result<Proofs, Error> {
     // Just use fund_mint_with_proofs which creates proofs via swap
     let mint_quote: MintQuoteBolt11Response<_> = mint
         .get_mint_quote(
             MintQuoteBolt11Request {
                 amount,
-                unit: CurrencyUnit::Sat,
+                unit: unit.clone(),
                 description: None,
                 pubkey: None,
             }
@@ -160,7 +254,7 @@ pub async fn mint_test_proofs(mint: &Mint, amount: Amount) -> Result<Proofs, Err
         sleep(Duration::from_secs(1)).await;
     }
 
-    let keysets = *mint.get_active_keysets().get(&CurrencyUnit::Sat).unwrap();
+    let keysets = *mint.get_active_keysets().get(&unit).unwrap();
 
     let keys = mint
         .keyset_pubkeys(&keysets)?
@@ -170,10 +264,12 @@ pub async fn mint_test_proofs(mint: &Mint, amount: Amount) -> Result<Proofs, Err
         .keys
         .clone();
 
-    let fees: (u64, Vec<u64>) = (0, keys.iter().map(|a| a.0.to_u64()).collect::<Vec<_>>());
+    // Split like a wallet would, below the max amount of the mint
+    let fees = FeeAndAmounts::from((0, keys.iter().map(|a| a.0.to_u64()).collect::<Vec<_>>()))
+        .with_max_amount(mint.max_amount(&unit));
 
     let premint_secrets =
-        PreMintSecrets::random(keysets, amount, &SplitTarget::None, &fees.into()).unwrap();
+        PreMintSecrets::random(keysets, amount, &SplitTarget::None, &fees).unwrap();
 
     let request = MintRequest {
         quote: mint_quote.quote,
@@ -232,3 +328,15 @@ pub async fn get_active_keyset_id(mint: &Mint) -> Result<Id, Error> {
     keys.verify_id()?;
     Ok(keys.id)
 }
+
+/// Asserts the mint stores every proof of `ys` in `expected_state`.
+///
+/// Proofs the mint never recorded, or dropped when an operation rolled back,
+/// have no state and are unspent.
+pub async fn assert_proofs_state(mint: &Mint, ys: &[PublicKey], expected_state: Option<State>) {
+    let states = mint.localstore().get_proofs_states(ys).await.unwrap();
+
+    for state in states {
+        assert_eq!(state, expected_state, "Proof state mismatch");
+    }
+}
@@ -0,0 +1,415 @@
+//! Automatic melting of the balance above a threshold
+//!
+//! With [`Wallet::set_auto_melt`] the wallet keeps at most a threshold of ecash: every call to
+//! [`Wallet::run_auto_melt`], typically on a timer of the embedding app, pays the excess balance
+//! to a bolt12 offer, a Lightning address or LNURL, or a node pubkey by keysend. The transactions
+//! of these melts carry the [`AUTO_MELT_METADATA_KEY`] metadata.
+//!
+//! A failed melt releases its inputs like any other melt and is retried by a later run, after a
+//! backoff growing with the number of consecutive failures. Only unspent proofs are counted, so
+//! the inputs of a melt still pending at the mint are not melted twice.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use cdk_common::payment::{KeysendExtra, KEYSEND_METHOD};
+use cdk_common::util::unix_time;
+use cdk_common::PaymentMethod;
+use lightning::offers::offer::Offer;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::lightning_address::LnurlPayEndpoint;
+use crate::nuts::{CurrencyUnit, MeltOptions, PublicKey};
+use crate::types::Melted;
+use crate::{Amount, Error, Wallet};
+
+/// KV store namespace of the auto melt policy and state, entries are kept per mint and unit
+const AUTO_MELT_KV_NAMESPACE: &str = "auto_melt";
+const POLICY_KEY: &str = "policy";
+const RESERVE_KEY: &str = "reserve";
+const STATE_KEY: &str = "state";
+
+/// Seconds to wait after the first failed auto melt, doubled for each further failure
+const AUTO_MELT_BACKOFF_BASE_SECS: u64 = 60;
+/// Longest wait between two failed auto melts
+const AUTO_MELT_BACKOFF_MAX_SECS: u64 = 60 * 60;
+
+/// Transaction metadata key set on the melts made by [`Wallet::run_auto_melt`]
+pub const AUTO_MELT_METADATA_KEY: &str = "auto_melt";
+
+/// Destination of the automatic melts
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case", tag = "type", content = "value")]
+pub enum AutoMeltTarget {
+    /// Amountless bolt12 offer
+    Bolt12Offer(String),
+    /// Lightning address or bech32 encoded LNURL-pay
+    LightningAddress(String),
+    /// Node paid by keysend, if the mint supports it
+    Keysend(PublicKey),
+}
+
+impl AutoMeltTarget {
+    fn validate(&self) -> Result<(), Error> {
+        match self {
+            Self::Bolt12Offer(offer) => {
+                Offer::from_str(offer).map_err(|_| Error::Bolt12parse)?;
+            }
+            Self::LightningAddress(address) => {
+                LnurlPayEndpoint::from_str(address)
+                    .map_err(|e| Error::LightningAddressParse(e.to_string()))?;
+            }
+            Self::Keysend(_) => {}
+        }
+        Ok(())
+    }
+}
+
+/// Auto melt policy of a wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoMeltPolicy {
+    /// Balance kept in the wallet
+    pub threshold: Amount,
+    /// Part of the excess balance not paid out, left for the fees of the melt
+    pub reserve: Amount,
+    /// Destination of the melts
+    pub target: AutoMeltTarget,
+}
+
+impl AutoMeltPolicy {
+    /// Amount paid out of `balance`, zero if nothing is over the threshold and reserve
+    pub fn melt_amount(&self, balance: Amount) -> Amount {
+        balance
+            .checked_sub(self.threshold)
+            .and_then(|excess| excess.checked_sub(self.reserve))
+            .unwrap_or(Amount::ZERO)
+    }
+}
+
+/// Threshold and target as stored, the reserve is stored apart so it outlives the policy
+#[derive(Serialize, Deserialize)]
+struct StoredPolicy {
+    threshold: Amount,
+    target: AutoMeltTarget,
+}
+
+/// Failures of the auto melt since its last success
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AutoMeltState {
+    /// Consecutive failed auto melts
+    pub failures: u32,
+    /// Unix timestamp before which [`Wallet::run_auto_melt`] does not try again
+    pub retry_at: u64,
+    /// Error of the last failed auto melt
+    pub last_error: Option<String>,
+}
+
+impl AutoMeltState {
+    /// Record a failure at `now` and back off
+    fn record_failure(&mut self, now: u64, err: &Error) {
+        self.failures = self.failures.saturating_add(1);
+        self.retry_at = now + backoff_secs(self.failures);
+        self.last_error = Some(err.to_string());
+    }
+}
+
+/// Seconds to wait after `failures` consecutive failures
+fn backoff_secs(failures: u32) -> u64 {
+    let doublings = failures.saturating_sub(1).min(u64::BITS - 1);
+    AUTO_MELT_BACKOFF_BASE_SECS
+        .saturating_mul(1 << doublings)
+        .min(AUTO_MELT_BACKOFF_MAX_SECS)
+}
+
+/// Result of [`Wallet::run_auto_melt`]
+#[derive(Debug, Clone)]
+pub enum AutoMeltOutcome {
+    /// No auto melt policy is set
+    Disabled,
+    /// The balance does not exceed the threshold and reserve
+    BelowThreshold,
+    /// A previous auto melt failed, the next is not tried before `retry_at`
+    BackingOff {
+        /// Unix timestamp of the next attempt
+        retry_at: u64,
+    },
+    /// The excess balance was melted, the payment may still be pending
+    Melted(Melted),
+}
+
+impl Wallet {
+    fn auto_melt_namespace(&self) -> String {
+        Sha256Hash::hash(format!("{}/{}", self.mint_url, self.unit).as_bytes()).to_string()
+    }
+
+    async fn auto_melt_read<T>(&self, key: &str) -> Result<Option<T>, Error>
+    where
+        T: for<'de> Deserialize<'de>,
+    {
+        self.localstore
+            .kv_read(AUTO_MELT_KV_NAMESPACE, &self.auto_melt_namespace(), key)
+            .await?
+            .map(|value| serde_json::from_slice(&value))
+            .transpose()
+            .map_err(Into::into)
+    }
+
+    async fn auto_melt_write<T>(&self, key: &str, value: &T) -> Result<(), Error>
+    where
+        T: Serialize,
+    {
+        self.localstore
+            .kv_write(
+                AUTO_MELT_KV_NAMESPACE,
+                &self.auto_melt_namespace(),
+                key,
+                &serde_json::to_vec(value)?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Melt the balance above `threshold` to `target` on [`Wallet::run_auto_melt`]
+    ///
+    /// Replaces the previous policy and clears its failures, so the next run tries again right
+    /// away. The reserve set with [`Wallet::set_auto_melt_reserve`] is kept.
+    #[instrument(skip(self))]
+    pub async fn set_auto_melt(
+        &self,
+        threshold: Amount,
+        target: AutoMeltTarget,
+    ) -> Result<(), Error> {
+        target.validate()?;
+
+        self.auto_melt_write(POLICY_KEY, &StoredPolicy { threshold, target })
+            .await?;
+        self.auto_melt_write(STATE_KEY, &AutoMeltState::default())
+            .await
+    }
+
+    /// Leave `reserve` of the excess balance unmelted, for the fees of the melt
+    ///
+    /// Fees beyond the reserve bring the balance below the threshold. Defaults to zero.
+    #[instrument(skip(self))]
+    pub async fn set_auto_melt_reserve(&self, reserve: Amount) -> Result<(), Error> {
+        self.auto_melt_write(RESERVE_KEY, &reserve).await
+    }
+
+    /// Stop melting automatically
+    #[instrument(skip(self))]
+    pub async fn clear_auto_melt(&self) -> Result<(), Error> {
+        let namespace = self.auto_melt_namespace();
+        for key in [POLICY_KEY, STATE_KEY] {
+            self.localstore
+                .kv_remove(AUTO_MELT_KV_NAMESPACE, &namespace, key)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Auto melt policy of this wallet, if set
+    pub async fn auto_melt_policy(&self) -> Result<Option<AutoMeltPolicy>, Error> {
+        let Some(StoredPolicy { threshold, target }) = self.auto_melt_read(POLICY_KEY).await?
+        else {
+            return Ok(None);
+        };
+        let reserve = self.auto_melt_read(RESERVE_KEY).await?.unwrap_or_default();
+
+        Ok(Some(AutoMeltPolicy {
+            threshold,
+            reserve,
+            target,
+        }))
+    }
+
+    /// Failures of the auto melt since its last success
+    pub async fn auto_melt_state(&self) -> Result<AutoMeltState, Error> {
+        Ok(self.auto_melt_read(STATE_KEY).await?.unwrap_or_default())
+    }
+
+    /// Melt the balance above the threshold and reserve of the auto melt policy
+    ///
+    /// Meant to be called periodically. Does nothing while backing off from a failed melt. A
+    /// failed melt is recorded in [`Wallet::auto_melt_state`] and its error returned; the wallet
+    /// is left as after any failed melt and the excess is melted by a later run.
+    ///
+    /// Runs must not overlap, as each would melt the same excess.
+    #[instrument(skip(self))]
+    pub async fn run_auto_melt(&self) -> Result<AutoMeltOutcome, Error> {
+        let Some(policy) = self.auto_melt_policy().await? else {
+            return Ok(AutoMeltOutcome::Disabled);
+        };
+
+        let mut state = self.auto_melt_state().await?;
+        let now = unix_time();
+        if state.retry_at > now {
+            tracing::debug!(
+                "Auto melt backing off after {} failures until {}",
+                state.failures,
+                state.retry_at
+            );
+            return Ok(AutoMeltOutcome::BackingOff {
+                retry_at: state.retry_at,
+            });
+        }
+
+        let amount = policy.melt_amount(self.total_balance().await?);
+        if amount == Amount::ZERO {
+            return Ok(AutoMeltOutcome::BelowThreshold);
+        }
+
+        tracing::info!(
+            "Auto melting {} {} to {:?}",
+            amount,
+            self.unit,
+            policy.target
+        );
+
+        match self.auto_melt_to(&policy.target, amount).await {
+            Ok(melted) => {
+                if state != AutoMeltState::default() {
+                    self.auto_melt_write(STATE_KEY, &AutoMeltState::default())
+                        .await?;
+                }
+                Ok(AutoMeltOutcome::Melted(melted))
+            }
+            Err(err) => {
+                state.record_failure(now, &err);
+                tracing::warn!(
+                    "Auto melt failed {} times, retrying after {}: {}",
+                    state.failures,
+                    state.retry_at,
+                    err
+                );
+                self.auto_melt_write(STATE_KEY, &state).await?;
+                Err(err)
+            }
+        }
+    }
+
+    async fn auto_melt_to(&self, target: &AutoMeltTarget, amount: Amount) -> Result<Melted, Error> {
+        let mut metadata =
+            HashMap::from([(AUTO_MELT_METADATA_KEY.to_string(), "true".to_string())]);
+
+        let quote = match target {
+            AutoMeltTarget::Bolt12Offer(offer) => {
+                let amount_msat = self.auto_melt_amount_msat(amount)?;
+                self.melt_bolt12_quote(
+                    offer.clone(),
+                    Some(MeltOptions::new_amountless(amount_msat)),
+                )
+                .await?
+            }
+            AutoMeltTarget::LightningAddress(address) => {
+                let amount_msat = self.auto_melt_amount_msat(amount)?;
+                let pay_invoice = self
+                    .request_lnurl_invoice(address, amount_msat, None)
+                    .await?;
+                metadata.insert("lightning_address".to_string(), address.trim().to_string());
+                self.melt_quote(pay_invoice.invoice.to_string(), None)
+                    .await?
+            }
+            AutoMeltTarget::Keysend(node) => {
+                self.melt_quote_unified(
+                    PaymentMethod::from(KEYSEND_METHOD),
+                    node.to_string(),
+                    None,
+                    Some(serde_json::to_value(KeysendExtra {
+                        amount,
+                        request_lookup_id: None,
+                    })?),
+                )
+                .await?
+            }
+        };
+
+        self.melt_with_metadata(&quote.id, metadata).await
+    }
+
+    fn auto_melt_amount_msat(&self, amount: Amount) -> Result<Amount, Error> {
+        Ok(Amount::new(amount.into(), self.unit.clone())
+            .convert_to(&CurrencyUnit::Msat)?
+            .into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(threshold: u64, reserve: u64) -> AutoMeltPolicy {
+        AutoMeltPolicy {
+            threshold: Amount::from(threshold),
+            reserve: Amount::from(reserve),
+            target: AutoMeltTarget::LightningAddress("alice@example.com".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_melt_amount() {
+        assert_eq!(
+            policy(1000, 10).melt_amount(Amount::from(1500)),
+            Amount::from(490)
+        );
+        assert_eq!(
+            policy(1000, 0).melt_amount(Amount::from(1000)),
+            Amount::ZERO
+        );
+        assert_eq!(
+            policy(1000, 10).melt_amount(Amount::from(1005)),
+            Amount::ZERO
+        );
+        assert_eq!(
+            policy(1000, 10).melt_amount(Amount::from(500)),
+            Amount::ZERO
+        );
+    }
+
+    #[test]
+    fn test_backoff_doubles_up_to_the_maximum() {
+        assert_eq!(backoff_secs(1), 60);
+        assert_eq!(backoff_secs(2), 120);
+        assert_eq!(backoff_secs(6), 1920);
+        assert_eq!(backoff_secs(7), AUTO_MELT_BACKOFF_MAX_SECS);
+        assert_eq!(backoff_secs(u32::MAX), AUTO_MELT_BACKOFF_MAX_SECS);
+    }
+
+    #[test]
+    fn test_record_failure() {
+        let mut state = AutoMeltState::default();
+        state.record_failure(1_000, &Error::InsufficientFunds);
+        state.record_failure(2_000, &Error::InsufficientFunds);
+
+        assert_eq!(state.failures, 2);
+        assert_eq!(state.retry_at, 2_120);
+        assert_eq!(state.last_error, Some(Error::InsufficientFunds.to_string()));
+    }
+
+    #[test]
+    fn test_target_validation() {
+        assert!(
+            AutoMeltTarget::LightningAddress("alice@example.com".to_string())
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            AutoMeltTarget::LightningAddress("not an address".to_string())
+                .validate()
+                .is_err()
+        );
+        assert!(AutoMeltTarget::Bolt12Offer("lno1invalid".to_string())
+            .validate()
+            .is_err());
+        assert!(AutoMeltTarget::Keysend(
+            PublicKey::from_hex(
+                "02a9acc1e48c25eeeb9289b5031cc57da9fe72f3fe2861d264bdc074209b107ba2"
+            )
+            .unwrap()
+        )
+        .validate()
+        .is_ok());
+    }
+}
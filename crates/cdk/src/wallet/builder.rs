@@ -16,7 +16,9 @@ use crate::nuts::CurrencyUnit;
 #[cfg(feature = "auth")]
 use crate::wallet::auth::AuthWallet;
 use crate::wallet::mint_metadata_cache::MintMetadataCache;
-use crate::wallet::{HttpClient, MintConnector, SubscriptionManager, Wallet};
+use crate::wallet::{
+    HttpClient, KeysetMigrationOptions, MintConnector, SubscriptionManager, Wallet,
+};
 
 /// Builder for creating a new [`Wallet`]
 pub struct WalletBuilder {
@@ -30,6 +32,7 @@ pub struct WalletBuilder {
     use_http_subscription: bool,
     client: Option<Arc<dyn MintConnector + Send + Sync>>,
     metadata_cache_ttl: Option<Duration>,
+    keyset_migration: KeysetMigrationOptions,
     metadata_cache: Option<Arc<MintMetadataCache>>,
     metadata_caches: HashMap<MintUrl, Arc<MintMetadataCache>>,
 }
@@ -56,6 +59,7 @@ impl Default for WalletBuilder {
             seed: None,
             client: None,
             metadata_cache_ttl: None,
+            keyset_migration: KeysetMigrationOptions::default(),
             use_http_subscription: false,
             metadata_cache: None,
             metadata_caches: HashMap::new(),
@@ -115,6 +119,12 @@ impl WalletBuilder {
         self
     }
 
+    /// Set the options for migrating proofs off inactive keysets
+    pub fn keyset_migration(mut self, options: KeysetMigrationOptions) -> Self {
+        self.keyset_migration = options;
+        self
+    }
+
     /// Set the auth wallet
     #[cfg(feature = "auth")]
     pub fn auth_wallet(mut self, auth_wallet: AuthWallet) -> Self {
@@ -249,6 +259,7 @@ impl WalletBuilder {
             localstore,
             metadata_cache,
             metadata_cache_ttl: Arc::new(RwLock::new(metadata_cache_ttl)),
+            keyset_migration: Arc::new(RwLock::new(self.keyset_migration)),
             target_proof_count: self.target_proof_count.unwrap_or(3),
             #[cfg(feature = "auth")]
             auth_wallet: Arc::new(TokioRwLock::new(self.auth_wallet)),
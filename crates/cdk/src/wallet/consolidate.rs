@@ -0,0 +1,89 @@
+//! Consolidation of small proofs
+//!
+//! Receiving and paying leaves a wallet with many small proofs, which make
+//! later requests larger and, on keysets with input fees, more expensive.
+//! This module swaps them for fewer larger proofs when that is worth the fee.
+
+use tracing::instrument;
+
+use crate::amount::SplitTarget;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::Proofs;
+use crate::{Amount, Error, Wallet};
+
+/// Result of [`Wallet::consolidate`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ConsolidationResult {
+    /// Number of proofs swapped
+    pub proofs_consolidated: usize,
+    /// Number of proofs received for them
+    pub proofs_received: usize,
+    /// Value received, after fees
+    pub amount_received: Amount,
+    /// Input fees paid for the swaps
+    pub fee_paid: Amount,
+}
+
+impl Wallet {
+    /// Swap unspent proofs for fewer, larger ones
+    ///
+    /// Proofs are swapped smallest first, in batches of at most the input
+    /// limit of the mint. Each swap asks for exactly the batch value less its
+    /// input fee. Batches whose fee would consume their value, or that would
+    /// not reduce the number of proofs, are left in place.
+    #[instrument(skip(self))]
+    pub async fn consolidate(&self) -> Result<ConsolidationResult, Error> {
+        let max_inputs = self
+            .load_mint_info()
+            .await?
+            .nuts
+            .request_limits
+            .unwrap_or_default()
+            .max_inputs;
+        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        let fee_and_amounts = self
+            .get_keyset_fees_and_amounts_by_id(active_keyset_id)
+            .await?;
+
+        let mut proofs = self.get_unspent_proofs().await?;
+        proofs.sort_by(|a, b| a.amount.cmp(&b.amount));
+
+        let mut result = ConsolidationResult::default();
+
+        for batch in proofs.chunks(max_inputs.max(2)) {
+            let batch: Proofs = batch.to_vec();
+            let batch_amount = batch.total_amount()?;
+            let fee = self.get_proofs_fee(&batch).await?.total;
+
+            let output_amount = batch_amount.checked_sub(fee).unwrap_or(Amount::ZERO);
+            let outputs = output_amount.split(&fee_and_amounts);
+
+            if outputs.is_empty() || outputs.len() >= batch.len() {
+                tracing::debug!(
+                    "Skipping consolidation of {} proofs worth {}, fee {}",
+                    batch.len(),
+                    batch_amount,
+                    fee
+                );
+                continue;
+            }
+
+            self.swap(None, SplitTarget::default(), batch.clone(), None, false)
+                .await?;
+
+            result.proofs_consolidated += batch.len();
+            result.proofs_received += outputs.len();
+            result.amount_received += output_amount;
+            result.fee_paid += fee;
+        }
+
+        tracing::info!(
+            "Consolidated {} proofs into {} for {} fee",
+            result.proofs_consolidated,
+            result.proofs_received,
+            result.fee_paid
+        );
+
+        Ok(result)
+    }
+}
@@ -0,0 +1,155 @@
+//! Tokens received while the mint was unreachable
+//!
+//! With [`ReceiveOptions::defer_if_unreachable`] a token whose mint can't be reached is checked
+//! against the cached keysets and keys, see [`Wallet::verify_token_offline`], and stored to be
+//! claimed later with [`Wallet::claim_deferred_receives`]. Until then the sender can still spend
+//! the proofs, so the value is not part of the balance.
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use cdk_common::util::unix_time;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use super::keysets::mint_unreachable;
+use super::{ReceiveOptions, ReceiveResult};
+use crate::nuts::Token;
+use crate::{Amount, Error, Wallet};
+
+/// KV store namespace of the deferred receives, entries are kept per mint
+const DEFERRED_RECEIVE_KV_NAMESPACE: &str = "deferred_receive";
+
+/// Token stored to be claimed once the mint is reachable
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeferredReceive {
+    /// Token to claim
+    pub token: Token,
+    /// Metadata of the receive, recorded in the transaction once claimed
+    pub metadata: std::collections::HashMap<String, String>,
+    /// Unix timestamp the token was received at
+    pub received_at: u64,
+}
+
+impl Wallet {
+    fn deferred_receive_namespace(&self) -> String {
+        Sha256Hash::hash(self.mint_url.to_string().as_bytes()).to_string()
+    }
+
+    /// Store `token` to claim it later, if it can be verified offline
+    ///
+    /// Returns `err`, the error the mint could not be reached with, if the token can't be
+    /// deferred: it has spending conditions, which need keys that are not stored, or it does not
+    /// verify against the cached keysets and keys.
+    pub(crate) async fn defer_receive(
+        &self,
+        token: &Token,
+        opts: &ReceiveOptions,
+        err: Error,
+    ) -> Result<ReceiveResult, Error> {
+        if !token.spending_conditions()?.is_empty() {
+            tracing::debug!("Token with spending conditions can't be deferred");
+            return Err(err);
+        }
+
+        let report = self.verify_token_offline(token).await?;
+        if !report.is_valid() {
+            tracing::debug!("Token can't be verified offline: {:?}", report);
+            return Err(err);
+        }
+
+        let entry = DeferredReceive {
+            token: token.clone(),
+            metadata: opts.metadata.clone(),
+            received_at: unix_time(),
+        };
+        self.localstore
+            .kv_write(
+                DEFERRED_RECEIVE_KV_NAMESPACE,
+                &self.deferred_receive_namespace(),
+                &Sha256Hash::hash(token.to_string().as_bytes()).to_string(),
+                &serde_json::to_vec(&entry)?,
+            )
+            .await?;
+
+        let amount_deferred = token.value()?;
+        tracing::warn!(
+            "Mint {} unreachable, deferred receiving {}: {}",
+            self.mint_url,
+            amount_deferred,
+            err
+        );
+
+        Ok(ReceiveResult {
+            amount_deferred,
+            ..Default::default()
+        })
+    }
+
+    /// Tokens of this wallet's mint waiting to be claimed
+    pub async fn deferred_receives(&self) -> Result<Vec<DeferredReceive>, Error> {
+        let namespace = self.deferred_receive_namespace();
+        let keys = self
+            .localstore
+            .kv_list(DEFERRED_RECEIVE_KV_NAMESPACE, &namespace)
+            .await?;
+
+        let mut entries = Vec::with_capacity(keys.len());
+        for key in keys {
+            if let Some(value) = self
+                .localstore
+                .kv_read(DEFERRED_RECEIVE_KV_NAMESPACE, &namespace, &key)
+                .await?
+            {
+                entries.push(serde_json::from_slice::<DeferredReceive>(&value)?);
+            }
+        }
+        entries.sort_by_key(|entry| entry.received_at);
+
+        Ok(entries)
+    }
+
+    /// Claim the tokens deferred while the mint was unreachable
+    ///
+    /// Tokens claimed, or already spent at the mint, are removed. Tokens that fail otherwise are
+    /// kept for a later call. Fails without claiming the remaining tokens if the mint is still
+    /// unreachable.
+    ///
+    /// Returns the amount claimed.
+    #[instrument(skip(self))]
+    pub async fn claim_deferred_receives(&self) -> Result<Amount, Error> {
+        let namespace = self.deferred_receive_namespace();
+        let mut claimed = Amount::ZERO;
+
+        for entry in self.deferred_receives().await? {
+            let encoded = entry.token.to_string();
+            let opts = ReceiveOptions {
+                metadata: entry.metadata,
+                ..Default::default()
+            };
+
+            match self.receive_with_result(&encoded, opts).await {
+                Ok(result) => {
+                    claimed += result.amount_claimed;
+                }
+                Err(Error::TokenAlreadySpent) => {
+                    tracing::warn!("Deferred token was spent before it could be claimed");
+                }
+                Err(err) if mint_unreachable(&err) => return Err(err),
+                Err(err) => {
+                    tracing::warn!("Could not claim deferred token: {}", err);
+                    continue;
+                }
+            }
+
+            self.localstore
+                .kv_remove(
+                    DEFERRED_RECEIVE_KV_NAMESPACE,
+                    &namespace,
+                    &Sha256Hash::hash(encoded.as_bytes()).to_string(),
+                )
+                .await?;
+        }
+
+        Ok(claimed)
+    }
+}
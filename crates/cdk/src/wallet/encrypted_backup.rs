@@ -0,0 +1,487 @@
+//! Encrypted single-file wallet backup
+//!
+//! Exports the proofs, keyset counters, known mints and transaction history of
+//! a [`Wallet`] into a passphrase protected container that can be imported
+//! into another store.
+//!
+//! Container layout (version 1):
+//!
+//! | field        | size |
+//! |--------------|------|
+//! | magic `CDKB` | 4    |
+//! | version      | 1    |
+//! | scrypt log_n | 1    |
+//! | salt         | 16   |
+//! | nonce        | 24   |
+//! | ciphertext   | rest |
+//!
+//! The key is derived with scrypt and the JSON payload is sealed with
+//! XChaCha20-Poly1305, using the header as associated data.
+
+use std::collections::HashSet;
+
+use cdk_common::common::ProofInfo;
+use cdk_common::util::unix_time;
+use cdk_common::wallet::Transaction;
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
+use getrandom::getrandom;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+use zeroize::Zeroizing;
+
+use crate::mint_url::MintUrl;
+use crate::nuts::{CurrencyUnit, Id, KeySetInfo, MintInfo};
+use crate::{Error, Wallet};
+
+const BACKUP_MAGIC: &[u8; 4] = b"CDKB";
+const BACKUP_VERSION: u8 = 1;
+#[cfg(not(test))]
+const SCRYPT_LOG_N: u8 = 15;
+// Cheaper key derivation keeps unit tests fast
+#[cfg(test)]
+const SCRYPT_LOG_N: u8 = 10;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+const HEADER_LEN: usize = BACKUP_MAGIC.len() + 2 + SALT_LEN + NONCE_LEN;
+
+/// Contents of a wallet backup
+#[derive(Debug, Serialize, Deserialize)]
+struct WalletBackupPayload {
+    mint_url: MintUrl,
+    unit: CurrencyUnit,
+    created_at: u64,
+    mints: Vec<(MintUrl, Option<MintInfo>)>,
+    keysets: Vec<KeySetInfo>,
+    counters: Vec<(Id, u32)>,
+    proofs: Vec<ProofInfo>,
+    transactions: Vec<Transaction>,
+}
+
+/// Result of [`Wallet::import_backup`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BackupImportResult {
+    /// Proofs added to the store
+    pub proofs_imported: usize,
+    /// Proofs skipped because they were already in the store
+    pub proofs_skipped: usize,
+    /// Transactions added to the store
+    pub transactions_imported: usize,
+    /// Keyset counters raised to the value in the backup
+    pub counters_updated: usize,
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], log_n: u8) -> Result<Zeroizing<[u8; 32]>, Error> {
+    let params = scrypt::Params::new(log_n, SCRYPT_R, SCRYPT_P, 32)
+        .map_err(|_| Error::InvalidBackup("invalid key derivation parameters".to_string()))?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    scrypt::scrypt(passphrase.as_bytes(), salt, &params, key.as_mut())
+        .map_err(|_| Error::InvalidBackup("invalid key derivation parameters".to_string()))?;
+    Ok(key)
+}
+
+impl Wallet {
+    /// Export the wallet's proofs, keyset counters, known mints and
+    /// transaction history as an encrypted backup
+    ///
+    /// Only data for the wallet's mint and unit is included, apart from the
+    /// list of known mints.
+    #[instrument(skip_all)]
+    pub async fn export_backup(&self, passphrase: &str) -> Result<Vec<u8>, Error> {
+        let keysets = self
+            .localstore
+            .get_mint_keysets(self.mint_url.clone())
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|k| k.unit == self.unit)
+            .collect::<Vec<_>>();
+
+        let mut counters = Vec::with_capacity(keysets.len());
+        for keyset in keysets.iter() {
+            let counter = self
+                .localstore
+                .increment_keyset_counter(&keyset.id, 0)
+                .await?;
+            if counter > 0 {
+                counters.push((keyset.id, counter));
+            }
+        }
+
+        let payload = WalletBackupPayload {
+            mint_url: self.mint_url.clone(),
+            unit: self.unit.clone(),
+            created_at: unix_time(),
+            mints: self.localstore.get_mints().await?.into_iter().collect(),
+            keysets,
+            counters,
+            proofs: self
+                .localstore
+                .get_proofs(
+                    Some(self.mint_url.clone()),
+                    Some(self.unit.clone()),
+                    None,
+                    None,
+                )
+                .await?,
+            transactions: self
+                .localstore
+                .list_transactions(Some(self.mint_url.clone()), None, Some(self.unit.clone()))
+                .await?,
+        };
+        let plaintext = Zeroizing::new(serde_json::to_vec(&payload)?);
+
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        getrandom(&mut salt).map_err(|_| Error::Internal)?;
+        getrandom(&mut nonce).map_err(|_| Error::Internal)?;
+
+        let mut backup = Vec::with_capacity(HEADER_LEN + plaintext.len() + 16);
+        backup.extend_from_slice(BACKUP_MAGIC);
+        backup.push(BACKUP_VERSION);
+        backup.push(SCRYPT_LOG_N);
+        backup.extend_from_slice(&salt);
+        backup.extend_from_slice(&nonce);
+
+        let key = derive_key(passphrase, &salt, SCRYPT_LOG_N)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let ciphertext = cipher
+            .encrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &plaintext,
+                    aad: &backup,
+                },
+            )
+            .map_err(|_| Error::Internal)?;
+        backup.extend_from_slice(&ciphertext);
+
+        Ok(backup)
+    }
+
+    /// Import a backup created by [`Wallet::export_backup`] into this wallet's store
+    ///
+    /// The backup must be for this wallet's mint and unit. Proofs and
+    /// transactions already in the store are kept as they are, and keyset
+    /// counters are raised to the backup's value but never lowered.
+    #[instrument(skip_all)]
+    pub async fn import_backup(
+        &self,
+        backup: &[u8],
+        passphrase: &str,
+    ) -> Result<BackupImportResult, Error> {
+        if backup.len() < HEADER_LEN || &backup[..BACKUP_MAGIC.len()] != BACKUP_MAGIC {
+            return Err(Error::InvalidBackup("not a wallet backup".to_string()));
+        }
+        let (header, ciphertext) = backup.split_at(HEADER_LEN);
+        let version = header[BACKUP_MAGIC.len()];
+        if version != BACKUP_VERSION {
+            return Err(Error::InvalidBackup(format!(
+                "unsupported version {version}"
+            )));
+        }
+        // The header is only authenticated after the key is derived, so an
+        // attacker controlled cost must never reach scrypt
+        let log_n = header[BACKUP_MAGIC.len() + 1];
+        if log_n != SCRYPT_LOG_N {
+            return Err(Error::InvalidBackup(format!(
+                "unsupported key derivation cost {log_n}"
+            )));
+        }
+        let salt_start = BACKUP_MAGIC.len() + 2;
+        let salt = &header[salt_start..salt_start + SALT_LEN];
+        let nonce = &header[salt_start + SALT_LEN..];
+
+        let key = derive_key(passphrase, salt, log_n)?;
+        let cipher = XChaCha20Poly1305::new(Key::from_slice(key.as_ref()));
+        let plaintext = Zeroizing::new(
+            cipher
+                .decrypt(
+                    XNonce::from_slice(nonce),
+                    Payload {
+                        msg: ciphertext,
+                        aad: header,
+                    },
+                )
+                .map_err(|_| Error::BackupDecryption)?,
+        );
+        let payload: WalletBackupPayload = serde_json::from_slice(&plaintext)
+            .map_err(|err| Error::InvalidBackup(err.to_string()))?;
+
+        if payload.mint_url != self.mint_url || payload.unit != self.unit {
+            return Err(Error::IncorrectWallet(format!(
+                "Backup is for {} {}, wallet is {} {}",
+                payload.mint_url, payload.unit, self.mint_url, self.unit
+            )));
+        }
+
+        let mut result = BackupImportResult::default();
+
+        let known_mints = self.localstore.get_mints().await?;
+        for (mint_url, mint_info) in payload.mints {
+            if !known_mints.contains_key(&mint_url) {
+                self.localstore.add_mint(mint_url, mint_info).await?;
+            }
+        }
+
+        if !payload.keysets.is_empty() {
+            self.localstore
+                .add_mint_keysets(self.mint_url.clone(), payload.keysets)
+                .await?;
+        }
+
+        for (keyset_id, counter) in payload.counters {
+            let current = self
+                .localstore
+                .increment_keyset_counter(&keyset_id, 0)
+                .await?;
+            if counter > current {
+                self.localstore
+                    .increment_keyset_counter(&keyset_id, counter - current)
+                    .await?;
+                result.counters_updated += 1;
+            }
+        }
+
+        let existing_ys: HashSet<_> = self
+            .localstore
+            .get_proofs_by_ys(payload.proofs.iter().map(|p| p.y).collect())
+            .await?
+            .into_iter()
+            .map(|p| p.y)
+            .collect();
+        let (skipped, new_proofs): (Vec<ProofInfo>, Vec<ProofInfo>) = payload
+            .proofs
+            .into_iter()
+            .partition(|p| existing_ys.contains(&p.y));
+        result.proofs_skipped = skipped.len();
+        result.proofs_imported = new_proofs.len();
+        if !new_proofs.is_empty() {
+            self.localstore.update_proofs(new_proofs, vec![]).await?;
+        }
+
+        for transaction in payload.transactions {
+            if self
+                .localstore
+                .get_transaction(transaction.id())
+                .await?
+                .is_none()
+            {
+                self.localstore.add_transaction(transaction).await?;
+                result.transactions_imported += 1;
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Arc;
+
+    use cdk_common::database::{self, WalletDatabase};
+    use cdk_common::wallet::TransactionDirection;
+
+    use super::*;
+    use crate::nuts::{Proof, PublicKey, SecretKey, State};
+    use crate::secret::Secret;
+    use crate::wallet::WalletBuilder;
+    use crate::Amount;
+
+    const MINT_URL: &str = "https://mint.example.com";
+
+    async fn test_wallet() -> Wallet {
+        let localstore: Arc<dyn WalletDatabase<database::Error> + Send + Sync> = Arc::new(
+            cdk_sqlite::wallet::memory::empty()
+                .await
+                .expect("Failed to create in-memory database"),
+        );
+        WalletBuilder::new()
+            .mint_url(MintUrl::from_str(MINT_URL).unwrap())
+            .unit(CurrencyUnit::Sat)
+            .localstore(localstore)
+            .seed([1u8; 64])
+            .build()
+            .unwrap()
+    }
+
+    fn keyset_id() -> Id {
+        Id::from_str("00456a94ab4e1c46").unwrap()
+    }
+
+    fn proof_info(amount: u64) -> ProofInfo {
+        let proof = Proof::new(
+            Amount::from(amount),
+            keyset_id(),
+            Secret::generate(),
+            SecretKey::generate().public_key(),
+        );
+        ProofInfo::new(
+            proof,
+            MintUrl::from_str(MINT_URL).unwrap(),
+            State::Unspent,
+            CurrencyUnit::Sat,
+        )
+        .unwrap()
+    }
+
+    fn transaction(ys: Vec<PublicKey>) -> Transaction {
+        Transaction {
+            mint_url: MintUrl::from_str(MINT_URL).unwrap(),
+            direction: TransactionDirection::Incoming,
+            amount: Amount::from(8),
+            fee: Amount::ZERO,
+            unit: CurrencyUnit::Sat,
+            ys,
+            timestamp: 1_700_000_000,
+            memo: None,
+            metadata: Default::default(),
+            quote_id: None,
+            payment_request: None,
+            payment_proof: None,
+            payment_method: None,
+            kind: None,
+            preimage_verified: false,
+            amount_spent: None,
+        }
+    }
+
+    async fn seed_wallet(wallet: &Wallet, proofs: Vec<ProofInfo>, counter: u32) {
+        wallet
+            .localstore
+            .add_mint(wallet.mint_url.clone(), None)
+            .await
+            .unwrap();
+        wallet
+            .localstore
+            .add_mint_keysets(
+                wallet.mint_url.clone(),
+                vec![KeySetInfo {
+                    id: keyset_id(),
+                    unit: CurrencyUnit::Sat,
+                    active: true,
+                    input_fee_ppk: 0,
+                    final_expiry: None,
+                    max_amount: None,
+                }],
+            )
+            .await
+            .unwrap();
+        wallet
+            .localstore
+            .increment_keyset_counter(&keyset_id(), counter)
+            .await
+            .unwrap();
+        let ys = proofs.iter().map(|p| p.y).collect();
+        wallet
+            .localstore
+            .update_proofs(proofs, vec![])
+            .await
+            .unwrap();
+        wallet
+            .localstore
+            .add_transaction(transaction(ys))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_backup_round_trip() {
+        let source = test_wallet().await;
+        seed_wallet(&source, vec![proof_info(8), proof_info(2)], 10).await;
+
+        let backup = source.export_backup("correct horse").await.unwrap();
+
+        let target = test_wallet().await;
+        let result = target
+            .import_backup(&backup, "correct horse")
+            .await
+            .unwrap();
+        assert_eq!(result.proofs_imported, 2);
+        assert_eq!(result.proofs_skipped, 0);
+        assert_eq!(result.transactions_imported, 1);
+        assert_eq!(result.counters_updated, 1);
+
+        assert_eq!(target.total_balance().await.unwrap(), Amount::from(10));
+        assert_eq!(
+            target
+                .localstore
+                .increment_keyset_counter(&keyset_id(), 0)
+                .await
+                .unwrap(),
+            10
+        );
+        assert!(target
+            .localstore
+            .get_mints()
+            .await
+            .unwrap()
+            .contains_key(&target.mint_url));
+    }
+
+    #[tokio::test]
+    async fn test_backup_merge_keeps_existing_data() {
+        let shared = proof_info(4);
+        let source = test_wallet().await;
+        seed_wallet(&source, vec![shared.clone(), proof_info(16)], 5).await;
+        let backup = source.export_backup("passphrase").await.unwrap();
+
+        let target = test_wallet().await;
+        seed_wallet(&target, vec![shared, proof_info(1)], 12).await;
+
+        let result = target.import_backup(&backup, "passphrase").await.unwrap();
+        assert_eq!(result.proofs_imported, 1);
+        assert_eq!(result.proofs_skipped, 1);
+        assert_eq!(result.counters_updated, 0);
+
+        assert_eq!(target.total_balance().await.unwrap(), Amount::from(21));
+        // The higher counter already in the store is kept
+        assert_eq!(
+            target
+                .localstore
+                .increment_keyset_counter(&keyset_id(), 0)
+                .await
+                .unwrap(),
+            12
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backup_rejects_wrong_passphrase_and_corruption() {
+        let source = test_wallet().await;
+        seed_wallet(&source, vec![proof_info(8)], 1).await;
+        let backup = source.export_backup("passphrase").await.unwrap();
+        let target = test_wallet().await;
+
+        assert!(matches!(
+            target.import_backup(&backup, "wrong").await,
+            Err(Error::BackupDecryption)
+        ));
+
+        let mut corrupted = backup.clone();
+        let last = corrupted.len() - 1;
+        corrupted[last] ^= 0x01;
+        assert!(matches!(
+            target.import_backup(&corrupted, "passphrase").await,
+            Err(Error::BackupDecryption)
+        ));
+
+        assert!(matches!(
+            target.import_backup(&backup[..10], "passphrase").await,
+            Err(Error::InvalidBackup(_))
+        ));
+
+        let mut expensive = backup.clone();
+        expensive[BACKUP_MAGIC.len() + 1] = 40;
+        assert!(matches!(
+            target.import_backup(&expensive, "passphrase").await,
+            Err(Error::InvalidBackup(_))
+        ));
+
+        assert_eq!(target.total_balance().await.unwrap(), Amount::ZERO);
+    }
+}
@@ -2,7 +2,7 @@ use std::collections::HashMap;
 
 use cdk_common::nut00::KnownMethod;
 use cdk_common::nut04::MintMethodOptions;
-use cdk_common::wallet::{MintQuote, Transaction, TransactionDirection};
+use cdk_common::wallet::{MintQuote, Transaction, TransactionDirection, TransactionKind};
 use cdk_common::PaymentMethod;
 use tracing::instrument;
 
@@ -11,11 +11,11 @@ use crate::dhke::construct_proofs;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
     nut12, MintQuoteBolt11Request, MintQuoteBolt11Response, MintRequest, PreMintSecrets, Proofs,
-    SecretKey, SpendingConditions, State,
+    SpendingConditions, State,
 };
 use crate::types::ProofInfo;
 use crate::util::unix_time;
-use crate::wallet::MintQuoteState;
+use crate::wallet::{JournalOperation, MintQuoteState};
 use crate::{Amount, Error, Wallet};
 
 impl Wallet {
@@ -72,7 +72,7 @@ impl Wallet {
             }
         }
 
-        let secret_key = SecretKey::generate();
+        let secret_key = self.next_mint_quote_secret_key().await?;
 
         let request = MintQuoteBolt11Request {
             amount,
@@ -112,6 +112,9 @@ impl Wallet {
                 let mut quote = quote;
 
                 quote.state = response.state;
+                if let Some(amount_paid) = response.amount_paid {
+                    quote.amount_paid = amount_paid;
+                }
                 self.localstore.add_mint_quote(quote).await?;
             }
             None => {
@@ -258,6 +261,7 @@ impl Wallet {
             s => s,
         };
 
+        let mut counters = 0..0;
         let premint_secrets = match &spending_conditions {
             Some(spending_conditions) => PreMintSecrets::with_conditions(
                 active_keyset_id,
@@ -284,6 +288,7 @@ impl Wallet {
                     .await?;
 
                 let count = new_counter - num_secrets;
+                counters = count..new_counter;
 
                 PreMintSecrets::from_seed(
                     active_keyset_id,
@@ -306,6 +311,16 @@ impl Wallet {
             request.sign(secret_key.clone())?;
         }
 
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Mint,
+                Some(quote_id),
+                counters,
+                &premint_secrets,
+                vec![],
+            )
+            .await?;
+
         let mint_res = self.client.post_mint(request).await?;
 
         let keys = self.load_keyset_keys(active_keyset_id).await?;
@@ -348,28 +363,36 @@ impl Wallet {
             })
             .collect::<Result<Vec<ProofInfo>, _>>()?;
 
-        // Add new proofs to store
-        self.localstore.update_proofs(proof_infos, vec![]).await?;
-
-        // Add transaction to store
+        // Add new proofs to store together with the transaction
         self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: proofs.total_amount()?,
-                fee: Amount::ZERO,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time,
-                memo: None,
-                metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
-                payment_request: Some(quote_info.request),
-                payment_proof: None,
-                payment_method: Some(quote_info.payment_method),
-            })
+            .update_proofs_with_transaction(
+                proof_infos,
+                vec![],
+                Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Incoming,
+                    amount: proofs.total_amount()?,
+                    fee: Amount::ZERO,
+                    unit: self.unit.clone(),
+                    ys: proofs.ys()?,
+                    timestamp: unix_time,
+                    memo: None,
+                    metadata: HashMap::new(),
+                    quote_id: Some(quote_id.to_string()),
+                    payment_request: Some(quote_info.request),
+                    payment_proof: None,
+                    payment_method: Some(quote_info.payment_method),
+                    kind: Some(TransactionKind::Mint),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
+            )
             .await?;
 
+        self.journal_finish(&journal_id).await?;
+
+        self.auto_migrate_inactive_proofs().await;
+
         Ok(proofs)
     }
 }
@@ -1,8 +1,8 @@
 use std::collections::HashMap;
 
 use cdk_common::nut04::MintMethodOptions;
-use cdk_common::wallet::{MintQuote, Transaction, TransactionDirection};
-use cdk_common::{MintQuoteState, Proofs, SecretKey};
+use cdk_common::wallet::{MintQuote, Transaction, TransactionDirection, TransactionKind};
+use cdk_common::{MintQuoteState, Proofs};
 use tracing::instrument;
 
 use crate::amount::SplitTarget;
@@ -14,6 +14,7 @@ use crate::nuts::{
 };
 use crate::types::ProofInfo;
 use crate::util::unix_time;
+use crate::wallet::JournalOperation;
 use crate::{Amount, Error, Wallet};
 
 impl Wallet {
@@ -50,7 +51,7 @@ impl Wallet {
             }
         }
 
-        let secret_key = SecretKey::generate();
+        let secret_key = self.next_mint_quote_secret_key().await?;
 
         let amount = amount.ok_or(Error::AmountUndefined)?;
 
@@ -122,6 +123,7 @@ impl Wallet {
             .get_keyset_fees_and_amounts_by_id(active_keyset_id)
             .await?;
 
+        let mut counters = 0..0;
         let premint_secrets = match &spending_conditions {
             Some(spending_conditions) => PreMintSecrets::with_conditions(
                 active_keyset_id,
@@ -149,6 +151,7 @@ impl Wallet {
                     .await?;
 
                 let count = new_counter - num_secrets;
+                counters = count..new_counter;
 
                 PreMintSecrets::from_seed(
                     active_keyset_id,
@@ -171,6 +174,16 @@ impl Wallet {
             request.sign(secret_key.clone())?;
         }
 
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Mint,
+                Some(quote_id),
+                counters,
+                &premint_secrets,
+                vec![],
+            )
+            .await?;
+
         let mint_res = self.client.post_mint(request).await?;
 
         let keys = self.load_keyset_keys(active_keyset_id).await?;
@@ -213,28 +226,36 @@ impl Wallet {
             })
             .collect::<Result<Vec<ProofInfo>, _>>()?;
 
-        // Add new proofs to store
-        self.localstore.update_proofs(proof_infos, vec![]).await?;
-
-        // Add transaction to store
+        // Add new proofs to store together with the transaction
         self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: proofs.total_amount()?,
-                fee: Amount::ZERO,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time,
-                memo: None,
-                metadata: HashMap::new(),
-                quote_id: Some(quote_id.to_string()),
-                payment_request: Some(quote_info.request),
-                payment_proof: None,
-                payment_method: Some(quote_info.payment_method),
-            })
+            .update_proofs_with_transaction(
+                proof_infos,
+                vec![],
+                Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Incoming,
+                    amount: proofs.total_amount()?,
+                    fee: Amount::ZERO,
+                    unit: self.unit.clone(),
+                    ys: proofs.ys()?,
+                    timestamp: unix_time,
+                    memo: None,
+                    metadata: HashMap::new(),
+                    quote_id: Some(quote_id.to_string()),
+                    payment_request: Some(quote_info.request),
+                    payment_proof: None,
+                    payment_method: Some(quote_info.payment_method),
+                    kind: Some(TransactionKind::Mint),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
+            )
             .await?;
 
+        self.journal_finish(&journal_id).await?;
+
+        self.auto_migrate_inactive_proofs().await;
+
         Ok(proofs)
     }
 }
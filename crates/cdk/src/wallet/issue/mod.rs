@@ -1,6 +1,7 @@
 mod bolt11;
 mod bolt12;
 mod custom;
+mod recover;
 
 use cdk_common::PaymentMethod;
 
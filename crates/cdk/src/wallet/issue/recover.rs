@@ -0,0 +1,160 @@
+//! Recovery of the mint quotes of a wallet
+//!
+//! Each mint quote is locked to its own key [NUT-20], derived from the seed, the mint url and a
+//! counter kept in the wallet database, so the mint can't link the quotes of a wallet. A wallet
+//! restored from its seed lists the quotes locked to its keys at the mint, in batches, until
+//! [`MINT_QUOTE_KEY_GAP_LIMIT`] keys in a row have no quote, and mints the ones paid meanwhile.
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use cdk_common::util::unix_time;
+use tokio::sync::Mutex;
+use tracing::instrument;
+
+use crate::nuts::{MintQuotesRequest, SecretKey, MAX_MINT_QUOTES_PUBKEYS};
+use crate::wallet::MintQuote;
+use crate::{Error, Wallet};
+
+/// Domain separator of the mint quote key derivation
+const MINT_QUOTE_KEY_DOMAIN: &[u8] = b"cdk_mint_quote_key";
+
+const MINT_QUOTE_KEY_KV_NAMESPACE: &str = "mint_quote_key";
+const COUNTER_KEY: &str = "counter";
+
+/// Unused mint quote keys in a row after which recovery stops
+const MINT_QUOTE_KEY_GAP_LIMIT: u32 = 20;
+
+/// Serializes the updates of the mint quote key counters, so no key locks two quotes
+static MINT_QUOTE_KEY_COUNTER_LOCK: Mutex<()> = Mutex::const_new(());
+
+impl Wallet {
+    /// Secret key locking the mint quote at `index` of the wallet at its mint
+    ///
+    /// Derived as `SHA256(seed || "cdk_mint_quote_key" || mint_url || index)`, with the index
+    /// as 4 big endian bytes.
+    pub(crate) fn mint_quote_secret_key(&self, index: u32) -> Result<SecretKey, Error> {
+        let mut data = self.seed.to_vec();
+        data.extend_from_slice(MINT_QUOTE_KEY_DOMAIN);
+        data.extend_from_slice(self.mint_url.to_string().as_bytes());
+        data.extend_from_slice(&index.to_be_bytes());
+
+        Ok(SecretKey::from_slice(
+            Sha256Hash::hash(&data).as_byte_array(),
+        )?)
+    }
+
+    /// Secret key for a new mint quote, moving the counter of the mint past it
+    pub(crate) async fn next_mint_quote_secret_key(&self) -> Result<SecretKey, Error> {
+        let _guard = MINT_QUOTE_KEY_COUNTER_LOCK.lock().await;
+
+        let index = self.mint_quote_key_counter().await?;
+        self.set_mint_quote_key_counter(index + 1).await?;
+
+        self.mint_quote_secret_key(index)
+    }
+
+    fn mint_quote_key_namespace(&self) -> String {
+        Sha256Hash::hash(self.mint_url.to_string().as_bytes()).to_string()
+    }
+
+    /// Index of the next unused mint quote key at the mint
+    async fn mint_quote_key_counter(&self) -> Result<u32, Error> {
+        Ok(self
+            .localstore
+            .kv_read(
+                MINT_QUOTE_KEY_KV_NAMESPACE,
+                &self.mint_quote_key_namespace(),
+                COUNTER_KEY,
+            )
+            .await?
+            .map(|value| serde_json::from_slice(&value))
+            .transpose()?
+            .unwrap_or_default())
+    }
+
+    async fn set_mint_quote_key_counter(&self, counter: u32) -> Result<(), Error> {
+        self.localstore
+            .kv_write(
+                MINT_QUOTE_KEY_KV_NAMESPACE,
+                &self.mint_quote_key_namespace(),
+                COUNTER_KEY,
+                &serde_json::to_vec(&counter)?,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Recover the mint quotes of the wallet unknown to its database
+    ///
+    /// Lists the quotes locked to the quote keys of the wallet at the mint, until
+    /// [`MINT_QUOTE_KEY_GAP_LIMIT`] keys in a row past the last used one have no quote, and
+    /// stores those of the wallet unit that are not in the database, with the state reported by
+    /// the mint. New quotes then use keys past the recovered ones. Returns the recovered quotes,
+    /// paid ones can then be minted as usual.
+    #[instrument(skip(self))]
+    pub async fn recover_mint_quotes(&self) -> Result<Vec<MintQuote>, Error> {
+        let batch_size = MAX_MINT_QUOTES_PUBKEYS as u32;
+        let counter = self.mint_quote_key_counter().await?;
+        let mut next_index = counter;
+        let mut start = 0;
+
+        let mut recovered = Vec::new();
+        while start < next_index.saturating_add(MINT_QUOTE_KEY_GAP_LIMIT) {
+            let secret_keys = (start..start.saturating_add(batch_size))
+                .map(|index| self.mint_quote_secret_key(index))
+                .collect::<Result<Vec<_>, _>>()?;
+            let request = MintQuotesRequest::new(&secret_keys, self.mint_url.clone(), unix_time())?;
+            let response = self.client.post_mint_quotes(request).await?;
+
+            for status in response.quotes {
+                let Some(position) = secret_keys
+                    .iter()
+                    .position(|secret_key| secret_key.public_key() == status.pubkey)
+                else {
+                    continue;
+                };
+                next_index = next_index.max(start + position as u32 + 1);
+
+                if status.unit != self.unit
+                    || self
+                        .localstore
+                        .get_mint_quote(&status.quote)
+                        .await?
+                        .is_some()
+                {
+                    continue;
+                }
+
+                let mut quote = MintQuote::new(
+                    status.quote,
+                    self.mint_url.clone(),
+                    status.method,
+                    status.amount,
+                    status.unit,
+                    status.request,
+                    status.expiry,
+                    Some(secret_keys[position].clone()),
+                );
+                quote.state = status.state;
+                quote.amount_paid = status.amount_paid;
+                quote.amount_issued = status.amount_issued;
+
+                self.localstore.add_mint_quote(quote.clone()).await?;
+                recovered.push(quote);
+            }
+
+            start = start.saturating_add(batch_size);
+        }
+
+        if next_index > counter {
+            let _guard = MINT_QUOTE_KEY_COUNTER_LOCK.lock().await;
+            let counter = self.mint_quote_key_counter().await?;
+            self.set_mint_quote_key_counter(counter.max(next_index))
+                .await?;
+        }
+
+        tracing::info!("Recovered {} mint quotes", recovered.len());
+
+        Ok(recovered)
+    }
+}
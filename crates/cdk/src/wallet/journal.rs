@@ -0,0 +1,436 @@
+//! Journal of the operations in flight with the mint
+//!
+//! Mint, swap and melt operations write a [`JournalEntry`] with the secrets of their outputs
+//! before contacting the mint, and remove it once the resulting proofs are stored. An entry left
+//! behind means the wallet stopped in between, possibly after the mint signed the outputs.
+//! [`Wallet::recover_interrupted_operations`] asks the mint which outputs it signed (NUT-09) and
+//! which inputs it spent (NUT-07), stores the proofs and finishes or rolls back each operation.
+
+use std::collections::HashMap;
+use std::ops::Range;
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use cdk_common::util::unix_time;
+use serde::{Deserialize, Serialize};
+use tracing::instrument;
+
+use crate::dhke::blind_message;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{
+    BlindedMessage, CheckStateRequest, Id, MeltQuoteState, MintQuoteState, PreMintSecrets, Proofs,
+    PublicKey, RestoreRequest, SecretKey, State,
+};
+use crate::secret::Secret;
+use crate::types::ProofInfo;
+use crate::{Amount, Error, Wallet};
+
+/// KV store namespace of the operation journal, entries are kept per mint
+const OPERATION_JOURNAL_KV_NAMESPACE: &str = "operation_journal";
+
+/// Operation recorded in the journal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JournalOperation {
+    /// Minting proofs for a paid mint quote
+    Mint,
+    /// Swapping proofs, including receiving a token
+    Swap,
+    /// Melting proofs, with change outputs
+    Melt,
+}
+
+/// Secret and blinding factor of an output
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalOutput {
+    /// Secret of the output
+    pub secret: Secret,
+    /// Blinding factor
+    pub r: SecretKey,
+}
+
+/// Operation started with the mint and not finished yet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalEntry {
+    /// Id of the entry
+    pub id: String,
+    /// Operation
+    pub operation: JournalOperation,
+    /// Quote of a mint or melt
+    pub quote_id: Option<String>,
+    /// Keyset of the outputs
+    pub keyset_id: Id,
+    /// Counters reserved for the outputs derived from the wallet seed
+    pub counters: Range<u32>,
+    /// Outputs the mint is asked to sign
+    pub outputs: Vec<JournalOutput>,
+    /// Ys of the proofs spent by the operation
+    pub input_ys: Vec<PublicKey>,
+    /// Unix timestamp the operation started at
+    pub created_at: u64,
+}
+
+/// Result of [`Wallet::recover_interrupted_operations`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OperationRecoveryResult {
+    /// Operations the mint had completed, finished in the wallet
+    pub completed: usize,
+    /// Operations the mint had not processed, with their inputs released
+    pub rolled_back: usize,
+    /// Operations still pending at the mint, kept in the journal
+    pub pending: usize,
+    /// Value of the proofs recovered from the outputs signed by the mint
+    pub amount_recovered: Amount,
+}
+
+/// Outcome of recovering one journal entry
+enum Recovery {
+    Completed(Amount),
+    RolledBack,
+    Pending,
+}
+
+impl Wallet {
+    fn journal_namespace(&self) -> String {
+        Sha256Hash::hash(self.mint_url.to_string().as_bytes()).to_string()
+    }
+
+    /// Record an operation before its request is sent to the mint
+    ///
+    /// Returns the id of the entry, to pass to [`Wallet::journal_finish`] once the proofs are
+    /// stored.
+    pub(crate) async fn journal_begin(
+        &self,
+        operation: JournalOperation,
+        quote_id: Option<&str>,
+        counters: Range<u32>,
+        premint_secrets: &PreMintSecrets,
+        input_ys: Vec<PublicKey>,
+    ) -> Result<String, Error> {
+        let entry = JournalEntry {
+            id: uuid::Uuid::new_v4().simple().to_string(),
+            operation,
+            quote_id: quote_id.map(str::to_string),
+            keyset_id: premint_secrets.keyset_id,
+            counters,
+            outputs: premint_secrets
+                .iter()
+                .map(|premint| JournalOutput {
+                    secret: premint.secret.clone(),
+                    r: premint.r.clone(),
+                })
+                .collect(),
+            input_ys,
+            created_at: unix_time(),
+        };
+
+        self.localstore
+            .kv_write(
+                OPERATION_JOURNAL_KV_NAMESPACE,
+                &self.journal_namespace(),
+                &entry.id,
+                &serde_json::to_vec(&entry)?,
+            )
+            .await?;
+
+        Ok(entry.id)
+    }
+
+    /// Counter range of the last `count` secrets reserved for `keyset_id`
+    pub(crate) async fn journal_counters(
+        &self,
+        keyset_id: &Id,
+        count: u32,
+    ) -> Result<Range<u32>, Error> {
+        let end = self
+            .localstore
+            .increment_keyset_counter(keyset_id, 0)
+            .await?;
+        Ok(end.saturating_sub(count)..end)
+    }
+
+    /// Remove a finished operation from the journal
+    pub(crate) async fn journal_finish(&self, id: &str) -> Result<(), Error> {
+        self.localstore
+            .kv_remove(
+                OPERATION_JOURNAL_KV_NAMESPACE,
+                &self.journal_namespace(),
+                id,
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Operations of this wallet's mint left in the journal
+    pub async fn interrupted_operations(&self) -> Result<Vec<JournalEntry>, Error> {
+        let namespace = self.journal_namespace();
+        let ids = self
+            .localstore
+            .kv_list(OPERATION_JOURNAL_KV_NAMESPACE, &namespace)
+            .await?;
+
+        let mut entries = Vec::with_capacity(ids.len());
+        for id in ids {
+            if let Some(value) = self
+                .localstore
+                .kv_read(OPERATION_JOURNAL_KV_NAMESPACE, &namespace, &id)
+                .await?
+            {
+                entries.push(serde_json::from_slice::<JournalEntry>(&value)?);
+            }
+        }
+        entries.sort_by_key(|entry| entry.created_at);
+
+        Ok(entries)
+    }
+
+    /// Finish or roll back the operations interrupted before their proofs were stored
+    ///
+    /// Meant to be called on startup, before new operations are started: an operation running
+    /// concurrently would be taken for an interrupted one. Proofs of outputs the mint signed are
+    /// stored, inputs the mint spent are removed and the others are made spendable again.
+    /// Operations the mint still has pending, such as a melt waiting for its payment, are kept
+    /// for a later call.
+    #[instrument(skip(self))]
+    pub async fn recover_interrupted_operations(&self) -> Result<OperationRecoveryResult, Error> {
+        let mut result = OperationRecoveryResult::default();
+
+        for entry in self.interrupted_operations().await? {
+            tracing::info!(
+                "Recovering interrupted {:?} operation {} started at {}",
+                entry.operation,
+                entry.id,
+                entry.created_at
+            );
+
+            match self.recover_operation(&entry).await? {
+                Recovery::Completed(amount) => {
+                    result.completed += 1;
+                    result.amount_recovered += amount;
+                }
+                Recovery::RolledBack => result.rolled_back += 1,
+                Recovery::Pending => {
+                    result.pending += 1;
+                    continue;
+                }
+            }
+
+            self.journal_finish(&entry.id).await?;
+        }
+
+        Ok(result)
+    }
+
+    /// Finish or roll back the melts of `quote_id` left in the journal
+    ///
+    /// Unlike [`Wallet::check_all_pending_proofs`], only the inputs of the melt are touched, so it
+    /// can run while other operations hold reserved proofs. The quote is checked with the mint
+    /// first, inputs still pending at the mint are kept for a later call.
+    #[instrument(skip(self))]
+    pub(crate) async fn reclaim_melt_inputs(&self, quote_id: &str) -> Result<(), Error> {
+        if let Err(err) = self.melt_quote_status(quote_id).await {
+            tracing::warn!("Could not check melt quote {}: {}", quote_id, err);
+        }
+
+        for entry in self.interrupted_operations().await? {
+            if entry.operation != JournalOperation::Melt
+                || entry.quote_id.as_deref() != Some(quote_id)
+            {
+                continue;
+            }
+
+            match self.recover_operation(&entry).await? {
+                Recovery::Pending => continue,
+                Recovery::Completed(_) | Recovery::RolledBack => {
+                    self.journal_finish(&entry.id).await?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    async fn recover_operation(&self, entry: &JournalEntry) -> Result<Recovery, Error> {
+        // The counters were reserved before the entry was written, but a restored database
+        // may be behind
+        let counter = self
+            .localstore
+            .increment_keyset_counter(&entry.keyset_id, 0)
+            .await?;
+        if counter < entry.counters.end {
+            self.localstore
+                .increment_keyset_counter(&entry.keyset_id, entry.counters.end - counter)
+                .await?;
+        }
+
+        let proofs = self.restore_journal_outputs(entry).await?;
+
+        // Only proofs not stored yet are added, the operation may have been finished but not
+        // removed from the journal
+        let known: Vec<_> = self
+            .localstore
+            .get_proofs_by_ys(proofs.ys()?)
+            .await?
+            .into_iter()
+            .map(|proof| proof.y)
+            .collect();
+        let proofs: Proofs = proofs
+            .into_iter()
+            .filter(|proof| proof.y().is_ok_and(|y| !known.contains(&y)))
+            .collect();
+
+        let mut recovered = Amount::ZERO;
+        if !proofs.is_empty() {
+            let states = self
+                .client
+                .post_check_state(CheckStateRequest { ys: proofs.ys()? })
+                .await?
+                .states;
+            let unspent: Proofs = proofs
+                .into_iter()
+                .zip(states)
+                .filter(|(_, state)| state.state == State::Unspent)
+                .map(|(proof, _)| proof)
+                .collect();
+
+            recovered = unspent.total_amount()?;
+            let unspent = unspent
+                .into_iter()
+                .map(|proof| {
+                    ProofInfo::new(
+                        proof,
+                        self.mint_url.clone(),
+                        State::Unspent,
+                        self.unit.clone(),
+                    )
+                })
+                .collect::<Result<Vec<ProofInfo>, _>>()?;
+            self.localstore.update_proofs(unspent, vec![]).await?;
+        }
+
+        let mut completed = recovered > Amount::ZERO || !known.is_empty();
+
+        if entry.operation == JournalOperation::Mint && recovered > Amount::ZERO {
+            if let Some(mut quote) = match &entry.quote_id {
+                Some(quote_id) => self.localstore.get_mint_quote(quote_id).await?,
+                None => None,
+            } {
+                quote.amount_issued += recovered;
+                quote.state = MintQuoteState::Issued;
+                self.localstore.add_mint_quote(quote).await?;
+            }
+        }
+
+        if !entry.input_ys.is_empty() {
+            let states = self
+                .client
+                .post_check_state(CheckStateRequest {
+                    ys: entry.input_ys.clone(),
+                })
+                .await?
+                .states;
+
+            if states.iter().any(|state| state.state == State::Pending) {
+                tracing::info!(
+                    "Operation {} is still pending at the mint, keeping it in the journal",
+                    entry.id
+                );
+                return Ok(Recovery::Pending);
+            }
+
+            let (spent, unspent): (Vec<_>, Vec<_>) = states
+                .into_iter()
+                .partition(|state| state.state == State::Spent);
+            completed |= !spent.is_empty();
+
+            if entry.operation == JournalOperation::Melt && !spent.is_empty() {
+                if let Some(mut quote) = match &entry.quote_id {
+                    Some(quote_id) => self.localstore.get_melt_quote(quote_id).await?,
+                    None => None,
+                } {
+                    quote.state = MeltQuoteState::Paid;
+                    self.localstore.add_melt_quote(quote).await?;
+                }
+            }
+
+            self.localstore
+                .update_proofs(vec![], spent.into_iter().map(|state| state.y).collect())
+                .await?;
+            if !unspent.is_empty() {
+                self.localstore
+                    .update_proofs_state(
+                        unspent.into_iter().map(|state| state.y).collect(),
+                        State::Unspent,
+                    )
+                    .await?;
+            }
+        }
+
+        if completed {
+            tracing::info!(
+                "Completed interrupted operation {}, recovered {}",
+                entry.id,
+                recovered
+            );
+            Ok(Recovery::Completed(recovered))
+        } else {
+            tracing::info!("Rolled back interrupted operation {}", entry.id);
+            Ok(Recovery::RolledBack)
+        }
+    }
+
+    /// Proofs of the outputs of `entry` the mint signed
+    ///
+    /// The mint may sign only some of the outputs, the blank change outputs of a melt are only
+    /// signed for the change actually returned.
+    async fn restore_journal_outputs(&self, entry: &JournalEntry) -> Result<Proofs, Error> {
+        if entry.outputs.is_empty() {
+            return Ok(Proofs::new());
+        }
+
+        let outputs = entry
+            .outputs
+            .iter()
+            .map(|output| {
+                let (blinded_secret, _) =
+                    blind_message(&output.secret.to_bytes(), Some(output.r.clone()))?;
+                Ok((blinded_secret, output))
+            })
+            .collect::<Result<HashMap<_, _>, Error>>()?;
+
+        let response = self
+            .client
+            .post_restore(RestoreRequest::new(
+                outputs
+                    .keys()
+                    .map(|blinded_secret| {
+                        BlindedMessage::new(Amount::ZERO, entry.keyset_id, *blinded_secret)
+                    })
+                    .collect(),
+            ))
+            .await?;
+
+        let mut signatures = Vec::new();
+        let mut rs = Vec::new();
+        let mut secrets = Vec::new();
+        for (output, signature) in response.outputs.iter().zip(response.signatures) {
+            let journal_output =
+                outputs
+                    .get(&output.blinded_secret)
+                    .ok_or(Error::InvalidMintResponse(
+                        "restore response contains an unknown output".to_string(),
+                    ))?;
+            signatures.push(signature);
+            rs.push(journal_output.r.clone());
+            secrets.push(journal_output.secret.clone());
+        }
+
+        if signatures.is_empty() {
+            return Ok(Proofs::new());
+        }
+
+        let keys = self.load_keyset_keys(entry.keyset_id).await?;
+        self.construct_signed_proofs(signatures, rs, secrets, entry.keyset_id, &keys)
+            .await
+    }
+}
@@ -0,0 +1,161 @@
+//! Migration of proofs off inactive keysets
+//!
+//! Mints rotate keysets and may eventually stop accepting proofs from old
+//! ones. This module swaps proofs held on inactive keysets into the active
+//! keyset, in batches and without spending more on fees than the proofs are
+//! worth.
+
+use std::collections::HashSet;
+
+use cdk_common::nut02::KeySetInfosMethods;
+use tracing::instrument;
+
+use crate::amount::SplitTarget;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{Id, Proofs};
+use crate::{Amount, Error, Wallet};
+
+/// Options for migrating proofs off inactive keysets
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeysetMigrationOptions {
+    /// Maximum number of proofs swapped in a single request
+    pub batch_size: usize,
+    /// Proofs worth this amount or less are left on their keyset
+    pub dust_threshold: Amount,
+    /// Run the migration automatically after receiving or minting
+    pub auto_migrate: bool,
+}
+
+impl Default for KeysetMigrationOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 64,
+            dust_threshold: Amount::ZERO,
+            auto_migrate: false,
+        }
+    }
+}
+
+impl KeysetMigrationOptions {
+    /// Create new [`KeysetMigrationOptions`]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the maximum number of proofs swapped per request
+    pub fn batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    /// Leave proofs worth `dust_threshold` or less on their keyset
+    pub fn dust_threshold(mut self, dust_threshold: Amount) -> Self {
+        self.dust_threshold = dust_threshold;
+        self
+    }
+
+    /// Run the migration automatically after receiving or minting
+    pub fn auto_migrate(mut self, auto_migrate: bool) -> Self {
+        self.auto_migrate = auto_migrate;
+        self
+    }
+}
+
+/// Result of [`Wallet::migrate_inactive_proofs`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeysetMigrationResult {
+    /// Number of proofs swapped off inactive keysets
+    pub proofs_migrated: usize,
+    /// Value of the proofs swapped, before fees
+    pub amount_migrated: Amount,
+    /// Input fees paid for the swaps
+    pub fee_paid: Amount,
+    /// Value left on inactive keysets because it was dust or not worth the fee
+    pub amount_skipped: Amount,
+}
+
+impl Wallet {
+    /// Swap unspent proofs on inactive keysets into the active keyset
+    ///
+    /// Refreshes the keyset list from the mint first. Proofs at or below the
+    /// configured dust threshold, and batches whose input fee would consume
+    /// their whole value, are left in place and reported as skipped.
+    #[instrument(skip(self))]
+    pub async fn migrate_inactive_proofs(&self) -> Result<KeysetMigrationResult, Error> {
+        let options = *self.keyset_migration.read();
+
+        self.refresh_keysets().await?;
+        let keysets = self.get_all_mint_keysets().await?;
+        let active_ids: HashSet<Id> = keysets.active().map(|k| k.id).collect();
+        let inactive_ids: HashSet<Id> =
+            keysets.iter().filter(|k| !k.active).map(|k| k.id).collect();
+
+        if active_ids.is_empty() {
+            return Err(Error::NoActiveKeyset);
+        }
+
+        let mut result = KeysetMigrationResult::default();
+
+        let (mut to_migrate, dust): (Proofs, Proofs) = self
+            .get_unspent_proofs()
+            .await?
+            .into_iter()
+            .filter(|proof| inactive_ids.contains(&proof.keyset_id))
+            .partition(|proof| proof.amount > options.dust_threshold);
+        result.amount_skipped = dust.total_amount()?;
+
+        if to_migrate.is_empty() {
+            return Ok(result);
+        }
+
+        // Largest first so remaining dust ends up batched together
+        to_migrate.sort_by(|a, b| b.amount.cmp(&a.amount));
+
+        for batch in to_migrate.chunks(options.batch_size.max(1)) {
+            let batch: Proofs = batch.to_vec();
+            let batch_amount = batch.total_amount()?;
+            let fee = self.get_proofs_fee(&batch).await?.total;
+
+            if fee >= batch_amount {
+                tracing::debug!(
+                    "Skipping migration of {} proofs worth {}, fee {}",
+                    batch.len(),
+                    batch_amount,
+                    fee
+                );
+                result.amount_skipped += batch_amount;
+                continue;
+            }
+
+            self.swap(None, SplitTarget::default(), batch.clone(), None, false)
+                .await?;
+
+            result.proofs_migrated += batch.len();
+            result.amount_migrated += batch_amount;
+            result.fee_paid += fee;
+        }
+
+        tracing::info!(
+            "Migrated {} proofs worth {} off inactive keysets for {} fee",
+            result.proofs_migrated,
+            result.amount_migrated,
+            result.fee_paid
+        );
+
+        Ok(result)
+    }
+
+    /// Run [`Wallet::migrate_inactive_proofs`] if automatic migration is enabled
+    ///
+    /// Failures are logged rather than returned so they never fail the
+    /// operation that triggered the migration.
+    pub(crate) async fn auto_migrate_inactive_proofs(&self) {
+        if !self.keyset_migration.read().auto_migrate {
+            return;
+        }
+
+        if let Err(err) = self.migrate_inactive_proofs().await {
+            tracing::warn!("Automatic keyset migration failed: {}", err);
+        }
+    }
+}
@@ -1,12 +1,23 @@
+use std::collections::hash_map::Entry;
 use std::collections::HashMap;
 
 use cdk_common::amount::{FeeAndAmounts, KeysetFeeAndAmounts};
 use cdk_common::nut02::{KeySetInfos, KeySetInfosMethods};
 use tracing::instrument;
 
-use crate::nuts::{Id, KeySetInfo, Keys};
+use crate::dhke::construct_proofs;
+use crate::nuts::{BlindSignature, Id, KeySetInfo, Keys, Proofs, SecretKey};
+use crate::secret::Secret;
 use crate::{Error, Wallet};
 
+/// Whether `err` means the mint could not be reached, rather than that it rejected a request
+pub(crate) fn mint_unreachable(err: &Error) -> bool {
+    matches!(
+        err,
+        Error::HttpError(None, _) | Error::HttpError(Some(500..=599), _)
+    )
+}
+
 impl Wallet {
     /// Load keys for mint keyset
     ///
@@ -26,6 +37,52 @@ impl Wallet {
             .ok_or(Error::UnknownKeySet)
     }
 
+    /// Unblind `signatures` for outputs of `keyset_id`, with the keys of the keyset each one was
+    /// signed with
+    ///
+    /// The mint signs melt change with the active keyset of the unit if the keyset of the
+    /// outputs was rotated out while the payment was in flight.
+    pub(crate) async fn construct_signed_proofs(
+        &self,
+        signatures: Vec<BlindSignature>,
+        rs: Vec<SecretKey>,
+        secrets: Vec<Secret>,
+        keyset_id: Id,
+        keys: &Keys,
+    ) -> Result<Proofs, Error> {
+        if signatures.len() != rs.len()
+            || signatures.len() != secrets.len()
+            || signatures
+                .iter()
+                .all(|signature| signature.keyset_id == keyset_id)
+        {
+            return Ok(construct_proofs(signatures, rs, secrets, keys)?);
+        }
+
+        let mut other_keys = HashMap::new();
+        let mut proofs = Proofs::with_capacity(signatures.len());
+        for ((signature, r), secret) in signatures.into_iter().zip(rs).zip(secrets) {
+            let signature_keys = if signature.keyset_id == keyset_id {
+                keys
+            } else {
+                match other_keys.entry(signature.keyset_id) {
+                    Entry::Occupied(entry) => entry.into_mut(),
+                    Entry::Vacant(entry) => {
+                        entry.insert(self.load_keyset_keys(signature.keyset_id).await?)
+                    }
+                }
+            };
+            proofs.extend(construct_proofs(
+                vec![signature],
+                vec![r],
+                vec![secret],
+                signature_keys,
+            )?);
+        }
+
+        Ok(proofs)
+    }
+
     /// Alias of get_mint_keysets, kept for backwards compatibility reasons
     #[instrument(skip(self))]
     pub async fn load_mint_keysets(&self) -> Result<Vec<KeySetInfo>, Error> {
@@ -64,6 +121,126 @@ impl Wallet {
         }
     }
 
+    /// Get all keysets of the wallet unit from metadata cache, including inactive ones
+    ///
+    /// Inactive keysets cannot sign new outputs but their proofs can still be spent.
+    #[instrument(skip(self))]
+    pub async fn get_all_mint_keysets(&self) -> Result<Vec<KeySetInfo>, Error> {
+        let keysets = self
+            .metadata_cache
+            .load(&self.localstore, &self.client, {
+                let ttl = self.metadata_cache_ttl.read();
+                *ttl
+            })
+            .await?
+            .keysets
+            .values()
+            .filter(|keyset| keyset.unit == self.unit)
+            .map(|keyset| (**keyset).clone())
+            .collect::<Vec<_>>();
+
+        if !keysets.is_empty() {
+            Ok(keysets)
+        } else {
+            Err(Error::UnknownKeySet)
+        }
+    }
+
+    /// Fetch all keysets of the wallet unit from the mint, including inactive ones
+    ///
+    /// Always fetches fresh data, so keysets rotated out since the cache was populated are
+    /// listed too. Used to enumerate every keyset the mint may have signed ecash with.
+    #[instrument(skip(self))]
+    pub async fn fetch_all_mint_keysets(&self) -> Result<Vec<KeySetInfo>, Error> {
+        let keysets = self
+            .metadata_cache
+            .load_from_mint(&self.localstore, &self.client)
+            .await?
+            .keysets
+            .values()
+            .filter(|keyset| keyset.unit == self.unit)
+            .map(|keyset| (**keyset).clone())
+            .collect::<Vec<_>>();
+
+        if !keysets.is_empty() {
+            Ok(keysets)
+        } else {
+            Err(Error::UnknownKeySet)
+        }
+    }
+
+    /// Fetch every keyset of the mint with its keys and persist them
+    ///
+    /// Keysets of every unit are fetched, active or not, and kept once the mint stops listing
+    /// them. With them cached, tokens of the mint can be inspected with
+    /// [`Wallet::inspect_token`] and received while the mint is briefly unreachable.
+    ///
+    /// Returns every keyset cached for the mint.
+    #[instrument(skip(self))]
+    pub async fn prefetch_keysets(&self) -> Result<Vec<KeySetInfo>, Error> {
+        let metadata = self
+            .metadata_cache
+            .load_from_mint(&self.localstore, &self.client)
+            .await?;
+
+        let missing_keys = metadata
+            .keysets
+            .keys()
+            .filter(|id| !metadata.keys.contains_key(id))
+            .count();
+        if missing_keys > 0 {
+            tracing::warn!(
+                "Keys of {} keysets of {} could not be fetched",
+                missing_keys,
+                self.mint_url
+            );
+        }
+
+        Ok(metadata
+            .keysets
+            .values()
+            .map(|keyset| (**keyset).clone())
+            .collect())
+    }
+
+    /// Keysets of every unit ever seen for the mint, to resolve the keyset ids of a token
+    ///
+    /// Falls back to the keysets persisted in the database when the mint can't be reached.
+    pub(crate) async fn load_token_keysets(&self) -> Result<Vec<KeySetInfo>, Error> {
+        let metadata = match self
+            .metadata_cache
+            .load(&self.localstore, &self.client, {
+                let ttl = self.metadata_cache_ttl.read();
+                *ttl
+            })
+            .await
+        {
+            Ok(metadata) => metadata,
+            Err(err) if mint_unreachable(&err) => {
+                let metadata = self
+                    .metadata_cache
+                    .load_from_storage(&self.localstore)
+                    .await?;
+                if metadata.keysets.is_empty() {
+                    return Err(err);
+                }
+                tracing::warn!(
+                    "Mint {} unreachable, using its cached keysets: {}",
+                    self.mint_url,
+                    err
+                );
+                metadata
+            }
+            Err(err) => return Err(err),
+        };
+
+        Ok(metadata
+            .keysets
+            .values()
+            .map(|keyset| (**keyset).clone())
+            .collect())
+    }
+
     /// Refresh keysets by fetching the latest from mint - always fetches fresh data
     ///
     /// Forces a fresh fetch of keyset information from the mint server,
@@ -145,15 +322,16 @@ impl Wallet {
         let mut fees = HashMap::new();
         for keyset in metadata.keysets.values() {
             let keys = self.load_keyset_keys(keyset.id).await?;
+            // Amounts above the max amount of the keyset are never requested from the mint
             fees.insert(
                 keyset.id,
-                (
+                FeeAndAmounts::from((
                     keyset.input_fee_ppk,
                     keys.iter()
                         .map(|(amount, _)| amount.to_u64())
                         .collect::<Vec<_>>(),
-                )
-                    .into(),
+                ))
+                .with_max_amount(keyset.max_amount),
             );
         }
 
@@ -1,14 +1,14 @@
 use std::collections::HashMap;
 use std::str::FromStr;
 
-use cdk_common::amount::SplitTarget;
+use cdk_common::amount::{KeysetFeeAndAmounts, SplitTarget};
 use cdk_common::nut00::KnownMethod;
-use cdk_common::wallet::{Transaction, TransactionDirection};
+use cdk_common::wallet::{SelectionStrategy, Transaction, TransactionDirection, TransactionKind};
 use cdk_common::PaymentMethod;
 use lightning_invoice::Bolt11Invoice;
 use tracing::instrument;
 
-use crate::dhke::construct_proofs;
+use crate::fees::calculate_fee;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::{
     CurrencyUnit, MeltOptions, MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltRequest,
@@ -16,8 +16,9 @@ use crate::nuts::{
 };
 use crate::types::{Melted, ProofInfo};
 use crate::util::unix_time;
+use crate::wallet::melt::{verify_melt_preimage, with_quote_input_fee};
 use crate::wallet::send::split_proofs_for_send;
-use crate::wallet::MeltQuote;
+use crate::wallet::{JournalOperation, MeltQuote};
 use crate::{ensure_cdk, Amount, Error, Wallet};
 
 impl Wallet {
@@ -91,6 +92,7 @@ impl Wallet {
             expiry: quote_res.expiry,
             payment_preimage: quote_res.payment_preimage,
             payment_method: PaymentMethod::Known(KnownMethod::Bolt11),
+            fees: quote_res.fees,
         };
 
         self.localstore.add_melt_quote(quote.clone()).await?;
@@ -118,6 +120,9 @@ impl Wallet {
                 }
 
                 quote.state = response.state;
+                if response.fees.is_some() {
+                    quote.fees = response.fees.clone();
+                }
                 self.localstore.add_melt_quote(quote).await?;
             }
             None => {
@@ -143,7 +148,8 @@ impl Wallet {
         proofs: Proofs,
         metadata: HashMap<String, String>,
     ) -> Result<Melted, Error> {
-        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        let active_keyset = self.fetch_active_keyset().await?;
+        let active_keyset_id = active_keyset.id;
         let mut quote_info = self
             .localstore
             .get_melt_quote(quote_id)
@@ -155,8 +161,22 @@ impl Wallet {
             Error::ExpiredQuote(quote_info.expiry, unix_time())
         );
 
+        // The mint requires the input fee on top of the quote amount and fee reserve
+        let keyset_fees: HashMap<cdk_common::Id, u64> = self
+            .melt_keyset_fees_and_amounts(&quote_info)
+            .await?
+            .iter()
+            .map(|(key, values)| (*key, values.fee()))
+            .collect();
+        let input_fee = calculate_fee(&proofs.count_by_keyset(), &keyset_fees)?.total;
+
         let proofs_total = proofs.total_amount()?;
-        if proofs_total < quote_info.amount + quote_info.fee_reserve {
+        let required_total = quote_info
+            .amount
+            .checked_add(quote_info.fee_reserve)
+            .and_then(|total| total.checked_add(input_fee))
+            .ok_or(Error::AmountOverflow)?;
+        if proofs_total < required_total {
             return Err(Error::InsufficientFunds);
         }
 
@@ -171,16 +191,18 @@ impl Wallet {
 
         // Calculate change accounting for input fees
         // The mint deducts input fees from available funds before calculating change
-        let input_fee = self.get_proofs_fee(&proofs).await?.total;
         let change_amount = proofs_total - quote_info.amount - input_fee;
 
+        let mut counters = 0..0;
         let premint_secrets = if change_amount <= Amount::ZERO {
             PreMintSecrets::new(active_keyset_id)
         } else {
-            // TODO: consolidate this calculation with from_seed_blank into a shared function
-            // Calculate how many secrets will be needed using the same logic as from_seed_blank
-            let num_secrets =
-                ((u64::from(change_amount) as f64).log2().ceil() as u64).max(1) as u32;
+            // The change outputs are derived from the seed, so change signed by the mint can be
+            // restored even if the response is lost
+            let num_secrets = PreMintSecrets::blank_count_with_max_amount(
+                change_amount,
+                active_keyset.max_amount,
+            );
 
             tracing::debug!(
                 "Incrementing keyset {} counter by {}",
@@ -195,8 +217,15 @@ impl Wallet {
                 .await?;
 
             let count = new_counter - num_secrets;
+            counters = count..new_counter;
 
-            PreMintSecrets::from_seed_blank(active_keyset_id, count, &self.seed, change_amount)?
+            PreMintSecrets::from_seed_blank_with_max_amount(
+                active_keyset_id,
+                count,
+                &self.seed,
+                change_amount,
+                active_keyset.max_amount,
+            )?
         };
 
         let request = MeltRequest::new(
@@ -205,6 +234,16 @@ impl Wallet {
             Some(premint_secrets.blinded_messages()),
         );
 
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Melt,
+                Some(quote_id),
+                counters,
+                &premint_secrets,
+                proofs.ys()?,
+            )
+            .await?;
+
         let melt_response = match quote_info.payment_method {
             cdk_common::PaymentMethod::Known(cdk_common::nut00::KnownMethod::Bolt11) => {
                 self.try_proof_operation_or_reclaim(
@@ -248,26 +287,39 @@ impl Wallet {
                     _ => num_change_proof,
                 };
 
-                Some(construct_proofs(
-                    change,
-                    premint_secrets.rs()[..num_change_proof].to_vec(),
-                    premint_secrets.secrets()[..num_change_proof].to_vec(),
-                    &active_keys,
-                )?)
+                Some(
+                    self.construct_signed_proofs(
+                        change,
+                        premint_secrets.rs()[..num_change_proof].to_vec(),
+                        premint_secrets.secrets()[..num_change_proof].to_vec(),
+                        active_keyset_id,
+                        &active_keys,
+                    )
+                    .await?,
+                )
             }
             None => None,
         };
 
         let payment_preimage = melt_response.payment_preimage.clone();
         let state = melt_response.state;
+        let preimage_verified = verify_melt_preimage(
+            &quote_info,
+            state,
+            payment_preimage.as_deref(),
+            melt_response.fees.as_ref(),
+        );
 
-        let melted = Melted::from_proofs(
+        let mut melted = Melted::from_proofs(
             state,
             payment_preimage.clone(),
             quote_info.amount,
             proofs.clone(),
             change_proofs.clone(),
         )?;
+        melted.fees = melt_response.fees;
+        melted.preimage_verified = preimage_verified;
+        melted.amount_spent = melt_response.amount_spent;
 
         let change_proof_infos = match change_proofs {
             Some(change_proofs) => {
@@ -299,28 +351,37 @@ impl Wallet {
 
         let deleted_ys = proofs.ys()?;
 
+        // Remove spent proofs, store change and add transaction to store
         self.localstore
-            .update_proofs(change_proof_infos, deleted_ys)
+            .update_proofs_with_transaction(
+                change_proof_infos,
+                deleted_ys,
+                Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Outgoing,
+                    amount: melted.amount,
+                    fee: melted.fee_paid,
+                    unit: self.unit.clone(),
+                    ys: proofs.ys()?,
+                    timestamp: unix_time(),
+                    memo: None,
+                    metadata,
+                    quote_id: Some(quote_id.to_string()),
+                    payment_request: Some(payment_request),
+                    payment_proof: payment_preimage,
+                    payment_method: Some(payment_method),
+                    kind: Some(TransactionKind::Melt),
+                    preimage_verified,
+                    amount_spent: melted.amount_spent,
+                },
+            )
             .await?;
 
-        // Add transaction to store
-        self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Outgoing,
-                amount: melted.amount,
-                fee: melted.fee_paid,
-                unit: self.unit.clone(),
-                ys: proofs.ys()?,
-                timestamp: unix_time(),
-                memo: None,
-                metadata,
-                quote_id: Some(quote_id.to_string()),
-                payment_request: Some(payment_request),
-                payment_proof: payment_preimage,
-                payment_method: Some(payment_method),
-            })
-            .await?;
+        // A pending melt has no change yet, it stays in the journal so the change can be
+        // recovered once the mint settles the payment
+        if state != cdk_common::MeltQuoteState::Pending {
+            self.journal_finish(&journal_id).await?;
+        }
 
         Ok(melted)
     }
@@ -390,6 +451,27 @@ impl Wallet {
         &self,
         quote_id: &str,
         metadata: HashMap<String, String>,
+    ) -> Result<Melted, Error> {
+        self.melt_with_metadata_and_strategy(quote_id, metadata, SelectionStrategy::default())
+            .await
+    }
+
+    /// Melt selecting the input proofs with the given [`SelectionStrategy`]
+    #[instrument(skip(self))]
+    pub async fn melt_with_strategy(
+        &self,
+        quote_id: &str,
+        strategy: SelectionStrategy,
+    ) -> Result<Melted, Error> {
+        self.melt_with_metadata_and_strategy(quote_id, HashMap::new(), strategy)
+            .await
+    }
+
+    async fn melt_with_metadata_and_strategy(
+        &self,
+        quote_id: &str,
+        metadata: HashMap<String, String>,
+        strategy: SelectionStrategy,
     ) -> Result<Melted, Error> {
         let quote_info = self
             .localstore
@@ -402,6 +484,61 @@ impl Wallet {
             Error::ExpiredQuote(quote_info.expiry, unix_time())
         );
 
+        let inputs = self.select_melt_inputs(&quote_info, strategy).await?;
+        let mut final_proofs = inputs.proofs;
+
+        if !inputs.proofs_to_swap.is_empty() {
+            tracing::debug!(
+                "Swapping {} proofs to get {} sats (swap fee: {} sats)",
+                inputs.proofs_to_swap.len(),
+                inputs.swap_amount,
+                inputs.swap_fee
+            );
+
+            if let Some(swapped) = self
+                .try_proof_operation_or_reclaim(
+                    inputs.proofs_to_swap.clone(),
+                    self.swap(
+                        Some(inputs.swap_amount),
+                        SplitTarget::None,
+                        inputs.proofs_to_swap,
+                        None,
+                        false, // fees already accounted for in inputs_total
+                    ),
+                )
+                .await?
+            {
+                final_proofs.extend(swapped);
+            }
+        }
+
+        self.melt_proofs_with_metadata(quote_id, final_proofs, metadata)
+            .await
+    }
+
+    /// Fees and amounts of the keysets of the mint, with the input fee advertised with `quote`
+    async fn melt_keyset_fees_and_amounts(
+        &self,
+        quote: &MeltQuote,
+    ) -> Result<KeysetFeeAndAmounts, Error> {
+        let active_keyset_id = self.get_active_keyset().await?.id;
+
+        Ok(with_quote_input_fee(
+            self.get_keyset_fees_and_amounts().await?,
+            active_keyset_id,
+            quote.fees.as_ref(),
+        ))
+    }
+
+    /// Select the proofs to pay a melt quote with, without reserving them
+    ///
+    /// Proofs matching the amount and fee reserve exactly are used directly. Otherwise some proofs
+    /// have to be swapped first for the exact denominations, which adds a swap fee.
+    pub(super) async fn select_melt_inputs(
+        &self,
+        quote_info: &MeltQuote,
+        strategy: SelectionStrategy,
+    ) -> Result<MeltInputs, Error> {
         let inputs_needed_amount = quote_info.amount + quote_info.fee_reserve;
 
         let active_keyset_ids = self
@@ -410,49 +547,58 @@ impl Wallet {
             .into_iter()
             .map(|k| k.id)
             .collect();
-        let keyset_fees_and_amounts = self.get_keyset_fees_and_amounts().await?;
+        let keyset_fees_and_amounts = self.melt_keyset_fees_and_amounts(quote_info).await?;
+        let keyset_fees: HashMap<cdk_common::Id, u64> = keyset_fees_and_amounts
+            .iter()
+            .map(|(key, values)| (*key, values.fee()))
+            .collect();
 
         let available_proofs = self.get_unspent_proofs().await?;
 
         // Two-step proof selection for melt:
-        // Step 1: Try to select proofs that exactly match inputs_needed_amount.
+        // Step 1: Try to select proofs that exactly match inputs_needed_amount and their input fee.
         //         If successful, no swap is required and we avoid paying swap fees.
         // Step 2: If exact match not possible, we need to swap to get optimal denominations.
         //         In this case, we must select more proofs to cover the additional swap fees.
         {
-            let input_proofs = Wallet::select_proofs(
+            let input_proofs = Wallet::select_proofs_with_strategy(
                 inputs_needed_amount,
                 available_proofs.clone(),
                 &active_keyset_ids,
                 &keyset_fees_and_amounts,
                 true,
+                strategy,
             )?;
             let proofs_total = input_proofs.total_amount()?;
+            let input_fee = calculate_fee(&input_proofs.count_by_keyset(), &keyset_fees)?.total;
 
             // If exact match, use proofs directly without swap
-            if proofs_total == inputs_needed_amount {
-                return self
-                    .melt_proofs_with_metadata(quote_id, input_proofs, metadata)
-                    .await;
+            if Some(proofs_total) == inputs_needed_amount.checked_add(input_fee) {
+                return Ok(MeltInputs {
+                    proofs: input_proofs,
+                    proofs_to_swap: Proofs::new(),
+                    swap_amount: Amount::ZERO,
+                    swap_fee: Amount::ZERO,
+                    input_fee,
+                    inputs_total: proofs_total,
+                });
             }
         }
 
         let active_keyset_id = self.get_active_keyset().await?.id;
-        let fee_and_amounts = self
-            .get_keyset_fees_and_amounts_by_id(active_keyset_id)
-            .await?;
+        let fee_and_amounts = keyset_fees_and_amounts
+            .get(&active_keyset_id)
+            .cloned()
+            .ok_or(Error::UnknownKeySet)?;
 
         // Calculate optimal denomination split and the fee for those proofs
         // First estimate based on inputs_needed_amount to get target_fee
         let initial_split = inputs_needed_amount.split(&fee_and_amounts);
-        let target_fee = self
-            .get_proofs_fee_by_count(
-                vec![(active_keyset_id, initial_split.len() as u64)]
-                    .into_iter()
-                    .collect(),
-            )
-            .await?
-            .total;
+        let target_fee = calculate_fee(
+            &HashMap::from([(active_keyset_id, initial_split.len() as u64)]),
+            &keyset_fees,
+        )?
+        .total;
 
         // Since we could not select the correct inputs amount needed for melting,
         // we select again this time including the amount we will now have to pay as a fee for the swap.
@@ -460,12 +606,13 @@ impl Wallet {
 
         // Recalculate target amounts based on the actual total we need (including fee)
         let target_amounts = inputs_total_needed.split(&fee_and_amounts);
-        let input_proofs = Wallet::select_proofs(
+        let input_proofs = Wallet::select_proofs_with_strategy(
             inputs_total_needed,
             available_proofs,
             &active_keyset_ids,
             &keyset_fees_and_amounts,
             true,
+            strategy,
         )?;
         let proofs_total = input_proofs.total_amount()?;
 
@@ -476,11 +623,6 @@ impl Wallet {
             inputs_total_needed
         );
 
-        let keyset_fees: HashMap<cdk_common::Id, u64> = keyset_fees_and_amounts
-            .iter()
-            .map(|(key, values)| (*key, values.fee()))
-            .collect();
-
         let split_result = split_proofs_for_send(
             input_proofs,
             &target_amounts,
@@ -491,38 +633,37 @@ impl Wallet {
             false,
         )?;
 
-        let mut final_proofs = split_result.proofs_to_send;
-
-        if !split_result.proofs_to_swap.is_empty() {
-            let swap_amount = inputs_total_needed
-                .checked_sub(final_proofs.total_amount()?)
-                .ok_or(Error::AmountOverflow)?;
-
-            tracing::debug!(
-                "Swapping {} proofs to get {} sats (swap fee: {} sats)",
-                split_result.proofs_to_swap.len(),
-                swap_amount,
-                split_result.swap_fee
-            );
-
-            if let Some(swapped) = self
-                .try_proof_operation_or_reclaim(
-                    split_result.proofs_to_swap.clone(),
-                    self.swap(
-                        Some(swap_amount),
-                        SplitTarget::None,
-                        split_result.proofs_to_swap,
-                        None,
-                        false, // fees already accounted for in inputs_total_needed
-                    ),
-                )
-                .await?
-            {
-                final_proofs.extend(swapped);
-            }
-        }
+        let swap_amount = if split_result.proofs_to_swap.is_empty() {
+            Amount::ZERO
+        } else {
+            inputs_total_needed
+                .checked_sub(split_result.proofs_to_send.total_amount()?)
+                .ok_or(Error::AmountOverflow)?
+        };
 
-        self.melt_proofs_with_metadata(quote_id, final_proofs, metadata)
-            .await
+        Ok(MeltInputs {
+            proofs: split_result.proofs_to_send,
+            proofs_to_swap: split_result.proofs_to_swap,
+            swap_amount,
+            swap_fee: split_result.swap_fee,
+            input_fee: target_fee,
+            inputs_total: inputs_total_needed,
+        })
     }
 }
+
+/// Proofs selected to pay a melt quote
+pub(super) struct MeltInputs {
+    /// Proofs used for the melt as they are
+    pub proofs: Proofs,
+    /// Proofs to swap first for proofs worth `swap_amount`, which are added to the melt inputs
+    pub proofs_to_swap: Proofs,
+    /// Amount the swap has to produce
+    pub swap_amount: Amount,
+    /// Fee of the swap
+    pub swap_fee: Amount,
+    /// Input fee of the melt itself
+    pub input_fee: Amount,
+    /// Total value of the melt inputs
+    pub inputs_total: Amount,
+}
@@ -61,6 +61,7 @@ impl Wallet {
             expiry: quote_res.expiry,
             payment_preimage: quote_res.payment_preimage,
             payment_method: PaymentMethod::Known(KnownMethod::Bolt12),
+            fees: quote_res.fees,
         };
 
         self.localstore.add_melt_quote(quote.clone()).await?;
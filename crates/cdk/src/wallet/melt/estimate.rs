@@ -0,0 +1,140 @@
+//! Melt estimates
+//!
+//! Lets apps show what paying an invoice will cost before the user confirms,
+//! and then pay with the same quote.
+
+use cdk_common::wallet::{MeltQuote, SelectionStrategy};
+use cdk_common::MeltQuoteState;
+use tracing::instrument;
+
+use crate::nuts::{MeltOptions, Proofs};
+use crate::types::Melted;
+use crate::util::unix_time;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Stored quotes expiring sooner than this are not reused for an estimate
+const MELT_QUOTE_REUSE_MIN_TTL: u64 = 30;
+
+/// Cost of paying a melt quote, see [`Wallet::estimate_melt`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MeltEstimate {
+    /// Quote the estimate is for
+    pub quote: MeltQuote,
+    /// Proofs that would be selected, not reserved
+    pub proofs: Proofs,
+    /// Amount paid to the payee
+    pub amount: Amount,
+    /// Lightning fee reserve of the quote
+    pub fee_reserve: Amount,
+    /// Input fee of the melt, from the keyset `input_fee_ppk`
+    pub input_fee: Amount,
+    /// Fee of the swap needed first for exact denominations, zero if none
+    pub swap_fee: Amount,
+    /// Most the wallet can spend, if the whole fee reserve is used
+    pub total: Amount,
+    /// Change returned if none of the fee reserve is used
+    pub expected_change: Amount,
+    /// Balance left in the worst case, after spending `total`
+    pub remaining_balance: Amount,
+}
+
+impl MeltEstimate {
+    /// Whether the quote can still be paid with this estimate
+    pub fn is_expired(&self) -> bool {
+        self.quote.expiry <= unix_time()
+    }
+}
+
+impl Wallet {
+    /// Estimate the cost of paying a bolt11 invoice
+    ///
+    /// Reuses an unpaid stored quote for the same invoice if it is still valid for a while,
+    /// otherwise requests a new one (always when `options` are given). The proofs that would be
+    /// selected and the resulting fees are computed without reserving anything. Fails with
+    /// [`Error::InsufficientFunds`] if the balance does not cover the payment.
+    #[instrument(skip(self, request))]
+    pub async fn estimate_melt(
+        &self,
+        request: String,
+        options: Option<MeltOptions>,
+    ) -> Result<MeltEstimate, Error> {
+        let reusable = match options {
+            Some(_) => None,
+            None => self
+                .localstore
+                .get_melt_quotes()
+                .await?
+                .into_iter()
+                .find(|quote| {
+                    quote.request == request
+                        && quote.unit == self.unit
+                        && quote.state == MeltQuoteState::Unpaid
+                        && quote.expiry > unix_time() + MELT_QUOTE_REUSE_MIN_TTL
+                }),
+        };
+
+        let quote = match reusable {
+            Some(quote) => quote,
+            None => self.melt_quote(request, options).await?,
+        };
+
+        self.estimate_melt_quote(quote).await
+    }
+
+    /// Estimate the cost of paying a stored melt quote
+    #[instrument(skip(self, quote))]
+    pub async fn estimate_melt_quote(&self, quote: MeltQuote) -> Result<MeltEstimate, Error> {
+        ensure_cdk!(
+            quote.expiry > unix_time(),
+            Error::ExpiredQuote(quote.expiry, unix_time())
+        );
+
+        let inputs = self
+            .select_melt_inputs(&quote, SelectionStrategy::default())
+            .await?;
+
+        let mut proofs = inputs.proofs;
+        proofs.extend(inputs.proofs_to_swap);
+
+        let total = inputs
+            .inputs_total
+            .checked_add(inputs.swap_fee)
+            .ok_or(Error::AmountOverflow)?;
+        let expected_change = inputs
+            .inputs_total
+            .checked_sub(quote.amount)
+            .and_then(|change| change.checked_sub(inputs.input_fee))
+            .unwrap_or(Amount::ZERO);
+        let remaining_balance = self
+            .total_balance()
+            .await?
+            .checked_sub(total)
+            .ok_or(Error::InsufficientFunds)?;
+
+        Ok(MeltEstimate {
+            amount: quote.amount,
+            fee_reserve: quote.fee_reserve,
+            quote,
+            proofs,
+            input_fee: inputs.input_fee,
+            swap_fee: inputs.swap_fee,
+            total,
+            expected_change,
+            remaining_balance,
+        })
+    }
+
+    /// Pay the quote of a [`MeltEstimate`] without requesting a new quote
+    ///
+    /// Proofs are selected again when melting, as the balance may have changed since the
+    /// estimate.
+    #[instrument(skip(self, estimate))]
+    pub async fn melt_estimate(&self, estimate: &MeltEstimate) -> Result<Melted, Error> {
+        ensure_cdk!(
+            !estimate.is_expired(),
+            Error::ExpiredQuote(estimate.quote.expiry, unix_time())
+        );
+
+        self.melt(&estimate.quote.id).await
+    }
+}
@@ -1,24 +1,36 @@
 //! Melt Lightning Address
 //!
-//! Implementation of melt functionality for Lightning addresses
+//! Implementation of melt functionality for Lightning addresses and LNURL-pay
 
+use std::collections::HashMap;
 use std::str::FromStr;
 
 use cdk_common::wallet::MeltQuote;
+use cdk_common::MeltQuoteState;
 use tracing::instrument;
 
-use crate::lightning_address::LightningAddress;
+use crate::lightning_address::{LnurlPayEndpoint, LnurlPayInvoice, SuccessAction};
+use crate::types::Melted;
 use crate::{Amount, Error, Wallet};
 
+/// Result of [`Wallet::melt_to_lightning_address`]
+#[derive(Debug, Clone)]
+pub struct LightningAddressMelted {
+    /// Result of the melt
+    pub melted: Melted,
+    /// Success action of the recipient, only set once the payment is made
+    pub success_action: Option<SuccessAction>,
+}
+
 impl Wallet {
     /// Melt Quote for Lightning address
     ///
-    /// This method resolves a Lightning address (e.g., "alice@example.com") to a Lightning invoice
-    /// and then creates a melt quote for that invoice.
+    /// This method resolves a Lightning address (e.g., "alice@example.com") or a bech32 encoded
+    /// LNURL to a Lightning invoice and then creates a melt quote for that invoice.
     ///
     /// # Arguments
     ///
-    /// * `lightning_address` - Lightning address in the format "user@domain.com"
+    /// * `lightning_address` - Lightning address in the format "user@domain.com", or LNURL
     /// * `amount_msat` - Amount to pay in millisatoshis
     ///
     /// # Returns
@@ -32,6 +44,7 @@ impl Wallet {
     /// - HTTP request to the Lightning address service fails
     /// - The amount is outside the acceptable range
     /// - The service returns an error
+    /// - The invoice is not for the amount or does not commit to the service metadata
     /// - The mint fails to provide a quote for the invoice
     ///
     /// # Example
@@ -52,10 +65,68 @@ impl Wallet {
         lightning_address: &str,
         amount_msat: impl Into<Amount>,
     ) -> Result<MeltQuote, Error> {
-        let amount = amount_msat.into();
+        let pay_invoice = self
+            .request_lnurl_invoice(lightning_address, amount_msat.into(), None)
+            .await?;
+
+        // Create a melt quote for the invoice using the existing bolt11 functionality
+        // The invoice from LNURL already contains the amount, so we don't need amountless options
+        self.melt_quote(pay_invoice.invoice.to_string(), None).await
+    }
+
+    /// Pay a Lightning address or LNURL-pay endpoint
+    ///
+    /// Requests an invoice for `amount_msat` with an optional `comment` for the recipient,
+    /// verifies it against the LNURL-pay metadata and melts proofs to pay it. The address is
+    /// saved in the metadata of the transaction.
+    ///
+    /// # Errors
+    ///
+    /// Fails as [`Wallet::melt_lightning_address_quote`] and [`Wallet::melt`] do, and if the
+    /// comment is longer than the service accepts.
+    #[instrument(skip(self, amount_msat, comment), fields(lightning_address = %lightning_address))]
+    pub async fn melt_to_lightning_address<A>(
+        &self,
+        lightning_address: &str,
+        amount_msat: A,
+        comment: Option<&str>,
+    ) -> Result<LightningAddressMelted, Error>
+    where
+        A: Into<Amount>,
+    {
+        let pay_invoice = self
+            .request_lnurl_invoice(lightning_address, amount_msat.into(), comment)
+            .await?;
+
+        let quote = self
+            .melt_quote(pay_invoice.invoice.to_string(), None)
+            .await?;
 
+        let metadata = HashMap::from([(
+            "lightning_address".to_string(),
+            lightning_address.trim().to_string(),
+        )]);
+        let melted = self.melt_with_metadata(&quote.id, metadata).await?;
+
+        let success_action = pay_invoice
+            .success_action
+            .filter(|_| melted.state == MeltQuoteState::Paid);
+
+        Ok(LightningAddressMelted {
+            melted,
+            success_action,
+        })
+    }
+
+    /// Resolve a Lightning address or LNURL and request a verified invoice from it
+    pub(crate) async fn request_lnurl_invoice(
+        &self,
+        lightning_address: &str,
+        amount_msat: Amount,
+        comment: Option<&str>,
+    ) -> Result<LnurlPayInvoice, Error> {
         // Parse the Lightning address
-        let ln_address = LightningAddress::from_str(lightning_address).map_err(|e| {
+        let endpoint = LnurlPayEndpoint::from_str(lightning_address).map_err(|e| {
             tracing::error!(
                 "Failed to parse Lightning address '{}': {}",
                 lightning_address,
@@ -64,11 +135,11 @@ impl Wallet {
             Error::LightningAddressParse(e.to_string())
         })?;
 
-        tracing::debug!("Resolving Lightning address: {}", ln_address);
+        tracing::debug!("Resolving Lightning address: {}", endpoint);
 
         // Request an invoice from the Lightning address service
-        let invoice = ln_address
-            .request_invoice(&self.client, amount)
+        let pay_invoice = endpoint
+            .request_invoice(&self.client, amount_msat, comment)
             .await
             .map_err(|e| {
                 tracing::error!(
@@ -80,11 +151,9 @@ impl Wallet {
 
         tracing::debug!(
             "Received invoice from Lightning address service: {}",
-            invoice
+            pay_invoice.invoice
         );
 
-        // Create a melt quote for the invoice using the existing bolt11 functionality
-        // The invoice from LNURL already contains the amount, so we don't need amountless options
-        self.melt_quote(invoice.to_string(), None).await
+        Ok(pay_invoice)
     }
 }
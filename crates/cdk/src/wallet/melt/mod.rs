@@ -1,24 +1,112 @@
 use std::collections::HashMap;
+use std::str::FromStr;
 
-use cdk_common::util::unix_time;
-use cdk_common::wallet::{MeltQuote, Transaction, TransactionDirection};
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use cdk_common::amount::{FeeAndAmounts, KeysetFeeAndAmounts};
+use cdk_common::util::{hex, unix_time};
+use cdk_common::wallet::{MeltQuote, Transaction, TransactionDirection, TransactionKind};
 use cdk_common::{
-    Error, MeltQuoteBolt11Response, MeltQuoteState, PaymentMethod, ProofsMethods, State,
+    Error, MeltQuoteBolt11Response, MeltQuoteFees, MeltQuoteState, PaymentMethod, ProofsMethods,
+    State,
 };
+use lightning_invoice::Bolt11Invoice;
 use tracing::instrument;
 
 use crate::nuts::nut00::KnownMethod;
-use crate::nuts::MeltOptions;
+use crate::nuts::{Id, MeltOptions};
 use crate::Wallet;
 
 mod bolt11;
 mod bolt12;
 mod custom;
+mod estimate;
 #[cfg(all(feature = "bip353", not(target_arch = "wasm32")))]
 mod melt_bip353;
 #[cfg(feature = "wallet")]
 mod melt_lightning_address;
 
+pub use estimate::MeltEstimate;
+#[cfg(feature = "wallet")]
+pub use melt_lightning_address::LightningAddressMelted;
+
+/// Check the preimage reported by the mint against the payment hash of the
+/// melted bolt11 invoice
+///
+/// Returns `true` only if the preimage hashes to the invoice payment hash. A
+/// paid melt without a matching preimage is logged as a warning, unless the
+/// mint settled it internally, in which case no lightning payment was made and
+/// there is no preimage to report.
+pub(crate) fn verify_melt_preimage(
+    quote: &MeltQuote,
+    state: MeltQuoteState,
+    preimage: Option<&str>,
+    fees: Option<&MeltQuoteFees>,
+) -> bool {
+    if state != MeltQuoteState::Paid
+        || quote.payment_method != PaymentMethod::Known(KnownMethod::Bolt11)
+    {
+        return false;
+    }
+
+    let internal = fees.is_some_and(|fees| fees.internal);
+
+    let Ok(invoice) = Bolt11Invoice::from_str(&quote.request) else {
+        tracing::warn!("Could not parse invoice of melt quote {}", quote.id);
+        return false;
+    };
+
+    let verified = preimage
+        .and_then(|preimage| hex::decode(preimage).ok())
+        .is_some_and(|preimage| Sha256Hash::hash(&preimage) == *invoice.payment_hash());
+
+    if !verified && !internal {
+        match preimage {
+            Some(preimage) => tracing::warn!(
+                "Mint reported melt quote {} as paid but preimage {} does not match payment hash {}",
+                quote.id,
+                preimage,
+                invoice.payment_hash()
+            ),
+            None => tracing::warn!(
+                "Mint reported melt quote {} as paid without a preimage for payment hash {}",
+                quote.id,
+                invoice.payment_hash()
+            ),
+        }
+    }
+
+    verified
+}
+
+/// Apply the input fee advertised with a melt quote to the active keyset
+///
+/// The mint charges the melt inputs of its active keyset the `input_fee_ppk` it advertised with
+/// the quote, which is used over the cached keyset info as that may be outdated.
+pub(crate) fn with_quote_input_fee(
+    mut keyset_fees_and_amounts: KeysetFeeAndAmounts,
+    active_keyset_id: Id,
+    fees: Option<&MeltQuoteFees>,
+) -> KeysetFeeAndAmounts {
+    if let (Some(fees), Some(fee_and_amounts)) =
+        (fees, keyset_fees_and_amounts.get_mut(&active_keyset_id))
+    {
+        if fees.input_fee_ppk != fee_and_amounts.fee() {
+            tracing::debug!(
+                "Melt quote input fee {} ppk differs from the cached {} ppk of keyset {}",
+                fees.input_fee_ppk,
+                fee_and_amounts.fee(),
+                active_keyset_id
+            );
+        }
+
+        *fee_and_amounts =
+            FeeAndAmounts::from((fees.input_fee_ppk, fee_and_amounts.amounts().to_vec()));
+    }
+
+    keyset_fees_and_amounts
+}
+
 impl Wallet {
     /// Check pending melt quotes
     #[instrument(skip_all)]
@@ -88,6 +176,14 @@ impl Wallet {
                         payment_request: Some(quote.request.clone()),
                         payment_proof: response.payment_preimage.clone(),
                         payment_method: Some(quote.payment_method.clone()),
+                        kind: Some(TransactionKind::Melt),
+                        preimage_verified: verify_melt_preimage(
+                            quote,
+                            response.state,
+                            response.payment_preimage.as_deref(),
+                            response.fees.as_ref(),
+                        ),
+                        amount_spent: response.amount_spent,
                     })
                     .await?;
             }
@@ -185,3 +281,129 @@ impl Wallet {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use bitcoin::secp256k1::{Secp256k1, SecretKey};
+    use cdk_common::nut00::KnownMethod;
+    use cdk_common::{Amount, CurrencyUnit};
+    use lightning_invoice::{Currency, InvoiceBuilder, PaymentSecret};
+
+    use super::*;
+
+    const PREIMAGE: [u8; 32] = [7; 32];
+
+    fn quote() -> MeltQuote {
+        let private_key = SecretKey::from_slice(&[0x42; 32]).unwrap();
+        let invoice = InvoiceBuilder::new(Currency::Bitcoin)
+            .payment_hash(Sha256Hash::hash(&PREIMAGE))
+            .payment_secret(PaymentSecret([42u8; 32]))
+            .amount_milli_satoshis(10_000)
+            .description("melt".to_string())
+            .current_timestamp()
+            .min_final_cltv_expiry_delta(144)
+            .build_signed(|hash| Secp256k1::new().sign_ecdsa_recoverable(hash, &private_key))
+            .unwrap();
+
+        MeltQuote {
+            id: "quote".to_string(),
+            unit: CurrencyUnit::Sat,
+            amount: Amount::from(10),
+            request: invoice.to_string(),
+            fee_reserve: Amount::from(1),
+            state: MeltQuoteState::Paid,
+            expiry: unix_time() + 600,
+            payment_preimage: None,
+            payment_method: PaymentMethod::Known(KnownMethod::Bolt11),
+            fees: None,
+        }
+    }
+
+    fn fees(internal: bool) -> MeltQuoteFees {
+        MeltQuoteFees {
+            ln_fee_reserve: Amount::from(1),
+            input_fee_ppk: 0,
+            ln_fee_paid: None,
+            internal,
+        }
+    }
+
+    #[test]
+    fn test_verify_melt_preimage_matching() {
+        let preimage = hex::encode(PREIMAGE);
+        assert!(verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Paid,
+            Some(&preimage),
+            None
+        ));
+    }
+
+    #[test]
+    fn test_verify_melt_preimage_mismatch() {
+        let preimage = hex::encode([8u8; 32]);
+        assert!(!verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Paid,
+            Some(&preimage),
+            None
+        ));
+        assert!(!verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Paid,
+            Some("not hex"),
+            None
+        ));
+    }
+
+    #[test]
+    fn test_verify_melt_preimage_missing() {
+        assert!(!verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Paid,
+            None,
+            Some(&fees(false))
+        ));
+        // Internal settlements have no preimage, they are not verified either
+        assert!(!verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Paid,
+            None,
+            Some(&fees(true))
+        ));
+    }
+
+    #[test]
+    fn test_quote_input_fee_applied_to_active_keyset() {
+        let active = Id::from_str("00916bbf7ef91a36").unwrap();
+        let inactive = Id::from_str("009a1f293253e41e").unwrap();
+        let cached = KeysetFeeAndAmounts::from([
+            (active, FeeAndAmounts::from((0, vec![1, 2, 4]))),
+            (inactive, FeeAndAmounts::from((100, vec![1, 2]))),
+        ]);
+
+        let fees = MeltQuoteFees {
+            input_fee_ppk: 250,
+            ..fees(false)
+        };
+        let applied = with_quote_input_fee(cached.clone(), active, Some(&fees));
+        assert_eq!(applied[&active].fee(), 250);
+        assert_eq!(applied[&active].amounts(), &[1, 2, 4]);
+        assert_eq!(applied[&inactive].fee(), 100);
+
+        // Quotes without a fee breakdown keep the cached fees
+        let applied = with_quote_input_fee(cached, active, None);
+        assert_eq!(applied[&active].fee(), 0);
+    }
+
+    #[test]
+    fn test_verify_melt_preimage_unpaid() {
+        let preimage = hex::encode(PREIMAGE);
+        assert!(!verify_melt_preimage(
+            &quote(),
+            MeltQuoteState::Pending,
+            Some(&preimage),
+            None
+        ));
+    }
+}
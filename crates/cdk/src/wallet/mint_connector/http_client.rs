@@ -25,14 +25,23 @@ use crate::nuts::{
     AuthToken, CheckStateRequest, CheckStateResponse, Id, KeySet, KeysResponse, KeysetResponse,
     MeltQuoteBolt11Request, MeltQuoteBolt11Response, MeltQuoteCustomRequest, MeltRequest, MintInfo,
     MintQuoteBolt11Request, MintQuoteBolt11Response, MintQuoteCustomRequest,
-    MintQuoteCustomResponse, MintRequest, MintResponse, RestoreRequest, RestoreResponse,
-    SwapRequest, SwapResponse,
+    MintQuoteCustomResponse, MintQuotesRequest, MintQuotesResponse, MintRequest, MintResponse,
+    RestoreRequest, RestoreResponse, SwapRequest, SwapResponse, MAX_RESTORE_PAGE_SIZE,
 };
 #[cfg(feature = "auth")]
 use crate::wallet::auth::{AuthMintConnector, AuthWallet};
 
 type Cache = (u64, HashSet<(nut19::Method, nut19::Path)>);
 
+/// Times a rate limited request is retried before the 429 is returned to the caller
+const MAX_RATE_LIMIT_RETRIES: usize = 5;
+
+/// Wait before retrying a rate limited request when the mint sent no `Retry-After`
+const DEFAULT_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Longest `Retry-After` the client is willing to wait for
+const MAX_RATE_LIMIT_BACKOFF: Duration = Duration::from_secs(60);
+
 /// Http Client
 #[derive(Debug, Clone)]
 pub struct HttpClient<T>
@@ -207,6 +216,61 @@ where
             };
         }
     }
+
+    /// Sends a request to a rate limited endpoint, waiting out the `Retry-After` of the mint
+    /// up to [`MAX_RATE_LIMIT_RETRIES`] times before giving up
+    async fn with_rate_limit_backoff<R, F, Fut>(&self, mut request: F) -> Result<R, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: std::future::Future<Output = Result<R, Error>>,
+    {
+        let mut retries = 0;
+        loop {
+            match request().await {
+                Err(Error::RateLimited(retry_after)) if retries < MAX_RATE_LIMIT_RETRIES => {
+                    retries += 1;
+                    let wait = retry_after
+                        .map(Duration::from_secs)
+                        .unwrap_or(DEFAULT_RATE_LIMIT_BACKOFF)
+                        .min(MAX_RATE_LIMIT_BACKOFF);
+                    tracing::warn!(
+                        "Rate limited by {}, retrying in {}s ({}/{})",
+                        self.mint_url,
+                        wait.as_secs(),
+                        retries,
+                        MAX_RATE_LIMIT_RETRIES
+                    );
+
+                    #[cfg(not(target_arch = "wasm32"))]
+                    tokio::time::sleep(wait).await;
+                    #[cfg(target_arch = "wasm32")]
+                    gloo_timers::future::sleep(wait).await;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    /// Sends one restore request [NUT-09], backing off when the mint rate limits it
+    async fn send_restore_request(
+        &self,
+        url: &Url,
+        request: &RestoreRequest,
+    ) -> Result<RestoreResponse, Error> {
+        self.with_rate_limit_backoff(|| async move {
+            #[cfg(feature = "auth")]
+            let auth_token = self
+                .get_auth_token(Method::Post, RoutePath::Restore)
+                .await?;
+
+            #[cfg(not(feature = "auth"))]
+            let auth_token = None;
+            self.transport
+                .http_post(url.clone(), auth_token, request)
+                .await
+        })
+        .await
+    }
 }
 
 #[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
@@ -474,29 +538,89 @@ where
         &self,
         request: CheckStateRequest,
     ) -> Result<CheckStateResponse, Error> {
-        let url = self.mint_url.join_paths(&["v1", "checkstate"])?;
-        #[cfg(feature = "auth")]
-        let auth_token = self
-            .get_auth_token(Method::Post, RoutePath::Checkstate)
-            .await?;
-
-        #[cfg(not(feature = "auth"))]
-        let auth_token = None;
-        self.transport.http_post(url, auth_token, &request).await
+        let url = &self.mint_url.join_paths(&["v1", "checkstate"])?;
+        let request = &request;
+        self.with_rate_limit_backoff(|| async move {
+            #[cfg(feature = "auth")]
+            let auth_token = self
+                .get_auth_token(Method::Post, RoutePath::Checkstate)
+                .await?;
+
+            #[cfg(not(feature = "auth"))]
+            let auth_token = None;
+            self.transport
+                .http_post(url.clone(), auth_token, request)
+                .await
+        })
+        .await
     }
 
     /// Restore request [NUT-13]
+    ///
+    /// Requests with more than [`MAX_RESTORE_PAGE_SIZE`] outputs are sent in pages, following
+    /// the offset returned by the mint, and the pages are merged into one response. Mints without
+    /// pagination answer every page in full, which gives the same result.
     #[instrument(skip(self, request), fields(mint_url = %self.mint_url))]
     async fn post_restore(&self, request: RestoreRequest) -> Result<RestoreResponse, Error> {
         let url = self.mint_url.join_paths(&["v1", "restore"])?;
-        #[cfg(feature = "auth")]
-        let auth_token = self
-            .get_auth_token(Method::Post, RoutePath::Restore)
-            .await?;
 
-        #[cfg(not(feature = "auth"))]
-        let auth_token = None;
-        self.transport.http_post(url, auth_token, &request).await
+        if request.limit.is_some() || request.outputs.len() as u64 <= MAX_RESTORE_PAGE_SIZE {
+            return self.send_restore_request(&url, &request).await;
+        }
+
+        let outputs = request.outputs;
+        let mut response = RestoreResponse {
+            outputs: Vec::new(),
+            signatures: Vec::new(),
+            promises: None,
+            offset: None,
+            limit: None,
+            next_offset: None,
+        };
+
+        let mut offset = 0;
+        while offset < outputs.len() {
+            let end = outputs.len().min(offset + MAX_RESTORE_PAGE_SIZE as usize);
+            let page_request = RestoreRequest::paginated(
+                outputs[offset..end].to_vec(),
+                offset as u64,
+                MAX_RESTORE_PAGE_SIZE,
+            );
+            let page = self.send_restore_request(&url, &page_request).await?;
+
+            response.outputs.extend(page.outputs);
+            response.signatures.extend(page.signatures);
+            if let Some(promises) = page.promises {
+                response
+                    .promises
+                    .get_or_insert_with(Vec::new)
+                    .extend(promises);
+            }
+
+            // The mint may answer fewer outputs than asked for, continue where it stopped
+            offset = match page.next_offset {
+                Some(next_offset) if next_offset as usize > offset => {
+                    (next_offset as usize).min(end)
+                }
+                _ => end,
+            };
+        }
+
+        Ok(response)
+    }
+
+    /// Mint quotes locked to the pubkeys of a signed request [NUT-20]
+    ///
+    /// The request is authenticated by its signatures, no auth token is sent. Waits out the
+    /// rate limit of the mint, as recovering the quotes of a wallet may take a few requests.
+    #[instrument(skip_all, fields(mint_url = %self.mint_url))]
+    async fn post_mint_quotes(
+        &self,
+        request: MintQuotesRequest,
+    ) -> Result<MintQuotesResponse<String>, Error> {
+        let url = self.mint_url.join_paths(&["v1", "mint", "quotes"])?;
+        self.with_rate_limit_backoff(|| self.transport.http_post(url.clone(), None, &request))
+            .await
     }
 
     /// Mint Quote Bolt12 [NUT-23]
@@ -748,3 +872,210 @@ where
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::str::FromStr;
+    use std::sync::Mutex;
+
+    use cdk_common::{Amount, BlindSignature, BlindedMessage, PublicKey, SecretKey};
+
+    use super::*;
+
+    /// Mint answering restore requests with a signature for every output, at most
+    /// `PAGE_SIZE` outputs per paginated request, after rate limiting the first
+    /// `rate_limited` requests
+    #[derive(Debug, Clone, Default)]
+    struct RestoreTransport {
+        requests: Arc<Mutex<Vec<RestoreRequest>>>,
+        rate_limited: Arc<Mutex<usize>>,
+    }
+
+    impl RestoreTransport {
+        const PAGE_SIZE: usize = 600;
+    }
+
+    #[async_trait]
+    impl Transport for RestoreTransport {
+        #[cfg(all(feature = "bip353", not(target_arch = "wasm32")))]
+        async fn resolve_dns_txt(&self, _domain: &str) -> Result<Vec<String>, Error> {
+            unimplemented!()
+        }
+
+        fn with_proxy(
+            &mut self,
+            _proxy: Url,
+            _host_matcher: Option<&str>,
+            _accept_invalid_certs: bool,
+        ) -> Result<(), Error> {
+            unimplemented!()
+        }
+
+        async fn http_get<R>(&self, _url: Url, _auth: Option<AuthToken>) -> Result<R, Error>
+        where
+            R: DeserializeOwned,
+        {
+            unimplemented!()
+        }
+
+        async fn http_post<P, R>(
+            &self,
+            _url: Url,
+            _auth_token: Option<AuthToken>,
+            payload: &P,
+        ) -> Result<R, Error>
+        where
+            P: Serialize + ?Sized + Send + Sync,
+            R: DeserializeOwned,
+        {
+            {
+                let mut rate_limited = self.rate_limited.lock().unwrap();
+                if *rate_limited > 0 {
+                    *rate_limited -= 1;
+                    return Err(Error::RateLimited(Some(0)));
+                }
+            }
+
+            let request: RestoreRequest =
+                serde_json::from_value(serde_json::to_value(payload).unwrap()).unwrap();
+            self.requests.lock().unwrap().push(request.clone());
+
+            let mut outputs = request.outputs;
+            let next_offset = match (request.offset, request.limit) {
+                (Some(offset), Some(_)) if outputs.len() > Self::PAGE_SIZE => {
+                    outputs.truncate(Self::PAGE_SIZE);
+                    Some(offset + Self::PAGE_SIZE as u64)
+                }
+                _ => None,
+            };
+            let signatures = outputs
+                .iter()
+                .map(|output| BlindSignature {
+                    amount: output.amount,
+                    keyset_id: output.keyset_id,
+                    c: output.blinded_secret,
+                    dleq: None,
+                })
+                .collect();
+
+            let response = RestoreResponse {
+                outputs,
+                signatures,
+                promises: None,
+                offset: request.offset,
+                limit: request.limit,
+                next_offset,
+            };
+            Ok(serde_json::from_value(serde_json::to_value(response).unwrap()).unwrap())
+        }
+    }
+
+    fn outputs(count: usize) -> Vec<BlindedMessage> {
+        let keyset_id = Id::from_str("00916bbf7ef91a36").unwrap();
+        (0..count)
+            .map(|_| {
+                BlindedMessage::new(
+                    Amount::from(1),
+                    keyset_id,
+                    SecretKey::generate().public_key(),
+                )
+            })
+            .collect()
+    }
+
+    fn client(transport: RestoreTransport) -> HttpClient<RestoreTransport> {
+        let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+        #[cfg(feature = "auth")]
+        return HttpClient::with_transport(mint_url, transport, None);
+        #[cfg(not(feature = "auth"))]
+        return HttpClient::with_transport(mint_url, transport);
+    }
+
+    #[tokio::test]
+    async fn restore_small_request_is_sent_at_once() {
+        let transport = RestoreTransport::default();
+        let outputs = outputs(10);
+
+        let response = client(transport.clone())
+            .post_restore(RestoreRequest::new(outputs.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.outputs, outputs);
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(*requests, vec![RestoreRequest::new(outputs)]);
+    }
+
+    #[tokio::test]
+    async fn restore_large_request_is_paginated() {
+        let transport = RestoreTransport::default();
+        let outputs = outputs(2_500);
+
+        let response = client(transport.clone())
+            .post_restore(RestoreRequest::new(outputs.clone()))
+            .await
+            .unwrap();
+
+        // Every output is answered once, in order
+        assert_eq!(response.outputs, outputs);
+        assert_eq!(
+            response
+                .signatures
+                .iter()
+                .map(|signature| signature.c)
+                .collect::<Vec<PublicKey>>(),
+            outputs
+                .iter()
+                .map(|output| output.blinded_secret)
+                .collect::<Vec<_>>()
+        );
+
+        // Pages resume where the mint stopped and never resend answered outputs
+        let requests = transport.requests.lock().unwrap();
+        assert_eq!(
+            requests
+                .iter()
+                .map(|request| (request.offset, request.outputs.len()))
+                .collect::<Vec<_>>(),
+            vec![
+                (Some(0), 1_000),
+                (Some(600), 1_000),
+                (Some(1_200), 1_000),
+                (Some(1_800), 700),
+                (Some(2_400), 100),
+            ]
+        );
+        assert!(requests
+            .iter()
+            .all(|request| request.limit == Some(MAX_RESTORE_PAGE_SIZE)));
+    }
+
+    #[tokio::test]
+    async fn restore_retries_after_being_rate_limited() {
+        let transport = RestoreTransport::default();
+        *transport.rate_limited.lock().unwrap() = 2;
+        let outputs = outputs(10);
+
+        let response = client(transport.clone())
+            .post_restore(RestoreRequest::new(outputs.clone()))
+            .await
+            .unwrap();
+
+        assert_eq!(response.outputs, outputs);
+        assert_eq!(*transport.rate_limited.lock().unwrap(), 0);
+        assert_eq!(transport.requests.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn restore_gives_up_when_rate_limit_persists() {
+        let transport = RestoreTransport::default();
+        *transport.rate_limited.lock().unwrap() = MAX_RATE_LIMIT_RETRIES + 1;
+
+        let result = client(transport.clone())
+            .post_restore(RestoreRequest::new(outputs(10)))
+            .await;
+
+        assert!(matches!(result, Err(Error::RateLimited(Some(0)))));
+        assert!(transport.requests.lock().unwrap().is_empty());
+    }
+}
@@ -7,12 +7,13 @@ use cdk_common::{MeltQuoteBolt12Request, MintQuoteBolt12Request, MintQuoteBolt12
 
 use super::Error;
 // Re-export Lightning address types for trait implementers
-pub use crate::lightning_address::{LnurlPayInvoiceResponse, LnurlPayResponse};
+pub use crate::lightning_address::{LnurlPayInvoiceResponse, LnurlPayResponse, SuccessAction};
 use crate::nuts::{
     CheckStateRequest, CheckStateResponse, Id, KeySet, KeysetResponse, MeltQuoteBolt11Request,
     MeltQuoteBolt11Response, MeltQuoteCustomRequest, MeltRequest, MintInfo, MintQuoteBolt11Request,
-    MintQuoteBolt11Response, MintQuoteCustomRequest, MintQuoteCustomResponse, MintRequest,
-    MintResponse, RestoreRequest, RestoreResponse, SwapRequest, SwapResponse,
+    MintQuoteBolt11Response, MintQuoteCustomRequest, MintQuoteCustomResponse, MintQuotesRequest,
+    MintQuotesResponse, MintRequest, MintResponse, RestoreRequest, RestoreResponse, SwapRequest,
+    SwapResponse,
 };
 #[cfg(feature = "auth")]
 use crate::wallet::AuthWallet;
@@ -94,6 +95,11 @@ pub trait MintConnector: Debug {
     ) -> Result<CheckStateResponse, Error>;
     /// Restore request [NUT-13]
     async fn post_restore(&self, request: RestoreRequest) -> Result<RestoreResponse, Error>;
+    /// Mint quotes locked to the pubkeys of a signed request [NUT-20]
+    async fn post_mint_quotes(
+        &self,
+        request: MintQuotesRequest,
+    ) -> Result<MintQuotesResponse<String>, Error>;
 
     /// Get the auth wallet for the client
     #[cfg(feature = "auth")]
@@ -150,23 +150,20 @@ impl Transport for Async {
             request = request.header(auth.header_key(), auth.to_string());
         }
 
-        let response = request
-            .send()
-            .await
-            .map_err(|e| {
-                Error::HttpError(
-                    e.status().map(|status_code| status_code.as_u16()),
-                    e.to_string(),
-                )
-            })?
-            .text()
-            .await
-            .map_err(|e| {
-                Error::HttpError(
-                    e.status().map(|status_code| status_code.as_u16()),
-                    e.to_string(),
-                )
-            })?;
+        let response = request.send().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
+        check_rate_limited(&response)?;
+
+        let response = response.text().await.map_err(|e| {
+            Error::HttpError(
+                e.status().map(|status_code| status_code.as_u16()),
+                e.to_string(),
+            )
+        })?;
 
         serde_json::from_str::<R>(&response).map_err(|err| {
             tracing::warn!("Http Response error: {}", err);
@@ -199,6 +196,7 @@ impl Transport for Async {
                 e.to_string(),
             )
         })?;
+        check_rate_limited(&response)?;
 
         let response = response.text().await.map_err(|e| {
             Error::HttpError(
@@ -217,5 +215,21 @@ impl Transport for Async {
     }
 }
 
+/// Fails with [`Error::RateLimited`] when the mint answered 429, carrying the `Retry-After`
+/// seconds so the caller can back off
+fn check_rate_limited(response: &reqwest::Response) -> Result<(), Error> {
+    if response.status() != reqwest::StatusCode::TOO_MANY_REQUESTS {
+        return Ok(());
+    }
+
+    let retry_after = response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<u64>().ok());
+
+    Err(Error::RateLimited(retry_after))
+}
+
 #[cfg(all(feature = "tor", not(target_arch = "wasm32")))]
 pub mod tor_transport;
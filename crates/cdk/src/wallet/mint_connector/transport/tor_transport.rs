@@ -201,6 +201,14 @@ impl TorAsync {
             .map_err(|e| Error::HttpError(None, e.to_string()))?;
 
         let status = resp.status().as_u16();
+        if status == 429 {
+            let retry_after = resp
+                .headers()
+                .get(hyper::header::RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse::<u64>().ok());
+            return Err(Error::RateLimited(retry_after));
+        }
         let bytes = hyper::body::to_bytes(resp.into_body())
             .await
             .map_err(|e| Error::HttpError(None, e.to_string()))?;
@@ -24,16 +24,19 @@
 //! let fresh = manager.load_from_mint(&storage, &client).await?;
 //! ```
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::Arc;
 use std::time::Duration;
 
 use arc_swap::ArcSwap;
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
 use cdk_common::database::{self, WalletDatabase};
 use cdk_common::mint_url::MintUrl;
-use cdk_common::nuts::{KeySetInfo, Keys};
+use cdk_common::nuts::{CurrencyUnit, KeySetInfo, Keys};
 use cdk_common::parking_lot::RwLock;
+use cdk_common::util::unix_time;
 use cdk_common::{KeySet, MintInfo};
 use tokio::sync::Mutex;
 use web_time::Instant;
@@ -44,6 +47,45 @@ use crate::wallet::MintConnector;
 use crate::wallet::{AuthMintConnector, AuthWallet};
 use crate::{Error, Wallet};
 
+/// KV store namespace for persisted mint metadata bookkeeping
+const MINT_METADATA_KV_NAMESPACE: &str = "mint_metadata";
+
+/// KV store secondary namespace for the time keysets were last fetched from a mint
+const KEYSETS_UPDATED_AT_NAMESPACE: &str = "keysets_updated_at";
+
+/// KV store key for a mint, mint urls contain characters the KV store does not allow
+fn mint_url_kv_key(mint_url: &MintUrl) -> String {
+    Sha256Hash::hash(mint_url.to_string().as_bytes()).to_string()
+}
+
+/// Unix timestamp of the last time keysets for `mint_url` were fetched from
+/// the mint and persisted to `storage`
+///
+/// Unlike the in-memory [`FreshnessStatus`] this survives restarts, so it can
+/// be used to judge how stale cached keysets are while offline.
+pub(crate) async fn keysets_updated_at(
+    storage: &Arc<dyn WalletDatabase<database::Error> + Send + Sync>,
+    mint_url: &MintUrl,
+) -> Result<Option<u64>, Error> {
+    let value = storage
+        .kv_read(
+            MINT_METADATA_KV_NAMESPACE,
+            KEYSETS_UPDATED_AT_NAMESPACE,
+            &mint_url_kv_key(mint_url),
+        )
+        .await?;
+
+    match value {
+        Some(bytes) => {
+            let timestamp = u64::from_be_bytes(bytes.try_into().map_err(|_| {
+                Error::Custom("Invalid keyset timestamp format in KV store".into())
+            })?);
+            Ok(Some(timestamp))
+        }
+        None => Ok(None),
+    }
+}
+
 /// Metadata freshness and versioning information
 ///
 /// Tracks when data was last fetched and which version is currently cached.
@@ -242,19 +284,10 @@ impl MintMetadataCache {
             return Ok(current_metadata);
         }
 
-        // Load keys from database before fetching from HTTP
-        // This prevents re-fetching keys we already have and avoids duplicate insertions
-        if let Some(keysets) = storage.get_mint_keysets(self.mint_url.clone()).await? {
-            let mut updated_metadata = (*self.metadata.load().clone()).clone();
-            for keyset_info in keysets {
-                if let Some(keys) = storage.get_keys(&keyset_info.id).await? {
-                    tracing::trace!("Loaded keys for keyset {} from database", keyset_info.id);
-                    updated_metadata.keys.insert(keyset_info.id, Arc::new(keys));
-                }
-            }
-            // Update cache with database keys before HTTP fetch
-            self.metadata.store(Arc::new(updated_metadata));
-        }
+        // Load keysets and keys from database before fetching from HTTP
+        // This prevents re-fetching keys we already have and avoids duplicate insertions, and
+        // keeps the keysets the mint no longer lists
+        self.merge_from_database(storage).await?;
 
         // Perform the fetch
         #[cfg(feature = "auth")]
@@ -329,6 +362,57 @@ impl MintMetadataCache {
         self.load_from_mint(storage, client).await
     }
 
+    /// Load metadata from cache, completed with the keysets and keys persisted in the database
+    ///
+    /// Never contacts the mint, so it can be used while the mint is unreachable. The returned
+    /// metadata is not marked as populated, the next [`MintMetadataCache::load`] still fetches
+    /// from the mint.
+    pub async fn load_from_storage(
+        &self,
+        storage: &Arc<dyn WalletDatabase<database::Error> + Send + Sync>,
+    ) -> Result<Arc<MintMetadata>, Error> {
+        let _guard = self.fetch_lock.lock().await;
+        self.merge_from_database(storage).await
+    }
+
+    /// Add the keysets and keys persisted in `storage` that are missing from the cache
+    ///
+    /// Must be called with the fetch lock held.
+    async fn merge_from_database(
+        &self,
+        storage: &Arc<dyn WalletDatabase<database::Error> + Send + Sync>,
+    ) -> Result<Arc<MintMetadata>, Error> {
+        let Some(keysets) = storage.get_mint_keysets(self.mint_url.clone()).await? else {
+            return Ok(self.metadata.load().clone());
+        };
+
+        let mut updated_metadata = (*self.metadata.load().clone()).clone();
+        for keyset_info in keysets {
+            if let std::collections::hash_map::Entry::Vacant(e) =
+                updated_metadata.keys.entry(keyset_info.id)
+            {
+                if let Some(keys) = storage.get_keys(&keyset_info.id).await? {
+                    tracing::trace!("Loaded keys for keyset {} from database", keyset_info.id);
+                    e.insert(Arc::new(keys));
+                }
+            }
+            updated_metadata
+                .keysets
+                .entry(keyset_info.id)
+                .or_insert_with(|| Arc::new(keyset_info));
+        }
+        updated_metadata.active_keysets = updated_metadata
+            .keysets
+            .values()
+            .filter(|keyset| keyset.active)
+            .cloned()
+            .collect();
+
+        let updated_metadata = Arc::new(updated_metadata);
+        self.metadata.store(updated_metadata.clone());
+        Ok(updated_metadata)
+    }
+
     /// Load auth keysets and keys (auth feature only)
     ///
     /// Fetches blind authentication keysets from the mint. Always performs
@@ -478,6 +562,20 @@ impl MintMetadataCache {
                 .await
                 .inspect_err(|e| tracing::warn!("Failed to save keysets for {}: {}", mint_url, e))
                 .ok();
+
+            // Record when keysets were fetched so offline checks can judge staleness
+            storage
+                .kv_write(
+                    MINT_METADATA_KV_NAMESPACE,
+                    KEYSETS_UPDATED_AT_NAMESPACE,
+                    &mint_url_kv_key(&mint_url),
+                    &unix_time().to_be_bytes(),
+                )
+                .await
+                .inspect_err(|e| {
+                    tracing::warn!("Failed to save keyset timestamp for {}: {}", mint_url, e)
+                })
+                .ok();
         }
 
         // Save keys for each keyset
@@ -576,24 +674,46 @@ impl MintMetadataCache {
             self.mint_url
         );
 
+        // Keysets the mint stopped listing are kept, proofs signed by them may still circulate,
+        // but they can no longer sign
+        if client.is_some() {
+            let listed: HashSet<Id> = keysets_to_fetch.iter().map(|keyset| keyset.id).collect();
+            for keyset in new_metadata.keysets.values_mut() {
+                if keyset.active
+                    && keyset.unit != CurrencyUnit::Auth
+                    && !listed.contains(&keyset.id)
+                {
+                    let mut unlisted = (**keyset).clone();
+                    unlisted.active = false;
+                    *keyset = Arc::new(unlisted);
+                }
+            }
+        }
+
         // Fetch keys for each keyset
         for keyset_info in keysets_to_fetch {
-            let keyset_arc = Arc::new(keyset_info.clone());
             new_metadata
                 .keysets
-                .insert(keyset_info.id, keyset_arc.clone());
-
-            // Track active keysets separately for quick access
-            if keyset_info.active {
-                new_metadata.active_keysets.push(keyset_arc);
-            }
+                .insert(keyset_info.id, Arc::new(keyset_info.clone()));
 
             // Only fetch keys if we don't already have them cached
             if let std::collections::hash_map::Entry::Vacant(e) =
                 new_metadata.keys.entry(keyset_info.id)
             {
                 let keyset = if let Some(client) = client.as_ref() {
-                    client.get_mint_keyset(keyset_info.id).await?
+                    match client.get_mint_keyset(keyset_info.id).await {
+                        Ok(keyset) => keyset,
+                        // Mints may not serve the keys of keysets they can no longer sign with
+                        Err(err) if !keyset_info.active => {
+                            tracing::warn!(
+                                "Failed to fetch keys of inactive keyset {}: {}",
+                                keyset_info.id,
+                                err
+                            );
+                            continue;
+                        }
+                        Err(err) => return Err(err),
+                    }
                 } else {
                     #[cfg(feature = "auth")]
                     if let Some(auth_client) = auth_client.as_ref() {
@@ -615,6 +735,14 @@ impl MintMetadataCache {
             }
         }
 
+        // Track active keysets separately for quick access
+        new_metadata.active_keysets = new_metadata
+            .keysets
+            .values()
+            .filter(|keyset| keyset.active)
+            .cloned()
+            .collect();
+
         // Update freshness status based on what was fetched
         if client.is_some() {
             new_metadata.status.is_populated = true;
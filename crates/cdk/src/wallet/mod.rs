@@ -1,6 +1,6 @@
 #![doc = include_str!("./README.md")]
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::str::FromStr;
 use std::sync::atomic::AtomicBool;
@@ -9,42 +9,52 @@ use std::time::Duration;
 
 use cdk_common::amount::FeeAndAmounts;
 use cdk_common::database::{self, WalletDatabase};
+use cdk_common::nut17::{MeltProgressNotification, MintInfoNotification};
 use cdk_common::parking_lot::RwLock;
 use cdk_common::subscription::WalletParams;
+use cdk_common::task::spawn;
 use getrandom::getrandom;
 use subscription::{ActiveSubscription, SubscriptionManager};
 #[cfg(any(feature = "auth", feature = "npubcash"))]
 use tokio::sync::RwLock as TokioRwLock;
+use tokio::task::JoinHandle;
 use tracing::instrument;
 use zeroize::Zeroize;
 
 use crate::amount::SplitTarget;
-use crate::dhke::construct_proofs;
 use crate::error::Error;
 use crate::fees::calculate_fee;
 use crate::mint_url::MintUrl;
 use crate::nuts::nut00::token::Token;
 use crate::nuts::nut17::Kind;
 use crate::nuts::{
-    nut10, CurrencyUnit, Id, Keys, MintInfo, MintQuoteState, PreMintSecrets, Proof, Proofs,
-    RestoreRequest, SpendingConditions, State,
+    nut10, CurrencyUnit, Id, Keys, MeltQuoteState, MintInfo, MintQuoteState, NotificationPayload,
+    PreMintSecrets, Proof, Proofs, RestoreRequest, SpendingConditions, State,
 };
 use crate::types::ProofInfo;
 use crate::util::unix_time;
 use crate::wallet::mint_metadata_cache::MintMetadataCache;
-use crate::Amount;
 #[cfg(feature = "auth")]
 use crate::OidcClient;
+use crate::{ensure_cdk, Amount};
 
 #[cfg(feature = "auth")]
 mod auth;
 #[cfg(feature = "nostr")]
 mod nostr_backup;
+#[cfg(feature = "nostr")]
+mod nostr_transport;
 #[cfg(all(feature = "tor", not(target_arch = "wasm32")))]
 pub use mint_connector::TorHttpClient;
+mod auto_melt;
 mod balance;
 mod builder;
+mod consolidate;
+mod deferred_receive;
+mod encrypted_backup;
 mod issue;
+mod journal;
+mod keyset_migration;
 mod keysets;
 mod melt;
 mod mint_connector;
@@ -52,10 +62,12 @@ mod mint_metadata_cache;
 pub mod multi_mint_wallet;
 #[cfg(feature = "npubcash")]
 mod npubcash;
+mod offline_verify;
 pub mod payment_request;
 mod proofs;
 mod receive;
 mod reclaim;
+mod seed_sweep;
 mod send;
 #[cfg(not(target_arch = "wasm32"))]
 mod streams;
@@ -66,24 +78,44 @@ pub mod util;
 
 #[cfg(feature = "auth")]
 pub use auth::{AuthMintConnector, AuthWallet};
+pub use auto_melt::{
+    AutoMeltOutcome, AutoMeltPolicy, AutoMeltState, AutoMeltTarget, AUTO_MELT_METADATA_KEY,
+};
 pub use builder::WalletBuilder;
 pub use cdk_common::wallet as types;
+pub use consolidate::ConsolidationResult;
+pub use deferred_receive::DeferredReceive;
+pub use encrypted_backup::BackupImportResult;
+pub use journal::{JournalEntry, JournalOperation, JournalOutput, OperationRecoveryResult};
+pub use keyset_migration::{KeysetMigrationOptions, KeysetMigrationResult};
+pub use melt::{LightningAddressMelted, MeltEstimate};
 #[cfg(feature = "auth")]
 pub use mint_connector::http_client::AuthHttpClient as BaseAuthHttpClient;
 pub use mint_connector::http_client::HttpClient as BaseHttpClient;
 pub use mint_connector::transport::Transport as HttpTransport;
 #[cfg(feature = "auth")]
 pub use mint_connector::AuthHttpClient;
-pub use mint_connector::{HttpClient, LnurlPayInvoiceResponse, LnurlPayResponse, MintConnector};
-pub use multi_mint_wallet::{MultiMintReceiveOptions, MultiMintSendOptions, MultiMintWallet};
+pub use mint_connector::{
+    HttpClient, LnurlPayInvoiceResponse, LnurlPayResponse, MintConnector, SuccessAction,
+};
+pub use multi_mint_wallet::{
+    MintAllowlist, MppMelted, MultiMintReceiveOptions, MultiMintSendOptions, MultiMintWallet,
+};
 #[cfg(feature = "nostr")]
 pub use nostr_backup::{BackupOptions, BackupResult, RestoreOptions, RestoreResult};
-pub use payment_request::CreateRequestParams;
+#[cfg(feature = "nostr")]
+pub use nostr_transport::{NostrReceived, NostrSent, NostrTransportOptions};
+pub use offline_verify::{
+    OfflineCheck, OfflineDleqStatus, OfflineKeysetStatus, OfflineProofReport, OfflineTokenReport,
+    TokenInspection, TokenKeysetInspection, TokenProofInspection,
+};
 #[cfg(feature = "nostr")]
 pub use payment_request::NostrWaitInfo;
-pub use receive::ReceiveOptions;
+pub use payment_request::{CreateRequestParams, PaymentRequestInbox};
+pub use receive::{ReceiveOptions, ReceiveResult};
+pub use seed_sweep::SeedSweepResult;
 pub use send::{PreparedSend, SendMemo, SendOptions};
-pub use types::{MeltQuote, MintQuote, SendKind};
+pub use types::{MeltQuote, MintQuote, SelectionStrategy, SendKind};
 
 use crate::nuts::nut00::ProofsMethods;
 
@@ -105,6 +137,7 @@ pub struct Wallet {
     /// The targeted amount of proofs to have at each size
     pub target_proof_count: usize,
     metadata_cache_ttl: Arc<RwLock<Option<Duration>>>,
+    keyset_migration: Arc<RwLock<KeysetMigrationOptions>>,
     #[cfg(feature = "auth")]
     auth_wallet: Arc<TokioRwLock<Option<AuthWallet>>>,
     #[cfg(feature = "npubcash")]
@@ -126,8 +159,12 @@ pub enum WalletSubscription {
     Bolt11MintQuoteState(Vec<String>),
     /// Melt quote subscription
     Bolt11MeltQuoteState(Vec<String>),
+    /// Progress of melt quotes while they are paid
+    Bolt11MeltProgress(Vec<String>),
     /// Mint bolt12 quote subscription
     Bolt12MintQuoteState(Vec<String>),
+    /// Mint info and active keyset changes
+    MintInfo,
 }
 
 impl From<WalletSubscription> for WalletParams {
@@ -162,15 +199,67 @@ impl From<WalletSubscription> for WalletParams {
                 kind: Kind::Bolt11MeltQuote,
                 id,
             },
+            WalletSubscription::Bolt11MeltProgress(filters) => WalletParams {
+                filters,
+                kind: Kind::Bolt11MeltProgress,
+                id,
+            },
             WalletSubscription::Bolt12MintQuoteState(filters) => WalletParams {
                 filters,
                 kind: Kind::Bolt12MintQuote,
                 id,
             },
+            WalletSubscription::MintInfo => WalletParams {
+                filters: vec![],
+                kind: Kind::MintInfo,
+                id,
+            },
+        }
+    }
+}
+
+/// Options for restoring a wallet from its seed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SeedRestoreOptions {
+    /// Number of counters checked with the mint per restore request
+    pub batch_size: u32,
+    /// Number of consecutive empty batches after which a keyset is considered restored
+    pub gap_limit: u32,
+}
+
+impl Default for SeedRestoreOptions {
+    fn default() -> Self {
+        Self {
+            batch_size: 100,
+            gap_limit: 3,
         }
     }
 }
 
+/// Progress of a seed restore, reported after every batch
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreProgress {
+    /// Keyset being restored
+    pub keyset_id: Id,
+    /// Index of the keyset being restored
+    pub keyset_index: usize,
+    /// Number of keysets to restore
+    pub keyset_count: usize,
+    /// Next counter to check for the keyset
+    pub counter: u32,
+    /// Unspent amount restored so far
+    pub restored: Amount,
+}
+
+/// Outputs a [`SplitTarget`] would produce, see [`Wallet::preview_split`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPreview {
+    /// Denominations of the outputs, in ascending order
+    pub amounts: Vec<Amount>,
+    /// Input fee of spending all of the outputs later
+    pub redeem_fee: Amount,
+}
+
 impl Wallet {
     /// Create new [`Wallet`] using the builder pattern
     /// # Synopsis
@@ -226,6 +315,97 @@ impl Wallet {
             .map_err(|e| Error::SubscriptionError(e.to_string()))
     }
 
+    /// Watch the mint for changes of its info and active keysets
+    ///
+    /// On each change the keysets or the mint info are fetched again, then `on_change` is
+    /// called with the notification. Only mints advertising the `mint_info` websocket command
+    /// publish changes, for other mints `on_change` is never called. Aborting the returned
+    /// task stops the watch.
+    pub async fn watch_mint_info<F>(&self, on_change: F) -> Result<JoinHandle<()>, Error>
+    where
+        F: Fn(MintInfoNotification) + Send + Sync + 'static,
+    {
+        let mut subscription = self.subscribe(WalletSubscription::MintInfo).await?;
+        let wallet = self.clone();
+
+        Ok(spawn(async move {
+            while let Some(event) = subscription.recv().await {
+                let NotificationPayload::MintInfo(notification) = event.into_inner() else {
+                    continue;
+                };
+
+                if !notification.active_keysets.is_empty() {
+                    if let Err(err) = wallet.refresh_keysets().await {
+                        tracing::warn!("Could not refresh keysets of {}: {}", wallet.mint_url, err);
+                    }
+                }
+                if !notification.changed.is_empty() {
+                    if let Err(err) = wallet.fetch_mint_info().await {
+                        tracing::warn!("Could not fetch mint info of {}: {}", wallet.mint_url, err);
+                    }
+                }
+
+                on_change(notification);
+            }
+        }))
+    }
+
+    /// Watch the progress of the payment of a melt quote
+    ///
+    /// `on_progress` is called with the `pending` event once the mint starts paying the quote,
+    /// then with an `attempting` event periodically while the payment is in flight. The task
+    /// ends once the quote is paid or its payment failed. Progress is only published over
+    /// websockets by mints advertising `bolt11_melt_progress`: otherwise `on_progress` is never
+    /// called and the task ends with the final state of the quote.
+    pub async fn watch_melt_progress<F>(
+        &self,
+        quote_id: &str,
+        on_progress: F,
+    ) -> Result<JoinHandle<()>, Error>
+    where
+        F: Fn(MeltProgressNotification<String>) + Send + Sync + 'static,
+    {
+        let mut quote_subscription = self
+            .subscribe(WalletSubscription::Bolt11MeltQuoteState(vec![
+                quote_id.to_string()
+            ]))
+            .await?;
+        let mut progress_subscription = self
+            .subscribe(WalletSubscription::Bolt11MeltProgress(vec![
+                quote_id.to_string()
+            ]))
+            .await?;
+
+        Ok(spawn(async move {
+            // The quote is unpaid before the payment starts and again if the payment failed
+            let mut paying = false;
+
+            loop {
+                let event = tokio::select! {
+                    Some(event) = progress_subscription.recv() => event,
+                    event = quote_subscription.recv() => match event {
+                        Some(event) => event,
+                        None => break,
+                    },
+                };
+
+                match event.into_inner() {
+                    NotificationPayload::MeltProgress(progress) => {
+                        paying = true;
+                        on_progress(progress);
+                    }
+                    NotificationPayload::MeltQuoteBolt11Response(quote) => match quote.state {
+                        MeltQuoteState::Pending => paying = true,
+                        MeltQuoteState::Paid | MeltQuoteState::Failed => break,
+                        MeltQuoteState::Unpaid if paying => break,
+                        _ => (),
+                    },
+                    _ => (),
+                }
+            }
+        }))
+    }
+
     /// Fee required to redeem proof set
     #[instrument(skip_all)]
     pub async fn get_proofs_fee(
@@ -278,21 +458,88 @@ impl Wallet {
             .ok_or(Error::UnknownKeySet)?
             .input_fee_ppk;
 
-        let fee = (input_fee_ppk * count).div_ceil(1000);
+        let fee = input_fee_ppk
+            .checked_mul(count)
+            .ok_or(Error::AmountOverflow)?
+            .div_ceil(1000);
 
         Ok(Amount::from(fee))
     }
 
-    /// Update Mint information and related entries in the event a mint changes
-    /// its URL
+    /// Move the wallet to the new url of its mint, for a mint that changed its url
+    ///
+    /// The mint at `new_mint_url` must serve all keysets of the wallet, otherwise
+    /// [`Error::MintUrlKeysetMismatch`] is returned and nothing is changed. The stored proofs,
+    /// mint quotes, keysets and transactions are moved to the new url.
     #[instrument(skip(self))]
     pub async fn update_mint_url(&mut self, new_mint_url: MintUrl) -> Result<(), Error> {
-        // Update the mint URL in the wallet DB
+        #[cfg(feature = "auth")]
+        let client = HttpClient::new(new_mint_url.clone(), None);
+        #[cfg(not(feature = "auth"))]
+        let client = HttpClient::new(new_mint_url.clone());
+
+        self.update_mint_url_with_client(new_mint_url, Arc::new(client))
+            .await
+    }
+
+    /// Move the wallet to the new url of its mint, reached with `client`
+    ///
+    /// See [`Wallet::update_mint_url`].
+    #[instrument(skip(self, client))]
+    pub async fn update_mint_url_with_client(
+        &mut self,
+        new_mint_url: MintUrl,
+        client: Arc<dyn MintConnector + Send + Sync>,
+    ) -> Result<(), Error> {
+        if new_mint_url == self.mint_url {
+            return Ok(());
+        }
+
+        // The keysets of the stored proofs and the known keysets must all be served
+        let mut keyset_ids: HashSet<Id> = self
+            .localstore
+            .get_mint_keysets(self.mint_url.clone())
+            .await?
+            .unwrap_or_default()
+            .into_iter()
+            .map(|keyset| keyset.id)
+            .collect();
+        keyset_ids.extend(
+            self.localstore
+                .get_proofs(Some(self.mint_url.clone()), None, None, None)
+                .await?
+                .into_iter()
+                .map(|proof| proof.proof.keyset_id),
+        );
+
+        let served: HashSet<Id> = client
+            .get_mint_keysets()
+            .await?
+            .keysets
+            .into_iter()
+            .map(|keyset| keyset.id)
+            .collect();
+
+        if !keyset_ids.is_subset(&served) {
+            tracing::warn!(
+                "Not moving {} to {}, keysets {:?} are not served",
+                self.mint_url,
+                new_mint_url,
+                keyset_ids.difference(&served).collect::<Vec<_>>()
+            );
+            return Err(Error::MintUrlKeysetMismatch {
+                mint_url: new_mint_url.to_string(),
+            });
+        }
+
         self.localstore
             .update_mint_url(self.mint_url.clone(), new_mint_url.clone())
             .await?;
 
-        // Update the mint URL in the wallet struct field
+        self.metadata_cache = Arc::new(MintMetadataCache::new(new_mint_url.clone()));
+        self.subscription =
+            SubscriptionManager::new(client.clone(), self.subscription.prefer_http());
+        self.client = client;
         self.mint_url = new_mint_url;
 
         Ok(())
@@ -445,9 +692,75 @@ impl Wallet {
         Ok(SplitTarget::Values(values))
     }
 
+    /// Preview the outputs for `amount` with a [`SplitTarget`] on the active keyset
+    ///
+    /// Returns the denominations mint, swap and receive would create and the
+    /// input fee of later spending them, since targets yielding more proofs
+    /// cost more to spend. [`SplitTarget::None`] previews the wallet's proof
+    /// state refill, as the operations do.
+    #[instrument(skip(self))]
+    pub async fn preview_split(
+        &self,
+        amount: Amount,
+        split_target: &SplitTarget,
+    ) -> Result<SplitPreview, Error> {
+        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        let fee_and_amounts = self
+            .get_keyset_fees_and_amounts_by_id(active_keyset_id)
+            .await?;
+
+        let split_target = match split_target {
+            SplitTarget::None => {
+                self.determine_split_target_values(amount, &fee_and_amounts)
+                    .await?
+            }
+            s => s.clone(),
+        };
+
+        let amounts = amount.split_targeted(&split_target, &fee_and_amounts)?;
+        let redeem_fee = self
+            .get_keyset_count_fee(&active_keyset_id, amounts.len() as u64)
+            .await?;
+
+        Ok(SplitPreview {
+            amounts,
+            redeem_fee,
+        })
+    }
+
     /// Restore
+    ///
+    /// Restores the wallet from its seed with the default [`SeedRestoreOptions`].
     #[instrument(skip(self))]
     pub async fn restore(&self) -> Result<Amount, Error> {
+        self.restore_with_options(SeedRestoreOptions::default(), |_| ())
+            .await
+    }
+
+    /// Restore from seed (NUT-13)
+    ///
+    /// For every keyset of the wallet unit listed by the mint, active or not, secrets are derived
+    /// from the seed in batches of `batch_size` counters and sent to the mint's restore endpoint.
+    /// The returned signatures are unblinded and the unspent proofs are stored. A keyset is done
+    /// once `gap_limit` consecutive batches came back empty. Keyset counters are moved past the
+    /// highest restored counter so new outputs never reuse an index. Keysets whose keys the mint
+    /// doesn't serve are skipped.
+    ///
+    /// `on_progress` is called after every batch.
+    #[instrument(skip(self, on_progress))]
+    pub async fn restore_with_options<F>(
+        &self,
+        options: SeedRestoreOptions,
+        mut on_progress: F,
+    ) -> Result<Amount, Error>
+    where
+        F: FnMut(RestoreProgress) + Send,
+    {
+        ensure_cdk!(
+            options.batch_size > 0,
+            Error::Custom("Restore batch size must be greater than zero".to_string())
+        );
+
         // Check that mint is in store of mints
         if self
             .localstore
@@ -458,103 +771,46 @@ impl Wallet {
             self.fetch_mint_info().await?;
         }
 
-        let keysets = self.load_mint_keysets().await?;
+        let keysets = self.fetch_all_mint_keysets().await?;
+        let keyset_count = keysets.len();
 
         let mut restored_value = Amount::ZERO;
 
-        for keyset in keysets {
-            let keys = self.load_keyset_keys(keyset.id).await?;
+        for (keyset_index, keyset) in keysets.into_iter().enumerate() {
+            let keys = match self.load_keyset_keys(keyset.id).await {
+                Ok(keys) => keys,
+                Err(Error::UnknownKeySet) => {
+                    tracing::warn!("Mint did not serve the keys of keyset {}", keyset.id);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
             let mut empty_batch = 0;
             let mut start_counter = 0;
             // Track the highest counter value that had a signature
             let mut highest_counter: Option<u32> = None;
 
-            while empty_batch.lt(&3) {
-                let premint_secrets = PreMintSecrets::restore_batch(
-                    keyset.id,
-                    &self.seed,
-                    start_counter,
-                    start_counter + 100,
-                )?;
-
-                tracing::debug!(
-                    "Attempting to restore counter {}-{} for mint {} keyset {}",
-                    start_counter,
-                    start_counter + 100,
-                    self.mint_url,
-                    keyset.id
-                );
-
-                let restore_request = RestoreRequest {
-                    outputs: premint_secrets.blinded_messages(),
-                };
-
-                let response = self.client.post_restore(restore_request).await?;
+            while empty_batch < options.gap_limit {
+                let end_counter = start_counter + options.batch_size;
 
-                if response.signatures.is_empty() {
+                let Some((proofs, highest_in_batch)) = self
+                    .restore_batch(&self.seed, keyset.id, &keys, start_counter, end_counter)
+                    .await?
+                else {
                     empty_batch += 1;
-                    start_counter += 100;
+                    start_counter = end_counter;
+                    on_progress(RestoreProgress {
+                        keyset_id: keyset.id,
+                        keyset_index,
+                        keyset_count,
+                        counter: start_counter,
+                        restored: restored_value,
+                    });
                     continue;
-                }
-
-                // Build a map from blinded_secret to signature for O(1) lookup
-                // This ensures we match signatures to secrets correctly regardless of response order
-                let signature_map: HashMap<_, _> = response
-                    .outputs
-                    .iter()
-                    .zip(response.signatures.iter())
-                    .map(|(output, sig)| (output.blinded_secret, sig.clone()))
-                    .collect();
-
-                // Enumerate secrets to track their original index (which corresponds to counter value)
-                // and match signatures by blinded_secret to ensure correct pairing
-                let matched_secrets: Vec<_> = premint_secrets
-                    .secrets
-                    .iter()
-                    .enumerate()
-                    .filter_map(|(idx, p)| {
-                        signature_map
-                            .get(&p.blinded_message.blinded_secret)
-                            .map(|sig| (idx, p, sig.clone()))
-                    })
-                    .collect();
-
-                // Update highest counter based on matched indices
-                if let Some(&(max_idx, _, _)) = matched_secrets.last() {
-                    let counter_value = start_counter + max_idx as u32;
-                    highest_counter =
-                        Some(highest_counter.map_or(counter_value, |c| c.max(counter_value)));
-                }
-
-                // the response outputs and premint secrets should be the same after filtering
-                // blinded messages the mint did not have signatures for
-                if response.outputs.len() != matched_secrets.len() {
-                    return Err(Error::InvalidMintResponse(format!(
-                        "restore response outputs ({}) does not match premint secrets ({})",
-                        response.outputs.len(),
-                        matched_secrets.len()
-                    )));
-                }
+                };
 
-                // Extract signatures, rs, and secrets in matching order
-                // Each tuple (idx, premint, signature) ensures correct pairing
-                let proofs = construct_proofs(
-                    matched_secrets
-                        .iter()
-                        .map(|(_, _, sig)| sig.clone())
-                        .collect(),
-                    matched_secrets
-                        .iter()
-                        .map(|(_, p, _)| p.r.clone())
-                        .collect(),
-                    matched_secrets
-                        .iter()
-                        .map(|(_, p, _)| p.secret.clone())
-                        .collect(),
-                    &keys,
-                )?;
-
-                tracing::debug!("Restored {} proofs", proofs.len());
+                highest_counter =
+                    Some(highest_counter.map_or(highest_in_batch, |c| c.max(highest_in_batch)));
 
                 let states = self.check_proofs_spent(proofs.clone()).await?;
 
@@ -585,17 +841,30 @@ impl Wallet {
                     .await?;
 
                 empty_batch = 0;
-                start_counter += 100;
+                start_counter = end_counter;
+                on_progress(RestoreProgress {
+                    keyset_id: keyset.id,
+                    keyset_index,
+                    keyset_count,
+                    counter: start_counter,
+                    restored: restored_value,
+                });
             }
 
-            // Set counter to highest found + 1 to avoid reusing any counter values
+            // Move the counter past the highest found to avoid reusing any counter values
             // that already have signatures at the mint
             if let Some(highest) = highest_counter {
-                self.localstore
-                    .increment_keyset_counter(&keyset.id, highest + 1)
+                let current = self
+                    .localstore
+                    .increment_keyset_counter(&keyset.id, 0)
                     .await?;
+                if current <= highest {
+                    self.localstore
+                        .increment_keyset_counter(&keyset.id, highest + 1 - current)
+                        .await?;
+                }
                 tracing::debug!(
-                    "Set keyset {} counter to {} after restore",
+                    "Set keyset {} counter to at least {} after restore",
                     keyset.id,
                     highest + 1
                 );
@@ -604,6 +873,100 @@ impl Wallet {
         Ok(restored_value)
     }
 
+    /// Restore the proofs the mint signed for counters `start_counter..end_counter` of `seed`
+    ///
+    /// Returns the proofs and the highest counter the mint had a signature for, or `None` if it
+    /// had none. Nothing is stored.
+    pub(crate) async fn restore_batch(
+        &self,
+        seed: &[u8; 64],
+        keyset_id: Id,
+        keys: &Keys,
+        start_counter: u32,
+        end_counter: u32,
+    ) -> Result<Option<(Proofs, u32)>, Error> {
+        let premint_secrets =
+            PreMintSecrets::restore_batch(keyset_id, seed, start_counter, end_counter)?;
+
+        tracing::debug!(
+            "Attempting to restore counter {}-{} for mint {} keyset {}",
+            start_counter,
+            end_counter,
+            self.mint_url,
+            keyset_id
+        );
+
+        let restore_request = RestoreRequest::new(premint_secrets.blinded_messages());
+
+        let response = self.client.post_restore(restore_request).await?;
+
+        if response.signatures.is_empty() {
+            return Ok(None);
+        }
+
+        // Build a map from blinded_secret to signature for O(1) lookup
+        // This ensures we match signatures to secrets correctly regardless of response order
+        let signature_map: HashMap<_, _> = response
+            .outputs
+            .iter()
+            .zip(response.signatures.iter())
+            .map(|(output, sig)| (output.blinded_secret, sig.clone()))
+            .collect();
+
+        // Enumerate secrets to track their original index (which corresponds to counter value)
+        // and match signatures by blinded_secret to ensure correct pairing
+        let matched_secrets: Vec<_> = premint_secrets
+            .secrets
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, p)| {
+                signature_map
+                    .get(&p.blinded_message.blinded_secret)
+                    .map(|sig| (idx, p, sig.clone()))
+            })
+            .collect();
+
+        // the response outputs and premint secrets should be the same after filtering
+        // blinded messages the mint did not have signatures for
+        if response.outputs.len() != matched_secrets.len() {
+            return Err(Error::InvalidMintResponse(format!(
+                "restore response outputs ({}) does not match premint secrets ({})",
+                response.outputs.len(),
+                matched_secrets.len()
+            )));
+        }
+
+        let highest_counter = match matched_secrets.last() {
+            Some(&(max_idx, _, _)) => start_counter + max_idx as u32,
+            None => return Ok(None),
+        };
+
+        // Extract signatures, rs, and secrets in matching order
+        // Each tuple (idx, premint, signature) ensures correct pairing
+        let proofs = self
+            .construct_signed_proofs(
+                matched_secrets
+                    .iter()
+                    .map(|(_, _, sig)| sig.clone())
+                    .collect(),
+                matched_secrets
+                    .iter()
+                    .map(|(_, p, _)| p.r.clone())
+                    .collect(),
+                matched_secrets
+                    .iter()
+                    .map(|(_, p, _)| p.secret.clone())
+                    .collect(),
+                keyset_id,
+                keys,
+            )
+            .await?;
+
+        tracing::debug!("Restored {} proofs", proofs.len());
+
+        Ok(Some((proofs, highest_counter)))
+    }
+
     /// Verify all proofs in token have meet the required spend
     /// Can be used to allow a wallet to accept payments offline while reducing
     /// the risk of claiming back to the limits let by the spending_conditions
@@ -794,6 +1157,13 @@ impl Wallet {
     pub fn set_target_proof_count(&mut self, count: usize) {
         self.target_proof_count = count;
     }
+
+    /// Set the options used when migrating proofs off inactive keysets
+    ///
+    /// Applies to all clones of this wallet.
+    pub fn set_keyset_migration(&self, options: KeysetMigrationOptions) {
+        *self.keyset_migration.write() = options;
+    }
 }
 
 impl Drop for Wallet {
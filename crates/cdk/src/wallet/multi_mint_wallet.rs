@@ -11,7 +11,7 @@ use std::sync::Arc;
 use anyhow::Result;
 use cdk_common::database::WalletDatabase;
 use cdk_common::task::spawn;
-use cdk_common::wallet::{MeltQuote, Transaction, TransactionDirection, TransactionId};
+use cdk_common::wallet::{MeltQuote, Pagination, Transaction, TransactionDirection, TransactionId};
 use cdk_common::{database, KeySetInfo};
 use tokio::sync::RwLock;
 use tracing::instrument;
@@ -21,11 +21,15 @@ use super::builder::WalletBuilder;
 use super::receive::ReceiveOptions;
 use super::send::{PreparedSend, SendOptions};
 use super::Error;
+use super::{SeedRestoreOptions, SeedSweepResult};
 use crate::amount::SplitTarget;
 use crate::mint_url::MintUrl;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::nut23::QuoteState;
-use crate::nuts::{CurrencyUnit, MeltOptions, Proof, Proofs, SpendingConditions, State, Token};
+use crate::nuts::{
+    CurrencyUnit, MeltOptions, MeltQuoteState, PaymentMethod, Proof, Proofs, SpendingConditions,
+    State, Token,
+};
 use crate::types::Melted;
 #[cfg(all(feature = "tor", not(target_arch = "wasm32")))]
 use crate::wallet::mint_connector::transport::tor_transport::TorAsync;
@@ -61,6 +65,21 @@ pub struct TransferResult {
     pub target_balance_after: Amount,
 }
 
+/// Result of paying an invoice, possibly split across several mints
+#[derive(Debug, Clone)]
+pub struct MppMelted {
+    /// State of the payment
+    pub state: MeltQuoteState,
+    /// Preimage of the payment, if any mint reported one
+    pub preimage: Option<String>,
+    /// Total amount paid across all mints
+    pub amount: Amount,
+    /// Total fees paid across all mints
+    pub fee_paid: Amount,
+    /// Result of the melt at each mint that paid part of the invoice
+    pub parts: Vec<(MintUrl, Melted)>,
+}
+
 /// Data extracted from a token including mint URL, proofs, and memo
 #[derive(Debug, Clone)]
 pub struct TokenData {
@@ -281,6 +300,13 @@ impl MultiMintWallet {
         &self.seed
     }
 
+    /// Get a reference to the shared localstore
+    #[inline(always)]
+    #[cfg(all(feature = "wallet", feature = "nostr"))]
+    pub(crate) fn localstore(&self) -> &Arc<dyn WalletDatabase<database::Error> + Send + Sync> {
+        &self.localstore
+    }
+
     /// Adds a mint to this [MultiMintWallet]
     ///
     /// Creates a wallet for the specified mint using default or global settings.
@@ -380,6 +406,41 @@ impl MultiMintWallet {
         Ok(())
     }
 
+    /// Move the wallet of a mint that changed its url from `old_mint_url` to `new_mint_url`
+    ///
+    /// The new url is reached with the proxy or Tor settings of this wallet, if the new url was
+    /// already added its wallet is kept. See [`Wallet::update_mint_url`].
+    #[instrument(skip(self))]
+    pub async fn update_mint_url(
+        &self,
+        old_mint_url: &MintUrl,
+        new_mint_url: MintUrl,
+    ) -> Result<(), Error> {
+        let mut wallets = self.wallets.write().await;
+
+        let mut wallet = wallets
+            .get(old_mint_url)
+            .ok_or(Error::UnknownMint {
+                mint_url: old_mint_url.to_string(),
+            })?
+            .clone();
+        let client = self
+            .create_wallet_with_config(new_mint_url.clone(), None)
+            .await?
+            .client
+            .clone();
+
+        wallet
+            .update_mint_url_with_client(new_mint_url.clone(), client)
+            .await?;
+
+        // A wallet already added for the new url now sees the moved proofs too
+        wallets.remove(old_mint_url);
+        wallets.entry(new_mint_url).or_insert(wallet);
+
+        Ok(())
+    }
+
     /// Remove mint from MultiMintWallet
     #[instrument(skip(self))]
     pub async fn remove_mint(&self, mint_url: &MintUrl) {
@@ -568,6 +629,18 @@ impl MultiMintWallet {
         target_wallet.get_mint_keysets().await
     }
 
+    /// Fetch and persist every keyset of a mint with its keys
+    ///
+    /// See [`Wallet::prefetch_keysets`].
+    #[instrument(skip(self))]
+    pub async fn prefetch_keysets(&self, mint_url: &MintUrl) -> Result<Vec<KeySetInfo>, Error> {
+        let wallet = self.get_wallet(mint_url).await.ok_or(Error::UnknownMint {
+            mint_url: mint_url.to_string(),
+        })?;
+
+        wallet.prefetch_keysets().await
+    }
+
     /// Get token data (mint URL and proofs) from a token
     ///
     /// This method extracts the mint URL and proofs from a token. It will automatically
@@ -636,6 +709,34 @@ impl MultiMintWallet {
         Ok(balances)
     }
 
+    /// Get unspent balances for every unit and mint held in the shared localstore
+    ///
+    /// Unlike [`Self::get_balances`] this is not limited to the unit of this wallet
+    /// or to the mints that have been added to it.
+    #[instrument(skip(self))]
+    pub async fn get_balances_by_unit(
+        &self,
+    ) -> Result<BTreeMap<CurrencyUnit, BTreeMap<MintUrl, Amount>>, Error> {
+        let proofs = self
+            .localstore
+            .get_proofs(None, None, Some(vec![State::Unspent]), None)
+            .await?;
+
+        let mut balances: BTreeMap<CurrencyUnit, BTreeMap<MintUrl, Amount>> = BTreeMap::new();
+        for proof in proofs {
+            let balance = balances
+                .entry(proof.unit)
+                .or_default()
+                .entry(proof.mint_url)
+                .or_default();
+            *balance = balance
+                .checked_add(proof.proof.amount)
+                .ok_or(Error::AmountOverflow)?;
+        }
+
+        Ok(balances)
+    }
+
     /// List proofs.
     #[instrument(skip(self))]
     pub async fn list_proofs(&self) -> Result<BTreeMap<MintUrl, Vec<Proof>>, Error> {
@@ -686,7 +787,9 @@ impl MultiMintWallet {
         let mut transactions = Vec::new();
 
         for (_, wallet) in self.wallets.read().await.iter() {
-            let wallet_transactions = wallet.list_transactions(direction).await?;
+            let wallet_transactions = wallet
+                .list_transactions(direction.into(), Pagination::default())
+                .await?;
             transactions.extend(wallet_transactions);
         }
 
@@ -848,6 +951,12 @@ impl MultiMintWallet {
         target_mint_url: &MintUrl,
         mode: TransferMode,
     ) -> Result<TransferResult, Error> {
+        if source_mint_url == target_mint_url {
+            return Err(Error::SameMintTransfer {
+                mint_url: source_mint_url.to_string(),
+            });
+        }
+
         // Get wallets for the specified mints and clone them to release the lock
         let (source_wallet, target_wallet) = {
             let wallets = self.wallets.read().await;
@@ -926,6 +1035,38 @@ impl MultiMintWallet {
         })
     }
 
+    /// Resume a transfer whose melt was paid but whose tokens were not yet minted
+    ///
+    /// Takes the mint quote from [`Error::TransferTimeout`] or [`Error::TransferMintFailed`]
+    /// and mints it at the target mint once it is paid. Returns the amount minted, which is zero if the quote was
+    /// already issued.
+    #[instrument(skip(self))]
+    pub async fn resume_transfer(
+        &self,
+        target_mint_url: &MintUrl,
+        mint_quote_id: &str,
+    ) -> Result<Amount, Error> {
+        let target_wallet = self
+            .get_wallet(target_mint_url)
+            .await
+            .ok_or(Error::UnknownMint {
+                mint_url: target_mint_url.to_string(),
+            })?;
+
+        let quote_state = target_wallet.mint_quote_state(mint_quote_id).await?;
+
+        match quote_state.state {
+            QuoteState::Paid => {
+                let proofs = target_wallet
+                    .mint(mint_quote_id, SplitTarget::default(), None)
+                    .await?;
+                Ok(proofs.total_amount()?)
+            }
+            QuoteState::Issued => Ok(Amount::ZERO),
+            QuoteState::Unpaid => Err(Error::UnpaidQuote),
+        }
+    }
+
     /// Handle exact receive transfer mode - target gets exactly the specified amount
     async fn handle_exact_receive_transfer(
         &self,
@@ -1028,13 +1169,26 @@ impl MultiMintWallet {
                     {
                         if quote_response.state == QuoteState::Paid {
                             // Quote is paid, now mint the tokens
-                            target_wallet
+                            if let Err(err) = target_wallet
                                 .mint(
                                     &final_mint_quote.id,
                                     crate::amount::SplitTarget::default(),
                                     None,
                                 )
-                                .await?;
+                                .await
+                            {
+                                tracing::warn!(
+                                    "Melt paid but minting quote {} at {} failed, resume with resume_transfer: {}",
+                                    final_mint_quote.id,
+                                    target_wallet.mint_url,
+                                    err
+                                );
+                                return Err(Error::TransferMintFailed {
+                                    target_mint: target_wallet.mint_url.to_string(),
+                                    mint_quote_id: final_mint_quote.id.clone(),
+                                    reason: err.to_string(),
+                                });
+                            }
                             break;
                         }
                     }
@@ -1046,6 +1200,7 @@ impl MultiMintWallet {
                         source_mint: source_wallet.mint_url.to_string(),
                         target_mint: target_wallet.mint_url.to_string(),
                         amount: final_mint_quote.amount.unwrap_or(Amount::ZERO),
+                        mint_quote_id: final_mint_quote.id.clone(),
                     });
                 }
                 Err(_) => {
@@ -1058,6 +1213,7 @@ impl MultiMintWallet {
                         source_mint: source_wallet.mint_url.to_string(),
                         target_mint: target_wallet.mint_url.to_string(),
                         amount: final_mint_quote.amount.unwrap_or(Amount::ZERO),
+                        mint_quote_id: final_mint_quote.id.clone(),
                     });
                 }
             }
@@ -1224,6 +1380,38 @@ impl MultiMintWallet {
         Ok(total_amount)
     }
 
+    /// Reclaim proofs left pending or reserved in all wallets
+    ///
+    /// See [`Wallet::check_all_pending_proofs`]. Returns the total amount returned to the
+    /// spendable balance.
+    #[instrument(skip(self))]
+    pub async fn check_all_pending_proofs(&self) -> Result<Amount, Error> {
+        let mut total_amount = Amount::ZERO;
+
+        for (_, wallet) in self.wallets.read().await.iter() {
+            total_amount += wallet.check_all_pending_proofs().await?;
+        }
+
+        Ok(total_amount)
+    }
+
+    /// Recover the interrupted operations of all wallets
+    ///
+    /// See [`Wallet::recover_interrupted_operations`]. Returns the total amount recovered.
+    #[instrument(skip(self))]
+    pub async fn recover_interrupted_operations(&self) -> Result<Amount, Error> {
+        let mut total_amount = Amount::ZERO;
+
+        for (_, wallet) in self.wallets.read().await.iter() {
+            total_amount += wallet
+                .recover_interrupted_operations()
+                .await?
+                .amount_recovered;
+        }
+
+        Ok(total_amount)
+    }
+
     /// Set the active mint for NpubCash integration
     ///
     /// This method sets the active mint for NpubCash in the key-value store.
@@ -1473,8 +1661,13 @@ impl MultiMintWallet {
         let mint_url = token_data.mint_url()?;
         let is_trusted = self.has_mint(&mint_url).await;
 
-        // If mint is not trusted and we don't allow untrusted mints, error
-        if !is_trusted && !opts.allow_untrusted {
+        // If mint is not trusted and neither untrusted mints nor the allowlist permit it, error
+        let is_allowed = opts.allow_untrusted
+            || opts
+                .mint_allowlist
+                .as_ref()
+                .is_some_and(|allowlist| allowlist(&mint_url));
+        if !is_trusted && !is_allowed {
             return Err(Error::UnknownMint {
                 mint_url: mint_url.to_string(),
             });
@@ -1508,7 +1701,7 @@ impl MultiMintWallet {
         let mut amount_received = Amount::ZERO;
 
         match wallet
-            .receive_proofs(proofs, opts.receive_options, token_data.memo().clone())
+            .receive_proofs(proofs, opts.receive_options, token_data.memo_text())
             .await
         {
             Ok(amount) => {
@@ -1586,6 +1779,33 @@ impl MultiMintWallet {
         wallet.restore().await
     }
 
+    /// Sweep funds derived from `old_seed` at each of `mints` into this wallet
+    ///
+    /// See [`Wallet::sweep_from_seed`]. Stops at the first mint that fails; mints already
+    /// swept keep their result, and sweeping again is safe as swept proofs are spent.
+    #[instrument(skip(self, old_seed))]
+    pub async fn sweep_from_seed(
+        &self,
+        old_seed: &[u8; 64],
+        mints: &[MintUrl],
+    ) -> Result<BTreeMap<MintUrl, SeedSweepResult>, Error> {
+        let wallets = self.wallets.read().await;
+        let mut results = BTreeMap::new();
+
+        for mint_url in mints {
+            let wallet = wallets.get(mint_url).ok_or(Error::UnknownMint {
+                mint_url: mint_url.to_string(),
+            })?;
+
+            let result = wallet
+                .sweep_from_seed(old_seed, SeedRestoreOptions::default())
+                .await?;
+            results.insert(mint_url.clone(), result);
+        }
+
+        Ok(results)
+    }
+
     /// Verify token matches p2pk conditions
     #[instrument(skip(self, token))]
     pub async fn verify_token_p2pk(
@@ -1836,13 +2056,26 @@ impl MultiMintWallet {
         options: Option<MeltOptions>,
         max_fee: Option<Amount>,
     ) -> Result<Melted, Error> {
+        self.melt_from_best_mint(bolt11, options, max_fee)
+            .await
+            .map(|(_, melted)| melted)
+    }
+
+    /// Pay an invoice from the single mint with the lowest fee reserve that can cover it
+    async fn melt_from_best_mint(
+        &self,
+        bolt11: &str,
+        options: Option<MeltOptions>,
+        max_fee: Option<Amount>,
+    ) -> Result<(MintUrl, Melted), Error> {
         // Parse the invoice to get the amount
         let invoice = bolt11
             .parse::<crate::Bolt11Invoice>()
             .map_err(Error::Invoice)?;
 
-        let amount = invoice
-            .amount_milli_satoshis()
+        let amount = options
+            .map(|opt| u64::from(opt.amount_msat()))
+            .or_else(|| invoice.amount_milli_satoshis())
             .map(|msats| Amount::from(msats / 1000))
             .ok_or(Error::InvoiceAmountUndefined)?;
 
@@ -1864,7 +2097,7 @@ impl MultiMintWallet {
         let mut best_quote = None;
         let mut best_wallet = None;
 
-        for (_, wallet) in eligible_wallets.iter() {
+        for (mint_url, wallet) in eligible_wallets.iter() {
             match wallet.melt_quote(bolt11.to_string(), options).await {
                 Ok(quote) => {
                     if let Some(max_fee) = max_fee {
@@ -1875,11 +2108,11 @@ impl MultiMintWallet {
 
                     if best_quote.is_none() {
                         best_quote = Some(quote);
-                        best_wallet = Some(wallet.clone());
+                        best_wallet = Some((mint_url.clone(), wallet.clone()));
                     } else if let Some(ref existing_quote) = best_quote {
                         if quote.fee_reserve < existing_quote.fee_reserve {
                             best_quote = Some(quote);
-                            best_wallet = Some(wallet.clone());
+                            best_wallet = Some((mint_url.clone(), wallet.clone()));
                         }
                     }
                 }
@@ -1887,13 +2120,234 @@ impl MultiMintWallet {
             }
         }
 
-        if let (Some(quote), Some(wallet)) = (best_quote, best_wallet) {
-            return wallet.melt(&quote.id).await;
+        if let (Some(quote), Some((mint_url, wallet))) = (best_quote, best_wallet) {
+            // Release the wallets lock before paying
+            drop(wallets);
+            return Ok((mint_url, wallet.melt(&quote.id).await?));
         }
 
         Err(Error::InsufficientFunds)
     }
 
+    /// Pay an invoice, splitting it across mints with NUT-15 MPP when needed
+    ///
+    /// If a single mint can cover the invoice it is paid as in [`Self::melt`].
+    /// Otherwise the amount is split across mints advertising MPP for bolt11 in
+    /// this wallet's unit, largest balance first, leaving room at each mint for its
+    /// fee reserve and keeping each part within the part limits the mint advertises.
+    /// All parts are melted concurrently. If any part fails, the inputs of the
+    /// parts that were not paid are reclaimed before the error is returned.
+    #[instrument(skip(self, bolt11))]
+    pub async fn melt_with_auto_mpp(
+        &self,
+        bolt11: &str,
+        max_fee: Option<Amount>,
+    ) -> Result<MppMelted, Error> {
+        let invoice = bolt11
+            .parse::<crate::Bolt11Invoice>()
+            .map_err(Error::Invoice)?;
+        let amount = invoice
+            .amount_milli_satoshis()
+            .map(|msats| Amount::from(msats / 1000))
+            .ok_or(Error::InvoiceAmountUndefined)?;
+
+        // Prefer paying from a single mint when one has enough funds
+        match self.melt_from_best_mint(bolt11, None, max_fee).await {
+            Ok((mint_url, melted)) => {
+                return Ok(MppMelted {
+                    state: melted.state,
+                    preimage: melted.preimage.clone(),
+                    amount: melted.amount,
+                    fee_paid: melted.fee_paid,
+                    parts: vec![(mint_url, melted)],
+                });
+            }
+            Err(Error::InsufficientFunds) => (),
+            Err(err) => return Err(err),
+        }
+
+        let wallets: Vec<(MintUrl, Wallet)> = self
+            .wallets
+            .read()
+            .await
+            .iter()
+            .map(|(mint_url, wallet)| (mint_url.clone(), wallet.clone()))
+            .collect();
+
+        // Only mints advertising MPP for bolt11 in our unit can take part
+        let mut candidates = Vec::new();
+        for (mint_url, wallet) in wallets {
+            let balance = wallet.total_balance().await?;
+            if balance == Amount::ZERO {
+                continue;
+            }
+
+            let mpp_settings = match wallet.load_mint_info().await {
+                Ok(mint_info) => mint_info
+                    .nuts
+                    .nut15
+                    .get_settings(&self.unit, &PaymentMethod::BOLT11)
+                    .cloned(),
+                Err(err) => {
+                    tracing::warn!("Could not load mint info for {}: {}", mint_url, err);
+                    None
+                }
+            };
+
+            if let Some(mpp_settings) = mpp_settings {
+                candidates.push((mint_url, wallet, balance, mpp_settings));
+            }
+        }
+        candidates.sort_by(|a, b| b.2.cmp(&a.2));
+
+        // Probe each mint for its fee reserve to learn how much it can contribute
+        let mut allocations = Vec::new();
+        let mut remaining = amount;
+        for (mint_url, wallet, balance, mpp_settings) in &candidates {
+            if remaining == Amount::ZERO {
+                break;
+            }
+
+            let max_part = mpp_settings.max_amount.unwrap_or(remaining);
+            let probe_amount = std::cmp::min(std::cmp::min(*balance, remaining), max_part);
+            if !mpp_settings.is_within_limits(probe_amount) {
+                tracing::debug!(
+                    "Skipping {} for MPP, {} is below its minimum part",
+                    mint_url,
+                    probe_amount
+                );
+                continue;
+            }
+            let probe_quote = match wallet
+                .melt_quote(
+                    bolt11.to_string(),
+                    Some(MeltOptions::new_mpp(u64::from(probe_amount) * 1000)),
+                )
+                .await
+            {
+                Ok(quote) => quote,
+                Err(err) => {
+                    tracing::warn!("Skipping {} for MPP, melt quote failed: {}", mint_url, err);
+                    continue;
+                }
+            };
+
+            let spendable = balance
+                .checked_sub(probe_quote.fee_reserve)
+                .unwrap_or(Amount::ZERO);
+            let part = std::cmp::min(std::cmp::min(spendable, remaining), max_part);
+            if part == Amount::ZERO || !mpp_settings.is_within_limits(part) {
+                continue;
+            }
+
+            allocations.push((mint_url.clone(), part));
+            remaining -= part;
+        }
+
+        if remaining > Amount::ZERO {
+            return Err(Error::InsufficientFunds);
+        }
+
+        let quotes = self.mpp_melt_quote(bolt11.to_string(), allocations).await?;
+
+        let total_fee_reserve = Amount::try_sum(quotes.iter().map(|(_, quote)| quote.fee_reserve))?;
+        if max_fee.is_some_and(|max_fee| total_fee_reserve > max_fee) {
+            return Err(Error::MaxFeeExceeded);
+        }
+
+        for (mint_url, quote) in &quotes {
+            let balance = candidates
+                .iter()
+                .find(|(url, _, _, _)| url == mint_url)
+                .map(|(_, _, balance, _)| *balance)
+                .unwrap_or(Amount::ZERO);
+            if quote.amount + quote.fee_reserve > balance {
+                return Err(Error::InsufficientFunds);
+            }
+        }
+
+        // Melt all parts concurrently and wait for every part before deciding the outcome
+        let mut tasks = Vec::new();
+        for (mint_url, quote) in quotes {
+            let wallet = candidates
+                .iter()
+                .find(|(url, _, _, _)| *url == mint_url)
+                .map(|(_, wallet, _, _)| wallet.clone())
+                .ok_or(Error::UnknownMint {
+                    mint_url: mint_url.to_string(),
+                })?;
+
+            let task_wallet = wallet.clone();
+            let quote_id = quote.id.clone();
+            tasks.push((
+                wallet,
+                quote.id,
+                spawn(async move {
+                    let melted = task_wallet.melt(&quote_id).await;
+                    (mint_url, melted)
+                }),
+            ));
+        }
+
+        let mut parts = Vec::new();
+        let mut unpaid = Vec::new();
+        let mut failure = None;
+        for (wallet, quote_id, task) in tasks {
+            match task.await {
+                Ok((mint_url, Ok(melted))) => {
+                    if melted.state != MeltQuoteState::Paid {
+                        tracing::error!(
+                            "MPP part at {} finished in state {}",
+                            mint_url,
+                            melted.state
+                        );
+                        failure.get_or_insert(Error::PaymentFailed);
+                        unpaid.push((wallet, quote_id));
+                    }
+                    parts.push((mint_url, melted));
+                }
+                Ok((mint_url, Err(err))) => {
+                    tracing::error!("MPP part at {} failed: {}", mint_url, err);
+                    failure.get_or_insert(err);
+                    unpaid.push((wallet, quote_id));
+                }
+                Err(err) => {
+                    tracing::error!("MPP melt task failed: {}", err);
+                    failure.get_or_insert(Error::Internal);
+                    unpaid.push((wallet, quote_id));
+                }
+            }
+        }
+
+        if let Some(err) = failure {
+            // Only the inputs of the parts that were not paid are reclaimed, other proofs of
+            // the wallets may be reserved by outstanding sends
+            for (wallet, quote_id) in unpaid {
+                if let Err(reclaim_err) = wallet.reclaim_melt_inputs(&quote_id).await {
+                    tracing::warn!(
+                        "Could not reclaim the inputs of melt quote {} at {}: {}",
+                        quote_id,
+                        wallet.mint_url,
+                        reclaim_err
+                    );
+                }
+            }
+            return Err(err);
+        }
+
+        let amount = Amount::try_sum(parts.iter().map(|(_, melted)| melted.amount))?;
+        let fee_paid = Amount::try_sum(parts.iter().map(|(_, melted)| melted.fee_paid))?;
+        let preimage = parts.iter().find_map(|(_, melted)| melted.preimage.clone());
+
+        Ok(MppMelted {
+            state: MeltQuoteState::Paid,
+            preimage,
+            amount,
+            fee_paid,
+            parts,
+        })
+    }
+
     /// Swap proofs with automatic wallet selection
     #[instrument(skip(self))]
     pub async fn swap(
@@ -1921,40 +2375,25 @@ impl MultiMintWallet {
     }
 
     /// Consolidate proofs from multiple wallets into fewer, larger proofs
-    /// This can help reduce the number of proofs and optimize wallet performance
+    ///
+    /// Runs [`Wallet::consolidate`] on every wallet and returns the value received for the
+    /// consolidated proofs, after fees. A wallet that fails to consolidate is logged and skipped.
     #[instrument(skip(self))]
     pub async fn consolidate(&self) -> Result<Amount, Error> {
         let mut total_consolidated = Amount::ZERO;
         let wallets = self.wallets.read().await;
 
         for (mint_url, wallet) in wallets.iter() {
-            // Get all unspent proofs for this wallet
-            let proofs = wallet.get_unspent_proofs().await?;
-            if proofs.len() > 1 {
-                // Consolidate by swapping all proofs for a single set
-                let proofs_amount = proofs.total_amount()?;
-
-                // Swap for optimized proof set
-                match wallet
-                    .swap(
-                        Some(proofs_amount),
-                        SplitTarget::default(),
-                        proofs,
-                        None,
-                        false,
-                    )
-                    .await
-                {
-                    Ok(_) => {
-                        total_consolidated += proofs_amount;
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            "Failed to consolidate proofs for mint {:?}: {}",
-                            mint_url,
-                            e
-                        );
-                    }
+            match wallet.consolidate().await {
+                Ok(result) => {
+                    total_consolidated += result.amount_received;
+                }
+                Err(e) => {
+                    tracing::warn!(
+                        "Failed to consolidate proofs for mint {:?}: {}",
+                        mint_url,
+                        e
+                    );
                 }
             }
         }
@@ -2179,13 +2618,20 @@ impl Drop for MultiMintWallet {
     }
 }
 
+/// Callback deciding whether an unknown mint may be added when receiving
+pub type MintAllowlist = Arc<dyn Fn(&MintUrl) -> bool + Send + Sync>;
+
 /// Multi-Mint Receive Options
 ///
 /// Controls how tokens are received, especially from untrusted mints
-#[derive(Debug, Clone, Default)]
+#[derive(Clone, Default)]
 pub struct MultiMintReceiveOptions {
     /// Whether to allow receiving from untrusted (not yet added) mints
     pub allow_untrusted: bool,
+    /// Callback consulted for untrusted mints when `allow_untrusted` is false
+    ///
+    /// Mints it accepts are added to the wallet before receiving.
+    pub mint_allowlist: Option<MintAllowlist>,
     /// Mint to transfer tokens to from untrusted mints (None means keep in original mint)
     pub transfer_to_mint: Option<MintUrl>,
     /// Base receive options to apply to the wallet receive
@@ -2204,6 +2650,15 @@ impl MultiMintReceiveOptions {
         self
     }
 
+    /// Only accept untrusted mints approved by the allowlist callback
+    pub fn mint_allowlist<F>(mut self, allowlist: F) -> Self
+    where
+        F: Fn(&MintUrl) -> bool + Send + Sync + 'static,
+    {
+        self.mint_allowlist = Some(Arc::new(allowlist));
+        self
+    }
+
     /// Set mint to transfer tokens to from untrusted mints
     pub fn transfer_to_mint(mut self, mint_url: Option<MintUrl>) -> Self {
         self.transfer_to_mint = mint_url;
@@ -2217,6 +2672,17 @@ impl MultiMintReceiveOptions {
     }
 }
 
+impl std::fmt::Debug for MultiMintReceiveOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MultiMintReceiveOptions")
+            .field("allow_untrusted", &self.allow_untrusted)
+            .field("mint_allowlist", &self.mint_allowlist.is_some())
+            .field("transfer_to_mint", &self.transfer_to_mint)
+            .field("receive_options", &self.receive_options)
+            .finish()
+    }
+}
+
 /// Multi-Mint Send Options
 ///
 /// Controls transfer behavior when the target mint doesn't have sufficient balance
@@ -2455,4 +2921,83 @@ mod tests {
         };
         assert!(token_data_no_memo.memo.is_none());
     }
+
+    #[tokio::test]
+    async fn test_transfer_same_mint_rejected() {
+        use std::str::FromStr;
+
+        let multi_wallet = create_test_multi_wallet().await;
+        let mint_url = MintUrl::from_str("https://mint1.example.com").unwrap();
+
+        let result = multi_wallet
+            .transfer(&mint_url, &mint_url, TransferMode::FullBalance)
+            .await;
+
+        assert!(matches!(result, Err(Error::SameMintTransfer { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_receive_rejected_by_allowlist() {
+        let multi_wallet = create_test_multi_wallet().await;
+
+        // Token from localhost:3338, which has not been added to the wallet
+        let token_str = "cashuBpGF0gaJhaUgArSaMTR9YJmFwgaNhYQFhc3hAOWE2ZGJiODQ3YmQyMzJiYTc2ZGIwZGYxOTcyMTZiMjlkM2I4Y2MxNDU1M2NkMjc4MjdmYzFjYzk0MmZlZGI0ZWFjWCEDhhhUP_trhpXfStS6vN6So0qWvc2X3O4NfM-Y1HISZ5JhZGlUaGFuayB5b3VhbXVodHRwOi8vbG9jYWxob3N0OjMzMzhhdWNzYXQ=";
+        let options = MultiMintReceiveOptions::new()
+            .mint_allowlist(|mint_url| mint_url.to_string().contains("trusted.example.com"));
+
+        let result = multi_wallet.receive(token_str, options).await;
+
+        assert!(matches!(result, Err(Error::UnknownMint { .. })));
+        assert!(multi_wallet.get_wallets().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_balances_by_unit() {
+        use std::str::FromStr;
+
+        use cdk_common::secret::Secret;
+
+        use crate::nuts::{Id, PublicKey};
+        use crate::types::ProofInfo;
+
+        let multi_wallet = create_test_multi_wallet().await;
+        let mint1 = MintUrl::from_str("https://mint1.example.com").unwrap();
+        let mint2 = MintUrl::from_str("https://mint2.example.com").unwrap();
+
+        let proof_info = |amount: u64, mint_url: &MintUrl, unit: CurrencyUnit, state: State| {
+            let proof = Proof::new(
+                Amount::from(amount),
+                Id::from_bytes(&[0; 8]).unwrap(),
+                Secret::generate(),
+                PublicKey::from_hex(
+                    "03deadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeefdeadbeef",
+                )
+                .unwrap(),
+            );
+            ProofInfo::new(proof, mint_url.clone(), state, unit).unwrap()
+        };
+
+        multi_wallet
+            .localstore
+            .update_proofs(
+                vec![
+                    proof_info(8, &mint1, CurrencyUnit::Sat, State::Unspent),
+                    proof_info(2, &mint1, CurrencyUnit::Sat, State::Unspent),
+                    proof_info(4, &mint2, CurrencyUnit::Sat, State::Unspent),
+                    proof_info(16, &mint2, CurrencyUnit::Usd, State::Unspent),
+                    proof_info(32, &mint1, CurrencyUnit::Sat, State::Spent),
+                ],
+                vec![],
+            )
+            .await
+            .unwrap();
+
+        let balances = multi_wallet.get_balances_by_unit().await.unwrap();
+
+        assert_eq!(balances.len(), 2);
+        assert_eq!(balances[&CurrencyUnit::Sat][&mint1], Amount::from(10));
+        assert_eq!(balances[&CurrencyUnit::Sat][&mint2], Amount::from(4));
+        assert_eq!(balances[&CurrencyUnit::Usd][&mint2], Amount::from(16));
+        assert!(!balances[&CurrencyUnit::Usd].contains_key(&mint1));
+    }
 }
@@ -0,0 +1,452 @@
+//! Nostr Token Transport
+//!
+//! Send ecash to an npub as a Nostr direct message and redeem tokens received
+//! the same way. Messages are sent as NIP-17 gift wraps, optionally falling back
+//! to NIP-04, and both kinds are accepted when receiving.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::time::Duration;
+
+use bitcoin::hashes::sha256::Hash as Sha256Hash;
+use bitcoin::hashes::Hash;
+use nostr_sdk::nips::nip04;
+use nostr_sdk::nips::nip59::UnwrappedGift;
+use nostr_sdk::prelude::*;
+use nostr_sdk::{Client as NostrClient, Filter, Keys};
+use tracing::instrument;
+
+use super::multi_mint_wallet::{MultiMintReceiveOptions, MultiMintWallet};
+use super::send::{SendMemo, SendOptions};
+use crate::error::Error;
+use crate::mint_url::MintUrl;
+use crate::nuts::Token;
+use crate::Amount;
+
+/// Domain separator used to derive the direct message keys from the wallet seed
+const DOMAIN_SEPARATOR: &[u8] = b"cashu-nostr-dm";
+
+/// KV store namespace for Nostr direct messages
+const NOSTR_DM_KV_NAMESPACE: &str = "nostr_dm";
+
+/// KV store secondary namespace for events whose tokens have been claimed
+const CLAIMED_EVENTS_NAMESPACE: &str = "claimed";
+
+/// Options for sending and receiving tokens over Nostr
+#[derive(Debug, Clone)]
+pub struct NostrTransportOptions {
+    /// Relays to publish to and read from
+    pub relays: Vec<String>,
+    /// Timeout for waiting for relay responses
+    pub timeout: Duration,
+    /// Send a NIP-04 direct message if the NIP-17 message could not be published
+    pub nip04_fallback: bool,
+    /// Options applied when redeeming received tokens
+    pub receive_options: MultiMintReceiveOptions,
+}
+
+impl Default for NostrTransportOptions {
+    fn default() -> Self {
+        Self {
+            relays: Vec::new(),
+            timeout: Duration::from_secs(10),
+            nip04_fallback: false,
+            receive_options: MultiMintReceiveOptions::default(),
+        }
+    }
+}
+
+impl NostrTransportOptions {
+    /// Create new options using the given relays
+    pub fn new<S>(relays: Vec<S>) -> Self
+    where
+        S: Into<String>,
+    {
+        Self {
+            relays: relays.into_iter().map(Into::into).collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Set the timeout for relay responses
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Fall back to a NIP-04 direct message if NIP-17 publishing fails
+    pub fn nip04_fallback(mut self, fallback: bool) -> Self {
+        self.nip04_fallback = fallback;
+        self
+    }
+
+    /// Set the options applied when redeeming received tokens
+    pub fn receive_options(mut self, options: MultiMintReceiveOptions) -> Self {
+        self.receive_options = options;
+        self
+    }
+}
+
+/// Result of sending a token over Nostr
+#[derive(Debug, Clone)]
+pub struct NostrSent {
+    /// The event ID of the published direct message
+    pub event_id: EventId,
+    /// Mint the token was sent from
+    pub mint_url: MintUrl,
+    /// Amount of the token
+    pub amount: Amount,
+    /// The token that was sent
+    pub token: Token,
+}
+
+/// A token redeemed from a Nostr direct message
+#[derive(Debug, Clone)]
+pub struct NostrReceived {
+    /// The event ID of the direct message
+    pub event_id: EventId,
+    /// Public key of the sender
+    pub sender: PublicKey,
+    /// Mint of the redeemed token
+    pub mint_url: MintUrl,
+    /// Amount received
+    pub amount: Amount,
+}
+
+impl MultiMintWallet {
+    /// Derive the Nostr keys used for direct messages from the wallet seed
+    ///
+    /// The public key of these keys is the npub others send tokens to.
+    pub fn nostr_dm_keys(&self) -> Result<Keys, Error> {
+        derive_dm_keys(self.seed())
+    }
+
+    /// Send a token to an npub as a Nostr direct message
+    ///
+    /// The token is created from the mint with the highest balance that covers
+    /// `amount`. If the message cannot be published to any relay the token is
+    /// reclaimed and an error is returned.
+    #[instrument(skip(self, options))]
+    pub async fn send_to_nostr(
+        &self,
+        npub: &str,
+        amount: Amount,
+        memo: Option<String>,
+        options: NostrTransportOptions,
+    ) -> Result<NostrSent, Error> {
+        let receiver =
+            PublicKey::parse(npub).map_err(|e| Error::Custom(format!("Invalid npub: {e}")))?;
+
+        let mint_url = self
+            .get_balances()
+            .await?
+            .into_iter()
+            .filter(|(_, balance)| *balance >= amount)
+            .max_by_key(|(_, balance)| *balance)
+            .map(|(mint_url, _)| mint_url)
+            .ok_or(Error::InsufficientFunds)?;
+        let wallet = self.get_wallet(&mint_url).await.ok_or(Error::UnknownMint {
+            mint_url: mint_url.to_string(),
+        })?;
+
+        let prepared_send = wallet.prepare_send(amount, SendOptions::default()).await?;
+        let token = prepared_send
+            .confirm(memo.as_deref().map(SendMemo::for_token))
+            .await?;
+
+        match publish_dm(
+            &self.nostr_dm_keys()?,
+            &receiver,
+            &token.to_string(),
+            &options,
+        )
+        .await
+        {
+            Ok(event_id) => Ok(NostrSent {
+                event_id,
+                mint_url,
+                amount,
+                token,
+            }),
+            Err(err) => {
+                tracing::warn!("Could not publish token to {}, reclaiming: {}", npub, err);
+                wallet
+                    .receive(&token.to_string(), Default::default())
+                    .await?;
+                Err(err)
+            }
+        }
+    }
+
+    /// Redeem tokens received as Nostr direct messages
+    ///
+    /// Fetches NIP-17 and NIP-04 messages addressed to [`Self::nostr_dm_keys`],
+    /// redeems every token they contain and records the sender in the transaction
+    /// metadata. Events are remembered once claimed so they are not processed again.
+    #[instrument(skip(self, options))]
+    pub async fn receive_from_nostr(
+        &self,
+        options: NostrTransportOptions,
+    ) -> Result<Vec<NostrReceived>, Error> {
+        let keys = self.nostr_dm_keys()?;
+
+        let client = NostrClient::new(keys.clone());
+        for relay in options.relays.iter() {
+            client
+                .add_read_relay(relay.as_str())
+                .await
+                .map_err(|e| Error::Custom(format!("Failed to add relay: {e}")))?;
+        }
+        client.connect().await;
+
+        let filter = Filter::new()
+            .pubkey(keys.public_key())
+            .kinds([Kind::GiftWrap, Kind::EncryptedDirectMessage]);
+        let events = client
+            .fetch_events(filter, options.timeout)
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to fetch direct messages: {e}")));
+        client.disconnect().await;
+
+        let mut received = Vec::new();
+        for event in events? {
+            let event_key = event.id.to_hex();
+            if self
+                .localstore()
+                .kv_read(NOSTR_DM_KV_NAMESPACE, CLAIMED_EVENTS_NAMESPACE, &event_key)
+                .await?
+                .is_some()
+            {
+                continue;
+            }
+
+            let (sender, message) = match decode_dm(&keys, &event).await {
+                Ok(decoded) => decoded,
+                Err(err) => {
+                    tracing::debug!("Ignoring undecodable event {}: {}", event.id, err);
+                    continue;
+                }
+            };
+
+            let mut claimed = true;
+            for token in extract_tokens(&message) {
+                let mint_url = token.mint_url()?;
+
+                let mut metadata = HashMap::new();
+                metadata.insert(
+                    "nostr_sender".to_string(),
+                    sender
+                        .to_bech32()
+                        .map_err(|e| Error::Custom(format!("Invalid sender key: {e}")))?,
+                );
+                metadata.insert("nostr_event_id".to_string(), event_key.clone());
+
+                let mut receive_options = options.receive_options.clone();
+                receive_options.receive_options.metadata.extend(metadata);
+
+                match self.receive(&token.to_string(), receive_options).await {
+                    Ok(amount) => received.push(NostrReceived {
+                        event_id: event.id,
+                        sender,
+                        mint_url,
+                        amount,
+                    }),
+                    Err(Error::TokenAlreadySpent) => {
+                        tracing::debug!("Token in event {} was already claimed", event.id);
+                    }
+                    Err(err) => {
+                        tracing::warn!("Failed to redeem token from event {}: {}", event.id, err);
+                        claimed = false;
+                    }
+                }
+            }
+
+            // Leave events with failed redemptions unclaimed so they are retried
+            if claimed {
+                self.localstore()
+                    .kv_write(
+                        NOSTR_DM_KV_NAMESPACE,
+                        CLAIMED_EVENTS_NAMESPACE,
+                        &event_key,
+                        &event.created_at.as_secs().to_be_bytes(),
+                    )
+                    .await?;
+            }
+        }
+
+        Ok(received)
+    }
+}
+
+/// Derive the direct message keys from the wallet seed
+fn derive_dm_keys(seed: &[u8; 64]) -> Result<Keys, Error> {
+    let mut combined_data = Vec::with_capacity(seed.len() + DOMAIN_SEPARATOR.len());
+    combined_data.extend_from_slice(seed);
+    combined_data.extend_from_slice(DOMAIN_SEPARATOR);
+
+    let hash = Sha256Hash::hash(&combined_data);
+    let secret_key = nostr_sdk::SecretKey::from_slice(&hash.to_byte_array())
+        .map_err(|e| Error::Custom(format!("Failed to derive Nostr keys: {e}")))?;
+
+    Ok(Keys::new(secret_key))
+}
+
+/// Publish `message` to `receiver` as a NIP-17 direct message, falling back to NIP-04
+async fn publish_dm(
+    keys: &Keys,
+    receiver: &PublicKey,
+    message: &str,
+    options: &NostrTransportOptions,
+) -> Result<EventId, Error> {
+    if options.relays.is_empty() {
+        return Err(Error::Custom("No relays provided".to_string()));
+    }
+
+    let client = NostrClient::new(keys.clone());
+    for relay in options.relays.iter() {
+        client
+            .add_write_relay(relay.as_str())
+            .await
+            .map_err(|e| Error::Custom(format!("Failed to add relay: {e}")))?;
+    }
+    client.connect().await;
+
+    let rumor = EventBuilder::private_msg_rumor(*receiver, message).build(keys.public_key());
+    let result = match client
+        .gift_wrap_to(
+            options.relays.iter().map(String::as_str),
+            receiver,
+            rumor,
+            [],
+        )
+        .await
+    {
+        Ok(output) if !output.success.is_empty() => Ok(output.val),
+        Ok(output) => Err(Error::Custom(format!(
+            "Failed to publish direct message: {:?}",
+            output.failed
+        ))),
+        Err(e) => Err(Error::Custom(format!(
+            "Failed to publish direct message: {e}"
+        ))),
+    };
+
+    let result = match result {
+        Err(err) if options.nip04_fallback => {
+            tracing::debug!("NIP-17 publish failed, falling back to NIP-04: {}", err);
+            let event = nip04_event(keys, receiver, message)?;
+            match client
+                .send_event_to(options.relays.iter().map(String::as_str), &event)
+                .await
+            {
+                Ok(output) if !output.success.is_empty() => Ok(output.val),
+                Ok(output) => Err(Error::Custom(format!(
+                    "Failed to publish direct message: {:?}",
+                    output.failed
+                ))),
+                Err(e) => Err(Error::Custom(format!(
+                    "Failed to publish direct message: {e}"
+                ))),
+            }
+        }
+        result => result,
+    };
+
+    client.disconnect().await;
+
+    result
+}
+
+/// Build a NIP-04 encrypted direct message
+fn nip04_event(keys: &Keys, receiver: &PublicKey, message: &str) -> Result<Event, Error> {
+    let content = nip04::encrypt(keys.secret_key(), receiver, message)
+        .map_err(|e| Error::Custom(format!("Failed to encrypt direct message: {e}")))?;
+
+    EventBuilder::new(Kind::EncryptedDirectMessage, content)
+        .tag(Tag::public_key(*receiver))
+        .sign_with_keys(keys)
+        .map_err(|e| Error::Custom(format!("Failed to sign direct message: {e}")))
+}
+
+/// Decode a NIP-17 gift wrap or NIP-04 event into its sender and message
+async fn decode_dm(keys: &Keys, event: &Event) -> Result<(PublicKey, String), Error> {
+    match event.kind {
+        Kind::GiftWrap => {
+            let unwrapped = UnwrappedGift::from_gift_wrap(keys, event)
+                .await
+                .map_err(|e| Error::Custom(format!("Failed to unwrap gift wrap: {e}")))?;
+            Ok((unwrapped.sender, unwrapped.rumor.content))
+        }
+        Kind::EncryptedDirectMessage => {
+            let message = nip04::decrypt(keys.secret_key(), &event.pubkey, &event.content)
+                .map_err(|e| Error::Custom(format!("Failed to decrypt direct message: {e}")))?;
+            Ok((event.pubkey, message))
+        }
+        kind => Err(Error::Custom(format!("Unsupported event kind {kind}"))),
+    }
+}
+
+/// Extract every cashu token embedded in a message
+fn extract_tokens(message: &str) -> Vec<Token> {
+    message
+        .split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("cashu")?;
+            Token::from_str(word[start..].trim_end_matches(['.', ',', ')', '"'])).ok()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: &str = "cashuBpGF0gaJhaUgArSaMTR9YJmFwgaNhYQFhc3hAOWE2ZGJiODQ3YmQyMzJiYTc2ZGIwZGYxOTcyMTZiMjlkM2I4Y2MxNDU1M2NkMjc4MjdmYzFjYzk0MmZlZGI0ZWFjWCEDhhhUP_trhpXfStS6vN6So0qWvc2X3O4NfM-Y1HISZ5JhZGlUaGFuayB5b3VhbXVodHRwOi8vbG9jYWxob3N0OjMzMzhhdWNzYXQ=";
+
+    #[test]
+    fn test_derive_dm_keys_deterministic() {
+        let keys = derive_dm_keys(&[1u8; 64]).unwrap();
+        assert_eq!(
+            keys.public_key(),
+            derive_dm_keys(&[1u8; 64]).unwrap().public_key()
+        );
+        assert_ne!(
+            keys.public_key(),
+            derive_dm_keys(&[2u8; 64]).unwrap().public_key()
+        );
+    }
+
+    #[test]
+    fn test_extract_tokens() {
+        let message = format!("Here you go: {TOKEN}. Thanks!");
+        let tokens = extract_tokens(&message);
+        assert_eq!(tokens.len(), 1);
+        assert_eq!(tokens[0], Token::from_str(TOKEN).unwrap());
+
+        assert!(extract_tokens("no ecash here").is_empty());
+        assert!(extract_tokens("cashuBnotatoken").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_decode_nip17_and_nip04() {
+        let sender = Keys::generate();
+        let receiver = Keys::generate();
+
+        let rumor = EventBuilder::private_msg_rumor(receiver.public_key(), TOKEN)
+            .build(sender.public_key());
+        let gift_wrap = EventBuilder::gift_wrap(&sender, &receiver.public_key(), rumor, [])
+            .await
+            .unwrap();
+        let (decoded_sender, message) = decode_dm(&receiver, &gift_wrap).await.unwrap();
+        assert_eq!(decoded_sender, sender.public_key());
+        assert_eq!(message, TOKEN);
+
+        let event = nip04_event(&sender, &receiver.public_key(), TOKEN).unwrap();
+        let (decoded_sender, message) = decode_dm(&receiver, &event).await.unwrap();
+        assert_eq!(decoded_sender, sender.public_key());
+        assert_eq!(message, TOKEN);
+
+        // Messages for someone else cannot be decoded
+        assert!(decode_dm(&Keys::generate(), &gift_wrap).await.is_err());
+    }
+}
@@ -10,7 +10,7 @@ use tracing::instrument;
 
 use crate::error::Error;
 use crate::nuts::SecretKey;
-use crate::wallet::types::{MintQuote, TransactionDirection};
+use crate::wallet::types::{MintQuote, Pagination, TransactionDirection, TransactionFilter};
 use crate::wallet::Wallet;
 
 /// KV store namespace for npubcash-related data
@@ -234,7 +234,10 @@ impl Wallet {
         mint_quote.secret_key = Some(secret_key);
 
         let exists = self
-            .list_transactions(Some(TransactionDirection::Incoming))
+            .list_transactions(
+                TransactionFilter::new().with_direction(TransactionDirection::Incoming),
+                Pagination::default(),
+            )
             .await?
             .iter()
             .any(|tx| tx.quote_id.as_ref() == Some(&mint_quote.id));
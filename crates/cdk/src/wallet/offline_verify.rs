@@ -0,0 +1,428 @@
+//! Offline token verification
+//!
+//! Lets a wallet assess a token using only data already cached in its store,
+//! e.g. a merchant accepting payment without connectivity. Anything that needs
+//! the mint, such as the spent state of the proofs, is reported as unverified
+//! rather than assumed.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+use cdk_common::nut02::ShortKeysetId;
+use cdk_common::nuts::{CurrencyUnit, Id, KeySetInfo};
+use tracing::instrument;
+
+use super::mint_metadata_cache::keysets_updated_at;
+use crate::mint_url::MintUrl;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{Keys, Proof, Token};
+use crate::{Amount, Error, Wallet};
+
+/// Status of the keyset a proof claims to be signed by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineKeysetStatus {
+    /// Keyset is cached and active
+    Active,
+    /// Keyset is cached but no longer active, the proof can still be spent
+    Inactive,
+    /// Keyset is not cached for the token's mint
+    Unknown,
+}
+
+/// Outcome of the DLEQ (NUT-12) check of a proof
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfflineDleqStatus {
+    /// DLEQ proof verified against the cached mint key
+    Valid,
+    /// DLEQ proof present but does not verify
+    Invalid,
+    /// Proof carries no DLEQ proof
+    Missing,
+    /// No cached key for the keyset and amount, so the DLEQ could not be checked
+    KeyUnavailable,
+}
+
+/// Checks that cannot be completed without contacting the mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum OfflineCheck {
+    /// Whether the proofs have already been spent (double-spend)
+    ProofState,
+    /// Whether the keysets belong to the mint, when they are not cached
+    Keysets,
+    /// Whether the mint signed the proofs, when DLEQ proofs are missing
+    Signatures,
+}
+
+/// Offline check result for a single proof
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfflineProofReport {
+    /// Keyset id of the proof
+    pub keyset_id: Id,
+    /// Amount of the proof
+    pub amount: Amount,
+    /// Status of the keyset in the cache
+    pub keyset: OfflineKeysetStatus,
+    /// DLEQ verification result
+    pub dleq: OfflineDleqStatus,
+}
+
+/// Report produced by [`Wallet::verify_token_offline`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfflineTokenReport {
+    /// Mint the token claims to be from
+    pub mint_url: MintUrl,
+    /// Whether the mint is known to the wallet store
+    pub mint_trusted: bool,
+    /// Unix timestamp of the last time the mint's keysets were fetched,
+    /// `None` if they never were for this store
+    pub keysets_updated_at: Option<u64>,
+    /// Number of proofs whose secret appears more than once in the token
+    pub duplicate_proofs: usize,
+    /// Per proof results, empty if the token's keyset ids could not be
+    /// resolved against the cached keysets
+    pub proofs: Vec<OfflineProofReport>,
+    /// Sum of proof amounts per unit, for proofs of cached keysets
+    pub amounts: BTreeMap<CurrencyUnit, Amount>,
+    /// Checks that could not be performed offline
+    pub unverified: Vec<OfflineCheck>,
+}
+
+impl OfflineTokenReport {
+    /// Whether every check that could be performed offline passed
+    ///
+    /// A valid report still carries the risks listed in
+    /// [`OfflineTokenReport::unverified`], in particular double-spends.
+    pub fn is_valid(&self) -> bool {
+        self.mint_trusted
+            && self.duplicate_proofs == 0
+            && !self.proofs.is_empty()
+            && self.proofs.iter().all(|proof| {
+                proof.keyset != OfflineKeysetStatus::Unknown
+                    && proof.dleq != OfflineDleqStatus::Invalid
+                    && proof.dleq != OfflineDleqStatus::KeyUnavailable
+            })
+    }
+}
+
+/// Proof of an inspected token
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenProofInspection {
+    /// Amount of the proof
+    pub amount: Amount,
+    /// DLEQ verification result
+    pub dleq: OfflineDleqStatus,
+}
+
+/// Keyset of an inspected token, with its proofs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenKeysetInspection {
+    /// Keyset id as carried by the token, a prefix of the id for V4 tokens
+    pub short_id: ShortKeysetId,
+    /// Full keyset id, `None` if it could not be resolved against the cached keysets
+    pub id: Option<Id>,
+    /// Unit of the keyset, `None` if it is not cached
+    pub unit: Option<CurrencyUnit>,
+    /// Status of the keyset in the cache
+    pub status: OfflineKeysetStatus,
+    /// Whether the keys of the keyset are cached
+    pub keys_cached: bool,
+    /// Proofs signed by the keyset
+    pub proofs: Vec<TokenProofInspection>,
+}
+
+/// Result of [`Wallet::inspect_token`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenInspection {
+    /// Mint the token claims to be from
+    pub mint_url: MintUrl,
+    /// Unit of the token
+    pub unit: Option<CurrencyUnit>,
+    /// Memo text of the token
+    pub memo: Option<String>,
+    /// Sum of the proof amounts
+    pub value: Amount,
+    /// Keysets of the token, in the order they appear in it
+    pub keysets: Vec<TokenKeysetInspection>,
+}
+
+impl TokenInspection {
+    /// Whether every keyset of the token is cached with its keys
+    ///
+    /// Otherwise only the structure of the token is known for the other keysets: their ids and
+    /// proof amounts, but not their unit nor whether the mint signed the proofs.
+    pub fn is_complete(&self) -> bool {
+        self.keysets
+            .iter()
+            .all(|keyset| keyset.status != OfflineKeysetStatus::Unknown && keyset.keys_cached)
+    }
+
+    /// Sum of the proof amounts per unit, for the cached keysets
+    pub fn amounts(&self) -> Result<BTreeMap<CurrencyUnit, Amount>, Error> {
+        let mut amounts: BTreeMap<CurrencyUnit, Amount> = BTreeMap::new();
+        for keyset in &self.keysets {
+            if let Some(unit) = &keyset.unit {
+                let amount = amounts.entry(unit.clone()).or_default();
+                for proof in &keyset.proofs {
+                    *amount = amount
+                        .checked_add(proof.amount)
+                        .ok_or(Error::AmountOverflow)?;
+                }
+            }
+        }
+        Ok(amounts)
+    }
+}
+
+impl Wallet {
+    /// Inspect a token using only cached mint data, without contacting the mint
+    ///
+    /// Lists the keysets of the token with their unit and proofs, and verifies the DLEQ proofs
+    /// against the cached keys. Keysets that are not cached are still listed with their proof
+    /// amounts, see [`TokenInspection::is_complete`]. Unlike
+    /// [`Wallet::verify_token_offline`] it does not judge whether the token can be accepted.
+    #[instrument(skip(self, token))]
+    pub async fn inspect_token(&self, token: &Token) -> Result<TokenInspection, Error> {
+        let mint_url = token.mint_url()?;
+        let cached_keysets: Vec<KeySetInfo> = self
+            .localstore
+            .get_mint_keysets(mint_url.clone())
+            .await?
+            .unwrap_or_default();
+        let keysets_by_id: HashMap<Id, &KeySetInfo> =
+            cached_keysets.iter().map(|k| (k.id, k)).collect();
+
+        let mut value = Amount::ZERO;
+        let mut keysets = Vec::new();
+
+        for (short_id, amounts, proofs) in token_keysets(token, &cached_keysets) {
+            for amount in &amounts {
+                value = value.checked_add(*amount).ok_or(Error::AmountOverflow)?;
+            }
+
+            let id = proofs.first().map(|proof| proof.keyset_id);
+            let keyset = id.and_then(|id| keysets_by_id.get(&id));
+            let keys = match id {
+                Some(id) => self.localstore.get_keys(&id).await?,
+                None => None,
+            };
+
+            let proofs = if proofs.is_empty() {
+                // Only the amounts are known without the full keyset id
+                amounts
+                    .into_iter()
+                    .map(|amount| TokenProofInspection {
+                        amount,
+                        dleq: OfflineDleqStatus::KeyUnavailable,
+                    })
+                    .collect()
+            } else {
+                proofs
+                    .iter()
+                    .map(|proof| TokenProofInspection {
+                        amount: proof.amount,
+                        dleq: dleq_status(proof, keys.as_ref()),
+                    })
+                    .collect()
+            };
+
+            keysets.push(TokenKeysetInspection {
+                short_id,
+                id,
+                unit: keyset.map(|keyset| keyset.unit.clone()),
+                status: keyset_status(keyset.copied()),
+                keys_cached: keys.is_some(),
+                proofs,
+            });
+        }
+
+        Ok(TokenInspection {
+            mint_url,
+            unit: token.unit(),
+            memo: token.memo_text(),
+            value,
+            keysets,
+        })
+    }
+
+    /// Verify a token using only cached mint data, without contacting the mint
+    ///
+    /// Checks the token structure, that each proof's keyset is a cached keyset
+    /// of a mint known to the wallet store, and DLEQ proofs where present.
+    /// The result is a report rather than an error so callers can decide
+    /// which risks to accept. Errors are returned only when the token cannot
+    /// be assessed at all, such as multi-mint v3 tokens.
+    #[instrument(skip(self, token))]
+    pub async fn verify_token_offline(&self, token: &Token) -> Result<OfflineTokenReport, Error> {
+        let mint_url = token.mint_url()?;
+        token.value()?;
+
+        let mint_trusted = self.localstore.get_mints().await?.contains_key(&mint_url);
+        let keysets: Vec<KeySetInfo> = self
+            .localstore
+            .get_mint_keysets(mint_url.clone())
+            .await?
+            .unwrap_or_default();
+        let keysets_updated_at = keysets_updated_at(&self.localstore, &mint_url).await?;
+
+        let mut unverified = vec![OfflineCheck::ProofState];
+
+        // V4 tokens carry short keyset ids that can only be resolved against known keysets
+        let proofs = match token.proofs(&keysets) {
+            Ok(proofs) => proofs,
+            Err(err) => {
+                tracing::debug!("Could not resolve token keysets offline: {}", err);
+                unverified.push(OfflineCheck::Keysets);
+                return Ok(OfflineTokenReport {
+                    mint_url,
+                    mint_trusted,
+                    keysets_updated_at,
+                    duplicate_proofs: 0,
+                    proofs: Vec::new(),
+                    amounts: BTreeMap::new(),
+                    unverified,
+                });
+            }
+        };
+
+        let mut seen_secrets = HashSet::new();
+        let duplicate_proofs = proofs
+            .iter()
+            .filter(|proof| !seen_secrets.insert(proof.secret.clone()))
+            .count();
+
+        let keysets_by_id: HashMap<Id, &KeySetInfo> = keysets.iter().map(|k| (k.id, k)).collect();
+        let mut keys_cache: HashMap<Id, Option<Keys>> = HashMap::new();
+        let mut amounts: BTreeMap<CurrencyUnit, Amount> = BTreeMap::new();
+        let mut proof_reports = Vec::with_capacity(proofs.len());
+
+        for proof in proofs.iter() {
+            let cached_keyset = keysets_by_id.get(&proof.keyset_id).copied();
+            if let Some(keyset) = cached_keyset {
+                let amount = amounts.entry(keyset.unit.clone()).or_default();
+                *amount = amount
+                    .checked_add(proof.amount)
+                    .ok_or(Error::AmountOverflow)?;
+            }
+            let keyset = keyset_status(cached_keyset);
+
+            let keys = match keys_cache.get(&proof.keyset_id) {
+                Some(keys) => keys.clone(),
+                None => {
+                    let keys = match keyset {
+                        OfflineKeysetStatus::Unknown => None,
+                        _ => self.localstore.get_keys(&proof.keyset_id).await?,
+                    };
+                    keys_cache.insert(proof.keyset_id, keys.clone());
+                    keys
+                }
+            };
+
+            proof_reports.push(OfflineProofReport {
+                keyset_id: proof.keyset_id,
+                amount: proof.amount,
+                keyset,
+                dleq: dleq_status(proof, keys.as_ref()),
+            });
+        }
+
+        if proof_reports
+            .iter()
+            .any(|p| p.keyset == OfflineKeysetStatus::Unknown)
+        {
+            unverified.push(OfflineCheck::Keysets);
+        }
+        if proof_reports.iter().any(|p| {
+            matches!(
+                p.dleq,
+                OfflineDleqStatus::Missing | OfflineDleqStatus::KeyUnavailable
+            )
+        }) {
+            unverified.push(OfflineCheck::Signatures);
+        }
+
+        tracing::debug!(
+            "Offline verification of {} proofs ({}) from {}",
+            proofs.len(),
+            proofs.total_amount()?,
+            mint_url
+        );
+
+        Ok(OfflineTokenReport {
+            mint_url,
+            mint_trusted,
+            keysets_updated_at,
+            duplicate_proofs,
+            proofs: proof_reports,
+            amounts,
+            unverified,
+        })
+    }
+}
+
+fn keyset_status(keyset: Option<&KeySetInfo>) -> OfflineKeysetStatus {
+    match keyset {
+        Some(keyset) if keyset.active => OfflineKeysetStatus::Active,
+        Some(_) => OfflineKeysetStatus::Inactive,
+        None => OfflineKeysetStatus::Unknown,
+    }
+}
+
+fn dleq_status(proof: &Proof, keys: Option<&Keys>) -> OfflineDleqStatus {
+    match (&proof.dleq, keys.and_then(|k| k.amount_key(proof.amount))) {
+        (None, _) => OfflineDleqStatus::Missing,
+        (Some(_), None) => OfflineDleqStatus::KeyUnavailable,
+        (Some(_), Some(mint_pubkey)) => match proof.verify_dleq(mint_pubkey) {
+            Ok(()) => OfflineDleqStatus::Valid,
+            Err(_) => OfflineDleqStatus::Invalid,
+        },
+    }
+}
+
+/// Proofs of `token` grouped by the keyset id they carry, with their amounts
+///
+/// The proofs are empty when the keyset id can't be resolved against `keysets`.
+fn token_keysets(
+    token: &Token,
+    keysets: &[KeySetInfo],
+) -> Vec<(ShortKeysetId, Vec<Amount>, Vec<Proof>)> {
+    let mut groups: Vec<(ShortKeysetId, Vec<Amount>, Vec<Proof>)> = Vec::new();
+
+    let mut push = |short_id: &ShortKeysetId, amount: Amount, proof: Option<Proof>| {
+        let position = match groups.iter().position(|(id, _, _)| id == short_id) {
+            Some(position) => position,
+            None => {
+                groups.push((short_id.clone(), Vec::new(), Vec::new()));
+                groups.len() - 1
+            }
+        };
+        let (_, amounts, proofs) = &mut groups[position];
+        amounts.push(amount);
+        proofs.extend(proof);
+    };
+
+    match token {
+        Token::TokenV3(token) => {
+            for proof in token.token.iter().flat_map(|t| t.proofs.iter()) {
+                let id = Id::from_short_keyset_id(&proof.keyset_id, keysets).ok();
+                push(
+                    &proof.keyset_id,
+                    proof.amount,
+                    id.map(|id| proof.into_proof(&id)),
+                );
+            }
+        }
+        Token::TokenV4(token) => {
+            for t in &token.token {
+                let id = Id::from_short_keyset_id(&t.keyset_id, keysets).ok();
+                for proof in &t.proofs {
+                    push(
+                        &t.keyset_id,
+                        proof.amount,
+                        id.map(|id| proof.into_proof(&id)),
+                    );
+                }
+            }
+        }
+    }
+
+    groups
+}
@@ -4,7 +4,9 @@
 //! Nostr or HTTP transports when available. If no transport is present in the request, an error
 //! is returned so callers can handle alternative delivery mechanisms explicitly.
 
+use std::collections::HashMap;
 use std::str::FromStr;
+use std::sync::Arc;
 
 use anyhow::Result;
 use bitcoin::hashes::sha256::Hash as Sha256Hash;
@@ -16,15 +18,15 @@ use nostr_sdk::prelude::*;
 #[cfg(feature = "nostr")]
 use nostr_sdk::{Client as NostrClient, EventBuilder, FromBech32, Keys, ToBech32};
 use reqwest::Client;
+use tokio::sync::RwLock;
 
 use crate::error::Error;
 use crate::mint_url::MintUrl;
+use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::nut11::{Conditions, SigFlag, SpendingConditions};
 use crate::nuts::nut18::Nut10SecretRequest;
-use crate::nuts::{CurrencyUnit, Nut10Secret, Transport};
-#[cfg(feature = "nostr")]
-use crate::wallet::MultiMintReceiveOptions;
-use crate::wallet::{MultiMintWallet, SendOptions};
+use crate::nuts::{CurrencyUnit, Nut10Secret, Token, Transport};
+use crate::wallet::{MultiMintReceiveOptions, MultiMintWallet, SendOptions};
 use crate::Wallet;
 
 impl Wallet {
@@ -282,6 +284,15 @@ impl MultiMintWallet {
             },
         };
 
+        if let Some(unit) = &payment_request.unit {
+            if unit != self.unit() {
+                return Err(Error::MultiMintCurrencyUnitMismatch {
+                    expected: self.unit().clone(),
+                    found: unit.clone(),
+                });
+            }
+        }
+
         // Get the list of mints accepted by the payment request (None means any mint is accepted)
         let accepted_mints = payment_request.mints.as_ref();
 
@@ -338,6 +349,19 @@ impl MultiMintWallet {
             .await
     }
 
+    /// Pay an encoded NUT-18 payment request (`creqA...`)
+    ///
+    /// Decodes the request and pays it from the accepted mint with the highest
+    /// balance, see [`Self::pay_request`].
+    pub async fn pay_payment_request(
+        &self,
+        encoded_request: &str,
+        custom_amount: Option<Amount>,
+    ) -> Result<(), Error> {
+        let payment_request = PaymentRequest::from_str(encoded_request)?;
+        self.pay_request(payment_request, None, custom_amount).await
+    }
+
     /// Derive enforceable NUT-10 spending conditions from high-level request params.
     ///
     /// Why:
@@ -740,3 +764,268 @@ impl MultiMintWallet {
         Ok(Amount::ZERO)
     }
 }
+
+/// Inbox for payments made against payment requests created by a [`MultiMintWallet`]
+///
+/// Requests created through the inbox are remembered by their payment id. Incoming
+/// [`PaymentRequestPayload`]s, e.g. the body of an HTTP POST transport, are checked
+/// against the matching request and their proofs received into the wallet.
+#[derive(Debug, Clone)]
+pub struct PaymentRequestInbox {
+    wallet: MultiMintWallet,
+    requests: Arc<RwLock<HashMap<String, PaymentRequest>>>,
+}
+
+impl PaymentRequestInbox {
+    /// Create a new [`PaymentRequestInbox`] receiving into `wallet`
+    pub fn new(wallet: MultiMintWallet) -> Self {
+        Self {
+            wallet,
+            requests: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Create an encoded single use payment request (`creqA...`) tracked by this inbox
+    ///
+    /// When `accepted_mints` is empty the mints known to the wallet are advertised.
+    pub async fn create_payment_request(
+        &self,
+        amount: Option<Amount>,
+        unit: CurrencyUnit,
+        accepted_mints: Vec<MintUrl>,
+        transports: Vec<Transport>,
+    ) -> Result<String, Error> {
+        if &unit != self.wallet.unit() {
+            return Err(Error::MultiMintCurrencyUnitMismatch {
+                expected: self.wallet.unit().clone(),
+                found: unit,
+            });
+        }
+
+        let mints = if accepted_mints.is_empty() {
+            self.wallet.get_balances().await?.into_keys().collect()
+        } else {
+            accepted_mints
+        };
+
+        let payment_id = uuid::Uuid::new_v4().simple().to_string();
+        let payment_request = PaymentRequest {
+            payment_id: Some(payment_id.clone()),
+            amount,
+            unit: Some(unit),
+            single_use: Some(true),
+            mints: Some(mints),
+            description: None,
+            transports,
+            nut10: None,
+        };
+
+        let encoded = payment_request.to_string();
+        self.requests
+            .write()
+            .await
+            .insert(payment_id, payment_request);
+
+        Ok(encoded)
+    }
+
+    /// Accept an incoming payment and receive its proofs into the wallet
+    ///
+    /// The payload must reference a request created by this inbox, be in its unit,
+    /// come from one of its accepted mints and carry at least the requested amount.
+    pub async fn accept(&self, payload: PaymentRequestPayload) -> Result<Amount, Error> {
+        let payment_id = payload
+            .id
+            .clone()
+            .ok_or_else(|| Error::Custom("Payment does not reference a request".to_string()))?;
+
+        let payment_request = {
+            let mut requests = self.requests.write().await;
+            let payment_request = requests
+                .get(&payment_id)
+                .cloned()
+                .ok_or_else(|| Error::Custom(format!("Unknown payment request {payment_id}")))?;
+
+            // Single use requests are taken out so a concurrent payment cannot reuse them
+            if payment_request.single_use == Some(true) {
+                requests.remove(&payment_id);
+            }
+
+            payment_request
+        };
+
+        let result = self.receive_payload(&payment_request, payload).await;
+
+        if result.is_err() && payment_request.single_use == Some(true) {
+            self.requests
+                .write()
+                .await
+                .insert(payment_id, payment_request);
+        }
+
+        result
+    }
+
+    async fn receive_payload(
+        &self,
+        payment_request: &PaymentRequest,
+        payload: PaymentRequestPayload,
+    ) -> Result<Amount, Error> {
+        if let Some(unit) = &payment_request.unit {
+            if unit != &payload.unit {
+                return Err(Error::MultiMintCurrencyUnitMismatch {
+                    expected: unit.clone(),
+                    found: payload.unit,
+                });
+            }
+        }
+
+        if let Some(mints) = &payment_request.mints {
+            if !mints.contains(&payload.mint) {
+                return Err(Error::Custom(format!(
+                    "Mint {} is not accepted by this payment request",
+                    payload.mint
+                )));
+            }
+        }
+
+        let paid = payload.proofs.total_amount()?;
+        if let Some(amount) = payment_request.amount {
+            if paid < amount {
+                return Err(Error::Custom(format!(
+                    "Payment of {paid} is less than the requested {amount}"
+                )));
+            }
+        }
+
+        let token = Token::new(payload.mint, payload.proofs, payload.memo, payload.unit);
+
+        // The request explicitly accepted this mint, so it may be added if unknown
+        self.wallet
+            .receive(
+                &token.to_string(),
+                MultiMintReceiveOptions::default().allow_untrusted(true),
+            )
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cdk_common::database::WalletDatabase;
+
+    use super::*;
+    use crate::nuts::TransportType;
+
+    async fn create_test_inbox() -> PaymentRequestInbox {
+        let localstore: Arc<dyn WalletDatabase<cdk_common::database::Error> + Send + Sync> =
+            Arc::new(
+                cdk_sqlite::wallet::memory::empty()
+                    .await
+                    .expect("Failed to create in-memory database"),
+            );
+        let wallet = MultiMintWallet::new(localstore, [0u8; 64], CurrencyUnit::Sat)
+            .await
+            .expect("Failed to create MultiMintWallet");
+        PaymentRequestInbox::new(wallet)
+    }
+
+    fn http_transport() -> Transport {
+        Transport {
+            _type: TransportType::HttpPost,
+            target: "https://receiver.example.com/pay".to_string(),
+            tags: None,
+        }
+    }
+
+    fn payload(id: Option<String>, mint: &str) -> PaymentRequestPayload {
+        PaymentRequestPayload {
+            id,
+            memo: None,
+            mint: MintUrl::from_str(mint).unwrap(),
+            unit: CurrencyUnit::Sat,
+            proofs: vec![],
+        }
+    }
+
+    #[tokio::test]
+    async fn test_create_payment_request_roundtrip() {
+        let inbox = create_test_inbox().await;
+        let mint_url = MintUrl::from_str("https://mint.example.com").unwrap();
+
+        let encoded = inbox
+            .create_payment_request(
+                Some(Amount::from(21)),
+                CurrencyUnit::Sat,
+                vec![mint_url.clone()],
+                vec![http_transport()],
+            )
+            .await
+            .unwrap();
+
+        assert!(encoded.starts_with("creqA"));
+
+        let decoded = PaymentRequest::from_str(&encoded).unwrap();
+        assert!(decoded.payment_id.is_some());
+        assert_eq!(decoded.amount, Some(Amount::from(21)));
+        assert_eq!(decoded.unit, Some(CurrencyUnit::Sat));
+        assert_eq!(decoded.single_use, Some(true));
+        assert_eq!(decoded.mints, Some(vec![mint_url]));
+        assert_eq!(decoded.transports, vec![http_transport()]);
+    }
+
+    #[tokio::test]
+    async fn test_create_payment_request_unit_mismatch() {
+        let inbox = create_test_inbox().await;
+
+        let result = inbox
+            .create_payment_request(None, CurrencyUnit::Usd, vec![], vec![])
+            .await;
+
+        assert!(matches!(
+            result,
+            Err(Error::MultiMintCurrencyUnitMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_inbox_rejects_unknown_and_unaccepted_payments() {
+        let inbox = create_test_inbox().await;
+
+        let encoded = inbox
+            .create_payment_request(
+                None,
+                CurrencyUnit::Sat,
+                vec![MintUrl::from_str("https://mint.example.com").unwrap()],
+                vec![http_transport()],
+            )
+            .await
+            .unwrap();
+        let payment_id = PaymentRequest::from_str(&encoded)
+            .unwrap()
+            .payment_id
+            .unwrap();
+
+        assert!(inbox
+            .accept(payload(None, "https://mint.example.com"))
+            .await
+            .is_err());
+        assert!(inbox
+            .accept(payload(
+                Some("unknown".to_string()),
+                "https://mint.example.com"
+            ))
+            .await
+            .is_err());
+        assert!(inbox
+            .accept(payload(
+                Some(payment_id.clone()),
+                "https://other-mint.example.com"
+            ))
+            .await
+            .is_err());
+
+        // A rejected payment leaves the single use request open
+        assert!(inbox.requests.read().await.contains_key(&payment_id));
+    }
+}
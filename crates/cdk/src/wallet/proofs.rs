@@ -1,7 +1,9 @@
+use std::cmp::Reverse;
 use std::collections::{HashMap, HashSet};
 
 use cdk_common::amount::KeysetFeeAndAmounts;
-use cdk_common::wallet::TransactionId;
+use cdk_common::nut02::KeySetInfosMethods;
+use cdk_common::wallet::{SelectionStrategy, TransactionId};
 use cdk_common::Id;
 use tracing::instrument;
 
@@ -105,15 +107,29 @@ impl Wallet {
     }
 
     /// NUT-07 Check the state of a [`Proof`] with the mint
+    ///
+    /// Proofs are checked in batches of at most the check state limit of the mint.
     #[instrument(skip(self, proofs))]
     pub async fn check_proofs_spent(&self, proofs: Proofs) -> Result<Vec<ProofState>, Error> {
-        let spendable = self
-            .client
-            .post_check_state(CheckStateRequest { ys: proofs.ys()? })
-            .await?;
+        let ys = proofs.ys()?;
+        let max_ys = self
+            .load_mint_info()
+            .await?
+            .nuts
+            .request_limits
+            .unwrap_or_default()
+            .max_check_state_ys;
+
+        let mut states = Vec::with_capacity(ys.len());
+        for batch in ys.chunks(max_ys.max(1)) {
+            let spendable = self
+                .client
+                .post_check_state(CheckStateRequest { ys: batch.to_vec() })
+                .await?;
+            states.extend(spendable.states);
+        }
 
-        let spent_ys: Vec<_> = spendable
-            .states
+        let spent_ys: Vec<_> = states
             .iter()
             .filter_map(|p| match p.state {
                 State::Spent => Some(p.y),
@@ -123,13 +139,29 @@ impl Wallet {
 
         self.localstore.update_proofs(vec![], spent_ys).await?;
 
-        Ok(spendable.states)
+        Ok(states)
     }
 
-    /// Checks pending proofs for spent status
+    /// Checks pending proofs for spent status and reclaims the unspent ones
+    ///
+    /// A melt that failed mid-flight, or an app killed during a send or melt, can leave proofs
+    /// pending or reserved in the local store. Active melt quotes are checked with the mint
+    /// first, so a melt that went through is recorded in the history. The mint is then asked
+    /// (NUT-07) for the state of every pending, reserved or pending spent proof: spent proofs
+    /// are removed, unspent pending or reserved proofs are returned to the spendable balance and
+    /// proofs still pending at the mint are left untouched.
+    ///
+    /// This must not run while a [`PreparedSend`](crate::wallet::PreparedSend) is outstanding,
+    /// as its reserved proofs would be released.
+    ///
+    /// Returns the amount returned to the spendable balance.
     #[instrument(skip(self))]
     pub async fn check_all_pending_proofs(&self) -> Result<Amount, Error> {
-        let mut balance = Amount::ZERO;
+        for quote in self.get_active_melt_quotes().await? {
+            if let Err(err) = self.melt_quote_status(&quote.id).await {
+                tracing::warn!("Could not check melt quote {}: {}", quote.id, err);
+            }
+        }
 
         let proofs = self
             .localstore
@@ -145,36 +177,32 @@ impl Wallet {
             return Ok(Amount::ZERO);
         }
 
+        // Spent proofs are removed by the check
         let states = self
-            .check_proofs_spent(proofs.clone().into_iter().map(|p| p.proof).collect())
+            .check_proofs_spent(proofs.iter().map(|p| p.proof.clone()).collect())
             .await?;
 
-        // Both `State::Pending` and `State::Unspent` should be included in the pending
-        // table. This is because a proof that has been created to send will be
-        // stored in the pending table in order to avoid accidentally double
-        // spending but to allow it to be explicitly reclaimed
-        let pending_states: HashSet<PublicKey> = states
+        let unspent_states: HashSet<PublicKey> = states
             .into_iter()
-            .filter(|s| s.state.ne(&State::Spent))
+            .filter(|s| s.state == State::Unspent)
             .map(|s| s.y)
             .collect();
 
-        let (pending_proofs, non_pending_proofs): (Vec<ProofInfo>, Vec<ProofInfo>) = proofs
+        // Proofs sent in a token stay pending spent until the receiver claims them
+        let reclaimed: Vec<ProofInfo> = proofs
             .into_iter()
-            .partition(|p| pending_states.contains(&p.y));
+            .filter(|p| {
+                matches!(p.state, State::Pending | State::Reserved) && unspent_states.contains(&p.y)
+            })
+            .collect();
 
-        let amount = Amount::try_sum(pending_proofs.iter().map(|p| p.proof.amount))?;
+        let amount = Amount::try_sum(reclaimed.iter().map(|p| p.proof.amount))?;
 
         self.localstore
-            .update_proofs(
-                vec![],
-                non_pending_proofs.into_iter().map(|p| p.y).collect(),
-            )
+            .update_proofs_state(reclaimed.into_iter().map(|p| p.y).collect(), State::Unspent)
             .await?;
 
-        balance += amount;
-
-        Ok(balance)
+        Ok(amount)
     }
 
     /// Select exact proofs
@@ -241,6 +269,128 @@ impl Wallet {
         Ok((input_proofs, exchange))
     }
 
+    /// Fee to spend the proofs that would be selected for `amount`
+    ///
+    /// Runs the same selection as a send or melt with the given [`SelectionStrategy`]
+    /// without reserving any proofs, so the cost can be shown before committing.
+    #[instrument(skip(self))]
+    pub async fn selection_fee(
+        &self,
+        amount: Amount,
+        strategy: SelectionStrategy,
+    ) -> Result<Amount, Error> {
+        let active_keyset_ids = self
+            .get_mint_keysets()
+            .await?
+            .active()
+            .map(|k| k.id)
+            .collect();
+        let keyset_fees = self.get_keyset_fees_and_amounts().await?;
+
+        let selected_proofs = Self::select_proofs_with_strategy(
+            amount,
+            self.get_unspent_proofs().await?,
+            &active_keyset_ids,
+            &keyset_fees,
+            true,
+            strategy,
+        )?;
+
+        Ok(self.get_proofs_fee(&selected_proofs).await?.total)
+    }
+
+    /// Select proofs with a [`SelectionStrategy`]
+    ///
+    /// [`SelectionStrategy::ExactMatchPreferred`] is the selection done by [`Wallet::select_proofs`].
+    /// The other strategies walk the proofs in the strategy's order until the amount (and the
+    /// input fee when `include_fees` is set) is covered.
+    #[instrument(skip_all)]
+    pub fn select_proofs_with_strategy(
+        amount: Amount,
+        proofs: Proofs,
+        active_keyset_ids: &Vec<Id>,
+        fees_and_keyset_amounts: &KeysetFeeAndAmounts,
+        include_fees: bool,
+        strategy: SelectionStrategy,
+    ) -> Result<Proofs, Error> {
+        if amount == Amount::ZERO {
+            return Ok(vec![]);
+        }
+        ensure_cdk!(proofs.total_amount()? >= amount, Error::InsufficientFunds);
+
+        let keyset_fees: HashMap<Id, u64> = fees_and_keyset_amounts
+            .iter()
+            .map(|(key, values)| (*key, values.fee()))
+            .collect();
+
+        let mut proofs = proofs;
+        match strategy {
+            SelectionStrategy::ExactMatchPreferred => {
+                return Self::select_proofs(
+                    amount,
+                    proofs,
+                    active_keyset_ids,
+                    fees_and_keyset_amounts,
+                    include_fees,
+                );
+            }
+            SelectionStrategy::SmallestFirst => proofs.sort_by(|a, b| a.amount.cmp(&b.amount)),
+            SelectionStrategy::LargestFirst => proofs.sort_by(|a, b| b.amount.cmp(&a.amount)),
+            SelectionStrategy::MinimizeFee => {
+                // Value left of a proof after its input fee, in parts per thousand
+                proofs.sort_by_key(|p| {
+                    let fee_ppk = keyset_fees.get(&p.keyset_id).copied().unwrap_or_default();
+                    Reverse(
+                        u64::from(p.amount)
+                            .saturating_mul(1000)
+                            .saturating_sub(fee_ppk),
+                    )
+                })
+            }
+        }
+
+        let mut selected_proofs = Vec::new();
+        for proof in proofs {
+            if Self::covers_amount(amount, &selected_proofs, &keyset_fees, include_fees)? {
+                break;
+            }
+            selected_proofs.push(proof);
+        }
+        ensure_cdk!(
+            Self::covers_amount(amount, &selected_proofs, &keyset_fees, include_fees)?,
+            Error::InsufficientFunds
+        );
+
+        if strategy != SelectionStrategy::SmallestFirst {
+            // Drop proofs that are not needed to cover the amount, starting from the least valuable
+            for i in (0..selected_proofs.len()).rev() {
+                let mut without = selected_proofs.clone();
+                without.remove(i);
+                if Self::covers_amount(amount, &without, &keyset_fees, include_fees)? {
+                    selected_proofs = without;
+                }
+            }
+        }
+
+        Ok(selected_proofs)
+    }
+
+    /// Check if the proofs cover the amount, plus their input fee if `include_fees` is set
+    fn covers_amount(
+        amount: Amount,
+        proofs: &Proofs,
+        keyset_fees: &HashMap<Id, u64>,
+        include_fees: bool,
+    ) -> Result<bool, Error> {
+        let fee = if include_fees {
+            calculate_fee(&proofs.count_by_keyset(), keyset_fees)?.total
+        } else {
+            Amount::ZERO
+        };
+
+        Ok(proofs.total_amount()? >= amount.checked_add(fee).ok_or(Error::AmountOverflow)?)
+    }
+
     /// Select proofs
     #[instrument(skip_all)]
     pub fn select_proofs(
@@ -538,6 +688,7 @@ mod tests {
     use std::collections::HashMap;
 
     use cdk_common::secret::Secret;
+    use cdk_common::wallet::SelectionStrategy;
     use cdk_common::{Amount, Id, Proof, PublicKey};
 
     use crate::Wallet;
@@ -2432,4 +2583,163 @@ mod tests {
             }
         }
     }
+
+    // ========================================================================
+    // Selection Strategy Tests
+    // ========================================================================
+
+    const STRATEGIES: [SelectionStrategy; 4] = [
+        SelectionStrategy::SmallestFirst,
+        SelectionStrategy::LargestFirst,
+        SelectionStrategy::ExactMatchPreferred,
+        SelectionStrategy::MinimizeFee,
+    ];
+
+    fn select_with_strategy(
+        amount: u64,
+        proofs: Vec<Proof>,
+        fee_ppk: u64,
+        strategy: SelectionStrategy,
+    ) -> Result<Vec<Proof>, crate::Error> {
+        Wallet::select_proofs_with_strategy(
+            amount.into(),
+            proofs,
+            &vec![id()],
+            &keyset_fee_and_amounts_with_fee(fee_ppk),
+            true,
+            strategy,
+        )
+    }
+
+    #[test]
+    fn test_select_proofs_smallest_first() {
+        let proofs = vec![proof(64), proof(1), proof(2), proof(1), proof(4)];
+
+        let selected =
+            select_with_strategy(7, proofs, 0, SelectionStrategy::SmallestFirst).unwrap();
+        let mut amounts: Vec<u64> = selected.iter().map(|p| p.amount.into()).collect();
+        amounts.sort();
+
+        assert_eq!(amounts, vec![1, 1, 2, 4]);
+    }
+
+    #[test]
+    fn test_select_proofs_largest_first() {
+        let proofs = vec![proof(1), proof(2), proof(4), proof(8), proof(64)];
+
+        let selected = select_with_strategy(7, proofs, 0, SelectionStrategy::LargestFirst).unwrap();
+        let amounts: Vec<u64> = selected.iter().map(|p| p.amount.into()).collect();
+
+        assert_eq!(amounts, vec![64]);
+    }
+
+    #[test]
+    fn test_select_proofs_minimize_fee() {
+        use crate::nuts::nut00::ProofsMethods;
+
+        // 1 sat per proof, spending the 16 is cheaper than the four small proofs
+        let proofs = vec![proof(1), proof(2), proof(4), proof(4), proof(16)];
+
+        let selected =
+            select_with_strategy(9, proofs, 1000, SelectionStrategy::MinimizeFee).unwrap();
+
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected.total_amount().unwrap(), Amount::from(16));
+    }
+
+    #[test]
+    fn test_select_proofs_exact_match_preferred_is_default() {
+        assert_eq!(
+            SelectionStrategy::default(),
+            SelectionStrategy::ExactMatchPreferred
+        );
+
+        let proofs = standard_proofs();
+        let mut selected: Vec<u64> = select_with_strategy(
+            300,
+            proofs.clone(),
+            200,
+            SelectionStrategy::ExactMatchPreferred,
+        )
+        .unwrap()
+        .iter()
+        .map(|p| p.amount.into())
+        .collect();
+        let mut expected: Vec<u64> = Wallet::select_proofs(
+            300.into(),
+            proofs,
+            &vec![id()],
+            &keyset_fee_and_amounts_with_fee(200),
+            true,
+        )
+        .unwrap()
+        .iter()
+        .map(|p| p.amount.into())
+        .collect();
+        selected.sort();
+        expected.sort();
+
+        assert_eq!(selected, expected);
+    }
+
+    #[test]
+    fn test_select_proofs_strategies_insufficient() {
+        for strategy in STRATEGIES {
+            let result = select_with_strategy(8, vec![proof(4), proof(4)], 1000, strategy);
+            assert!(result.is_err(), "{strategy:?} should not cover fees");
+        }
+    }
+
+    /// Every strategy covers the amount plus the input fee and never selects a proof twice
+    #[test]
+    fn test_select_proofs_strategies_properties() {
+        use std::collections::HashSet;
+
+        use rand::rngs::StdRng;
+        use rand::{Rng, SeedableRng};
+
+        use crate::fees::calculate_fee;
+        use crate::nuts::nut00::ProofsMethods;
+
+        // Seeded so a failing case can be reproduced
+        let mut rng = StdRng::seed_from_u64(0x5e1ec7);
+
+        for _ in 0..200 {
+            let fee_ppk = [0, 100, 200, 1000][rng.random_range(0..4)];
+            let proofs: Vec<Proof> = (0..rng.random_range(1..40))
+                .map(|_| proof(2u64.pow(rng.random_range(0..11))))
+                .collect();
+            let total: u64 = proofs.total_amount().unwrap().into();
+            let amount = rng.random_range(1..=total);
+            let keyset_fees = HashMap::from([(id(), fee_ppk)]);
+
+            for strategy in STRATEGIES {
+                let selected = match select_with_strategy(amount, proofs.clone(), fee_ppk, strategy)
+                {
+                    Ok(selected) => selected,
+                    Err(_) => {
+                        // Without fees the amount can always be covered
+                        assert_ne!(fee_ppk, 0, "{strategy:?} failed to select {amount}");
+                        continue;
+                    }
+                };
+
+                let fee = calculate_fee(&selected.count_by_keyset(), &keyset_fees)
+                    .unwrap()
+                    .total;
+                assert!(
+                    selected.total_amount().unwrap() >= Amount::from(amount) + fee,
+                    "{strategy:?} selected less than {amount} + {fee}"
+                );
+
+                let secrets: HashSet<_> = selected.iter().map(|p| p.secret.clone()).collect();
+                assert_eq!(
+                    secrets.len(),
+                    selected.len(),
+                    "{strategy:?} selected a proof twice"
+                );
+                assert!(selected.iter().all(|p| proofs.contains(p)));
+            }
+        }
+    }
 }
@@ -1,24 +1,51 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
 
 use bitcoin::hashes::sha256::Hash as Sha256Hash;
 use bitcoin::hashes::Hash;
 use bitcoin::XOnlyPublicKey;
 use cdk_common::util::unix_time;
-use cdk_common::wallet::{Transaction, TransactionDirection};
+use cdk_common::wallet::{Transaction, TransactionDirection, TransactionKind};
 use tracing::instrument;
 
+use super::keysets::mint_unreachable;
 use crate::amount::SplitTarget;
 use crate::dhke::construct_proofs;
 use crate::nuts::nut00::ProofsMethods;
 use crate::nuts::nut10::Kind;
-use crate::nuts::{Conditions, Proofs, PublicKey, SecretKey, SigFlag, State, Token};
+use crate::nuts::{
+    CheckStateRequest, Conditions, Proof, Proofs, PublicKey, SecretKey, SigFlag, State, Token,
+};
 use crate::types::ProofInfo;
 use crate::util::hex;
+use crate::wallet::JournalOperation;
 use crate::{ensure_cdk, Amount, Error, Wallet, SECP256K1};
 
+/// Result of [`Wallet::receive_with_result`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ReceiveResult {
+    /// Value claimed into the wallet, after fees
+    pub amount_claimed: Amount,
+    /// Input fees paid for the swap
+    pub fee_paid: Amount,
+    /// Value of the proofs already spent, or pending, at the mint
+    pub amount_already_spent: Amount,
+    /// Proofs that could not be claimed, with their state at the mint
+    pub failed_proofs: Vec<(Proof, State)>,
+    /// Whether every claimed proof carried a DLEQ proof (NUT-12), all of them verified
+    ///
+    /// DLEQ proofs are verified whenever present, receiving fails if one does not verify.
+    pub dleq_verified: bool,
+    /// Value of the token stored to be claimed later because the mint could not be reached
+    ///
+    /// See [`ReceiveOptions::defer_if_unreachable`].
+    pub amount_deferred: Amount,
+}
+
 impl Wallet {
     /// Receive proofs
+    ///
+    /// Proofs already spent at the mint are skipped, see [`Wallet::receive_proofs_with_result`].
     #[instrument(skip_all)]
     pub async fn receive_proofs(
         &self,
@@ -26,10 +53,66 @@ impl Wallet {
         opts: ReceiveOptions,
         memo: Option<String>,
     ) -> Result<Amount, Error> {
+        Ok(self
+            .receive_proofs_with_result(proofs, opts, memo)
+            .await?
+            .amount_claimed)
+    }
+
+    /// Receive proofs, claiming the ones still unspent at the mint
+    ///
+    /// The states of the proofs are checked first (NUT-07) and only the unspent ones are swapped,
+    /// the others are listed in the result. Fails with [`Error::TokenAlreadySpent`] (or
+    /// [`Error::TokenPending`]) if none of the proofs can be claimed, or if any of them cannot be
+    /// and [`ReceiveOptions::all_or_nothing`] is set.
+    #[instrument(skip_all)]
+    pub async fn receive_proofs_with_result(
+        &self,
+        proofs: Proofs,
+        opts: ReceiveOptions,
+        memo: Option<String>,
+    ) -> Result<ReceiveResult, Error> {
         // Incase the wallet is getting ecash for the first time
         // we want to get the mint info for our db
         let _mint_info = self.load_mint_info().await?;
 
+        let states = self
+            .client
+            .post_check_state(CheckStateRequest { ys: proofs.ys()? })
+            .await?
+            .states;
+        ensure_cdk!(states.len() == proofs.len(), Error::UnexpectedProofState);
+
+        let (proofs, failed_proofs): (Vec<_>, Vec<_>) = proofs
+            .into_iter()
+            .zip(states)
+            .map(|(proof, state)| (proof, state.state))
+            .partition(|(_, state)| *state == State::Unspent);
+        let mut proofs: Proofs = proofs.into_iter().map(|(proof, _)| proof).collect();
+
+        if !failed_proofs.is_empty() {
+            if proofs.is_empty() || opts.all_or_nothing {
+                return Err(
+                    if failed_proofs
+                        .iter()
+                        .any(|(_, state)| *state == State::Spent)
+                    {
+                        Error::TokenAlreadySpent
+                    } else {
+                        Error::TokenPending
+                    },
+                );
+            }
+
+            tracing::warn!(
+                "Skipping {} proofs already spent or pending at the mint",
+                failed_proofs.len()
+            );
+        }
+
+        let amount_already_spent = Amount::try_sum(failed_proofs.iter().map(|(p, _)| p.amount))?;
+        let dleq_verified = proofs.iter().all(|proof| proof.dleq.is_some());
+
         let mint_url = &self.mint_url;
 
         let active_keyset_id = self.fetch_active_keyset().await?.id;
@@ -39,8 +122,6 @@ impl Wallet {
 
         let keys = self.load_keyset_keys(active_keyset_id).await?;
 
-        let mut proofs = proofs;
-
         let proofs_amount = proofs.total_amount()?;
         let proofs_ys = proofs.ys()?;
 
@@ -62,6 +143,9 @@ impl Wallet {
             .map(|s| (s.x_only_public_key(&SECP256K1).0, s))
             .collect();
 
+        // Keys of the SIG_ALL conditions, signing the whole swap request
+        let mut sig_all_signing_keys: HashMap<XOnlyPublicKey, &SecretKey> = HashMap::new();
+
         for proof in &mut proofs {
             // Verify that proof DLEQ is valid
             if proof.dleq.is_some() {
@@ -98,15 +182,35 @@ impl Wallet {
                             proof.add_preimage(preimage.to_string());
                         }
                     }
-                    for pubkey in pubkeys {
-                        if let Some(signing) = p2pk_signing_keys.get(&pubkey.x_only_public_key()) {
-                            proof.sign_p2pk(signing.to_owned().clone())?;
-                        }
+                    // Once the locktime has passed the refund keys can claim the proof
+                    if conditions
+                        .locktime
+                        .is_some_and(|locktime| locktime < unix_time())
+                    {
+                        pubkeys.extend(conditions.refund_keys.unwrap_or_default());
                     }
 
-                    if conditions.sig_flag.eq(&SigFlag::SigAll) {
+                    // SIG_ALL proofs are signed together once the swap request is built
+                    let sig_all = conditions.sig_flag.eq(&SigFlag::SigAll);
+                    if sig_all {
                         sig_flag = SigFlag::SigAll;
                     }
+
+                    let mut signed_with = HashSet::new();
+                    for pubkey in pubkeys {
+                        let x_only = pubkey.x_only_public_key();
+                        if let Some(signing) = p2pk_signing_keys.get(&x_only) {
+                            if !signed_with.insert(x_only) {
+                                continue;
+                            }
+
+                            if sig_all {
+                                sig_all_signing_keys.insert(x_only, *signing);
+                            } else {
+                                proof.sign_p2pk(signing.to_owned().clone())?;
+                            }
+                        }
+                    }
                 }
             }
         }
@@ -138,13 +242,26 @@ impl Wallet {
             .await?;
 
         if sig_flag.eq(&SigFlag::SigAll) {
-            for blinded_message in pre_swap.swap_request.outputs_mut() {
-                for signing_key in p2pk_signing_keys.values() {
-                    blinded_message.sign_p2pk(signing_key.to_owned().clone())?
-                }
+            for signing_key in sig_all_signing_keys.values() {
+                pre_swap
+                    .swap_request
+                    .sign_sig_all(signing_key.to_owned().clone())?;
             }
         }
 
+        let counters = self
+            .journal_counters(&active_keyset_id, pre_swap.derived_secret_count)
+            .await?;
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Swap,
+                None,
+                counters,
+                &pre_swap.pre_mint_secrets,
+                proofs_ys.clone(),
+            )
+            .await?;
+
         let swap_response = match self.client.post_swap(pre_swap.swap_request).await {
             Ok(response) => response,
             Err(err) => {
@@ -178,33 +295,44 @@ impl Wallet {
             .map(|proof| ProofInfo::new(proof, mint_url.clone(), State::Unspent, self.unit.clone()))
             .collect::<Result<Vec<ProofInfo>, _>>()?;
 
+        // Store received proofs together with the transaction
         self.localstore
-            .update_proofs(
+            .update_proofs_with_transaction(
                 recv_proof_infos,
                 proofs_info.into_iter().map(|p| p.y).collect(),
+                Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Incoming,
+                    amount: total_amount,
+                    fee: proofs_amount - total_amount,
+                    unit: self.unit.clone(),
+                    ys: proofs_ys,
+                    timestamp: unix_time(),
+                    memo,
+                    metadata: opts.metadata,
+                    quote_id: None,
+                    payment_request: None,
+                    payment_proof: None,
+                    payment_method: None,
+                    kind: Some(TransactionKind::Receive),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
             )
             .await?;
 
-        // Add transaction to store
-        self.localstore
-            .add_transaction(Transaction {
-                mint_url: self.mint_url.clone(),
-                direction: TransactionDirection::Incoming,
-                amount: total_amount,
-                fee: proofs_amount - total_amount,
-                unit: self.unit.clone(),
-                ys: proofs_ys,
-                timestamp: unix_time(),
-                memo,
-                metadata: opts.metadata,
-                quote_id: None,
-                payment_request: None,
-                payment_proof: None,
-                payment_method: None,
-            })
-            .await?;
+        self.journal_finish(&journal_id).await?;
+
+        self.auto_migrate_inactive_proofs().await;
 
-        Ok(total_amount)
+        Ok(ReceiveResult {
+            amount_claimed: total_amount,
+            fee_paid: proofs_amount - total_amount,
+            amount_already_spent,
+            failed_proofs,
+            dleq_verified,
+            amount_deferred: Amount::ZERO,
+        })
     }
 
     /// Receive
@@ -237,25 +365,49 @@ impl Wallet {
         encoded_token: &str,
         opts: ReceiveOptions,
     ) -> Result<Amount, Error> {
+        Ok(self
+            .receive_with_result(encoded_token, opts)
+            .await?
+            .amount_claimed)
+    }
+
+    /// Receive a token, claiming the proofs still unspent at the mint
+    ///
+    /// The keyset ids of the token are resolved against every keyset cached for the mint, so it
+    /// can be checked while the mint is unreachable, see
+    /// [`ReceiveOptions::defer_if_unreachable`]. See [`Wallet::receive_proofs_with_result`].
+    #[instrument(skip_all)]
+    pub async fn receive_with_result(
+        &self,
+        encoded_token: &str,
+        opts: ReceiveOptions,
+    ) -> Result<ReceiveResult, Error> {
         let token = Token::from_str(encoded_token)?;
         let unit = token.unit().unwrap_or_default();
 
         ensure_cdk!(unit == self.unit, Error::UnsupportedUnit);
 
-        let keysets_info = self.load_mint_keysets().await?;
-        let proofs = token.proofs(&keysets_info)?;
-
         if let Token::TokenV3(token) = &token {
             ensure_cdk!(!token.is_multi_mint(), Error::MultiMintTokenNotSupported);
         }
 
         ensure_cdk!(self.mint_url == token.mint_url()?, Error::IncorrectMint);
 
-        let amount = self
-            .receive_proofs(proofs, opts, token.memo().clone())
-            .await?;
+        let defer_opts = opts.defer_if_unreachable.then(|| opts.clone());
+        let result = async {
+            let keysets_info = self.load_token_keysets().await?;
+            let proofs = token.proofs(&keysets_info)?;
+            self.receive_proofs_with_result(proofs, opts, token.memo_text())
+                .await
+        }
+        .await;
 
-        Ok(amount)
+        match (result, defer_opts) {
+            (Err(err), Some(opts)) if mint_unreachable(&err) => {
+                self.defer_receive(&token, &opts, err).await
+            }
+            (result, _) => result,
+        }
     }
 
     /// Receive
@@ -305,4 +457,11 @@ pub struct ReceiveOptions {
     pub preimages: Vec<String>,
     /// Metadata
     pub metadata: HashMap<String, String>,
+    /// Fail if any proof is already spent or pending instead of claiming the others
+    pub all_or_nothing: bool,
+    /// Store the token to claim it later if the mint can't be reached, instead of failing
+    ///
+    /// Only tokens without spending conditions that verify against the cached keysets and keys
+    /// are deferred, see [`Wallet::claim_deferred_receives`].
+    pub defer_if_unreachable: bool,
 }
@@ -0,0 +1,258 @@
+//! Sweeping funds from another seed
+//!
+//! After a seed is compromised, or when retiring a legacy seed, everything
+//! derived from it can be restored (NUT-09) and swapped into proofs derived
+//! from the wallet's own seed. Restored proofs are only held in memory, so a
+//! failed swap never leaves proofs of the old seed in the store; the funds
+//! stay restorable from the old seed.
+
+use std::collections::HashMap;
+
+use cdk_common::wallet::{Transaction, TransactionDirection, TransactionKind};
+use tracing::instrument;
+
+use super::SeedRestoreOptions;
+use crate::dhke::construct_proofs;
+use crate::nuts::nut00::ProofsMethods;
+use crate::nuts::{CheckStateRequest, PreMintSecrets, Proofs, State, SwapRequest};
+use crate::types::ProofInfo;
+use crate::util::unix_time;
+use crate::wallet::JournalOperation;
+use crate::{ensure_cdk, Amount, Error, Wallet};
+
+/// Number of attempts for each swap of restored proofs
+const SWEEP_SWAP_ATTEMPTS: u32 = 3;
+
+/// Result of [`Wallet::sweep_from_seed`]
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeedSweepResult {
+    /// Number of unspent proofs restored from the old seed and swapped
+    pub proofs_swept: usize,
+    /// Value of the proofs swept, before fees
+    pub amount_recovered: Amount,
+    /// Input fees paid for the swaps
+    pub fee_paid: Amount,
+}
+
+impl Wallet {
+    /// Move all funds derived from `old_seed` at this wallet's mint into this wallet
+    ///
+    /// Restores the proofs of `old_seed` for every keyset of the wallet unit, keeps the unspent
+    /// ones in memory and swaps them, in batches of `batch_size`, for proofs derived from the
+    /// wallet's seed. Each swap is retried a few times before giving up. Restored proofs are
+    /// never stored, so after an error the remaining funds can be swept again later.
+    ///
+    /// For a mnemonic, pass `mnemonic.to_seed_normalized("")`.
+    #[instrument(skip_all)]
+    pub async fn sweep_from_seed(
+        &self,
+        old_seed: &[u8; 64],
+        options: SeedRestoreOptions,
+    ) -> Result<SeedSweepResult, Error> {
+        ensure_cdk!(
+            old_seed != &self.seed,
+            Error::Custom("Cannot sweep the wallet's own seed".to_string())
+        );
+        ensure_cdk!(
+            options.batch_size > 0,
+            Error::Custom("Restore batch size must be greater than zero".to_string())
+        );
+
+        if self
+            .localstore
+            .get_mint(self.mint_url.clone())
+            .await?
+            .is_none()
+        {
+            self.fetch_mint_info().await?;
+        }
+
+        // Only unspent proofs can be swapped, pending ones may still be claimed by the old seed
+        let mut unspent = Proofs::new();
+        for keyset in self.fetch_all_mint_keysets().await? {
+            let keys = match self.load_keyset_keys(keyset.id).await {
+                Ok(keys) => keys,
+                Err(Error::UnknownKeySet) => {
+                    tracing::warn!("Mint did not serve the keys of keyset {}", keyset.id);
+                    continue;
+                }
+                Err(err) => return Err(err),
+            };
+            let mut empty_batch = 0;
+            let mut start_counter = 0;
+
+            while empty_batch < options.gap_limit {
+                let end_counter = start_counter + options.batch_size;
+                let batch = self
+                    .restore_batch(old_seed, keyset.id, &keys, start_counter, end_counter)
+                    .await?;
+                start_counter = end_counter;
+
+                let Some((proofs, _)) = batch else {
+                    empty_batch += 1;
+                    continue;
+                };
+                empty_batch = 0;
+
+                let states = self
+                    .client
+                    .post_check_state(CheckStateRequest { ys: proofs.ys()? })
+                    .await?
+                    .states;
+                unspent.extend(
+                    proofs
+                        .into_iter()
+                        .zip(states)
+                        .filter(|(_, state)| state.state == State::Unspent)
+                        .map(|(proof, _)| proof),
+                );
+            }
+        }
+
+        let mut result = SeedSweepResult::default();
+
+        for batch in unspent.chunks(options.batch_size as usize) {
+            let batch = batch.to_vec();
+            let batch_amount = batch.total_amount()?;
+
+            let mut attempt = 1;
+            let fee = loop {
+                match self.swap_restored_proofs(batch.clone()).await {
+                    Ok(fee) => break fee,
+                    Err(err) if attempt < SWEEP_SWAP_ATTEMPTS => {
+                        tracing::warn!(
+                            "Swapping {} restored proofs failed (attempt {}): {}",
+                            batch.len(),
+                            attempt,
+                            err
+                        );
+                        attempt += 1;
+                    }
+                    Err(err) => return Err(err),
+                }
+            };
+
+            result.proofs_swept += batch.len();
+            result.amount_recovered += batch_amount;
+            result.fee_paid += fee;
+        }
+
+        tracing::info!(
+            "Swept {} proofs worth {} from old seed at {} for {} fee",
+            result.proofs_swept,
+            result.amount_recovered,
+            self.mint_url,
+            result.fee_paid
+        );
+
+        Ok(result)
+    }
+
+    /// Swap proofs that are not in the store for new proofs of the wallet
+    ///
+    /// Returns the input fee paid.
+    async fn swap_restored_proofs(&self, proofs: Proofs) -> Result<Amount, Error> {
+        let active_keyset_id = self.fetch_active_keyset().await?.id;
+        let fee_and_amounts = self
+            .get_keyset_fees_and_amounts_by_id(active_keyset_id)
+            .await?;
+
+        let fee = self.get_proofs_fee(&proofs).await?.total;
+        let amount = proofs
+            .total_amount()?
+            .checked_sub(fee)
+            .ok_or(Error::InsufficientFunds)?;
+        ensure_cdk!(amount > Amount::ZERO, Error::InsufficientFunds);
+
+        let split_target = self
+            .determine_split_target_values(amount, &fee_and_amounts)
+            .await?;
+        let num_secrets = amount
+            .split_targeted(&split_target, &fee_and_amounts)?
+            .len() as u32;
+        let counter = self
+            .localstore
+            .increment_keyset_counter(&active_keyset_id, num_secrets)
+            .await?
+            - num_secrets;
+
+        let mut premint_secrets = PreMintSecrets::from_seed(
+            active_keyset_id,
+            counter,
+            &self.seed,
+            amount,
+            &split_target,
+            &fee_and_amounts,
+        )?;
+        premint_secrets.sort_secrets();
+
+        // The swept proofs are not in the store, so only the outputs are journaled
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Swap,
+                None,
+                counter..counter + num_secrets,
+                &premint_secrets,
+                vec![],
+            )
+            .await?;
+
+        let ys = proofs.ys()?;
+        let response = self
+            .client
+            .post_swap(SwapRequest::new(
+                proofs.without_dleqs(),
+                premint_secrets.blinded_messages(),
+            ))
+            .await?;
+
+        let keys = self.load_keyset_keys(active_keyset_id).await?;
+        let new_proofs = construct_proofs(
+            response.signatures,
+            premint_secrets.rs(),
+            premint_secrets.secrets(),
+            &keys,
+        )?;
+
+        let proofs_info = new_proofs
+            .into_iter()
+            .map(|proof| {
+                ProofInfo::new(
+                    proof,
+                    self.mint_url.clone(),
+                    State::Unspent,
+                    self.unit.clone(),
+                )
+            })
+            .collect::<Result<Vec<ProofInfo>, _>>()?;
+
+        self.localstore
+            .update_proofs_with_transaction(
+                proofs_info,
+                vec![],
+                Transaction {
+                    mint_url: self.mint_url.clone(),
+                    direction: TransactionDirection::Incoming,
+                    amount,
+                    fee,
+                    unit: self.unit.clone(),
+                    ys,
+                    timestamp: unix_time(),
+                    memo: None,
+                    metadata: HashMap::from([("sweep".to_string(), "seed".to_string())]),
+                    quote_id: None,
+                    payment_request: None,
+                    payment_proof: None,
+                    payment_method: None,
+                    kind: Some(TransactionKind::Receive),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
+            )
+            .await?;
+
+        self.journal_finish(&journal_id).await?;
+
+        Ok(fee)
+    }
+}
@@ -3,7 +3,7 @@ use std::fmt::Debug;
 
 use cdk_common::nut02::KeySetInfosMethods;
 use cdk_common::util::unix_time;
-use cdk_common::wallet::{Transaction, TransactionDirection};
+use cdk_common::wallet::{SelectionStrategy, Transaction, TransactionDirection, TransactionKind};
 use cdk_common::Id;
 use tracing::instrument;
 
@@ -11,8 +11,9 @@ use super::SendKind;
 use crate::amount::SplitTarget;
 use crate::fees::calculate_fee;
 use crate::nuts::nut00::ProofsMethods;
-use crate::nuts::{Proofs, SpendingConditions, State, Token};
-use crate::{Amount, Error, Wallet};
+use crate::nuts::{Conditions, Proofs, PublicKey, SpendingConditions, State, Token, TokenMemo};
+use crate::types::ProofInfo;
+use crate::{ensure_cdk, Amount, Error, Wallet};
 
 impl Wallet {
     /// Prepare A Send Transaction
@@ -97,28 +98,27 @@ impl Wallet {
             .await?;
 
         let selection_amount = if opts.include_fee {
-            let send_split = amount.split_with_fee(&fee_and_amounts)?;
-            let send_fee = self
-                .get_proofs_fee_by_count(
-                    vec![(active_keyset_id, send_split.len() as u64)]
-                        .into_iter()
-                        .collect(),
-                )
-                .await?;
-            amount + send_fee.total
+            Amount::try_sum(amount.split_with_fee(&fee_and_amounts)?)?
         } else {
             amount
         };
 
-        let selected_proofs = Wallet::select_proofs(
+        let selected_proofs = Wallet::select_proofs_with_strategy(
             selection_amount,
             available_proofs,
             &active_keyset_ids,
             &keyset_fees,
             opts.include_fee,
+            opts.selection_strategy,
         )?;
         let selected_total = selected_proofs.total_amount()?;
 
+        // Stored proofs without a DLEQ proof are swapped for proofs carrying one
+        if opts.include_dleq && selected_proofs.iter().any(|proof| proof.dleq.is_none()) {
+            ensure_cdk!(opts.send_kind.is_online(), Error::DleqProofNotProvided);
+            force_swap = true;
+        }
+
         // Check if selected proofs are exact
         let send_fee = if opts.include_fee {
             self.get_proofs_fee(&selected_proofs).await?.total
@@ -161,24 +161,33 @@ impl Wallet {
         let fee_and_amounts = self
             .get_keyset_fees_and_amounts_by_id(active_keyset_id)
             .await?;
-        let (send_amounts, send_fee) = if opts.include_fee {
+        // Selected proofs that net exactly the amount after their own redeem fee are sent as is
+        let proofs_fee = if opts.include_fee {
+            self.get_proofs_fee(&proofs).await?.total
+        } else {
+            Amount::ZERO
+        };
+        let mut exact_proofs = proofs.total_amount()? == amount + proofs_fee;
+        if let Some(max_proofs) = opts.max_proofs {
+            exact_proofs &= proofs.len() <= max_proofs;
+        }
+
+        let (send_amounts, send_fee) = if exact_proofs {
+            (
+                proofs.iter().map(|proof| proof.amount).collect(),
+                proofs_fee,
+            )
+        } else if opts.include_fee {
             tracing::debug!("Keyset fee per proof: {:?}", fee_and_amounts.fee());
+            // The swap splits the gross amount the same way, so the token has exactly
+            // these parts and the receiver pays exactly the gross minus the amount
             let send_split = amount.split_with_fee(&fee_and_amounts)?;
-            let send_fee = self
-                .get_proofs_fee_by_count(
-                    vec![(active_keyset_id, send_split.len() as u64)]
-                        .into_iter()
-                        .collect(),
-                )
-                .await?;
+            let send_fee = Amount::try_sum(send_split.iter().copied())?
+                .checked_sub(amount)
+                .ok_or(Error::AmountOverflow)?;
             (send_split, send_fee)
         } else {
-            let send_split = amount.split(&fee_and_amounts);
-            let send_fee = crate::fees::ProofsFeeBreakdown {
-                total: Amount::ZERO,
-                per_keyset: std::collections::HashMap::new(),
-            };
-            (send_split, send_fee)
+            (amount.split(&fee_and_amounts), Amount::ZERO)
         };
         tracing::debug!("Send amounts: {:?}", send_amounts);
         tracing::debug!("Send fee: {:?}", send_fee);
@@ -188,12 +197,6 @@ impl Wallet {
             .update_proofs_state(proofs.ys()?, State::Reserved)
             .await?;
 
-        // Check if proofs are exact send amount (and does not exceed max_proofs)
-        let mut exact_proofs = proofs.total_amount()? == amount + send_fee.total;
-        if let Some(max_proofs) = opts.max_proofs {
-            exact_proofs &= proofs.len() <= max_proofs;
-        }
-
         // Determine if we should send all proofs directly
         let is_exact_or_offline =
             exact_proofs || opts.send_kind.is_offline() || opts.send_kind.has_tolerance();
@@ -210,7 +213,7 @@ impl Wallet {
             proofs,
             &send_amounts,
             amount,
-            send_fee.total,
+            send_fee,
             &keyset_fees,
             force_swap,
             is_exact_or_offline,
@@ -224,7 +227,7 @@ impl Wallet {
             proofs_to_swap: split_result.proofs_to_swap,
             swap_fee: split_result.swap_fee,
             proofs_to_send: split_result.proofs_to_send,
-            send_fee: send_fee.total,
+            send_fee,
         })
     }
 }
@@ -267,10 +270,21 @@ impl PreparedSend {
     }
 
     /// Send fee
+    ///
+    /// The fee the receiver pays to redeem the token, included in the token when
+    /// [`SendOptions::include_fee`] is set.
     pub fn send_fee(&self) -> Amount {
         self.send_fee
     }
 
+    /// Gross amount
+    ///
+    /// The value of the token: the amount plus the send fee, so a receiver swapping the token
+    /// nets the amount.
+    pub fn gross_amount(&self) -> Amount {
+        self.amount + self.send_fee
+    }
+
     /// All proofs
     pub fn proofs(&self) -> Proofs {
         let mut proofs = self.proofs_to_swap.clone();
@@ -336,6 +350,11 @@ impl PreparedSend {
             return Err(Error::InsufficientFunds);
         }
 
+        ensure_cdk!(
+            !self.options.include_dleq || proofs_to_send.iter().all(|proof| proof.dleq.is_some()),
+            Error::DleqProofNotProvided
+        );
+
         // Check if proofs are reserved or unspent
         let sendable_proof_ys = self
             .wallet
@@ -360,40 +379,76 @@ impl PreparedSend {
             proofs_to_send.ys()?
         );
 
-        self.wallet
-            .localstore
-            .update_proofs_state(proofs_to_send.ys()?, State::PendingSpent)
-            .await?;
-
         // Include token memo
         let send_memo = self.options.memo.or(memo);
+        let token_memo = match &send_memo {
+            Some(send_memo) if send_memo.include_mint_info => {
+                // The snapshot is best effort, the send does not depend on the mint being reachable
+                let mint_info = self
+                    .wallet
+                    .load_mint_info()
+                    .await
+                    .inspect_err(|err| {
+                        tracing::warn!("Could not load mint info for the token memo: {}", err)
+                    })
+                    .unwrap_or_default();
+                Some(
+                    TokenMemo {
+                        text: send_memo.include_memo.then(|| send_memo.memo.clone()),
+                        mint_name: mint_info.name,
+                        mint_icon_url: mint_info.icon_url,
+                    }
+                    .to_string(),
+                )
+            }
+            _ => None,
+        };
         let memo = send_memo.and_then(|m| if m.include_memo { Some(m.memo) } else { None });
 
-        // Add transaction to store
+        let pending_proofs = proofs_to_send
+            .iter()
+            .map(|proof| {
+                ProofInfo::new(
+                    proof.clone(),
+                    self.wallet.mint_url.clone(),
+                    State::PendingSpent,
+                    self.wallet.unit.clone(),
+                )
+            })
+            .collect::<Result<Vec<ProofInfo>, _>>()?;
+
+        // Mark proofs pending spent and add transaction to store
         self.wallet
             .localstore
-            .add_transaction(Transaction {
-                mint_url: self.wallet.mint_url.clone(),
-                direction: TransactionDirection::Outgoing,
-                amount: self.amount,
-                fee: total_send_fee,
-                unit: self.wallet.unit.clone(),
-                ys: proofs_to_send.ys()?,
-                timestamp: unix_time(),
-                memo: memo.clone(),
-                metadata: self.options.metadata,
-                quote_id: None,
-                payment_request: None,
-                payment_proof: None,
-                payment_method: None,
-            })
+            .update_proofs_with_transaction(
+                pending_proofs,
+                vec![],
+                Transaction {
+                    mint_url: self.wallet.mint_url.clone(),
+                    direction: TransactionDirection::Outgoing,
+                    amount: self.amount,
+                    fee: total_send_fee,
+                    unit: self.wallet.unit.clone(),
+                    ys: proofs_to_send.ys()?,
+                    timestamp: unix_time(),
+                    memo: memo.clone(),
+                    metadata: self.options.metadata,
+                    quote_id: None,
+                    payment_request: None,
+                    payment_proof: None,
+                    payment_method: None,
+                    kind: Some(TransactionKind::Send),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
+            )
             .await?;
 
         // Create and return token
         Ok(Token::new(
             self.wallet.mint_url.clone(),
             proofs_to_send,
-            memo,
+            token_memo.or(memo),
             self.wallet.unit.clone(),
         ))
     }
@@ -467,13 +522,68 @@ pub struct SendOptions {
     pub send_kind: SendKind,
     /// Include fee
     ///
-    /// When this is true the token created will include the amount of fees needed to redeem the token (amount + fee_to_redeem)
+    /// When this is true the token created will include the amount of fees needed to redeem the token (amount + fee_to_redeem),
+    /// so the receiver nets the amount after its swap. [`PreparedSend::gross_amount`] is the resulting token value.
     pub include_fee: bool,
     /// Maximum number of proofs to include in the token
     /// Default is `None`, which means all selected proofs will be included.
     pub max_proofs: Option<usize>,
+    /// Strategy used to select the proofs to send
+    pub selection_strategy: SelectionStrategy,
     /// Metadata
     pub metadata: HashMap<String, String>,
+    /// Include the DLEQ proof (NUT-12) of every proof in the token
+    ///
+    /// Lets the receiver verify offline that the mint signed the proofs. Stored proofs without
+    /// a DLEQ proof are swapped first, so offline sends fail if any is selected.
+    pub include_dleq: bool,
+}
+
+impl SendOptions {
+    /// Lock the sent proofs to receiver public keys (NUT-11)
+    ///
+    /// The first key is used as the secret data and any further keys are added as
+    /// additional signers, `num_sigs` of which must sign to redeem. Once `locktime`
+    /// has passed the `refund_keys` can claim the proofs instead. The witness is
+    /// left for whoever redeems the token.
+    pub fn with_p2pk_lock(
+        mut self,
+        pubkeys: Vec<PublicKey>,
+        num_sigs: Option<u64>,
+        locktime: Option<u64>,
+        refund_keys: Option<Vec<PublicKey>>,
+    ) -> Result<Self, Error> {
+        let mut pubkeys = pubkeys.into_iter();
+        let data = pubkeys.next().ok_or_else(|| {
+            Error::InvalidSpendConditions("At least one receiver pubkey is required".to_string())
+        })?;
+        let additional_pubkeys: Vec<PublicKey> = pubkeys.collect();
+
+        if let Some(num_sigs) = num_sigs {
+            ensure_cdk!(
+                num_sigs >= 1 && num_sigs <= additional_pubkeys.len() as u64 + 1,
+                Error::InvalidSpendConditions(
+                    "n_sigs must be between 1 and the number of receiver pubkeys".to_string()
+                )
+            );
+        }
+        ensure_cdk!(
+            refund_keys.is_none() || locktime.is_some(),
+            Error::InvalidSpendConditions("Must set locktime".to_string())
+        );
+
+        let conditions = Conditions::new(
+            locktime,
+            (!additional_pubkeys.is_empty()).then_some(additional_pubkeys),
+            refund_keys,
+            num_sigs,
+            None,
+            None,
+        )?;
+
+        self.conditions = Some(SpendingConditions::new_p2pk(data, Some(conditions)));
+        Ok(self)
+    }
 }
 
 /// Send memo
@@ -483,6 +593,8 @@ pub struct SendMemo {
     pub memo: String,
     /// Include memo in token
     pub include_memo: bool,
+    /// Include the name and icon of the mint in the token memo, as a [`TokenMemo`]
+    pub include_mint_info: bool,
 }
 
 impl SendMemo {
@@ -491,8 +603,15 @@ impl SendMemo {
         Self {
             memo: memo.to_string(),
             include_memo: true,
+            include_mint_info: false,
         }
     }
+
+    /// Include the name and icon of the mint in the token memo
+    pub fn with_mint_info(mut self) -> Self {
+        self.include_mint_info = true;
+        self
+    }
 }
 
 /// Result of splitting proofs for a send operation
@@ -1887,4 +2006,52 @@ mod tests {
             .collect();
         assert!(swap_amounts.contains(&16));
     }
+
+    // ========================================================================
+    // P2PK Lock Tests
+    // ========================================================================
+
+    #[test]
+    fn test_with_p2pk_lock_builds_conditions() {
+        let receiver = crate::nuts::SecretKey::generate().public_key();
+        let cosigner = crate::nuts::SecretKey::generate().public_key();
+        let refund = crate::nuts::SecretKey::generate().public_key();
+        let locktime = unix_time() + 3600;
+
+        let options = SendOptions::default()
+            .with_p2pk_lock(
+                vec![receiver, cosigner],
+                Some(2),
+                Some(locktime),
+                Some(vec![refund]),
+            )
+            .unwrap();
+
+        let conditions = options.conditions.unwrap();
+        assert_eq!(conditions.num_sigs(), Some(2));
+        assert_eq!(conditions.locktime(), Some(locktime));
+        let pubkeys = conditions.pubkeys().unwrap();
+        assert_eq!(pubkeys.len(), 2);
+        assert!(pubkeys.contains(&receiver) && pubkeys.contains(&cosigner));
+        assert_eq!(conditions.refund_keys(), Some(vec![refund]));
+    }
+
+    #[test]
+    fn test_with_p2pk_lock_rejects_invalid_conditions() {
+        let receiver = crate::nuts::SecretKey::generate().public_key();
+        let refund = crate::nuts::SecretKey::generate().public_key();
+
+        assert!(SendOptions::default()
+            .with_p2pk_lock(vec![], None, None, None)
+            .is_err());
+        assert!(SendOptions::default()
+            .with_p2pk_lock(vec![receiver], Some(2), None, None)
+            .is_err());
+        assert!(SendOptions::default()
+            .with_p2pk_lock(vec![receiver], None, None, Some(vec![refund]))
+            .is_err());
+        assert!(SendOptions::default()
+            .with_p2pk_lock(vec![receiver], None, Some(1), Some(vec![refund]))
+            .is_err());
+    }
 }
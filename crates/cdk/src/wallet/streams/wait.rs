@@ -1,6 +1,6 @@
 use cdk_common::amount::SplitTarget;
 use cdk_common::wallet::MintQuote;
-use cdk_common::{Amount, Error, Proofs, SpendingConditions};
+use cdk_common::{Amount, Error, MintQuoteState, Proofs, SpendingConditions};
 use futures::future::BoxFuture;
 use futures::StreamExt;
 use tokio::time::{timeout, Duration};
@@ -26,6 +26,36 @@ impl Wallet {
         .map_err(|_| Error::Timeout)?
     }
 
+    /// Waits until a mint quote from the wallet store is paid
+    ///
+    /// Uses NUT-17 websocket notifications when the mint supports them, and polls
+    /// the quote state otherwise or while the connection is down. The stored quote
+    /// is updated and returned once paid.
+    pub async fn wait_for_mint_quote_paid(
+        &self,
+        quote_id: &str,
+        timeout_duration: Duration,
+    ) -> Result<MintQuote, Error> {
+        let mut quote = self
+            .localstore
+            .get_mint_quote(quote_id)
+            .await?
+            .ok_or(Error::UnknownQuote)?;
+
+        if quote.state == MintQuoteState::Paid {
+            return Ok(quote);
+        }
+
+        match self.wait_for_payment(&quote, timeout_duration).await? {
+            // Bolt12 quotes report the amount that can be issued
+            Some(amount) => quote.amount_paid = quote.amount_issued + amount,
+            None => quote.state = MintQuoteState::Paid,
+        }
+        self.localstore.add_mint_quote(quote.clone()).await?;
+
+        Ok(quote)
+    }
+
     /// Returns a BoxFuture that will wait for payment on the given event with a timeout check
     #[allow(private_bounds)]
     pub fn wait_for_payment(
@@ -5,13 +5,13 @@
 //! subscription manager that allows clients to subscribe to notifications from
 //! multiple mint servers using WebSocket or with a poll-based system, using
 //! the HTTP client.
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 use std::sync::atomic::AtomicUsize;
 use std::sync::Arc;
 
 use cdk_common::nut17::ws::{WsMethodRequest, WsRequest, WsUnsubscribeRequest};
-use cdk_common::nut17::{Kind, NotificationId};
+use cdk_common::nut17::{Kind, NotificationId, WsCommand};
 use cdk_common::parking_lot::RwLock;
 use cdk_common::pub_sub::remote_consumer::{
     Consumer, InternalRelay, RemoteActiveConsumer, StreamCtrl, SubscribeMessage, Transport,
@@ -81,6 +81,11 @@ impl SubscriptionManager {
         }
     }
 
+    /// Whether HTTP polling is preferred over websockets
+    pub(crate) fn prefer_http(&self) -> bool {
+        self.prefer_http
+    }
+
     /// Subscribe to updates from a mint server with a given filter
     pub fn subscribe(
         &self,
@@ -153,19 +158,22 @@ impl SubscriptionClient {
         id: String,
         params: NotificationId<String>,
     ) -> Option<(usize, String)> {
-        let (kind, filter) = match params {
-            NotificationId::ProofState(x) => (Kind::ProofState, x.to_string()),
+        let (kind, filters) = match params {
+            NotificationId::ProofState(x) => (Kind::ProofState, vec![x.to_string()]),
             NotificationId::MeltQuoteBolt11(q) | NotificationId::MeltQuoteBolt12(q) => {
-                (Kind::Bolt11MeltQuote, q)
+                (Kind::Bolt11MeltQuote, vec![q])
             }
-            NotificationId::MintQuoteBolt11(q) => (Kind::Bolt11MintQuote, q),
-            NotificationId::MintQuoteBolt12(q) => (Kind::Bolt12MintQuote, q),
+            NotificationId::MintQuoteBolt11(q) => (Kind::Bolt11MintQuote, vec![q]),
+            NotificationId::MintQuoteBolt12(q) => (Kind::Bolt12MintQuote, vec![q]),
+            NotificationId::InternalSettlement(q) => (Kind::InternalSettlement, vec![q]),
+            NotificationId::MeltProgress(q) => (Kind::Bolt11MeltProgress, vec![q]),
+            NotificationId::MintInfo => (Kind::MintInfo, vec![]),
         };
 
         let request: WsRequest<_> = (
             WsMethodRequest::Subscribe(WalletParams {
                 kind,
-                filters: vec![filter],
+                filters,
                 id: id.into(),
             }),
             self.req_id
@@ -215,7 +223,30 @@ impl Transport for SubscriptionClient {
         _reply_to: InternalRelay<Self::Spec>,
     ) -> Result<(), PubsubError> {
         #[cfg(not(target_arch = "wasm32"))]
-        let r = ws::stream_client(self, _ctrls, _topics, _reply_to).await;
+        let r = {
+            // Only open a websocket if the mint advertises NUT-17, otherwise stick to polling
+            let mint_info = self
+                .http_client
+                .get_mint_info()
+                .await
+                .map_err(|e| PubsubError::Internal(Box::new(e)))?;
+
+            if mint_info.nuts.nut17.supported.is_empty() {
+                tracing::debug!("{} does not support websocket subscriptions", self.mint_url);
+                return Err(PubsubError::NotSupported);
+            }
+
+            // Older mints don't know the opt-in kinds, they are only subscribed to if advertised
+            let supported_commands = mint_info
+                .nuts
+                .nut17
+                .supported
+                .into_iter()
+                .flat_map(|supported| supported.commands)
+                .collect::<HashSet<WsCommand>>();
+
+            ws::stream_client(self, _ctrls, _topics, _reply_to, supported_commands).await
+        };
 
         #[cfg(target_arch = "wasm32")]
         let r = Err(PubsubError::NotSupported);
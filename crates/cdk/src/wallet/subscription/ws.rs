@@ -1,4 +1,7 @@
+use std::collections::HashSet;
+
 use cdk_common::nut17::ws::WsMessageOrResponse;
+use cdk_common::nut17::{NotificationId, WsCommand};
 use cdk_common::pub_sub::remote_consumer::{InternalRelay, StreamCtrl, SubscribeMessage};
 use cdk_common::pub_sub::Error as PubsubError;
 #[cfg(feature = "auth")]
@@ -11,12 +14,23 @@ use tokio_tungstenite::tungstenite::Message;
 
 use super::{MintSubTopics, SubscriptionClient};
 
+/// Command a mint must advertise before `topic` is subscribed to, for the kinds older mints
+/// don't know
+fn opt_in_command(topic: &NotificationId<String>) -> Option<WsCommand> {
+    match topic {
+        NotificationId::MintInfo => Some(WsCommand::MintInfo),
+        NotificationId::MeltProgress(_) => Some(WsCommand::Bolt11MeltProgress),
+        _ => None,
+    }
+}
+
 #[inline(always)]
 pub(crate) async fn stream_client(
     client: &SubscriptionClient,
     mut ctrl: mpsc::Receiver<StreamCtrl<MintSubTopics>>,
     topics: Vec<SubscribeMessage<MintSubTopics>>,
     reply_to: InternalRelay<MintSubTopics>,
+    supported_commands: HashSet<WsCommand>,
 ) -> Result<(), PubsubError> {
     let mut url = client
         .mint_url
@@ -90,12 +104,24 @@ pub(crate) async fn stream_client(
     tracing::debug!("Connected to {}", url);
     let (mut write, mut read) = ws_stream.split();
 
+    // Requests a mint may reject without the connection being at fault
+    let mut optional_requests = HashSet::new();
+
     for (name, index) in topics {
-        let (_, req) = if let Some(req) = client.get_sub_request(name, index) {
+        let opt_in = opt_in_command(&index);
+        if let Some(command) = opt_in.as_ref().filter(|c| !supported_commands.contains(*c)) {
+            tracing::debug!("{} does not support {:?}", client.mint_url, command);
+            continue;
+        }
+        let optional = opt_in.is_some();
+        let (id, req) = if let Some(req) = client.get_sub_request(name, index) {
             req
         } else {
             continue;
         };
+        if optional {
+            optional_requests.insert(id);
+        }
 
         let _ = write.send(Message::Text(req.into())).await;
     }
@@ -105,11 +131,20 @@ pub(crate) async fn stream_client(
             Some(msg) = ctrl.recv() => {
                 match msg {
                     StreamCtrl::Subscribe(msg) => {
-                        let (_, req) = if let Some(req) = client.get_sub_request(msg.0, msg.1) {
+                        let opt_in = opt_in_command(&msg.1);
+                        if let Some(command) = opt_in.as_ref().filter(|c| !supported_commands.contains(*c)) {
+                            tracing::debug!("{} does not support {:?}", client.mint_url, command);
+                            continue;
+                        }
+                        let optional = opt_in.is_some();
+                        let (id, req) = if let Some(req) = client.get_sub_request(msg.0, msg.1) {
                             req
                         } else {
                             continue;
                         };
+                        if optional {
+                            optional_requests.insert(id);
+                        }
                         let _ = write.send(Message::Text(req.into())).await;
                     }
                     StreamCtrl::Unsubscribe(msg) => {
@@ -128,14 +163,20 @@ pub(crate) async fn stream_client(
                     }
                 };
             }
-            Some(msg) = read.next() => {
+            msg = read.next() => {
+                // A dropped connection is reported as an error so the consumer falls back to
+                // polling and reconnects with backoff, resubscribing to every active topic
                 let msg = match msg {
-                    Ok(msg) => msg,
-                    Err(_) => {
+                    Some(Ok(msg)) => msg,
+                    Some(Err(err)) => {
                         if let Err(err) = write.send(Message::Close(None)).await {
                             tracing::error!("Closing error {err:?}");
                         }
-                        break;
+                        return Err(PubsubError::Internal(Box::new(err)));
+                    }
+                    None => {
+                        tracing::debug!("Connection to {} closed", url);
+                        return Err(PubsubError::InternalStr("Connection closed".to_owned()));
                     }
                 };
                 let msg = match msg {
@@ -156,6 +197,9 @@ pub(crate) async fn stream_client(
                     }
                     WsMessageOrResponse::ErrorResponse(error) => {
                         tracing::debug!("Received an error from server: {:?}", error);
+                        if optional_requests.remove(&error.id) {
+                            continue;
+                        }
                         return Err(PubsubError::InternalStr(error.error.message));
                     }
                 }
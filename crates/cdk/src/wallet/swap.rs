@@ -1,5 +1,9 @@
+use std::collections::HashMap;
+
 use cdk_common::amount::FeeAndAmounts;
 use cdk_common::nut02::KeySetInfosMethods;
+use cdk_common::util::unix_time;
+use cdk_common::wallet::{Transaction, TransactionDirection, TransactionKind};
 use cdk_common::Id;
 use tracing::instrument;
 
@@ -11,6 +15,7 @@ use crate::nuts::{
     nut10, PreMintSecrets, PreSwap, Proofs, PublicKey, SpendingConditions, State, SwapRequest,
 };
 use crate::types::ProofInfo;
+use crate::wallet::JournalOperation;
 use crate::{ensure_cdk, Amount, Error, Wallet};
 
 impl Wallet {
@@ -47,6 +52,22 @@ impl Wallet {
             )
             .await?;
 
+        let counters = self
+            .journal_counters(
+                &pre_swap.pre_mint_secrets.keyset_id,
+                pre_swap.derived_secret_count,
+            )
+            .await?;
+        let journal_id = self
+            .journal_begin(
+                JournalOperation::Swap,
+                None,
+                counters,
+                &pre_swap.pre_mint_secrets,
+                input_proofs.ys()?,
+            )
+            .await?;
+
         let swap_response = self
             .try_proof_operation_or_reclaim(
                 pre_swap.swap_request.inputs().clone(),
@@ -64,6 +85,7 @@ impl Wallet {
             pre_swap.pre_mint_secrets.secrets(),
             &active_keys,
         )?;
+        let swapped_amount = post_swap_proofs.total_amount()?;
 
         let mut added_proofs = Vec::new();
         let change_proofs;
@@ -132,10 +154,34 @@ impl Wallet {
             .map(|proof| proof.y())
             .collect::<Result<Vec<PublicKey>, _>>()?;
 
+        // Swap inputs for outputs and record the swap in one write
         self.localstore
-            .update_proofs(added_proofs, deleted_ys)
+            .update_proofs_with_transaction(
+                added_proofs,
+                deleted_ys.clone(),
+                Transaction {
+                    mint_url: mint_url.clone(),
+                    direction: TransactionDirection::Outgoing,
+                    amount: swapped_amount,
+                    fee: fee_breakdown.total,
+                    unit: unit.clone(),
+                    ys: deleted_ys,
+                    timestamp: unix_time(),
+                    memo: None,
+                    metadata: HashMap::new(),
+                    quote_id: None,
+                    payment_request: None,
+                    payment_proof: None,
+                    payment_method: None,
+                    kind: Some(TransactionKind::Swap),
+                    preimage_verified: false,
+                    amount_spent: None,
+                },
+            )
             .await?;
 
+        self.journal_finish(&journal_id).await?;
+
         Ok(send_proofs)
     }
 
@@ -1,26 +1,42 @@
-use cdk_common::wallet::{Transaction, TransactionDirection, TransactionId};
+use cdk_common::wallet::{
+    Pagination, Transaction, TransactionDirection, TransactionFilter, TransactionId,
+};
 use cdk_common::Proofs;
 
 use crate::{Error, Wallet};
 
 impl Wallet {
     /// List transactions
+    ///
+    /// Transactions are returned newest first. The wallet's mint and unit
+    /// always apply; the remaining fields of `filter` narrow the result
+    /// further before `pagination` is applied.
     pub async fn list_transactions(
         &self,
-        direction: Option<TransactionDirection>,
+        filter: TransactionFilter,
+        pagination: Pagination,
     ) -> Result<Vec<Transaction>, Error> {
-        let mut transactions = self
+        let filter = TransactionFilter {
+            mint_url: Some(self.mint_url.clone()),
+            unit: Some(self.unit.clone()),
+            ..filter
+        };
+
+        let mut transactions: Vec<Transaction> = self
             .localstore
             .list_transactions(
-                Some(self.mint_url.clone()),
-                direction,
-                Some(self.unit.clone()),
+                filter.mint_url.clone(),
+                filter.direction,
+                filter.unit.clone(),
             )
-            .await?;
+            .await?
+            .into_iter()
+            .filter(|transaction| transaction.matches_filter(&filter))
+            .collect();
 
         transactions.sort();
 
-        Ok(transactions)
+        Ok(pagination.apply(transactions))
     }
 
     /// Get transaction by ID